@@ -0,0 +1,183 @@
+//! Command-line parsing
+
+use crate::glob;
+use mconv_core::convert::ImageFormat;
+use mconv_core::resize::ResizeAlgorithm;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: mconv <command> [options] <inputs>...
+
+Commands:
+  convert   Re-encode images in another format
+  resize    Scale images, keeping their format unless --to is given;
+            JPEG inputs, which mconv cannot encode, become PNG
+  probe     Print format, size and metadata without decoding pixels
+
+Inputs are files or patterns using * and ?.
+
+Options:
+  -t, --to <format>        Output format: bmp, png, gif or webp (jpeg decodes only)
+  -o, --out-dir <dir>      Write outputs here instead of next to each input
+      --width <pixels>     Target width; alone, the height follows the aspect ratio
+      --height <pixels>    Target height; alone, the width follows the aspect ratio
+  -a, --algorithm <name>   nearest, bilinear, bicubic or lanczos (default)
+      --no-orient          Ignore the EXIF orientation tag
+      --help               Show this help
+";
+
+/// Where and how converted images are written
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputOptions {
+    /// Output format; `None` keeps each input's format
+    pub format: Option<ImageFormat>,
+    pub out_dir: Option<PathBuf>,
+    pub auto_orient: bool,
+}
+
+/// Target size for `resize`; a 0 side follows the aspect ratio
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResizeOptions {
+    pub width: u32,
+    pub height: u32,
+    pub algorithm: ResizeAlgorithm,
+}
+
+impl ResizeOptions {
+    /// Output size for a `width` x `height` source
+    pub fn target_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scaled = |len: u32, num: u32, den: u32| ((len as f64 * num as f64 / den.max(1) as f64).round() as u32).max(1);
+        match (self.width, self.height) {
+            (w, 0) => (w, scaled(height, w, width)),
+            (0, h) => (scaled(width, h, height), h),
+            (w, h) => (w, h),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Probe { inputs: Vec<PathBuf> },
+    Convert { inputs: Vec<PathBuf>, output: OutputOptions },
+    Resize { inputs: Vec<PathBuf>, output: OutputOptions, resize: ResizeOptions },
+}
+
+fn parse_format(name: &str) -> Result<ImageFormat, String> {
+    ImageFormat::from_extension(name).ok_or_else(|| format!("Unknown format {}", name))
+}
+
+fn parse_algorithm(name: &str) -> Result<ResizeAlgorithm, String> {
+    match name.to_ascii_lowercase().as_str() {
+        "nearest" => Ok(ResizeAlgorithm::Nearest),
+        "bilinear" => Ok(ResizeAlgorithm::Bilinear),
+        "bicubic" => Ok(ResizeAlgorithm::Bicubic),
+        "lanczos" => Ok(ResizeAlgorithm::Lanczos),
+        _ => Err(format!("Unknown resize algorithm {}", name)),
+    }
+}
+
+fn parse_pixels(option: &str, value: &str) -> Result<u32, String> {
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("{} needs a positive number of pixels, got {}", option, value)),
+    }
+}
+
+/// Parse the arguments after the program name
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let Some((command, rest)) = args.split_first() else { return Ok(Command::Help) };
+    match command.as_str() {
+        "help" | "--help" | "-h" => return Ok(Command::Help),
+        "probe" | "convert" | "resize" => {}
+        other => return Err(format!("Unknown command {}", other)),
+    }
+
+    let mut patterns = Vec::new();
+    let mut output = OutputOptions { format: None, out_dir: None, auto_orient: true };
+    let mut resize = ResizeOptions { width: 0, height: 0, algorithm: ResizeAlgorithm::Lanczos };
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--help" => return Ok(Command::Help),
+            "-t" | "--to" => output.format = Some(parse_format(value()?)?),
+            "-o" | "--out-dir" => output.out_dir = Some(PathBuf::from(value()?)),
+            "--width" => resize.width = parse_pixels(arg, value()?)?,
+            "--height" => resize.height = parse_pixels(arg, value()?)?,
+            "-a" | "--algorithm" => resize.algorithm = parse_algorithm(value()?)?,
+            "--no-orient" => output.auto_orient = false,
+            // Everything after `--` is an input, even if it looks like an option
+            "--" => patterns.extend(iter.by_ref().map(String::as_str)),
+            option if option.starts_with('-') && option.len() > 1 => return Err(format!("Unknown option {}", option)),
+            pattern => patterns.push(pattern),
+        }
+    }
+
+    if patterns.is_empty() {
+        return Err(format!("{} needs at least one input", command));
+    }
+    let mut inputs = Vec::new();
+    for pattern in patterns {
+        inputs.extend(glob::expand(pattern)?);
+    }
+
+    match command.as_str() {
+        "probe" => Ok(Command::Probe { inputs }),
+        "convert" if output.format.is_none() => Err("convert needs an output format (--to)".to_string()),
+        "convert" => Ok(Command::Convert { inputs, output }),
+        _ if resize.width == 0 && resize.height == 0 => Err("resize needs --width, --height or both".to_string()),
+        _ => Ok(Command::Resize { inputs, output, resize }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Command, String> {
+        parse(&args.split_whitespace().map(String::from).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse_str(""), Ok(Command::Help));
+        assert_eq!(parse_str("probe a.png b.gif"), Ok(Command::Probe { inputs: vec!["a.png".into(), "b.gif".into()] }));
+        assert_eq!(
+            parse_str("convert --to WEBP -o out --no-orient a.jpg"),
+            Ok(Command::Convert {
+                inputs: vec!["a.jpg".into()],
+                output: OutputOptions { format: Some(ImageFormat::WebP), out_dir: Some("out".into()), auto_orient: false },
+            })
+        );
+        assert_eq!(
+            parse_str("resize --width 64 -a nearest -- -odd.png"),
+            Ok(Command::Resize {
+                inputs: vec!["-odd.png".into()],
+                output: OutputOptions { format: None, out_dir: None, auto_orient: true },
+                resize: ResizeOptions { width: 64, height: 0, algorithm: ResizeAlgorithm::Nearest },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_str("convert a.png"), Err("convert needs an output format (--to)".to_string()));
+        assert_eq!(parse_str("convert --to tiff a.png"), Err("Unknown format tiff".to_string()));
+        assert_eq!(parse_str("resize a.png"), Err("resize needs --width, --height or both".to_string()));
+        assert_eq!(parse_str("resize --width 0 a.png"), Err("--width needs a positive number of pixels, got 0".to_string()));
+        assert_eq!(parse_str("probe --to"), Err("--to needs a value".to_string()));
+        assert_eq!(parse_str("probe"), Err("probe needs at least one input".to_string()));
+        assert_eq!(parse_str("probe --fast a.png"), Err("Unknown option --fast".to_string()));
+        assert_eq!(parse_str("shrink a.png"), Err("Unknown command shrink".to_string()));
+    }
+
+    #[test]
+    fn test_target_size() {
+        let resize = |width, height| ResizeOptions { width, height, algorithm: ResizeAlgorithm::Lanczos };
+        assert_eq!(resize(100, 0).target_size(400, 300), (100, 75));
+        assert_eq!(resize(0, 30).target_size(400, 300), (40, 30));
+        assert_eq!(resize(10, 10).target_size(400, 300), (10, 10));
+        assert_eq!(resize(1, 0).target_size(1000, 1), (1, 1));
+    }
+}
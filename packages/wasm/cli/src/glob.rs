@@ -0,0 +1,127 @@
+//! Wildcard expansion of input paths
+//!
+//! Shells on Unix expand globs before mconv sees them, but Windows shells
+//! and quoted patterns do not, so `*` and `?` are expanded here too. A
+//! wildcard may appear in any path component; `**` is not special.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Whether `name` matches `pattern`, where `*` is any run of characters
+/// and `?` is any one character
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it is matched up to
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn has_wildcard(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Files matching `pattern` in sorted order; a pattern without wildcards is
+/// returned as is, whether or not it exists
+pub fn expand(pattern: &str) -> Result<Vec<PathBuf>, String> {
+    if !has_wildcard(pattern) {
+        return Ok(vec![PathBuf::from(pattern)]);
+    }
+
+    let mut paths = vec![PathBuf::new()];
+    for component in Path::new(pattern).components() {
+        let name = match component {
+            Component::Normal(name) => name.to_string_lossy(),
+            other => {
+                paths.iter_mut().for_each(|path| path.push(other));
+                continue;
+            }
+        };
+        if !has_wildcard(&name) {
+            paths.iter_mut().for_each(|path| path.push(&*name));
+            continue;
+        }
+
+        let mut expanded = Vec::new();
+        for dir in &paths {
+            let listing = if dir.as_os_str().is_empty() { Path::new(".") } else { dir.as_path() };
+            // Unreadable directories just contribute no matches
+            let Ok(entries) = fs::read_dir(listing) else { continue };
+            for entry in entries.flatten() {
+                let entry_name = entry.file_name().to_string_lossy().into_owned();
+                // As in shells, wildcards skip hidden files unless the pattern starts with a dot
+                if entry_name.starts_with('.') && !name.starts_with('.') {
+                    continue;
+                }
+                if matches(&name, &entry_name) {
+                    expanded.push(dir.join(entry_name));
+                }
+            }
+        }
+        paths = expanded;
+    }
+
+    // Intermediate components matched directories; the last one must be files
+    paths.retain(|path| path.is_file());
+    if paths.is_empty() {
+        return Err(format!("No files match {}", pattern));
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*.png", "photo.png"));
+        assert!(matches("*.png", ".png"));
+        assert!(!matches("*.png", "photo.jpg"));
+        assert!(matches("img_??.*", "img_01.webp"));
+        assert!(!matches("img_??.*", "img_1.webp"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("a*b*c", "aXbYbZ"));
+        assert!(matches("*", ""));
+        assert!(matches("exact", "exact"));
+    }
+
+    #[test]
+    fn test_expand() {
+        let root = std::env::temp_dir().join(format!("mconv-glob-{}", std::process::id()));
+        for file in ["a/one.png", "a/two.png", "a/skip.jpg", "b/three.png", "a/.hidden.png"] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+
+        let pattern = root.join("*").join("*.png");
+        let found = expand(pattern.to_str().unwrap()).unwrap();
+        assert_eq!(found, [root.join("a/one.png"), root.join("a/two.png"), root.join("b/three.png")]);
+        assert!(expand(root.join("*.gif").to_str().unwrap()).is_err());
+        assert_eq!(expand("plain.png").unwrap(), [PathBuf::from("plain.png")]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+//! mconv: convert, resize and inspect images from the command line with
+//! the same codecs as the WASM build
+
+mod args;
+mod glob;
+
+use args::{Command, OutputOptions, ResizeOptions, USAGE};
+use mconv_core::convert::{convert_batch, decode_auto, detect_format, encode, ConvertOptions, ImageFormat};
+use mconv_core::probe::{probe, ImageInfo};
+use mconv_core::resize::resize;
+use mconv_core::transform::RgbaImage;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args::parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("mconv: {}\nRun `mconv --help` for usage", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let failures = match command {
+        Command::Help => {
+            print!("{}", USAGE);
+            0
+        }
+        Command::Probe { inputs } => probe_files(&inputs),
+        Command::Convert { inputs, output } => convert_files(&inputs, &output),
+        Command::Resize { inputs, output, resize } => resize_files(&inputs, &output, &resize),
+    };
+    if failures == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Report a failed input, returning 1 for the failure count
+fn fail(path: &Path, error: &str) -> usize {
+    eprintln!("mconv: {}: {}", path.display(), error);
+    1
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| e.to_string())
+}
+
+fn describe(info: &ImageInfo) -> String {
+    let frames = if info.frames == 1 { "1 frame".to_string() } else { format!("{} frames", info.frames) };
+    let mut line = format!("{} {}x{}, {}-bit, {} channels, {}", info.format, info.width, info.height, info.bit_depth, info.channels, frames);
+    if info.has_icc {
+        line += ", ICC profile";
+    }
+    if let Some(orientation) = info.orientation {
+        line += &format!(", orientation {}", orientation);
+    }
+    if let (Some(x), Some(y)) = (info.dpi_x, info.dpi_y) {
+        line += &format!(", {:.0}x{:.0} dpi", x, y);
+    }
+    line
+}
+
+fn probe_files(inputs: &[PathBuf]) -> usize {
+    let mut failures = 0;
+    for path in inputs {
+        match read(path).and_then(|data| probe(&data)) {
+            Ok(info) => println!("{}: {}", path.display(), describe(&info)),
+            Err(e) => failures += fail(path, &e),
+        }
+    }
+    failures
+}
+
+/// Where the `format` version of `input` goes; never the input itself
+fn output_path(input: &Path, output: &OutputOptions, format: ImageFormat) -> Result<PathBuf, String> {
+    let dir = output.out_dir.as_deref().or_else(|| input.parent()).unwrap_or(Path::new(""));
+    let stem = input.file_stem().ok_or("Input has no file name")?;
+    let mut name = stem.to_os_string();
+    name.push(".");
+    name.push(format.extension());
+    let path = dir.join(name);
+    if path == input {
+        return Err(format!("Output {} would overwrite the input; pass --out-dir", path.display()));
+    }
+    Ok(path)
+}
+
+fn write(input: &Path, output: &OutputOptions, format: ImageFormat, data: &[u8]) -> Result<(), String> {
+    let path = output_path(input, output, format)?;
+    fs::write(&path, data).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    println!("{} -> {}", input.display(), path.display());
+    Ok(())
+}
+
+fn create_out_dir(output: &OutputOptions) -> Result<(), String> {
+    match &output.out_dir {
+        Some(dir) => fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e)),
+        None => Ok(()),
+    }
+}
+
+/// Convert every input in one batch, in parallel with the `threads` feature
+fn convert_files(inputs: &[PathBuf], output: &OutputOptions) -> usize {
+    if let Err(e) = create_out_dir(output) {
+        eprintln!("mconv: {}", e);
+        return inputs.len();
+    }
+    let format = output.format.expect("convert always has a format");
+
+    let mut failures = 0;
+    let mut readable = Vec::new();
+    let mut data = Vec::new();
+    for path in inputs {
+        match read(path) {
+            Ok(bytes) => {
+                readable.push(path);
+                data.push(bytes);
+            }
+            Err(e) => failures += fail(path, &e),
+        }
+    }
+
+    let options = ConvertOptions { auto_orient: output.auto_orient };
+    for (path, result) in readable.into_iter().zip(convert_batch(&data, format, &options)) {
+        let written = match (result.data, result.error) {
+            (Some(converted), _) => write(path, output, format, &converted),
+            (None, error) => Err(error.unwrap_or_default()),
+        };
+        if let Err(e) = written {
+            failures += fail(path, &e);
+        }
+    }
+    failures
+}
+
+/// Output format for resizing an `input` image: the `--to` format, else the
+/// input's own, with PNG standing in for JPEG, which only decodes
+fn resize_format(input: ImageFormat, output: &OutputOptions) -> ImageFormat {
+    match (output.format, input) {
+        (Some(format), _) => format,
+        (None, ImageFormat::Jpeg) => ImageFormat::Png,
+        (None, format) => format,
+    }
+}
+
+fn resize_file(path: &Path, output: &OutputOptions, options: &ResizeOptions) -> Result<(), String> {
+    let data = read(path)?;
+    let format = resize_format(detect_format(&data).ok_or("Unknown or unsupported image format")?, output);
+    let image = decode_auto(&data, &ConvertOptions { auto_orient: output.auto_orient })?;
+    let (width, height) = options.target_size(image.width, image.height);
+    let resized = RgbaImage { width, height, data: resize(&image.data, image.width, image.height, width, height, options.algorithm) };
+    write(path, output, format, &encode(&resized, format)?)
+}
+
+fn resize_files(inputs: &[PathBuf], output: &OutputOptions, options: &ResizeOptions) -> usize {
+    if let Err(e) = create_out_dir(output) {
+        eprintln!("mconv: {}", e);
+        return inputs.len();
+    }
+    inputs.iter().map(|path| resize_file(path, output, options).map_or_else(|e| fail(path, &e), |_| 0)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        let output = |out_dir: Option<&str>| OutputOptions { format: None, out_dir: out_dir.map(PathBuf::from), auto_orient: true };
+        let input = Path::new("photos/cat.jpg");
+        assert_eq!(output_path(input, &output(None), ImageFormat::WebP), Ok(PathBuf::from("photos/cat.webp")));
+        assert_eq!(output_path(input, &output(Some("out")), ImageFormat::Png), Ok(PathBuf::from("out/cat.png")));
+        assert_eq!(output_path(Path::new("v1.2.bmp"), &output(None), ImageFormat::Gif), Ok(PathBuf::from("v1.2.gif")));
+        assert_eq!(output_path(Path::new("cat.png"), &output(None), ImageFormat::Png), Err("Output cat.png would overwrite the input; pass --out-dir".to_string()));
+    }
+
+    #[test]
+    fn test_resize_format() {
+        let output = |format| OutputOptions { format, out_dir: None, auto_orient: true };
+        assert_eq!(resize_format(ImageFormat::Gif, &output(None)), ImageFormat::Gif);
+        assert_eq!(resize_format(ImageFormat::Jpeg, &output(None)), ImageFormat::Png);
+        assert_eq!(resize_format(ImageFormat::Jpeg, &output(Some(ImageFormat::WebP))), ImageFormat::WebP);
+    }
+}
@@ -0,0 +1,389 @@
+//! Animated images: a codec-neutral frame sequence and transcoding between
+//! GIF, APNG and animated WebP
+
+mod spritesheet;
+#[cfg(feature = "std")]
+mod transcode;
+
+pub use spritesheet::{frames_to_spritesheet, spritesheet_to_frames, SPRITE_FRAME_DURATION};
+#[cfg(feature = "std")]
+pub use transcode::{decode_animation, detect_animation_format, encode_animation, transcode_animation, TranscodeOptions};
+
+#[cfg(feature = "wasm")]
+use crate::quantize::QuantizeOptions;
+#[cfg(feature = "std")]
+use crate::limits::Budget;
+#[cfg(feature = "std")]
+use crate::resize::{resize, ResizeAlgorithm};
+#[cfg(feature = "wasm")]
+use crate::transform::RgbaImage;
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use crate::utils::to_array_buffer;
+use alloc::{string::String, vec, vec::Vec};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Animated formats with a decoder and encoder in this crate
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif = 0,
+    Apng = 1,
+    /// Lossless (VP8L) on encode; lossy and lossless on decode
+    WebP = 2,
+}
+
+/// One fully composited frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationFrame {
+    /// Canvas-sized RGBA
+    pub data: Vec<u8>,
+    /// Display time in milliseconds
+    pub duration: u32,
+}
+
+/// Fully composited RGBA frames sharing one canvas size
+///
+/// Decoders resolve disposal and blending, so every frame stands alone and
+/// encoders are free to re-derive their own frame deltas.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameSequence {
+    width: u32,
+    height: u32,
+    /// Number of times to play; 0 loops forever
+    pub loop_count: u32,
+    frames: Vec<AnimationFrame>,
+}
+
+impl FrameSequence {
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    /// Append a canvas-sized RGBA frame
+    pub fn push(&mut self, data: Vec<u8>, duration: u32) -> Result<(), String> {
+        check_rgba(&data, self.width, self.height)?;
+        self.frames.push(AnimationFrame { data, duration });
+        Ok(())
+    }
+
+    /// Change how long frame `index` is shown
+    pub fn set_duration(&mut self, index: usize, duration: u32) -> Result<(), String> {
+        let frame = self.frames.get_mut(index).ok_or("Frame index out of range")?;
+        frame.duration = duration;
+        Ok(())
+    }
+
+    /// Total display time in milliseconds
+    pub fn total_duration(&self) -> u64 {
+        self.frames.iter().map(|f| f.duration as u64).sum()
+    }
+
+    /// Re-time to a constant frame rate, showing whichever source frame is
+    /// on screen at the start of each output frame
+    #[cfg(feature = "std")]
+    pub fn resample(&self, fps: f64) -> Result<FrameSequence, String> {
+        if !(fps > 0.0 && fps.is_finite()) {
+            return Err(format!("Invalid frame rate {}", fps));
+        }
+        let total = self.total_duration();
+        if total == 0 || self.frames.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let count = ((total as f64 * fps / 1000.0).round() as u64).max(1);
+        let boundary = |k: u64| if k >= count { total } else { ((k as f64 * 1000.0 / fps).round() as u64).min(total) };
+        let mut output = FrameSequence { frames: Vec::with_capacity(count as usize), ..self.clone_empty() };
+        let mut source = 0;
+        let mut source_end = self.frames[0].duration as u64;
+        for k in 0..count {
+            let (start, end) = (boundary(k), boundary(k + 1));
+            while start >= source_end && source + 1 < self.frames.len() {
+                source += 1;
+                source_end += self.frames[source].duration as u64;
+            }
+            output.frames.push(AnimationFrame {
+                data: self.frames[source].data.clone(),
+                duration: (end - start) as u32,
+            });
+        }
+        Ok(output)
+    }
+
+    /// Scale every frame to `width` x `height`, within the pixel limits set
+    /// by `set_limits`
+    #[cfg(feature = "std")]
+    pub fn resize(&self, width: u32, height: u32, algorithm: ResizeAlgorithm) -> Result<FrameSequence, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("Invalid size {}x{}", width, height));
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err(format!("Cannot resize an empty {}x{} sequence", self.width, self.height));
+        }
+        Budget::new().check_size(width as usize, height as usize)?;
+        let frames = self
+            .frames
+            .iter()
+            .map(|f| AnimationFrame {
+                data: resize(&f.data, self.width, self.height, width, height, algorithm),
+                duration: f.duration,
+            })
+            .collect();
+        Ok(FrameSequence { width, height, loop_count: self.loop_count, frames })
+    }
+
+    #[cfg(feature = "std")]
+    fn clone_empty(&self) -> FrameSequence {
+        FrameSequence { frames: Vec::new(), ..*self }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FrameSequence {
+    /// An empty sequence that loops forever
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32) -> FrameSequence {
+        FrameSequence { width, height, loop_count: 0, frames: Vec::new() }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = frameCount))]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Total display time in milliseconds
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn duration(&self) -> f64 {
+        self.total_duration() as f64
+    }
+
+    /// RGBA canvas of frame `index`
+    #[cfg(feature = "wasm")]
+    pub fn frame(&self, index: usize) -> Result<RgbaImage, JsError> {
+        let frame = self.frames.get(index).ok_or_else(|| JsError::new("Frame index out of range"))?;
+        Ok(RgbaImage { width: self.width, height: self.height, data: frame.data.clone() })
+    }
+
+    /// Display time of frame `index` in milliseconds
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = frameDuration)]
+    pub fn frame_duration(&self, index: usize) -> Result<u32, JsError> {
+        self.frames.get(index).map(|f| f.duration).ok_or_else(|| JsError::new("Frame index out of range"))
+    }
+
+    /// Show frame `index` for `duration` milliseconds
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = setFrameDuration)]
+    pub fn set_duration_js(&mut self, index: usize, duration: u32) -> Result<(), JsError> {
+        self.set_duration(index, duration).map_err(|e| JsError::new(&e))
+    }
+
+    /// Append a canvas-sized RGBA frame shown for `duration` milliseconds
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = pushFrame)]
+    pub fn push_js(&mut self, data: Vec<u8>, duration: u32) -> Result<(), JsError> {
+        self.push(data, duration).map_err(|e| JsError::new(&e))
+    }
+
+    /// Copy re-timed to a constant frame rate
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = resample)]
+    pub fn resample_js(&self, fps: f64) -> Result<FrameSequence, JsError> {
+        self.resample(fps).map_err(|e| JsError::new(&e))
+    }
+
+    /// Copy with every frame scaled to `width` x `height`
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = resize)]
+    pub fn resize_js(&self, width: u32, height: u32, algorithm: ResizeAlgorithm) -> Result<FrameSequence, JsError> {
+        self.resize(width, height, algorithm).map_err(|e| JsError::new(&e))
+    }
+
+    /// Move each frame's pixels into its own transferable `ArrayBuffer`,
+    /// consuming the sequence; frames are freed as they are copied out
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = intoTransferable)]
+    pub fn into_transferable(self) -> js_sys::Array {
+        self.frames.into_iter().map(|frame| to_array_buffer(&frame.data)).collect()
+    }
+}
+
+/// A frame rectangle on the canvas
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Compositing surface shared by the animation decoders
+pub(crate) struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Canvas {
+    /// A fully transparent canvas
+    pub fn new(width: u32, height: u32) -> Canvas {
+        Canvas { width, height, data: vec![0; width as usize * height as usize * 4] }
+    }
+
+    /// Visible part of `rect` as canvas row ranges: (canvas offset, source offset, bytes)
+    fn rows(&self, rect: Rect) -> impl Iterator<Item = (usize, usize, usize)> {
+        let w = rect.width.min(self.width.saturating_sub(rect.x)) as usize * 4;
+        let h = if w == 0 { 0 } else { rect.height.min(self.height.saturating_sub(rect.y)) as usize };
+        let (stride, src_stride) = (self.width as usize * 4, rect.width as usize * 4);
+        let origin = rect.y as usize * stride + rect.x as usize * 4;
+        (0..h).map(move |row| (origin + row * stride, row * src_stride, w))
+    }
+
+    /// Draw a `rect`-sized RGBA image, replacing pixels or alpha-blending over them
+    pub fn draw(&mut self, src: &[u8], rect: Rect, blend: bool) {
+        for (at, from, len) in self.rows(rect).collect::<Vec<_>>() {
+            let (dst, src) = (&mut self.data[at..at + len], &src[from..from + len]);
+            if !blend {
+                dst.copy_from_slice(src);
+                continue;
+            }
+            for (d, s) in dst.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+                blend_over(d, s);
+            }
+        }
+    }
+
+    /// Reset `rect` to transparent
+    #[cfg_attr(not(any(feature = "gif", feature = "png", feature = "webp")), allow(dead_code))]
+    pub fn clear(&mut self, rect: Rect) {
+        for (at, _, len) in self.rows(rect).collect::<Vec<_>>() {
+            self.data[at..at + len].fill(0);
+        }
+    }
+}
+
+/// Smallest rectangle containing every pixel that differs between two canvases
+#[cfg_attr(not(any(feature = "gif", feature = "png", feature = "webp")), allow(dead_code))]
+pub(crate) fn changed_rect(prev: &[u8], cur: &[u8], width: u32) -> Option<Rect> {
+    let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+    for (i, (a, b)) in prev.chunks_exact(4).zip(cur.chunks_exact(4)).enumerate() {
+        if a != b {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+        }
+    }
+    (x0 != u32::MAX).then(|| Rect { x: x0, y: y0, width: x1 - x0 + 1, height: y1 - y0 + 1 })
+}
+
+/// Non-premultiplied source-over blending of one pixel
+#[inline]
+pub(crate) fn blend_over(dst: &mut [u8], src: &[u8]) {
+    let sa = src[3] as u32;
+    if sa == 255 || dst[3] == 0 {
+        dst.copy_from_slice(src);
+        return;
+    }
+    if sa == 0 {
+        return;
+    }
+    let da = dst[3] as u32 * (255 - sa) / 255;
+    let oa = sa + da;
+    for c in 0..3 {
+        dst[c] = ((src[c] as u32 * sa + dst[c] as u32 * da + oa / 2) / oa) as u8;
+    }
+    dst[3] = oa as u8;
+}
+
+/// Decode any supported animation to a frame sequence
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeAnimation)]
+pub fn decode_animation_js(data: &[u8]) -> Result<FrameSequence, JsError> {
+    decode_animation(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode a frame sequence; `quantize` only applies to GIF
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeAnimation)]
+pub fn encode_animation_js(sequence: &FrameSequence, format: AnimationFormat, quantize: &QuantizeOptions) -> Result<Vec<u8>, JsError> {
+    encode_animation(sequence, format, quantize).map_err(|e| JsError::new(&e))
+}
+
+/// Decode an animation, resample and resize it, and re-encode as `format`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = transcodeAnimation)]
+pub fn transcode_animation_js(input: &[u8], format: AnimationFormat, options: &TranscodeOptions) -> Result<Vec<u8>, JsError> {
+    transcode_animation(input, format, options).map_err(|e| JsError::new(&e))
+}
+
+/// Lay frames out on a grid, `columns` per row with `padding` pixels between cells
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = framesToSpritesheet)]
+pub fn frames_to_spritesheet_js(sequence: &FrameSequence, columns: u32, padding: u32) -> Result<RgbaImage, JsError> {
+    frames_to_spritesheet(sequence, columns, padding).map_err(|e| JsError::new(&e))
+}
+
+/// Cut a sprite sheet into frames of `cellWidth` x `cellHeight`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = spritesheetToFrames)]
+pub fn spritesheet_to_frames_js(data: &[u8], width: u32, height: u32, cell_width: u32, cell_height: u32) -> Result<FrameSequence, JsError> {
+    spritesheet_to_frames(data, width, height, cell_width, cell_height).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(color: [u8; 4], n: usize) -> Vec<u8> {
+        color.repeat(n)
+    }
+
+    #[test]
+    fn test_resample_and_resize() {
+        let mut sequence = FrameSequence::new(2, 2);
+        sequence.push(solid([255, 0, 0, 255], 4), 100).unwrap();
+        sequence.push(solid([0, 255, 0, 255], 4), 50).unwrap();
+        sequence.push(solid([0, 0, 255, 255], 4), 150).unwrap();
+        assert!(sequence.push(vec![0; 3], 10).is_err());
+
+        let resampled = sequence.resample(20.0).unwrap();
+        assert_eq!(resampled.total_duration(), 300);
+        assert_eq!(resampled.frames().len(), 6);
+        let colors: Vec<u8> = resampled.frames().iter().map(|f| f.data[1]).collect();
+        assert_eq!(colors, [0, 0, 255, 0, 0, 0]);
+        assert_eq!(resampled.frames()[4].data[2], 255);
+
+        let resized = sequence.resize(4, 1, ResizeAlgorithm::Nearest).unwrap();
+        assert_eq!((resized.width(), resized.height()), (4, 1));
+        assert_eq!(resized.frames()[2].data, solid([0, 0, 255, 255], 4));
+        assert!(sequence.resample(0.0).is_err());
+        assert!(sequence.resize(0, 4, ResizeAlgorithm::Nearest).is_err());
+        assert!(sequence.resize(1 << 16, 1 << 16, ResizeAlgorithm::Nearest).unwrap_err().contains("over the limit"));
+
+        let mut empty = FrameSequence::new(0, 0);
+        empty.push(Vec::new(), 100).unwrap();
+        assert_eq!(empty.resize(4, 4, ResizeAlgorithm::Bilinear).unwrap_err(), "Cannot resize an empty 0x0 sequence");
+    }
+
+    #[test]
+    fn test_canvas_draw_and_blend() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.draw(&solid([10, 20, 30, 255], 4), Rect { x: 2, y: 1, width: 2, height: 2 }, false);
+        assert_eq!(&canvas.data[20..24], &[10, 20, 30, 255]);
+        canvas.draw(&[200, 0, 0, 128], Rect { x: 2, y: 1, width: 1, height: 1 }, true);
+        assert_eq!(&canvas.data[20..24], &[105, 10, 15, 255]);
+        canvas.clear(Rect { x: 0, y: 0, width: 3, height: 2 });
+        assert!(canvas.data.iter().all(|&b| b == 0));
+    }
+}
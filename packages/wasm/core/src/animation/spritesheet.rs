@@ -0,0 +1,88 @@
+//! Sprite sheets: frames laid out on a grid in one image
+
+use super::{Canvas, FrameSequence, Rect};
+use crate::transform::{crop, CropRegion, RgbaImage};
+use crate::utils::check_rgba;
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
+/// Display time given to frames cut from a sheet, which carries no timing
+pub const SPRITE_FRAME_DURATION: u32 = 100;
+
+/// Lay frames out left to right, top to bottom, `columns` per row with
+/// `padding` transparent pixels between cells
+pub fn frames_to_spritesheet(sequence: &FrameSequence, columns: u32, padding: u32) -> Result<RgbaImage, String> {
+    let frames = sequence.frames();
+    if frames.is_empty() {
+        return Err("Animation has no frames".to_string());
+    }
+    if columns == 0 {
+        return Err("Sprite sheet needs at least one column".to_string());
+    }
+    let (cell_width, cell_height) = (sequence.width(), sequence.height());
+    let columns = columns.min(frames.len() as u32);
+    let rows = (frames.len() as u32).div_ceil(columns);
+    let size = |cells: u32, cell: u32| cells.checked_mul(cell)?.checked_add((cells - 1).checked_mul(padding)?);
+    let (Some(width), Some(height)) = (size(columns, cell_width), size(rows, cell_height)) else {
+        return Err("Sprite sheet is too large".to_string());
+    };
+
+    let mut canvas = Canvas::new(width, height);
+    for (i, frame) in frames.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        let rect = Rect { x: column * (cell_width + padding), y: row * (cell_height + padding), width: cell_width, height: cell_height };
+        canvas.draw(&frame.data, rect, false);
+    }
+    Ok(RgbaImage { width, height, data: canvas.data })
+}
+
+/// Cut a sheet into `cell_width` x `cell_height` frames, row by row
+///
+/// Cells are packed without gaps; a partial cell at the right or bottom edge
+/// is ignored, as are fully transparent cells at the end of the sheet (the
+/// unused tail of the last row). Each frame lasts `SPRITE_FRAME_DURATION` ms.
+pub fn spritesheet_to_frames(data: &[u8], width: u32, height: u32, cell_width: u32, cell_height: u32) -> Result<FrameSequence, String> {
+    check_rgba(data, width, height)?;
+    if cell_width == 0 || cell_height == 0 || cell_width > width || cell_height > height {
+        return Err(format!("Cell size {}x{} does not fit a {}x{} sheet", cell_width, cell_height, width, height));
+    }
+    let (columns, rows) = (width / cell_width, height / cell_height);
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let region = CropRegion { x: column * cell_width, y: row * cell_height, width: cell_width, height: cell_height };
+            cells.push(crop(data, width, height, region)?);
+        }
+    }
+    let used = cells.iter().rposition(|cell| cell.chunks_exact(4).any(|p| p[3] != 0)).map_or(1, |last| last + 1);
+
+    let mut sequence = FrameSequence::new(cell_width, cell_height);
+    for cell in cells.into_iter().take(used) {
+        sequence.push(cell, SPRITE_FRAME_DURATION)?;
+    }
+    Ok(sequence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheet_roundtrip() {
+        let mut sequence = FrameSequence::new(2, 3);
+        for i in 1..=5u8 {
+            sequence.push([i, 0, 0, 255].repeat(6), SPRITE_FRAME_DURATION).unwrap();
+        }
+        let sheet = frames_to_spritesheet(&sequence, 3, 0).unwrap();
+        assert_eq!((sheet.width, sheet.height), (6, 6));
+        // The unused sixth cell is dropped again
+        assert_eq!(spritesheet_to_frames(&sheet.data, 6, 6, 2, 3).unwrap(), sequence);
+
+        let padded = frames_to_spritesheet(&sequence, 2, 1).unwrap();
+        assert_eq!((padded.width, padded.height), (5, 11));
+        assert_eq!(&padded.data[(4 * 5 + 3) * 4..(4 * 5 + 3) * 4 + 4], &[4, 0, 0, 255]);
+        assert_eq!(&padded.data[(3 * 5 + 3) * 4..(3 * 5 + 3) * 4 + 4], &[0, 0, 0, 0]);
+
+        assert!(frames_to_spritesheet(&sequence, 0, 0).is_err());
+        assert!(spritesheet_to_frames(&sheet.data, 6, 6, 7, 3).is_err());
+    }
+}
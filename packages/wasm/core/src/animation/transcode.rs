@@ -0,0 +1,169 @@
+//! Format dispatch and the decode → resample → resize → encode pipeline
+
+use super::{AnimationFormat, FrameSequence};
+use crate::convert::not_built;
+use crate::metadata::container::PNG_SIGNATURE;
+use crate::quantize::{DitherMethod, QuantizeMethod, QuantizeOptions};
+use crate::resize::ResizeAlgorithm;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Settings for `transcode_animation`
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TranscodeOptions {
+    /// Constant output frame rate; 0 keeps the source timing
+    pub fps: f64,
+    /// Output width; 0 derives it from `height` keeping the aspect ratio
+    pub width: u32,
+    /// Output height; 0 derives it from `width`, both 0 keep the source size
+    pub height: u32,
+    pub algorithm: ResizeAlgorithm,
+    /// GIF palette size per frame, 2-256
+    pub max_colors: u32,
+    pub method: QuantizeMethod,
+    pub dither: DitherMethod,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TranscodeOptions {
+    /// Keep timing and size, 256-color median cut palettes without dithering
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> TranscodeOptions {
+        TranscodeOptions {
+            fps: 0.0,
+            width: 0,
+            height: 0,
+            algorithm: ResizeAlgorithm::Bilinear,
+            max_colors: 256,
+            method: QuantizeMethod::MedianCut,
+            dither: DitherMethod::None,
+        }
+    }
+}
+
+impl Default for TranscodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TranscodeOptions {
+    fn quantize(&self) -> QuantizeOptions {
+        QuantizeOptions { max_colors: self.max_colors, method: self.method, dither: self.dither }
+    }
+
+    /// Output size for a `width` x `height` source
+    fn target_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scaled = |len: u32, num: u32, den: u32| ((len as f64 * num as f64 / den.max(1) as f64).round() as u32).max(1);
+        match (self.width, self.height) {
+            (0, 0) => (width, height),
+            (w, 0) => (w, scaled(height, w, width)),
+            (0, h) => (scaled(width, h, height), h),
+            (w, h) => (w, h),
+        }
+    }
+}
+
+/// Identify an animation container from its signature; still PNGs count as APNG
+pub fn detect_animation_format(data: &[u8]) -> Option<AnimationFormat> {
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some(AnimationFormat::Gif)
+    } else if data.starts_with(&PNG_SIGNATURE) {
+        Some(AnimationFormat::Apng)
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some(AnimationFormat::WebP)
+    } else {
+        None
+    }
+}
+
+/// Decode a GIF, (A)PNG or WebP into composited frames
+pub fn decode_animation(data: &[u8]) -> Result<FrameSequence, String> {
+    match detect_animation_format(data) {
+        #[cfg(feature = "gif")]
+        Some(AnimationFormat::Gif) => crate::gif::decode_gif(data),
+        #[cfg(feature = "png")]
+        Some(AnimationFormat::Apng) => crate::png::decode_apng(data),
+        #[cfg(feature = "webp")]
+        Some(AnimationFormat::WebP) => crate::webp::decode_webp_animation(data),
+        #[allow(unreachable_patterns)]
+        Some(format) => Err(not_built(format)),
+        None => Err("Unknown or unsupported animation format".to_string()),
+    }
+}
+
+/// Encode frames as GIF, APNG or lossless animated WebP
+#[cfg_attr(not(feature = "gif"), allow(unused_variables))]
+pub fn encode_animation(sequence: &FrameSequence, format: AnimationFormat, quantize: &QuantizeOptions) -> Result<Vec<u8>, String> {
+    match format {
+        #[cfg(feature = "gif")]
+        AnimationFormat::Gif => crate::gif::encode_gif(sequence, quantize),
+        #[cfg(feature = "png")]
+        AnimationFormat::Apng => crate::png::encode_apng(sequence),
+        #[cfg(feature = "webp")]
+        AnimationFormat::WebP => crate::webp::encode_webp_animation(sequence),
+        #[allow(unreachable_patterns)]
+        _ => Err(not_built(format)),
+    }
+}
+
+/// Decode any supported animation, optionally resample it to a constant
+/// frame rate and resize it, then encode it as `format`
+pub fn transcode_animation(input: &[u8], format: AnimationFormat, options: &TranscodeOptions) -> Result<Vec<u8>, String> {
+    let mut sequence = decode_animation(input)?;
+    // Resampling first means resizing only the frames that survive
+    if options.fps != 0.0 {
+        sequence = sequence.resample(options.fps)?;
+    }
+    let (width, height) = options.target_size(sequence.width(), sequence.height());
+    if (width, height) != (sequence.width(), sequence.height()) {
+        sequence = sequence.resize(width, height, options.algorithm)?;
+    }
+    encode_animation(&sequence, format, &options.quantize())
+}
+
+#[cfg(all(test, feature = "gif", feature = "png", feature = "webp"))]
+mod tests {
+    use super::*;
+
+    fn sample() -> FrameSequence {
+        let mut sequence = FrameSequence::new(8, 6);
+        sequence.loop_count = 3;
+        for i in 0..4u8 {
+            let mut data = vec![0u8; 8 * 6 * 4];
+            for (p, px) in data.chunks_exact_mut(4).enumerate() {
+                let (x, y) = (p % 8, p / 8);
+                let on = x / 2 == i as usize && y > 0;
+                px.copy_from_slice(&if on { [255, 40 * i, 0, 255] } else if y == 0 { [0, 0, 0, 0] } else { [0, 0, 200, 255] });
+            }
+            sequence.push(data, 40 + 20 * i as u32).unwrap();
+        }
+        sequence
+    }
+
+    #[test]
+    fn test_roundtrip_all_formats() {
+        let sequence = sample();
+        let quantize = QuantizeOptions::new(256);
+        for format in [AnimationFormat::Gif, AnimationFormat::Apng, AnimationFormat::WebP] {
+            let encoded = encode_animation(&sequence, format, &quantize).unwrap();
+            assert_eq!(detect_animation_format(&encoded), Some(format));
+            let decoded = decode_animation(&encoded).unwrap();
+            assert_eq!(decoded, sequence, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_transcode_gif_to_webp_with_resampling() {
+        let gif = encode_animation(&sample(), AnimationFormat::Gif, &QuantizeOptions::new(256)).unwrap();
+        let options = TranscodeOptions { fps: 10.0, width: 4, ..TranscodeOptions::new() };
+        let webp = transcode_animation(&gif, AnimationFormat::WebP, &options).unwrap();
+        let decoded = decode_animation(&webp).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (4, 3));
+        assert_eq!(decoded.loop_count, 3);
+        let durations: Vec<u32> = decoded.frames().iter().map(|f| f.duration).collect();
+        assert_eq!(durations, [100, 100, 80]);
+        assert!(transcode_animation(b"nope", AnimationFormat::Gif, &options).is_err());
+    }
+}
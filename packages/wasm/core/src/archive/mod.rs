@@ -0,0 +1,5 @@
+//! Archive formats
+
+pub mod seven_zip;
+pub mod tar;
+pub mod zip;
@@ -0,0 +1,610 @@
+//! 7z archives - read-only subset
+//!
+//! Lists any 7z archive and extracts entries from folders coded with a single
+//! Copy, LZMA or LZMA2 coder, including archives with a compressed header.
+//! Encrypted archives and filter chains (BCJ, Delta) are rejected on extraction.
+
+use crate::checksum::crc32;
+use crate::compress::lzma::lzma2_decode_with_limit;
+use crate::compress::{limit_error, lzma_decode_raw};
+use crate::limits::output_limit;
+use crate::utils::read_u32_le;
+use std::cell::RefCell;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const SIGNATURE: [u8; 6] = [b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C];
+const START_HEADER_SIZE: u64 = 32;
+
+const K_END: u8 = 0x00;
+const K_HEADER: u8 = 0x01;
+const K_ARCHIVE_PROPERTIES: u8 = 0x02;
+const K_ADDITIONAL_STREAMS_INFO: u8 = 0x03;
+const K_MAIN_STREAMS_INFO: u8 = 0x04;
+const K_FILES_INFO: u8 = 0x05;
+const K_PACK_INFO: u8 = 0x06;
+const K_UNPACK_INFO: u8 = 0x07;
+const K_SUBSTREAMS_INFO: u8 = 0x08;
+const K_SIZE: u8 = 0x09;
+const K_CRC: u8 = 0x0A;
+const K_FOLDER: u8 = 0x0B;
+const K_CODERS_UNPACK_SIZE: u8 = 0x0C;
+const K_NUM_UNPACK_STREAM: u8 = 0x0D;
+const K_EMPTY_STREAM: u8 = 0x0E;
+const K_EMPTY_FILE: u8 = 0x0F;
+const K_NAME: u8 = 0x11;
+const K_MTIME: u8 = 0x14;
+const K_WIN_ATTRIBUTES: u8 = 0x15;
+const K_ENCODED_HEADER: u8 = 0x17;
+
+const METHOD_COPY: &[u8] = &[0x00];
+const METHOD_LZMA: &[u8] = &[0x03, 0x01, 0x01];
+const METHOD_LZMA2: &[u8] = &[0x21];
+const METHOD_AES: &[u8] = &[0x06, 0xF1, 0x07, 0x01];
+
+const ATTRIBUTE_DIRECTORY: u32 = 0x10;
+/// Seconds between the FILETIME epoch (1601) and the Unix epoch
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+/// One file or directory in a 7z archive
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SevenZipEntry {
+    pub name: String,
+    pub size: u64,
+    pub is_dir: bool,
+    /// Modification time in seconds since the Unix epoch, 0 if not stored
+    pub mtime: u64,
+    stream: Option<Substream>,
+}
+
+/// Location of a file's data inside a decoded folder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Substream {
+    folder: usize,
+    offset: u64,
+    crc: Option<u32>,
+}
+
+struct Coder {
+    method: Vec<u8>,
+    props: Vec<u8>,
+}
+
+struct Folder {
+    coders: Vec<Coder>,
+    /// Index of the first pack stream used by this folder
+    pack_index: usize,
+    unpack_size: u64,
+    crc: Option<u32>,
+}
+
+#[derive(Default)]
+struct StreamsInfo {
+    pack_pos: u64,
+    pack_sizes: Vec<u64>,
+    folders: Vec<Folder>,
+    /// (folder, offset, size, crc) of each unpacked stream, in file order
+    substreams: Vec<(usize, u64, u64, Option<u32>)>,
+}
+
+/// Cursor over header bytes
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let b = *self.data.get(self.pos).ok_or("Truncated 7z header")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let slice = self.pos.checked_add(n).and_then(|end| self.data.get(self.pos..end)).ok_or("Truncated 7z header")?;
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn expect(&mut self, id: u8) -> Result<(), String> {
+        match self.byte()? {
+            b if b == id => Ok(()),
+            b => Err(format!("Unexpected 7z property 0x{:02x}, expected 0x{:02x}", b, id)),
+        }
+    }
+
+    /// Variable-length number: leading one bits of the first byte count extra bytes
+    fn number(&mut self) -> Result<u64, String> {
+        let first = self.byte()?;
+        let mut value = 0u64;
+        for i in 0..8 {
+            let mask = 0x80u8 >> i;
+            if first & mask == 0 {
+                return Ok(value | ((first & mask.wrapping_sub(1)) as u64) << (8 * i));
+            }
+            value |= (self.byte()? as u64) << (8 * i);
+        }
+        Ok(value)
+    }
+
+    /// A number used as an item count, bounded by the remaining data
+    fn count(&mut self) -> Result<usize, String> {
+        let n = self.number()?;
+        if n > ((self.data.len() - self.pos) as u64 + 1) * 8 {
+            return Err("Invalid 7z item count".to_string());
+        }
+        Ok(n as usize)
+    }
+
+    fn bits(&mut self, n: usize) -> Result<Vec<bool>, String> {
+        let bytes = self.bytes(n.div_ceil(8))?;
+        Ok((0..n).map(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0).collect())
+    }
+
+    /// "All defined" flag, otherwise an explicit bit vector
+    fn defined(&mut self, n: usize) -> Result<Vec<bool>, String> {
+        if self.byte()? != 0 {
+            Ok(vec![true; n])
+        } else {
+            self.bits(n)
+        }
+    }
+
+    fn digests(&mut self, n: usize) -> Result<Vec<Option<u32>>, String> {
+        let defined = self.defined(n)?;
+        defined.into_iter().map(|d| if d { self.bytes(4).map(|b| Some(read_u32_le(b, 0))) } else { Ok(None) }).collect()
+    }
+}
+
+/// Parse a folder: (folder, output stream count, main output index, packed stream count)
+fn read_folder(r: &mut Reader, pack_index: usize) -> Result<(Folder, usize, usize, usize), String> {
+    let num_coders = r.count()?;
+    let mut coders = Vec::with_capacity(num_coders);
+    let (mut total_in, mut total_out) = (0, 0);
+    for _ in 0..num_coders {
+        let flags = r.byte()?;
+        if flags & 0x80 != 0 {
+            return Err("7z alternative coder methods are not supported".to_string());
+        }
+        let method = r.bytes((flags & 0x0F) as usize)?.to_vec();
+        let (num_in, num_out) = if flags & 0x10 != 0 { (r.count()?, r.count()?) } else { (1, 1) };
+        let props = if flags & 0x20 != 0 {
+            let n = r.count()?;
+            r.bytes(n)?.to_vec()
+        } else {
+            Vec::new()
+        };
+        total_in += num_in;
+        total_out += num_out;
+        coders.push(Coder { method, props });
+    }
+    if total_out == 0 {
+        return Err("7z folder without output".to_string());
+    }
+
+    let mut bound_out = vec![false; total_out];
+    for _ in 0..total_out - 1 {
+        r.number()?;
+        let out = r.number()? as usize;
+        *bound_out.get_mut(out).ok_or("Invalid 7z bind pair")? = true;
+    }
+    let num_packed = total_in.checked_sub(total_out - 1).ok_or("Invalid 7z folder")?;
+    if num_packed > 1 {
+        for _ in 0..num_packed {
+            r.number()?;
+        }
+    }
+    let main_out = bound_out.iter().position(|&b| !b).ok_or("Invalid 7z folder")?;
+    let folder = Folder { coders, pack_index, unpack_size: 0, crc: None };
+    Ok((folder, total_out, main_out, num_packed))
+}
+
+fn read_streams_info(r: &mut Reader) -> Result<StreamsInfo, String> {
+    let mut info = StreamsInfo::default();
+    let mut substreams_read = false;
+    loop {
+        match r.byte()? {
+            K_END => break,
+            K_PACK_INFO => {
+                info.pack_pos = r.number()?;
+                let n = r.count()?;
+                loop {
+                    match r.byte()? {
+                        K_END => break,
+                        K_SIZE => info.pack_sizes = (0..n).map(|_| r.number()).collect::<Result<_, _>>()?,
+                        K_CRC => drop(r.digests(n)?),
+                        id => return Err(format!("Unexpected 7z pack info property 0x{:02x}", id)),
+                    }
+                }
+            }
+            K_UNPACK_INFO => {
+                r.expect(K_FOLDER)?;
+                let n = r.count()?;
+                if r.byte()? != 0 {
+                    return Err("External 7z folders are not supported".to_string());
+                }
+                let mut layouts = Vec::with_capacity(n);
+                let mut pack_index = 0;
+                for _ in 0..n {
+                    let (folder, outs, main_out, packed) = read_folder(r, pack_index)?;
+                    pack_index += packed;
+                    layouts.push((folder, outs, main_out));
+                }
+                r.expect(K_CODERS_UNPACK_SIZE)?;
+                for (mut folder, outs, main_out) in layouts {
+                    for i in 0..outs {
+                        let size = r.number()?;
+                        if i == main_out {
+                            folder.unpack_size = size;
+                        }
+                    }
+                    info.folders.push(folder);
+                }
+                loop {
+                    match r.byte()? {
+                        K_END => break,
+                        K_CRC => {
+                            for (folder, crc) in info.folders.iter_mut().zip(r.digests(n)?) {
+                                folder.crc = crc;
+                            }
+                        }
+                        id => return Err(format!("Unexpected 7z unpack info property 0x{:02x}", id)),
+                    }
+                }
+            }
+            K_SUBSTREAMS_INFO => {
+                read_substreams(r, &mut info)?;
+                substreams_read = true;
+            }
+            id => return Err(format!("Unexpected 7z streams property 0x{:02x}", id)),
+        }
+    }
+    if !substreams_read {
+        info.substreams = info.folders.iter().enumerate().map(|(i, f)| (i, 0, f.unpack_size, f.crc)).collect();
+    }
+    Ok(info)
+}
+
+fn read_substreams(r: &mut Reader, info: &mut StreamsInfo) -> Result<(), String> {
+    let mut counts = vec![1usize; info.folders.len()];
+    let mut id = r.byte()?;
+    if id == K_NUM_UNPACK_STREAM {
+        for count in counts.iter_mut() {
+            *count = r.count()?;
+        }
+        id = r.byte()?;
+    }
+
+    // Sizes are stored for all but the last stream of each folder
+    for (i, &count) in counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        if count > 1 && id != K_SIZE {
+            return Err("Missing 7z substream sizes".to_string());
+        }
+        let mut offset = 0u64;
+        for _ in 1..count {
+            let size = r.number()?;
+            info.substreams.push((i, offset, size, None));
+            offset = offset.checked_add(size).ok_or("Invalid 7z substream size")?;
+        }
+        let last = info.folders[i].unpack_size.checked_sub(offset).ok_or("Invalid 7z substream size")?;
+        info.substreams.push((i, offset, last, None));
+    }
+    if id == K_SIZE {
+        id = r.byte()?;
+    }
+
+    // A folder holding one stream reuses the folder CRC; the rest are listed here
+    let known: Vec<bool> = info.substreams.iter().map(|&(f, ..)| counts[f] == 1 && info.folders[f].crc.is_some()).collect();
+    for (stream, &known) in info.substreams.iter_mut().zip(&known) {
+        if known {
+            stream.3 = info.folders[stream.0].crc;
+        }
+    }
+    loop {
+        match id {
+            K_END => return Ok(()),
+            K_CRC => {
+                let mut digests = r.digests(known.iter().filter(|&&k| !k).count())?.into_iter();
+                for (stream, &known) in info.substreams.iter_mut().zip(&known) {
+                    if !known {
+                        stream.3 = digests.next().flatten();
+                    }
+                }
+            }
+            id => return Err(format!("Unexpected 7z substreams property 0x{:02x}", id)),
+        }
+        id = r.byte()?;
+    }
+}
+
+fn read_files(r: &mut Reader, substreams: &[(usize, u64, u64, Option<u32>)]) -> Result<Vec<SevenZipEntry>, String> {
+    let n = r.count()?;
+    let mut empty_stream = vec![false; n];
+    let mut empty_file = Vec::new();
+    let mut names = Vec::new();
+    let mut mtimes = vec![None; n];
+    let mut attributes = vec![None; n];
+
+    loop {
+        let id = r.number()?;
+        if id == K_END as u64 {
+            break;
+        }
+        let size = r.count()?;
+        let mut p = Reader::new(r.bytes(size)?);
+        match id as u8 {
+            K_EMPTY_STREAM => empty_stream = p.bits(n)?,
+            K_EMPTY_FILE => empty_file = p.bits(empty_stream.iter().filter(|&&e| e).count())?,
+            K_NAME => {
+                if p.byte()? != 0 {
+                    return Err("External 7z names are not supported".to_string());
+                }
+                let units: Vec<u16> = p.data[1..].chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+                names = units.split(|&u| u == 0).take(n).map(String::from_utf16_lossy).collect();
+            }
+            K_MTIME | K_WIN_ATTRIBUTES => {
+                let defined = p.defined(n)?;
+                if p.byte()? != 0 {
+                    return Err("External 7z file properties are not supported".to_string());
+                }
+                for (i, _) in defined.iter().enumerate().filter(|(_, &d)| d) {
+                    if id as u8 == K_MTIME {
+                        let filetime = u64::from_le_bytes(p.bytes(8)?.try_into().unwrap());
+                        mtimes[i] = Some((filetime / 10_000_000).saturating_sub(FILETIME_UNIX_OFFSET));
+                    } else {
+                        attributes[i] = Some(read_u32_le(p.bytes(4)?, 0));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if names.len() != n {
+        return Err("Missing 7z file names".to_string());
+    }
+
+    let mut streams = substreams.iter();
+    let mut empty_index = 0;
+    let mut entries = Vec::with_capacity(n);
+    for (i, name) in names.into_iter().enumerate() {
+        let (stream, size, is_dir) = if empty_stream[i] {
+            let is_file = empty_file.get(empty_index).copied().unwrap_or(false);
+            empty_index += 1;
+            (None, 0, !is_file)
+        } else {
+            let &(folder, offset, size, crc) = streams.next().ok_or("7z file without a stream")?;
+            (Some(Substream { folder, offset, crc }), size, false)
+        };
+        entries.push(SevenZipEntry {
+            name,
+            size,
+            is_dir: is_dir || attributes[i].is_some_and(|a| a & ATTRIBUTE_DIRECTORY != 0),
+            mtime: mtimes[i].unwrap_or(0),
+            stream,
+        });
+    }
+    Ok(entries)
+}
+
+/// Decode one folder's packed data, refusing folders over the output byte limit
+fn decode_folder(data: &[u8], info: &StreamsInfo, index: usize) -> Result<Vec<u8>, String> {
+    let folder = &info.folders[index];
+    let limit = output_limit();
+    let unpack_size = usize::try_from(folder.unpack_size).ok().filter(|&n| n <= limit).ok_or_else(|| limit_error(limit))?;
+    let coder = match folder.coders.as_slice() {
+        [coder] => coder,
+        coders if coders.iter().any(|c| c.method == METHOD_AES) => return Err("Encrypted 7z archives are not supported".to_string()),
+        _ => return Err("7z coder chains are not supported".to_string()),
+    };
+
+    let skipped: u64 = info.pack_sizes.iter().take(folder.pack_index).sum();
+    let start = START_HEADER_SIZE + info.pack_pos + skipped;
+    let size = *info.pack_sizes.get(folder.pack_index).ok_or("Missing 7z pack size")?;
+    let packed = usize::try_from(start)
+        .ok()
+        .zip(usize::try_from(size).ok())
+        .and_then(|(s, n)| data.get(s..s.checked_add(n)?))
+        .ok_or("7z packed stream out of range")?;
+
+    let output = match coder.method.as_slice() {
+        METHOD_COPY => packed.to_vec(),
+        METHOD_LZMA => lzma_decode_raw(&coder.props, packed, Some(folder.unpack_size))?,
+        METHOD_LZMA2 => lzma2_decode_with_limit(packed, unpack_size)?.0,
+        METHOD_AES => return Err("Encrypted 7z archives are not supported".to_string()),
+        method => return Err(format!("Unsupported 7z method {:02x?}", method)),
+    };
+    if output.len() as u64 != folder.unpack_size || folder.crc.is_some_and(|crc| crc != crc32(&output)) {
+        return Err("7z folder checksum mismatch".to_string());
+    }
+    Ok(output)
+}
+
+/// Parse the archive header: (entries, main streams)
+fn read_header(data: &[u8]) -> Result<(Vec<SevenZipEntry>, StreamsInfo), String> {
+    if data.len() < START_HEADER_SIZE as usize || data[..6] != SIGNATURE {
+        return Err("Invalid 7z signature".to_string());
+    }
+    if crc32(&data[12..32]) != read_u32_le(data, 8) {
+        return Err("7z start header checksum mismatch".to_string());
+    }
+    let offset = u64::from_le_bytes(data[12..20].try_into().unwrap());
+    let size = u64::from_le_bytes(data[20..28].try_into().unwrap());
+    if size == 0 {
+        return Ok((Vec::new(), StreamsInfo::default()));
+    }
+    let mut header = START_HEADER_SIZE
+        .checked_add(offset)
+        .and_then(|start| Some((usize::try_from(start).ok()?, usize::try_from(size).ok()?)))
+        .and_then(|(s, n)| data.get(s..s.checked_add(n)?))
+        .ok_or("7z header out of range")?
+        .to_vec();
+    if crc32(&header) != read_u32_le(data, 28) {
+        return Err("7z header checksum mismatch".to_string());
+    }
+
+    loop {
+        let mut r = Reader::new(&header);
+        match r.byte()? {
+            K_ENCODED_HEADER => {
+                let info = read_streams_info(&mut r)?;
+                if info.folders.is_empty() {
+                    return Err("Empty 7z encoded header".to_string());
+                }
+                header = decode_folder(data, &info, 0)?;
+            }
+            K_HEADER => {
+                let mut main = StreamsInfo::default();
+                let mut entries = Vec::new();
+                loop {
+                    match r.byte()? {
+                        K_END => break,
+                        K_ARCHIVE_PROPERTIES => loop {
+                            if r.byte()? == 0 {
+                                break;
+                            }
+                            let n = r.count()?;
+                            r.bytes(n)?;
+                        },
+                        K_ADDITIONAL_STREAMS_INFO => drop(read_streams_info(&mut r)?),
+                        K_MAIN_STREAMS_INFO => main = read_streams_info(&mut r)?,
+                        K_FILES_INFO => entries = read_files(&mut r, &main.substreams)?,
+                        id => return Err(format!("Unexpected 7z header property 0x{:02x}", id)),
+                    }
+                }
+                return Ok((entries, main));
+            }
+            id => return Err(format!("Unexpected 7z header type 0x{:02x}", id)),
+        }
+    }
+}
+
+/// A 7z archive whose entries can be listed and extracted individually
+///
+/// Solid archives pack many files into one folder; the last decoded folder is
+/// cached so extracting its files in turn decodes it only once.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct SevenZipArchive {
+    data: Vec<u8>,
+    entries: Vec<SevenZipEntry>,
+    streams: StreamsInfo,
+    cache: RefCell<Option<(usize, Vec<u8>)>>,
+}
+
+impl SevenZipArchive {
+    pub fn parse(data: Vec<u8>) -> Result<SevenZipArchive, String> {
+        let (entries, streams) = read_header(&data)?;
+        Ok(SevenZipArchive { data, entries, streams, cache: RefCell::new(None) })
+    }
+
+    pub fn entries(&self) -> &[SevenZipEntry] {
+        &self.entries
+    }
+
+    /// Decompress the entry at `index` (empty for directories)
+    pub fn read(&self, index: usize) -> Result<Vec<u8>, String> {
+        let entry = self.entries.get(index).ok_or_else(|| format!("No 7z entry {}", index))?;
+        let Some(stream) = entry.stream else {
+            return Ok(Vec::new());
+        };
+
+        let mut cache = self.cache.borrow_mut();
+        if cache.as_ref().is_none_or(|(folder, _)| *folder != stream.folder) {
+            *cache = Some((stream.folder, decode_folder(&self.data, &self.streams, stream.folder)?));
+        }
+        let folder = &cache.as_ref().unwrap().1;
+        let output = folder
+            .get(stream.offset as usize..(stream.offset + entry.size) as usize)
+            .ok_or_else(|| format!("{}: data out of range", entry.name))?
+            .to_vec();
+        if stream.crc.is_some_and(|crc| crc != crc32(&output)) {
+            return Err(format!("{}: checksum mismatch", entry.name));
+        }
+        Ok(output)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SevenZipArchive {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<SevenZipArchive, JsError> {
+        SevenZipArchive::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// Number of entries
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn length(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entry(&self, index: usize) -> Option<SevenZipEntry> {
+        self.entries.get(index).cloned()
+    }
+
+    /// Index of the entry with this exact name
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = findEntry))]
+    pub fn find_entry(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.name == name)
+    }
+
+    /// Decompress the entry at `index`
+    #[cfg(feature = "wasm")]
+    pub fn extract(&self, index: usize) -> Result<Vec<u8>, JsError> {
+        self.read(index).map_err(|e| JsError::new(&e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    /// LZMA-coded archive with an LZMA-encoded header: `docs/`, `docs/a.txt`, empty `empty.txt`
+    fn sample() -> Vec<u8> {
+        hex(concat!(
+            "377abcaf271c00046bfbc2e89d0000000000000021000000000000005c56acb900341949ee8de90612ec86b2e9ba2879c1262606706aa51656f0ffc5b7b0ffffb7aa",
+            "00000000813307ae0fd04b393c9f3f47415cf6671c1605356a7e44bc82c0024c3d2c48189038dd82e580506cd58d8e25879776255a0bede5cc988161dbbcd88afc41",
+            "39fbe5ea26a3bb0ea933c099bb93fd1409ac5966e7b22983ba9efe9936bfdc3284f688e63efdcd73c3eeb189271cc37e2c93b7fffff971a00017062401097900070b",
+            "01000123030101055d000010000c80940a015bec47250000"
+        ))
+    }
+
+    #[test]
+    fn test_read_encoded_header() {
+        let archive = SevenZipArchive::parse(sample()).unwrap();
+        let names: Vec<_> = archive.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["docs", "docs/a.txt", "empty.txt"]);
+        assert!(archive.entries()[0].is_dir);
+        assert!(!archive.entries()[2].is_dir);
+        assert_eq!(archive.entries()[1].mtime, 1_700_000_001);
+        assert_eq!(archive.read(1).unwrap(), b"hello 7z\n".repeat(300));
+        assert_eq!(archive.read(2).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_corrupt_data() {
+        let mut data = sample();
+        data[40] ^= 0x01;
+        assert!(SevenZipArchive::parse(data).and_then(|a| a.read(1)).is_err());
+    }
+
+    #[test]
+    fn test_number_encoding() {
+        let mut r = Reader::new(&[0x7F, 0x81, 0x23, 0xC0, 0x34, 0x12, 0xFF, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(r.number().unwrap(), 0x7F);
+        assert_eq!(r.number().unwrap(), 0x123);
+        assert_eq!(r.number().unwrap(), 0x1234);
+        assert_eq!(r.number().unwrap(), 0x0807_0605_0403_0201);
+    }
+}
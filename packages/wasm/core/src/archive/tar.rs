@@ -0,0 +1,454 @@
+//! TAR archives - ustar and pax reading and writing
+//!
+//! GNU long name records are also understood when reading. Pair with the
+//! gzip or XZ codecs for `.tar.gz` and `.tar.xz`.
+
+use std::collections::HashSet;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const BLOCK: usize = 512;
+/// Largest size an 11-digit octal field can hold
+const OCTAL_LIMIT: u64 = 0o77777777777;
+
+/// Type of a TAR entry
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TarEntryKind {
+    File = 0,
+    Directory = 1,
+    Symlink = 2,
+    Hardlink = 3,
+    Other = 4,
+}
+
+/// One entry in a TAR archive
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TarEntry {
+    pub name: String,
+    pub kind: TarEntryKind,
+    pub size: u64,
+    /// Permission bits
+    pub mode: u32,
+    /// Modification time in seconds since the Unix epoch
+    pub mtime: u64,
+    /// Target of a symlink or hardlink, empty otherwise
+    pub link_name: String,
+    offset: u64,
+}
+
+/// Parse an octal or base-256 numeric field
+fn parse_number(field: &[u8]) -> Result<u64, String> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        // GNU base-256: big-endian with the marker bit cleared
+        return Ok(field[1..].iter().fold((field[0] & 0x7F) as u64, |acc, &b| acc << 8 | b as u64));
+    }
+    let digits = field.iter().skip_while(|&&b| b == b' ').take_while(|&&b| (b'0'..=b'7').contains(&b));
+    let mut value = 0u64;
+    for &b in digits {
+        value = value.checked_mul(8).ok_or("TAR numeric field overflow")? + (b - b'0') as u64;
+    }
+    Ok(value)
+}
+
+/// NUL-terminated string field
+fn parse_string(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn verify_checksum(header: &[u8]) -> bool {
+    let stored = match parse_number(&header[148..156]) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    let unsigned: u64 = header.iter().enumerate().map(|(i, &b)| if (148..156).contains(&i) { 32 } else { b as u64 }).sum();
+    // Some old writers summed signed bytes
+    let signed: i64 = header.iter().enumerate().map(|(i, &b)| if (148..156).contains(&i) { 32 } else { b as i8 as i64 }).sum();
+    stored == unsigned || stored as i64 == signed
+}
+
+/// Parse pax extended header records (`"<len> <key>=<value>\n"`)
+fn parse_pax(mut data: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let mut records = Vec::new();
+    while !data.is_empty() && data[0] != 0 {
+        let space = data.iter().position(|&b| b == b' ').ok_or("Invalid pax record")?;
+        let len: usize = std::str::from_utf8(&data[..space]).ok().and_then(|s| s.parse().ok()).ok_or("Invalid pax record length")?;
+        if len <= space + 1 || len > data.len() || data[len - 1] != b'\n' {
+            return Err("Invalid pax record length".to_string());
+        }
+        let record = &data[space + 1..len - 1];
+        let eq = record.iter().position(|&b| b == b'=').ok_or("Invalid pax record")?;
+        records.push((String::from_utf8_lossy(&record[..eq]).into_owned(), String::from_utf8_lossy(&record[eq + 1..]).into_owned()));
+        data = &data[len..];
+    }
+    Ok(records)
+}
+
+/// Overrides collected from pax and GNU records for the next entry
+#[derive(Default, Clone)]
+struct Overrides {
+    path: Option<String>,
+    link_path: Option<String>,
+    size: Option<u64>,
+    mtime: Option<u64>,
+}
+
+impl Overrides {
+    fn apply_pax(&mut self, records: Vec<(String, String)>) {
+        for (key, value) in records {
+            match key.as_str() {
+                "path" => self.path = Some(value),
+                "linkpath" => self.link_path = Some(value),
+                "size" => self.size = value.parse().ok(),
+                // Fractional seconds are dropped
+                "mtime" => self.mtime = value.split('.').next().and_then(|s| s.parse().ok()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// List all entries of a TAR archive
+pub fn read_tar_entries(data: &[u8]) -> Result<Vec<TarEntry>, String> {
+    let mut entries = Vec::new();
+    let mut global = Overrides::default();
+    let mut local = Overrides::default();
+    let mut pos = 0;
+
+    while pos + BLOCK <= data.len() {
+        let header = &data[pos..pos + BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if !verify_checksum(header) {
+            return Err(format!("Invalid TAR header checksum at offset {}", pos));
+        }
+
+        let typeflag = header[156];
+        let header_size = parse_number(&header[124..136])?;
+        let size = match typeflag {
+            b'x' | b'g' | b'L' | b'K' => header_size,
+            _ => local.size.or(global.size).unwrap_or(header_size),
+        };
+        let start = pos + BLOCK;
+        let body = usize::try_from(size)
+            .ok()
+            .and_then(|n| data.get(start..start.checked_add(n)?))
+            .ok_or_else(|| format!("Truncated TAR entry at offset {}", pos))?;
+        pos = start + body.len().div_ceil(BLOCK) * BLOCK;
+
+        match typeflag {
+            b'x' => local.apply_pax(parse_pax(body)?),
+            b'g' => global.apply_pax(parse_pax(body)?),
+            b'L' => local.path = Some(parse_string(body)),
+            b'K' => local.link_path = Some(parse_string(body)),
+            _ => {
+                let mut name = parse_string(&header[0..100]);
+                if &header[257..262] == b"ustar" {
+                    let prefix = parse_string(&header[345..500]);
+                    if !prefix.is_empty() {
+                        name = format!("{}/{}", prefix, name);
+                    }
+                }
+                let kind = match typeflag {
+                    b'0' | 0 | b'7' if name.ends_with('/') => TarEntryKind::Directory,
+                    b'0' | 0 | b'7' => TarEntryKind::File,
+                    b'5' => TarEntryKind::Directory,
+                    b'2' => TarEntryKind::Symlink,
+                    b'1' => TarEntryKind::Hardlink,
+                    _ => TarEntryKind::Other,
+                };
+                let overrides = std::mem::take(&mut local);
+                entries.push(TarEntry {
+                    name: overrides.path.or_else(|| global.path.clone()).unwrap_or(name),
+                    kind,
+                    size,
+                    mode: (parse_number(&header[100..108])? & 0o7777) as u32,
+                    mtime: overrides.mtime.or(global.mtime).map_or_else(|| parse_number(&header[136..148]), Ok)?,
+                    link_name: overrides.link_path.or_else(|| global.link_path.clone()).unwrap_or_else(|| parse_string(&header[157..257])),
+                    offset: start as u64,
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// A TAR archive whose entries can be listed and extracted individually
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct TarArchive {
+    data: Vec<u8>,
+    entries: Vec<TarEntry>,
+}
+
+impl TarArchive {
+    pub fn parse(data: Vec<u8>) -> Result<TarArchive, String> {
+        let entries = read_tar_entries(&data)?;
+        Ok(TarArchive { data, entries })
+    }
+
+    pub fn entries(&self) -> &[TarEntry] {
+        &self.entries
+    }
+
+    /// Contents of the entry at `index` (empty for non-files); entries over
+    /// the output byte limit set by `set_limits` are refused
+    pub fn read(&self, index: usize) -> Result<&[u8], String> {
+        let entry = self.entries.get(index).ok_or_else(|| format!("No TAR entry {}", index))?;
+        if entry.kind != TarEntryKind::File {
+            return Ok(&[]);
+        }
+        let limit = crate::limits::output_limit();
+        if entry.size > limit as u64 {
+            return Err(format!("{}: size exceeds limit of {} bytes", entry.name, limit));
+        }
+        let start = entry.offset as usize;
+        Ok(&self.data[start..start + entry.size as usize])
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TarArchive {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<TarArchive, JsError> {
+        TarArchive::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// Number of entries
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn length(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entry(&self, index: usize) -> Option<TarEntry> {
+        self.entries.get(index).cloned()
+    }
+
+    /// Index of the entry with this exact name
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = findEntry))]
+    pub fn find_entry(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.name == name)
+    }
+
+    /// Contents of the entry at `index`
+    #[cfg(feature = "wasm")]
+    pub fn extract(&self, index: usize) -> Result<Vec<u8>, JsError> {
+        self.read(index).map(|d| d.to_vec()).map_err(|e| JsError::new(&e))
+    }
+}
+
+/// Builds a ustar archive one entry at a time
+///
+/// Names that do not fit ustar's name/prefix fields, non-ASCII names and
+/// sizes beyond 8 GiB are carried in pax extended headers. Entries get a
+/// fixed zero timestamp so output is reproducible.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct TarWriter {
+    pending: Vec<u8>,
+    names: HashSet<String>,
+}
+
+impl Default for TarWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split a name into ustar (prefix, name) fields when possible
+fn split_ustar_name(name: &str) -> Option<(&str, &str)> {
+    if !name.is_ascii() {
+        return None;
+    }
+    if name.len() <= 100 {
+        return Some(("", name));
+    }
+    // The prefix must end at a '/' and the rest fit in 100 bytes
+    name.char_indices()
+        .filter(|&(i, c)| c == '/' && i <= 155 && name.len() - i - 1 <= 100 && i + 1 < name.len())
+        .map(|(i, _)| (&name[..i], &name[i + 1..]))
+        .next()
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let text = format!("{:0width$o}", value, width = digits);
+    field[..digits].copy_from_slice(text.as_bytes());
+    field[digits] = 0;
+}
+
+fn pax_record(key: &str, value: &str) -> String {
+    // The length prefix counts its own digits
+    let base = key.len() + value.len() + 3;
+    let mut len = base + base.to_string().len();
+    if len.to_string().len() != base.to_string().len() {
+        len = base + len.to_string().len();
+    }
+    format!("{} {}={}\n", len, key, value)
+}
+
+impl TarWriter {
+    /// Add a regular file with mode 0644
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<(), String> {
+        self.check_name(name)?;
+        self.write_entry(name, b'0', 0o644, data);
+        Ok(())
+    }
+
+    /// Add a directory with mode 0755; a trailing `/` is appended if missing
+    pub fn add_directory(&mut self, name: &str) -> Result<(), String> {
+        let name = if name.ends_with('/') { name.to_string() } else { format!("{}/", name) };
+        self.check_name(&name)?;
+        self.write_entry(&name, b'5', 0o755, &[]);
+        Ok(())
+    }
+
+    /// Drain the bytes written so far
+    pub fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Write the end-of-archive marker and return the remaining bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        self.pending.extend_from_slice(&[0; 2 * BLOCK]);
+        self.pending
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), String> {
+        if name.is_empty() || name.contains('\0') {
+            return Err(format!("Invalid TAR entry name: {:?}", name));
+        }
+        if self.names.contains(name) {
+            return Err(format!("Duplicate TAR entry: {}", name));
+        }
+        Ok(())
+    }
+
+    fn write_header(&mut self, name: &str, prefix: &str, typeflag: u8, mode: u32, size: u64) {
+        let mut header = [0u8; BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        write_octal(&mut header[100..108], mode as u64);
+        write_octal(&mut header[108..116], 0);
+        write_octal(&mut header[116..124], 0);
+        write_octal(&mut header[124..136], size.min(OCTAL_LIMIT));
+        write_octal(&mut header[136..148], 0);
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        write_octal(&mut header[148..155], sum as u64);
+        header[155] = b' ';
+        self.pending.extend_from_slice(&header);
+    }
+
+    fn write_body(&mut self, body: &[u8]) {
+        self.pending.extend_from_slice(body);
+        let padding = body.len().next_multiple_of(BLOCK) - body.len();
+        self.pending.resize(self.pending.len() + padding, 0);
+    }
+
+    fn write_entry(&mut self, name: &str, typeflag: u8, mode: u32, data: &[u8]) {
+        let size = data.len() as u64;
+        let split = split_ustar_name(name);
+
+        let mut pax = String::new();
+        if split.is_none() {
+            pax.push_str(&pax_record("path", name));
+        }
+        if size > OCTAL_LIMIT {
+            pax.push_str(&pax_record("size", &size.to_string()));
+        }
+        if !pax.is_empty() {
+            self.write_header("PaxHeader", "", b'x', 0o644, pax.len() as u64);
+            self.write_body(pax.as_bytes());
+        }
+
+        // Without a ustar split, a truncated ASCII name stands in for readers lacking pax
+        let fallback: String = name.chars().filter(char::is_ascii).take(100).collect();
+        let (prefix, short) = split.unwrap_or(("", &fallback));
+        self.write_header(short, prefix, typeflag, mode, size);
+        self.write_body(data);
+        self.names.insert(name.to_string());
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TarWriter {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> TarWriter {
+        TarWriter { pending: Vec::new(), names: HashSet::new() }
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = addFile)]
+    pub fn add_file_js(&mut self, name: &str, data: &[u8]) -> Result<(), JsError> {
+        self.add_file(name, data).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = addDirectory)]
+    pub fn add_directory_js(&mut self, name: &str) -> Result<(), JsError> {
+        self.add_directory(name).map_err(|e| JsError::new(&e))
+    }
+
+    /// Drain the bytes written so far
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = take)]
+    pub fn take_js(&mut self) -> Vec<u8> {
+        self.take()
+    }
+
+    /// Write the end-of-archive marker and return the remaining bytes
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = finish)]
+    pub fn finish_js(self) -> Vec<u8> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_with_long_names() {
+        let long = format!("{}/file.txt", "nested".repeat(30));
+        let mut writer = TarWriter::new();
+        writer.add_directory("images").unwrap();
+        writer.add_file("images/a.png", b"png bytes").unwrap();
+        writer.add_file(&long, &[7; 600]).unwrap();
+        writer.add_file("caf\u{e9}.txt", b"x").unwrap();
+        assert!(writer.add_file("images/a.png", b"").is_err());
+        let tar = writer.finish();
+        assert!(tar.len().is_multiple_of(BLOCK));
+
+        let archive = TarArchive::parse(tar).unwrap();
+        let names: Vec<_> = archive.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["images/", "images/a.png", long.as_str(), "caf\u{e9}.txt"]);
+        assert_eq!(archive.entries()[0].kind, TarEntryKind::Directory);
+        assert_eq!(archive.entries()[1].mode, 0o644);
+        assert_eq!(archive.read(1).unwrap(), b"png bytes");
+        assert_eq!(archive.read(2).unwrap(), [7; 600]);
+    }
+
+    #[test]
+    fn test_pax_record_length() {
+        assert_eq!(pax_record("path", "a"), "9 path=a\n");
+        let record = pax_record("path", &"x".repeat(93));
+        assert_eq!(record.len().to_string(), record.split(' ').next().unwrap());
+    }
+
+    #[test]
+    fn test_bad_checksum() {
+        let mut writer = TarWriter::new();
+        writer.add_file("a", b"data").unwrap();
+        let mut tar = writer.finish();
+        tar[0] = b'b';
+        assert!(read_tar_entries(&tar).is_err());
+    }
+}
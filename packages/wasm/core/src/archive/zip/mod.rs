@@ -0,0 +1,206 @@
+//! ZIP archives - reading and writing with ZIP64 support
+//!
+//! Entries are listed from the central directory and decompressed one at a
+//! time on request, so only the entry being extracted is held uncompressed.
+//! Stored, Deflate, bzip2, LZMA, Zstandard and XZ entries are supported;
+//! the writer produces stored and Deflate entries.
+
+mod reader;
+mod writer;
+
+pub use reader::{read_entries, read_entry};
+pub use writer::ZipWriter;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub(crate) const LOCAL_SIGNATURE: u32 = 0x0403_4B50;
+pub(crate) const CENTRAL_SIGNATURE: u32 = 0x0201_4B50;
+pub(crate) const EOCD_SIGNATURE: u32 = 0x0605_4B50;
+pub(crate) const ZIP64_EOCD_SIGNATURE: u32 = 0x0606_4B50;
+pub(crate) const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4B50;
+
+/// One file or directory in a ZIP archive
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    pub compressed_size: u64,
+    pub crc32: u32,
+    /// Compression method (0 = stored, 8 = deflate)
+    pub method: u16,
+    pub is_dir: bool,
+    pub encrypted: bool,
+    /// Modification time as `YYYY-MM-DDTHH:MM:SS` (local time, as stored)
+    pub modified: String,
+    offset: u64,
+}
+
+/// A ZIP archive whose entries can be listed and extracted individually
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ZipArchive {
+    data: Vec<u8>,
+    entries: Vec<ZipEntry>,
+}
+
+impl ZipArchive {
+    pub fn parse(data: Vec<u8>) -> Result<ZipArchive, String> {
+        let entries = read_entries(&data)?;
+        Ok(ZipArchive { data, entries })
+    }
+
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Decompress the entry at `index`
+    pub fn read(&self, index: usize) -> Result<Vec<u8>, String> {
+        let entry = self.entries.get(index).ok_or_else(|| format!("No ZIP entry {}", index))?;
+        read_entry(&self.data, entry)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ZipArchive {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<ZipArchive, JsError> {
+        ZipArchive::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// Number of entries
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn length(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entry(&self, index: usize) -> Option<ZipEntry> {
+        self.entries.get(index).cloned()
+    }
+
+    /// Index of the entry with this exact name
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = findEntry))]
+    pub fn find_entry(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.name == name)
+    }
+
+    /// Decompress the entry at `index`
+    #[cfg(feature = "wasm")]
+    pub fn extract(&self, index: usize) -> Result<Vec<u8>, JsError> {
+        self.read(index).map_err(|e| JsError::new(&e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::crc32;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    /// Single stored entry, optionally with sizes and offset moved to a ZIP64 extra field
+    fn stored_zip(name: &[u8], content: &[u8], zip64: bool) -> Vec<u8> {
+        let mut zip = LOCAL_SIGNATURE.to_le_bytes().to_vec();
+        zip.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        zip.extend_from_slice(&crc32(content).to_le_bytes());
+        zip.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&[0, 0]);
+        zip.extend_from_slice(name);
+        zip.extend_from_slice(content);
+
+        let directory = zip.len();
+        let (small, extra) = if zip64 {
+            let mut extra = vec![1, 0, 24, 0];
+            extra.extend_from_slice(&(content.len() as u64).to_le_bytes());
+            extra.extend_from_slice(&(content.len() as u64).to_le_bytes());
+            extra.extend_from_slice(&0u64.to_le_bytes());
+            (0xFFFF_FFFFu32, extra)
+        } else {
+            (content.len() as u32, Vec::new())
+        };
+        zip.extend_from_slice(&CENTRAL_SIGNATURE.to_le_bytes());
+        zip.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0]);
+        zip.extend_from_slice(&0x7A21u16.to_le_bytes()); // 15:17:02
+        zip.extend_from_slice(&0x5A51u16.to_le_bytes()); // 2025-02-17
+        zip.extend_from_slice(&crc32(content).to_le_bytes());
+        zip.extend_from_slice(&small.to_le_bytes());
+        zip.extend_from_slice(&small.to_le_bytes());
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&[0; 10]);
+        zip.extend_from_slice(&(if zip64 { 0xFFFF_FFFFu32 } else { 0 }).to_le_bytes());
+        zip.extend_from_slice(name);
+        zip.extend_from_slice(&extra);
+        let size = zip.len() - directory;
+
+        zip.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        zip.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        zip.extend_from_slice(&(size as u32).to_le_bytes());
+        zip.extend_from_slice(&(directory as u32).to_le_bytes());
+        zip.extend_from_slice(&[0, 0]);
+        zip
+    }
+
+    #[test]
+    fn test_read_stored_cp437() {
+        let archive = ZipArchive::parse(stored_zip(b"caf\x82.txt", b"coffee", false)).unwrap();
+        let entry = &archive.entries()[0];
+        assert_eq!(entry.name, "café.txt");
+        assert_eq!(entry.modified, "2025-02-17T15:17:02");
+        assert!(!entry.is_dir);
+        assert_eq!(archive.read(0).unwrap(), b"coffee");
+    }
+
+    #[test]
+    fn test_read_zip64_extra() {
+        let archive = ZipArchive::parse(stored_zip(b"big.bin", b"0123456789", true)).unwrap();
+        assert_eq!(archive.entries()[0].size, 10);
+        assert_eq!(archive.read(0).unwrap(), b"0123456789");
+    }
+
+    #[test]
+    fn test_read_deflated() {
+        // Python zipfile, deflated, ZIP64 local header
+        let zip = hex("504b03042d0000000800000021008088f9e5ffffffffffffffff0600140068692e7478740100100011000000000000000a00000000000000cb48cdc9c957c8409000504b01022d032d0000000800000021008088f9e50a0000001100000006000000000000000000000080010000000068692e747874504b0506000000000100010034000000420000000000");
+        let archive = ZipArchive::parse(zip).unwrap();
+        assert_eq!(archive.find_entry("hi.txt"), Some(0));
+        assert_eq!(archive.read(0).unwrap(), b"hello hello hello");
+    }
+
+    #[test]
+    fn test_writer_roundtrip() {
+        let text = b"line of text\n".repeat(200);
+        let mut writer = ZipWriter::new();
+        writer.add_file("notes.txt", &text, 6).unwrap();
+        let mut zip = writer.take();
+        writer.add_directory("images").unwrap();
+        writer.add_file("images/\u{e9}t\u{e9}.bin", &[1, 2, 3], 6).unwrap();
+        assert!(writer.add_file("notes.txt", b"", 0).is_err());
+        zip.extend(writer.finish());
+
+        let archive = ZipArchive::parse(zip).unwrap();
+        let entries = archive.entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].method, 8);
+        assert!(entries[0].compressed_size < text.len() as u64);
+        assert!(entries[1].is_dir);
+        // Incompressible data falls back to stored
+        assert_eq!(entries[2].name, "images/\u{e9}t\u{e9}.bin");
+        assert_eq!(entries[2].method, 0);
+        assert_eq!(archive.read(0).unwrap(), text);
+        assert_eq!(archive.read(2).unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut zip = stored_zip(b"a", b"data", false);
+        zip[31] ^= 1;
+        assert!(ZipArchive::parse(zip).unwrap().read(0).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! ZIP central directory parsing and entry extraction
+
+use super::{ZipEntry, CENTRAL_SIGNATURE, EOCD_SIGNATURE, LOCAL_SIGNATURE, ZIP64_EOCD_SIGNATURE, ZIP64_LOCATOR_SIGNATURE};
+use crate::checksum::crc32;
+use crate::compress::{self, limit_error};
+use crate::limits::output_limit;
+use crate::text::decode_utf8;
+use crate::utils::{read_u16_le, read_u32_le};
+
+const FLAG_ENCRYPTED: u16 = 1 << 0;
+const FLAG_UTF8: u16 = 1 << 11;
+const EXTRA_ZIP64: u16 = 0x0001;
+const EXTRA_UNICODE_PATH: u16 = 0x7075;
+
+/// Code page 437, the legacy encoding of non-UTF-8 names
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{A0}";
+
+fn read_u64_le(data: &[u8], offset: usize) -> u64 {
+    read_u32_le(data, offset) as u64 | (read_u32_le(data, offset + 4) as u64) << 32
+}
+
+/// Decode a name as UTF-8 when flagged, falling back to CP437 for names that
+/// are not valid UTF-8 despite the flag
+fn decode_name(bytes: &[u8], utf8: bool) -> String {
+    if utf8 || bytes.is_ascii() {
+        if let Ok(name) = decode_utf8(bytes, true) {
+            return name;
+        }
+    }
+    bytes
+        .iter()
+        .map(|&b| if b < 0x80 { b as char } else { CP437_HIGH.chars().nth(b as usize - 0x80).unwrap_or('?') })
+        .collect()
+}
+
+/// Iterate (id, data) extra fields
+fn extra_fields(extra: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        if pos + 4 > extra.len() {
+            return None;
+        }
+        let id = read_u16_le(extra, pos);
+        let len = read_u16_le(extra, pos + 2) as usize;
+        let field = extra.get(pos + 4..pos + 4 + len)?;
+        pos += 4 + len;
+        Some((id, field))
+    })
+}
+
+/// Render an MS-DOS date and time as ISO 8601
+fn dos_datetime(date: u16, time: u16) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        1980 + (date >> 9),
+        (date >> 5) & 15,
+        date & 31,
+        time >> 11,
+        (time >> 5) & 63,
+        (time & 31) * 2
+    )
+}
+
+/// Locate the central directory: (offset, size, entry count)
+fn find_central_directory(data: &[u8]) -> Result<(usize, usize, usize), String> {
+    if data.len() < 22 {
+        return Err("ZIP data too small".to_string());
+    }
+    // The end record sits before a comment of up to 64 KiB
+    let lowest = data.len().saturating_sub(22 + 65535);
+    let eocd = (lowest..=data.len() - 22)
+        .rev()
+        .find(|&i| read_u32_le(data, i) == EOCD_SIGNATURE)
+        .ok_or("ZIP end of central directory not found")?;
+
+    let mut count = read_u16_le(data, eocd + 10) as u64;
+    let mut size = read_u32_le(data, eocd + 12) as u64;
+    let mut offset = read_u32_le(data, eocd + 16) as u64;
+
+    if eocd >= 20 && read_u32_le(data, eocd - 20) == ZIP64_LOCATOR_SIGNATURE {
+        let record = read_u64_le(data, eocd - 20 + 8) as usize;
+        if record.checked_add(56).is_none_or(|end| end > data.len()) || read_u32_le(data, record) != ZIP64_EOCD_SIGNATURE {
+            return Err("Invalid ZIP64 end of central directory".to_string());
+        }
+        count = read_u64_le(data, record + 32);
+        size = read_u64_le(data, record + 40);
+        offset = read_u64_le(data, record + 48);
+    }
+
+    if offset.checked_add(size).is_none_or(|end| end > data.len() as u64) {
+        return Err("ZIP central directory out of range".to_string());
+    }
+    Ok((offset as usize, size as usize, count as usize))
+}
+
+/// Parse all central directory entries
+pub fn read_entries(data: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let (offset, size, count) = find_central_directory(data)?;
+    let directory = &data[offset..offset + size];
+    let mut entries = Vec::with_capacity(count.min(size / 46));
+    let mut pos = 0;
+
+    while entries.len() < count {
+        if pos + 46 > directory.len() || read_u32_le(directory, pos) != CENTRAL_SIGNATURE {
+            return Err("Invalid ZIP central directory entry".to_string());
+        }
+        let h = &directory[pos..];
+        let flags = read_u16_le(h, 8);
+        let name_len = read_u16_le(h, 28) as usize;
+        let extra_len = read_u16_le(h, 30) as usize;
+        let comment_len = read_u16_le(h, 32) as usize;
+        let name_bytes = h.get(46..46 + name_len).ok_or("Truncated ZIP entry name")?;
+        let extra = h.get(46 + name_len..46 + name_len + extra_len).ok_or("Truncated ZIP extra field")?;
+
+        let mut name = decode_name(name_bytes, flags & FLAG_UTF8 != 0);
+        let mut compressed_size = read_u32_le(h, 20) as u64;
+        let mut size = read_u32_le(h, 24) as u64;
+        let mut local_offset = read_u32_le(h, 42) as u64;
+
+        for (id, field) in extra_fields(extra) {
+            match id {
+                EXTRA_ZIP64 => {
+                    // Only the fields saturated in the fixed header are present, in this order
+                    let mut values = field.chunks_exact(8).map(|c| read_u64_le(c, 0));
+                    if size == 0xFFFF_FFFF {
+                        size = values.next().ok_or("Truncated ZIP64 extra field")?;
+                    }
+                    if compressed_size == 0xFFFF_FFFF {
+                        compressed_size = values.next().ok_or("Truncated ZIP64 extra field")?;
+                    }
+                    if local_offset == 0xFFFF_FFFF {
+                        local_offset = values.next().ok_or("Truncated ZIP64 extra field")?;
+                    }
+                }
+                // Only valid while it matches the name it was made for
+                EXTRA_UNICODE_PATH if field.len() > 5 && field[0] == 1 && read_u32_le(field, 1) == crc32(name_bytes) => {
+                    if let Ok(unicode) = decode_utf8(&field[5..], true) {
+                        name = unicode;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        entries.push(ZipEntry {
+            is_dir: name.ends_with('/'),
+            name,
+            size,
+            compressed_size,
+            crc32: read_u32_le(h, 16),
+            method: read_u16_le(h, 10),
+            encrypted: flags & FLAG_ENCRYPTED != 0,
+            modified: dos_datetime(read_u16_le(h, 14), read_u16_le(h, 12)),
+            offset: local_offset,
+        });
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Decompress one entry and verify its CRC-32; entries over the output byte
+/// limit set by `set_limits` are refused before decompressing
+pub fn read_entry(data: &[u8], entry: &ZipEntry) -> Result<Vec<u8>, String> {
+    if entry.encrypted {
+        return Err(format!("{}: encrypted entries are not supported", entry.name));
+    }
+    let offset = entry.offset as usize;
+    if offset.checked_add(30).is_none_or(|end| end > data.len()) || read_u32_le(data, offset) != LOCAL_SIGNATURE {
+        return Err(format!("{}: invalid local header", entry.name));
+    }
+    let start = offset + 30 + read_u16_le(data, offset + 26) as usize + read_u16_le(data, offset + 28) as usize;
+    let raw = usize::try_from(entry.compressed_size)
+        .ok()
+        .and_then(|n| data.get(start..start.checked_add(n)?))
+        .ok_or_else(|| format!("{}: compressed data out of range", entry.name))?;
+    let size = usize::try_from(entry.size).map_err(|_| format!("{}: entry too large", entry.name))?;
+    let limit = output_limit();
+    if size > limit {
+        return Err(format!("{}: {}", entry.name, limit_error(limit)));
+    }
+
+    let output = match entry.method {
+        0 => raw.to_vec(),
+        8 => compress::deflate::inflate_with_limit(raw, size)?.data,
+        12 => compress::bzip2_decompress(raw)?,
+        14 => {
+            // Version (2 bytes), properties size (2), then LZMA properties and stream
+            let props_len = raw.get(2..4).map(|b| read_u16_le(b, 0) as usize).ok_or("Truncated LZMA header")?;
+            let props = raw.get(4..4 + props_len).filter(|p| p.len() >= 5).ok_or("Invalid LZMA properties")?;
+            compress::lzma_decode_raw(props, &raw[4 + props_len..], Some(entry.size))?
+        }
+        93 => compress::zstd_decompress(raw)?,
+        95 => compress::xz_decompress(raw)?,
+        m => return Err(format!("{}: unsupported compression method {}", entry.name, m)),
+    };
+
+    if output.len() != size || crc32(&output) != entry.crc32 {
+        return Err(format!("{}: checksum mismatch", entry.name));
+    }
+    Ok(output)
+}
@@ -0,0 +1,221 @@
+//! Incremental ZIP writer
+
+use super::{CENTRAL_SIGNATURE, EOCD_SIGNATURE, LOCAL_SIGNATURE, ZIP64_EOCD_SIGNATURE, ZIP64_LOCATOR_SIGNATURE};
+use crate::checksum::crc32;
+use crate::compress::deflate;
+use std::collections::HashSet;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const FLAG_UTF8: u16 = 1 << 11;
+/// Fixed 1980-01-01 00:00:00 timestamp so output is reproducible
+const DOS_DATE: u16 = (1 << 5) | 1;
+const LIMIT_32: u64 = 0xFFFF_FFFF;
+
+/// Builds a ZIP archive one entry at a time
+///
+/// Bytes produced so far can be drained with `take` and handed to a stream,
+/// so only the central directory stays in memory until `finish`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ZipWriter {
+    pending: Vec<u8>,
+    written: u64,
+    central: Vec<u8>,
+    count: u64,
+    names: HashSet<String>,
+}
+
+impl Default for ZipWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZipWriter {
+    /// Add a file; `level` 0 stores it, 1-9 deflate it
+    ///
+    /// Deflated entries that would not shrink are stored instead.
+    pub fn add_file(&mut self, name: &str, data: &[u8], level: u8) -> Result<(), String> {
+        self.check_name(name)?;
+        let compressed = if level > 0 { Some(deflate(data, level.min(9))) } else { None };
+        match compressed {
+            Some(packed) if packed.len() < data.len() => self.write_entry(name, 8, crc32(data), data.len() as u64, &packed, false),
+            _ => self.write_entry(name, 0, crc32(data), data.len() as u64, data, false),
+        }
+        Ok(())
+    }
+
+    /// Add an empty directory entry; a trailing `/` is appended if missing
+    pub fn add_directory(&mut self, name: &str) -> Result<(), String> {
+        let name = if name.ends_with('/') { name.to_string() } else { format!("{}/", name) };
+        self.check_name(&name)?;
+        self.write_entry(&name, 0, 0, 0, &[], true);
+        Ok(())
+    }
+
+    /// Drain the bytes written so far
+    pub fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Write the central directory and return the remaining bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.written;
+        let directory_size = self.central.len() as u64;
+        let central = std::mem::take(&mut self.central);
+        self.push(&central);
+
+        let zip64 = self.count >= 0xFFFF || directory_offset >= LIMIT_32 || directory_size >= LIMIT_32;
+        if zip64 {
+            let record_offset = self.written;
+            let mut record = Vec::with_capacity(76);
+            record.extend_from_slice(&ZIP64_EOCD_SIGNATURE.to_le_bytes());
+            record.extend_from_slice(&44u64.to_le_bytes());
+            record.extend_from_slice(&[45, 0, 45, 0]);
+            record.extend_from_slice(&[0; 8]);
+            record.extend_from_slice(&self.count.to_le_bytes());
+            record.extend_from_slice(&self.count.to_le_bytes());
+            record.extend_from_slice(&directory_size.to_le_bytes());
+            record.extend_from_slice(&directory_offset.to_le_bytes());
+            record.extend_from_slice(&ZIP64_LOCATOR_SIGNATURE.to_le_bytes());
+            record.extend_from_slice(&0u32.to_le_bytes());
+            record.extend_from_slice(&record_offset.to_le_bytes());
+            record.extend_from_slice(&1u32.to_le_bytes());
+            self.push(&record);
+        }
+
+        let count = self.count.min(0xFFFF) as u16;
+        let mut eocd = Vec::with_capacity(22);
+        eocd.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        eocd.extend_from_slice(&[0; 4]);
+        eocd.extend_from_slice(&count.to_le_bytes());
+        eocd.extend_from_slice(&count.to_le_bytes());
+        eocd.extend_from_slice(&(directory_size.min(LIMIT_32) as u32).to_le_bytes());
+        eocd.extend_from_slice(&(directory_offset.min(LIMIT_32) as u32).to_le_bytes());
+        eocd.extend_from_slice(&[0, 0]);
+        self.push(&eocd);
+
+        self.pending
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), String> {
+        if name.is_empty() || name.starts_with('/') || name.contains('\\') {
+            return Err(format!("Invalid ZIP entry name: {:?}", name));
+        }
+        if name.len() > 0xFFFF {
+            return Err("ZIP entry name too long".to_string());
+        }
+        if self.names.contains(name) {
+            return Err(format!("Duplicate ZIP entry: {}", name));
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        self.written += bytes.len() as u64;
+    }
+
+    fn write_entry(&mut self, name: &str, method: u16, crc: u32, size: u64, payload: &[u8], is_dir: bool) {
+        let offset = self.written;
+        let compressed_size = payload.len() as u64;
+        let flags = if name.is_ascii() { 0 } else { FLAG_UTF8 };
+        let large = size >= LIMIT_32 || compressed_size >= LIMIT_32;
+        let version: u16 = if large || offset >= LIMIT_32 { 45 } else { 20 };
+
+        // Sizes are known up front, so the local header carries them directly
+        let mut local = Vec::with_capacity(30 + name.len() + 20);
+        local.extend_from_slice(&LOCAL_SIGNATURE.to_le_bytes());
+        local.extend_from_slice(&version.to_le_bytes());
+        local.extend_from_slice(&flags.to_le_bytes());
+        local.extend_from_slice(&method.to_le_bytes());
+        local.extend_from_slice(&0u16.to_le_bytes());
+        local.extend_from_slice(&DOS_DATE.to_le_bytes());
+        local.extend_from_slice(&crc.to_le_bytes());
+        if large {
+            local.extend_from_slice(&[0xFF; 8]);
+        } else {
+            local.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+            local.extend_from_slice(&(size as u32).to_le_bytes());
+        }
+        local.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        local.extend_from_slice(&(if large { 20u16 } else { 0 }).to_le_bytes());
+        local.extend_from_slice(name.as_bytes());
+        if large {
+            local.extend_from_slice(&[1, 0, 16, 0]);
+            local.extend_from_slice(&size.to_le_bytes());
+            local.extend_from_slice(&compressed_size.to_le_bytes());
+        }
+        self.push(&local);
+        self.push(payload);
+
+        // ZIP64 extra holds only the saturated fields, in this order
+        let mut extra = Vec::new();
+        for value in [size, compressed_size, offset] {
+            if value >= LIMIT_32 {
+                extra.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        if !extra.is_empty() {
+            let len = extra.len() as u16;
+            extra.splice(0..0, [1, 0, len as u8, (len >> 8) as u8]);
+        }
+
+        let c = &mut self.central;
+        c.extend_from_slice(&CENTRAL_SIGNATURE.to_le_bytes());
+        c.extend_from_slice(&version.to_le_bytes());
+        c.extend_from_slice(&version.to_le_bytes());
+        c.extend_from_slice(&flags.to_le_bytes());
+        c.extend_from_slice(&method.to_le_bytes());
+        c.extend_from_slice(&0u16.to_le_bytes());
+        c.extend_from_slice(&DOS_DATE.to_le_bytes());
+        c.extend_from_slice(&crc.to_le_bytes());
+        c.extend_from_slice(&(compressed_size.min(LIMIT_32) as u32).to_le_bytes());
+        c.extend_from_slice(&(size.min(LIMIT_32) as u32).to_le_bytes());
+        c.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        c.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        c.extend_from_slice(&[0; 6]);
+        c.extend_from_slice(&(if is_dir { 0x10u32 } else { 0 }).to_le_bytes());
+        c.extend_from_slice(&(offset.min(LIMIT_32) as u32).to_le_bytes());
+        c.extend_from_slice(name.as_bytes());
+        c.extend_from_slice(&extra);
+
+        self.count += 1;
+        self.names.insert(name.to_string());
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ZipWriter {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> ZipWriter {
+        ZipWriter { pending: Vec::new(), written: 0, central: Vec::new(), count: 0, names: HashSet::new() }
+    }
+
+    /// Add a file; `level` 0 stores it, 1-9 deflate it
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = addFile)]
+    pub fn add_file_js(&mut self, name: &str, data: &[u8], level: u8) -> Result<(), JsError> {
+        self.add_file(name, data, level).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = addDirectory)]
+    pub fn add_directory_js(&mut self, name: &str) -> Result<(), JsError> {
+        self.add_directory(name).map_err(|e| JsError::new(&e))
+    }
+
+    /// Drain the bytes written so far
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = take)]
+    pub fn take_js(&mut self) -> Vec<u8> {
+        self.take()
+    }
+
+    /// Write the central directory and return the remaining bytes
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = finish)]
+    pub fn finish_js(self) -> Vec<u8> {
+        self.finish()
+    }
+}
@@ -0,0 +1,135 @@
+//! Windowing, inverse MDCT and overlap-add
+
+use super::ics::{IcsInfo, LONG_START, LONG_STOP};
+use crate::audio::mdct::Imdct;
+use std::f64::consts::PI;
+
+/// Rising half of a sine window of `2n` points
+fn sine_window(n: usize) -> Vec<f32> {
+    (0..n).map(|i| (PI / (2 * n) as f64 * (i as f64 + 0.5)).sin() as f32).collect()
+}
+
+/// Zeroth-order modified Bessel function of the first kind
+fn bessel_i0(x: f64) -> f64 {
+    let (mut sum, mut term) = (1.0, 1.0);
+    for k in 1..50 {
+        term *= (x / (2.0 * k as f64)).powi(2);
+        sum += term;
+    }
+    sum
+}
+
+/// Rising half of a Kaiser-Bessel derived window of `2n` points
+fn kbd_window(n: usize, alpha: f64) -> Vec<f32> {
+    let kaiser: Vec<f64> = (0..=n)
+        .map(|j| {
+            let x = 2.0 * j as f64 / n as f64 - 1.0;
+            bessel_i0(PI * alpha * (1.0 - x * x).sqrt())
+        })
+        .collect();
+    let total: f64 = kaiser.iter().sum();
+    kaiser[..n]
+        .iter()
+        .scan(0.0, |acc, &k| {
+            *acc += k;
+            Some((*acc / total).sqrt() as f32)
+        })
+        .collect()
+}
+
+/// Overlap state of one output channel
+pub struct ChannelState {
+    overlap: Vec<f32>,
+    prev_kbd: bool,
+}
+
+impl ChannelState {
+    pub fn new() -> ChannelState {
+        ChannelState { overlap: vec![0.0; 1024], prev_kbd: false }
+    }
+}
+
+pub struct Filterbank {
+    imdct_long: Imdct,
+    imdct_short: Imdct,
+    /// Rising window halves, indexed by window shape (sine, KBD)
+    long: [Vec<f32>; 2],
+    short: [Vec<f32>; 2],
+}
+
+impl Filterbank {
+    pub fn new() -> Filterbank {
+        Filterbank {
+            imdct_long: Imdct::new(2048),
+            imdct_short: Imdct::new(256),
+            long: [sine_window(1024), kbd_window(1024, 4.0)],
+            short: [sine_window(128), kbd_window(128, 6.0)],
+        }
+    }
+
+    /// Turn a spectrum in 16-bit sample units into 1024 samples in [-1, 1]
+    pub fn synthesize(&self, info: &IcsInfo, spectrum: &[f32], state: &mut ChannelState) -> Vec<f32> {
+        let (shape, prev) = (info.kbd as usize, state.prev_kbd as usize);
+        let mut block = vec![0.0f32; 2048];
+        if info.is_short() {
+            // Eight overlapping short blocks centered in the long frame
+            let scale = 2.0 / 256.0 / 32768.0;
+            let mut out = vec![0.0f32; 256];
+            for (w, coefficients) in spectrum.chunks_exact(128).enumerate() {
+                let scaled: Vec<f32> = coefficients.iter().map(|c| c * scale).collect();
+                self.imdct_short.inverse(&scaled, &mut out);
+                let rise = &self.short[if w == 0 { prev } else { shape }];
+                let offset = 448 + 128 * w;
+                for i in 0..128 {
+                    block[offset + i] += out[i] * rise[i];
+                    block[offset + 128 + i] += out[128 + i] * self.short[shape][127 - i];
+                }
+            }
+        } else {
+            let scale = 2.0 / 2048.0 / 32768.0;
+            let scaled: Vec<f32> = spectrum.iter().map(|c| c * scale).collect();
+            self.imdct_long.inverse(&scaled, &mut block);
+            let (rise, fall) = block.split_at_mut(1024);
+            if info.window_sequence == LONG_STOP {
+                rise[..448].fill(0.0);
+                for (s, w) in rise[448..576].iter_mut().zip(&self.short[prev]) {
+                    *s *= w;
+                }
+            } else {
+                for (s, w) in rise.iter_mut().zip(&self.long[prev]) {
+                    *s *= w;
+                }
+            }
+            if info.window_sequence == LONG_START {
+                for (s, w) in fall[448..576].iter_mut().zip(self.short[shape].iter().rev()) {
+                    *s *= w;
+                }
+                fall[576..].fill(0.0);
+            } else {
+                for (s, w) in fall.iter_mut().zip(self.long[shape].iter().rev()) {
+                    *s *= w;
+                }
+            }
+        }
+        let out = block[..1024].iter().zip(&state.overlap).map(|(a, b)| a + b).collect();
+        state.overlap.copy_from_slice(&block[1024..]);
+        state.prev_kbd = info.kbd;
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_are_power_complementary() {
+        // Princen-Bradley: w[i]^2 + w[n-1-i]^2 = 1 gives perfect reconstruction
+        for window in [sine_window(128), kbd_window(128, 6.0), kbd_window(1024, 4.0)] {
+            let n = window.len();
+            for i in 0..n {
+                assert!((window[i].powi(2) + window[n - 1 - i].powi(2) - 1.0).abs() < 1e-5);
+            }
+        }
+    }
+}
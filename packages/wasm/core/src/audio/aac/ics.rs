@@ -0,0 +1,431 @@
+//! Individual channel streams: side info, scalefactors and spectral data
+
+use super::huffman::{Codebooks, SPECTRAL_BOOKS};
+use crate::compress::bits::MsbBitReader;
+
+/// Window sequences other than a plain long window (0)
+pub const LONG_START: u8 = 1;
+pub const EIGHT_SHORT: u8 = 2;
+pub const LONG_STOP: u8 = 3;
+
+pub const ZERO_BOOK: u8 = 0;
+pub const NOISE_BOOK: u8 = 13;
+pub const INTENSITY_OUT_OF_PHASE: u8 = 14;
+pub const INTENSITY_IN_PHASE: u8 = 15;
+
+const SWB_LONG_96: &[usize] = &[
+    0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 64, 72, 80, 88, 96, 108, 120, 132, 144, 156, 172, 188, 212,
+    240, 276, 320, 384, 448, 512, 576, 640, 704, 768, 832, 896, 960, 1024,
+];
+const SWB_LONG_64: &[usize] = &[
+    0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 64, 72, 80, 88, 100, 112, 124, 140, 156, 172, 192, 216, 240,
+    268, 304, 344, 384, 424, 464, 504, 544, 584, 624, 664, 704, 744, 784, 824, 864, 904, 944, 984, 1024,
+];
+const SWB_LONG_48: &[usize] = &[
+    0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 48, 56, 64, 72, 80, 88, 96, 108, 120, 132, 144, 160, 176, 196, 216, 240,
+    264, 292, 320, 352, 384, 416, 448, 480, 512, 544, 576, 608, 640, 672, 704, 736, 768, 800, 832, 864, 896, 928, 1024,
+];
+const SWB_LONG_32: &[usize] = &[
+    0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 48, 56, 64, 72, 80, 88, 96, 108, 120, 132, 144, 160, 176, 196, 216, 240,
+    264, 292, 320, 352, 384, 416, 448, 480, 512, 544, 576, 608, 640, 672, 704, 736, 768, 800, 832, 864, 896, 928, 960,
+    992, 1024,
+];
+const SWB_LONG_24: &[usize] = &[
+    0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 52, 60, 68, 76, 84, 92, 100, 108, 116, 124, 136, 148, 160, 172, 188, 204,
+    220, 240, 260, 284, 308, 336, 364, 396, 432, 468, 508, 552, 600, 652, 704, 768, 832, 896, 960, 1024,
+];
+const SWB_LONG_16: &[usize] = &[
+    0, 8, 16, 24, 32, 40, 48, 56, 64, 72, 80, 88, 100, 112, 124, 136, 148, 160, 172, 184, 196, 212, 228, 244, 260, 280,
+    300, 320, 344, 368, 396, 424, 456, 492, 532, 572, 616, 664, 716, 772, 832, 896, 960, 1024,
+];
+const SWB_LONG_8: &[usize] = &[
+    0, 12, 24, 36, 48, 60, 72, 84, 96, 108, 120, 132, 144, 156, 172, 188, 204, 220, 236, 252, 268, 288, 308, 328, 348,
+    372, 396, 420, 448, 476, 508, 544, 580, 620, 664, 712, 764, 820, 880, 944, 1024,
+];
+
+const SWB_SHORT_64: &[usize] = &[0, 4, 8, 12, 16, 20, 24, 32, 40, 48, 64, 92, 128];
+const SWB_SHORT_48: &[usize] = &[0, 4, 8, 12, 16, 20, 28, 36, 44, 56, 68, 80, 96, 112, 128];
+const SWB_SHORT_24: &[usize] = &[0, 4, 8, 12, 16, 20, 24, 28, 36, 44, 52, 64, 76, 92, 108, 128];
+const SWB_SHORT_16: &[usize] = &[0, 4, 8, 12, 16, 20, 24, 28, 32, 40, 48, 60, 72, 88, 108, 128];
+const SWB_SHORT_8: &[usize] = &[0, 4, 8, 12, 16, 20, 24, 28, 36, 44, 52, 60, 72, 88, 108, 128];
+
+/// Scalefactor band offsets (long, short) per sampling frequency index
+pub const SWB_OFFSETS: [(&[usize], &[usize]); 13] = [
+    (SWB_LONG_96, SWB_SHORT_64),
+    (SWB_LONG_96, SWB_SHORT_64),
+    (SWB_LONG_64, SWB_SHORT_64),
+    (SWB_LONG_48, SWB_SHORT_48),
+    (SWB_LONG_48, SWB_SHORT_48),
+    (SWB_LONG_32, SWB_SHORT_48),
+    (SWB_LONG_24, SWB_SHORT_24),
+    (SWB_LONG_24, SWB_SHORT_24),
+    (SWB_LONG_16, SWB_SHORT_16),
+    (SWB_LONG_16, SWB_SHORT_16),
+    (SWB_LONG_16, SWB_SHORT_16),
+    (SWB_LONG_8, SWB_SHORT_8),
+    (SWB_LONG_8, SWB_SHORT_8),
+];
+
+/// Highest band TNS may filter (long, short) per sampling frequency index
+const TNS_MAX_BANDS: [(usize, usize); 13] = [
+    (31, 9),
+    (31, 9),
+    (34, 10),
+    (40, 14),
+    (42, 14),
+    (51, 14),
+    (46, 14),
+    (46, 14),
+    (42, 14),
+    (42, 14),
+    (42, 14),
+    (39, 14),
+    (39, 14),
+];
+
+/// Window shape and grouping, shared by both channels of a common-window pair
+#[derive(Clone, Debug, Default)]
+pub struct IcsInfo {
+    pub window_sequence: u8,
+    /// Kaiser-Bessel derived rather than sine
+    pub kbd: bool,
+    pub max_sfb: usize,
+    /// Number of consecutive short windows in each group (one group of
+    /// one window for long blocks)
+    pub group_lengths: Vec<usize>,
+}
+
+impl IcsInfo {
+    pub fn read(r: &mut MsbBitReader, rate_index: usize) -> Result<IcsInfo, String> {
+        if r.bit()? {
+            return Err("AAC ics_info reserved bit is set".to_string());
+        }
+        let window_sequence = r.bits(2)? as u8;
+        let kbd = r.bit()?;
+        let (max_sfb, group_lengths, bands) = if window_sequence == EIGHT_SHORT {
+            let max_sfb = r.bits(4)? as usize;
+            let grouping = r.bits(7)?;
+            let mut groups = vec![1];
+            // Each set bit joins the next window to the current group
+            for i in (0..7).rev() {
+                if grouping >> i & 1 != 0 {
+                    *groups.last_mut().unwrap_or(&mut 0) += 1;
+                } else {
+                    groups.push(1);
+                }
+            }
+            (max_sfb, groups, SWB_OFFSETS[rate_index].1)
+        } else {
+            let max_sfb = r.bits(6)? as usize;
+            if r.bit()? {
+                return Err("AAC prediction is not supported (AAC Main profile)".to_string());
+            }
+            (max_sfb, vec![1], SWB_OFFSETS[rate_index].0)
+        };
+        if max_sfb > bands.len() - 1 {
+            return Err(format!("AAC max_sfb {} exceeds the {} bands", max_sfb, bands.len() - 1));
+        }
+        Ok(IcsInfo { window_sequence, kbd, max_sfb, group_lengths })
+    }
+
+    pub fn is_short(&self) -> bool {
+        self.window_sequence == EIGHT_SHORT
+    }
+
+    pub fn bands(&self, rate_index: usize) -> &'static [usize] {
+        if self.is_short() {
+            SWB_OFFSETS[rate_index].1
+        } else {
+            SWB_OFFSETS[rate_index].0
+        }
+    }
+
+    /// Window indices of each group
+    pub fn groups(&self) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+        self.group_lengths.iter().scan(0, |start, &len| {
+            *start += len;
+            Some(*start - len..*start)
+        })
+    }
+}
+
+struct TnsFilter {
+    /// Length in bands, counted down from the previous filter
+    length: usize,
+    /// Filter the spectrum from high to low frequencies
+    downward: bool,
+    lpc: Vec<f32>,
+}
+
+/// Noise generator shared with common decoders, so noise substitution
+/// output can be compared bit for bit
+pub struct Noise(u32);
+
+impl Noise {
+    pub fn new() -> Noise {
+        Noise(0x1F2E_3D4C)
+    }
+
+    fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (self.0 as i32 >> 16) as i16 as f32
+    }
+}
+
+/// One decoded channel's spectrum, scaled to 16-bit sample units
+pub struct Ics {
+    pub info: IcsInfo,
+    /// Codebook per group and band
+    pub band_books: Vec<Vec<u8>>,
+    /// Scalefactor per group and band: a gain exponent for regular bands,
+    /// the energy for noise bands and the position for intensity bands
+    pub scalefactors: Vec<Vec<i32>>,
+    tns: Vec<Vec<TnsFilter>>,
+    pub spectrum: Vec<f32>,
+}
+
+impl Ics {
+    /// Read an individual_channel_stream; `common` is the ics_info of a
+    /// common-window channel pair
+    pub fn read(r: &mut MsbBitReader, books: &Codebooks, rate_index: usize, common: Option<&IcsInfo>, noise: &mut Noise) -> Result<Ics, String> {
+        let global_gain = r.bits(8)? as i32;
+        let info = match common {
+            Some(info) => info.clone(),
+            None => IcsInfo::read(r, rate_index)?,
+        };
+        let bands = info.bands(rate_index);
+
+        // Section data: runs of bands sharing a codebook
+        let section_bits = if info.is_short() { 3 } else { 5 };
+        let escape = (1 << section_bits) - 1;
+        let mut band_books = Vec::with_capacity(info.group_lengths.len());
+        for _ in &info.group_lengths {
+            let mut group = Vec::with_capacity(info.max_sfb);
+            while group.len() < info.max_sfb {
+                let book = r.bits(4)? as u8;
+                if book == 12 {
+                    return Err("AAC reserved codebook 12".to_string());
+                }
+                let mut length = 0;
+                loop {
+                    let increment = r.bits(section_bits)?;
+                    length += increment as usize;
+                    if increment != escape {
+                        break;
+                    }
+                }
+                if group.len() + length > info.max_sfb {
+                    return Err("AAC section runs past max_sfb".to_string());
+                }
+                group.extend(std::iter::repeat_n(book, length));
+            }
+            band_books.push(group);
+        }
+
+        // Scalefactors are coded as differences, separately for regular,
+        // noise and intensity bands
+        let (mut gain, mut energy, mut position) = (global_gain, global_gain - 90, 0);
+        let mut first_noise = true;
+        let mut scalefactors = Vec::with_capacity(band_books.len());
+        for group in &band_books {
+            let mut factors = Vec::with_capacity(group.len());
+            for &book in group {
+                let value = match book {
+                    ZERO_BOOK => 0,
+                    INTENSITY_OUT_OF_PHASE | INTENSITY_IN_PHASE => {
+                        position += books.scalefactor.decode(r)? as i32 - 60;
+                        position
+                    }
+                    NOISE_BOOK => {
+                        energy += if first_noise { r.bits(9)? as i32 - 256 } else { books.scalefactor.decode(r)? as i32 - 60 };
+                        first_noise = false;
+                        energy
+                    }
+                    _ => {
+                        gain += books.scalefactor.decode(r)? as i32 - 60;
+                        if !(0..256).contains(&gain) {
+                            return Err("AAC scalefactor out of range".to_string());
+                        }
+                        gain
+                    }
+                };
+                factors.push(value);
+            }
+            scalefactors.push(factors);
+        }
+
+        let mut pulses = Vec::new();
+        if r.bit()? {
+            if info.is_short() {
+                return Err("AAC pulse data in a short block".to_string());
+            }
+            let count = r.bits(2)? as usize + 1;
+            let mut k = *bands.get(r.bits(6)? as usize).ok_or("AAC pulse start band is out of range")?;
+            for _ in 0..count {
+                k += r.bits(5)? as usize;
+                pulses.push((k, r.bits(4)? as i32));
+            }
+        }
+
+        let tns = if r.bit()? { read_tns(r, &info)? } else { Vec::new() };
+        if r.bit()? {
+            return Err("AAC gain control is not supported (AAC SSR profile)".to_string());
+        }
+
+        // Quantized spectrum; windows of a short block are 128 apart
+        let mut quantized = vec![0i32; 1024];
+        for (windows, group) in info.groups().zip(&band_books) {
+            for (sfb, &book) in group.iter().enumerate() {
+                if !(1..=11).contains(&book) {
+                    continue;
+                }
+                for w in windows.clone() {
+                    let band = &mut quantized[w * 128 + bands[sfb]..w * 128 + bands[sfb + 1]];
+                    read_spectral(r, books, book, band)?;
+                }
+            }
+        }
+        for (k, amplitude) in pulses {
+            let q = quantized.get_mut(k).ok_or("AAC pulse position is out of range")?;
+            *q += if *q > 0 { amplitude } else { -amplitude };
+        }
+
+        let mut spectrum = vec![0.0f32; 1024];
+        for ((windows, group), factors) in info.groups().zip(&band_books).zip(&scalefactors) {
+            for (sfb, (&book, &factor)) in group.iter().zip(factors).enumerate() {
+                for w in windows.clone() {
+                    let range = w * 128 + bands[sfb]..w * 128 + bands[sfb + 1];
+                    match book {
+                        1..=11 => {
+                            let scale = 2f32.powf(0.25 * (factor - 100) as f32);
+                            for (s, &q) in spectrum[range.clone()].iter_mut().zip(&quantized[range]) {
+                                *s = (q.unsigned_abs() as f32).powf(4.0 / 3.0).copysign(q as f32) * scale;
+                            }
+                        }
+                        NOISE_BOOK => {
+                            let band = &mut spectrum[range];
+                            band.iter_mut().for_each(|s| *s = noise.next());
+                            let power: f32 = band.iter().map(|s| s * s).sum();
+                            let scale = 2f32.powf(0.25 * factor as f32) / power.sqrt();
+                            band.iter_mut().for_each(|s| *s *= scale);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(Ics { info, band_books, scalefactors, tns, spectrum })
+    }
+
+    /// Apply temporal noise shaping to the spectrum
+    pub fn apply_tns(&mut self, rate_index: usize) {
+        let bands = self.info.bands(rate_index);
+        let num_bands = bands.len() - 1;
+        let max_band = if self.info.is_short() { TNS_MAX_BANDS[rate_index].1 } else { TNS_MAX_BANDS[rate_index].0 }.min(self.info.max_sfb);
+        for (w, filters) in self.tns.iter().enumerate() {
+            let window = &mut self.spectrum[w * 128..];
+            let mut top = num_bands;
+            for filter in filters {
+                let bottom = top.saturating_sub(filter.length);
+                let (start, end) = (bands[bottom.min(max_band)], bands[top.min(max_band)]);
+                top = bottom;
+                if filter.lpc.is_empty() || start >= end {
+                    continue;
+                }
+                // All-pole filter running across frequency
+                let order = filter.lpc.len();
+                let positions: Vec<usize> = if filter.downward { (start..end).rev().collect() } else { (start..end).collect() };
+                for (m, &i) in positions.iter().enumerate() {
+                    let mut y = window[i];
+                    for (j, a) in filter.lpc.iter().enumerate().take(order.min(m)) {
+                        y -= a * window[positions[m - j - 1]];
+                    }
+                    window[i] = y;
+                }
+            }
+        }
+    }
+}
+
+fn read_tns(r: &mut MsbBitReader, info: &IcsInfo) -> Result<Vec<Vec<TnsFilter>>, String> {
+    let short = info.is_short();
+    let (windows, max_order) = if short { (8, 7) } else { (1, 12) };
+    let mut all = Vec::with_capacity(windows);
+    for _ in 0..windows {
+        let count = r.bits(if short { 1 } else { 2 })?;
+        let mut filters = Vec::new();
+        let resolution = if count > 0 { r.bits(1)? + 3 } else { 3 };
+        for _ in 0..count {
+            let length = r.bits(if short { 4 } else { 6 })? as usize;
+            let order = r.bits(if short { 3 } else { 5 })? as usize;
+            if order > max_order {
+                return Err(format!("AAC TNS order {} is too high", order));
+            }
+            let mut lpc = Vec::new();
+            let mut downward = false;
+            if order > 0 {
+                downward = r.bit()?;
+                let bits = resolution - r.bits(1)?;
+                // Reflection coefficients, quantized on an arcsine scale
+                let half = (1 << (resolution - 1)) as f32;
+                let reflection: Vec<f32> = (0..order)
+                    .map(|_| {
+                        let raw = r.bits(bits)?;
+                        let value = ((raw << (32 - bits)) as i32 >> (32 - bits)) as f32;
+                        let step = if value >= 0.0 { half - 0.5 } else { half + 0.5 };
+                        Ok((value / (step / std::f32::consts::FRAC_PI_2)).sin())
+                    })
+                    .collect::<Result<_, String>>()?;
+                // Step up to direct-form LPC coefficients
+                for (m, &k) in reflection.iter().enumerate() {
+                    let previous = lpc.clone();
+                    for i in 0..m {
+                        lpc[i] = previous[i] + k * previous[m - 1 - i];
+                    }
+                    lpc.push(k);
+                }
+            }
+            filters.push(TnsFilter { length, downward, lpc });
+        }
+        all.push(filters);
+    }
+    Ok(all)
+}
+
+/// Decode one band of quantized values with spectral codebook `book`
+fn read_spectral(r: &mut MsbBitReader, books: &Codebooks, book: u8, band: &mut [i32]) -> Result<(), String> {
+    let layout = SPECTRAL_BOOKS[book as usize - 1];
+    let huffman = &books.spectral[book as usize - 1];
+    let offset = if layout.signed { (layout.modulo / 2) as i32 } else { 0 };
+    for values in band.chunks_exact_mut(layout.dimension) {
+        let mut index = huffman.decode(r)?;
+        for v in values.iter_mut().rev() {
+            *v = (index % layout.modulo) as i32 - offset;
+            index /= layout.modulo;
+        }
+        if layout.signed {
+            continue;
+        }
+        for v in values.iter_mut() {
+            if *v != 0 && r.bit()? {
+                *v = -*v;
+            }
+        }
+        if book == 11 {
+            for v in values.iter_mut().filter(|v| v.abs() == 16) {
+                // Escape: a unary prefix of N ones gives N + 4 more bits
+                let mut n = 4;
+                while r.bit()? {
+                    n += 1;
+                    if n > 12 {
+                        return Err("AAC escape code is too long".to_string());
+                    }
+                }
+                let magnitude = (1 << n) + r.bits(n)? as i32;
+                *v = magnitude * v.signum();
+            }
+        }
+    }
+    Ok(())
+}
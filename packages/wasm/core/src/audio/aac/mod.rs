@@ -0,0 +1,517 @@
+//! AAC-LC audio decoding
+//!
+//! Decodes the low-complexity profile of MPEG-4 AAC (and MPEG-2 AAC LC):
+//! long and short blocks, M/S and intensity stereo, noise substitution,
+//! pulses and temporal noise shaping. Input is either an ADTS stream or
+//! raw access units with their AudioSpecificConfig, as stored in MP4.
+//! HE-AAC streams decode to their AAC-LC core, at half the sample rate and
+//! without spectral band replication.
+
+mod filterbank;
+mod huffman;
+mod ics;
+
+use super::AudioData;
+use crate::compress::bits::MsbBitReader;
+use crate::limits::Budget;
+#[cfg(feature = "video")]
+use crate::video::mp4::{read_mp4, TrackKind};
+use filterbank::{ChannelState, Filterbank};
+use huffman::Codebooks;
+use ics::{Ics, IcsInfo, Noise, INTENSITY_IN_PHASE, INTENSITY_OUT_OF_PHASE, NOISE_BOOK};
+
+/// Sampling frequencies by index
+pub const SAMPLE_RATES: [u32; 13] = [96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350];
+
+const OBJECT_TYPE_LC: u8 = 2;
+const OBJECT_TYPE_SBR: u8 = 5;
+const OBJECT_TYPE_PS: u8 = 29;
+
+const ELEMENT_SCE: u32 = 0;
+const ELEMENT_CPE: u32 = 1;
+const ELEMENT_CCE: u32 = 2;
+const ELEMENT_LFE: u32 = 3;
+const ELEMENT_DSE: u32 = 4;
+const ELEMENT_PCE: u32 = 5;
+const ELEMENT_FIL: u32 = 6;
+const ELEMENT_END: u32 = 7;
+
+/// Stream parameters from an AudioSpecificConfig or ADTS header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AacConfig {
+    /// MPEG-4 audio object type of the core; 2 is AAC-LC
+    pub object_type: u8,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Channel configuration, 0 when a program config element defines it
+    pub channel_config: u8,
+}
+
+/// Index of the band tables to use for any sample rate
+fn rate_index(sample_rate: u32) -> usize {
+    const MIN_RATES: [u32; 12] = [92017, 75132, 55426, 46009, 37566, 27713, 23004, 18783, 13856, 11502, 9391, 0];
+    MIN_RATES.iter().position(|&min| sample_rate >= min).unwrap_or(11)
+}
+
+fn read_sample_rate(r: &mut MsbBitReader) -> Result<u32, String> {
+    match r.bits(4)? as usize {
+        15 => r.bits(24),
+        index => SAMPLE_RATES.get(index).copied().ok_or_else(|| format!("Reserved AAC sampling frequency index {}", index)),
+    }
+}
+
+fn read_object_type(r: &mut MsbBitReader) -> Result<u8, String> {
+    Ok(match r.bits(5)? as u8 {
+        31 => 32 + r.bits(6)? as u8,
+        object_type => object_type,
+    })
+}
+
+/// Channels per channel configuration
+fn config_channels(channel_config: u8) -> Result<u16, String> {
+    match channel_config {
+        1..=6 => Ok(channel_config as u16),
+        7 => Ok(8),
+        _ => Err(format!("Unsupported AAC channel configuration {}", channel_config)),
+    }
+}
+
+/// Read a program_config_element and return its channel count
+fn read_program_config(r: &mut MsbBitReader) -> Result<u16, String> {
+    r.bits(4 + 2 + 4)?;
+    let (front, side, back) = (r.bits(4)?, r.bits(4)?, r.bits(4)?);
+    let (lfe, data, coupling) = (r.bits(2)?, r.bits(3)?, r.bits(4)?);
+    for bits in [4, 4, 3] {
+        // Mono, stereo and matrix mixdown, each with its element tag
+        if r.bit()? {
+            r.bits(bits)?;
+        }
+    }
+    let mut channels = lfe as u16;
+    for _ in 0..front + side + back {
+        channels += 1 + r.bits(1)? as u16;
+        r.bits(4)?;
+    }
+    r.bits(4 * (lfe + data))?;
+    for _ in 0..coupling {
+        r.bits(5)?;
+    }
+    r.align();
+    for _ in 0..r.bits(8)? {
+        r.bits(8)?;
+    }
+    Ok(channels)
+}
+
+/// Parse an MPEG-4 AudioSpecificConfig (the `esds` decoder config in MP4)
+pub fn parse_audio_specific_config(data: &[u8]) -> Result<AacConfig, String> {
+    let r = &mut MsbBitReader::new(data);
+    let mut object_type = read_object_type(r)?;
+    let sample_rate = read_sample_rate(r)?;
+    let channel_config = r.bits(4)? as u8;
+    if object_type == OBJECT_TYPE_SBR || object_type == OBJECT_TYPE_PS {
+        // HE-AAC: skip the extension rate and decode the core
+        read_sample_rate(r)?;
+        object_type = read_object_type(r)?;
+    }
+    if object_type != OBJECT_TYPE_LC {
+        return Err(format!("Unsupported AAC object type {} (only AAC-LC is supported)", object_type));
+    }
+    if r.bit()? {
+        return Err("960-sample AAC frames are not supported".to_string());
+    }
+    if r.bit()? {
+        // Core coder delay
+        r.bits(14)?;
+    }
+    r.bit()?;
+    let channels = if channel_config == 0 { read_program_config(r)? } else { config_channels(channel_config)? };
+    if sample_rate == 0 || channels == 0 {
+        return Err("Invalid AAC stream parameters".to_string());
+    }
+    Ok(AacConfig { object_type, sample_rate, channels, channel_config })
+}
+
+/// A parsed ADTS frame header
+struct AdtsHeader {
+    config: AacConfig,
+    header_len: usize,
+    frame_len: usize,
+    blocks: usize,
+    /// Each of several blocks is followed by its own CRC
+    block_crc: bool,
+}
+
+fn read_adts_header(data: &[u8]) -> Result<AdtsHeader, String> {
+    let r = &mut MsbBitReader::new(data);
+    if r.bits(12)? != 0xFFF {
+        return Err("Lost ADTS frame sync".to_string());
+    }
+    // MPEG version and layer
+    r.bits(3)?;
+    let protection_absent = r.bit()?;
+    let object_type = r.bits(2)? as u8 + 1;
+    let sample_rate = *SAMPLE_RATES.get(r.bits(4)? as usize).ok_or("Invalid ADTS sampling frequency")?;
+    r.bit()?;
+    let channel_config = r.bits(3)? as u8;
+    r.bits(4)?;
+    let frame_len = r.bits(13)? as usize;
+    r.bits(11)?;
+    let blocks = r.bits(2)? as usize + 1;
+    let header_len = if protection_absent { 7 } else { 9 + if blocks > 1 { 2 * blocks } else { 0 } };
+    if object_type != OBJECT_TYPE_LC {
+        return Err(format!("Unsupported AAC object type {} (only AAC-LC is supported)", object_type));
+    }
+    if frame_len < header_len {
+        return Err("Invalid ADTS frame length".to_string());
+    }
+    // Configuration 0 streams carry a program config in the first block;
+    // assume stereo until then
+    let channels = if channel_config == 0 { 2 } else { config_channels(channel_config)? };
+    Ok(AdtsHeader {
+        config: AacConfig { object_type, sample_rate, channels, channel_config },
+        header_len,
+        frame_len,
+        blocks,
+        block_crc: !protection_absent && blocks > 1,
+    })
+}
+
+/// Decoder state for one AAC-LC stream
+pub struct AacDecoder {
+    config: AacConfig,
+    rate_index: usize,
+    books: Codebooks,
+    filterbank: Filterbank,
+    channels: Vec<ChannelState>,
+    noise: Noise,
+}
+
+impl AacDecoder {
+    pub fn new(config: AacConfig) -> AacDecoder {
+        AacDecoder {
+            config,
+            rate_index: rate_index(config.sample_rate),
+            books: Codebooks::new(),
+            filterbank: Filterbank::new(),
+            channels: (0..config.channels).map(|_| ChannelState::new()).collect(),
+            noise: Noise::new(),
+        }
+    }
+
+    /// Decoder for raw access units described by an AudioSpecificConfig
+    pub fn from_config(audio_specific_config: &[u8]) -> Result<AacDecoder, String> {
+        Ok(AacDecoder::new(parse_audio_specific_config(audio_specific_config)?))
+    }
+
+    pub fn config(&self) -> AacConfig {
+        self.config
+    }
+
+    /// Decode one raw access unit into 1024 samples per channel
+    ///
+    /// Channels come in WAV order (front left, front right, center, LFE,
+    /// surrounds) for the standard configurations.
+    pub fn decode_frame(&mut self, data: &[u8]) -> Result<Vec<Vec<f32>>, String> {
+        self.decode_block(&mut MsbBitReader::new(data))
+    }
+
+    fn decode_block(&mut self, r: &mut MsbBitReader) -> Result<Vec<Vec<f32>>, String> {
+        let mut output: Vec<Vec<f32>> = Vec::new();
+        loop {
+            let element = r.bits(3)?;
+            match element {
+                ELEMENT_SCE | ELEMENT_LFE => {
+                    r.bits(4)?;
+                    let mut ics = Ics::read(r, &self.books, self.rate_index, None, &mut self.noise)?;
+                    ics.apply_tns(self.rate_index);
+                    let channel = channel(&mut self.channels, output.len())?;
+                    output.push(self.filterbank.synthesize(&ics.info, &ics.spectrum, channel));
+                }
+                ELEMENT_CPE => {
+                    r.bits(4)?;
+                    let [left, right] = self.read_pair(r)?;
+                    for ics in [left, right] {
+                        let channel = channel(&mut self.channels, output.len())?;
+                        output.push(self.filterbank.synthesize(&ics.info, &ics.spectrum, channel));
+                    }
+                }
+                ELEMENT_CCE => return Err("AAC coupling channel elements are not supported".to_string()),
+                ELEMENT_DSE => {
+                    r.bits(4)?;
+                    let align = r.bit()?;
+                    let mut count = r.bits(8)?;
+                    if count == 255 {
+                        count += r.bits(8)?;
+                    }
+                    if align {
+                        r.align();
+                    }
+                    for _ in 0..count {
+                        r.bits(8)?;
+                    }
+                }
+                ELEMENT_PCE => {
+                    let channels = read_program_config(r)?;
+                    if self.config.channel_config == 0 && channels != self.config.channels {
+                        self.config.channels = channels;
+                        self.channels = (0..channels).map(|_| ChannelState::new()).collect();
+                    }
+                }
+                ELEMENT_FIL => {
+                    // Extension payloads such as SBR are skipped
+                    let mut count = r.bits(4)?;
+                    if count == 15 {
+                        count += r.bits(8)? - 1;
+                    }
+                    for _ in 0..count {
+                        r.bits(8)?;
+                    }
+                }
+                ELEMENT_END => break,
+                _ => unreachable!(),
+            }
+        }
+        r.align();
+
+        output.resize(self.config.channels as usize, vec![0.0; 1024]);
+        // MPEG order puts the center first and the LFE last
+        let order: &[usize] = match self.config.channel_config {
+            3 => &[1, 2, 0],
+            4 => &[1, 2, 0, 3],
+            5 => &[1, 2, 0, 3, 4],
+            6 => &[1, 2, 0, 5, 3, 4],
+            7 => &[1, 2, 0, 7, 5, 6, 3, 4],
+            _ => return Ok(output),
+        };
+        Ok(order.iter().map(|&i| std::mem::take(&mut output[i])).collect())
+    }
+
+    /// Read a channel pair element and undo joint stereo coding
+    fn read_pair(&mut self, r: &mut MsbBitReader) -> Result<[Ics; 2], String> {
+        let common = if r.bit()? { Some(IcsInfo::read(r, self.rate_index)?) } else { None };
+        let mut ms_used = Vec::new();
+        let mut ms_mask = 0;
+        if let Some(info) = &common {
+            ms_mask = r.bits(2)?;
+            if ms_mask == 3 {
+                return Err("Reserved AAC M/S mask".to_string());
+            }
+            for _ in &info.group_lengths {
+                let group: Vec<bool> = (0..info.max_sfb).map(|_| Ok(ms_mask == 2 || (ms_mask == 1 && r.bit()?))).collect::<Result<_, String>>()?;
+                ms_used.push(group);
+            }
+        }
+        let mut left = Ics::read(r, &self.books, self.rate_index, common.as_ref(), &mut self.noise)?;
+        let mut right = Ics::read(r, &self.books, self.rate_index, common.as_ref(), &mut self.noise)?;
+
+        if let Some(info) = &common {
+            let bands = info.bands(self.rate_index);
+            for (g, windows) in info.groups().enumerate() {
+                for sfb in 0..info.max_sfb {
+                    let (left_book, right_book) = (left.band_books[g][sfb], right.band_books[g][sfb]);
+                    for w in windows.clone() {
+                        let range = w * 128 + bands[sfb]..w * 128 + bands[sfb + 1];
+                        let (l, rt) = (&mut left.spectrum[range.clone()], &mut right.spectrum[range]);
+                        if right_book == INTENSITY_IN_PHASE || right_book == INTENSITY_OUT_OF_PHASE {
+                            // The right channel is the left scaled by the intensity position
+                            let mut scale = 0.5f32.powf(0.25 * right.scalefactors[g][sfb] as f32);
+                            if (right_book == INTENSITY_OUT_OF_PHASE) != (ms_mask == 1 && ms_used[g][sfb]) {
+                                scale = -scale;
+                            }
+                            for (r, l) in rt.iter_mut().zip(l.iter()) {
+                                *r = l * scale;
+                            }
+                        } else if left_book == NOISE_BOOK && right_book == NOISE_BOOK {
+                            // M/S on noise bands means both use the same noise
+                            if ms_used[g][sfb] {
+                                let scale = 2f32.powf(0.25 * (right.scalefactors[g][sfb] - left.scalefactors[g][sfb]) as f32);
+                                for (r, l) in rt.iter_mut().zip(l.iter()) {
+                                    *r = l * scale;
+                                }
+                            }
+                        } else if ms_used[g][sfb] && left_book != NOISE_BOOK && right_book != NOISE_BOOK {
+                            for (l, r) in l.iter_mut().zip(rt.iter_mut()) {
+                                (*l, *r) = (*l + *r, *l - *r);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        left.apply_tns(self.rate_index);
+        right.apply_tns(self.rate_index);
+        Ok([left, right])
+    }
+}
+
+/// Overlap state for the next decoded channel
+fn channel(channels: &mut [ChannelState], index: usize) -> Result<&mut ChannelState, String> {
+    channels.get_mut(index).ok_or_else(|| "AAC frame has more channels than configured".to_string())
+}
+
+/// Append planar samples to `out`, charging them to `budget` first
+fn interleave(planar: &[Vec<f32>], out: &mut Vec<f32>, budget: &mut Budget) -> Result<(), String> {
+    let frames = planar.first().map_or(0, Vec::len);
+    budget.output(frames * planar.len() * 4)?;
+    for i in 0..frames {
+        out.extend(planar.iter().map(|channel| channel[i]));
+    }
+    Ok(())
+}
+
+/// Decode an ADTS (`.aac`) stream, within the output byte limit set by
+/// `set_limits`
+///
+/// Output includes the encoder's priming delay, which ADTS does not record.
+pub fn decode_adts(data: &[u8]) -> Result<AudioData, String> {
+    let mut decoder: Option<AacDecoder> = None;
+    let mut budget = Budget::new();
+    let mut samples = Vec::new();
+    let mut pos = 0;
+    // Skip an ID3v2 tag in front of the stream
+    if data.len() >= 10 && &data[..3] == b"ID3" {
+        let size = data[6..10].iter().fold(0usize, |acc, &b| (acc << 7) | (b & 0x7F) as usize);
+        pos = 10 + size;
+    }
+    while pos + 7 <= data.len() {
+        let header = match read_adts_header(&data[pos..]) {
+            Ok(header) => header,
+            // Resynchronize on the next sync word
+            Err(_) if decoder.is_some() => {
+                match data[pos + 1..].windows(2).position(|w| w[0] == 0xFF && w[1] & 0xF6 == 0xF0) {
+                    Some(skip) => pos += 1 + skip,
+                    None => break,
+                }
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let truncated = pos + header.frame_len > data.len();
+        let frame = &data[pos..data.len().min(pos + header.frame_len)];
+        pos += header.frame_len;
+        let decoder = decoder.get_or_insert_with(|| AacDecoder::new(header.config));
+        let r = &mut MsbBitReader::new(&frame[header.header_len..]);
+        for _ in 0..header.blocks {
+            match decoder.decode_block(r) {
+                Ok(planar) => interleave(&planar, &mut samples, &mut budget)?,
+                // A truncated final frame ends the stream
+                Err(_) if truncated => break,
+                Err(e) => return Err(e),
+            }
+            if header.block_crc {
+                r.bits(16)?;
+            }
+        }
+    }
+    let decoder = decoder.ok_or("No ADTS frames found")?;
+    let config = decoder.config();
+    Ok(AudioData { sample_rate: config.sample_rate, channels: config.channels, samples })
+}
+
+/// Decode raw AAC access units, such as MP4 samples, given the stream's
+/// AudioSpecificConfig, within the output byte limit set by `set_limits`
+pub fn decode_aac<'a>(audio_specific_config: &[u8], frames: impl IntoIterator<Item = &'a [u8]>) -> Result<AudioData, String> {
+    let mut decoder = AacDecoder::from_config(audio_specific_config)?;
+    let mut budget = Budget::new();
+    let mut samples = Vec::new();
+    for frame in frames {
+        interleave(&decoder.decode_frame(frame)?, &mut samples, &mut budget)?;
+    }
+    let config = decoder.config();
+    Ok(AudioData { sample_rate: config.sample_rate, channels: config.channels, samples })
+}
+
+/// Decode the first AAC track of an MP4 or M4A file
+#[cfg(feature = "video")]
+pub fn decode_m4a(data: &[u8]) -> Result<AudioData, String> {
+    let movie = read_mp4(data)?;
+    let index = movie
+        .tracks
+        .iter()
+        .position(|t| t.kind == TrackKind::Audio && t.codec == "mp4a" && !t.codec_config.is_empty())
+        .ok_or("No AAC track found")?;
+    let frames = movie.samples[index].iter().map(|s| s.data(data)).collect::<Result<Vec<_>, _>>()?;
+    decode_aac(&movie.tracks[index].codec_config, frames)
+}
+
+/// MP4 files need the demuxer of the `video` feature
+#[cfg(not(feature = "video"))]
+pub fn decode_m4a(_data: &[u8]) -> Result<AudioData, String> {
+    Err("MP4 support was left out of this build".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress::bits::MsbBitWriter;
+
+    #[test]
+    fn test_audio_specific_config() {
+        // AAC-LC, 44.1 kHz, stereo
+        let config = parse_audio_specific_config(&[0x12, 0x10]).unwrap();
+        assert_eq!((config.object_type, config.sample_rate, config.channels), (2, 44100, 2));
+        // HE-AAC signalled explicitly: 24 kHz core, 48 kHz output
+        let config = parse_audio_specific_config(&[0x2B, 0x11, 0x88, 0x00]).unwrap();
+        assert_eq!((config.object_type, config.sample_rate, config.channels), (2, 24000, 2));
+        assert!(parse_audio_specific_config(&[0x0A, 0x10]).is_err());
+    }
+
+    /// An ADTS frame of silence: one SCE with every band zero
+    fn silent_frame(w: &mut MsbBitWriter, channels: u32) {
+        let mut body = MsbBitWriter::new();
+        for _ in 0..channels {
+            body.write(ELEMENT_SCE, 3);
+            body.write(0, 4);
+            // Global gain, ics_info with max_sfb 0, no pulse/TNS/gain control
+            body.write(100, 8);
+            body.write(0, 1 + 2 + 1 + 6 + 1);
+            body.write(0, 3);
+        }
+        body.write(ELEMENT_END, 3);
+        let body = body.finish();
+        let len = 7 + body.len() as u32;
+        w.write(0xFFF, 12);
+        w.write(0b0001, 4);
+        // LC, 44.1 kHz, mono
+        w.write(1, 2);
+        w.write(4, 4);
+        w.write(0, 1);
+        w.write(1, 3);
+        w.write(0, 4);
+        w.write(len, 13);
+        w.write(0x7FF, 11);
+        w.write(0, 2);
+        for byte in body {
+            w.write(byte as u32, 8);
+        }
+    }
+
+    #[test]
+    fn test_adts_silence() {
+        let mut w = MsbBitWriter::new();
+        for _ in 0..3 {
+            silent_frame(&mut w, 1);
+        }
+        let audio = decode_adts(&w.finish()).unwrap();
+        assert_eq!((audio.sample_rate, audio.channels, audio.samples.len()), (44100, 1, 3072));
+        assert!(audio.samples.iter().all(|&s| s == 0.0));
+
+        // A second SCE does not fit a mono configuration
+        let mut w = MsbBitWriter::new();
+        silent_frame(&mut w, 2);
+        assert!(decode_adts(&w.finish()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "video")]
+    fn test_m4a() {
+        let mut w = MsbBitWriter::new();
+        silent_frame(&mut w, 1);
+        // The raw access unit without its ADTS header
+        let frame = w.finish()[7..].to_vec();
+        let file = crate::video::mp4::tests::audio_mp4(&[0x12, 0x08], &[&frame, &frame]);
+        let audio = decode_m4a(&file).unwrap();
+        assert_eq!((audio.sample_rate, audio.channels, audio.samples.len()), (44100, 1, 2048));
+    }
+}
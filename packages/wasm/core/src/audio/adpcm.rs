@@ -0,0 +1,315 @@
+//! IMA (DVI) and Microsoft ADPCM block coding, as stored in WAV files
+//!
+//! Both formats code 4 bits per sample in fixed-size blocks that each start
+//! with an uncompressed header, so blocks decode independently.
+
+use crate::utils::read_u16_le;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// ADPCM variant for encoding
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdpcmFormat {
+    /// IMA/DVI ADPCM (WAV format tag 0x0011)
+    Ima = 0,
+    /// Microsoft ADPCM (WAV format tag 0x0002)
+    Ms = 1,
+}
+
+const IMA_STEPS: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66, 73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371,
+    408, 449, 494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272, 2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845,
+    8630, 9493, 10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+const IMA_INDEX: [i32; 8] = [-1, -1, -1, -1, 2, 4, 6, 8];
+
+const MS_ADAPT: [i32; 16] = [230, 230, 230, 230, 307, 409, 512, 614, 768, 614, 512, 409, 307, 230, 230, 230];
+
+/// The seven predictor coefficient pairs every MS ADPCM file declares
+pub const MS_COEFFICIENTS: [(i32, i32); 7] = [(256, 0), (512, -256), (0, 0), (192, 64), (240, 0), (460, -208), (392, -232)];
+
+/// Samples per channel in a block of `block_align` bytes
+pub fn ima_samples_per_block(block_align: usize, channels: usize) -> usize {
+    (block_align / channels).saturating_sub(4) * 2 + 1
+}
+
+/// Samples per channel in a block of `block_align` bytes
+pub fn ms_samples_per_block(block_align: usize, channels: usize) -> usize {
+    (block_align / channels).saturating_sub(7) * 2 + 2
+}
+
+#[derive(Clone, Copy)]
+struct ImaState {
+    predictor: i32,
+    index: i32,
+}
+
+impl ImaState {
+    fn decode(&mut self, nibble: u8) -> i16 {
+        let step = IMA_STEPS[self.index as usize];
+        let mut diff = step >> 3;
+        if nibble & 4 != 0 {
+            diff += step;
+        }
+        if nibble & 2 != 0 {
+            diff += step >> 1;
+        }
+        if nibble & 1 != 0 {
+            diff += step >> 2;
+        }
+        self.predictor = if nibble & 8 != 0 { self.predictor - diff } else { self.predictor + diff }.clamp(-32768, 32767);
+        self.index = (self.index + IMA_INDEX[(nibble & 7) as usize]).clamp(0, 88);
+        self.predictor as i16
+    }
+
+    fn encode(&mut self, sample: i16) -> u8 {
+        let mut diff = sample as i32 - self.predictor;
+        let mut nibble = 0;
+        if diff < 0 {
+            nibble = 8;
+            diff = -diff;
+        }
+        let mut step = IMA_STEPS[self.index as usize];
+        for bit in [4, 2, 1] {
+            if diff >= step {
+                nibble |= bit;
+                diff -= step;
+            }
+            step >>= 1;
+        }
+        // Track the decoder's reconstruction, not the input
+        self.decode(nibble);
+        nibble
+    }
+}
+
+/// Decode one IMA ADPCM block to interleaved samples
+///
+/// A short final block decodes as far as its data reaches.
+pub fn decode_ima_block(block: &[u8], channels: usize, out: &mut Vec<i16>) -> Result<(), String> {
+    if block.len() < 4 * channels {
+        return Err("IMA ADPCM block is too short".to_string());
+    }
+    let mut states: Vec<ImaState> = (0..channels)
+        .map(|c| ImaState { predictor: read_u16_le(block, 4 * c) as i16 as i32, index: block[4 * c + 2].min(88) as i32 })
+        .collect();
+    out.extend(states.iter().map(|s| s.predictor as i16));
+    // Each channel contributes 4 bytes (8 samples) in turn
+    let mut frames = vec![0i16; 8 * channels];
+    for group in block[4 * channels..].chunks_exact(4 * channels) {
+        for (c, state) in states.iter_mut().enumerate() {
+            for (i, &byte) in group[4 * c..4 * c + 4].iter().enumerate() {
+                frames[(2 * i) * channels + c] = state.decode(byte & 0x0F);
+                frames[(2 * i + 1) * channels + c] = state.decode(byte >> 4);
+            }
+        }
+        out.extend_from_slice(&frames);
+    }
+    Ok(())
+}
+
+/// Encode interleaved samples as IMA ADPCM blocks of `block_align` bytes
+///
+/// The final block is padded with silence.
+pub fn encode_ima(samples: &[i16], channels: usize, block_align: usize) -> Vec<u8> {
+    let per_block = ima_samples_per_block(block_align, channels);
+    let mut out = Vec::new();
+    // Start with a step size that fits the opening slope of each channel
+    let mut states: Vec<ImaState> = (0..channels)
+        .map(|c| {
+            let slope = match (samples.get(c), samples.get(channels + c)) {
+                (Some(&a), Some(&b)) => (b as i32 - a as i32).abs(),
+                _ => 0,
+            };
+            ImaState { predictor: 0, index: IMA_STEPS.iter().position(|&s| s >= slope).unwrap_or(88) as i32 }
+        })
+        .collect();
+    for block in samples.chunks(per_block * channels) {
+        let sample = |frame: usize, c: usize| block.get(frame * channels + c).copied().unwrap_or(0);
+        for (c, state) in states.iter_mut().enumerate() {
+            // The step index carries over between blocks
+            state.predictor = sample(0, c) as i32;
+            out.extend_from_slice(&sample(0, c).to_le_bytes());
+            out.extend_from_slice(&[state.index as u8, 0]);
+        }
+        for group in (1..per_block).step_by(8) {
+            for (c, state) in states.iter_mut().enumerate() {
+                for i in 0..4 {
+                    let low = state.encode(sample(group + 2 * i, c));
+                    let high = state.encode(sample(group + 2 * i + 1, c));
+                    out.push(high << 4 | low);
+                }
+            }
+        }
+    }
+    out
+}
+
+#[derive(Clone, Copy)]
+struct MsState {
+    coefficients: (i32, i32),
+    delta: i32,
+    sample1: i32,
+    sample2: i32,
+}
+
+impl MsState {
+    fn predict(&self) -> i32 {
+        (self.sample1 * self.coefficients.0 + self.sample2 * self.coefficients.1) >> 8
+    }
+
+    fn decode(&mut self, nibble: u8) -> i16 {
+        let signed = ((nibble as i8) << 4 >> 4) as i32;
+        let sample = (self.predict() + signed * self.delta).clamp(-32768, 32767);
+        self.sample2 = self.sample1;
+        self.sample1 = sample;
+        self.delta = ((MS_ADAPT[nibble as usize] * self.delta) >> 8).max(16);
+        sample as i16
+    }
+
+    fn encode(&mut self, sample: i16) -> u8 {
+        let error = sample as i32 - self.predict();
+        let rounded = if error < 0 { error - self.delta / 2 } else { error + self.delta / 2 };
+        let nibble = (rounded / self.delta).clamp(-8, 7) as u8 & 0x0F;
+        self.decode(nibble);
+        nibble
+    }
+}
+
+/// Decode one MS ADPCM block to interleaved samples
+pub fn decode_ms_block(block: &[u8], channels: usize, coefficients: &[(i32, i32)], out: &mut Vec<i16>) -> Result<(), String> {
+    if block.len() < 7 * channels {
+        return Err("MS ADPCM block is too short".to_string());
+    }
+    // Header fields are grouped by kind, one per channel each
+    let word = |field: usize, c: usize| read_u16_le(block, channels + 2 * (field * channels + c)) as i16 as i32;
+    let mut states = Vec::with_capacity(channels);
+    for (c, &predictor) in block[..channels].iter().enumerate() {
+        let predictor = predictor as usize;
+        let coefficients = *coefficients.get(predictor).ok_or_else(|| format!("Invalid MS ADPCM predictor {}", predictor))?;
+        states.push(MsState { coefficients, delta: word(0, c), sample1: word(1, c), sample2: word(2, c) });
+    }
+    out.extend(states.iter().map(|s| s.sample2 as i16));
+    out.extend(states.iter().map(|s| s.sample1 as i16));
+    // Nibbles run high first, cycling through the channels
+    let mut c = 0;
+    for &byte in &block[7 * channels..] {
+        for nibble in [byte >> 4, byte & 0x0F] {
+            out.push(states[c].decode(nibble));
+            c = (c + 1) % channels;
+        }
+    }
+    Ok(())
+}
+
+/// Squared error of coding `frames` of one channel with a predictor
+fn ms_trial(state: MsState, frames: impl Iterator<Item = i16>) -> i64 {
+    let mut state = state;
+    frames
+        .map(|s| {
+            state.encode(s);
+            let e = (s as i32 - state.sample1) as i64;
+            e * e
+        })
+        .sum()
+}
+
+/// Encode interleaved samples as MS ADPCM blocks of `block_align` bytes,
+/// using the standard coefficient set
+///
+/// Each block and channel picks the predictor with the least error. The
+/// final block is padded with silence.
+pub fn encode_ms(samples: &[i16], channels: usize, block_align: usize) -> Vec<u8> {
+    let per_block = ms_samples_per_block(block_align, channels);
+    let mut out = Vec::new();
+    for block in samples.chunks(per_block * channels) {
+        let sample = |frame: usize, c: usize| block.get(frame * channels + c).copied().unwrap_or(0);
+        let mut states = Vec::with_capacity(channels);
+        for c in 0..channels {
+            // Start the step size near the typical prediction error
+            let spread = (2..per_block.min(10)).map(|f| (sample(f, c) as i32 - sample(f - 1, c) as i32).abs()).max().unwrap_or(0);
+            let initial = |coefficients| MsState { coefficients, delta: (spread / 4).max(16), sample1: sample(1, c) as i32, sample2: sample(0, c) as i32 };
+            let best = (0..MS_COEFFICIENTS.len())
+                .min_by_key(|&p| ms_trial(initial(MS_COEFFICIENTS[p]), (2..per_block).map(|f| sample(f, c))))
+                .unwrap_or(0);
+            states.push((best, initial(MS_COEFFICIENTS[best])));
+        }
+        out.extend(states.iter().map(|&(p, _)| p as u8));
+        for field in 0..3 {
+            for (_, s) in &states {
+                let value = [s.delta, s.sample1, s.sample2][field] as i16;
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        let mut high = None;
+        for f in 2..per_block {
+            for (c, (_, state)) in states.iter_mut().enumerate() {
+                let nibble = state.encode(sample(f, c));
+                match high.take() {
+                    Some(h) => out.push(h << 4 | nibble),
+                    None => high = Some(nibble),
+                }
+            }
+        }
+        if let Some(h) = high {
+            out.push(h << 4);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frames: usize, channels: usize) -> Vec<i16> {
+        (0..frames * channels).map(|i| ((i / channels) as f64 * 0.05 + (i % channels) as f64).sin() as f32 * 12000.0).map(|s| s as i16).collect()
+    }
+
+    fn max_error(a: &[i16], b: &[i16]) -> i32 {
+        a.iter().zip(b).map(|(&x, &y)| (x as i32 - y as i32).abs()).max().unwrap()
+    }
+
+    #[test]
+    fn test_ima_round_trip() {
+        let input = tone(1017, 2);
+        let encoded = encode_ima(&input, 2, 512);
+        assert_eq!(ima_samples_per_block(512, 2), 505);
+        assert_eq!(encoded.len(), 3 * 512);
+        let mut decoded = Vec::new();
+        for block in encoded.chunks(512) {
+            decode_ima_block(block, 2, &mut decoded).unwrap();
+        }
+        assert_eq!(decoded.len(), 3 * 505 * 2);
+        // Block headers hold the first sample exactly
+        assert_eq!(decoded[505 * 2 + 1], input[505 * 2 + 1]);
+        assert!(max_error(&input, &decoded) < 1200);
+    }
+
+    #[test]
+    fn test_ima_nibble_order() {
+        // Low nibbles first: +7 (step 7), +1 (step 9), -9 (step 8), +1
+        let block = [0, 0, 0, 0, 0x04, 0x0C, 0, 0];
+        let mut out = Vec::new();
+        decode_ima_block(&block, 1, &mut out).unwrap();
+        assert_eq!(out[..5], [0, 7, 8, -1, 0]);
+    }
+
+    #[test]
+    fn test_ms_round_trip() {
+        let input = tone(1000, 1);
+        let encoded = encode_ms(&input, 1, 256);
+        let per_block = ms_samples_per_block(256, 1);
+        assert_eq!(per_block, 500);
+        assert_eq!(encoded.len(), 2 * 256);
+        let mut decoded = Vec::new();
+        for block in encoded.chunks(256) {
+            decode_ms_block(block, 1, &MS_COEFFICIENTS, &mut decoded).unwrap();
+        }
+        assert_eq!(decoded.len(), 1000);
+        assert_eq!(decoded[..2], input[..2]);
+        assert!(max_error(&input, &decoded) < 600);
+        assert!(decode_ms_block(&[9, 0, 0, 0, 0, 0, 0], 1, &MS_COEFFICIENTS, &mut decoded).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! AIFF and AIFF-C decoding and encoding
+//!
+//! Decodes big-endian integer PCM of 1 to 32 bits, the little-endian `sowt`
+//! variant, 32- and 64-bit float, and G.711 µ-law and A-law. Encoding writes
+//! integer PCM as AIFF and float as AIFF-C `fl32`.
+
+use super::g711;
+use super::pcm::{requantize, Dither, SampleFormat};
+use super::AudioData;
+use crate::limits::Budget;
+use crate::utils::{read_u16_be, read_u32_be};
+
+/// Decode an 80-bit IEEE extended float, as used for the sample rate
+fn read_extended(b: &[u8]) -> f64 {
+    let exponent = (read_u16_be(b, 0) & 0x7FFF) as i32;
+    let mantissa = u64::from_be_bytes(b[2..10].try_into().unwrap_or([0; 8]));
+    if exponent == 0 && mantissa == 0 {
+        return 0.0;
+    }
+    let value = mantissa as f64 * 2f64.powi(exponent - 16383 - 63);
+    if b[0] & 0x80 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+fn write_extended(value: u32) -> [u8; 10] {
+    let mut out = [0u8; 10];
+    if value == 0 {
+        return out;
+    }
+    let shift = (value as u64).leading_zeros();
+    out[..2].copy_from_slice(&((16383 + 63 - shift) as u16).to_be_bytes());
+    out[2..].copy_from_slice(&((value as u64) << shift).to_be_bytes());
+    out
+}
+
+struct Common {
+    channels: u16,
+    frames: u32,
+    bits: u16,
+    sample_rate: f64,
+    compression: [u8; 4],
+}
+
+fn parse_common(chunk: &[u8], compressed: bool) -> Result<Common, String> {
+    if chunk.len() < 18 || (compressed && chunk.len() < 22) {
+        return Err("AIFF COMM chunk is too short".to_string());
+    }
+    let compression = if compressed { [chunk[18], chunk[19], chunk[20], chunk[21]] } else { *b"NONE" };
+    Ok(Common {
+        channels: read_u16_be(chunk, 0),
+        frames: read_u32_be(chunk, 2),
+        bits: read_u16_be(chunk, 6),
+        sample_rate: read_extended(&chunk[8..18]),
+        compression,
+    })
+}
+
+fn decode_samples(common: &Common, data: &[u8]) -> Result<Vec<f32>, String> {
+    let int = |s: &[u8], little: bool| {
+        let mut v = [0u8; 4];
+        for (i, &b) in s.iter().enumerate() {
+            v[if little { 4 - s.len() + i } else { i }] = b;
+        }
+        (if little { i32::from_le_bytes(v) } else { i32::from_be_bytes(v) }) as f32 / 2_147_483_648.0
+    };
+    let width = match &common.compression {
+        b"NONE" | b"twos" | b"sowt" | b"in24" | b"in32" | b"raw " => match common.bits {
+            1..=32 => (common.bits as usize).div_ceil(8),
+            bits => return Err(format!("Unsupported AIFF sample size {}", bits)),
+        },
+        b"fl32" | b"FL32" => 4,
+        b"fl64" | b"FL64" => 8,
+        b"ulaw" | b"ULAW" | b"alaw" | b"ALAW" => 1,
+        other => return Err(format!("Unsupported AIFF-C compression '{}'", String::from_utf8_lossy(other))),
+    };
+    let count = data.len() / width / common.channels as usize * common.channels as usize;
+    let count = count.min(common.frames as usize * common.channels as usize);
+    Budget::new().output(count * 4)?;
+    Ok(data[..count * width]
+        .chunks_exact(width)
+        .map(|s| match &common.compression {
+            b"sowt" => int(s, true),
+            b"raw " => (s[0] as f32 - 128.0) / 128.0,
+            b"fl32" | b"FL32" => f32::from_be_bytes([s[0], s[1], s[2], s[3]]),
+            b"fl64" | b"FL64" => f64::from_be_bytes(s.try_into().unwrap_or([0; 8])) as f32,
+            b"ulaw" | b"ULAW" => g711::mulaw_decode(s[0]) as f32 / 32768.0,
+            b"alaw" | b"ALAW" => g711::alaw_decode(s[0]) as f32 / 32768.0,
+            _ => int(s, false),
+        })
+        .collect())
+}
+
+/// Decode an AIFF or AIFF-C file to interleaved f32 samples, within the output
+/// byte limit set by `set_limits`
+pub fn decode_aiff(data: &[u8]) -> Result<AudioData, String> {
+    if data.len() < 12 || &data[0..4] != b"FORM" || !matches!(&data[8..12], b"AIFF" | b"AIFC") {
+        return Err("Not an AIFF file".to_string());
+    }
+    let compressed = &data[8..12] == b"AIFC";
+    let mut common = None;
+    let mut sound = None;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let size = read_u32_be(data, pos + 4) as usize;
+        let body = pos + 8;
+        let end = data.len().min(body.saturating_add(size));
+        match &data[pos..pos + 4] {
+            b"COMM" => common = Some(parse_common(&data[body..end], compressed)?),
+            b"SSND" if end >= body + 8 => {
+                let offset = read_u32_be(data, body) as usize;
+                sound = Some(data.get(body + 8 + offset..end).unwrap_or(&[]));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        pos = body.saturating_add(size).saturating_add(size & 1);
+    }
+    let common = common.ok_or("AIFF file has no COMM chunk")?;
+    if common.channels == 0 {
+        return Err("AIFF file has no channels".to_string());
+    }
+    let sample_rate = common.sample_rate.round();
+    if !(1.0..=u32::MAX as f64).contains(&sample_rate) {
+        return Err(format!("Invalid AIFF sample rate {}", common.sample_rate));
+    }
+    let samples = decode_samples(&common, sound.unwrap_or(&[]))?;
+    Ok(AudioData { sample_rate: sample_rate as u32, channels: common.channels, samples })
+}
+
+/// Encode interleaved f32 samples as AIFF (integer PCM) or AIFF-C (float)
+pub fn encode_aiff(audio: &AudioData, format: SampleFormat, dither: Dither) -> Result<Vec<u8>, String> {
+    let channels = audio.channels as usize;
+    if channels == 0 || !audio.samples.len().is_multiple_of(channels) {
+        return Err("Sample count is not a multiple of the channel count".to_string());
+    }
+    let float = format == SampleFormat::F32;
+    let mut sound = vec![0u8; 8];
+    if float {
+        for s in &audio.samples {
+            sound.extend_from_slice(&s.to_be_bytes());
+        }
+    } else {
+        // AIFF 8-bit samples are signed, unlike WAV
+        for q in requantize(&audio.samples, channels, format.bits(), dither) {
+            sound.extend_from_slice(&q.to_be_bytes()[4 - format.bytes()..]);
+        }
+    }
+
+    let mut comm = Vec::with_capacity(38);
+    comm.extend_from_slice(&audio.channels.to_be_bytes());
+    comm.extend_from_slice(&(audio.frames() as u32).to_be_bytes());
+    comm.extend_from_slice(&(format.bits() as u16).to_be_bytes());
+    comm.extend_from_slice(&write_extended(audio.sample_rate));
+    if float {
+        // Compression type and its Pascal-string name, padded to even
+        comm.extend_from_slice(b"fl32");
+        comm.extend_from_slice(b"\x0c32-bit float\0");
+    }
+
+    let mut out = b"FORM\0\0\0\0".to_vec();
+    out.extend_from_slice(if float { b"AIFC" } else { b"AIFF" });
+    if float {
+        // Format version chunk, required in AIFF-C
+        out.extend_from_slice(b"FVER\0\0\0\x04\xA2\x80\x51\x40");
+    }
+    for (id, body) in [(b"COMM", &comm), (b"SSND", &sound)] {
+        out.extend_from_slice(id);
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+    let form_size = out.len() as u32 - 8;
+    out[4..8].copy_from_slice(&form_size.to_be_bytes());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended() {
+        for rate in [8000, 44100, 48000, 96000, 1] {
+            assert_eq!(read_extended(&write_extended(rate)), rate as f64);
+        }
+        // 44100 Hz as written by classic Mac tools
+        assert_eq!(read_extended(&[0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]), 44100.0);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let audio = AudioData { sample_rate: 22050, channels: 2, samples: vec![0.5, -0.5, 0.25, -1.0, 0.0, 0.75] };
+        for format in [SampleFormat::U8, SampleFormat::I16, SampleFormat::I24, SampleFormat::F32] {
+            let file = encode_aiff(&audio, format, Dither::None).unwrap();
+            assert_eq!(decode_aiff(&file).unwrap(), audio, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_sowt() {
+        let mut comm = vec![0, 1, 0, 0, 0, 2, 0, 16];
+        comm.extend_from_slice(&write_extended(8000));
+        comm.extend_from_slice(b"sowt\0\0");
+        let mut file = b"FORM\0\0\0\0AIFCCOMM\0\0\0\x18".to_vec();
+        file.extend_from_slice(&comm);
+        file.extend_from_slice(b"SSND\0\0\0\x0c\0\0\0\0\0\0\0\0\x00\x40\x00\xC0");
+        let audio = decode_aiff(&file).unwrap();
+        assert_eq!((audio.sample_rate, audio.channels), (8000, 1));
+        assert_eq!(audio.samples, [0.5, -0.5]);
+        assert!(decode_aiff(b"FORM\0\0\0\x04AIFF").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Radix-2 complex FFT
+
+use std::f64::consts::PI;
+
+/// A complex number as (real, imaginary)
+pub type Complex = (f32, f32);
+
+/// `(cos, sin)` of an angle
+pub fn rotation(angle: f64) -> Complex {
+    (angle.cos() as f32, angle.sin() as f32)
+}
+
+pub fn mul(a: Complex, b: Complex) -> Complex {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+/// Precomputed roots of unity for transforms of up to `len` points
+pub struct Fft {
+    roots: Vec<Complex>,
+}
+
+impl Fft {
+    /// `len` is a power of two
+    pub fn new(len: usize) -> Fft {
+        Fft { roots: (0..len / 2).map(|k| rotation(-2.0 * PI * k as f64 / len as f64)).collect() }
+    }
+
+    /// In-place forward FFT of `data.len()` points, a power of two no larger
+    /// than the planned size
+    pub fn forward(&self, data: &mut [Complex]) {
+        let len = data.len();
+        let bits = len.trailing_zeros();
+        if bits == 0 {
+            return;
+        }
+        for i in 0..len {
+            let j = i.reverse_bits() >> (usize::BITS - bits);
+            if i < j {
+                data.swap(i, j);
+            }
+        }
+        let mut size = 2;
+        while size <= len {
+            let stride = 2 * self.roots.len() / size;
+            for start in (0..len).step_by(size) {
+                for k in 0..size / 2 {
+                    let t = mul(data[start + k + size / 2], self.roots[k * stride]);
+                    let u = data[start + k];
+                    data[start + k] = (u.0 + t.0, u.1 + t.1);
+                    data[start + k + size / 2] = (u.0 - t.0, u.1 - t.1);
+                }
+            }
+            size *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_direct_dft() {
+        let fft = Fft::new(64);
+        for len in [1, 2, 16, 64] {
+            let input: Vec<Complex> = (0..len).map(|i| (((i * 7) % 5) as f32 - 2.0, ((i * 3) % 4) as f32)).collect();
+            let mut data = input.clone();
+            fft.forward(&mut data);
+            for (k, &(re, im)) in data.iter().enumerate() {
+                let expected = input.iter().enumerate().fold((0.0, 0.0), |acc, (n, &x)| {
+                    let w = rotation(-2.0 * PI * (k * n) as f64 / len as f64);
+                    let p = mul(x, w);
+                    (acc.0 + p.0, acc.1 + p.1)
+                });
+                assert!((re - expected.0).abs() < 1e-3 && (im - expected.1).abs() < 1e-3);
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! G.711 µ-law and A-law companding
+//!
+//! Each 8-bit code holds a sign, a 3-bit segment and a 4-bit step, giving
+//! 14 (µ-law) or 13 (A-law) bits of logarithmically spaced amplitude.
+
+use super::pcm::{requantize, Dither};
+
+const MULAW_BIAS: i32 = 0x84;
+const MULAW_CLIP: i32 = 8159;
+
+pub fn mulaw_decode(code: u8) -> i16 {
+    let u = !code;
+    let t = ((((u & 0x0F) as i32) << 3) + MULAW_BIAS) << ((u & 0x70) >> 4);
+    (if u & 0x80 != 0 { MULAW_BIAS - t } else { t - MULAW_BIAS }) as i16
+}
+
+pub fn mulaw_encode(sample: i16) -> u8 {
+    // µ-law codes 14 bits; negative codes keep the sign bit clear
+    let mut value = sample as i32 >> 2;
+    let mask = if value >= 0 {
+        0xFF
+    } else {
+        value = -value;
+        0x7F
+    };
+    value = value.min(MULAW_CLIP) + (MULAW_BIAS >> 2);
+    let segment = (32 - value.leading_zeros() as i32 - 6).max(0);
+    if segment > 7 {
+        return 0x7F ^ mask;
+    }
+    let step = (value >> (segment + 1)) & 0x0F;
+    ((segment << 4) as u8 | step as u8) ^ mask
+}
+
+pub fn alaw_decode(code: u8) -> i16 {
+    let a = code ^ 0x55;
+    let mut t = ((a & 0x0F) as i32) << 4;
+    let segment = (a & 0x70) >> 4;
+    t += if segment == 0 { 8 } else { 0x108 };
+    if segment > 1 {
+        t <<= segment - 1;
+    }
+    (if a & 0x80 != 0 { t } else { -t }) as i16
+}
+
+pub fn alaw_encode(sample: i16) -> u8 {
+    // A-law codes 13 bits, with the sign folded to one's complement
+    let mut value = sample as i32 >> 3;
+    let mask = if value >= 0 {
+        0xD5
+    } else {
+        value = -value - 1;
+        0x55
+    };
+    let segment = (32 - value.leading_zeros() as i32 - 5).max(0);
+    let step = if segment < 2 { value >> 1 } else { value >> segment } & 0x0F;
+    ((segment << 4) as u8 | step as u8) ^ mask
+}
+
+/// Decode µ-law codes to f32 samples
+pub fn decode_mulaw(codes: &[u8]) -> Vec<f32> {
+    codes.iter().map(|&c| mulaw_decode(c) as f32 / 32768.0).collect()
+}
+
+/// Encode f32 samples as µ-law codes
+pub fn encode_mulaw(samples: &[f32]) -> Vec<u8> {
+    requantize(samples, 1, 16, Dither::None).into_iter().map(|s| mulaw_encode(s as i16)).collect()
+}
+
+/// Decode A-law codes to f32 samples
+pub fn decode_alaw(codes: &[u8]) -> Vec<f32> {
+    codes.iter().map(|&c| alaw_decode(c) as f32 / 32768.0).collect()
+}
+
+/// Encode f32 samples as A-law codes
+pub fn encode_alaw(samples: &[f32]) -> Vec<u8> {
+    requantize(samples, 1, 16, Dither::None).into_iter().map(|s| alaw_encode(s as i16)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_codes() {
+        // Silence, and the extremes of each law
+        assert_eq!((mulaw_encode(0), mulaw_decode(0xFF)), (0xFF, 0));
+        assert_eq!((mulaw_encode(i16::MAX), mulaw_encode(i16::MIN)), (0x80, 0x00));
+        assert_eq!((mulaw_decode(0x80), mulaw_decode(0x00)), (32124, -32124));
+        assert_eq!((alaw_encode(0), alaw_decode(0xD5)), (0xD5, 8));
+        assert_eq!((alaw_encode(i16::MAX), alaw_encode(i16::MIN)), (0xAA, 0x2A));
+        assert_eq!((alaw_decode(0xAA), alaw_decode(0x2A)), (32256, -32256));
+    }
+
+    #[test]
+    fn test_codes_are_stable() {
+        // Every code decodes to a value that encodes back to it, except
+        // µ-law's negative zero
+        for code in 0..=255u8 {
+            assert_eq!(alaw_encode(alaw_decode(code)), code);
+            if code != 0x7F {
+                assert_eq!(mulaw_encode(mulaw_decode(code)), code);
+            }
+        }
+        let samples = [0.5, -0.25, 0.001];
+        for (a, b) in samples.iter().zip(decode_mulaw(&encode_mulaw(&samples))) {
+            assert!((a - b).abs() < 0.04 * a.abs() + 1e-3);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Integrated loudness per ITU-R BS.1770-4 / EBU R128, and normalization
+//!
+//! Samples are K-weighted, measured in 400 ms blocks overlapping by 75%,
+//! and gated at -70 LUFS and then 10 LU below the ungated mean.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// ReplayGain 2.0 reference level
+pub const REPLAY_GAIN_REFERENCE: f64 = -18.0;
+
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+
+/// Loudness measurement of a recording
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Loudness {
+    /// Integrated loudness in LUFS; -Infinity for silence or audio shorter
+    /// than one 400 ms block
+    pub integrated: f64,
+    /// Largest absolute sample value
+    pub peak: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Loudness {
+    /// ReplayGain 2.0 track gain in dB (toward -18 LUFS)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter = replayGain))]
+    pub fn replay_gain(&self) -> f64 {
+        REPLAY_GAIN_REFERENCE - self.integrated
+    }
+}
+
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        // Transposed direct form II
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The two K-weighting stages (high shelf, then high pass) for a sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate as f64;
+    let k = (std::f64::consts::PI * 1681.974450955533 / rate).tan();
+    let q = 0.7071752369554196;
+    let vh = 10f64.powf(3.999843853973347 / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        state: [0.0; 2],
+    };
+    let k = (std::f64::consts::PI * 38.13547087602444 / rate).tan();
+    let q = 0.5003270373238773;
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad { b: [1.0, -2.0, 1.0], a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0], state: [0.0; 2] };
+    [shelf, high_pass]
+}
+
+/// Channel weights; 5.1 in WAV order weights surrounds up and skips the LFE
+fn channel_weights(channels: usize) -> Vec<f64> {
+    if channels == 6 {
+        vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41]
+    } else {
+        vec![1.0; channels]
+    }
+}
+
+fn to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Measure integrated loudness and sample peak
+pub fn measure_loudness(samples: &[f32], channels: usize, sample_rate: u32) -> Result<Loudness, String> {
+    if channels == 0 || !samples.len().is_multiple_of(channels) {
+        return Err("Sample count is not a multiple of the channel count".to_string());
+    }
+    if sample_rate == 0 {
+        return Err("Sample rate must be positive".to_string());
+    }
+    let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+
+    // Weighted energy per 100 ms step; a block spans four steps
+    let step = (sample_rate as usize).div_ceil(10);
+    let weights = channel_weights(channels);
+    let mut filters: Vec<[Biquad; 2]> = (0..channels).map(|_| k_weighting(sample_rate)).collect();
+    let mut steps = Vec::new();
+    for chunk in samples.chunks(step * channels) {
+        let mut energy = 0.0;
+        for frame in chunk.chunks_exact(channels) {
+            for ((s, [shelf, high_pass]), w) in frame.iter().zip(filters.iter_mut()).zip(&weights) {
+                let y = high_pass.process(shelf.process(*s as f64));
+                energy += w * y * y;
+            }
+        }
+        if chunk.len() == step * channels {
+            steps.push(energy);
+        }
+    }
+    let blocks: Vec<f64> = steps.windows(4).map(|w| w.iter().sum::<f64>() / (4 * step) as f64).collect();
+
+    let gated_mean = |threshold: f64| {
+        let kept: Vec<f64> = blocks.iter().copied().filter(|&p| to_lufs(p) > threshold).collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+    let integrated = match gated_mean(ABSOLUTE_GATE) {
+        Some(mean) => gated_mean(to_lufs(mean) + RELATIVE_GATE).map_or(f64::NEG_INFINITY, to_lufs),
+        None => f64::NEG_INFINITY,
+    };
+    Ok(Loudness { integrated, peak })
+}
+
+/// Gain in dB that brings audio to `target` LUFS without its peak
+/// exceeding `peak_limit` dBFS
+pub fn normalization_gain(loudness: &Loudness, target: f64, peak_limit: f64) -> f64 {
+    if !loudness.integrated.is_finite() {
+        return 0.0;
+    }
+    let gain = target - loudness.integrated;
+    if loudness.peak > 0.0 {
+        gain.min(peak_limit - 20.0 * (loudness.peak as f64).log10())
+    } else {
+        gain
+    }
+}
+
+/// Normalize to `target` LUFS, limiting the gain so the sample peak stays
+/// at or below `peak_limit` dBFS; silence is returned unchanged
+pub fn normalize_loudness(samples: &[f32], channels: usize, sample_rate: u32, target: f64, peak_limit: f64) -> Result<Vec<f32>, String> {
+    let loudness = measure_loudness(samples, channels, sample_rate)?;
+    let gain = 10f64.powf(normalization_gain(&loudness, target, peak_limit) / 20.0) as f32;
+    Ok(samples.iter().map(|s| s * gain).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(rate: u32, seconds: f32, amplitude: f32, channels: usize) -> Vec<f32> {
+        let frames = (rate as f32 * seconds) as usize;
+        (0..frames * channels).map(|i| amplitude * (2.0 * std::f32::consts::PI * 1000.0 * (i / channels) as f32 / rate as f32).sin()).collect()
+    }
+
+    #[test]
+    fn test_reference_sine() {
+        // EBU Tech 3341 case 1: a -23 dBFS 1 kHz stereo sine reads -23 LUFS
+        let amplitude = 10f32.powf(-23.0 / 20.0);
+        for rate in [48000, 44100] {
+            let loudness = measure_loudness(&sine(rate, 20.0, amplitude, 2), 2, rate).unwrap();
+            assert!((loudness.integrated + 23.0).abs() < 0.1, "{} Hz: {}", rate, loudness.integrated);
+            assert!((loudness.replay_gain() - 5.0).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_gating_and_normalization() {
+        // Silence after the tone is gated out rather than averaged in (which
+        // would cost 3 dB); only the blocks straddling the cut count
+        let mut samples = sine(48000, 5.0, 0.1, 1);
+        let tone = measure_loudness(&samples, 1, 48000).unwrap().integrated;
+        samples.extend(vec![0.0; 48000 * 5]);
+        assert!((measure_loudness(&samples, 1, 48000).unwrap().integrated - tone).abs() < 0.2);
+        assert_eq!(measure_loudness(&[0.0; 4800], 1, 48000).unwrap().integrated, f64::NEG_INFINITY);
+
+        let before = measure_loudness(&samples, 1, 48000).unwrap().integrated;
+        let louder = normalize_loudness(&samples, 1, 48000, before + 6.0, 0.0).unwrap();
+        assert!((measure_loudness(&louder, 1, 48000).unwrap().integrated - before - 6.0).abs() < 0.01);
+        // The peak limit wins over the loudness target
+        let limited = normalize_loudness(&samples, 1, 48000, 0.0, -6.0).unwrap();
+        assert!((limited.iter().fold(0.0f32, |m, s| m.max(s.abs())) - 0.5012).abs() < 1e-3);
+    }
+}
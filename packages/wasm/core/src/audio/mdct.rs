@@ -0,0 +1,76 @@
+//! Inverse MDCT through a DCT-IV computed with a half-size complex FFT
+
+use super::fft::{mul, rotation, Complex, Fft};
+use std::f64::consts::PI;
+
+/// Precomputed twiddles for one block size
+pub struct Imdct {
+    n: usize,
+    /// Pre-rotation, one per FFT input
+    pre: Vec<Complex>,
+    /// Post-rotation, one per FFT output
+    post: Vec<Complex>,
+    fft: Fft,
+}
+
+impl Imdct {
+    /// `n` is the block size, a power of two of at least 8
+    pub fn new(n: usize) -> Imdct {
+        let m = n / 2;
+        let h = m / 2;
+        Imdct {
+            n,
+            pre: (0..h).map(|p| rotation(-PI * (4 * p + 1) as f64 / (4 * m) as f64)).collect(),
+            post: (0..h).map(|k| rotation(-PI * k as f64 / m as f64)).collect(),
+            fft: Fft::new(h),
+        }
+    }
+
+    /// DCT-IV of `x` in place: `x[k] = sum x[j] cos(pi/M (j+1/2)(k+1/2))`
+    fn dct_iv(&self, x: &mut [f32]) {
+        let m = x.len();
+        let mut v: Vec<Complex> = (0..m / 2).map(|p| mul((x[2 * p], x[m - 1 - 2 * p]), self.pre[p])).collect();
+        self.fft.forward(&mut v);
+        for (k, &c) in v.iter().enumerate() {
+            let c = mul(c, self.post[k]);
+            x[2 * k] = c.0;
+            x[m - 1 - 2 * k] = -c.1;
+        }
+    }
+
+    /// Transform `n / 2` coefficients into `n` time-domain samples
+    pub fn inverse(&self, spectrum: &[f32], out: &mut [f32]) {
+        let n = self.n;
+        let (n4, n34) = (n / 4, n - n / 4);
+        let mut u = spectrum[..n / 2].to_vec();
+        self.dct_iv(&mut u);
+        out[..n4].copy_from_slice(&u[n4..n / 2]);
+        for i in n4..n34 {
+            out[i] = -u[n34 - i - 1];
+        }
+        for i in n34..n {
+            out[i] = -u[i - n34];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_direct_imdct() {
+        for n in [8, 64, 256] {
+            let spectrum: Vec<f32> = (0..n / 2).map(|k| ((k * 37 % 11) as f32 - 5.0) / 3.0).collect();
+            let mut out = vec![0.0; n];
+            Imdct::new(n).inverse(&spectrum, &mut out);
+            for (i, &y) in out.iter().enumerate() {
+                // y[i] = sum X[k] cos(2pi/N (i + 1/2 + N/4)(k + 1/2))
+                let expected: f64 = spectrum.iter().enumerate().map(|(k, &x)| {
+                    x as f64 * (2.0 * PI / n as f64 * (i as f64 + 0.5 + n as f64 / 4.0) * (k as f64 + 0.5)).cos()
+                }).sum();
+                assert!((y as f64 - expected).abs() < 1e-3, "n={} i={} {} vs {}", n, i, y, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Channel mixing and layout conversion for interleaved samples
+//!
+//! Layouts follow the WAV channel order: front left, front right, center,
+//! LFE, back left, back right.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+fn frames(samples: &[f32], channels: usize) -> Result<usize, String> {
+    if channels == 0 || !samples.len().is_multiple_of(channels) {
+        return Err("Sample count is not a multiple of the channel count".to_string());
+    }
+    Ok(samples.len() / channels)
+}
+
+/// Mix through a row-major `outputs × channels` gain matrix
+pub fn mix_matrix(samples: &[f32], channels: usize, matrix: &[f32], outputs: usize) -> Result<Vec<f32>, String> {
+    let frames = frames(samples, channels)?;
+    if matrix.len() != outputs * channels {
+        return Err(format!("Mix matrix needs {} gains, got {}", outputs * channels, matrix.len()));
+    }
+    let mut out = Vec::with_capacity(frames * outputs);
+    for frame in samples.chunks_exact(channels) {
+        out.extend(matrix.chunks_exact(channels).map(|row| row.iter().zip(frame).map(|(g, s)| g * s).sum::<f32>()));
+    }
+    Ok(out)
+}
+
+/// Gain matrix converting `from` channels to `to`
+///
+/// Downmixes to mono average the channels and mono is copied to every
+/// output. 5.1 folds to stereo per ITU-R BS.775, dropping the LFE and
+/// scaled so a full-scale signal cannot clip. Other conversions keep the
+/// shared leading channels and silence the rest.
+fn remix_matrix(from: usize, to: usize) -> Vec<f32> {
+    if to == 1 {
+        return vec![1.0 / from as f32; from];
+    }
+    if from == 1 {
+        return vec![1.0; to];
+    }
+    if from == 6 && to == 2 {
+        let scale = 1.0 / (1.0 + 2.0 * FRAC_1_SQRT_2);
+        // Center and each surround join at -3 dB
+        let side = FRAC_1_SQRT_2 * scale;
+        return vec![scale, 0.0, side, 0.0, side, 0.0, 0.0, scale, side, 0.0, 0.0, side];
+    }
+    let mut matrix = vec![0.0; to * from];
+    for i in 0..to.min(from) {
+        matrix[i * from + i] = 1.0;
+    }
+    matrix
+}
+
+/// Convert between channel counts
+pub fn remix(samples: &[f32], from: usize, to: usize) -> Result<Vec<f32>, String> {
+    if to == 0 {
+        return Err("Target channel count must be positive".to_string());
+    }
+    if from == to {
+        frames(samples, from)?;
+        return Ok(samples.to_vec());
+    }
+    mix_matrix(samples, from, &remix_matrix(from, to), to)
+}
+
+/// Rearrange channels: output channel `i` is input channel `order[i]`
+///
+/// Channels may be repeated or dropped.
+pub fn reorder_channels(samples: &[f32], channels: usize, order: &[usize]) -> Result<Vec<f32>, String> {
+    let frames = frames(samples, channels)?;
+    if let Some(&bad) = order.iter().find(|&&c| c >= channels) {
+        return Err(format!("Channel {} does not exist", bad));
+    }
+    let mut out = Vec::with_capacity(frames * order.len());
+    for frame in samples.chunks_exact(channels) {
+        out.extend(order.iter().map(|&c| frame[c]));
+    }
+    Ok(out)
+}
+
+/// Scale each channel by its own linear gain, in place
+pub fn apply_gain(samples: &mut [f32], channels: usize, gains: &[f32]) -> Result<(), String> {
+    frames(samples, channels)?;
+    if gains.len() != channels {
+        return Err(format!("Expected {} channel gains, got {}", channels, gains.len()));
+    }
+    for frame in samples.chunks_exact_mut(channels) {
+        for (s, g) in frame.iter_mut().zip(gains) {
+            *s *= g;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remix() {
+        let stereo = [0.5, -0.5, 1.0, 0.0];
+        assert_eq!(remix(&stereo, 2, 1).unwrap(), [0.0, 0.5]);
+        assert_eq!(remix(&[0.25, 1.0], 1, 2).unwrap(), [0.25, 0.25, 1.0, 1.0]);
+        assert_eq!(remix(&stereo, 2, 3).unwrap(), [0.5, -0.5, 0.0, 1.0, 0.0, 0.0]);
+        assert!(remix(&stereo, 3, 1).is_err());
+
+        // Full-scale on every 5.1 channel stays within range
+        let surround = remix(&[1.0; 6], 6, 2).unwrap();
+        assert!(surround.iter().all(|&s| (s - 1.0).abs() < 1e-6));
+        let center = remix(&[0.0, 0.0, 1.0, 1.0, 0.0, 0.0], 6, 2).unwrap();
+        assert!((center[0] - center[1]).abs() < 1e-6 && (center[0] - 0.2929).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_reorder_and_gain() {
+        let samples = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        assert_eq!(reorder_channels(&samples, 3, &[2, 0]).unwrap(), [3.0, 1.0, 6.0, 4.0]);
+        assert!(reorder_channels(&samples, 3, &[3]).is_err());
+
+        let mut samples = samples.to_vec();
+        apply_gain(&mut samples, 2, &[2.0, 0.5]).unwrap();
+        assert_eq!(samples, [2.0, 1.0, 6.0, 2.0, 10.0, 3.0]);
+        assert!(apply_gain(&mut samples, 2, &[1.0]).is_err());
+        assert!(mix_matrix(&samples, 2, &[1.0; 3], 2).is_err());
+    }
+}
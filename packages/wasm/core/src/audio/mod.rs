@@ -0,0 +1,213 @@
+//! Audio decoding, encoding and processing on interleaved f32 samples
+
+pub mod aac;
+pub mod adpcm;
+pub mod aiff;
+pub(crate) mod fft;
+pub mod g711;
+pub mod loudness;
+pub(crate) mod mdct;
+pub mod mix;
+pub mod ogg;
+pub mod pcm;
+pub mod spectrogram;
+pub mod vorbis;
+pub mod wav;
+pub mod waveform;
+
+pub use aac::{decode_aac, decode_adts, decode_m4a, parse_audio_specific_config, AacConfig, AacDecoder};
+pub use adpcm::AdpcmFormat;
+pub use aiff::{decode_aiff, encode_aiff};
+pub use g711::{decode_alaw, decode_mulaw, encode_alaw, encode_mulaw};
+pub use loudness::{measure_loudness, normalize_loudness, Loudness};
+pub use mix::{apply_gain, mix_matrix, remix, reorder_channels};
+pub use ogg::{read_packets, read_pages, OggPacket, OggPage};
+pub use pcm::{from_pcm, quantize, to_pcm, Dither, SampleFormat};
+pub use spectrogram::{spectrogram, Colormap, Spectrogram};
+pub use vorbis::{decode_vorbis, VorbisDecoder, VorbisInfo};
+pub use wav::{decode_wav, encode_adpcm_wav, encode_wav, read_wav, WavFile, WavFormat};
+pub use waveform::waveform_peaks;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Decoded audio
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioData {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Interleaved samples, nominally in [-1, 1]
+    pub samples: Vec<f32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AudioData {
+    /// Number of sample frames (samples per channel)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    /// Duration in seconds
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn duration(&self) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.frames() as f64 / self.sample_rate as f64
+    }
+}
+
+/// Decode a WAV file to interleaved f32 samples
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeWav)]
+pub fn decode_wav_js(data: &[u8]) -> Result<AudioData, JsError> {
+    decode_wav(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode interleaved f32 samples as a PCM or float WAV file
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeWav)]
+pub fn encode_wav_js(samples: Vec<f32>, sample_rate: u32, channels: u16, format: SampleFormat, dither: Dither) -> Result<Vec<u8>, JsError> {
+    encode_wav(&AudioData { sample_rate, channels, samples }, format, dither).map_err(|e| JsError::new(&e))
+}
+
+/// Convert f32 samples to packed little-endian PCM bytes
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = samplesToPcm)]
+pub fn to_pcm_js(samples: &[f32], channels: u32, format: SampleFormat, dither: Dither) -> Vec<u8> {
+    to_pcm(samples, channels as usize, format, dither)
+}
+
+/// Convert packed little-endian PCM bytes to f32 samples
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = pcmToSamples)]
+pub fn from_pcm_js(data: &[u8], format: SampleFormat) -> Vec<f32> {
+    from_pcm(data, format)
+}
+
+/// Decode an AIFF or AIFF-C file to interleaved f32 samples
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeAiff)]
+pub fn decode_aiff_js(data: &[u8]) -> Result<AudioData, JsError> {
+    decode_aiff(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode interleaved f32 samples as AIFF, or AIFF-C for float
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeAiff)]
+pub fn encode_aiff_js(samples: Vec<f32>, sample_rate: u32, channels: u16, format: SampleFormat, dither: Dither) -> Result<Vec<u8>, JsError> {
+    encode_aiff(&AudioData { sample_rate, channels, samples }, format, dither).map_err(|e| JsError::new(&e))
+}
+
+/// Encode interleaved f32 samples as an IMA or MS ADPCM WAV file
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeAdpcmWav)]
+pub fn encode_adpcm_wav_js(samples: Vec<f32>, sample_rate: u32, channels: u16, format: AdpcmFormat) -> Result<Vec<u8>, JsError> {
+    encode_adpcm_wav(&AudioData { sample_rate, channels, samples }, format).map_err(|e| JsError::new(&e))
+}
+
+/// Convert interleaved samples between channel counts
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = remixChannels)]
+pub fn remix_js(samples: &[f32], from: u32, to: u32) -> Result<Vec<f32>, JsError> {
+    remix(samples, from as usize, to as usize).map_err(|e| JsError::new(&e))
+}
+
+/// Mix channels through a row-major outputs × channels gain matrix
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = mixChannels)]
+pub fn mix_matrix_js(samples: &[f32], channels: u32, matrix: &[f32], outputs: u32) -> Result<Vec<f32>, JsError> {
+    mix_matrix(samples, channels as usize, matrix, outputs as usize).map_err(|e| JsError::new(&e))
+}
+
+/// Rearrange channels: output channel i is input channel order[i]
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = reorderChannels)]
+pub fn reorder_channels_js(samples: &[f32], channels: u32, order: &[u32]) -> Result<Vec<f32>, JsError> {
+    let order: Vec<usize> = order.iter().map(|&c| c as usize).collect();
+    reorder_channels(samples, channels as usize, &order).map_err(|e| JsError::new(&e))
+}
+
+/// Scale each channel by its own linear gain
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = applyChannelGain)]
+pub fn apply_gain_js(samples: &[f32], channels: u32, gains: &[f32]) -> Result<Vec<f32>, JsError> {
+    let mut out = samples.to_vec();
+    apply_gain(&mut out, channels as usize, gains).map_err(|e| JsError::new(&e))?;
+    Ok(out)
+}
+
+/// Min/max pairs per bucket and channel, for drawing waveforms
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = waveformPeaks)]
+pub fn waveform_peaks_js(samples: &[f32], channels: u32, buckets: u32) -> Result<Vec<f32>, JsError> {
+    waveform_peaks(samples, channels as usize, buckets as usize).map_err(|e| JsError::new(&e))
+}
+
+/// Render mono samples as an RGBA spectrogram image
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = spectrogram)]
+pub fn spectrogram_js(samples: &[f32], sample_rate: u32, fft_size: u32, hop: u32, colormap: Colormap) -> Result<Spectrogram, JsError> {
+    spectrogram(samples, sample_rate, fft_size as usize, hop as usize, colormap).map_err(|e| JsError::new(&e))
+}
+
+/// Measure integrated loudness (EBU R128) and sample peak
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = measureLoudness)]
+pub fn measure_loudness_js(samples: &[f32], channels: u32, sample_rate: u32) -> Result<Loudness, JsError> {
+    measure_loudness(samples, channels as usize, sample_rate).map_err(|e| JsError::new(&e))
+}
+
+/// Normalize to a target loudness in LUFS, keeping the peak at or below
+/// `peakLimit` dBFS
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = normalizeLoudness)]
+pub fn normalize_loudness_js(samples: &[f32], channels: u32, sample_rate: u32, target: f64, peak_limit: f64) -> Result<Vec<f32>, JsError> {
+    normalize_loudness(samples, channels as usize, sample_rate, target, peak_limit).map_err(|e| JsError::new(&e))
+}
+
+/// Decode G.711 µ-law codes to f32 samples
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = mulawDecode)]
+pub fn mulaw_decode_js(codes: &[u8]) -> Vec<f32> {
+    decode_mulaw(codes)
+}
+
+/// Encode f32 samples as G.711 µ-law codes
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = mulawEncode)]
+pub fn mulaw_encode_js(samples: &[f32]) -> Vec<u8> {
+    encode_mulaw(samples)
+}
+
+/// Decode G.711 A-law codes to f32 samples
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = alawDecode)]
+pub fn alaw_decode_js(codes: &[u8]) -> Vec<f32> {
+    decode_alaw(codes)
+}
+
+/// Encode f32 samples as G.711 A-law codes
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = alawEncode)]
+pub fn alaw_encode_js(samples: &[f32]) -> Vec<u8> {
+    encode_alaw(samples)
+}
+
+/// Decode AAC-LC audio, an ADTS stream (`.aac`) or the first AAC track of
+/// an MP4/M4A file, to interleaved f32 samples
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeAac)]
+pub fn decode_aac_js(data: &[u8]) -> Result<AudioData, JsError> {
+    let audio = if data.get(4..8) == Some(b"ftyp") { decode_m4a(data) } else { decode_adts(data) };
+    audio.map_err(|e| JsError::new(&e))
+}
+
+/// Decode an Ogg Vorbis file to interleaved f32 samples
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeVorbis)]
+pub fn decode_vorbis_js(data: &[u8]) -> Result<AudioData, JsError> {
+    decode_vorbis(data).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,204 @@
+//! Ogg container (RFC 3533) - page parsing and packet reassembly
+
+use crate::utils::read_u32_le;
+
+const CAPTURE: &[u8; 4] = b"OggS";
+const HEADER_LEN: usize = 27;
+
+/// Page flags
+pub const FLAG_CONTINUED: u8 = 0x01;
+pub const FLAG_FIRST: u8 = 0x02;
+pub const FLAG_LAST: u8 = 0x04;
+
+/// Page CRC (MSB-first, polynomial 0x04C11DB7, no inversion)
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = (i as u32) << 24;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 0x8000_0000 != 0 { (c << 1) ^ 0x04C1_1DB7 } else { c << 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+fn page_crc(page: &[u8]) -> u32 {
+    page.iter().enumerate().fold(0u32, |c, (i, &b)| {
+        // The checksum field itself is summed as zeros
+        let b = if (22..26).contains(&i) { 0 } else { b };
+        (c << 8) ^ CRC_TABLE[((c >> 24) ^ b as u32) as usize]
+    })
+}
+
+/// One Ogg page
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OggPage<'a> {
+    pub flags: u8,
+    /// Codec-defined position at the end of the last packet completed on
+    /// this page, or -1 when no packet ends here
+    pub granule: i64,
+    pub serial: u32,
+    pub sequence: u32,
+    /// Segment lengths; a segment shorter than 255 ends a packet
+    pub lacing: &'a [u8],
+    pub body: &'a [u8],
+}
+
+/// Parse the page at `pos`, returning it and its total length
+fn parse_page(data: &[u8], pos: usize) -> Option<(OggPage<'_>, usize)> {
+    let header = data.get(pos..pos + HEADER_LEN)?;
+    if &header[..4] != CAPTURE || header[4] != 0 {
+        return None;
+    }
+    let segments = header[26] as usize;
+    let lacing = data.get(pos + HEADER_LEN..pos + HEADER_LEN + segments)?;
+    let body_len: usize = lacing.iter().map(|&l| l as usize).sum();
+    let len = HEADER_LEN + segments + body_len;
+    let page = data.get(pos..pos + len)?;
+    if page_crc(page) != read_u32_le(header, 22) {
+        return None;
+    }
+    let page = OggPage {
+        flags: header[5],
+        granule: i64::from_le_bytes(header[6..14].try_into().ok()?),
+        serial: read_u32_le(header, 14),
+        sequence: read_u32_le(header, 18),
+        lacing,
+        body: &page[HEADER_LEN + segments..],
+    };
+    Some((page, len))
+}
+
+/// Read every page with a valid checksum, resynchronizing past damage
+pub fn read_pages(data: &[u8]) -> Vec<OggPage<'_>> {
+    let mut pages = Vec::new();
+    let mut pos = 0;
+    while pos + HEADER_LEN <= data.len() {
+        match parse_page(data, pos) {
+            Some((page, len)) => {
+                pages.push(page);
+                pos += len;
+            }
+            None => {
+                pos = match data[pos + 1..].windows(4).position(|w| w == CAPTURE) {
+                    Some(skip) => pos + 1 + skip,
+                    None => break,
+                };
+            }
+        }
+    }
+    pages
+}
+
+/// A packet reassembled from one logical stream
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OggPacket {
+    pub data: Vec<u8>,
+    /// Granule position of the page the packet ends on, if it is the last
+    /// packet completed there
+    pub granule: Option<i64>,
+    /// The packet ends on the stream's final page
+    pub last: bool,
+}
+
+/// Reassemble the packets of the logical stream with serial number `serial`
+///
+/// A packet left incomplete by a missing page is dropped.
+pub fn read_packets(pages: &[OggPage], serial: u32) -> Vec<OggPacket> {
+    let mut packets = Vec::new();
+    let mut partial: Vec<u8> = Vec::new();
+    let mut expected = None;
+    for page in pages.iter().filter(|p| p.serial == serial) {
+        // A gap in sequence numbers or an unexpected continuation loses the
+        // packet in progress
+        let in_sequence = expected.is_none_or(|s| s == page.sequence);
+        if !in_sequence || page.flags & FLAG_CONTINUED == 0 {
+            partial.clear();
+        }
+        let skip_first = page.flags & FLAG_CONTINUED != 0 && (!in_sequence || expected.is_none());
+        expected = Some(page.sequence.wrapping_add(1));
+
+        let ends: Vec<usize> = page.lacing.iter().enumerate().filter(|(_, &l)| l < 255).map(|(i, _)| i).collect();
+        let mut offset = 0;
+        let mut discard = skip_first;
+        for (i, &len) in page.lacing.iter().enumerate() {
+            if !discard {
+                partial.extend_from_slice(&page.body[offset..offset + len as usize]);
+            }
+            offset += len as usize;
+            if len < 255 {
+                if !discard {
+                    let last_here = ends.last() == Some(&i);
+                    packets.push(OggPacket {
+                        data: std::mem::take(&mut partial),
+                        granule: (last_here && page.granule != -1).then_some(page.granule),
+                        last: last_here && page.flags & FLAG_LAST != 0,
+                    });
+                }
+                discard = false;
+            }
+        }
+        if discard {
+            partial.clear();
+        }
+    }
+    packets
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a page with a valid checksum from segment lengths and body
+    pub(crate) fn page(flags: u8, granule: i64, serial: u32, sequence: u32, lacing: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut p = CAPTURE.to_vec();
+        p.extend_from_slice(&[0, flags]);
+        p.extend_from_slice(&granule.to_le_bytes());
+        p.extend_from_slice(&serial.to_le_bytes());
+        p.extend_from_slice(&sequence.to_le_bytes());
+        p.extend_from_slice(&[0; 4]);
+        p.push(lacing.len() as u8);
+        p.extend_from_slice(lacing);
+        p.extend_from_slice(body);
+        let crc = page_crc(&p);
+        p[22..26].copy_from_slice(&crc.to_le_bytes());
+        p
+    }
+
+    #[test]
+    fn test_packets_across_pages() {
+        // A 300-byte packet spans two pages; a second stream is interleaved
+        let big: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let mut data = page(FLAG_FIRST, 0, 7, 0, &[3], b"abc");
+        data.extend(page(FLAG_FIRST, 0, 9, 0, &[1], b"z"));
+        data.extend(page(0, -1, 7, 1, &[255], &big[..255]));
+        data.extend_from_slice(b"junk");
+        data.extend(page(FLAG_CONTINUED | FLAG_LAST, 1234, 7, 2, &[45, 2], &[&big[255..], &b"de"[..]].concat()));
+
+        let pages = read_pages(&data);
+        assert_eq!(pages.len(), 4);
+        let packets = read_packets(&pages, 7);
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].data, b"abc");
+        assert_eq!((packets[1].data.as_slice(), packets[1].granule), (&big[..], None));
+        assert_eq!((packets[2].data.as_slice(), packets[2].granule, packets[2].last), (&b"de"[..], Some(1234), true));
+    }
+
+    #[test]
+    fn test_damaged_page_is_skipped() {
+        let mut data = page(FLAG_FIRST, 0, 1, 0, &[1], b"a");
+        let mut bad = page(0, 5, 1, 1, &[255], &[0; 255]);
+        bad[40] ^= 1;
+        data.extend(bad);
+        data.extend(page(FLAG_CONTINUED, 9, 1, 2, &[1, 1], b"bc"));
+        let packets = read_packets(&read_pages(&data), 1);
+        // The continuation of the lost packet is discarded too
+        let payloads: Vec<&[u8]> = packets.iter().map(|p| p.data.as_slice()).collect();
+        assert_eq!(payloads, [&b"a"[..], b"c"]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Conversion between f32 samples and integer PCM, with optional dither
+//!
+//! Integer formats scale by 2^(bits-1), so -1.0 maps to the most negative
+//! code and values at or above 1.0 clip to the most positive one.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Little-endian PCM sample format
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Unsigned 8-bit, centered on 128
+    U8 = 0,
+    I16 = 1,
+    I24 = 2,
+    F32 = 3,
+}
+
+impl SampleFormat {
+    pub fn bits(self) -> u32 {
+        match self {
+            SampleFormat::U8 => 8,
+            SampleFormat::I16 => 16,
+            SampleFormat::I24 => 24,
+            SampleFormat::F32 => 32,
+        }
+    }
+
+    pub fn bytes(self) -> usize {
+        self.bits() as usize / 8
+    }
+}
+
+/// Requantization noise treatment
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dither {
+    /// Plain rounding
+    None = 0,
+    /// Triangular dither of ±1 LSB, decorrelating the error from the signal
+    Tpdf = 1,
+    /// TPDF dither with first-order error feedback, moving the noise
+    /// toward high frequencies where it is less audible
+    Shaped = 2,
+}
+
+/// Deterministic xorshift noise, so output is reproducible
+struct Noise(u32);
+
+impl Noise {
+    fn uniform(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f64 / 4_294_967_296.0
+    }
+
+    /// Triangular on (-1, 1)
+    fn triangular(&mut self) -> f64 {
+        self.uniform() - self.uniform()
+    }
+}
+
+/// Quantize interleaved samples to signed integers of `bits` bits (1-32)
+pub fn quantize(samples: &[f32], channels: usize, bits: u32, dither: Dither) -> Result<Vec<i32>, String> {
+    if !(1..=32).contains(&bits) {
+        return Err(format!("Sample size must be 1-32 bits, got {}", bits));
+    }
+    Ok(requantize(samples, channels, bits, dither))
+}
+
+/// `quantize` for a bit depth known to be 1-32
+pub(super) fn requantize(samples: &[f32], channels: usize, bits: u32, dither: Dither) -> Vec<i32> {
+    let scale = (1u64 << (bits - 1)) as f64;
+    let (min, max) = (-scale, scale - 1.0);
+    let channels = channels.max(1);
+    let mut noise = Noise(0x9E37_79B9);
+    // Previous total error per channel, for noise shaping
+    let mut errors = vec![0.0f64; channels];
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| {
+            let error = &mut errors[i % channels];
+            let wanted = s as f64 * scale - *error;
+            let d = if dither == Dither::None { 0.0 } else { noise.triangular() };
+            let q = (wanted + d).round().clamp(min, max);
+            if dither == Dither::Shaped {
+                // Bounded so clipping cannot feed back without limit
+                *error = (q - wanted).clamp(-2.0, 2.0);
+            }
+            q as i32
+        })
+        .collect()
+}
+
+/// Convert f32 samples to packed PCM bytes
+pub fn to_pcm(samples: &[f32], channels: usize, format: SampleFormat, dither: Dither) -> Vec<u8> {
+    let mut out = Vec::with_capacity(samples.len() * format.bytes());
+    if format == SampleFormat::F32 {
+        for s in samples {
+            out.extend_from_slice(&s.to_le_bytes());
+        }
+        return out;
+    }
+    for q in requantize(samples, channels, format.bits(), dither) {
+        match format {
+            SampleFormat::U8 => out.push((q + 128) as u8),
+            SampleFormat::I16 => out.extend_from_slice(&(q as i16).to_le_bytes()),
+            _ => out.extend_from_slice(&q.to_le_bytes()[..3]),
+        }
+    }
+    out
+}
+
+/// Convert packed PCM bytes to f32 samples, ignoring a trailing partial sample
+pub fn from_pcm(data: &[u8], format: SampleFormat) -> Vec<f32> {
+    data.chunks_exact(format.bytes())
+        .map(|s| match format {
+            SampleFormat::U8 => (s[0] as f32 - 128.0) / 128.0,
+            SampleFormat::I16 => i16::from_le_bytes([s[0], s[1]]) as f32 / 32768.0,
+            SampleFormat::I24 => i32::from_le_bytes([0, s[0], s[1], s[2]]) as f32 / 2_147_483_648.0,
+            SampleFormat::F32 => f32::from_le_bytes([s[0], s[1], s[2], s[3]]),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_clipping() {
+        let samples = [0.0, 0.5, -1.0, 1.0, -0.25];
+        for format in [SampleFormat::U8, SampleFormat::I16, SampleFormat::I24, SampleFormat::F32] {
+            let bytes = to_pcm(&samples, 1, format, Dither::None);
+            assert_eq!(bytes.len(), samples.len() * format.bytes());
+            let back = from_pcm(&bytes, format);
+            let lsb = 1.0 / (1u64 << (format.bits() - 1)) as f32;
+            for (a, b) in samples.iter().zip(&back) {
+                // Only +1.0 loses more than rounding, clipping to max
+                assert!((a - b).abs() <= lsb, "{:?} {} {}", format, a, b);
+            }
+        }
+        assert_eq!(to_pcm(&[1.0, -1.0, 2.0], 1, SampleFormat::I16, Dither::None), [0xFF, 0x7F, 0x00, 0x80, 0xFF, 0x7F]);
+    }
+
+    #[test]
+    fn test_dither() {
+        // A constant between codes is reproduced on average only with dither
+        let samples = vec![0.3 / 128.0; 20000];
+        let mean = |q: Vec<i32>| q.iter().sum::<i32>() as f64 / q.len() as f64;
+        assert_eq!(mean(quantize(&samples, 1, 8, Dither::None).unwrap()), 0.0);
+        assert!((mean(quantize(&samples, 1, 8, Dither::Tpdf).unwrap()) - 0.3).abs() < 0.02);
+        let shaped = quantize(&samples, 1, 8, Dither::Shaped).unwrap();
+        assert!((mean(shaped.clone()) - 0.3).abs() < 0.02);
+        assert!(shaped.iter().all(|q| (-3..=3).contains(q)));
+        assert_eq!(quantize(&samples, 1, 8, Dither::Tpdf), quantize(&samples, 1, 8, Dither::Tpdf));
+    }
+
+    #[test]
+    fn test_bit_depths() {
+        assert_eq!(quantize(&[-1.0, 1.0], 1, 1, Dither::None).unwrap(), [-1, 0]);
+        assert_eq!(quantize(&[-1.0, 1.0], 1, 32, Dither::None).unwrap(), [i32::MIN, i32::MAX]);
+        assert_eq!(quantize(&[0.5], 1, 0, Dither::None).unwrap_err(), "Sample size must be 1-32 bits, got 0");
+        assert!(quantize(&[0.5], 1, 33, Dither::None).is_err());
+        assert!(quantize(&[0.5], 1, u32::MAX, Dither::None).is_err());
+    }
+}
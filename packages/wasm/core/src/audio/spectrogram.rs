@@ -0,0 +1,164 @@
+//! Spectrogram images from short-time Fourier transforms
+
+use super::fft::{Complex, Fft};
+use std::f64::consts::PI;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Levels this far below full scale map to the bottom of the colormap
+const DYNAMIC_RANGE_DB: f32 = 100.0;
+
+/// Color ramp from quiet to loud
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colormap {
+    /// Black to white
+    Grayscale = 0,
+    /// Dark purple through teal to yellow; perceptually uniform
+    Viridis = 1,
+    /// Black through purple and orange to pale yellow
+    Magma = 2,
+}
+
+/// Evenly spaced stops of each non-gray colormap
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 82, 139],
+    [44, 113, 142],
+    [33, 145, 140],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+const MAGMA: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [28, 16, 68],
+    [79, 18, 123],
+    [129, 37, 129],
+    [181, 54, 122],
+    [229, 80, 100],
+    [251, 135, 97],
+    [254, 194, 135],
+    [252, 253, 191],
+];
+
+impl Colormap {
+    /// Color of a level in [0, 1]
+    fn color(self, t: f32) -> [u8; 3] {
+        let stops = match self {
+            Colormap::Grayscale => {
+                let v = (t * 255.0).round() as u8;
+                return [v, v, v];
+            }
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Magma => &MAGMA,
+        };
+        let x = t * (stops.len() - 1) as f32;
+        let i = (x as usize).min(stops.len() - 2);
+        let f = x - i as f32;
+        let mut out = [0; 3];
+        for (c, o) in out.iter_mut().enumerate() {
+            *o = (stops[i][c] as f32 + (stops[i + 1][c] as f32 - stops[i][c] as f32) * f).round() as u8;
+        }
+        out
+    }
+}
+
+/// An RGBA spectrogram with its time and frequency scales
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrogram {
+    /// One column per analysis frame
+    pub width: u32,
+    /// One row per frequency bin, lowest frequency at the bottom
+    pub height: u32,
+    pub data: Vec<u8>,
+    /// Time between columns in seconds
+    pub seconds_per_column: f64,
+    /// Bandwidth of each row in Hz
+    pub hz_per_row: f64,
+}
+
+/// Render mono samples as a spectrogram
+///
+/// Each column is a Hann-windowed FFT of `fft_size` samples, `hop` samples
+/// after the previous one, and each row one of the `fft_size / 2` bins from
+/// DC up to just below Nyquist. Levels are in dB relative to a full-scale
+/// sine, spread over a 100 dB range. Input shorter than one frame is
+/// zero-padded.
+pub fn spectrogram(samples: &[f32], sample_rate: u32, fft_size: usize, hop: usize, colormap: Colormap) -> Result<Spectrogram, String> {
+    if !fft_size.is_power_of_two() || !(16..=65536).contains(&fft_size) {
+        return Err("FFT size must be a power of two from 16 to 65536".to_string());
+    }
+    if hop == 0 || sample_rate == 0 {
+        return Err("Hop and sample rate must be positive".to_string());
+    }
+    if samples.is_empty() {
+        return Err("No audio to analyze".to_string());
+    }
+    let width = 1 + samples.len().saturating_sub(fft_size).div_ceil(hop);
+    let height = fft_size / 2;
+
+    let window: Vec<f32> = (0..fft_size).map(|i| (0.5 - 0.5 * (2.0 * PI * i as f64 / fft_size as f64).cos()) as f32).collect();
+    // A full-scale sine peaks at half the window's sum
+    let reference = window.iter().sum::<f32>() / 2.0;
+    let fft = Fft::new(fft_size);
+    let mut frame: Vec<Complex> = vec![(0.0, 0.0); fft_size];
+    let mut data = vec![0u8; width * height * 4];
+    for x in 0..width {
+        let start = x * hop;
+        for (i, (f, w)) in frame.iter_mut().zip(&window).enumerate() {
+            *f = (samples.get(start + i).copied().unwrap_or(0.0) * w, 0.0);
+        }
+        fft.forward(&mut frame);
+        for (bin, &(re, im)) in frame[..height].iter().enumerate() {
+            let db = 20.0 * ((re * re + im * im).sqrt() / reference).max(1e-10).log10();
+            let t = (1.0 + db / DYNAMIC_RANGE_DB).clamp(0.0, 1.0);
+            let [r, g, b] = colormap.color(t);
+            let y = height - 1 - bin;
+            let pixel = (y * width + x) * 4;
+            data[pixel..pixel + 4].copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+    Ok(Spectrogram {
+        width: width as u32,
+        height: height as u32,
+        data,
+        seconds_per_column: hop as f64 / sample_rate as f64,
+        hz_per_row: sample_rate as f64 / fft_size as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sine_lights_its_row() {
+        // A full-scale sine centered on bin 16 of a 256-point FFT
+        let rate = 8000;
+        let freq = 16.0 * rate as f64 / 256.0;
+        let samples: Vec<f32> = (0..1024).map(|i| (2.0 * PI * freq * i as f64 / rate as f64).sin() as f32).collect();
+        let image = spectrogram(&samples, rate, 256, 128, Colormap::Grayscale).unwrap();
+        assert_eq!((image.width, image.height, image.data.len()), (7, 128, 7 * 128 * 4));
+        assert_eq!(image.hz_per_row, 31.25);
+        let level = |x: usize, bin: usize| image.data[((127 - bin) * 7 + x) * 4];
+        for x in 0..7 {
+            assert!(level(x, 16) >= 254);
+            assert!(level(x, 40) < 40);
+        }
+    }
+
+    #[test]
+    fn test_short_input_and_colormaps() {
+        let image = spectrogram(&[0.0; 10], 44100, 64, 16, Colormap::Viridis).unwrap();
+        assert_eq!((image.width, image.height), (1, 32));
+        assert_eq!(&image.data[..4], &[68, 1, 84, 255]);
+        assert_eq!(Colormap::Magma.color(1.0), [252, 253, 191]);
+        assert_eq!(Colormap::Viridis.color(0.5), [33, 145, 140]);
+        assert!(spectrogram(&[0.0; 10], 44100, 100, 16, Colormap::Magma).is_err());
+        assert!(spectrogram(&[], 44100, 64, 16, Colormap::Magma).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Vorbis codebooks - Huffman entry decoding and VQ lookup vectors
+
+use super::ilog;
+use crate::compress::bits::BitReader;
+
+const SYNC: u32 = 0x56_4342;
+
+/// Codewords up to this length are resolved with a single table lookup
+const FAST_BITS: u32 = 10;
+
+/// Upper bound on `entries * dimensions` for books with a lookup table
+const MAX_VECTOR_VALUES: usize = 1 << 24;
+
+pub struct Codebook {
+    pub dimensions: usize,
+    /// Lookup table indexed by the next `FAST_BITS` bits: (entry, length)
+    fast: Vec<(u32, u8)>,
+    /// Longer codewords as (bit-reversed codeword, length, entry), sorted
+    slow: Vec<(u32, u8, u32)>,
+    /// The one used entry of a single-entry book, coded as either bit value
+    single: Option<u32>,
+    /// Flattened `entries * dimensions` VQ vectors, if the book has a lookup
+    vectors: Option<Vec<f32>>,
+}
+
+/// Unpack the 32-bit float format used in codebook headers
+fn unpack_float(x: u32) -> f32 {
+    let mantissa = (x & 0x1F_FFFF) as f64;
+    let exponent = ((x >> 21) & 0x3FF) as i32 - 788;
+    let value = mantissa * 2f64.powi(exponent);
+    (if x & 0x8000_0000 != 0 { -value } else { value }) as f32
+}
+
+/// Largest `r` with `r^dimensions <= entries`
+pub fn lookup1_values(entries: usize, dimensions: usize) -> usize {
+    let mut r = (entries as f64).powf(1.0 / dimensions as f64).floor() as usize;
+    // Correct floating-point error in either direction
+    let pow = |r: usize| (0..dimensions).try_fold(1usize, |acc, _| acc.checked_mul(r));
+    while pow(r + 1).is_some_and(|p| p <= entries) {
+        r += 1;
+    }
+    while r > 0 && pow(r).is_none_or(|p| p > entries) {
+        r -= 1;
+    }
+    r
+}
+
+/// Assign codewords to lengths in entry order (Vorbis I, section 3.2.1)
+fn assign_codewords(lengths: &[u8]) -> Result<Vec<u32>, String> {
+    let mut codes = vec![0u32; lengths.len()];
+    let mut marker = [0u32; 33];
+    for (i, &len) in lengths.iter().enumerate() {
+        if len == 0 {
+            continue;
+        }
+        let len = len as usize;
+        let entry = marker[len];
+        if len < 32 && entry >> len != 0 {
+            return Err("Overspecified Vorbis codebook".to_string());
+        }
+        codes[i] = entry;
+        for j in (1..=len).rev() {
+            if marker[j] & 1 != 0 {
+                marker[j] = if j == 1 { marker[1] + 1 } else { marker[j - 1] << 1 };
+                break;
+            }
+            marker[j] += 1;
+        }
+        let mut entry = entry;
+        for j in len + 1..33 {
+            if marker[j] >> 1 != entry {
+                break;
+            }
+            entry = marker[j];
+            marker[j] = marker[j - 1] << 1;
+        }
+    }
+    Ok(codes)
+}
+
+impl Codebook {
+    pub fn read(r: &mut BitReader) -> Result<Codebook, String> {
+        if r.bits(24)? != SYNC {
+            return Err("Invalid Vorbis codebook sync pattern".to_string());
+        }
+        let dimensions = r.bits(16)? as usize;
+        let entries = r.bits(24)? as usize;
+        let mut lengths = vec![0u8; entries];
+        if r.bits(1)? == 1 {
+            let mut current = 0;
+            let mut length = r.bits(5)? + 1;
+            while current < entries {
+                let count = r.bits(ilog((entries - current) as u32))? as usize;
+                if current + count > entries || length > 32 {
+                    return Err("Invalid ordered Vorbis codebook".to_string());
+                }
+                lengths[current..current + count].fill(length as u8);
+                current += count;
+                length += 1;
+            }
+        } else {
+            let sparse = r.bits(1)? == 1;
+            for len in lengths.iter_mut() {
+                if !sparse || r.bits(1)? == 1 {
+                    *len = r.bits(5)? as u8 + 1;
+                }
+            }
+        }
+
+        let lookup_type = r.bits(4)?;
+        let vectors = match lookup_type {
+            0 => None,
+            1 | 2 => {
+                let minimum = unpack_float(r.bits(32)?);
+                let delta = unpack_float(r.bits(32)?);
+                let value_bits = r.bits(4)? + 1;
+                let sequence = r.bits(1)? == 1;
+                let total = entries.checked_mul(dimensions).filter(|&t| t <= MAX_VECTOR_VALUES).ok_or("Vorbis codebook is too large")?;
+                let count = if lookup_type == 1 { lookup1_values(entries, dimensions) } else { total };
+                let mut multiplicands = Vec::with_capacity(count.min(1 << 20));
+                for _ in 0..count {
+                    multiplicands.push(r.bits(value_bits)? as f32);
+                }
+                if dimensions == 0 || (lookup_type == 1 && count == 0) {
+                    return Err("Invalid Vorbis codebook lookup".to_string());
+                }
+                let mut vectors = Vec::with_capacity(total);
+                for entry in 0..entries {
+                    let mut last = 0.0;
+                    let mut divisor = 1usize;
+                    for i in 0..dimensions {
+                        let offset = if lookup_type == 1 { (entry / divisor) % count } else { entry * dimensions + i };
+                        let value = multiplicands[offset] * delta + minimum + last;
+                        vectors.push(value);
+                        if sequence {
+                            last = value;
+                        }
+                        divisor = divisor.saturating_mul(count);
+                    }
+                }
+                Some(vectors)
+            }
+            other => return Err(format!("Invalid Vorbis codebook lookup type {}", other)),
+        };
+
+        let used: Vec<usize> = (0..entries).filter(|&i| lengths[i] > 0).collect();
+        let single = (used.len() == 1).then(|| used[0] as u32);
+        let codes = assign_codewords(&lengths)?;
+        let mut fast = vec![(0u32, 0u8); 1 << FAST_BITS];
+        let mut slow = Vec::new();
+        for &i in &used {
+            let len = lengths[i] as u32;
+            // Codewords are read a bit at a time, first bit lowest
+            let reversed = codes[i].reverse_bits() >> (32 - len);
+            if len <= FAST_BITS {
+                for fill in (reversed as usize..1 << FAST_BITS).step_by(1 << len) {
+                    fast[fill] = (i as u32, len as u8);
+                }
+            } else {
+                slow.push((reversed, len as u8, i as u32));
+            }
+        }
+        slow.sort_unstable();
+        Ok(Codebook { dimensions, fast, slow, single, vectors })
+    }
+
+    /// Decode one entry number, or `None` at the end of the packet
+    pub fn decode(&self, r: &mut BitReader) -> Option<u32> {
+        if let Some(entry) = self.single {
+            r.consume(1).ok()?;
+            return Some(entry);
+        }
+        let (entry, len) = self.fast[r.peek(FAST_BITS) as usize];
+        if len > 0 {
+            r.consume(len as u32).ok()?;
+            return Some(entry);
+        }
+        let bits = r.peek(32);
+        let (_, len, entry) = self.slow.iter().find(|&&(code, len, _)| bits & ((1u64 << len) - 1) as u32 == code)?;
+        r.consume(*len as u32).ok()?;
+        Some(*entry)
+    }
+
+    /// Decode an entry and return its VQ vector
+    pub fn decode_vector(&self, r: &mut BitReader) -> Option<&[f32]> {
+        let entry = self.decode(r)? as usize;
+        let vectors = self.vectors.as_ref()?;
+        Some(&vectors[entry * self.dimensions..(entry + 1) * self.dimensions])
+    }
+
+    pub fn has_lookup(&self) -> bool {
+        self.vectors.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codeword_assignment() {
+        // Example from the Vorbis I specification, section 3.2.1
+        let codes = assign_codewords(&[2, 4, 4, 4, 4, 2, 3, 3]).unwrap();
+        assert_eq!(codes, [0b00, 0b0100, 0b0101, 0b0110, 0b0111, 0b10, 0b110, 0b111]);
+        assert!(assign_codewords(&[1, 1, 1]).is_err());
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(lookup1_values(81, 4), 3);
+        assert_eq!(lookup1_values(80, 4), 2);
+        assert_eq!(lookup1_values(1, 1), 1);
+        // 1.0 = 2^20 * 2^-20, with a biased exponent of 768
+        assert_eq!(unpack_float(768 << 21 | 1 << 20), 1.0);
+        assert_eq!(unpack_float(0x8000_0000 | 768 << 21 | 3 << 19), -1.5);
+    }
+}
@@ -0,0 +1,373 @@
+//! Vorbis floors - the spectral envelope each channel's residue is scaled by
+
+use super::codebook::Codebook;
+use super::ilog;
+use crate::compress::bits::BitReader;
+
+/// Floor type 0: an LSP filter sampled on the Bark scale
+pub struct Floor0 {
+    order: usize,
+    rate: u32,
+    bark_map_size: u32,
+    amplitude_bits: u32,
+    amplitude_offset: u32,
+    books: Vec<usize>,
+}
+
+/// Floor type 1: a piecewise-linear curve in the log domain
+pub struct Floor1 {
+    partition_classes: Vec<usize>,
+    class_dimensions: Vec<usize>,
+    class_subclasses: Vec<u32>,
+    class_masterbooks: Vec<usize>,
+    /// Books per class and subclass; `None` decodes as zero
+    subclass_books: Vec<Vec<Option<usize>>>,
+    multiplier: u32,
+    range_bits: u32,
+    xs: Vec<u32>,
+    /// Indices of `xs` in ascending order
+    sorted: Vec<usize>,
+    /// Low and high neighbor of each point, from the points before it
+    neighbors: Vec<(usize, usize)>,
+}
+
+pub enum Floor {
+    Type0(Floor0),
+    Type1(Floor1),
+}
+
+/// A floor decoded from an audio packet, before curve synthesis
+pub enum FloorData {
+    Type0 { amplitude: u32, coefficients: Vec<f32> },
+    Type1(Vec<i32>),
+}
+
+fn book(index: u32, books: &[Codebook]) -> Result<usize, String> {
+    let index = index as usize;
+    if index >= books.len() {
+        return Err(format!("Vorbis floor references missing codebook {}", index));
+    }
+    Ok(index)
+}
+
+impl Floor {
+    pub fn read(r: &mut BitReader, books: &[Codebook]) -> Result<Floor, String> {
+        match r.bits(16)? {
+            0 => {
+                let order = r.bits(8)? as usize;
+                let rate = r.bits(16)?;
+                let bark_map_size = r.bits(16)?;
+                let amplitude_bits = r.bits(6)?;
+                let amplitude_offset = r.bits(8)?;
+                let count = r.bits(4)? + 1;
+                let books = (0..count).map(|_| book(r.bits(8)?, books)).collect::<Result<_, _>>()?;
+                if order == 0 || rate == 0 || bark_map_size == 0 {
+                    return Err("Invalid Vorbis floor 0".to_string());
+                }
+                Ok(Floor::Type0(Floor0 { order, rate, bark_map_size, amplitude_bits, amplitude_offset, books }))
+            }
+            1 => {
+                let partitions = r.bits(5)? as usize;
+                let partition_classes: Vec<usize> = (0..partitions).map(|_| r.bits(4).map(|c| c as usize)).collect::<Result<_, _>>()?;
+                let classes = partition_classes.iter().max().map_or(0, |&m| m + 1);
+                let mut floor = Floor1 {
+                    partition_classes,
+                    class_dimensions: Vec::with_capacity(classes),
+                    class_subclasses: Vec::with_capacity(classes),
+                    class_masterbooks: Vec::with_capacity(classes),
+                    subclass_books: Vec::with_capacity(classes),
+                    multiplier: 0,
+                    range_bits: 0,
+                    xs: Vec::new(),
+                    sorted: Vec::new(),
+                    neighbors: Vec::new(),
+                };
+                for _ in 0..classes {
+                    floor.class_dimensions.push(r.bits(3)? as usize + 1);
+                    let subclasses = r.bits(2)?;
+                    floor.class_subclasses.push(subclasses);
+                    floor.class_masterbooks.push(if subclasses > 0 { book(r.bits(8)?, books)? } else { 0 });
+                    let mut subs = Vec::with_capacity(1 << subclasses);
+                    for _ in 0..1 << subclasses {
+                        let index = r.bits(8)?;
+                        subs.push(if index == 0 { None } else { Some(book(index - 1, books)?) });
+                    }
+                    floor.subclass_books.push(subs);
+                }
+                floor.multiplier = r.bits(2)? + 1;
+                floor.range_bits = r.bits(4)?;
+                floor.xs = vec![0, 1 << floor.range_bits];
+                for &class in &floor.partition_classes {
+                    for _ in 0..floor.class_dimensions[class] {
+                        floor.xs.push(r.bits(floor.range_bits)?);
+                    }
+                }
+                if floor.xs.len() > 65 {
+                    return Err("Vorbis floor 1 has too many points".to_string());
+                }
+                floor.sorted = (0..floor.xs.len()).collect();
+                floor.sorted.sort_by_key(|&i| floor.xs[i]);
+                if floor.sorted.windows(2).any(|w| floor.xs[w[0]] == floor.xs[w[1]]) {
+                    return Err("Vorbis floor 1 has duplicate points".to_string());
+                }
+                floor.neighbors = (0..floor.xs.len()).map(|i| neighbors(&floor.xs, i)).collect();
+                Ok(Floor::Type1(floor))
+            }
+            other => Err(format!("Unsupported Vorbis floor type {}", other)),
+        }
+    }
+
+    /// Decode the floor for one channel; `None` marks the channel unused
+    pub fn decode(&self, r: &mut BitReader, books: &[Codebook]) -> Option<FloorData> {
+        match self {
+            Floor::Type0(f) => {
+                let amplitude = r.bits(f.amplitude_bits).ok()?;
+                if amplitude == 0 {
+                    return None;
+                }
+                let index = r.bits(ilog(f.books.len() as u32)).ok()? as usize;
+                let book = &books[*f.books.get(index)?];
+                let mut coefficients = Vec::with_capacity(f.order + book.dimensions);
+                let mut last = 0.0;
+                while coefficients.len() < f.order {
+                    let vector = book.decode_vector(r)?;
+                    coefficients.extend(vector.iter().map(|v| v + last));
+                    last = *coefficients.last()?;
+                }
+                Some(FloorData::Type0 { amplitude, coefficients })
+            }
+            Floor::Type1(f) => {
+                if r.bits(1).ok()? == 0 {
+                    return None;
+                }
+                let range = [256, 128, 86, 64][f.multiplier as usize - 1];
+                let bits = ilog(range - 1);
+                let mut ys = Vec::with_capacity(f.xs.len());
+                ys.push(r.bits(bits).ok()? as i32);
+                ys.push(r.bits(bits).ok()? as i32);
+                for &class in &f.partition_classes {
+                    let sub_bits = f.class_subclasses[class];
+                    let mut selector = if sub_bits > 0 { books[f.class_masterbooks[class]].decode(r)? } else { 0 };
+                    for _ in 0..f.class_dimensions[class] {
+                        let book = f.subclass_books[class][(selector & ((1 << sub_bits) - 1)) as usize];
+                        selector >>= sub_bits;
+                        ys.push(match book {
+                            Some(b) => books[b].decode(r)? as i32,
+                            None => 0,
+                        });
+                    }
+                }
+                Some(FloorData::Type1(ys))
+            }
+        }
+    }
+
+    /// Render the floor curve over `out`, which spans half a block
+    pub fn synthesize(&self, data: &FloorData, out: &mut [f32]) {
+        match (self, data) {
+            (Floor::Type0(f), FloorData::Type0 { amplitude, coefficients }) => f.synthesize(*amplitude, coefficients, out),
+            (Floor::Type1(f), FloorData::Type1(ys)) => f.synthesize(ys, out),
+            _ => out.fill(0.0),
+        }
+    }
+}
+
+fn bark(x: f64) -> f64 {
+    13.1 * (0.00074 * x).atan() + 2.24 * (0.0000000185 * x * x).atan() + 0.0001 * x
+}
+
+impl Floor0 {
+    fn synthesize(&self, amplitude: u32, coefficients: &[f32], out: &mut [f32]) {
+        let n = out.len();
+        let map = |i: usize| -> i64 {
+            let foobar = (bark(self.rate as f64 * i as f64 / (2.0 * n as f64)) * self.bark_map_size as f64 / bark(0.5 * self.rate as f64)).floor() as i64;
+            foobar.min(self.bark_map_size as i64 - 1)
+        };
+        let cosines: Vec<f64> = coefficients[..self.order].iter().map(|&c| (c as f64).cos()).collect();
+        let max_amplitude = ((1u64 << self.amplitude_bits) - 1) as f64;
+        let mut i = 0;
+        while i < n {
+            let key = map(i);
+            let omega = std::f64::consts::PI * key as f64 / self.bark_map_size as f64;
+            let w = omega.cos();
+            let product = |start: usize| cosines.iter().skip(start).step_by(2).map(|&c| 4.0 * (c - w) * (c - w)).product::<f64>();
+            let (p, q) = if self.order % 2 == 1 {
+                ((1.0 - w * w) * product(1), 0.25 * product(0))
+            } else {
+                ((1.0 - w) / 2.0 * product(1), (1.0 + w) / 2.0 * product(0))
+            };
+            let value = (0.11512925 * (amplitude as f64 * self.amplitude_offset as f64 / (max_amplitude * (p + q).sqrt()) - self.amplitude_offset as f64)).exp() as f32;
+            while i < n && map(i) == key {
+                out[i] = value;
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Positions of the nearest smaller and larger x among the points before `i`
+fn neighbors(xs: &[u32], i: usize) -> (usize, usize) {
+    let mut low = 0;
+    let mut high = 1;
+    for j in 0..i {
+        if xs[j] < xs[i] && xs[j] > xs[low] {
+            low = j;
+        }
+        if xs[j] > xs[i] && xs[j] < xs[high] {
+            high = j;
+        }
+    }
+    (low, high)
+}
+
+fn render_point(x0: i32, y0: i32, x1: i32, y1: i32, x: i32) -> i32 {
+    let dy = y1 - y0;
+    let offset = dy.abs() * (x - x0) / (x1 - x0);
+    if dy < 0 {
+        y0 - offset
+    } else {
+        y0 + offset
+    }
+}
+
+/// Bresenham-style line from `x0` up to (not including) `x1`
+fn render_line(x0: i32, y0: i32, x1: i32, y1: i32, out: &mut [f32]) {
+    let dy = y1 - y0;
+    let adx = x1 - x0;
+    let base = dy / adx;
+    let step = if dy < 0 { base - 1 } else { base + 1 };
+    let ady = dy.abs() - base.abs() * adx;
+    let mut y = y0;
+    let mut err = 0;
+    let end = (x1 as usize).min(out.len());
+    for (x, v) in out.iter_mut().enumerate().take(end).skip(x0 as usize) {
+        if x > x0 as usize {
+            err += ady;
+            if err >= adx {
+                err -= adx;
+                y += step;
+            } else {
+                y += base;
+            }
+        }
+        *v = INVERSE_DB[y.clamp(0, 255) as usize];
+    }
+}
+
+/// Floor 1 amplitudes: 256 steps of about 0.547 dB up to unity
+#[allow(clippy::excessive_precision)]
+const INVERSE_DB: [f32; 256] = [
+    1.0649863e-07, 1.1341951e-07, 1.2079015e-07, 1.2863978e-07, 1.3699951e-07, 1.4590251e-07, 1.5538408e-07, 1.6548181e-07,
+    1.7623575e-07, 1.8768855e-07, 1.9988561e-07, 2.1287530e-07, 2.2670913e-07, 2.4144197e-07, 2.5713223e-07, 2.7384213e-07,
+    2.9163793e-07, 3.1059021e-07, 3.3077411e-07, 3.5226968e-07, 3.7516214e-07, 3.9954229e-07, 4.2550680e-07, 4.5315863e-07,
+    4.8260743e-07, 5.1396998e-07, 5.4737065e-07, 5.8294187e-07, 6.2082472e-07, 6.6116941e-07, 7.0413592e-07, 7.4989464e-07,
+    7.9862701e-07, 8.5052630e-07, 9.0579828e-07, 9.6466216e-07, 1.0273513e-06, 1.0941144e-06, 1.1652161e-06, 1.2409384e-06,
+    1.3215816e-06, 1.4074654e-06, 1.4989305e-06, 1.5963394e-06, 1.7000785e-06, 1.8105592e-06, 1.9282195e-06, 2.0535261e-06,
+    2.1869758e-06, 2.3290978e-06, 2.4804557e-06, 2.6416497e-06, 2.8133190e-06, 2.9961443e-06, 3.1908506e-06, 3.3982101e-06,
+    3.6190449e-06, 3.8542308e-06, 4.1047004e-06, 4.3714470e-06, 4.6555282e-06, 4.9580707e-06, 5.2802740e-06, 5.6234160e-06,
+    5.9888572e-06, 6.3780469e-06, 6.7925283e-06, 7.2339451e-06, 7.7040476e-06, 8.2047000e-06, 8.7378876e-06, 9.3057248e-06,
+    9.9104632e-06, 1.0554501e-05, 1.1240392e-05, 1.1970856e-05, 1.2748789e-05, 1.3577278e-05, 1.4459606e-05, 1.5399272e-05,
+    1.6400004e-05, 1.7465768e-05, 1.8600792e-05, 1.9809576e-05, 2.1096914e-05, 2.2467911e-05, 2.3928002e-05, 2.5482978e-05,
+    2.7139006e-05, 2.8902651e-05, 3.0780908e-05, 3.2781225e-05, 3.4911534e-05, 3.7180282e-05, 3.9596466e-05, 4.2169667e-05,
+    4.4910090e-05, 4.7828601e-05, 5.0936773e-05, 5.4246931e-05, 5.7772202e-05, 6.1526565e-05, 6.5524908e-05, 6.9783085e-05,
+    7.4317983e-05, 7.9147585e-05, 8.4291040e-05, 8.9768747e-05, 9.5602426e-05, 0.00010181521, 0.00010843174, 0.00011547824,
+    0.00012298267, 0.00013097477, 0.00013948625, 0.00014855085, 0.00015820453, 0.00016848555, 0.00017943469, 0.00019109536,
+    0.00020351382, 0.00021673929, 0.00023082423, 0.00024582449, 0.00026179955, 0.00027881276, 0.00029693158, 0.00031622787,
+    0.00033677814, 0.00035866388, 0.00038197188, 0.00040679456, 0.00043323036, 0.00046138411, 0.00049136745, 0.00052329927,
+    0.00055730621, 0.00059352311, 0.00063209358, 0.00067317058, 0.00071691700, 0.00076350630, 0.00081312324, 0.00086596457,
+    0.00092223983, 0.00098217216, 0.0010459992, 0.0011139742, 0.0011863665, 0.0012634633, 0.0013455702, 0.0014330129,
+    0.0015261382, 0.0016253153, 0.0017309374, 0.0018434235, 0.0019632195, 0.0020908006, 0.0022266726, 0.0023713743,
+    0.0025254795, 0.0026895994, 0.0028643847, 0.0030505286, 0.0032487691, 0.0034598925, 0.0036847358, 0.0039241906,
+    0.0041792066, 0.0044507950, 0.0047400328, 0.0050480668, 0.0053761186, 0.0057254891, 0.0060975636, 0.0064938176,
+    0.0069158225, 0.0073652516, 0.0078438871, 0.0083536271, 0.0088964928, 0.009474637, 0.010090352, 0.010746080,
+    0.011444421, 0.012188144, 0.012980198, 0.013823725, 0.014722068, 0.015678791, 0.016697687, 0.017782797,
+    0.018938423, 0.020169149, 0.021479854, 0.022875735, 0.024362330, 0.025945531, 0.027631618, 0.029427276,
+    0.031339626, 0.033376252, 0.035545228, 0.037855157, 0.040315199, 0.042935108, 0.045725273, 0.048696758,
+    0.051861348, 0.055231591, 0.058820850, 0.062643361, 0.066714279, 0.071049749, 0.075666962, 0.080584227,
+    0.085821044, 0.091398179, 0.097337747, 0.10366330, 0.11039993, 0.11757434, 0.12521498, 0.13335215,
+    0.14201813, 0.15124727, 0.16107617, 0.17154380, 0.18269168, 0.19456402, 0.20720788, 0.22067342,
+    0.23501402, 0.25028656, 0.26655159, 0.28387361, 0.30232132, 0.32196786, 0.34289114, 0.36517414,
+    0.38890521, 0.41417847, 0.44109412, 0.46975890, 0.50028648, 0.53279791, 0.56742212, 0.60429640,
+    0.64356699, 0.68538959, 0.72993007, 0.77736504, 0.82788260, 0.88168307, 0.9389798, 1.0,
+];
+
+impl Floor1 {
+    fn synthesize(&self, ys: &[i32], out: &mut [f32]) {
+        let range = [256, 128, 86, 64][self.multiplier as usize - 1];
+        let count = self.xs.len();
+        let mut final_y = vec![0i32; count];
+        let mut used = vec![false; count];
+        final_y[0] = ys[0];
+        final_y[1] = ys[1];
+        used[0] = true;
+        used[1] = true;
+        for i in 2..count {
+            let (low, high) = self.neighbors[i];
+            let predicted = render_point(self.xs[low] as i32, final_y[low], self.xs[high] as i32, final_y[high], self.xs[i] as i32);
+            let value = ys[i];
+            let high_room = range - predicted;
+            let low_room = predicted;
+            let room = high_room.min(low_room) * 2;
+            if value == 0 {
+                final_y[i] = predicted;
+                continue;
+            }
+            used[low] = true;
+            used[high] = true;
+            used[i] = true;
+            final_y[i] = if value >= room {
+                if high_room > low_room {
+                    value - low_room + predicted
+                } else {
+                    predicted - value + high_room - 1
+                }
+            } else if value % 2 == 1 {
+                predicted - (value + 1) / 2
+            } else {
+                predicted + value / 2
+            };
+        }
+
+        for y in &mut final_y {
+            *y = (*y).clamp(0, range - 1);
+        }
+
+        let multiplier = self.multiplier as i32;
+        let (mut lx, mut ly) = (0, final_y[self.sorted[0]] * multiplier);
+        for &i in &self.sorted[1..] {
+            if used[i] {
+                let (hx, hy) = (self.xs[i] as i32, final_y[i] * multiplier);
+                render_line(lx, ly, hx, hy, out);
+                lx = hx;
+                ly = hy;
+            }
+        }
+        if (lx as usize) < out.len() {
+            render_line(lx, ly, out.len() as i32, ly, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_line() {
+        let mut out = [0.0f32; 8];
+        render_line(0, 255, 4, 251, &mut out);
+        assert_eq!(out[..4], [INVERSE_DB[255], INVERSE_DB[254], INVERSE_DB[253], INVERSE_DB[252]]);
+        assert_eq!(out[4], 0.0);
+        assert_eq!((INVERSE_DB[0], INVERSE_DB[255]), (1.0649863e-7, 1.0));
+    }
+
+    #[test]
+    fn test_neighbors_and_prediction() {
+        let xs = [0, 128, 64, 32, 96];
+        assert_eq!(neighbors(&xs, 2), (0, 1));
+        assert_eq!(neighbors(&xs, 3), (0, 2));
+        assert_eq!(neighbors(&xs, 4), (2, 1));
+        assert_eq!(render_point(0, 10, 128, 20, 64), 15);
+        assert_eq!(render_point(0, 20, 128, 10, 32), 18);
+    }
+}
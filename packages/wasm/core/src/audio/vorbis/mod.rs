@@ -0,0 +1,381 @@
+//! Vorbis I audio decoding
+//!
+//! Implements the full Vorbis I specification: both floor types, all three
+//! residue types, channel coupling and variable block sizes. Streams are
+//! read from Ogg; the last page's granule position trims the final block.
+
+mod codebook;
+mod floor;
+mod residue;
+
+use super::mdct::Imdct;
+use super::ogg::{read_packets, read_pages};
+use super::AudioData;
+use crate::compress::bits::BitReader;
+use crate::limits::Budget;
+use codebook::Codebook;
+use floor::{Floor, FloorData};
+use residue::Residue;
+
+/// Number of bits needed to represent `x`
+pub(crate) fn ilog(x: u32) -> u32 {
+    32 - x.leading_zeros()
+}
+
+struct Mapping {
+    /// (magnitude, angle) channel pairs
+    couplings: Vec<(usize, usize)>,
+    /// Submap of each channel
+    mux: Vec<usize>,
+    /// (floor, residue) of each submap
+    submaps: Vec<(usize, usize)>,
+}
+
+struct Mode {
+    long: bool,
+    mapping: usize,
+}
+
+/// The stream parameters from the identification header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VorbisInfo {
+    pub channels: u16,
+    pub sample_rate: u32,
+    /// Short and long block sizes
+    pub blocksizes: [usize; 2],
+}
+
+/// Decoder state for one logical Vorbis stream
+pub struct VorbisDecoder {
+    info: VorbisInfo,
+    codebooks: Vec<Codebook>,
+    floors: Vec<Floor>,
+    residues: Vec<Residue>,
+    mappings: Vec<Mapping>,
+    modes: Vec<Mode>,
+    imdct: [Imdct; 2],
+    /// Rising half of the window for each block size
+    slopes: [Vec<f32>; 2],
+    /// Unwindowed right part of the previous block, per channel
+    previous: Option<Vec<Vec<f32>>>,
+}
+
+fn check_header(packet: &[u8], kind: u8) -> Result<BitReader<'_>, String> {
+    if packet.len() < 7 || packet[0] != kind || &packet[1..7] != b"vorbis" {
+        return Err(format!("Missing Vorbis header packet {}", kind));
+    }
+    Ok(BitReader::new(&packet[7..]))
+}
+
+/// Parse the identification header
+pub fn read_info(packet: &[u8]) -> Result<VorbisInfo, String> {
+    let mut r = check_header(packet, 1)?;
+    if r.bits(32)? != 0 {
+        return Err("Unsupported Vorbis version".to_string());
+    }
+    let channels = r.bits(8)? as u16;
+    let sample_rate = r.bits(32)?;
+    r.bits(32)?;
+    r.bits(32)?;
+    r.bits(32)?;
+    let short = r.bits(4)?;
+    let long = r.bits(4)?;
+    if channels == 0 || sample_rate == 0 {
+        return Err("Vorbis stream has no channels".to_string());
+    }
+    if !(6..=13).contains(&short) || !(6..=13).contains(&long) || short > long {
+        return Err("Invalid Vorbis block sizes".to_string());
+    }
+    if r.bits(1)? != 1 {
+        return Err("Vorbis identification header is not framed".to_string());
+    }
+    Ok(VorbisInfo { channels, sample_rate, blocksizes: [1 << short, 1 << long] })
+}
+
+/// Read a count stored as `bits` bits minus one
+fn count(r: &mut BitReader, bits: u32) -> Result<usize, String> {
+    Ok(r.bits(bits)? as usize + 1)
+}
+
+fn read_mapping(r: &mut BitReader, channels: usize, floors: usize, residues: usize) -> Result<Mapping, String> {
+    if r.bits(16)? != 0 {
+        return Err("Unsupported Vorbis mapping type".to_string());
+    }
+    let submap_count = if r.bits(1)? == 1 { count(r, 4)? } else { 1 };
+    let mut couplings = Vec::new();
+    if r.bits(1)? == 1 {
+        let bits = ilog(channels as u32 - 1);
+        for _ in 0..count(r, 8)? {
+            let magnitude = r.bits(bits)? as usize;
+            let angle = r.bits(bits)? as usize;
+            if magnitude == angle || magnitude >= channels || angle >= channels {
+                return Err("Invalid Vorbis channel coupling".to_string());
+            }
+            couplings.push((magnitude, angle));
+        }
+    }
+    if r.bits(2)? != 0 {
+        return Err("Invalid Vorbis mapping".to_string());
+    }
+    let mut mux = vec![0; channels];
+    if submap_count > 1 {
+        for m in mux.iter_mut() {
+            *m = r.bits(4)? as usize;
+            if *m >= submap_count {
+                return Err("Invalid Vorbis mapping submap".to_string());
+            }
+        }
+    }
+    let mut submaps = Vec::with_capacity(submap_count);
+    for _ in 0..submap_count {
+        r.bits(8)?;
+        let floor = r.bits(8)? as usize;
+        let residue = r.bits(8)? as usize;
+        if floor >= floors || residue >= residues {
+            return Err("Vorbis mapping references a missing floor or residue".to_string());
+        }
+        submaps.push((floor, residue));
+    }
+    Ok(Mapping { couplings, mux, submaps })
+}
+
+/// Rising window half of `len` samples: sin(pi/2 sin^2(...))
+fn slope(len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| {
+            let s = ((i as f64 + 0.5) / len as f64 * std::f64::consts::FRAC_PI_2).sin();
+            (std::f64::consts::FRAC_PI_2 * s * s).sin() as f32
+        })
+        .collect()
+}
+
+/// Undo square polar coupling of a magnitude and angle value
+fn decouple(m: f32, a: f32) -> (f32, f32) {
+    match (m > 0.0, a > 0.0) {
+        (true, true) => (m, m - a),
+        (true, false) => (m + a, m),
+        (false, true) => (m, m + a),
+        (false, false) => (m - a, m),
+    }
+}
+
+impl VorbisDecoder {
+    /// Build a decoder from the identification and setup headers
+    pub fn new(info: VorbisInfo, setup: &[u8]) -> Result<VorbisDecoder, String> {
+        let mut r = check_header(setup, 5)?;
+        let channels = info.channels as usize;
+        let codebooks = (0..count(&mut r, 8)?).map(|_| Codebook::read(&mut r)).collect::<Result<Vec<_>, _>>()?;
+        for _ in 0..count(&mut r, 6)? {
+            if r.bits(16)? != 0 {
+                return Err("Invalid Vorbis time-domain transform".to_string());
+            }
+        }
+        let floors = (0..count(&mut r, 6)?).map(|_| Floor::read(&mut r, &codebooks)).collect::<Result<Vec<_>, _>>()?;
+        let residues = (0..count(&mut r, 6)?).map(|_| Residue::read(&mut r, &codebooks)).collect::<Result<Vec<_>, _>>()?;
+        let mappings = (0..count(&mut r, 6)?).map(|_| read_mapping(&mut r, channels, floors.len(), residues.len())).collect::<Result<Vec<_>, _>>()?;
+        let mut modes = Vec::new();
+        for _ in 0..count(&mut r, 6)? {
+            let long = r.bits(1)? == 1;
+            let window = r.bits(16)?;
+            let transform = r.bits(16)?;
+            let mapping = r.bits(8)? as usize;
+            if window != 0 || transform != 0 || mapping >= mappings.len() {
+                return Err("Invalid Vorbis mode".to_string());
+            }
+            modes.push(Mode { long, mapping });
+        }
+        if r.bits(1)? != 1 {
+            return Err("Vorbis setup header is not framed".to_string());
+        }
+        let [short, long] = info.blocksizes;
+        Ok(VorbisDecoder {
+            info,
+            codebooks,
+            floors,
+            residues,
+            mappings,
+            modes,
+            imdct: [Imdct::new(short), Imdct::new(long)],
+            slopes: [slope(short / 2), slope(long / 2)],
+            previous: None,
+        })
+    }
+
+    pub fn info(&self) -> VorbisInfo {
+        self.info
+    }
+
+    /// Decode one audio packet into per-channel samples
+    ///
+    /// The first packet only primes the overlap and returns no samples.
+    pub fn decode_packet(&mut self, packet: &[u8]) -> Result<Vec<Vec<f32>>, String> {
+        let channels = self.info.channels as usize;
+        let mut r = BitReader::new(packet);
+        if r.bits(1)? != 0 {
+            return Err("Expected a Vorbis audio packet".to_string());
+        }
+        let mode = r.bits(ilog(self.modes.len() as u32 - 1))? as usize;
+        let mode = self.modes.get(mode).ok_or("Invalid Vorbis mode number")?;
+        let n = self.info.blocksizes[mode.long as usize];
+        let (previous_long, next_long) = if mode.long { (r.bits(1)? == 1, r.bits(1)? == 1) } else { (false, false) };
+        let mapping = &self.mappings[mode.mapping];
+
+        // Floors, with an end of packet marking the channel unused
+        let floors: Vec<Option<FloorData>> = (0..channels)
+            .map(|c| self.floors[mapping.submaps[mapping.mux[c]].0].decode(&mut r, &self.codebooks))
+            .collect();
+        let mut unused: Vec<bool> = floors.iter().map(Option::is_none).collect();
+        for &(m, a) in &mapping.couplings {
+            if !unused[m] || !unused[a] {
+                unused[m] = false;
+                unused[a] = false;
+            }
+        }
+
+        // Residues, decoded per submap
+        let mut spectra = vec![Vec::new(); channels];
+        for (submap, &(_, residue)) in mapping.submaps.iter().enumerate() {
+            let members: Vec<usize> = (0..channels).filter(|&c| mapping.mux[c] == submap).collect();
+            let skip: Vec<bool> = members.iter().map(|&c| unused[c]).collect();
+            let vectors = self.residues[residue].decode(&mut r, &self.codebooks, &skip, n / 2);
+            for (c, v) in members.into_iter().zip(vectors) {
+                spectra[c] = v;
+            }
+        }
+        for &(m, a) in mapping.couplings.iter().rev() {
+            let (magnitude, angle) = if m < a {
+                let (low, high) = spectra.split_at_mut(a);
+                (&mut low[m], &mut high[0])
+            } else {
+                let (low, high) = spectra.split_at_mut(m);
+                (&mut high[0], &mut low[a])
+            };
+            for (mv, av) in magnitude.iter_mut().zip(angle.iter_mut()) {
+                (*mv, *av) = decouple(*mv, *av);
+            }
+        }
+
+        // Apply the floor curves and transform to the time domain
+        let mut curve = vec![0.0; n / 2];
+        let mut blocks = Vec::with_capacity(channels);
+        for (c, spectrum) in spectra.iter_mut().enumerate() {
+            match &floors[c] {
+                Some(data) => {
+                    self.floors[mapping.submaps[mapping.mux[c]].0].synthesize(data, &mut curve);
+                    for (s, f) in spectrum.iter_mut().zip(&curve) {
+                        *s *= f;
+                    }
+                }
+                None => spectrum.fill(0.0),
+            }
+            let mut block = vec![0.0; n];
+            self.imdct[mode.long as usize].inverse(spectrum, &mut block);
+            blocks.push(block);
+        }
+
+        // Window bounds; a long block next to a short one uses a short slope
+        let short = self.info.blocksizes[0];
+        let (left_start, left_len) = if !mode.long || previous_long { (0, n / 2) } else { ((n - short) / 4, short / 2) };
+        let (right_start, right_end) = if !mode.long || next_long { (n / 2, n) } else { ((3 * n - short) / 4, (3 * n + short) / 4) };
+
+        let previous = self.previous.replace(blocks.iter().map(|b| b[right_start..right_end].to_vec()).collect());
+        let Some(previous) = previous else {
+            return Ok(vec![Vec::new(); channels]);
+        };
+        let overlap = previous.first().map_or(0, |p| p.len());
+        if overlap != left_len {
+            return Err("Vorbis block sizes do not overlap".to_string());
+        }
+        let slope = &self.slopes[(left_len != short / 2) as usize];
+        Ok(blocks
+            .into_iter()
+            .zip(previous)
+            .map(|(mut block, prev)| {
+                for i in 0..overlap {
+                    let v = &mut block[left_start + i];
+                    *v = *v * slope[i] + prev[i] * slope[overlap - 1 - i];
+                }
+                block.truncate(right_start);
+                block.drain(..left_start);
+                block
+            })
+            .collect())
+    }
+}
+
+/// Decode the first Vorbis stream in an Ogg file to interleaved f32 samples,
+/// within the output byte limit set by `set_limits`
+pub fn decode_vorbis(data: &[u8]) -> Result<AudioData, String> {
+    let pages = read_pages(data);
+    let serial = pages
+        .iter()
+        .find(|p| p.body.starts_with(b"\x01vorbis") && p.flags & super::ogg::FLAG_FIRST != 0)
+        .map(|p| p.serial)
+        .ok_or("No Vorbis stream found")?;
+    let packets = read_packets(&pages, serial);
+    if packets.len() < 3 {
+        return Err("Vorbis stream is missing its headers".to_string());
+    }
+    let info = read_info(&packets[0].data)?;
+    check_header(&packets[1].data, 3)?;
+    let mut decoder = VorbisDecoder::new(info, &packets[2].data)?;
+
+    let channels = info.channels as usize;
+    let mut budget = Budget::new();
+    let mut samples = Vec::new();
+    for packet in &packets[3..] {
+        // Damaged or empty packets are skipped, as a player would
+        let Ok(block) = decoder.decode_packet(&packet.data) else { continue };
+        let frames = block[0].len();
+        budget.output(frames * channels * 4)?;
+        samples.reserve(frames * channels);
+        for i in 0..frames {
+            samples.extend(block.iter().map(|ch| ch[i]));
+        }
+        // The final granule position marks where the audio really ends
+        if let (true, Some(granule)) = (packet.last, packet.granule) {
+            if let Ok(end) = usize::try_from(granule) {
+                samples.truncate(samples.len().min(end.saturating_mul(channels)));
+            }
+        }
+    }
+    Ok(AudioData { sample_rate: info.sample_rate, channels: info.channels, samples })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ogg::tests::page;
+    use crate::audio::ogg::FLAG_FIRST;
+
+    fn identification(channels: u8, blocksizes: u8) -> Vec<u8> {
+        let mut p = b"\x01vorbis\0\0\0\0".to_vec();
+        p.push(channels);
+        p.extend_from_slice(&48000u32.to_le_bytes());
+        p.extend_from_slice(&[0; 12]);
+        p.extend_from_slice(&[blocksizes, 1]);
+        p
+    }
+
+    #[test]
+    fn test_identification_header() {
+        let info = read_info(&identification(2, 0xB8)).unwrap();
+        assert_eq!(info, VorbisInfo { channels: 2, sample_rate: 48000, blocksizes: [256, 2048] });
+        // The short block may not exceed the long one
+        assert!(read_info(&identification(2, 0x8B)).is_err());
+        assert!(read_info(&identification(0, 0xB8)).is_err());
+
+        let header = identification(1, 0xB8);
+        let file = page(FLAG_FIRST, 0, 3, 0, &[header.len() as u8], &header);
+        assert!(decode_vorbis(&file).unwrap_err().contains("headers"));
+        assert!(decode_vorbis(b"OggS").is_err());
+    }
+
+    #[test]
+    fn test_decouple() {
+        assert_eq!(decouple(3.0, 1.0), (3.0, 2.0));
+        assert_eq!(decouple(3.0, -1.0), (2.0, 3.0));
+        assert_eq!(decouple(-3.0, 1.0), (-3.0, -2.0));
+        assert_eq!(decouple(-3.0, -1.0), (-2.0, -3.0));
+    }
+}
@@ -0,0 +1,143 @@
+//! Vorbis residues - the fine spectral detail, coded as VQ partitions
+
+use super::codebook::Codebook;
+use crate::compress::bits::BitReader;
+
+pub struct Residue {
+    kind: u32,
+    begin: usize,
+    end: usize,
+    partition_size: usize,
+    classifications: usize,
+    classbook: usize,
+    /// Book per class and pass, `None` where the pass codes nothing
+    books: Vec<[Option<usize>; 8]>,
+}
+
+impl Residue {
+    pub fn read(r: &mut BitReader, books: &[Codebook]) -> Result<Residue, String> {
+        let kind = r.bits(16)?;
+        if kind > 2 {
+            return Err(format!("Unsupported Vorbis residue type {}", kind));
+        }
+        let begin = r.bits(24)? as usize;
+        let end = r.bits(24)? as usize;
+        let partition_size = r.bits(24)? as usize + 1;
+        let classifications = r.bits(6)? as usize + 1;
+        let classbook = r.bits(8)? as usize;
+        let mut cascade = Vec::with_capacity(classifications);
+        for _ in 0..classifications {
+            let low = r.bits(3)?;
+            let high = if r.bits(1)? == 1 { r.bits(5)? } else { 0 };
+            cascade.push(high << 3 | low);
+        }
+        let mut residue_books = Vec::with_capacity(classifications);
+        for bits in cascade {
+            let mut passes = [None; 8];
+            for (pass, slot) in passes.iter_mut().enumerate() {
+                if bits & 1 << pass != 0 {
+                    let index = r.bits(8)? as usize;
+                    if !books.get(index).is_some_and(|b| b.has_lookup()) {
+                        return Err(format!("Vorbis residue references invalid codebook {}", index));
+                    }
+                    *slot = Some(index);
+                }
+            }
+            residue_books.push(passes);
+        }
+        let Some(class_book) = books.get(classbook) else {
+            return Err(format!("Vorbis residue references missing codebook {}", classbook));
+        };
+        if class_book.dimensions == 0 {
+            return Err("Vorbis residue classbook has no dimensions".to_string());
+        }
+        Ok(Residue { kind, begin, end, partition_size, classifications, classbook, books: residue_books })
+    }
+
+    /// Decode the residue vectors of `skip.len()` channels, each `n` long
+    ///
+    /// Channels flagged in `skip` are left at zero. Running out of packet is
+    /// not an error: the rest of the vectors stay zero.
+    pub fn decode(&self, r: &mut BitReader, books: &[Codebook], skip: &[bool], n: usize) -> Vec<Vec<f32>> {
+        let channels = skip.len();
+        if self.kind != 2 {
+            let mut vectors = vec![vec![0.0; n]; channels];
+            self.decode_partitions(r, books, skip, &mut vectors);
+            return vectors;
+        }
+        // Type 2 codes all channels as one interleaved vector
+        let mut interleaved = vec![vec![0.0; n * channels]];
+        if skip.iter().any(|&s| !s) {
+            self.decode_partitions(r, books, &[false], &mut interleaved);
+        }
+        (0..channels).map(|c| interleaved[0].iter().skip(c).step_by(channels).copied().collect()).collect()
+    }
+
+    fn decode_partitions(&self, r: &mut BitReader, books: &[Codebook], skip: &[bool], vectors: &mut [Vec<f32>]) {
+        let n = vectors.first().map_or(0, |v| v.len());
+        let begin = self.begin.min(n);
+        let end = self.end.min(n);
+        let partitions = (end - begin) / self.partition_size;
+        let classbook = &books[self.classbook];
+        let per_word = classbook.dimensions;
+        let mut classes = vec![vec![0usize; partitions + per_word]; skip.len()];
+        for pass in 0..8 {
+            let mut partition = 0;
+            while partition < partitions {
+                if pass == 0 {
+                    for (c, &s) in skip.iter().enumerate() {
+                        if s {
+                            continue;
+                        }
+                        let Some(mut word) = classbook.decode(r).map(|w| w as usize) else { return };
+                        for i in (0..per_word).rev() {
+                            classes[c][partition + i] = word % self.classifications;
+                            word /= self.classifications;
+                        }
+                    }
+                }
+                for _ in 0..per_word {
+                    if partition >= partitions {
+                        break;
+                    }
+                    for (c, &s) in skip.iter().enumerate() {
+                        if s {
+                            continue;
+                        }
+                        if let Some(book) = self.books[classes[c][partition]][pass] {
+                            let offset = begin + partition * self.partition_size;
+                            let out = &mut vectors[c][offset..offset + self.partition_size];
+                            if self.read_partition(r, &books[book], out).is_none() {
+                                return;
+                            }
+                        }
+                    }
+                    partition += 1;
+                }
+            }
+        }
+    }
+
+    fn read_partition(&self, r: &mut BitReader, book: &Codebook, out: &mut [f32]) -> Option<()> {
+        if self.kind == 0 {
+            // Each vector is spread across the partition with a stride
+            let step = out.len() / book.dimensions;
+            for i in 0..step {
+                for (j, v) in book.decode_vector(r)?.iter().enumerate() {
+                    out[i + j * step] += v;
+                }
+            }
+        } else {
+            let mut i = 0;
+            while i < out.len() {
+                let vector = book.decode_vector(r)?;
+                let len = vector.len().min(out.len() - i);
+                for (o, v) in out[i..i + len].iter_mut().zip(vector) {
+                    *o += v;
+                }
+                i += vector.len();
+            }
+        }
+        Some(())
+    }
+}
@@ -0,0 +1,391 @@
+//! WAV (RIFF/WAVE) parsing and PCM decoding
+//!
+//! Integer PCM of 8, 16, 24 and 32 bits and IEEE float of 32 and 64 bits are
+//! decoded, including WAVE_FORMAT_EXTENSIBLE files and RF64 files larger
+//! than 4 GiB, as are G.711 µ-law and A-law and IMA and Microsoft ADPCM. A data chunk that runs past
+//! the end of the file (as left by interrupted recordings) is decoded up to
+//! the last whole frame.
+
+use super::adpcm::{self, AdpcmFormat};
+use super::g711;
+use super::pcm::{self, Dither, SampleFormat};
+use super::AudioData;
+use crate::limits::Budget;
+use crate::utils::{read_u16_le, read_u32_le};
+
+pub const FORMAT_PCM: u16 = 0x0001;
+pub const FORMAT_MS_ADPCM: u16 = 0x0002;
+pub const FORMAT_FLOAT: u16 = 0x0003;
+pub const FORMAT_ALAW: u16 = 0x0006;
+pub const FORMAT_MULAW: u16 = 0x0007;
+pub const FORMAT_IMA_ADPCM: u16 = 0x0011;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// The `fmt ` chunk of a WAVE file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WavFormat {
+    /// Format tag, taken from the sub-format GUID for extensible files
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    /// Bytes per sample frame (or per block for compressed formats)
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+    /// Format-specific bytes following the common fields
+    pub extra: Vec<u8>,
+}
+
+/// A parsed WAVE file borrowing its sample data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WavFile<'a> {
+    pub format: WavFormat,
+    /// Frame count from the `fact` chunk, which compressed formats carry
+    pub frames: Option<u32>,
+    pub data: &'a [u8],
+}
+
+fn parse_format(chunk: &[u8]) -> Result<WavFormat, String> {
+    if chunk.len() < 16 {
+        return Err("WAV fmt chunk is too short".to_string());
+    }
+    let mut format = WavFormat {
+        format_tag: read_u16_le(chunk, 0),
+        channels: read_u16_le(chunk, 2),
+        sample_rate: read_u32_le(chunk, 4),
+        block_align: read_u16_le(chunk, 12),
+        bits_per_sample: read_u16_le(chunk, 14),
+        extra: Vec::new(),
+    };
+    if chunk.len() >= 18 {
+        let size = read_u16_le(chunk, 16) as usize;
+        format.extra = chunk[18..].get(..size).unwrap_or(&chunk[18..]).to_vec();
+    }
+    if format.format_tag == FORMAT_EXTENSIBLE {
+        // Valid bits (2), channel mask (4), then the sub-format GUID
+        if format.extra.len() < 22 {
+            return Err("WAV extensible format is too short".to_string());
+        }
+        format.format_tag = read_u16_le(&format.extra, 6);
+    }
+    if format.channels == 0 || format.block_align == 0 {
+        return Err("WAV format has no channels".to_string());
+    }
+    Ok(format)
+}
+
+/// Locate the format and sample data of a RIFF/WAVE or RF64 file
+pub fn read_wav(data: &[u8]) -> Result<WavFile<'_>, String> {
+    if data.len() < 12 || !matches!(&data[0..4], b"RIFF" | b"RF64") || &data[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+    let mut format = None;
+    let mut frames = None;
+    let mut rf64_data_size = None;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let id = &data[pos..pos + 4];
+        let mut size = read_u32_le(data, pos + 4) as u64;
+        let body = pos + 8;
+        match id {
+            b"ds64" if data.len() >= body + 16 => {
+                rf64_data_size = Some(read_u32_le(data, body + 8) as u64 | (read_u32_le(data, body + 12) as u64) << 32);
+            }
+            b"fact" if data.len() >= body + 4 => {
+                frames = Some(read_u32_le(data, body));
+            }
+            b"fmt " => {
+                let end = data.len().min(body.saturating_add(size as usize));
+                format = Some(parse_format(&data[body..end])?);
+            }
+            b"data" => {
+                if size == 0xFFFF_FFFF {
+                    size = rf64_data_size.unwrap_or(u64::MAX);
+                }
+                let format = format.ok_or("WAV data chunk precedes the fmt chunk")?;
+                let end = (body as u64).saturating_add(size).min(data.len() as u64) as usize;
+                return Ok(WavFile { format, frames, data: &data[body..end] });
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        let next = (body as u64).saturating_add(size).saturating_add(size & 1);
+        pos = usize::try_from(next).unwrap_or(usize::MAX);
+    }
+    Err("WAV file has no data chunk".to_string())
+}
+
+fn decode_pcm(format: &WavFormat, data: &[u8], budget: &mut Budget) -> Result<Vec<f32>, String> {
+    let width = (format.bits_per_sample as usize).div_ceil(8);
+    let channels = format.channels as usize;
+    if width == 0 || (format.block_align as usize) < width * channels {
+        return Err(format!("Invalid WAV block alignment {}", format.block_align));
+    }
+    let frames = data.len() / format.block_align as usize;
+    budget.output(frames * channels * 4)?;
+    let mut samples = Vec::with_capacity(frames * channels);
+    for frame in data.chunks_exact(format.block_align as usize) {
+        for s in frame[..width * channels].chunks_exact(width) {
+            let sample = match (format.format_tag, width) {
+                (FORMAT_PCM, 1) => (s[0] as f32 - 128.0) / 128.0,
+                (FORMAT_PCM, 2) => i16::from_le_bytes([s[0], s[1]]) as f32 / 32768.0,
+                (FORMAT_PCM, 3) => i32::from_le_bytes([0, s[0], s[1], s[2]]) as f32 / 2_147_483_648.0,
+                (FORMAT_PCM, 4) => i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as f32 / 2_147_483_648.0,
+                (FORMAT_FLOAT, 4) => f32::from_le_bytes([s[0], s[1], s[2], s[3]]),
+                (FORMAT_ALAW, 1) => g711::alaw_decode(s[0]) as f32 / 32768.0,
+                (FORMAT_MULAW, 1) => g711::mulaw_decode(s[0]) as f32 / 32768.0,
+                (FORMAT_FLOAT, 8) => f64::from_le_bytes([s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7]]) as f32,
+                _ => return Err(format!("Unsupported WAV format {:#06x} with {} bits per sample", format.format_tag, format.bits_per_sample)),
+            };
+            samples.push(sample);
+        }
+    }
+    Ok(samples)
+}
+
+fn decode_adpcm(wav: &WavFile, budget: &mut Budget) -> Result<Vec<f32>, String> {
+    let format = &wav.format;
+    let channels = format.channels as usize;
+    let block_align = format.block_align as usize;
+    let per_block = match format.format_tag {
+        FORMAT_IMA_ADPCM => adpcm::ima_samples_per_block(block_align, channels),
+        _ => adpcm::ms_samples_per_block(block_align, channels),
+    };
+    budget.output(wav.data.len().div_ceil(block_align).saturating_mul(per_block).saturating_mul(channels * 4))?;
+    let mut coefficients = adpcm::MS_COEFFICIENTS.to_vec();
+    if format.format_tag == FORMAT_MS_ADPCM && format.extra.len() >= 4 {
+        // The file's own table, which starts with the standard seven
+        let count = read_u16_le(&format.extra, 2) as usize;
+        coefficients = (0..count)
+            .map_while(|i| format.extra.get(4 + 4 * i..8 + 4 * i))
+            .map(|c| (read_u16_le(c, 0) as i16 as i32, read_u16_le(c, 2) as i16 as i32))
+            .collect();
+    }
+    let mut pcm = Vec::new();
+    for block in wav.data.chunks(block_align) {
+        if format.format_tag == FORMAT_IMA_ADPCM {
+            adpcm::decode_ima_block(block, channels, &mut pcm)?;
+        } else {
+            adpcm::decode_ms_block(block, channels, &coefficients, &mut pcm)?;
+        }
+    }
+    let mut frames = pcm.len() / channels;
+    if let Some(total) = wav.frames {
+        frames = frames.min(total as usize);
+    }
+    Ok(pcm[..frames * channels].iter().map(|&s| s as f32 / 32768.0).collect())
+}
+
+/// Decode a WAV file to interleaved f32 samples, within the output byte limit
+/// set by `set_limits`
+pub fn decode_wav(data: &[u8]) -> Result<AudioData, String> {
+    let wav = read_wav(data)?;
+    let mut budget = Budget::new();
+    let samples = match wav.format.format_tag {
+        FORMAT_IMA_ADPCM | FORMAT_MS_ADPCM => decode_adpcm(&wav, &mut budget)?,
+        _ => decode_pcm(&wav.format, wav.data, &mut budget)?,
+    };
+    Ok(AudioData { sample_rate: wav.format.sample_rate, channels: wav.format.channels, samples })
+}
+
+/// Assemble a RIFF/WAVE file; `frames` adds a `fact` chunk
+fn write_wav(format: &WavFormat, byte_rate: u32, frames: Option<u32>, data: &[u8]) -> Vec<u8> {
+    let mut fmt = Vec::with_capacity(18 + format.extra.len());
+    fmt.extend_from_slice(&format.format_tag.to_le_bytes());
+    fmt.extend_from_slice(&format.channels.to_le_bytes());
+    fmt.extend_from_slice(&format.sample_rate.to_le_bytes());
+    fmt.extend_from_slice(&byte_rate.to_le_bytes());
+    fmt.extend_from_slice(&format.block_align.to_le_bytes());
+    fmt.extend_from_slice(&format.bits_per_sample.to_le_bytes());
+    if format.format_tag != FORMAT_PCM {
+        fmt.extend_from_slice(&(format.extra.len() as u16).to_le_bytes());
+        fmt.extend_from_slice(&format.extra);
+    }
+
+    let mut out = b"RIFF\0\0\0\0WAVE".to_vec();
+    let fact = frames.map(u32::to_le_bytes);
+    let chunks = [(b"fmt ", Some(&fmt[..])), (b"fact", fact.as_ref().map(|f| &f[..])), (b"data", Some(data))];
+    for (id, body) in chunks {
+        let Some(body) = body else { continue };
+        out.extend_from_slice(id);
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+    let riff_size = out.len() as u32 - 8;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    out
+}
+
+fn check_frames(audio: &AudioData) -> Result<(), String> {
+    let channels = audio.channels as usize;
+    if channels == 0 || !audio.samples.len().is_multiple_of(channels) {
+        return Err("Sample count is not a multiple of the channel count".to_string());
+    }
+    Ok(())
+}
+
+/// Encode interleaved f32 samples as an integer PCM or float WAV file
+pub fn encode_wav(audio: &AudioData, format: SampleFormat, dither: Dither) -> Result<Vec<u8>, String> {
+    check_frames(audio)?;
+    let block_align = audio.channels as usize * format.bytes();
+    if block_align > u16::MAX as usize {
+        return Err(format!("Too many channels for WAV: {}", audio.channels));
+    }
+    let format_tag = if format == SampleFormat::F32 { FORMAT_FLOAT } else { FORMAT_PCM };
+    let wav_format = WavFormat { format_tag, channels: audio.channels, sample_rate: audio.sample_rate, block_align: block_align as u16, bits_per_sample: format.bits() as u16, extra: Vec::new() };
+    let data = pcm::to_pcm(&audio.samples, audio.channels as usize, format, dither);
+    let byte_rate = audio.sample_rate.saturating_mul(block_align as u32);
+    // Float data requires a fact chunk
+    let frames = (format == SampleFormat::F32).then(|| audio.frames() as u32);
+    Ok(write_wav(&wav_format, byte_rate, frames, &data))
+}
+
+/// Encode interleaved f32 samples as an ADPCM WAV file
+///
+/// Blocks hold 256 bytes per channel per 11025 Hz of sample rate, the
+/// common convention for both formats.
+pub fn encode_adpcm_wav(audio: &AudioData, format: AdpcmFormat) -> Result<Vec<u8>, String> {
+    check_frames(audio)?;
+    let channels = audio.channels as usize;
+    let block_align = 256 * channels * (audio.sample_rate as usize / 11025).clamp(1, 4);
+    if block_align > u16::MAX as usize {
+        return Err(format!("Too many channels for ADPCM: {}", channels));
+    }
+    let pcm: Vec<i16> = pcm::requantize(&audio.samples, channels, 16, Dither::None).into_iter().map(|s| s as i16).collect();
+    let (format_tag, per_block, data) = match format {
+        AdpcmFormat::Ima => (FORMAT_IMA_ADPCM, adpcm::ima_samples_per_block(block_align, channels), adpcm::encode_ima(&pcm, channels, block_align)),
+        AdpcmFormat::Ms => (FORMAT_MS_ADPCM, adpcm::ms_samples_per_block(block_align, channels), adpcm::encode_ms(&pcm, channels, block_align)),
+    };
+    let mut extra = (per_block as u16).to_le_bytes().to_vec();
+    if format == AdpcmFormat::Ms {
+        extra.extend_from_slice(&(adpcm::MS_COEFFICIENTS.len() as u16).to_le_bytes());
+        for (c1, c2) in adpcm::MS_COEFFICIENTS {
+            extra.extend_from_slice(&(c1 as i16).to_le_bytes());
+            extra.extend_from_slice(&(c2 as i16).to_le_bytes());
+        }
+    }
+    let wav_format = WavFormat { format_tag, channels: audio.channels, sample_rate: audio.sample_rate, block_align: block_align as u16, bits_per_sample: 4, extra };
+    let byte_rate = (audio.sample_rate as u64 * block_align as u64 / per_block as u64) as u32;
+    Ok(write_wav(&wav_format, byte_rate, Some(audio.frames() as u32), &data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::Limits;
+
+    /// Build a WAV file from an `fmt ` chunk body and sample bytes
+    fn wav(fmt: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = b"RIFF\0\0\0\0WAVE".to_vec();
+        for (id, body) in [(b"LIST", &b"odd"[..]), (b"fmt ", fmt), (b"data", data)] {
+            out.extend_from_slice(id);
+            out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            out.extend_from_slice(body);
+            if body.len() % 2 == 1 {
+                out.push(0);
+            }
+        }
+        let riff_size = out.len() as u32 - 8;
+        out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        out
+    }
+
+    fn fmt(tag: u16, channels: u16, bits: u16) -> Vec<u8> {
+        let align = channels * bits / 8;
+        let mut f = Vec::new();
+        for v in [tag, channels] {
+            f.extend_from_slice(&v.to_le_bytes());
+        }
+        f.extend_from_slice(&44100u32.to_le_bytes());
+        f.extend_from_slice(&(44100 * align as u32).to_le_bytes());
+        f.extend_from_slice(&align.to_le_bytes());
+        f.extend_from_slice(&bits.to_le_bytes());
+        f
+    }
+
+    #[test]
+    fn test_decode_pcm_depths() {
+        let audio = decode_wav(&wav(&fmt(FORMAT_PCM, 2, 16), &[0x00, 0x80, 0xFF, 0x7F, 0x00, 0x40, 0x00, 0x00])).unwrap();
+        assert_eq!((audio.sample_rate, audio.channels, audio.frames()), (44100, 2, 2));
+        assert_eq!(audio.samples, [-1.0, 32767.0 / 32768.0, 0.5, 0.0]);
+
+        let audio = decode_wav(&wav(&fmt(FORMAT_PCM, 1, 8), &[0, 128, 192])).unwrap();
+        assert_eq!(audio.samples, [-1.0, 0.0, 0.5]);
+
+        let audio = decode_wav(&wav(&fmt(FORMAT_PCM, 1, 24), &[0, 0, 0xC0, 0, 0, 0x40])).unwrap();
+        assert_eq!(audio.samples, [-0.5, 0.5]);
+
+        let mut data = 0.25f32.to_le_bytes().to_vec();
+        data.extend_from_slice(&(-0.75f32).to_le_bytes());
+        assert_eq!(decode_wav(&wav(&fmt(FORMAT_FLOAT, 1, 32), &data)).unwrap().samples, [0.25, -0.75]);
+
+        let audio = decode_wav(&wav(&fmt(FORMAT_MULAW, 1, 8), &[0xFF, 0x80])).unwrap();
+        assert_eq!(audio.samples, [0.0, 32124.0 / 32768.0]);
+        let audio = decode_wav(&wav(&fmt(FORMAT_ALAW, 1, 8), &[0xD5, 0x2A])).unwrap();
+        assert_eq!(audio.samples, [8.0 / 32768.0, -32256.0 / 32768.0]);
+    }
+
+    #[test]
+    fn test_extensible_and_truncated() {
+        let mut f = fmt(FORMAT_EXTENSIBLE, 1, 32);
+        f.extend_from_slice(&22u16.to_le_bytes());
+        f.extend_from_slice(&32u16.to_le_bytes());
+        f.extend_from_slice(&4u32.to_le_bytes());
+        f.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0x10, 0, 0x80, 0, 0, 0xAA, 0, 0x38, 0x9B, 0x71]);
+        let mut file = wav(&f, &1.0f32.to_le_bytes());
+        assert_eq!(read_wav(&file).unwrap().format.format_tag, FORMAT_FLOAT);
+
+        // A data chunk claiming more than is present keeps the whole frames
+        let len = file.len();
+        file[len - 8..len - 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        file.extend_from_slice(&[0, 0]);
+        assert_eq!(decode_wav(&file).unwrap().samples, [1.0]);
+
+        assert!(decode_wav(b"RIFF\0\0\0\0WAVE").is_err());
+        assert!(decode_wav(&wav(&fmt(0x55, 1, 16), &[0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let audio = AudioData { sample_rate: 8000, channels: 2, samples: vec![0.5, -0.5, 0.25, -1.0, 0.0, 0.75] };
+        for format in [SampleFormat::U8, SampleFormat::I16, SampleFormat::I24, SampleFormat::F32] {
+            let file = encode_wav(&audio, format, Dither::None).unwrap();
+            let wav = read_wav(&file).unwrap();
+            assert_eq!(wav.format.bits_per_sample as u32, format.bits());
+            assert_eq!(decode_wav(&file).unwrap(), audio);
+        }
+        assert!(encode_wav(&AudioData { samples: vec![0.0; 3], ..audio }, SampleFormat::I16, Dither::Tpdf).is_err());
+    }
+
+    #[test]
+    fn test_adpcm_wav_round_trip() {
+        let samples: Vec<f32> = (0..3000).map(|i| (i as f32 * 0.03).sin() * 0.4).collect();
+        let audio = AudioData { sample_rate: 22050, channels: 2, samples };
+        for format in [AdpcmFormat::Ima, AdpcmFormat::Ms] {
+            let file = encode_adpcm_wav(&audio, format).unwrap();
+            let wav = read_wav(&file).unwrap();
+            assert_eq!((wav.format.block_align, wav.frames), (1024, Some(1500)));
+            // The fact chunk trims the padding of the last block
+            let decoded = decode_wav(&file).unwrap();
+            assert_eq!((decoded.channels, decoded.sample_rate, decoded.samples.len()), (2, 22050, 3000));
+            let error = audio.samples.iter().zip(&decoded.samples).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+            assert!(error < 0.02, "{:?} error {}", format, error);
+        }
+    }
+
+    #[test]
+    fn test_output_limit() {
+        let budget = |bytes| Budget::with_limits(Limits { max_output_bytes: bytes, ..Limits::new() });
+        let file = wav(&fmt(FORMAT_PCM, 2, 16), &[0; 8]);
+        let pcm = read_wav(&file).unwrap();
+        assert!(decode_pcm(&pcm.format, pcm.data, &mut budget(16)).is_ok());
+        assert_eq!(decode_pcm(&pcm.format, pcm.data, &mut budget(15)), Err("Decoded output takes more than the limit of 15 bytes".to_string()));
+
+        // ADPCM is charged for whole blocks before decoding
+        let audio = AudioData { sample_rate: 22050, channels: 1, samples: vec![0.0; 1000] };
+        let file = encode_adpcm_wav(&audio, AdpcmFormat::Ima).unwrap();
+        assert!(decode_adpcm(&read_wav(&file).unwrap(), &mut budget(3999)).is_err());
+    }
+}
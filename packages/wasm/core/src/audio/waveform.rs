@@ -0,0 +1,43 @@
+//! Min/max peak extraction for waveform displays
+
+/// Minimum and maximum of each channel over `buckets` equal spans of frames
+///
+/// The result holds `buckets * channels` (min, max) pairs, bucket by bucket
+/// with channels interleaved as in the input. With fewer frames than
+/// buckets, each bucket covers the nearest frame; no audio gives zeros.
+pub fn waveform_peaks(samples: &[f32], channels: usize, buckets: usize) -> Result<Vec<f32>, String> {
+    if channels == 0 || !samples.len().is_multiple_of(channels) {
+        return Err("Sample count is not a multiple of the channel count".to_string());
+    }
+    let frames = samples.len() / channels;
+    let mut peaks = vec![0.0; buckets * channels * 2];
+    if frames == 0 {
+        return Ok(peaks);
+    }
+    for (bucket, out) in peaks.chunks_exact_mut(channels * 2).enumerate() {
+        let start = bucket * frames / buckets;
+        let end = ((bucket + 1) * frames / buckets).max(start + 1);
+        for (c, pair) in out.chunks_exact_mut(2).enumerate() {
+            let span = samples[start * channels..end * channels].iter().skip(c).step_by(channels);
+            let (min, max) = span.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+            pair.copy_from_slice(&[min, max]);
+        }
+    }
+    Ok(peaks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peaks() {
+        let samples = [0.1, -0.5, 0.9, 0.0, -0.3, 0.2, 0.4, -1.0];
+        assert_eq!(waveform_peaks(&samples, 1, 2).unwrap(), [-0.5, 0.9, -1.0, 0.4]);
+        assert_eq!(waveform_peaks(&samples, 2, 2).unwrap(), [0.1, 0.9, -0.5, 0.0, -0.3, 0.4, -1.0, 0.2]);
+        // More buckets than frames repeats the nearest frame
+        assert_eq!(waveform_peaks(&[0.5, -0.5], 1, 4).unwrap(), [0.5, 0.5, 0.5, 0.5, -0.5, -0.5, -0.5, -0.5]);
+        assert_eq!(waveform_peaks(&[], 2, 2).unwrap(), [0.0; 8]);
+        assert!(waveform_peaks(&samples, 3, 2).is_err());
+    }
+}
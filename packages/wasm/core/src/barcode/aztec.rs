@@ -0,0 +1,322 @@
+//! Aztec Code symbols, compact and full-range
+
+use crate::ecc::GaloisField;
+use super::ModuleMatrix;
+
+/// Mode latches and shifts, in the code space of the mode they are sent from
+const UPPER_TO_LOWER: u16 = 28;
+const TO_DIGIT: u16 = 30;
+const BINARY_SHIFT: u16 = 31;
+const LOWER_TO_UPPER_SHIFT: u16 = 28;
+const DIGIT_TO_UPPER: u16 = 14;
+const DIGIT_TO_UPPER_SHIFT: u16 = 15;
+
+/// Share of the message, in percent, to add as check words (plus 11 bits)
+const MIN_ECC_PERCENT: usize = 33;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Upper,
+    Lower,
+    Digit,
+}
+
+struct BitWriter(Vec<bool>);
+
+impl BitWriter {
+    fn push(&mut self, value: u32, bits: u32) {
+        self.0.extend((0..bits).rev().map(|i| value >> i & 1 != 0));
+    }
+
+    fn code(&mut self, mode: Mode, value: u16) {
+        self.push(value as u32, if mode == Mode::Digit { 4 } else { 5 });
+    }
+}
+
+/// Code of `c` in `mode`, if it has one
+fn code_in(mode: Mode, c: u8) -> Option<u16> {
+    match (mode, c) {
+        (_, b' ') => Some(1),
+        (Mode::Upper, b'A'..=b'Z') => Some((c - b'A') as u16 + 2),
+        (Mode::Lower, b'a'..=b'z') => Some((c - b'a') as u16 + 2),
+        (Mode::Digit, b'0'..=b'9') => Some((c - b'0') as u16 + 2),
+        (Mode::Digit, b',') => Some(12),
+        (Mode::Digit, b'.') => Some(13),
+        _ => None,
+    }
+}
+
+/// Text modes: upper, lower and digit, with binary shifts for every other byte
+fn encode_bits(data: &[u8]) -> Vec<bool> {
+    let mut out = BitWriter(Vec::new());
+    let mut mode = Mode::Upper;
+    let mut i = 0;
+    while i < data.len() {
+        let c = data[i];
+        if let Some(code) = code_in(mode, c) {
+            out.code(mode, code);
+            i += 1;
+            continue;
+        }
+        let next = match c {
+            b'A'..=b'Z' => Some(Mode::Upper),
+            b'a'..=b'z' => Some(Mode::Lower),
+            b'0'..=b'9' | b',' | b'.' => Some(Mode::Digit),
+            _ => None,
+        };
+        match (mode, next) {
+            // A lone capital among lowercase or digits is shifted rather than latched
+            (Mode::Lower, Some(Mode::Upper)) | (Mode::Digit, Some(Mode::Upper)) if !data.get(i + 1).is_some_and(u8::is_ascii_uppercase) => {
+                out.code(mode, if mode == Mode::Lower { LOWER_TO_UPPER_SHIFT } else { DIGIT_TO_UPPER_SHIFT });
+                out.code(Mode::Upper, code_in(Mode::Upper, c).unwrap_or(0));
+                i += 1;
+            }
+            (_, Some(next)) => {
+                // Lower and digit reach upper through the digit latch
+                match (mode, next) {
+                    (Mode::Upper, Mode::Lower) => out.code(mode, UPPER_TO_LOWER),
+                    (Mode::Digit, Mode::Lower) => {
+                        out.code(mode, DIGIT_TO_UPPER);
+                        out.code(Mode::Upper, UPPER_TO_LOWER);
+                    }
+                    (Mode::Lower, Mode::Upper) => {
+                        out.code(mode, TO_DIGIT);
+                        out.code(Mode::Digit, DIGIT_TO_UPPER);
+                    }
+                    (Mode::Digit, Mode::Upper) => out.code(mode, DIGIT_TO_UPPER),
+                    (_, Mode::Digit) => out.code(mode, TO_DIGIT),
+                    _ => {}
+                }
+                mode = next;
+            }
+            (_, None) => {
+                let len = data[i..].iter().take_while(|&&c| code_in(Mode::Upper, c).or(code_in(Mode::Lower, c)).or(code_in(Mode::Digit, c)).is_none()).count().min(31 + 2047);
+                if mode == Mode::Digit {
+                    out.code(mode, DIGIT_TO_UPPER);
+                    mode = Mode::Upper;
+                }
+                out.code(mode, BINARY_SHIFT);
+                if len <= 31 {
+                    out.push(len as u32, 5);
+                } else {
+                    out.push(0, 5);
+                    out.push(len as u32 - 31, 11);
+                }
+                for &byte in &data[i..i + len] {
+                    out.push(byte as u32, 8);
+                }
+                i += len;
+            }
+        }
+    }
+    out.0
+}
+
+fn total_bits(layers: u32, compact: bool) -> usize {
+    ((if compact { 88 } else { 112 } + 16 * layers) * layers) as usize
+}
+
+fn word_size(layers: u32) -> usize {
+    match layers {
+        1..=2 => 6,
+        3..=8 => 8,
+        9..=22 => 10,
+        _ => 12,
+    }
+}
+
+fn field(word_size: usize) -> GaloisField {
+    match word_size {
+        4 => GaloisField::new(0x13, 16, 1),
+        6 => GaloisField::new(0x43, 64, 1),
+        8 => GaloisField::new(0x12d, 256, 1),
+        10 => GaloisField::new(0x409, 1024, 1),
+        _ => GaloisField::new(0x1069, 4096, 1),
+    }
+}
+
+/// Split bits into words, avoiding all-zero and all-one words by stuffing a
+/// complementary bit after `word_size - 1` equal ones; the tail is padded with ones
+fn stuff_bits(bits: &[bool], word_size: usize) -> Vec<u16> {
+    let mask = (1u16 << word_size) - 2;
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < bits.len() {
+        let word = (0..word_size).fold(0u16, |w, j| w << 1 | (bits.get(i + j).copied().unwrap_or(true) as u16));
+        if word & mask == mask {
+            words.push(word & mask);
+            i += word_size - 1;
+        } else if word & mask == 0 {
+            words.push(word | 1);
+            i += word_size - 1;
+        } else {
+            words.push(word);
+            i += word_size;
+        }
+    }
+    words
+}
+
+/// Words followed by their check words, as bits right-aligned in `total` bits
+fn with_check_words(words: &[u16], word_size: usize, total: usize) -> Vec<bool> {
+    let count = total / word_size;
+    let check = field(word_size).encode(words, count - words.len());
+    let mut out = BitWriter(vec![false; total % word_size]);
+    for &word in words.iter().chain(&check) {
+        out.push(word as u32, word_size as u32);
+    }
+    out.0
+}
+
+/// Encode bytes as the smallest Aztec symbol that holds them with about a
+/// third of it spent on error correction
+pub(super) fn encode_aztec(data: &[u8]) -> Result<ModuleMatrix, String> {
+    let bits = encode_bits(data);
+    let ecc_bits = bits.len() * MIN_ECC_PERCENT / 100 + 11;
+    // Compact symbols have 1-4 layers; full-range ones start where they become smaller
+    let (layers, compact, words) = (1..=4)
+        .map(|l| (l, true))
+        .chain((4..=32).map(|l| (l, false)))
+        .filter(|&(l, compact)| bits.len() + ecc_bits <= total_bits(l, compact))
+        .find_map(|(l, compact)| {
+            let (size, words) = (word_size(l), stuff_bits(&bits, word_size(l)));
+            let usable = total_bits(l, compact) / size * size;
+            let fits = words.len() * size + ecc_bits <= usable && words.len() <= if compact { 64 } else { 2048 };
+            fits.then_some((l, compact, words))
+        })
+        .ok_or("Data is too long for an Aztec symbol")?;
+
+    let size = word_size(layers);
+    let message = with_check_words(&words, size, total_bits(layers, compact));
+    let mut mode = BitWriter(Vec::new());
+    if compact {
+        mode.push(layers - 1, 2);
+        mode.push(words.len() as u32 - 1, 6);
+    } else {
+        mode.push(layers - 1, 5);
+        mode.push(words.len() as u32 - 1, 11);
+    }
+    let mode_words: Vec<u16> = mode.0.chunks(4).map(|c| c.iter().fold(0, |w, &b| w << 1 | b as u16)).collect();
+    let mode = with_check_words(&mode_words, 4, if compact { 28 } else { 40 });
+
+    // Full-range symbols add reference grid lines every 16 modules from the center
+    let base = if compact { 11 + layers * 4 } else { 14 + layers * 4 };
+    let (matrix_size, map): (u32, Vec<u32>) = if compact {
+        (base, (0..base).collect())
+    } else {
+        let matrix_size = base + 1 + 2 * ((base / 2 - 1) / 15);
+        let (orig_center, center) = (base / 2, matrix_size / 2);
+        let mut map = vec![0; base as usize];
+        for i in 0..orig_center {
+            let offset = i + i / 15;
+            map[(orig_center - i - 1) as usize] = center - offset - 1;
+            map[(orig_center + i) as usize] = center + offset + 1;
+        }
+        (matrix_size, map)
+    };
+    let mut matrix = ModuleMatrix::new(matrix_size, matrix_size);
+    let m = |i: u32| map[i as usize];
+
+    // Data spirals inwards layer by layer, two modules thick, one side at a time
+    let mut offset = 0;
+    for i in 0..layers {
+        let row = (layers - i) * 4 + if compact { 9 } else { 12 };
+        for j in 0..row {
+            for k in 0..2 {
+                let at = |side: u32| message[offset + (side * row * 2 + j * 2 + k) as usize];
+                let far = base - 1 - i * 2;
+                if at(0) {
+                    matrix.set(m(i * 2 + k), m(i * 2 + j), true);
+                }
+                if at(1) {
+                    matrix.set(m(i * 2 + j), m(far - k), true);
+                }
+                if at(2) {
+                    matrix.set(m(far - k), m(far - j), true);
+                }
+                if at(3) {
+                    matrix.set(m(far - j), m(i * 2 + k), true);
+                }
+            }
+        }
+        offset += (row * 8) as usize;
+    }
+
+    let center = matrix_size / 2;
+    if compact {
+        for i in 0..7 {
+            let at = center - 3 + i;
+            let i = i as usize;
+            matrix.set(at, center - 5, mode[i]);
+            matrix.set(center + 5, at, mode[i + 7]);
+            matrix.set(at, center + 5, mode[20 - i]);
+            matrix.set(center - 5, at, mode[27 - i]);
+        }
+    } else {
+        for i in 0..10 {
+            let at = center - 5 + i + i / 5;
+            let i = i as usize;
+            matrix.set(at, center - 7, mode[i]);
+            matrix.set(center + 7, at, mode[i + 10]);
+            matrix.set(at, center + 7, mode[29 - i]);
+            matrix.set(center - 7, at, mode[39 - i]);
+        }
+    }
+
+    // Bull's eye rings and orientation marks
+    let eye = if compact { 5 } else { 7 };
+    for r in (0..eye).step_by(2) {
+        for j in center - r..=center + r {
+            for (x, y) in [(j, center - r), (j, center + r), (center - r, j), (center + r, j)] {
+                matrix.set(x, y, true);
+            }
+        }
+    }
+    for (x, y) in [(center - eye, center - eye), (center - eye + 1, center - eye), (center - eye, center - eye + 1), (center + eye, center - eye), (center + eye, center - eye + 1), (center + eye, center + eye - 1)] {
+        matrix.set(x, y, true);
+    }
+
+    if !compact {
+        for j in (0..base / 2 - 1).step_by(15).map(|i| i / 15 * 16) {
+            for k in ((matrix_size / 2) & 1..matrix_size).step_by(2) {
+                for (x, y) in [(center - j, k), (center + j, k), (k, center - j), (k, center + j)] {
+                    matrix.set(x, y, true);
+                }
+            }
+        }
+    }
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bits: &[bool]) -> String {
+        bits.iter().map(|&b| if b { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn test_high_level_encoding() {
+        // "A" upper, latch lower, "b", latch digit, "1", upper shift "C", binary shift 1 byte "!"
+        let bits = encode_bits(b"Ab1C!");
+        assert_eq!(read(&bits), ["00010", "11100", "00011", "11110", "0011", "1111", "00100", "1110", "11111", "00001", "00100001"].concat());
+        assert_eq!(stuff_bits(&[true; 7], 6), [0b111110, 0b111110]);
+        assert_eq!(stuff_bits(&[false; 5], 6), [0b000001]);
+    }
+
+    #[test]
+    fn test_symbol() {
+        let matrix = encode_aztec(b"Aztec").unwrap();
+        assert_eq!((matrix.width, matrix.height), (15, 15));
+        // Bull's eye: dark center, light then dark rings
+        assert!(matrix.get(7, 7) && !matrix.get(8, 7) && matrix.get(9, 9) && !matrix.get(10, 7) && matrix.get(11, 3));
+
+        let full = encode_aztec("Aztec ".repeat(60).as_bytes()).unwrap();
+        assert!(full.width > 27 && full.width % 2 == 1);
+        // Reference grid through the center row, alternating
+        let center = full.width / 2;
+        assert!((0..full.width).all(|x| full.get(x, center) == (x + center).is_multiple_of(2) || (center - 7..=center + 7).contains(&x)));
+        assert!(encode_aztec(&[0; 4000]).is_err());
+    }
+}
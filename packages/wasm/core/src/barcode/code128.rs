@@ -0,0 +1,129 @@
+//! Code 128 with automatic code set selection
+
+/// Bar/space widths of symbol values 0-105
+#[rustfmt::skip]
+const PATTERNS: [&[u8; 6]; 106] = [
+    b"212222", b"222122", b"222221", b"121223", b"121322", b"131222", b"122213", b"122312", b"132212", b"221213",
+    b"221312", b"231212", b"112232", b"122132", b"122231", b"113222", b"123122", b"123221", b"223211", b"221132",
+    b"221231", b"213212", b"223112", b"312131", b"311222", b"321122", b"321221", b"312212", b"322112", b"322211",
+    b"212123", b"212321", b"232121", b"111323", b"131123", b"131321", b"112313", b"132113", b"132311", b"211313",
+    b"231113", b"231311", b"112133", b"112331", b"132131", b"113123", b"113321", b"133121", b"313121", b"211331",
+    b"231131", b"213113", b"213311", b"213131", b"311123", b"311321", b"331121", b"312113", b"312311", b"332111",
+    b"314111", b"221411", b"431111", b"111224", b"111422", b"121124", b"121421", b"141122", b"141221", b"112214",
+    b"112412", b"122114", b"122411", b"142112", b"142211", b"241211", b"221114", b"413111", b"241112", b"134111",
+    b"111242", b"121142", b"121241", b"114212", b"124112", b"124211", b"411212", b"421112", b"421211", b"212141",
+    b"214121", b"412121", b"111143", b"111341", b"131141", b"114113", b"114311", b"411113", b"411311", b"113141",
+    b"114131", b"311141", b"411131", b"211412", b"211214", b"211232",
+];
+const STOP: &[u8; 7] = b"2331112";
+
+const CODE_C: u8 = 99;
+const CODE_B: u8 = 100;
+const CODE_A: u8 = 101;
+const START_A: u8 = 103;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CodeSet {
+    A,
+    B,
+    C,
+}
+
+impl CodeSet {
+    /// Set needed for a non-digit-pair character: control codes only exist
+    /// in A, lowercase only in B
+    fn for_char(c: u8, current: CodeSet) -> CodeSet {
+        match c {
+            0..=31 => CodeSet::A,
+            96.. => CodeSet::B,
+            _ if current == CodeSet::C => CodeSet::B,
+            _ => current,
+        }
+    }
+
+    fn value(self, c: u8) -> u8 {
+        match (self, c) {
+            (CodeSet::A, 0..=31) => c + 64,
+            _ => c - 32,
+        }
+    }
+
+    fn switch_code(self) -> u8 {
+        match self {
+            CodeSet::A => CODE_A,
+            CodeSet::B => CODE_B,
+            CodeSet::C => CODE_C,
+        }
+    }
+}
+
+/// Symbol values from start code to check symbol, switching to code set C
+/// for runs of four or more digits
+fn symbols(text: &[u8]) -> Vec<u8> {
+    let digits_at = |i: usize| text[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+    let mut values = Vec::new();
+    let mut set = None;
+    let mut i = 0;
+    while i < text.len() {
+        let run = digits_at(i);
+        // An odd run leaves its first digit in A or B so the rest pairs up
+        let pairs = match set {
+            Some(CodeSet::C) => run >= 2,
+            None => run >= 2 && run.is_multiple_of(2) && (run >= 4 || run == text.len()),
+            Some(_) => run >= 4 && run.is_multiple_of(2),
+        };
+        let wanted = if pairs { CodeSet::C } else { CodeSet::for_char(text[i], set.unwrap_or(CodeSet::B)) };
+        if set != Some(wanted) {
+            values.push(match set {
+                None => START_A + wanted as u8,
+                Some(_) => wanted.switch_code(),
+            });
+            set = Some(wanted);
+        }
+        if wanted == CodeSet::C {
+            values.push((text[i] - b'0') * 10 + text[i + 1] - b'0');
+            i += 2;
+        } else {
+            values.push(wanted.value(text[i]));
+            i += 1;
+        }
+    }
+    let check = values.iter().enumerate().map(|(i, &v)| i.max(1) as u32 * v as u32).sum::<u32>() % 103;
+    values.push(check as u8);
+    values
+}
+
+/// Encode ASCII text as Code 128 modules (true for a bar)
+pub(super) fn encode_code128(text: &str) -> Result<Vec<bool>, String> {
+    if text.is_empty() || !text.is_ascii() {
+        return Err("Code 128 encodes 1 or more ASCII characters".to_string());
+    }
+    let mut modules = Vec::new();
+    for pattern in symbols(text.as_bytes()).iter().map(|&v| &PATTERNS[v as usize][..]).chain([&STOP[..]]) {
+        for (i, &width) in pattern.iter().enumerate() {
+            modules.extend(std::iter::repeat_n(i % 2 == 0, (width - b'0') as usize));
+        }
+    }
+    Ok(modules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_sets() {
+        assert!(PATTERNS.iter().all(|p| p.iter().map(|w| (w - b'0') as u32).sum::<u32>() == 11));
+        // All digits: start C, pairs, check (105 + 12 + 2 * 34) % 103
+        assert_eq!(symbols(b"1234"), [105, 12, 34, 82]);
+        // Odd digit run: the first digit stays in B
+        assert_eq!(&symbols(b"a12345")[..5], [104, 65, 17, 99, 23]);
+        assert_eq!(symbols(b"AB"), [104, 33, 34, (104 + 33 + 68) % 103]);
+        // Control characters need code set A
+        assert_eq!(&symbols(b"A\tb")[..6], [104, 33, 101, 73, 100, 66]);
+        let modules = encode_code128("1234").unwrap();
+        assert_eq!(modules.len(), 4 * 11 + 13);
+        assert_eq!(&modules[..11], [true, true, false, true, false, false, true, true, true, false, false]);
+        assert!(encode_code128("é").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Data Matrix (ECC 200) square symbols
+
+use crate::ecc::GaloisField;
+use super::ModuleMatrix;
+
+/// Square symbol sizes: (side, data codewords, check codewords, regions per side, interleaved blocks)
+const SYMBOLS: [(u32, usize, usize, u32, usize); 24] = [
+    (10, 3, 5, 1, 1), (12, 5, 7, 1, 1), (14, 8, 10, 1, 1), (16, 12, 12, 1, 1), (18, 18, 14, 1, 1), (20, 22, 18, 1, 1),
+    (22, 30, 20, 1, 1), (24, 36, 24, 1, 1), (26, 44, 28, 1, 1), (32, 62, 36, 2, 1), (36, 86, 42, 2, 1), (40, 114, 48, 2, 1),
+    (44, 144, 56, 2, 1), (48, 174, 68, 2, 1), (52, 204, 84, 2, 2), (64, 280, 112, 4, 2), (72, 368, 144, 4, 4), (80, 456, 192, 4, 4),
+    (88, 576, 224, 4, 4), (96, 696, 272, 4, 4), (104, 816, 336, 4, 6), (120, 1050, 408, 6, 6), (132, 1304, 496, 6, 8), (144, 1558, 620, 6, 10),
+];
+
+const PAD: u8 = 129;
+const UPPER_SHIFT: u8 = 235;
+
+/// ASCII encodation: digit pairs share a codeword, bytes above 127 take an upper shift
+fn encode_ascii(data: &[u8]) -> Vec<u8> {
+    let mut codewords = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        match (data[i], data.get(i + 1)) {
+            (a @ b'0'..=b'9', Some(&b @ b'0'..=b'9')) => {
+                codewords.push(130 + (a - b'0') * 10 + (b - b'0'));
+                i += 1;
+            }
+            (c @ 128.., _) => codewords.extend([UPPER_SHIFT, c - 127]),
+            (c, _) => codewords.push(c + 1),
+        }
+        i += 1;
+    }
+    codewords
+}
+
+/// Fill unused capacity: 129, then pseudo-randomized pads keyed by position
+fn pad(codewords: &mut Vec<u8>, capacity: usize) {
+    if codewords.len() < capacity {
+        codewords.push(PAD);
+    }
+    while codewords.len() < capacity {
+        let position = codewords.len() as u32 + 1;
+        let value = PAD as u32 + (149 * position) % 253 + 1;
+        codewords.push(if value > 254 { value - 254 } else { value } as u8);
+    }
+}
+
+/// Codeword placement over the combined data regions (ISO/IEC 16022 annex F)
+struct Placement {
+    rows: usize,
+    columns: usize,
+    /// Module values; None until placed
+    modules: Vec<Option<bool>>,
+}
+
+impl Placement {
+    fn module(&mut self, mut row: isize, mut column: isize, codeword: u8, bit: u32) {
+        let (rows, columns) = (self.rows as isize, self.columns as isize);
+        if row < 0 {
+            row += rows;
+            column += 4 - (rows + 4) % 8;
+        }
+        if column < 0 {
+            column += columns;
+            row += 4 - (columns + 4) % 8;
+        }
+        self.modules[row as usize * self.columns + column as usize] = Some(codeword >> (8 - bit) & 1 != 0);
+    }
+
+    /// Place one codeword's bits at `positions`, most significant first
+    fn place(&mut self, positions: [(isize, isize); 8], codeword: u8) {
+        for (bit, (row, column)) in positions.into_iter().enumerate() {
+            self.module(row, column, codeword, bit as u32 + 1);
+        }
+    }
+
+    fn utah(&mut self, r: isize, c: isize, codeword: u8) {
+        self.place([(r - 2, c - 2), (r - 2, c - 1), (r - 1, c - 2), (r - 1, c - 1), (r - 1, c), (r, c - 2), (r, c - 1), (r, c)], codeword);
+    }
+
+    fn is_free(&self, row: isize, column: isize) -> bool {
+        self.modules[row as usize * self.columns + column as usize].is_none()
+    }
+
+    fn run(rows: usize, columns: usize, codewords: &[u8]) -> Vec<bool> {
+        let mut p = Placement { rows, columns, modules: vec![None; rows * columns] };
+        let (nr, nc) = (rows as isize, columns as isize);
+        let mut words = codewords.iter().copied();
+        let mut next = || words.next().unwrap_or(0);
+        let (mut row, mut column) = (4isize, 0isize);
+        loop {
+            if row == nr && column == 0 {
+                p.place([(nr - 1, 0), (nr - 1, 1), (nr - 1, 2), (0, nc - 2), (0, nc - 1), (1, nc - 1), (2, nc - 1), (3, nc - 1)], next());
+            }
+            if row == nr - 2 && column == 0 && nc % 4 != 0 {
+                p.place([(nr - 3, 0), (nr - 2, 0), (nr - 1, 0), (0, nc - 4), (0, nc - 3), (0, nc - 2), (0, nc - 1), (1, nc - 1)], next());
+            }
+            if row == nr - 2 && column == 0 && nc % 8 == 4 {
+                p.place([(nr - 3, 0), (nr - 2, 0), (nr - 1, 0), (0, nc - 2), (0, nc - 1), (1, nc - 1), (2, nc - 1), (3, nc - 1)], next());
+            }
+            if row == nr + 4 && column == 2 && nc % 8 == 0 {
+                p.place([(nr - 1, 0), (nr - 1, nc - 1), (0, nc - 3), (0, nc - 2), (0, nc - 1), (1, nc - 3), (1, nc - 2), (1, nc - 1)], next());
+            }
+            // Sweep up and to the right
+            loop {
+                if row < nr && column >= 0 && p.is_free(row, column) {
+                    p.utah(row, column, next());
+                }
+                row -= 2;
+                column += 2;
+                if row < 0 || column >= nc {
+                    break;
+                }
+            }
+            row += 1;
+            column += 3;
+            // Then down and to the left
+            loop {
+                if row >= 0 && column < nc && p.is_free(row, column) {
+                    p.utah(row, column, next());
+                }
+                row += 2;
+                column -= 2;
+                if row >= nr || column < 0 {
+                    break;
+                }
+            }
+            row += 3;
+            column += 1;
+            if row >= nr && column >= nc {
+                break;
+            }
+        }
+        // Unfilled bottom-right corner gets a fixed pattern
+        if p.is_free(nr - 1, nc - 1) {
+            p.modules[rows * columns - 1] = Some(true);
+            p.modules[(rows - 1) * columns - 2] = Some(true);
+        }
+        p.modules.into_iter().map(|m| m.unwrap_or(false)).collect()
+    }
+}
+
+/// Encode bytes as the smallest square Data Matrix that holds them
+pub(super) fn encode_data_matrix(data: &[u8]) -> Result<ModuleMatrix, String> {
+    let mut codewords = encode_ascii(data);
+    let &(size, capacity, ecc_len, regions, blocks) =
+        SYMBOLS.iter().find(|s| s.1 >= codewords.len()).ok_or_else(|| format!("{} codewords exceed Data Matrix capacity", codewords.len()))?;
+    pad(&mut codewords, capacity);
+
+    let field = GaloisField::new(0x12d, 256, 1);
+    let mut stream = codewords.clone();
+    stream.resize(capacity + ecc_len, 0);
+    for block in 0..blocks {
+        let data: Vec<u16> = codewords.iter().skip(block).step_by(blocks).map(|&c| c as u16).collect();
+        for (i, c) in field.encode(&data, ecc_len / blocks).into_iter().enumerate() {
+            stream[capacity + i * blocks + block] = c as u8;
+        }
+    }
+
+    let region = size / regions - 2;
+    let area = (region * regions) as usize;
+    let placed = Placement::run(area, area, &stream);
+    let mut matrix = ModuleMatrix::new(size, size);
+    for y in 0..size {
+        for x in 0..size {
+            let (ry, rx) = (y % (region + 2), x % (region + 2));
+            let dark = match (ry, rx) {
+                (_, 0) => true,
+                (r, _) if r == region + 1 => true,
+                (0, _) => x % 2 == 0,
+                (_, c) if c == region + 1 => y % 2 == 1,
+                _ => placed[((y / (region + 2) * region + ry - 1) * area as u32 + x / (region + 2) * region + rx - 1) as usize],
+            };
+            matrix.set(x, y, dark);
+        }
+    }
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codewords() {
+        assert_eq!(encode_ascii(b"123456"), [142, 164, 186]);
+        assert_eq!(encode_ascii(b"A1b\xe9"), [66, 50, 99, 235, 106]);
+        let mut padded = vec![66];
+        pad(&mut padded, 4);
+        assert_eq!(padded, [66, 129, 70, 220]);
+    }
+
+    #[test]
+    fn test_symbol() {
+        let matrix = encode_data_matrix(b"123456").unwrap();
+        assert_eq!((matrix.width, matrix.height), (10, 10));
+        // Finder: solid left and bottom edges, alternating top and right
+        assert!((0..10).all(|i| matrix.get(0, i) && matrix.get(i, 9)));
+        assert!((0..10).all(|i| matrix.get(i, 0) == (i % 2 == 0) && matrix.get(9, i) == (i % 2 == 1)));
+        let large = encode_data_matrix(&[b'x'; 250]).unwrap();
+        assert_eq!(large.width, 64);
+        // Alignment lines between the 4x4 regions
+        assert!((0..64).all(|y| large.get(16, y) && large.get(15, y) == (y % 2 == 1)));
+        assert!(encode_data_matrix(&[b'x'; 1600]).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! EAN-13 and UPC-A
+
+/// Left-hand odd parity (L) codes; R codes are their complement and G
+/// codes the R codes reversed
+const L_CODES: [u8; 10] = [0x0d, 0x19, 0x13, 0x3d, 0x23, 0x31, 0x2f, 0x3b, 0x37, 0x0b];
+/// L/G choice for the left six digits, keyed by the implied first digit (bit set = G)
+const PARITY: [u8; 10] = [0x00, 0x0b, 0x0d, 0x0e, 0x13, 0x19, 0x1c, 0x15, 0x16, 0x1a];
+
+/// Check digit over all other digits, weighting alternately 1 and 3 from the left
+fn check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits.iter().rev().enumerate().map(|(i, &d)| d as u32 * if i % 2 == 0 { 3 } else { 1 }).sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+/// Digits of `text`, appending the check digit if only `len - 1` are given
+fn digits(text: &str, len: usize, name: &str) -> Result<Vec<u8>, String> {
+    if !text.bytes().all(|c| c.is_ascii_digit()) || (text.len() != len && text.len() != len - 1) {
+        return Err(format!("{} takes {} digits, or {} without the check digit", name, len, len - 1));
+    }
+    let mut digits: Vec<u8> = text.bytes().map(|c| c - b'0').collect();
+    let check = check_digit(&digits[..len - 1]);
+    match digits.get(len - 1) {
+        None => digits.push(check),
+        Some(&given) if given != check => return Err(format!("{} check digit should be {}, got {}", name, check, given)),
+        Some(_) => {}
+    }
+    Ok(digits)
+}
+
+fn push_bits(modules: &mut Vec<bool>, bits: u8, count: u32) {
+    modules.extend((0..count).rev().map(|i| bits >> i & 1 != 0));
+}
+
+/// Encode 13 digits (the first one carried by the left half's parity)
+fn encode_digits(digits: &[u8]) -> Vec<bool> {
+    let mut modules = Vec::with_capacity(95);
+    push_bits(&mut modules, 0b101, 3);
+    for (i, &d) in digits[1..7].iter().enumerate() {
+        let code = if PARITY[digits[0] as usize] >> (5 - i) & 1 != 0 { (!L_CODES[d as usize] & 0x7f).reverse_bits() >> 1 } else { L_CODES[d as usize] };
+        push_bits(&mut modules, code, 7);
+    }
+    push_bits(&mut modules, 0b01010, 5);
+    for &d in &digits[7..] {
+        push_bits(&mut modules, !L_CODES[d as usize] & 0x7f, 7);
+    }
+    push_bits(&mut modules, 0b101, 3);
+    modules
+}
+
+/// Encode 12 or 13 digits as EAN-13 modules
+pub(super) fn encode_ean13(text: &str) -> Result<Vec<bool>, String> {
+    Ok(encode_digits(&digits(text, 13, "EAN-13")?))
+}
+
+/// Encode 11 or 12 digits as UPC-A modules, which are EAN-13 with a leading 0
+pub(super) fn encode_upca(text: &str) -> Result<Vec<bool>, String> {
+    let mut ean = vec![0];
+    ean.extend(digits(text, 12, "UPC-A")?);
+    Ok(encode_digits(&ean))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(modules: &[bool]) -> String {
+        modules.iter().map(|&m| if m { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn test_ean13() {
+        assert_eq!(digits("400638133393", 13, "EAN-13").unwrap()[12], 1);
+        assert!(encode_ean13("4006381333932").is_err());
+        let modules = encode_ean13("4006381333931").unwrap();
+        assert_eq!(modules.len(), 95);
+        // Guard, 0 in L, 0 in G, 6 in L (parity of 4 is LGLLGG), ..., 1 in R, guard
+        assert_eq!(bits(&modules[..24]), "101000110101001110101111");
+        assert_eq!(bits(&modules[85..]), "1100110101");
+
+        // UPC-A 036000291452
+        let upc = encode_upca("03600029145").unwrap();
+        assert_eq!(upc, encode_ean13("0036000291452").unwrap());
+        assert_eq!(bits(&upc[3..10]), "0001101");
+    }
+}
@@ -0,0 +1,238 @@
+//! Barcode generation: linear and 2D symbologies rendered to RGBA or SVG
+//! path data
+
+mod aztec;
+mod code128;
+mod datamatrix;
+mod ean;
+
+use crate::transform::RgbaImage;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Linear barcode symbologies
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarcodeFormat {
+    /// Any ASCII text
+    Code128 = 0,
+    /// 12 digits, or 13 including the check digit
+    Ean13 = 1,
+    /// 11 digits, or 12 including the check digit
+    UpcA = 2,
+}
+
+impl BarcodeFormat {
+    /// Blank modules required on each side
+    fn quiet_zone(self) -> u32 {
+        match self {
+            BarcodeFormat::Code128 => 10,
+            BarcodeFormat::Ean13 | BarcodeFormat::UpcA => 9,
+        }
+    }
+}
+
+/// 2D symbologies
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatrixCodeFormat {
+    /// ECC 200, square symbols from 10x10 to 144x144
+    DataMatrix = 0,
+    /// Compact (up to 4 layers) or full-range symbols
+    Aztec = 1,
+}
+
+impl MatrixCodeFormat {
+    /// Blank modules required around the symbol
+    fn quiet_zone(self) -> u32 {
+        match self {
+            MatrixCodeFormat::DataMatrix => 1,
+            MatrixCodeFormat::Aztec => 0,
+        }
+    }
+}
+
+/// A grid of modules, true for dark
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleMatrix {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major
+    pub modules: Vec<bool>,
+}
+
+impl ModuleMatrix {
+    fn new(width: u32, height: u32) -> ModuleMatrix {
+        ModuleMatrix { width, height, modules: vec![false; width as usize * height as usize] }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.modules[(y * self.width + x) as usize]
+    }
+
+    fn set(&mut self, x: u32, y: u32, dark: bool) {
+        self.modules[(y * self.width + x) as usize] = dark;
+    }
+}
+
+/// Encode `text` as a row of modules, true for a bar, without quiet zones
+pub fn encode_barcode(format: BarcodeFormat, text: &str) -> Result<Vec<bool>, String> {
+    match format {
+        BarcodeFormat::Code128 => code128::encode_code128(text),
+        BarcodeFormat::Ean13 => ean::encode_ean13(text),
+        BarcodeFormat::UpcA => ean::encode_upca(text),
+    }
+}
+
+/// Bars as (first module, width in modules), counted from the left quiet zone edge
+fn bars(format: BarcodeFormat, text: &str) -> Result<(u32, Vec<(u32, u32)>), String> {
+    let modules = encode_barcode(format, text)?;
+    let quiet = format.quiet_zone();
+    let mut bars: Vec<(u32, u32)> = Vec::new();
+    for (i, _) in modules.iter().enumerate().filter(|(_, &bar)| bar) {
+        let x = quiet + i as u32;
+        match bars.last_mut() {
+            Some((start, width)) if *start + *width == x => *width += 1,
+            _ => bars.push((x, 1)),
+        }
+    }
+    Ok((modules.len() as u32 + 2 * quiet, bars))
+}
+
+/// Render black bars on white, `module_width` pixels per module and
+/// `height` pixels tall, with the symbology's quiet zones
+pub fn render_barcode(format: BarcodeFormat, text: &str, module_width: u32, height: u32) -> Result<RgbaImage, String> {
+    if module_width == 0 || height == 0 {
+        return Err("Module width and height must be positive".to_string());
+    }
+    let (modules, bars) = bars(format, text)?;
+    let width = modules.checked_mul(module_width).filter(|w| *w as u64 * height as u64 <= u32::MAX as u64 / 4).ok_or("Barcode is too large")?;
+    let mut row = [255u8; 4].repeat(width as usize);
+    for (x, w) in bars {
+        row[(x * module_width * 4) as usize..((x + w) * module_width * 4) as usize].chunks_exact_mut(4).for_each(|p| p.copy_from_slice(&[0, 0, 0, 255]));
+    }
+    Ok(RgbaImage { width, height, data: row.repeat(height as usize) })
+}
+
+/// SVG path data drawing the bars as rectangles, one unit per module and
+/// `height` units tall; the symbol with quiet zones spans
+/// `barcode_width(format, text)` units
+pub fn barcode_svg_path(format: BarcodeFormat, text: &str, height: u32) -> Result<String, String> {
+    let (_, bars) = bars(format, text)?;
+    Ok(bars.iter().map(|(x, w)| format!("M{},0h{}v{}h-{}z", x, w, height, w)).collect())
+}
+
+/// Width in modules of the symbol including quiet zones
+pub fn barcode_width(format: BarcodeFormat, text: &str) -> Result<u32, String> {
+    bars(format, text).map(|(width, _)| width)
+}
+
+/// Encode `text`, which both symbologies read as ISO 8859-1, as a module grid
+/// without quiet zone
+pub fn encode_matrix_code(format: MatrixCodeFormat, text: &str) -> Result<ModuleMatrix, String> {
+    let bytes = text.chars().map(|c| u8::try_from(c).map_err(|_| format!("'{}' is not in ISO 8859-1", c))).collect::<Result<Vec<u8>, String>>()?;
+    match format {
+        MatrixCodeFormat::DataMatrix => datamatrix::encode_data_matrix(&bytes),
+        MatrixCodeFormat::Aztec => aztec::encode_aztec(&bytes),
+    }
+}
+
+/// Render dark modules on white, `module_size` pixels square, with the
+/// symbology's quiet zone
+pub fn render_matrix_code(format: MatrixCodeFormat, text: &str, module_size: u32) -> Result<RgbaImage, String> {
+    if module_size == 0 {
+        return Err("Module size must be positive".to_string());
+    }
+    let matrix = encode_matrix_code(format, text)?;
+    let quiet = format.quiet_zone();
+    let side = (matrix.width + 2 * quiet) * module_size;
+    let mut data = [255u8; 4].repeat(side as usize * side as usize);
+    for (i, _) in matrix.modules.iter().enumerate().filter(|(_, &dark)| dark) {
+        let (x, y) = ((i as u32 % matrix.width + quiet) * module_size, (i as u32 / matrix.width + quiet) * module_size);
+        for row in y..y + module_size {
+            let at = (row * side + x) as usize * 4;
+            data[at..at + module_size as usize * 4].chunks_exact_mut(4).for_each(|p| p.copy_from_slice(&[0, 0, 0, 255]));
+        }
+    }
+    Ok(RgbaImage { width: side, height: side, data })
+}
+
+/// SVG path data drawing each row's runs of dark modules, one unit per
+/// module, offset by the quiet zone
+pub fn matrix_code_svg_path(format: MatrixCodeFormat, text: &str) -> Result<String, String> {
+    let matrix = encode_matrix_code(format, text)?;
+    let quiet = format.quiet_zone();
+    let mut path = String::new();
+    for (y, row) in matrix.modules.chunks_exact(matrix.width as usize).enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            let run = row[x..].iter().take_while(|&&dark| dark).count();
+            if run > 0 {
+                path.push_str(&format!("M{},{}h{}v1h-{}z", x as u32 + quiet, y as u32 + quiet, run, run));
+            }
+            x += run.max(1);
+        }
+    }
+    Ok(path)
+}
+
+/// Render a barcode to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = renderBarcode)]
+pub fn render_barcode_js(format: BarcodeFormat, text: &str, module_width: u32, height: u32) -> Result<RgbaImage, JsError> {
+    render_barcode(format, text, module_width, height).map_err(|e| JsError::new(&e))
+}
+
+/// SVG path data for a barcode, one unit per module
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = barcodeSvgPath)]
+pub fn barcode_svg_path_js(format: BarcodeFormat, text: &str, height: u32) -> Result<String, JsError> {
+    barcode_svg_path(format, text, height).map_err(|e| JsError::new(&e))
+}
+
+/// Width in modules of a barcode including quiet zones
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = barcodeWidth)]
+pub fn barcode_width_js(format: BarcodeFormat, text: &str) -> Result<u32, JsError> {
+    barcode_width(format, text).map_err(|e| JsError::new(&e))
+}
+
+/// Render a Data Matrix or Aztec symbol to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = renderMatrixCode)]
+pub fn render_matrix_code_js(format: MatrixCodeFormat, text: &str, module_size: u32) -> Result<RgbaImage, JsError> {
+    render_matrix_code(format, text, module_size).map_err(|e| JsError::new(&e))
+}
+
+/// SVG path data for a Data Matrix or Aztec symbol, one unit per module
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = matrixCodeSvgPath)]
+pub fn matrix_code_svg_path_js(format: MatrixCodeFormat, text: &str) -> Result<String, JsError> {
+    matrix_code_svg_path(format, text).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let image = render_barcode(BarcodeFormat::Ean13, "4006381333931", 2, 3).unwrap();
+        assert_eq!((image.width, image.height), ((95 + 18) * 2, 3));
+        let dark: Vec<bool> = image.data[..image.width as usize * 4].chunks_exact(8).map(|p| p[0] == 0).collect();
+        assert_eq!(&dark[9..104], encode_barcode(BarcodeFormat::Ean13, "4006381333931").unwrap());
+        assert!(!dark[8] && !dark[104]);
+
+        let path = barcode_svg_path(BarcodeFormat::UpcA, "03600029145", 40).unwrap();
+        assert!(path.starts_with("M9,0h1v40h-1zM11,0h1v40h-1zM"));
+        assert_eq!(barcode_width(BarcodeFormat::Code128, "1234").unwrap(), 57 + 20);
+        assert!(render_barcode(BarcodeFormat::Code128, "", 1, 1).is_err());
+
+        let image = render_matrix_code(MatrixCodeFormat::DataMatrix, "123456", 3).unwrap();
+        assert_eq!((image.width, image.height), (36, 36));
+        // Quiet zone, then the solid left edge of the finder
+        assert_eq!(&image.data[(5 * 36 + 2) * 4..(5 * 36 + 4) * 4], &[255, 255, 255, 255, 0, 0, 0, 255]);
+        assert!(matrix_code_svg_path(MatrixCodeFormat::DataMatrix, "123456").unwrap().starts_with("M1,1h1v1h-1zM3,1h1v1h-1z"));
+        assert!(encode_matrix_code(MatrixCodeFormat::Aztec, "\u{263a}").is_err());
+    }
+}
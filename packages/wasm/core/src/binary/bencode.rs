@@ -0,0 +1,245 @@
+//! Bencode, the BitTorrent metainfo serialization format
+
+#[cfg(feature = "wasm")]
+use super::js::{bytes_or_text, int_to_js, js_to_int, set, JsKind};
+use super::MAX_DEPTH;
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Object, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A bencoded value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BencodeValue {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<BencodeValue>),
+    /// Entries in the order they were read; encoding sorts them by key
+    Dict(Vec<(Vec<u8>, BencodeValue)>),
+}
+
+impl BencodeValue {
+    /// Look up a dictionary entry by key
+    pub fn get(&self, key: &str) -> Option<&BencodeValue> {
+        match self {
+            BencodeValue::Dict(entries) => entries.iter().find(|(k, _)| k == key.as_bytes()).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            BencodeValue::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            BencodeValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Result<u8, String> {
+        self.data.get(self.pos).copied().ok_or_else(|| "Truncated bencode".to_string())
+    }
+
+    /// Read digits up to `end`, rejecting leading zeros and `-0`
+    fn number(&mut self, end: u8) -> Result<i64, String> {
+        let start = self.pos;
+        let len = self.data[start..].iter().position(|&b| b == end).ok_or("Truncated bencode")?;
+        let text = std::str::from_utf8(&self.data[start..start + len]).map_err(|_| "Invalid bencode integer")?;
+        let digits = text.strip_prefix('-').unwrap_or(text);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) || (digits.starts_with('0') && text != "0") {
+            return Err(format!("Invalid bencode integer '{}' at byte {}", text, start));
+        }
+        self.pos += len + 1;
+        text.parse().map_err(|_| format!("Bencode integer '{}' out of range", text))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, String> {
+        let len = usize::try_from(self.number(b':')?).map_err(|_| "Negative bencode string length")?;
+        let bytes = self.data.get(self.pos..).and_then(|d| d.get(..len)).ok_or("Truncated bencode string")?;
+        self.pos += len;
+        Ok(bytes.to_vec())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<BencodeValue, String> {
+        if depth > MAX_DEPTH {
+            return Err("Bencode nesting is too deep".to_string());
+        }
+        match self.peek()? {
+            b'i' => {
+                self.pos += 1;
+                Ok(BencodeValue::Int(self.number(b'e')?))
+            }
+            b'0'..=b'9' => Ok(BencodeValue::Bytes(self.bytes()?)),
+            b'l' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while self.peek()? != b'e' {
+                    items.push(self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(BencodeValue::List(items))
+            }
+            b'd' => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                while self.peek()? != b'e' {
+                    if !self.peek()?.is_ascii_digit() {
+                        return Err(format!("Bencode dictionary key must be a string at byte {}", self.pos));
+                    }
+                    let key = self.bytes()?;
+                    entries.push((key, self.value(depth + 1)?));
+                }
+                self.pos += 1;
+                Ok(BencodeValue::Dict(entries))
+            }
+            other => Err(format!("Unexpected byte 0x{:02X} in bencode at {}", other, self.pos)),
+        }
+    }
+}
+
+/// Decode a single bencoded value that spans all of `data`
+pub fn bencode_decode(data: &[u8]) -> Result<BencodeValue, String> {
+    let mut parser = Parser { data, pos: 0 };
+    let value = parser.value(0)?;
+    if parser.pos != data.len() {
+        return Err(format!("Trailing data after bencode value at byte {}", parser.pos));
+    }
+    Ok(value)
+}
+
+fn write_value(value: &BencodeValue, out: &mut Vec<u8>) {
+    match value {
+        BencodeValue::Int(i) => out.extend_from_slice(format!("i{}e", i).as_bytes()),
+        BencodeValue::Bytes(b) => {
+            out.extend_from_slice(format!("{}:", b.len()).as_bytes());
+            out.extend_from_slice(b);
+        }
+        BencodeValue::List(items) => {
+            out.push(b'l');
+            for item in items {
+                write_value(item, out);
+            }
+            out.push(b'e');
+        }
+        BencodeValue::Dict(entries) => {
+            let mut sorted: Vec<_> = entries.iter().collect();
+            sorted.sort_by(|a, b| a.0.cmp(&b.0));
+            out.push(b'd');
+            for (key, item) in sorted {
+                write_value(&BencodeValue::Bytes(key.clone()), out);
+                write_value(item, out);
+            }
+            out.push(b'e');
+        }
+    }
+}
+
+/// Encode a value, with dictionary keys in sorted order
+pub fn bencode_encode(value: &BencodeValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_value(value, &mut out);
+    out
+}
+
+#[cfg(feature = "wasm")]
+fn to_js(value: &BencodeValue) -> JsValue {
+    match value {
+        BencodeValue::Int(i) => int_to_js(*i as i128),
+        BencodeValue::Bytes(b) => bytes_or_text(b),
+        BencodeValue::List(items) => items.iter().map(to_js).collect::<Array>().into(),
+        BencodeValue::Dict(entries) => {
+            let object = Object::new();
+            for (key, item) in entries {
+                set(&object, &JsValue::from_str(&String::from_utf8_lossy(key)), &to_js(item));
+            }
+            object.into()
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn from_js(value: &JsValue, depth: usize) -> Result<BencodeValue, String> {
+    if depth > MAX_DEPTH {
+        return Err("Value is nested too deeply".to_string());
+    }
+    Ok(match JsKind::of(value) {
+        JsKind::Number(_) | JsKind::BigInt => {
+            let i = js_to_int(value)?;
+            BencodeValue::Int(i64::try_from(i).map_err(|_| "Integer out of bencode range")?)
+        }
+        JsKind::String(s) => BencodeValue::Bytes(s.into_bytes()),
+        JsKind::Bytes(b) => BencodeValue::Bytes(b),
+        JsKind::Array(items) => BencodeValue::List(items.iter().map(|v| from_js(&v, depth + 1)).collect::<Result<_, _>>()?),
+        JsKind::Object(entries) => {
+            let mut dict = Vec::with_capacity(entries.len());
+            for (key, item) in entries {
+                let key = key.as_string().ok_or("Bencode dictionary keys must be strings")?;
+                dict.push((key.into_bytes(), from_js(&item, depth + 1)?));
+            }
+            BencodeValue::Dict(dict)
+        }
+        other => return Err(format!("Cannot bencode a {} value", other.name())),
+    })
+}
+
+/// Decode bencode into JS values
+///
+/// Integers become numbers (or BigInts beyond 2^53), byte strings become
+/// strings when they are valid UTF-8 and `Uint8Array`s otherwise, lists
+/// become arrays and dictionaries become plain objects.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = bencodeDecode)]
+pub fn bencode_decode_js(data: &[u8]) -> Result<JsValue, JsError> {
+    bencode_decode(data).map(|v| to_js(&v)).map_err(|e| JsError::new(&e))
+}
+
+/// Encode a JS value as bencode
+///
+/// Accepts integers, BigInts, strings, `Uint8Array`s, arrays, `Map`s and
+/// plain objects.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = bencodeEncode)]
+pub fn bencode_encode_js(value: JsValue) -> Result<Uint8Array, JsError> {
+    let value = from_js(&value, 0).map_err(|e| JsError::new(&e))?;
+    Ok(Uint8Array::from(&bencode_encode(&value)[..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let data = b"d8:announce18:http://tracker/ann4:infod6:lengthi-42e4:name3:a.b6:piecesli1ei2eeee";
+        let value = bencode_decode(data).unwrap();
+        assert_eq!(value.get("announce").and_then(|v| v.as_bytes()), Some(&b"http://tracker/ann"[..]));
+        let info = value.get("info").unwrap();
+        assert_eq!(info.get("length").and_then(BencodeValue::as_int), Some(-42));
+        assert_eq!(info.get("pieces"), Some(&BencodeValue::List(vec![BencodeValue::Int(1), BencodeValue::Int(2)])));
+        assert_eq!(bencode_encode(&value), data);
+
+        // Keys are sorted on output
+        let unsorted = BencodeValue::Dict(vec![(b"b".to_vec(), BencodeValue::Int(0)), (b"a".to_vec(), BencodeValue::Bytes(vec![]))]);
+        assert_eq!(bencode_encode(&unsorted), b"d1:a0:1:bi0ee");
+    }
+
+    #[test]
+    fn test_malformed() {
+        for bad in [&b"i-0e"[..], b"i03e", b"ie", b"5:abc", b"l", b"di1ei2ee", b"i1ei2e", b"x"] {
+            assert!(bencode_decode(bad).is_err(), "{:?}", bad);
+        }
+        assert!(bencode_decode(&b"l".repeat(10_000)).is_err());
+    }
+}
@@ -0,0 +1,459 @@
+//! CBOR (RFC 8949) encoding and decoding
+//!
+//! The encoder uses preferred serialization: the shortest integer heads,
+//! definite lengths, and the smallest float width that holds a value exactly.
+//! The decoder also accepts indefinite-length items.
+
+#[cfg(feature = "wasm")]
+use super::js::{int_to_js, js_to_int, set, JsKind};
+use super::MAX_DEPTH;
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Map, Object, Uint8Array};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const BREAK: u8 = 0xFF;
+
+/// A CBOR data item
+#[derive(Clone, Debug, PartialEq)]
+pub enum CborValue {
+    Unsigned(u64),
+    /// The integer `-1 - n`
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<CborValue>),
+    Map(Vec<(CborValue, CborValue)>),
+    Tag(u64, Box<CborValue>),
+    Bool(bool),
+    Null,
+    Undefined,
+    Float(f64),
+    /// Unassigned simple value
+    Simple(u8),
+}
+
+impl CborValue {
+    /// Integer value, if this is an integer
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            CborValue::Unsigned(n) => Some(*n as i128),
+            CborValue::Negative(n) => Some(-1 - *n as i128),
+            _ => None,
+        }
+    }
+
+    /// Map entry with a text key
+    pub fn get(&self, key: &str) -> Option<&CborValue> {
+        match self {
+            CborValue::Map(entries) => entries.iter().find(|(k, _)| matches!(k, CborValue::Text(t) if t == key)).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl From<i64> for CborValue {
+    fn from(n: i64) -> Self {
+        if n < 0 {
+            CborValue::Negative(!n as u64)
+        } else {
+            CborValue::Unsigned(n as u64)
+        }
+    }
+}
+
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (bits >> 10) & 0x1F;
+    let mantissa = (bits & 0x3FF) as f64;
+    sign * match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        e => (1.0 + mantissa / 1024.0) * 2f64.powi(e as i32 - 15),
+    }
+}
+
+/// Half-precision bits for `value` when the conversion is exact
+fn f64_to_f16(value: f64) -> Option<u16> {
+    if value.is_nan() {
+        return Some(0x7E00);
+    }
+    let single = value as f32;
+    if single as f64 != value {
+        return None;
+    }
+    let bits = single.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+    match exponent {
+        0 if mantissa == 0 => Some(sign),
+        0xFF => Some(sign | 0x7C00),
+        // Normal half-precision range, with the low mantissa bits unused
+        113..=142 if mantissa & 0x1FFF == 0 => Some(sign | ((exponent - 112) as u16) << 10 | (mantissa >> 13) as u16),
+        // Subnormal half-precision values
+        103..=112 => {
+            let full = mantissa | 0x80_0000;
+            let shift = 126 - exponent;
+            (full & ((1 << shift) - 1) == 0).then_some(sign | (full >> shift) as u16)
+        }
+        _ => None,
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        let bytes = self.data.get(self.pos..).and_then(|d| d.get(..len)).ok_or("Truncated CBOR")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Argument for additional info `info`; `None` means indefinite length
+    fn argument(&mut self, info: u8) -> Result<Option<u64>, String> {
+        let size = match info {
+            0..=23 => return Ok(Some(info as u64)),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            31 => return Ok(None),
+            _ => return Err(format!("Reserved CBOR additional info {} at byte {}", info, self.pos - 1)),
+        };
+        Ok(Some(self.take(size)?.iter().fold(0, |v, &b| v << 8 | b as u64)))
+    }
+
+    fn length(&mut self, info: u8) -> Result<Option<usize>, String> {
+        self.argument(info)?.map(|n| usize::try_from(n).map_err(|_| "CBOR length out of range".to_string())).transpose()
+    }
+
+    /// Byte or text string body, joining indefinite-length chunks
+    fn string(&mut self, major: u8, info: u8) -> Result<Vec<u8>, String> {
+        if let Some(len) = self.length(info)? {
+            return Ok(self.take(len)?.to_vec());
+        }
+        let mut out = Vec::new();
+        loop {
+            let head = self.byte()?;
+            if head == BREAK {
+                return Ok(out);
+            }
+            if head >> 5 != major {
+                return Err("Invalid chunk in indefinite-length CBOR string".to_string());
+            }
+            let len = self.length(head & 0x1F)?.ok_or("Nested indefinite-length CBOR string")?;
+            out.extend_from_slice(self.take(len)?);
+        }
+    }
+
+    fn at_break(&mut self) -> Result<bool, String> {
+        if *self.data.get(self.pos).ok_or("Truncated CBOR")? == BREAK {
+            self.pos += 1;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<CborValue, String> {
+        if depth > MAX_DEPTH {
+            return Err("CBOR nesting is too deep".to_string());
+        }
+        let start = self.pos;
+        let head = self.byte()?;
+        let (major, info) = (head >> 5, head & 0x1F);
+        let indefinite = || format!("Unexpected indefinite length at byte {}", start);
+        Ok(match major {
+            0 => CborValue::Unsigned(self.argument(info)?.ok_or_else(indefinite)?),
+            1 => CborValue::Negative(self.argument(info)?.ok_or_else(indefinite)?),
+            2 => CborValue::Bytes(self.string(2, info)?),
+            3 => CborValue::Text(String::from_utf8(self.string(3, info)?).map_err(|_| format!("Invalid UTF-8 in CBOR text at byte {}", start))?),
+            4 => {
+                let mut items = Vec::new();
+                match self.length(info)? {
+                    Some(n) => {
+                        // Every item takes at least a byte, which bounds the allocation
+                        items.reserve(n.min(self.data.len() - self.pos));
+                        for _ in 0..n {
+                            items.push(self.value(depth + 1)?);
+                        }
+                    }
+                    None => {
+                        while !self.at_break()? {
+                            items.push(self.value(depth + 1)?);
+                        }
+                    }
+                }
+                CborValue::Array(items)
+            }
+            5 => {
+                let mut entries = Vec::new();
+                match self.length(info)? {
+                    Some(n) => {
+                        entries.reserve(n.min(self.data.len() - self.pos));
+                        for _ in 0..n {
+                            entries.push((self.value(depth + 1)?, self.value(depth + 1)?));
+                        }
+                    }
+                    None => {
+                        while !self.at_break()? {
+                            entries.push((self.value(depth + 1)?, self.value(depth + 1)?));
+                        }
+                    }
+                }
+                CborValue::Map(entries)
+            }
+            6 => {
+                let tag = self.argument(info)?.ok_or_else(indefinite)?;
+                CborValue::Tag(tag, Box::new(self.value(depth + 1)?))
+            }
+            _ => match info {
+                20 => CborValue::Bool(false),
+                21 => CborValue::Bool(true),
+                22 => CborValue::Null,
+                23 => CborValue::Undefined,
+                24 => CborValue::Simple(self.byte()?),
+                25 => CborValue::Float(f16_to_f64(self.argument(25)?.unwrap_or(0) as u16)),
+                26 => CborValue::Float(f32::from_bits(self.argument(26)?.unwrap_or(0) as u32) as f64),
+                27 => CborValue::Float(f64::from_bits(self.argument(27)?.unwrap_or(0))),
+                0..=19 => CborValue::Simple(info),
+                _ => return Err(format!("Unexpected CBOR byte 0x{:02X} at {}", head, start)),
+            },
+        })
+    }
+}
+
+/// Decode a single CBOR data item that spans all of `data`
+pub fn cbor_decode(data: &[u8]) -> Result<CborValue, String> {
+    let mut parser = Parser { data, pos: 0 };
+    let value = parser.value(0)?;
+    if parser.pos != data.len() {
+        return Err(format!("Trailing data after CBOR item at byte {}", parser.pos));
+    }
+    Ok(value)
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    match n {
+        0..=23 => out.push(major | n as u8),
+        24..=0xFF => out.extend_from_slice(&[major | 24, n as u8]),
+        0x100..=0xFFFF => {
+            out.push(major | 25);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xFFFF_FFFF => {
+            out.push(major | 26);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+fn write_value(value: &CborValue, out: &mut Vec<u8>) {
+    match value {
+        CborValue::Unsigned(n) => write_head(out, 0, *n),
+        CborValue::Negative(n) => write_head(out, 1, *n),
+        CborValue::Bytes(b) => {
+            write_head(out, 2, b.len() as u64);
+            out.extend_from_slice(b);
+        }
+        CborValue::Text(t) => {
+            write_head(out, 3, t.len() as u64);
+            out.extend_from_slice(t.as_bytes());
+        }
+        CborValue::Array(items) => {
+            write_head(out, 4, items.len() as u64);
+            for item in items {
+                write_value(item, out);
+            }
+        }
+        CborValue::Map(entries) => {
+            write_head(out, 5, entries.len() as u64);
+            for (key, item) in entries {
+                write_value(key, out);
+                write_value(item, out);
+            }
+        }
+        CborValue::Tag(tag, inner) => {
+            write_head(out, 6, *tag);
+            write_value(inner, out);
+        }
+        CborValue::Bool(b) => out.push(if *b { 0xF5 } else { 0xF4 }),
+        CborValue::Null => out.push(0xF6),
+        CborValue::Undefined => out.push(0xF7),
+        CborValue::Float(f) => {
+            if let Some(half) = f64_to_f16(*f) {
+                out.push(0xF9);
+                out.extend_from_slice(&half.to_be_bytes());
+            } else if (*f as f32) as f64 == *f {
+                out.push(0xFA);
+                out.extend_from_slice(&(*f as f32).to_be_bytes());
+            } else {
+                out.push(0xFB);
+                out.extend_from_slice(&f.to_be_bytes());
+            }
+        }
+        CborValue::Simple(n) if *n < 24 => out.push(0xE0 | n),
+        CborValue::Simple(n) => out.extend_from_slice(&[0xF8, *n]),
+    }
+}
+
+/// Encode a value using preferred serialization
+pub fn cbor_encode(value: &CborValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_value(value, &mut out);
+    out
+}
+
+#[cfg(feature = "wasm")]
+fn to_js(value: &CborValue) -> JsValue {
+    match value {
+        CborValue::Unsigned(_) | CborValue::Negative(_) => int_to_js(value.as_int().unwrap_or(0)),
+        CborValue::Bytes(b) => Uint8Array::from(&b[..]).into(),
+        CborValue::Text(t) => JsValue::from_str(t),
+        CborValue::Array(items) => items.iter().map(to_js).collect::<Array>().into(),
+        CborValue::Map(entries) if entries.iter().all(|(k, _)| matches!(k, CborValue::Text(_))) => {
+            let object = Object::new();
+            for (key, item) in entries {
+                set(&object, &to_js(key), &to_js(item));
+            }
+            object.into()
+        }
+        CborValue::Map(entries) => {
+            let map = Map::new();
+            for (key, item) in entries {
+                map.set(&to_js(key), &to_js(item));
+            }
+            map.into()
+        }
+        CborValue::Tag(_, inner) => to_js(inner),
+        CborValue::Bool(b) => JsValue::from_bool(*b),
+        CborValue::Null => JsValue::NULL,
+        CborValue::Undefined => JsValue::UNDEFINED,
+        CborValue::Float(f) => JsValue::from_f64(*f),
+        CborValue::Simple(n) => JsValue::from_f64(*n as f64),
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn int_to_cbor(n: i128) -> Result<CborValue, String> {
+    if n >= 0 {
+        u64::try_from(n).map(CborValue::Unsigned)
+    } else {
+        u64::try_from(-1 - n).map(CborValue::Negative)
+    }
+    .map_err(|_| "Integer out of CBOR range".to_string())
+}
+
+#[cfg(feature = "wasm")]
+fn from_js(value: &JsValue, depth: usize) -> Result<CborValue, String> {
+    if depth > MAX_DEPTH {
+        return Err("Value is nested too deeply".to_string());
+    }
+    Ok(match JsKind::of(value) {
+        JsKind::Undefined => CborValue::Undefined,
+        JsKind::Null => CborValue::Null,
+        JsKind::Bool(b) => CborValue::Bool(b),
+        // Integral numbers are written as integers, everything else as floats
+        JsKind::Number(n) if n.fract() == 0.0 && n.abs() <= 9_007_199_254_740_991.0 => int_to_cbor(n as i128)?,
+        JsKind::Number(n) => CborValue::Float(n),
+        JsKind::BigInt => int_to_cbor(js_to_int(value)?)?,
+        JsKind::String(s) => CborValue::Text(s),
+        JsKind::Bytes(b) => CborValue::Bytes(b),
+        JsKind::Array(items) => CborValue::Array(items.iter().map(|v| from_js(&v, depth + 1)).collect::<Result<_, _>>()?),
+        JsKind::Object(entries) => {
+            let mut map = Vec::with_capacity(entries.len());
+            for (key, item) in entries {
+                map.push((from_js(&key, depth + 1)?, from_js(&item, depth + 1)?));
+            }
+            CborValue::Map(map)
+        }
+        JsKind::Other(kind) => return Err(format!("Cannot encode a {} value as CBOR", kind)),
+    })
+}
+
+/// Decode CBOR into JS values
+///
+/// Integers become numbers (or BigInts beyond 2^53), byte strings become
+/// `Uint8Array`s, maps with only text keys become plain objects and other
+/// maps become `Map`s. Tags are dropped in favor of their content.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = cborDecode)]
+pub fn cbor_decode_js(data: &[u8]) -> Result<JsValue, JsError> {
+    cbor_decode(data).map(|v| to_js(&v)).map_err(|e| JsError::new(&e))
+}
+
+/// Encode a JS value as CBOR
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = cborEncode)]
+pub fn cbor_encode_js(value: JsValue) -> Result<Uint8Array, JsError> {
+    let value = from_js(&value, 0).map_err(|e| JsError::new(&e))?;
+    Ok(Uint8Array::from(&cbor_encode(&value)[..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_rfc8949_examples() {
+        let examples = [
+            ("00", CborValue::Unsigned(0)),
+            ("1903e8", CborValue::Unsigned(1000)),
+            ("1bffffffffffffffff", CborValue::Unsigned(u64::MAX)),
+            ("3bffffffffffffffff", CborValue::Negative(u64::MAX)),
+            ("3863", CborValue::from(-100)),
+            ("f90000", CborValue::Float(0.0)),
+            ("f93e00", CborValue::Float(1.5)),
+            ("f97bff", CborValue::Float(65504.0)),
+            ("f90001", CborValue::Float(5.960464477539063e-8)),
+            ("fa47c35000", CborValue::Float(100000.0)),
+            ("fb3ff199999999999a", CborValue::Float(1.1)),
+            ("f97c00", CborValue::Float(f64::INFINITY)),
+            ("f4", CborValue::Bool(false)),
+            ("f7", CborValue::Undefined),
+            ("f0", CborValue::Simple(16)),
+            ("f8ff", CborValue::Simple(255)),
+            ("c11a514b67b0", CborValue::Tag(1, Box::new(CborValue::Unsigned(1363896240)))),
+            ("4401020304", CborValue::Bytes(vec![1, 2, 3, 4])),
+            ("62c3bc", CborValue::Text("ü".to_string())),
+            ("8301820203820405", CborValue::Array(vec![CborValue::Unsigned(1), CborValue::Array(vec![CborValue::Unsigned(2), CborValue::Unsigned(3)]), CborValue::Array(vec![CborValue::Unsigned(4), CborValue::Unsigned(5)])])),
+            ("a26161016162820203", CborValue::Map(vec![(CborValue::Text("a".to_string()), CborValue::Unsigned(1)), (CborValue::Text("b".to_string()), CborValue::Array(vec![CborValue::Unsigned(2), CborValue::Unsigned(3)]))])),
+        ];
+        for (encoded, value) in examples {
+            assert_eq!(cbor_decode(&hex(encoded)).unwrap(), value, "{}", encoded);
+            assert_eq!(cbor_encode(&value), hex(encoded), "{}", encoded);
+        }
+        assert!(matches!(cbor_decode(&hex("f97e00")), Ok(CborValue::Float(f)) if f.is_nan()));
+    }
+
+    #[test]
+    fn test_indefinite_and_malformed() {
+        assert_eq!(cbor_decode(&hex("5f42010243030405ff")).unwrap(), CborValue::Bytes(vec![1, 2, 3, 4, 5]));
+        assert_eq!(cbor_decode(&hex("7f657374726561646d696e67ff")).unwrap(), CborValue::Text("streaming".to_string()));
+        let map = cbor_decode(&hex("bf61610161629f0203ffff")).unwrap();
+        assert_eq!(map.get("b"), Some(&CborValue::Array(vec![CborValue::Unsigned(2), CborValue::Unsigned(3)])));
+        assert_eq!(map.get("a").and_then(CborValue::as_int), Some(1));
+
+        for bad in ["18", "1c", "62c3", "9f01", "5f01ff", "1f", "ff", "0000", "9bffffffffffffffff"] {
+            assert!(cbor_decode(&hex(bad)).is_err(), "{}", bad);
+        }
+        assert!(cbor_decode(&vec![0x81; 10_000]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! Mapping between JS values and the structured binary formats
+
+use js_sys::{Array, Map, Object, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Largest integer a JS number holds exactly
+const MAX_SAFE_INTEGER: u128 = (1 << 53) - 1;
+
+pub(crate) fn set(target: &Object, key: &JsValue, value: &JsValue) {
+    // Reflect::set on a plain object cannot fail
+    let _ = Reflect::set(target, key, value);
+}
+
+/// A number when exactly representable, otherwise a BigInt
+pub(crate) fn int_to_js(value: i128) -> JsValue {
+    if value.unsigned_abs() <= MAX_SAFE_INTEGER {
+        JsValue::from_f64(value as f64)
+    } else {
+        JsValue::from(value)
+    }
+}
+
+/// A string when valid UTF-8, otherwise a `Uint8Array`
+pub(crate) fn bytes_or_text(bytes: &[u8]) -> JsValue {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => JsValue::from_str(text),
+        Err(_) => Uint8Array::from(bytes).into(),
+    }
+}
+
+/// An integral number or BigInt as an integer
+pub(crate) fn js_to_int(value: &JsValue) -> Result<i128, String> {
+    if let Some(n) = value.as_f64() {
+        if n.fract() != 0.0 || !n.is_finite() {
+            return Err(format!("{} is not an integer", n));
+        }
+        return Ok(n as i128);
+    }
+    i128::try_from(value.clone()).map_err(|_| "BigInt is out of range".to_string())
+}
+
+/// A JS value classified by the shape the encoders care about
+pub(crate) enum JsKind {
+    Undefined,
+    Null,
+    Bool(bool),
+    Number(f64),
+    BigInt,
+    String(String),
+    Bytes(Vec<u8>),
+    Array(Array),
+    /// Entries of a `Map` or the own enumerable properties of an object
+    Object(Vec<(JsValue, JsValue)>),
+    /// Anything else, by its `typeof`
+    Other(String),
+}
+
+impl JsKind {
+        pub(crate) fn of(value: &JsValue) -> JsKind {
+        if value.is_undefined() {
+            JsKind::Undefined
+        } else if value.is_null() {
+            JsKind::Null
+        } else if let Some(b) = value.as_bool() {
+            JsKind::Bool(b)
+        } else if let Some(n) = value.as_f64() {
+            JsKind::Number(n)
+        } else if value.is_bigint() {
+            JsKind::BigInt
+        } else if let Some(s) = value.as_string() {
+            JsKind::String(s)
+        } else if let Some(bytes) = value.dyn_ref::<Uint8Array>() {
+            JsKind::Bytes(bytes.to_vec())
+        } else if Array::is_array(value) {
+            JsKind::Array(value.clone().unchecked_into())
+        } else if let Some(map) = value.dyn_ref::<Map>() {
+            let mut entries = Vec::with_capacity(map.size() as usize);
+            map.for_each(&mut |v, k| entries.push((k, v)));
+            JsKind::Object(entries)
+        } else if let Some(object) = value.dyn_ref::<Object>().filter(|_| value.is_object()) {
+            let pairs = Object::entries(object);
+            let entries = pairs.iter().map(|pair| {
+                let pair: Array = pair.unchecked_into();
+                (pair.get(0), pair.get(1))
+            });
+            JsKind::Object(entries.collect())
+        } else {
+            JsKind::Other(value.js_typeof().as_string().unwrap_or_default())
+        }
+    }
+
+    /// Short description for error messages
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            JsKind::Undefined => "undefined",
+            JsKind::Null => "null",
+            JsKind::Bool(_) => "boolean",
+            JsKind::Number(_) => "number",
+            JsKind::BigInt => "bigint",
+            JsKind::String(_) => "string",
+            JsKind::Bytes(_) => "Uint8Array",
+            JsKind::Array(_) => "array",
+            JsKind::Object(_) => "object",
+            JsKind::Other(kind) => kind,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! LEB128 variable-length integers and zigzag signed mapping
+
+/// Longest LEB128 encoding of a 64-bit value
+const MAX_BYTES: usize = 10;
+
+/// Map a signed integer to unsigned so small magnitudes stay short
+pub fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+pub fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Append an unsigned LEB128 value
+pub fn write_uleb128(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Append a signed LEB128 value
+pub fn write_sleb128(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = value as u8 & 0x7F;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Read raw LEB128 groups at `*pos`, returning the value bits, the bit
+/// count, and the final byte
+fn read_groups(data: &[u8], pos: &mut usize) -> Result<(u64, u32, u8), String> {
+    let mut value = 0u64;
+    for i in 0..MAX_BYTES {
+        let byte = *data.get(*pos + i).ok_or("Truncated LEB128 value")?;
+        value |= ((byte & 0x7F) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            *pos += i + 1;
+            return Ok((value, 7 * (i as u32 + 1), byte));
+        }
+    }
+    Err("LEB128 value is longer than 10 bytes".to_string())
+}
+
+/// Read an unsigned LEB128 value at `*pos`, advancing past it
+pub fn read_uleb128(data: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let start = *pos;
+    let (value, bits, last) = read_groups(data, pos)?;
+    // The tenth byte may only carry the top bit
+    if bits == 70 && last > 1 {
+        *pos = start;
+        return Err("LEB128 value overflows 64 bits".to_string());
+    }
+    Ok(value)
+}
+
+/// Read a signed LEB128 value at `*pos`, advancing past it
+pub fn read_sleb128(data: &[u8], pos: &mut usize) -> Result<i64, String> {
+    let start = *pos;
+    let (value, bits, last) = read_groups(data, pos)?;
+    if bits == 70 && last != 0 && last != 0x7F {
+        *pos = start;
+        return Err("LEB128 value overflows 64 bits".to_string());
+    }
+    // Sign-extend from the last group's sign bit
+    if bits < 64 && last & 0x40 != 0 {
+        return Ok((value | (!0u64 << bits)) as i64);
+    }
+    Ok(value as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leb128_vectors() {
+        let unsigned: [(u64, &[u8]); 4] = [(0, &[0]), (127, &[0x7F]), (624485, &[0xE5, 0x8E, 0x26]), (u64::MAX, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01])];
+        for (value, bytes) in unsigned {
+            let mut out = Vec::new();
+            write_uleb128(&mut out, value);
+            assert_eq!(out, bytes);
+            let mut pos = 0;
+            assert_eq!(read_uleb128(&out, &mut pos), Ok(value));
+            assert_eq!(pos, bytes.len());
+        }
+
+        let signed: [(i64, &[u8]); 5] = [(0, &[0]), (-1, &[0x7F]), (63, &[0x3F]), (-64, &[0x40]), (-123456, &[0xC0, 0xBB, 0x78])];
+        for (value, bytes) in signed {
+            let mut out = Vec::new();
+            write_sleb128(&mut out, value);
+            assert_eq!(out, bytes);
+            assert_eq!(read_sleb128(&out, &mut 0), Ok(value));
+        }
+        for value in [i64::MIN, i64::MAX, 64, -65] {
+            let mut out = Vec::new();
+            write_sleb128(&mut out, value);
+            assert_eq!(read_sleb128(&out, &mut 0), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert!(read_uleb128(&[0x80, 0x80], &mut 0).is_err());
+        assert!(read_uleb128(&[0xFF; 10], &mut 0).is_err());
+        assert!(read_uleb128(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02], &mut 0).is_err());
+        for v in [0, 1, -1, i64::MIN, i64::MAX] {
+            assert_eq!(zigzag_decode(zigzag_encode(v)), v);
+        }
+        assert_eq!(zigzag_encode(-1), 1);
+        assert_eq!(zigzag_encode(1), 2);
+    }
+}
@@ -0,0 +1,78 @@
+//! Binary serialization - LEB128 varints, the protobuf wire format, bencode
+//! and CBOR
+
+pub mod bencode;
+pub mod cbor;
+#[cfg(feature = "wasm")]
+mod js;
+pub mod leb128;
+pub mod protobuf;
+
+pub use bencode::{bencode_decode, bencode_encode, BencodeValue};
+pub use cbor::{cbor_decode, cbor_encode, CborValue};
+pub use leb128::{read_sleb128, read_uleb128, write_sleb128, write_uleb128, zigzag_decode, zigzag_encode};
+pub use protobuf::{read_fields, ProtoField, ProtoMessage, ProtoWriter, WireType};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Nesting limit for decoding and encoding, guarding the stack
+pub(crate) const MAX_DEPTH: usize = 128;
+
+/// Encode values as consecutive unsigned LEB128 varints
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = uleb128Encode)]
+pub fn uleb128_encode_js(values: &[u64]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(values.len());
+    for &v in values {
+        write_uleb128(&mut out, v);
+    }
+    out
+}
+
+/// Decode every unsigned LEB128 varint in `data`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = uleb128Decode)]
+pub fn uleb128_decode_js(data: &[u8]) -> Result<Vec<u64>, JsError> {
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        values.push(read_uleb128(data, &mut pos).map_err(|e| JsError::new(&e))?);
+    }
+    Ok(values)
+}
+
+/// Encode values as consecutive signed LEB128 varints
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = sleb128Encode)]
+pub fn sleb128_encode_js(values: &[i64]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(values.len());
+    for &v in values {
+        write_sleb128(&mut out, v);
+    }
+    out
+}
+
+/// Decode every signed LEB128 varint in `data`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = sleb128Decode)]
+pub fn sleb128_decode_js(data: &[u8]) -> Result<Vec<i64>, JsError> {
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        values.push(read_sleb128(data, &mut pos).map_err(|e| JsError::new(&e))?);
+    }
+    Ok(values)
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = zigzagEncode)]
+pub fn zigzag_encode_js(value: i64) -> u64 {
+    zigzag_encode(value)
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = zigzagDecode)]
+pub fn zigzag_decode_js(value: u64) -> i64 {
+    zigzag_decode(value)
+}
@@ -0,0 +1,299 @@
+//! Protocol Buffers wire format - field-level reading and writing
+//!
+//! Messages are handled without a schema: each field is returned with its
+//! number, wire type and raw value, and nested messages can be parsed again
+//! from a length-delimited field's bytes.
+
+use super::leb128::{read_uleb128, write_uleb128, zigzag_decode, zigzag_encode};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Largest field number allowed by the wire format
+const MAX_FIELD_NUMBER: u32 = (1 << 29) - 1;
+
+/// How a field's value is encoded
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireType {
+    Varint = 0,
+    Fixed64 = 1,
+    LengthDelimited = 2,
+    StartGroup = 3,
+    EndGroup = 4,
+    Fixed32 = 5,
+}
+
+/// One field of a protobuf message
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtoField {
+    pub number: u32,
+    pub wire_type: WireType,
+    /// Varint or fixed-width value; zero for other wire types
+    pub value: u64,
+    /// Payload of a length-delimited field
+    pub data: Vec<u8>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ProtoField {
+    /// Value as a zigzag-encoded `sint32`/`sint64`
+    pub fn sint(&self) -> i64 {
+        zigzag_decode(self.value)
+    }
+
+    /// Value as a two's complement `int32`/`int64`/`sfixed64`
+    pub fn int(&self) -> i64 {
+        self.value as i64
+    }
+
+    pub fn float(&self) -> f32 {
+        f32::from_bits(self.value as u32)
+    }
+
+    pub fn double(&self) -> f64 {
+        f64::from_bits(self.value)
+    }
+
+    /// Payload as UTF-8 text, if valid
+    pub fn string(&self) -> Option<String> {
+        String::from_utf8(self.data.clone()).ok()
+    }
+}
+
+/// Split a message into its fields
+pub fn read_fields(data: &[u8]) -> Result<Vec<ProtoField>, String> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let key = read_uleb128(data, &mut pos)?;
+        let number = u32::try_from(key >> 3).ok().filter(|n| (1..=MAX_FIELD_NUMBER).contains(n)).ok_or("Invalid protobuf field number")?;
+        let fixed = |pos: &mut usize, len: usize| -> Result<u64, String> {
+            let bytes = data.get(*pos..*pos + len).ok_or("Truncated protobuf field")?;
+            *pos += len;
+            Ok(bytes.iter().rev().fold(0, |v, &b| v << 8 | b as u64))
+        };
+        let (wire_type, value, payload) = match key & 7 {
+            0 => (WireType::Varint, read_uleb128(data, &mut pos)?, Vec::new()),
+            1 => (WireType::Fixed64, fixed(&mut pos, 8)?, Vec::new()),
+            2 => {
+                let len = usize::try_from(read_uleb128(data, &mut pos)?).map_err(|_| "Protobuf field too long")?;
+                let bytes = data.get(pos..).and_then(|d| d.get(..len)).ok_or("Truncated protobuf field")?;
+                pos += len;
+                (WireType::LengthDelimited, 0, bytes.to_vec())
+            }
+            3 => (WireType::StartGroup, 0, Vec::new()),
+            4 => (WireType::EndGroup, 0, Vec::new()),
+            5 => (WireType::Fixed32, fixed(&mut pos, 4)?, Vec::new()),
+            other => return Err(format!("Invalid protobuf wire type {}", other)),
+        };
+        fields.push(ProtoField { number, wire_type, value, data: payload });
+    }
+    Ok(fields)
+}
+
+/// A decoded protobuf message
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ProtoMessage {
+    fields: Vec<ProtoField>,
+}
+
+impl ProtoMessage {
+    pub fn parse(data: &[u8]) -> Result<ProtoMessage, String> {
+        Ok(ProtoMessage { fields: read_fields(data)? })
+    }
+
+    pub fn fields(&self) -> &[ProtoField] {
+        &self.fields
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ProtoMessage {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &[u8]) -> Result<ProtoMessage, JsError> {
+        ProtoMessage::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// Number of fields, counting each repeated value
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn length(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn field(&self, index: usize) -> Option<ProtoField> {
+        self.fields.get(index).cloned()
+    }
+
+    /// Index of the last field with this number, which wins for scalars
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = findField))]
+    pub fn find_field(&self, number: u32) -> Option<usize> {
+        self.fields.iter().rposition(|f| f.number == number)
+    }
+}
+
+/// Builds a protobuf message field by field
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ProtoWriter {
+    out: Vec<u8>,
+}
+
+impl Default for ProtoWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtoWriter {
+    fn key(&mut self, number: u32, wire_type: WireType) -> Result<(), String> {
+        if !(1..=MAX_FIELD_NUMBER).contains(&number) {
+            return Err(format!("Invalid protobuf field number {}", number));
+        }
+        write_uleb128(&mut self.out, (number as u64) << 3 | wire_type as u64);
+        Ok(())
+    }
+
+    pub fn write_varint(&mut self, number: u32, value: u64) -> Result<(), String> {
+        self.key(number, WireType::Varint)?;
+        write_uleb128(&mut self.out, value);
+        Ok(())
+    }
+
+    /// Write a zigzag-encoded `sint32`/`sint64`
+    pub fn write_sint(&mut self, number: u32, value: i64) -> Result<(), String> {
+        self.write_varint(number, zigzag_encode(value))
+    }
+
+    pub fn write_fixed32(&mut self, number: u32, value: u32) -> Result<(), String> {
+        self.key(number, WireType::Fixed32)?;
+        self.out.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    pub fn write_fixed64(&mut self, number: u32, value: u64) -> Result<(), String> {
+        self.key(number, WireType::Fixed64)?;
+        self.out.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    /// Write a length-delimited field (bytes, string or nested message)
+    pub fn write_bytes(&mut self, number: u32, data: &[u8]) -> Result<(), String> {
+        self.key(number, WireType::LengthDelimited)?;
+        write_uleb128(&mut self.out, data.len() as u64);
+        self.out.extend_from_slice(data);
+        Ok(())
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.out
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ProtoWriter {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> ProtoWriter {
+        ProtoWriter { out: Vec::new() }
+    }
+
+    /// Write an unsigned or two's complement integer
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = varint)]
+    pub fn varint_js(&mut self, number: u32, value: u64) -> Result<(), JsError> {
+        self.write_varint(number, value).map_err(|e| JsError::new(&e))
+    }
+
+    /// Write a zigzag-encoded `sint32`/`sint64`
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = sint)]
+    pub fn sint_js(&mut self, number: u32, value: i64) -> Result<(), JsError> {
+        self.write_sint(number, value).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = fixed32)]
+    pub fn fixed32_js(&mut self, number: u32, value: u32) -> Result<(), JsError> {
+        self.write_fixed32(number, value).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = fixed64)]
+    pub fn fixed64_js(&mut self, number: u32, value: u64) -> Result<(), JsError> {
+        self.write_fixed64(number, value).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = float)]
+    pub fn float_js(&mut self, number: u32, value: f32) -> Result<(), JsError> {
+        self.write_fixed32(number, value.to_bits()).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = double)]
+    pub fn double_js(&mut self, number: u32, value: f64) -> Result<(), JsError> {
+        self.write_fixed64(number, value.to_bits()).map_err(|e| JsError::new(&e))
+    }
+
+    /// Write a length-delimited field (bytes or a nested message)
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = bytes)]
+    pub fn bytes_js(&mut self, number: u32, data: &[u8]) -> Result<(), JsError> {
+        self.write_bytes(number, data).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = string)]
+    pub fn string_js(&mut self, number: u32, text: &str) -> Result<(), JsError> {
+        self.write_bytes(number, text.as_bytes()).map_err(|e| JsError::new(&e))
+    }
+
+    /// Return the encoded message
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = finish)]
+    pub fn finish_js(self) -> Vec<u8> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_known_message() {
+        // int32 a = 1 (150), string b = 2 ("testing"), sint32 c = 3 (-2), float d = 4 (1.5)
+        let data = [0x08, 0x96, 0x01, 0x12, 0x07, b't', b'e', b's', b't', b'i', b'n', b'g', 0x18, 0x03, 0x25, 0, 0, 0xC0, 0x3F];
+        let message = ProtoMessage::parse(&data).unwrap();
+        let f = message.fields();
+        assert_eq!((f[0].number, f[0].wire_type, f[0].value), (1, WireType::Varint, 150));
+        assert_eq!((f[1].wire_type, f[1].string().as_deref()), (WireType::LengthDelimited, Some("testing")));
+        assert_eq!(f[2].sint(), -2);
+        assert_eq!((f[3].wire_type, f[3].float()), (WireType::Fixed32, 1.5));
+        assert_eq!(message.find_field(3), Some(2));
+
+        assert!(read_fields(&[0x12, 0x05, b'a']).is_err());
+        assert!(read_fields(&[0x0F]).is_err());
+        assert!(read_fields(&[0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_writer_roundtrip() {
+        let mut inner = ProtoWriter::new();
+        inner.write_sint(1, -300).unwrap();
+        let inner = inner.finish();
+
+        let mut w = ProtoWriter::new();
+        w.write_varint(1, 150).unwrap();
+        w.write_fixed64(2, 2.5f64.to_bits()).unwrap();
+        w.write_bytes(MAX_FIELD_NUMBER, &inner).unwrap();
+        assert!(w.write_varint(0, 1).is_err());
+        let data = w.finish();
+        assert_eq!(&data[..3], [0x08, 0x96, 0x01]);
+
+        let fields = read_fields(&data).unwrap();
+        assert_eq!(fields[1].double(), 2.5);
+        assert_eq!(fields[2].number, MAX_FIELD_NUMBER);
+        assert_eq!(read_fields(&fields[2].data).unwrap()[0].sint(), -300);
+    }
+}
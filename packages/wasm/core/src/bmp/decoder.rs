@@ -1,8 +1,11 @@
 //! BMP decoder - pure Rust implementation
 
+use crate::limits::Budget;
 use crate::utils::{read_i32_le, read_u16_le, read_u32_le};
+use alloc::{format, string::{String, ToString}, vec::Vec};
 
 const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
 const BI_BITFIELDS: u32 = 3;
 
 /// Decode BMP to RGBA pixel data
@@ -40,11 +43,25 @@ pub fn decode_bmp(data: &[u8]) -> Result<Vec<u8>, String> {
     if abs_width == 0 || abs_height == 0 {
         return Err(format!("Invalid dimensions: {}x{}", abs_width, abs_height));
     }
+    let mut budget = Budget::new();
+    budget.check_size(abs_width, abs_height)?;
+    budget.rows(abs_height)?;
 
     // Validate compression
-    if compression != BI_RGB && compression != BI_BITFIELDS {
+    if compression != BI_RGB && compression != BI_BITFIELDS && !(compression == BI_RLE8 && bits_per_pixel == 8) {
         return Err(format!("Unsupported compression: {}", compression));
     }
+    if ![1, 4, 8, 16, 24, 32].contains(&bits_per_pixel) {
+        return Err(format!("Unsupported bits per pixel: {}", bits_per_pixel));
+    }
+
+    // RLE8 expands to one palette index per pixel, rows in file order
+    let rle_indices = if compression == BI_RLE8 {
+        let encoded = data.get(data_offset..).ok_or("BMP data offset out of range")?;
+        Some(crate::compress::primitives::rle8_decode(encoded, abs_width as u32, abs_height as u32)?)
+    } else {
+        None
+    };
 
     // Read color table for indexed formats
     let color_table: Option<&[u8]> = if bits_per_pixel <= 8 {
@@ -61,6 +78,9 @@ pub fn decode_bmp(data: &[u8]) -> Result<Vec<u8>, String> {
 
     // Bit masks for BITFIELDS
     let (r_mask, g_mask, b_mask, a_mask) = if compression == BI_BITFIELDS && dib_size >= 52 {
+        if data.len() < if dib_size >= 56 { 70 } else { 66 } {
+            return Err("Truncated BMP".to_string());
+        }
         (
             read_u32_le(data, 54),
             read_u32_le(data, 58),
@@ -71,8 +91,13 @@ pub fn decode_bmp(data: &[u8]) -> Result<Vec<u8>, String> {
         (0x00ff0000, 0x0000ff00, 0x000000ff, 0xff000000)
     };
 
-    // Row stride (padded to 4 bytes)
-    let row_stride = ((bits_per_pixel as usize * abs_width + 31) / 32) * 4;
+    // Row stride (padded to 4 bytes); the padding of the last row may be missing
+    let row_stride = (bits_per_pixel as usize * abs_width).div_ceil(32) * 4;
+    let row_bytes = (bits_per_pixel as usize * abs_width).div_ceil(8);
+    let source_row = |src_y: usize| -> Result<&[u8], String> {
+        let start = src_y.checked_mul(row_stride).and_then(|o| o.checked_add(data_offset));
+        start.and_then(|start| data.get(start..start.checked_add(row_bytes)?)).ok_or_else(|| "Truncated BMP".to_string())
+    };
 
     // Output: [width, height, rgba_data...]
     let mut output = Vec::with_capacity(8 + abs_width * abs_height * 4);
@@ -84,25 +109,23 @@ pub fn decode_bmp(data: &[u8]) -> Result<Vec<u8>, String> {
     // Decode pixels
     for y in 0..abs_height {
         let src_y = if top_down { y } else { abs_height - 1 - y };
-        let src_row_offset = data_offset + src_y * row_stride;
+        let row = if rle_indices.is_some() { &[][..] } else { source_row(src_y)? };
 
         for x in 0..abs_width {
             let (r, g, b, a) = match bits_per_pixel {
                 1 => {
-                    let byte_idx = src_row_offset + x / 8;
                     let bit_idx = 7 - (x % 8);
-                    let color_idx = ((data[byte_idx] >> bit_idx) & 1) as usize;
+                    let color_idx = ((row[x / 8] >> bit_idx) & 1) as usize;
                     let table = color_table.unwrap();
                     let table_idx = color_idx * 4;
                     (table[table_idx + 2], table[table_idx + 1], table[table_idx], 255)
                 }
 
                 4 => {
-                    let byte_idx = src_row_offset + x / 2;
                     let nibble = if x % 2 == 0 {
-                        (data[byte_idx] >> 4) & 0x0f
+                        (row[x / 2] >> 4) & 0x0f
                     } else {
-                        data[byte_idx] & 0x0f
+                        row[x / 2] & 0x0f
                     } as usize;
                     let table = color_table.unwrap();
                     let table_idx = nibble * 4;
@@ -110,15 +133,17 @@ pub fn decode_bmp(data: &[u8]) -> Result<Vec<u8>, String> {
                 }
 
                 8 => {
-                    let color_idx = data[src_row_offset + x] as usize;
+                    let color_idx = match &rle_indices {
+                        Some(indices) => indices[src_y * abs_width + x] as usize,
+                        None => row[x] as usize,
+                    };
                     let table = color_table.unwrap();
                     let table_idx = color_idx * 4;
                     (table[table_idx + 2], table[table_idx + 1], table[table_idx], 255)
                 }
 
                 16 => {
-                    let pixel_offset = src_row_offset + x * 2;
-                    let pixel = read_u16_le(data, pixel_offset);
+                    let pixel = read_u16_le(row, x * 2);
                     let r = (((pixel >> 10) & 0x1f) << 3) as u8;
                     let g = (((pixel >> 5) & 0x1f) << 3) as u8;
                     let b = ((pixel & 0x1f) << 3) as u8;
@@ -126,14 +151,14 @@ pub fn decode_bmp(data: &[u8]) -> Result<Vec<u8>, String> {
                 }
 
                 24 => {
-                    let pixel_offset = src_row_offset + x * 3;
-                    (data[pixel_offset + 2], data[pixel_offset + 1], data[pixel_offset], 255)
+                    let pixel_offset = x * 3;
+                    (row[pixel_offset + 2], row[pixel_offset + 1], row[pixel_offset], 255)
                 }
 
                 32 => {
-                    let pixel_offset = src_row_offset + x * 4;
+                    let pixel_offset = x * 4;
                     if compression == BI_BITFIELDS {
-                        let pixel = read_u32_le(data, pixel_offset);
+                        let pixel = read_u32_le(row, pixel_offset);
                         (
                             apply_mask(pixel, r_mask),
                             apply_mask(pixel, g_mask),
@@ -141,7 +166,7 @@ pub fn decode_bmp(data: &[u8]) -> Result<Vec<u8>, String> {
                             if a_mask != 0 { apply_mask(pixel, a_mask) } else { 255 },
                         )
                     } else {
-                        (data[pixel_offset + 2], data[pixel_offset + 1], data[pixel_offset], data[pixel_offset + 3])
+                        (row[pixel_offset + 2], row[pixel_offset + 1], row[pixel_offset], row[pixel_offset + 3])
                     }
                 }
 
@@ -155,9 +180,30 @@ pub fn decode_bmp(data: &[u8]) -> Result<Vec<u8>, String> {
         }
     }
 
+    // Convert wide-gamut pixels to sRGB; an unusable profile leaves data
+    // untouched, and no_std builds, which lack the float math, skip it
+    #[cfg(feature = "std")]
+    if let Some(profile) = embedded_icc_profile(data) {
+        if let Ok(converted) = crate::icc::apply_icc_profile(&output[8..], abs_width as u32, abs_height as u32, profile) {
+            output[8..].copy_from_slice(&converted);
+        }
+    }
+
     Ok(output)
 }
 
+/// Embedded ICC profile of a BITMAPV5HEADER BMP, if any
+pub fn embedded_icc_profile(data: &[u8]) -> Option<&[u8]> {
+    const PROFILE_EMBEDDED: u32 = 0x4D42_4544; // 'MBED'
+
+    if data.len() < 14 + 124 || read_u32_le(data, 14) < 124 || read_u32_le(data, 14 + 56) != PROFILE_EMBEDDED {
+        return None;
+    }
+    let offset = 14 + read_u32_le(data, 14 + 112) as usize;
+    let size = read_u32_le(data, 14 + 116) as usize;
+    data.get(offset..offset.checked_add(size)?)
+}
+
 /// Apply bit mask and normalize to 0-255
 #[inline]
 fn apply_mask(value: u32, mask: u32) -> u8 {
@@ -184,6 +230,38 @@ fn apply_mask(value: u32, mask: u32) -> u8 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_decode_rle8() {
+        // 4x2 8-bit BMP, bottom-up, palette: 0 = black, 1 = red
+        let pixels = crate::compress::primitives::rle8_encode(&[1, 1, 1, 1, 0, 1, 0, 1], 4, 2).unwrap();
+        let mut bmp = vec![0u8; 54 + 256 * 4];
+        bmp[0..2].copy_from_slice(b"BM");
+        bmp[10..14].copy_from_slice(&(54u32 + 1024).to_le_bytes());
+        bmp[14..18].copy_from_slice(&40u32.to_le_bytes());
+        bmp[18..22].copy_from_slice(&4i32.to_le_bytes());
+        bmp[22..26].copy_from_slice(&2i32.to_le_bytes());
+        bmp[28..30].copy_from_slice(&8u16.to_le_bytes());
+        bmp[30..34].copy_from_slice(&BI_RLE8.to_le_bytes());
+        bmp[54 + 4 + 2] = 255;
+        bmp.extend_from_slice(&pixels);
+
+        let decoded = decode_bmp(&bmp).unwrap();
+        // Top row is the second stored row
+        assert_eq!(&decoded[8..12], &[0, 0, 0, 255]);
+        assert_eq!(&decoded[12..16], &[255, 0, 0, 255]);
+        assert!(decoded[8 + 16..].chunks(4).all(|p| p == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_truncated() {
+        // 32-bit BITFIELDS, so 54 bytes stop inside the masks
+        let bmp = crate::bmp::encode_bmp(3, 3, &[200u8; 3 * 3 * 4]).unwrap();
+        assert!(decode_bmp(&bmp).is_ok());
+        for len in [54, bmp.len() - 12] {
+            assert_eq!(decode_bmp(&bmp[..len]), Err("Truncated BMP".to_string()));
+        }
+    }
+
     #[test]
     fn test_apply_mask() {
         assert_eq!(apply_mask(0x00ff0000, 0x00ff0000), 255);
@@ -0,0 +1,248 @@
+//! BMP encoder - pure Rust implementation
+
+use crate::utils::{write_u16_le, write_u32_le};
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+/// Encode RGBA pixel data to BMP format (32-bit with alpha)
+pub fn encode_bmp(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+    encode_bmp_rgba(width, height, data, None)
+}
+
+/// Encode RGBA pixel data to a BITMAPV5HEADER BMP with an embedded ICC profile
+pub fn encode_bmp_with_profile(width: u32, height: u32, data: &[u8], profile: &[u8]) -> Result<Vec<u8>, String> {
+    if profile.is_empty() {
+        return Err("ICC profile is empty".to_string());
+    }
+    encode_bmp_rgba(width, height, data, Some(profile))
+}
+
+fn encode_bmp_rgba(width: u32, height: u32, data: &[u8], profile: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let expected_len = (width * height * 4) as usize;
+    if data.len() != expected_len {
+        return Err(format!(
+            "Data length mismatch: expected {}, got {}",
+            expected_len,
+            data.len()
+        ));
+    }
+
+    // BITMAPV4HEADER for alpha support, V5 when a profile is embedded
+    let header_size: u32 = 14; // File header
+    let dib_size: u32 = if profile.is_some() { 124 } else { 108 };
+    let data_offset = header_size + dib_size;
+
+    // Row stride (32-bit = 4 bytes per pixel, already 4-byte aligned)
+    let row_stride = width * 4;
+    let pixel_data_size = row_stride * height;
+
+    // Profile data follows the pixels
+    let profile_size = profile.map_or(0, |p| p.len() as u32);
+    let file_size = data_offset + pixel_data_size + profile_size;
+    let mut output = vec![0u8; file_size as usize];
+
+    // File header (14 bytes)
+    output[0] = 0x42; // 'B'
+    output[1] = 0x4D; // 'M'
+    write_u32_le(&mut output, 2, file_size);
+    write_u16_le(&mut output, 6, 0); // Reserved
+    write_u16_le(&mut output, 8, 0); // Reserved
+    write_u32_le(&mut output, 10, data_offset);
+
+    // BITMAPV4HEADER (108 bytes)
+    write_u32_le(&mut output, 14, dib_size);
+    write_u32_le(&mut output, 18, width);
+    write_u32_le(&mut output, 22, height); // Positive = bottom-up
+    write_u16_le(&mut output, 26, 1); // Planes
+    write_u16_le(&mut output, 28, 32); // Bits per pixel
+    write_u32_le(&mut output, 30, 3); // BI_BITFIELDS
+    write_u32_le(&mut output, 34, pixel_data_size);
+    write_u32_le(&mut output, 38, 2835); // X pixels per meter (~72 DPI)
+    write_u32_le(&mut output, 42, 2835); // Y pixels per meter
+    write_u32_le(&mut output, 46, 0); // Colors used
+    write_u32_le(&mut output, 50, 0); // Important colors
+
+    // Bit masks for RGBA
+    write_u32_le(&mut output, 54, 0x00ff0000); // Red mask
+    write_u32_le(&mut output, 58, 0x0000ff00); // Green mask
+    write_u32_le(&mut output, 62, 0x000000ff); // Blue mask
+    write_u32_le(&mut output, 66, 0xff000000); // Alpha mask
+
+    // Color space (LCS_sRGB)
+    write_u32_le(&mut output, 70, 0x73524742); // 'sRGB'
+
+    // CIEXYZTRIPLE endpoints and gamma values (48 bytes total) - zeros for sRGB
+    // Already zeroed by vec![0u8; ...]
+
+    if let Some(profile) = profile {
+        let profile_offset = data_offset + pixel_data_size;
+        write_u32_le(&mut output, 70, 0x4D424544); // PROFILE_EMBEDDED 'MBED'
+        write_u32_le(&mut output, 122, 4); // LCS_GM_IMAGES
+        write_u32_le(&mut output, 126, profile_offset - header_size); // Relative to DIB header
+        write_u32_le(&mut output, 130, profile_size);
+        output[profile_offset as usize..].copy_from_slice(profile);
+    }
+
+    // Write pixel data (bottom-up)
+    let data_start = data_offset as usize;
+    for y in 0..height as usize {
+        let src_y = (height as usize) - 1 - y; // Flip vertically
+        let src_row_offset = src_y * (width as usize) * 4;
+        let dst_row_offset = data_start + y * (row_stride as usize);
+
+        for x in 0..width as usize {
+            let src_idx = src_row_offset + x * 4;
+            let dst_idx = dst_row_offset + x * 4;
+
+            // RGBA -> BGRA
+            output[dst_idx] = data[src_idx + 2]; // B
+            output[dst_idx + 1] = data[src_idx + 1]; // G
+            output[dst_idx + 2] = data[src_idx]; // R
+            output[dst_idx + 3] = data[src_idx + 3]; // A
+        }
+    }
+
+    Ok(output)
+}
+
+/// Encode palette indices to a paletted BMP (1, 4, or 8 bits per pixel)
+///
+/// `palette` holds RGBA entries (alpha is ignored); the bit depth is the
+/// smallest that fits the palette.
+pub fn encode_bmp_indexed(width: u32, height: u32, palette: &[u8], indices: &[u8]) -> Result<Vec<u8>, String> {
+    let pixel_count = (width * height) as usize;
+    if indices.len() != pixel_count {
+        return Err(format!(
+            "Index length mismatch: expected {}, got {}",
+            pixel_count,
+            indices.len()
+        ));
+    }
+    if palette.is_empty() || !palette.len().is_multiple_of(4) || palette.len() > 256 * 4 {
+        return Err(format!("Invalid palette length: {}", palette.len()));
+    }
+
+    let color_count = palette.len() / 4;
+    if let Some(&bad) = indices.iter().find(|&&i| i as usize >= color_count) {
+        return Err(format!("Palette index out of range: {}", bad));
+    }
+
+    let bits_per_pixel: u32 = match color_count {
+        0..=2 => 1,
+        3..=16 => 4,
+        _ => 8,
+    };
+    let table_entries = 1u32 << bits_per_pixel;
+
+    let header_size: u32 = 14;
+    let dib_size: u32 = 40; // BITMAPINFOHEADER
+    let data_offset = header_size + dib_size + table_entries * 4;
+    let row_stride = (bits_per_pixel * width).div_ceil(32) * 4;
+    let pixel_data_size = row_stride * height;
+    let file_size = data_offset + pixel_data_size;
+    let mut output = vec![0u8; file_size as usize];
+
+    // File header
+    output[0] = 0x42; // 'B'
+    output[1] = 0x4D; // 'M'
+    write_u32_le(&mut output, 2, file_size);
+    write_u32_le(&mut output, 10, data_offset);
+
+    // BITMAPINFOHEADER
+    write_u32_le(&mut output, 14, dib_size);
+    write_u32_le(&mut output, 18, width);
+    write_u32_le(&mut output, 22, height); // Positive = bottom-up
+    write_u16_le(&mut output, 26, 1); // Planes
+    write_u16_le(&mut output, 28, bits_per_pixel as u16);
+    write_u32_le(&mut output, 30, 0); // BI_RGB
+    write_u32_le(&mut output, 34, pixel_data_size);
+    write_u32_le(&mut output, 38, 2835); // X pixels per meter (~72 DPI)
+    write_u32_le(&mut output, 42, 2835); // Y pixels per meter
+    write_u32_le(&mut output, 46, table_entries); // Colors used
+    write_u32_le(&mut output, 50, 0); // Important colors
+
+    // Color table (BGRX)
+    for (i, entry) in palette.chunks_exact(4).enumerate() {
+        let at = 54 + i * 4;
+        output[at] = entry[2];
+        output[at + 1] = entry[1];
+        output[at + 2] = entry[0];
+    }
+
+    // Pack indices (bottom-up, MSB first)
+    let per_byte = (8 / bits_per_pixel) as usize;
+    for y in 0..height as usize {
+        let src_row = &indices[(height as usize - 1 - y) * width as usize..][..width as usize];
+        let dst_row_offset = data_offset as usize + y * row_stride as usize;
+
+        for (x, &index) in src_row.iter().enumerate() {
+            let byte = dst_row_offset + x / per_byte;
+            let shift = 8 - bits_per_pixel as usize * (x % per_byte + 1);
+            output[byte] |= index << shift;
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bmp::decoder::decode_bmp;
+
+    #[test]
+    fn test_roundtrip() {
+        let width = 2u32;
+        let height = 2u32;
+        let data = vec![
+            255, 0, 0, 255,     // Red
+            0, 255, 0, 255,     // Green
+            0, 0, 255, 255,     // Blue
+            255, 255, 255, 255, // White
+        ];
+
+        let encoded = encode_bmp(width, height, &data).unwrap();
+
+        // Check signature
+        assert_eq!(encoded[0], 0x42);
+        assert_eq!(encoded[1], 0x4D);
+
+        let decoded = decode_bmp(&encoded).unwrap();
+
+        // First 8 bytes are dimensions
+        let dec_width = u32::from_le_bytes([decoded[0], decoded[1], decoded[2], decoded[3]]);
+        let dec_height = u32::from_le_bytes([decoded[4], decoded[5], decoded[6], decoded[7]]);
+
+        assert_eq!(dec_width, width);
+        assert_eq!(dec_height, height);
+        assert_eq!(&decoded[8..], &data[..]);
+    }
+
+    #[test]
+    fn test_embedded_profile_roundtrip() {
+        let profile = crate::icc::BuiltinProfile::Srgb.to_bytes();
+        let data = [10, 200, 90, 255, 250, 120, 0, 128];
+
+        let encoded = encode_bmp_with_profile(2, 1, &data, &profile).unwrap();
+        assert_eq!(crate::bmp::embedded_icc_profile(&encoded), Some(&profile[..]));
+
+        let decoded = decode_bmp(&encoded).unwrap();
+        for (a, b) in data.iter().zip(&decoded[8..]) {
+            assert!((*a as i32 - *b as i32).abs() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_indexed_roundtrip() {
+        let palette = [0, 0, 0, 255, 255, 255, 255, 255];
+        let indices = [0, 1, 1, 0, 1, 0, 0, 0, 1];
+
+        let encoded = encode_bmp_indexed(3, 3, &palette, &indices).unwrap();
+        assert_eq!(u16::from_le_bytes([encoded[28], encoded[29]]), 1);
+
+        let decoded = decode_bmp(&encoded).unwrap();
+        for (i, &index) in indices.iter().enumerate() {
+            let expected = if index == 1 { 255 } else { 0 };
+            assert_eq!(decoded[8 + i * 4], expected);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! BMP codec implementation in pure Rust
+
+mod decoder;
+mod encoder;
+
+pub use decoder::{decode_bmp, embedded_icc_profile};
+pub use encoder::{encode_bmp, encode_bmp_indexed, encode_bmp_with_profile};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Decode BMP to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeBmp)]
+pub fn decode_bmp_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    decode_bmp(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode RGBA to BMP
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeBmp)]
+pub fn encode_bmp_js(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, JsError> {
+    encode_bmp(width, height, data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode RGBA to a BMP V5 with an embedded ICC profile
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeBmpWithProfile)]
+pub fn encode_bmp_with_profile_js(width: u32, height: u32, data: &[u8], profile: &[u8]) -> Result<Vec<u8>, JsError> {
+    encode_bmp_with_profile(width, height, data, profile).map_err(|e| JsError::new(&e))
+}
+
+/// Encode palette indices to a 1/4/8-bit paletted BMP
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeBmpIndexed)]
+pub fn encode_bmp_indexed_js(width: u32, height: u32, palette: &[u8], indices: &[u8]) -> Result<Vec<u8>, JsError> {
+    encode_bmp_indexed(width, height, palette, indices).map_err(|e| JsError::new(&e))
+}
+
+/// Quantize (optionally dithered) and encode RGBA to a paletted BMP
+///
+/// Two colors produce a 1-bit BMP, up to 16 a 4-bit one, otherwise 8-bit.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeBmpPaletted)]
+pub fn encode_bmp_paletted_js(
+    width: u32,
+    height: u32,
+    data: &[u8],
+    options: &crate::quantize::QuantizeOptions,
+) -> Result<Vec<u8>, JsError> {
+    let quantized = crate::quantize::quantize_with_options(data, width, height, options).map_err(|e| JsError::new(&e))?;
+    encode_bmp_indexed(width, height, &quantized.palette, &quantized.indices).map_err(|e| JsError::new(&e))
+}
+
+/// Get decoded image dimensions from BMP header
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = getBmpDimensions)]
+pub fn get_bmp_dimensions(data: &[u8]) -> Result<Vec<u32>, JsError> {
+    if data.len() < 26 {
+        return Err(JsError::new("BMP data too small"));
+    }
+
+    if data[0] != 0x42 || data[1] != 0x4D {
+        return Err(JsError::new("Invalid BMP signature"));
+    }
+
+    let width = crate::utils::read_i32_le(data, 18).unsigned_abs();
+    let height = crate::utils::read_i32_le(data, 22).unsigned_abs();
+
+    Ok(vec![width, height])
+}
@@ -0,0 +1,316 @@
+//! Checksums - CRC-32, CRC-32C, CRC-64, Adler-32 and xxHash
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Byte-wise lookup table for the reflected polynomial
+const fn crc_table(poly: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { poly ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// Slice-by-8 tables: `t[k][b]` is the CRC of byte `b` followed by `k` zero bytes
+const fn slice_tables(poly: u32) -> [[u32; 256]; 8] {
+    let mut tables = [[0u32; 256]; 8];
+    tables[0] = crc_table(poly);
+    let mut k = 1;
+    while k < 8 {
+        let mut i = 0;
+        while i < 256 {
+            let prev = tables[k - 1][i];
+            tables[k][i] = (prev >> 8) ^ tables[0][(prev & 0xFF) as usize];
+            i += 1;
+        }
+        k += 1;
+    }
+    tables
+}
+
+const CRC32_TABLES: [[u32; 256]; 8] = slice_tables(0xEDB8_8320);
+const CRC32C_TABLES: [[u32; 256]; 8] = slice_tables(0x82F6_3B78);
+
+/// Reflected CRC-32 update, eight bytes per step
+fn crc32_sliced(t: &[[u32; 256]; 8], crc: u32, data: &[u8]) -> u32 {
+    let mut c = !crc;
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let lo = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) ^ c;
+        let hi = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+        c = t[7][(lo & 0xFF) as usize]
+            ^ t[6][((lo >> 8) & 0xFF) as usize]
+            ^ t[5][((lo >> 16) & 0xFF) as usize]
+            ^ t[4][(lo >> 24) as usize]
+            ^ t[3][(hi & 0xFF) as usize]
+            ^ t[2][((hi >> 8) & 0xFF) as usize]
+            ^ t[1][((hi >> 16) & 0xFF) as usize]
+            ^ t[0][(hi >> 24) as usize];
+    }
+    !chunks
+        .remainder()
+        .iter()
+        .fold(c, |c, &b| t[0][((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8))
+}
+
+/// Continue a CRC-32 over more data (start with 0)
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    crc32_sliced(&CRC32_TABLES, crc, data)
+}
+
+/// CRC-32 (IEEE 802.3, as used by PNG, gzip, and ZIP)
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// Continue a CRC-32C over more data (start with 0)
+pub fn crc32c_update(crc: u32, data: &[u8]) -> u32 {
+    crc32_sliced(&CRC32C_TABLES, crc, data)
+}
+
+/// CRC-32C (Castagnoli, as used by Snappy framing and iSCSI)
+pub fn crc32c(data: &[u8]) -> u32 {
+    crc32c_update(0, data)
+}
+
+const CRC64_TABLE: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u64;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xC96C_5795_D787_0F42 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-64 (ECMA-182, as used by XZ)
+pub fn crc64(data: &[u8]) -> u64 {
+    !data
+        .iter()
+        .fold(!0u64, |c, &b| CRC64_TABLE[((c ^ b as u64) & 0xFF) as usize] ^ (c >> 8))
+}
+
+const ADLER_MOD: u32 = 65521;
+/// Largest run before the sums must be reduced to avoid u32 overflow
+const ADLER_NMAX: usize = 5552;
+
+/// Continue an Adler-32 over more data (start with 1)
+pub fn adler32_update(adler: u32, data: &[u8]) -> u32 {
+    let mut a = adler & 0xFFFF;
+    let mut b = adler >> 16;
+    for chunk in data.chunks(ADLER_NMAX) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= ADLER_MOD;
+        b %= ADLER_MOD;
+    }
+    (b << 16) | a
+}
+
+/// Adler-32 (as used by zlib)
+pub fn adler32(data: &[u8]) -> u32 {
+    adler32_update(1, data)
+}
+
+const XXH32_PRIMES: [u32; 5] = [0x9E37_79B1, 0x85EB_CA77, 0xC2B2_AE3D, 0x27D4_EB2F, 0x1656_67B1];
+const XXH64_PRIMES: [u64; 5] = [
+    0x9E37_79B1_85EB_CA87,
+    0xC2B2_AE3D_27D4_EB4F,
+    0x1656_67B1_9E37_79F9,
+    0x85EB_CA77_C2B2_AE63,
+    0x27D4_EB2F_1656_67C5,
+];
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes([data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7]])
+}
+
+/// xxHash32 with a seed
+pub fn xxh32(data: &[u8], seed: u32) -> u32 {
+    let [p1, p2, p3, p4, p5] = XXH32_PRIMES;
+    let round = |acc: u32, input: u32| acc.wrapping_add(input.wrapping_mul(p2)).rotate_left(13).wrapping_mul(p1);
+
+    let mut stripes = data.chunks_exact(16);
+    let mut h = if data.len() >= 16 {
+        let mut v = [seed.wrapping_add(p1).wrapping_add(p2), seed.wrapping_add(p2), seed, seed.wrapping_sub(p1)];
+        for stripe in &mut stripes {
+            for (i, acc) in v.iter_mut().enumerate() {
+                *acc = round(*acc, read_u32(&stripe[i * 4..]));
+            }
+        }
+        v[0].rotate_left(1).wrapping_add(v[1].rotate_left(7)).wrapping_add(v[2].rotate_left(12)).wrapping_add(v[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(p5)
+    };
+    h = h.wrapping_add(data.len() as u32);
+
+    let rest = stripes.remainder();
+    let mut words = rest.chunks_exact(4);
+    for word in &mut words {
+        h = h.wrapping_add(read_u32(word).wrapping_mul(p3)).rotate_left(17).wrapping_mul(p4);
+    }
+    for &b in words.remainder() {
+        h = h.wrapping_add((b as u32).wrapping_mul(p5)).rotate_left(11).wrapping_mul(p1);
+    }
+
+    h ^= h >> 15;
+    h = h.wrapping_mul(p2);
+    h ^= h >> 13;
+    h = h.wrapping_mul(p3);
+    h ^ (h >> 16)
+}
+
+/// xxHash64 with a seed (as used by zstd content checksums)
+pub fn xxh64(data: &[u8], seed: u64) -> u64 {
+    let [p1, p2, p3, p4, p5] = XXH64_PRIMES;
+    let round = |acc: u64, input: u64| acc.wrapping_add(input.wrapping_mul(p2)).rotate_left(31).wrapping_mul(p1);
+    let merge = |h: u64, v: u64| (h ^ round(0, v)).wrapping_mul(p1).wrapping_add(p4);
+
+    let mut stripes = data.chunks_exact(32);
+    let mut h = if data.len() >= 32 {
+        let mut v = [seed.wrapping_add(p1).wrapping_add(p2), seed.wrapping_add(p2), seed, seed.wrapping_sub(p1)];
+        for stripe in &mut stripes {
+            for (i, acc) in v.iter_mut().enumerate() {
+                *acc = round(*acc, read_u64(&stripe[i * 8..]));
+            }
+        }
+        let h = v[0].rotate_left(1).wrapping_add(v[1].rotate_left(7)).wrapping_add(v[2].rotate_left(12)).wrapping_add(v[3].rotate_left(18));
+        v.iter().fold(h, |h, &v| merge(h, v))
+    } else {
+        seed.wrapping_add(p5)
+    };
+    h = h.wrapping_add(data.len() as u64);
+
+    let rest = stripes.remainder();
+    let mut words = rest.chunks_exact(8);
+    for word in &mut words {
+        h = (h ^ round(0, read_u64(word))).rotate_left(27).wrapping_mul(p1).wrapping_add(p4);
+    }
+    let mut tail = words.remainder();
+    if tail.len() >= 4 {
+        h = (h ^ (read_u32(tail) as u64).wrapping_mul(p1)).rotate_left(23).wrapping_mul(p2).wrapping_add(p3);
+        tail = &tail[4..];
+    }
+    for &b in tail {
+        h = (h ^ (b as u64).wrapping_mul(p5)).rotate_left(11).wrapping_mul(p1);
+    }
+
+    h ^= h >> 33;
+    h = h.wrapping_mul(p2);
+    h ^= h >> 29;
+    h = h.wrapping_mul(p3);
+    h ^ (h >> 32)
+}
+
+/// CRC-32 of a byte buffer
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = crc32)]
+pub fn crc32_js(data: &[u8]) -> u32 {
+    crc32(data)
+}
+
+/// Continue a CRC-32 over another chunk (start with 0)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = crc32Update)]
+pub fn crc32_update_js(crc: u32, data: &[u8]) -> u32 {
+    crc32_update(crc, data)
+}
+
+/// CRC-32C of a byte buffer
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = crc32c)]
+pub fn crc32c_js(data: &[u8]) -> u32 {
+    crc32c(data)
+}
+
+/// Continue a CRC-32C over another chunk (start with 0)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = crc32cUpdate)]
+pub fn crc32c_update_js(crc: u32, data: &[u8]) -> u32 {
+    crc32c_update(crc, data)
+}
+
+/// Adler-32 of a byte buffer
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = adler32)]
+pub fn adler32_js(data: &[u8]) -> u32 {
+    adler32(data)
+}
+
+/// Continue an Adler-32 over another chunk (start with 1)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = adler32Update)]
+pub fn adler32_update_js(adler: u32, data: &[u8]) -> u32 {
+    adler32_update(adler, data)
+}
+
+/// xxHash32 of a byte buffer
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = xxh32)]
+pub fn xxh32_js(data: &[u8], seed: u32) -> u32 {
+    xxh32(data, seed)
+}
+
+/// xxHash64 of a byte buffer
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = xxh64)]
+pub fn xxh64_js(data: &[u8], seed: u64) -> u64 {
+    xxh64(data, seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xCBF4_3926);
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc64(b"123456789"), 0x995D_C9BB_DF19_39FA);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn test_sliced_crc_matches_bytewise() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 31 + 7) as u8).collect();
+        for len in [0, 1, 7, 8, 9, 63, 1000] {
+            let bytewise = !data[..len].iter().fold(!0u32, |c, &b| CRC32_TABLES[0][((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8));
+            assert_eq!(crc32(&data[..len]), bytewise);
+        }
+        assert_eq!(crc32c_update(crc32c(b"12345"), b"6789"), 0xE306_9283);
+    }
+
+    #[test]
+    fn test_xxhash() {
+        assert_eq!(xxh32(b"", 0), 0x02CC_5D05);
+        assert_eq!(xxh32(b"abc", 0), 0x32D1_53FF);
+        assert_eq!(xxh64(b"", 0), 0xEF46_DB37_51D8_E999);
+        assert_eq!(xxh64(b"abc", 0), 0x44BC_2CF5_AD77_0999);
+        let long = b"Nobody inspects the spammish repetition";
+        assert_eq!(xxh32(long, 0), 0xE229_3B2F);
+        assert_eq!(xxh64(long, 0), 0xFBCE_A83C_8A37_8BF1);
+    }
+}
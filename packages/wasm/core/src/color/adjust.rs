@@ -0,0 +1,101 @@
+//! Tone adjustments - brightness, contrast, exposure, and gamma via a 256-entry LUT
+
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Tone adjustment parameters
+///
+/// Applied in order: exposure, brightness, contrast, gamma.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adjustments {
+    /// Additive offset in [-1, 1] (fraction of full scale)
+    pub brightness: f32,
+    /// Contrast in [-1, 1] around mid-gray; 0 leaves the image unchanged
+    pub contrast: f32,
+    /// Exposure in stops; each stop doubles intensity
+    pub exposure: f32,
+    /// Gamma > 0; values above 1 brighten midtones
+    pub gamma: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Adjustments {
+    /// Identity adjustments
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Adjustments {
+        Adjustments {
+            brightness: 0.0,
+            contrast: 0.0,
+            exposure: 0.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build the 256-entry lookup table for the given adjustments
+pub fn build_lut(adj: &Adjustments) -> [u8; 256] {
+    let exposure = 2f32.powf(adj.exposure);
+    let contrast = adj.contrast.clamp(-1.0, 0.999);
+    let contrast_factor = (1.0 + contrast) / (1.0 - contrast);
+    let inv_gamma = if adj.gamma > 0.0 { 1.0 / adj.gamma } else { 1.0 };
+
+    let mut lut = [0u8; 256];
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let mut v = i as f32 / 255.0;
+        v *= exposure;
+        v += adj.brightness;
+        v = (v - 0.5) * contrast_factor + 0.5;
+        v = v.clamp(0.0, 1.0).powf(inv_gamma);
+        *entry = (v * 255.0).round() as u8;
+    }
+    lut
+}
+
+/// Apply a per-channel lookup table to the RGB channels, preserving alpha
+pub fn apply_lut(data: &[u8], width: u32, height: u32, lut: &[u8; 256]) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+
+    let mut output = data.to_vec();
+    for pixel in output.chunks_exact_mut(4) {
+        pixel[0] = lut[pixel[0] as usize];
+        pixel[1] = lut[pixel[1] as usize];
+        pixel[2] = lut[pixel[2] as usize];
+    }
+    Ok(output)
+}
+
+/// Apply brightness/contrast/exposure/gamma in a single pass
+pub fn adjust(data: &[u8], width: u32, height: u32, adj: &Adjustments) -> Result<Vec<u8>, String> {
+    apply_lut(data, width, height, &build_lut(adj))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_lut() {
+        let lut = build_lut(&Adjustments::new());
+        for (i, &v) in lut.iter().enumerate() {
+            assert_eq!(v as usize, i);
+        }
+    }
+
+    #[test]
+    fn test_exposure_and_alpha() {
+        let adj = Adjustments {
+            exposure: 1.0,
+            ..Adjustments::new()
+        };
+        let out = adjust(&[50, 100, 200, 77], 1, 1, &adj).unwrap();
+        assert_eq!(out, vec![100, 200, 255, 77]);
+    }
+}
@@ -0,0 +1,81 @@
+//! CMYK <-> RGB conversion for print workflows
+//!
+//! Uses the naive (device-independent) formulas. CMYK buffers hold 4 bytes per
+//! pixel where 0 is no ink; Adobe-style JPEG/TIFF data stores the inverse, which
+//! callers select with `inverted`.
+
+/// Normalized RGB to CMYK (all components in [0, 1])
+#[inline]
+pub fn rgb_to_cmyk(rgb: [f32; 3]) -> [f32; 4] {
+    let k = 1.0 - rgb[0].max(rgb[1]).max(rgb[2]);
+    if k >= 1.0 {
+        return [0.0, 0.0, 0.0, 1.0];
+    }
+    let d = 1.0 - k;
+    [
+        (1.0 - rgb[0] - k) / d,
+        (1.0 - rgb[1] - k) / d,
+        (1.0 - rgb[2] - k) / d,
+        k,
+    ]
+}
+
+/// CMYK to normalized RGB (all components in [0, 1])
+#[inline]
+pub fn cmyk_to_rgb(cmyk: [f32; 4]) -> [f32; 3] {
+    let k = 1.0 - cmyk[3];
+    [(1.0 - cmyk[0]) * k, (1.0 - cmyk[1]) * k, (1.0 - cmyk[2]) * k]
+}
+
+/// Convert a CMYK buffer to opaque RGBA
+pub fn cmyk_to_rgba(data: &[u8], width: u32, height: u32, inverted: bool) -> Result<Vec<u8>, String> {
+    // Same layout as RGBA: 4 bytes per pixel
+    crate::utils::check_rgba(data, width, height)?;
+
+    let mut output = Vec::with_capacity(data.len());
+    for p in data.chunks_exact(4) {
+        let ink = |v: u8| if inverted { 255 - v } else { v } as u32;
+        let (c, m, y, k) = (ink(p[0]), ink(p[1]), ink(p[2]), ink(p[3]));
+        // Integer form of cmyk_to_rgb: (255 - c) * (255 - k) / 255
+        let channel = |v: u32| (((255 - v) * (255 - k) + 127) / 255) as u8;
+        output.extend_from_slice(&[channel(c), channel(m), channel(y), 255]);
+    }
+    Ok(output)
+}
+
+/// Convert RGBA to a CMYK buffer (alpha is discarded)
+pub fn rgba_to_cmyk(data: &[u8], width: u32, height: u32, inverted: bool) -> Result<Vec<u8>, String> {
+    crate::utils::check_rgba(data, width, height)?;
+
+    let mut output = Vec::with_capacity(data.len());
+    for p in data.chunks_exact(4) {
+        let cmyk = rgb_to_cmyk([p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0]);
+        for v in cmyk {
+            let byte = (v * 255.0).round().clamp(0.0, 255.0) as u8;
+            output.push(if inverted { 255 - byte } else { byte });
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primaries() {
+        assert_eq!(rgb_to_cmyk([1.0, 0.0, 0.0]), [0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(rgb_to_cmyk([0.0, 0.0, 0.0]), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(cmyk_to_rgb([0.0, 1.0, 1.0, 0.0]), [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_buffer_roundtrip_inverted() {
+        let rgba = [200, 100, 50, 255, 0, 0, 0, 255];
+        let cmyk = rgba_to_cmyk(&rgba, 2, 1, true).unwrap();
+        let back = cmyk_to_rgba(&cmyk, 2, 1, true).unwrap();
+        for (a, b) in rgba.iter().zip(back.iter()) {
+            assert!((*a as i32 - *b as i32).abs() <= 1);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! HSL-based hue, saturation, and vibrance adjustments
+
+use super::space::{hsl_to_rgb, rgb_to_hsl};
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Hue/saturation/vibrance parameters
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HslAdjustments {
+    /// Hue rotation in degrees
+    pub hue: f32,
+    /// Saturation change in [-1, 1]; -1 fully desaturates
+    pub saturation: f32,
+    /// Vibrance in [-1, 1]; like saturation but weighted toward muted colors
+    pub vibrance: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HslAdjustments {
+    /// Identity adjustments
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> HslAdjustments {
+        HslAdjustments {
+            hue: 0.0,
+            saturation: 0.0,
+            vibrance: 0.0,
+        }
+    }
+}
+
+impl Default for HslAdjustments {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Apply hue rotation, saturation, and vibrance to an RGBA image
+pub fn adjust_hsl(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    adj: &HslAdjustments,
+) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+
+    let saturation = 1.0 + adj.saturation.clamp(-1.0, 1.0);
+    let vibrance = adj.vibrance.clamp(-1.0, 1.0);
+
+    let mut output = data.to_vec();
+    for pixel in output.chunks_exact_mut(4) {
+        let [h, s, l] = rgb_to_hsl([
+            pixel[0] as f32 / 255.0,
+            pixel[1] as f32 / 255.0,
+            pixel[2] as f32 / 255.0,
+        ]);
+
+        let mut s = s * saturation;
+        s *= 1.0 + vibrance * (1.0 - s.min(1.0));
+        let [r, g, b] = hsl_to_rgb([h + adj.hue, s.clamp(0.0, 1.0), l]);
+
+        pixel[0] = (r * 255.0).round().clamp(0.0, 255.0) as u8;
+        pixel[1] = (g * 255.0).round().clamp(0.0, 255.0) as u8;
+        pixel[2] = (b * 255.0).round().clamp(0.0, 255.0) as u8;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hue_rotation() {
+        let adj = HslAdjustments {
+            hue: 120.0,
+            ..HslAdjustments::new()
+        };
+        let out = adjust_hsl(&[255, 0, 0, 255], 1, 1, &adj).unwrap();
+        assert_eq!(out, vec![0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_desaturate() {
+        let adj = HslAdjustments {
+            saturation: -1.0,
+            ..HslAdjustments::new()
+        };
+        let out = adjust_hsl(&[200, 50, 50, 10], 1, 1, &adj).unwrap();
+        assert_eq!(out[0], out[1]);
+        assert_eq!(out[1], out[2]);
+        assert_eq!(out[3], 10);
+    }
+}
@@ -0,0 +1,76 @@
+//! Color adjustments and conversions
+
+pub mod adjust;
+pub mod cmyk;
+pub mod hsl;
+pub mod space;
+pub mod tonemap;
+
+pub use adjust::{adjust, Adjustments};
+pub use cmyk::{cmyk_to_rgba, rgba_to_cmyk};
+pub use hsl::{adjust_hsl, HslAdjustments};
+pub use space::{ColorSpace, YCbCrMatrix};
+pub use tonemap::{tone_map, ToneMapOperator, ToneMapOptions};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Apply brightness/contrast/exposure/gamma to an RGBA image
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = adjust)]
+pub fn adjust_js(data: &[u8], width: u32, height: u32, adjustments: &Adjustments) -> Result<Vec<u8>, JsError> {
+    adjust(data, width, height, adjustments).map_err(|e| JsError::new(&e))
+}
+
+/// Apply hue rotation, saturation, and vibrance to an RGBA image
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = adjustHsl)]
+pub fn adjust_hsl_js(data: &[u8], width: u32, height: u32, adjustments: &HslAdjustments) -> Result<Vec<u8>, JsError> {
+    adjust_hsl(data, width, height, adjustments).map_err(|e| JsError::new(&e))
+}
+
+/// Convert RGBA to three f32 components per pixel in the given color space
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = toColorSpace)]
+pub fn to_color_space_js(data: &[u8], width: u32, height: u32, space: ColorSpace) -> Result<Vec<f32>, JsError> {
+    space::rgba_to_space(data, width, height, space).map_err(|e| JsError::new(&e))
+}
+
+/// Convert three f32 components per pixel in the given color space back to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = fromColorSpace)]
+pub fn from_color_space_js(values: &[f32], width: u32, height: u32, space: ColorSpace) -> Result<Vec<u8>, JsError> {
+    space::space_to_rgba(values, width, height, space).map_err(|e| JsError::new(&e))
+}
+
+/// CIEDE2000 difference between two sRGB colors given as [r, g, b] bytes
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = deltaE2000)]
+pub fn delta_e2000_js(r1: u8, g1: u8, b1: u8, r2: u8, g2: u8, b2: u8) -> f32 {
+    let a = space::rgb8_to_space([r1, g1, b1], ColorSpace::Lab);
+    let b = space::rgb8_to_space([r2, g2, b2], ColorSpace::Lab);
+    space::delta_e2000(a, b)
+}
+
+/// Convert a CMYK buffer (4 bytes per pixel) to RGBA
+///
+/// Set `inverted` for Adobe-style CMYK as stored by most JPEG/TIFF writers.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = cmykToRgba)]
+pub fn cmyk_to_rgba_js(data: &[u8], width: u32, height: u32, inverted: bool) -> Result<Vec<u8>, JsError> {
+    cmyk_to_rgba(data, width, height, inverted).map_err(|e| JsError::new(&e))
+}
+
+/// Convert RGBA to a CMYK buffer (4 bytes per pixel)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = rgbaToCmyk)]
+pub fn rgba_to_cmyk_js(data: &[u8], width: u32, height: u32, inverted: bool) -> Result<Vec<u8>, JsError> {
+    rgba_to_cmyk(data, width, height, inverted).map_err(|e| JsError::new(&e))
+}
+
+/// Tone map a linear HDR buffer (RGB or RGBA f32) to sRGB RGBA8
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = toneMap)]
+pub fn tone_map_js(data: &[f32], width: u32, height: u32, options: &ToneMapOptions) -> Result<Vec<u8>, JsError> {
+    tone_map(data, width, height, options).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,418 @@
+//! Color space conversions - sRGB, YCbCr, HSV/HSL, CIE XYZ, and CIE Lab
+//!
+//! Unless noted otherwise, RGB components are gamma-encoded sRGB in [0, 1].
+
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Target color space for buffer conversions
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Linear-light RGB in [0, 1]
+    LinearRgb = 0,
+    /// Hue in degrees, saturation and value in [0, 1]
+    Hsv = 1,
+    /// Hue in degrees, saturation and lightness in [0, 1]
+    Hsl = 2,
+    /// Full-range BT.601 YCbCr in [0, 255] (JPEG/JFIF)
+    YCbCr601 = 3,
+    /// Full-range BT.709 YCbCr in [0, 255]
+    YCbCr709 = 4,
+    /// CIE XYZ, D65 white point, Y in [0, 1]
+    Xyz = 5,
+    /// CIE L*a*b*, D65 white point, L in [0, 100]
+    Lab = 6,
+}
+
+/// Luma coefficients for YCbCr conversion
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YCbCrMatrix {
+    Bt601 = 0,
+    Bt709 = 1,
+}
+
+impl YCbCrMatrix {
+    /// (Kr, Kb) luma weights
+    fn coefficients(self) -> (f32, f32) {
+        match self {
+            YCbCrMatrix::Bt601 => (0.299, 0.114),
+            YCbCrMatrix::Bt709 => (0.2126, 0.0722),
+        }
+    }
+}
+
+/// D65 reference white in XYZ
+pub const D65_WHITE: [f32; 3] = [0.95047, 1.0, 1.08883];
+
+/// Decode one sRGB component to linear light
+#[inline]
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode one linear-light component to sRGB
+#[inline]
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Build a 256-entry sRGB byte to linear-light lookup table
+pub fn srgb_to_linear_lut() -> [f32; 256] {
+    std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0))
+}
+
+/// RGB in [0, 255] to full-range YCbCr in [0, 255] (chroma centered at 128)
+#[inline]
+pub fn rgb_to_ycbcr(rgb: [f32; 3], matrix: YCbCrMatrix) -> [f32; 3] {
+    let (kr, kb) = matrix.coefficients();
+    let kg = 1.0 - kr - kb;
+    let y = kr * rgb[0] + kg * rgb[1] + kb * rgb[2];
+    let cb = 128.0 + (rgb[2] - y) / (2.0 * (1.0 - kb));
+    let cr = 128.0 + (rgb[0] - y) / (2.0 * (1.0 - kr));
+    [y, cb, cr]
+}
+
+/// Full-range YCbCr in [0, 255] to RGB in [0, 255] (unclamped)
+#[inline]
+pub fn ycbcr_to_rgb(ycc: [f32; 3], matrix: YCbCrMatrix) -> [f32; 3] {
+    let (kr, kb) = matrix.coefficients();
+    let kg = 1.0 - kr - kb;
+    let r = ycc[0] + 2.0 * (1.0 - kr) * (ycc[2] - 128.0);
+    let b = ycc[0] + 2.0 * (1.0 - kb) * (ycc[1] - 128.0);
+    let g = (ycc[0] - kr * r - kb * b) / kg;
+    [r, g, b]
+}
+
+/// RGB to HSV (hue in degrees [0, 360))
+pub fn rgb_to_hsv(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let d = max - min;
+    let s = if max > 0.0 { d / max } else { 0.0 };
+    [hue(r, g, b, max, d), s, max]
+}
+
+/// HSV to RGB
+pub fn hsv_to_rgb(hsv: [f32; 3]) -> [f32; 3] {
+    let [h, s, v] = hsv;
+    let c = v * s;
+    let (r, g, b) = hue_to_rgb(h, c);
+    let m = v - c;
+    [r + m, g + m, b + m]
+}
+
+/// RGB to HSL (hue in degrees [0, 360))
+pub fn rgb_to_hsl(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+
+    if d == 0.0 {
+        return [0.0, 0.0, l];
+    }
+
+    let s = if l > 0.5 { d / (2.0 - max - min) } else { d / (max + min) };
+    [hue(r, g, b, max, d), s, l]
+}
+
+/// HSL to RGB
+pub fn hsl_to_rgb(hsl: [f32; 3]) -> [f32; 3] {
+    let [h, s, l] = hsl;
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let (r, g, b) = hue_to_rgb(h, c);
+    let m = l - c / 2.0;
+    [r + m, g + m, b + m]
+}
+
+/// Hue angle shared by HSV and HSL
+#[inline]
+fn hue(r: f32, g: f32, b: f32, max: f32, d: f32) -> f32 {
+    if d == 0.0 {
+        return 0.0;
+    }
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    h * 60.0
+}
+
+/// RGB offsets (before adding the minimum) for a hue and chroma
+#[inline]
+fn hue_to_rgb(h: f32, c: f32) -> (f32, f32, f32) {
+    let hp = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (hp % 2.0 - 1.0).abs());
+    match hp as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    }
+}
+
+/// Linear-light RGB to CIE XYZ (D65)
+#[inline]
+pub fn linear_rgb_to_xyz(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    [
+        0.4124564 * r + 0.3575761 * g + 0.1804375 * b,
+        0.2126729 * r + 0.7151522 * g + 0.0721750 * b,
+        0.0193339 * r + 0.119192 * g + 0.9503041 * b,
+    ]
+}
+
+/// CIE XYZ (D65) to linear-light RGB
+#[inline]
+pub fn xyz_to_linear_rgb(xyz: [f32; 3]) -> [f32; 3] {
+    let [x, y, z] = xyz;
+    [
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.969266 * x + 1.8760108 * y + 0.0415560 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    ]
+}
+
+/// sRGB to CIE XYZ (D65)
+pub fn rgb_to_xyz(rgb: [f32; 3]) -> [f32; 3] {
+    linear_rgb_to_xyz(rgb.map(srgb_to_linear))
+}
+
+/// CIE XYZ (D65) to sRGB (unclamped)
+pub fn xyz_to_rgb(xyz: [f32; 3]) -> [f32; 3] {
+    xyz_to_linear_rgb(xyz).map(linear_to_srgb)
+}
+
+const LAB_EPSILON: f32 = 216.0 / 24389.0;
+const LAB_KAPPA: f32 = 24389.0 / 27.0;
+
+/// CIE XYZ to L*a*b* relative to D65
+pub fn xyz_to_lab(xyz: [f32; 3]) -> [f32; 3] {
+    let f = |t: f32| {
+        if t > LAB_EPSILON {
+            t.cbrt()
+        } else {
+            (LAB_KAPPA * t + 16.0) / 116.0
+        }
+    };
+    let fx = f(xyz[0] / D65_WHITE[0]);
+    let fy = f(xyz[1] / D65_WHITE[1]);
+    let fz = f(xyz[2] / D65_WHITE[2]);
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// CIE L*a*b* (D65) to XYZ
+pub fn lab_to_xyz(lab: [f32; 3]) -> [f32; 3] {
+    lab_to_xyz_with_white(lab, D65_WHITE)
+}
+
+/// CIE L*a*b* to XYZ relative to an arbitrary reference white
+pub fn lab_to_xyz_with_white(lab: [f32; 3], white: [f32; 3]) -> [f32; 3] {
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+    let inv = |f: f32| {
+        let f3 = f * f * f;
+        if f3 > LAB_EPSILON {
+            f3
+        } else {
+            (116.0 * f - 16.0) / LAB_KAPPA
+        }
+    };
+    let y = if lab[0] > LAB_KAPPA * LAB_EPSILON { fy * fy * fy } else { lab[0] / LAB_KAPPA };
+    [inv(fx) * white[0], y * white[1], inv(fz) * white[2]]
+}
+
+/// sRGB to CIE L*a*b*
+pub fn rgb_to_lab(rgb: [f32; 3]) -> [f32; 3] {
+    xyz_to_lab(rgb_to_xyz(rgb))
+}
+
+/// CIE L*a*b* to sRGB (unclamped)
+pub fn lab_to_rgb(lab: [f32; 3]) -> [f32; 3] {
+    xyz_to_rgb(lab_to_xyz(lab))
+}
+
+/// CIE76 color difference (Euclidean distance in Lab)
+pub fn delta_e76(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// CIEDE2000 color difference
+pub fn delta_e2000(lab1: [f32; 3], lab2: [f32; 3]) -> f32 {
+    let [l1, a1, b1] = lab1.map(|v| v as f64);
+    let [l2, a2, b2] = lab2.map(|v| v as f64);
+
+    let c1 = (a1 * a1 + b1 * b1).sqrt();
+    let c2 = (a2 * a2 + b2 * b2).sqrt();
+    let c_bar7 = ((c1 + c2) / 2.0).powi(7);
+    let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + 25f64.powi(7))).sqrt());
+
+    let a1p = a1 * (1.0 + g);
+    let a2p = a2 * (1.0 + g);
+    let c1p = (a1p * a1p + b1 * b1).sqrt();
+    let c2p = (a2p * a2p + b2 * b2).sqrt();
+    let h = |b: f64, a: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let h1p = h(b1, a1p);
+    let h2p = h(b2, a2p);
+
+    let dl = l2 - l1;
+    let dc = c2p - c1p;
+    let dh_angle = if c1p * c2p == 0.0 {
+        0.0
+    } else if (h2p - h1p).abs() <= 180.0 {
+        h2p - h1p
+    } else if h2p - h1p > 180.0 {
+        h2p - h1p - 360.0
+    } else {
+        h2p - h1p + 360.0
+    };
+    let dh = 2.0 * (c1p * c2p).sqrt() * (dh_angle / 2.0).to_radians().sin();
+
+    let l_bar = (l1 + l2) / 2.0;
+    let c_bar = (c1p + c2p) / 2.0;
+    let h_bar = if c1p * c2p == 0.0 {
+        h1p + h2p
+    } else if (h1p - h2p).abs() <= 180.0 {
+        (h1p + h2p) / 2.0
+    } else if h1p + h2p < 360.0 {
+        (h1p + h2p + 360.0) / 2.0
+    } else {
+        (h1p + h2p - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (h_bar - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_bar).to_radians().cos()
+        + 0.32 * (3.0 * h_bar + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_bar - 63.0).to_radians().cos();
+    let d_theta = 30.0 * (-((h_bar - 275.0) / 25.0).powi(2)).exp();
+    let c_bar7 = c_bar.powi(7);
+    let rc = 2.0 * (c_bar7 / (c_bar7 + 25f64.powi(7))).sqrt();
+    let sl = 1.0 + 0.015 * (l_bar - 50.0).powi(2) / (20.0 + (l_bar - 50.0).powi(2)).sqrt();
+    let sc = 1.0 + 0.045 * c_bar;
+    let sh = 1.0 + 0.015 * c_bar * t;
+    let rt = -(2.0 * d_theta).to_radians().sin() * rc;
+
+    let terms = [dl / sl, dc / sc, dh / sh];
+    (terms[0].powi(2) + terms[1].powi(2) + terms[2].powi(2) + rt * terms[1] * terms[2]).sqrt() as f32
+}
+
+/// Convert one 8-bit RGB pixel into the given space
+pub fn rgb8_to_space(p: [u8; 3], space: ColorSpace) -> [f32; 3] {
+    let rgb = p.map(|v| v as f32 / 255.0);
+    match space {
+        ColorSpace::LinearRgb => rgb.map(srgb_to_linear),
+        ColorSpace::Hsv => rgb_to_hsv(rgb),
+        ColorSpace::Hsl => rgb_to_hsl(rgb),
+        ColorSpace::YCbCr601 => rgb_to_ycbcr(p.map(|v| v as f32), YCbCrMatrix::Bt601),
+        ColorSpace::YCbCr709 => rgb_to_ycbcr(p.map(|v| v as f32), YCbCrMatrix::Bt709),
+        ColorSpace::Xyz => rgb_to_xyz(rgb),
+        ColorSpace::Lab => rgb_to_lab(rgb),
+    }
+}
+
+/// Convert one pixel from the given space back to clamped 8-bit RGB
+pub fn space_to_rgb8(v: [f32; 3], space: ColorSpace) -> [u8; 3] {
+    let rgb = match space {
+        ColorSpace::LinearRgb => v.map(linear_to_srgb),
+        ColorSpace::Hsv => hsv_to_rgb(v),
+        ColorSpace::Hsl => hsl_to_rgb(v),
+        ColorSpace::YCbCr601 => ycbcr_to_rgb(v, YCbCrMatrix::Bt601).map(|c| c / 255.0),
+        ColorSpace::YCbCr709 => ycbcr_to_rgb(v, YCbCrMatrix::Bt709).map(|c| c / 255.0),
+        ColorSpace::Xyz => xyz_to_rgb(v),
+        ColorSpace::Lab => lab_to_rgb(v),
+    };
+    rgb.map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+/// Convert an RGBA image to three f32 components per pixel in `space`
+pub fn rgba_to_space(data: &[u8], width: u32, height: u32, space: ColorSpace) -> Result<Vec<f32>, String> {
+    check_rgba(data, width, height)?;
+    Ok(data
+        .chunks_exact(4)
+        .flat_map(|p| rgb8_to_space([p[0], p[1], p[2]], space))
+        .collect())
+}
+
+/// Convert three f32 components per pixel in `space` back to opaque RGBA
+pub fn space_to_rgba(values: &[f32], width: u32, height: u32, space: ColorSpace) -> Result<Vec<u8>, String> {
+    let expected_len = width as usize * height as usize * 3;
+    if values.len() != expected_len {
+        return Err(format!(
+            "Data length mismatch: expected {}, got {}",
+            expected_len,
+            values.len()
+        ));
+    }
+
+    let mut output = Vec::with_capacity(width as usize * height as usize * 4);
+    for v in values.chunks_exact(3) {
+        let [r, g, b] = space_to_rgb8([v[0], v[1], v[2]], space);
+        output.extend_from_slice(&[r, g, b, 255]);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_all_spaces() {
+        let spaces = [
+            ColorSpace::LinearRgb,
+            ColorSpace::Hsv,
+            ColorSpace::Hsl,
+            ColorSpace::YCbCr601,
+            ColorSpace::YCbCr709,
+            ColorSpace::Xyz,
+            ColorSpace::Lab,
+        ];
+        for &space in &spaces {
+            for &p in &[[255u8, 0, 0], [12, 200, 99], [128, 128, 128], [0, 0, 0], [255, 255, 255]] {
+                assert_eq!(space_to_rgb8(rgb8_to_space(p, space), space), p, "{:?}", space);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reference_values() {
+        let lab = rgb_to_lab([1.0, 1.0, 1.0]);
+        assert!((lab[0] - 100.0).abs() < 0.01 && lab[1].abs() < 0.01 && lab[2].abs() < 0.01);
+
+        let ycc = rgb_to_ycbcr([255.0, 0.0, 0.0], YCbCrMatrix::Bt601);
+        assert!((ycc[0] - 76.245).abs() < 0.01);
+        assert!((ycc[2] - 255.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_delta_e2000_reference_pair() {
+        // Sharma et al. test data, pair 1
+        let d = delta_e2000([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485]);
+        assert!((d - 2.0425).abs() < 1e-3, "{}", d);
+    }
+}
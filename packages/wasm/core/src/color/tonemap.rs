@@ -0,0 +1,133 @@
+//! HDR tone mapping - Reinhard, ACES, and Hable (Uncharted 2) operators
+
+use super::space::linear_to_srgb;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Tone mapping curve
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    Reinhard = 0,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces = 1,
+    /// John Hable's Uncharted 2 filmic curve
+    Hable = 2,
+}
+
+/// Tone mapping parameters
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapOptions {
+    pub operator: ToneMapOperator,
+    /// Exposure in stops applied before the curve
+    pub exposure: f32,
+    /// Reinhard white point (smallest value mapped to white); 0 disables it
+    pub white: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ToneMapOptions {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(operator: ToneMapOperator) -> ToneMapOptions {
+        ToneMapOptions {
+            operator,
+            exposure: 0.0,
+            white: 0.0,
+        }
+    }
+}
+
+fn hable_partial(x: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+/// Map a linear scene value to linear display range [0, 1]
+pub fn tone_map_value(x: f32, operator: ToneMapOperator, white: f32) -> f32 {
+    let x = x.max(0.0);
+    let y = match operator {
+        ToneMapOperator::Reinhard => {
+            if white > 0.0 {
+                x * (1.0 + x / (white * white)) / (1.0 + x)
+            } else {
+                x / (1.0 + x)
+            }
+        }
+        ToneMapOperator::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        ToneMapOperator::Hable => {
+            const WHITE: f32 = 11.2;
+            hable_partial(x * 2.0) / hable_partial(WHITE)
+        }
+    };
+    y.clamp(0.0, 1.0)
+}
+
+/// Tone map a linear HDR buffer (RGB or RGBA f32) to sRGB RGBA8
+///
+/// The channel count is inferred from the buffer length; alpha is clamped
+/// to [0, 1] and passed through.
+pub fn tone_map(data: &[f32], width: u32, height: u32, options: &ToneMapOptions) -> Result<Vec<u8>, String> {
+    let pixels = width as usize * height as usize;
+    let channels = match data.len() {
+        n if n == pixels * 3 => 3,
+        n if n == pixels * 4 => 4,
+        n => {
+            return Err(format!(
+                "Data length mismatch: expected {} (RGB) or {} (RGBA), got {}",
+                pixels * 3,
+                pixels * 4,
+                n
+            ))
+        }
+    };
+
+    let scale = 2f32.powf(options.exposure);
+    let encode = |v: f32| {
+        let mapped = tone_map_value(v * scale, options.operator, options.white);
+        (linear_to_srgb(mapped) * 255.0).round() as u8
+    };
+
+    let mut output = Vec::with_capacity(pixels * 4);
+    for p in data.chunks_exact(channels) {
+        let alpha = if channels == 4 { (p[3].clamp(0.0, 1.0) * 255.0).round() as u8 } else { 255 };
+        output.extend_from_slice(&[encode(p[0]), encode(p[1]), encode(p[2]), alpha]);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operators_are_monotonic_and_bounded() {
+        for op in [ToneMapOperator::Reinhard, ToneMapOperator::Aces, ToneMapOperator::Hable] {
+            let mut prev = 0.0;
+            for i in 0..200 {
+                let y = tone_map_value(i as f32 * 0.25, op, 0.0);
+                assert!(y >= prev && y <= 1.0, "{:?} at {}", op, i);
+                prev = y;
+            }
+            assert!(tone_map_value(0.0, op, 0.0) < 0.01);
+        }
+        // Extended Reinhard maps the white point to 1
+        assert!((tone_map_value(4.0, ToneMapOperator::Reinhard, 4.0) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_exposure_brightens() {
+        let data = [0.18f32, 0.18, 0.18];
+        let mut options = ToneMapOptions::new(ToneMapOperator::Aces);
+        let base = tone_map(&data, 1, 1, &options).unwrap();
+        options.exposure = 2.0;
+        let bright = tone_map(&data, 1, 1, &options).unwrap();
+        assert!(bright[0] > base[0]);
+        assert_eq!(bright[3], 255);
+    }
+}
@@ -0,0 +1,101 @@
+//! Pixel-level difference visualization for visual regression testing
+
+use crate::utils::{check_rgba, luma};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// How differences are rendered
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffMode {
+    /// Black background, changed pixels colored blue (small) to red (large)
+    Heatmap = 0,
+    /// Faded grayscale of the first image with changed pixels in red
+    Overlay = 1,
+}
+
+/// Difference image and statistics
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+pub struct DiffResult {
+    /// RGBA visualization, same size as the inputs
+    pub image: Vec<u8>,
+    /// Pixels whose difference exceeds the threshold
+    pub changed: u32,
+    /// Largest per-pixel difference in [0, 1]
+    pub max_difference: f32,
+}
+
+/// Blue-to-red ramp through green and yellow
+fn heat(t: f32) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0) * 3.0;
+    let (r, g, b) = match t {
+        t if t < 1.0 => (0.0, t, 1.0 - t),
+        t if t < 2.0 => (t - 1.0, 1.0, 0.0),
+        t => (1.0, 3.0 - t, 0.0),
+    };
+    [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8]
+}
+
+/// Compare two RGBA images of equal size
+///
+/// A pixel counts as changed when its largest channel difference (alpha
+/// included) exceeds `threshold`, given as a fraction of full scale.
+pub fn diff_image(a: &[u8], b: &[u8], width: u32, height: u32, threshold: f32, mode: DiffMode) -> Result<DiffResult, String> {
+    check_rgba(a, width, height)?;
+    check_rgba(b, width, height)?;
+
+    let threshold = threshold.clamp(0.0, 1.0);
+    let mut image = Vec::with_capacity(a.len());
+    let mut changed = 0u32;
+    let mut max_difference = 0f32;
+
+    for (p, q) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+        let delta = p.iter().zip(q).map(|(x, y)| x.abs_diff(*y)).max().unwrap_or(0) as f32 / 255.0;
+        max_difference = max_difference.max(delta);
+        let is_changed = delta > threshold;
+        changed += is_changed as u32;
+
+        let pixel = match (mode, is_changed) {
+            (DiffMode::Heatmap, true) => {
+                let [r, g, b] = heat(delta);
+                [r, g, b, 255]
+            }
+            (DiffMode::Heatmap, false) => [0, 0, 0, 255],
+            (DiffMode::Overlay, true) => [255, 0, 0, 255],
+            (DiffMode::Overlay, false) => {
+                // Fade toward white so highlights stand out
+                let l = luma(p[0], p[1], p[2]) * p[3] as f32 / 255.0;
+                let v = (255.0 - (255.0 - l) * 0.1) as u8;
+                [v, v, v, 255]
+            }
+        };
+        image.extend_from_slice(&pixel);
+    }
+
+    Ok(DiffResult {
+        image,
+        changed,
+        max_difference,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_changes_above_threshold() {
+        let a = [10u8, 10, 10, 255, 100, 100, 100, 255, 0, 0, 0, 255];
+        let b = [12u8, 10, 10, 255, 200, 100, 100, 255, 0, 0, 0, 0];
+
+        let result = diff_image(&a, &b, 3, 1, 0.05, DiffMode::Overlay).unwrap();
+        assert_eq!(result.changed, 2);
+        assert_eq!(result.max_difference, 1.0);
+        assert_eq!(&result.image[4..8], &[255, 0, 0, 255]);
+        assert_ne!(&result.image[..4], &[255, 0, 0, 255]);
+
+        let heatmap = diff_image(&a, &a, 3, 1, 0.0, DiffMode::Heatmap).unwrap();
+        assert_eq!(heatmap.changed, 0);
+        assert!(heatmap.image.chunks_exact(4).all(|p| p == [0, 0, 0, 255]));
+    }
+}
@@ -0,0 +1,205 @@
+//! Full-reference quality metrics - PSNR, SSIM, and MS-SSIM
+
+use crate::utils::{check_rgba, luma};
+
+const SSIM_K1: f64 = 0.01;
+const SSIM_K2: f64 = 0.03;
+const SSIM_SIGMA: f64 = 1.5;
+const SSIM_RADIUS: usize = 5;
+
+/// Per-scale weights from Wang, Simoncelli & Bovik (2003)
+const MS_SSIM_WEIGHTS: [f64; 5] = [0.0448, 0.2856, 0.3001, 0.2363, 0.1333];
+
+fn check_pair(a: &[u8], b: &[u8], width: u32, height: u32) -> Result<(), String> {
+    check_rgba(a, width, height)?;
+    check_rgba(b, width, height)
+}
+
+/// Peak signal-to-noise ratio over the RGB channels, in dB
+///
+/// Identical images give infinity.
+pub fn psnr(a: &[u8], b: &[u8], width: u32, height: u32) -> Result<f64, String> {
+    check_pair(a, b, width, height)?;
+    if a.is_empty() {
+        return Err("Images are empty".to_string());
+    }
+
+    let sum: u64 = a
+        .chunks_exact(4)
+        .zip(b.chunks_exact(4))
+        .map(|(p, q)| (0..3).map(|c| (p[c] as i64 - q[c] as i64).pow(2) as u64).sum::<u64>())
+        .sum();
+    let mse = sum as f64 / (a.len() / 4 * 3) as f64;
+    if mse == 0.0 {
+        return Ok(f64::INFINITY);
+    }
+    Ok(10.0 * (255.0 * 255.0 / mse).log10())
+}
+
+fn luma_plane(data: &[u8]) -> Vec<f64> {
+    data.chunks_exact(4).map(|p| luma(p[0], p[1], p[2]) as f64).collect()
+}
+
+/// Separable Gaussian blur with edge clamping
+fn gaussian_blur(plane: &[f64], w: usize, h: usize, kernel: &[f64]) -> Vec<f64> {
+    let r = kernel.len() / 2;
+    let mut tmp = vec![0f64; plane.len()];
+    for y in 0..h {
+        let row = &plane[y * w..(y + 1) * w];
+        for x in 0..w {
+            tmp[y * w + x] = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, &wt)| wt * row[(x + k).saturating_sub(r).min(w - 1)])
+                .sum();
+        }
+    }
+    let mut out = vec![0f64; plane.len()];
+    for y in 0..h {
+        for x in 0..w {
+            out[y * w + x] = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, &wt)| wt * tmp[(y + k).saturating_sub(r).min(h - 1) * w + x])
+                .sum();
+        }
+    }
+    out
+}
+
+fn gaussian_kernel() -> Vec<f64> {
+    let k: Vec<f64> = (0..=2 * SSIM_RADIUS)
+        .map(|i| {
+            let d = i as f64 - SSIM_RADIUS as f64;
+            (-d * d / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp()
+        })
+        .collect();
+    let sum: f64 = k.iter().sum();
+    k.into_iter().map(|v| v / sum).collect()
+}
+
+/// Per-pixel luminance and contrast-structure terms of SSIM
+fn ssim_terms(x: &[f64], y: &[f64], w: usize, h: usize) -> Vec<(f64, f64)> {
+    let kernel = gaussian_kernel();
+    let c1 = (SSIM_K1 * 255.0).powi(2);
+    let c2 = (SSIM_K2 * 255.0).powi(2);
+
+    let product = |p: &[f64], q: &[f64]| p.iter().zip(q).map(|(a, b)| a * b).collect::<Vec<f64>>();
+    let mu_x = gaussian_blur(x, w, h, &kernel);
+    let mu_y = gaussian_blur(y, w, h, &kernel);
+    let xx = gaussian_blur(&product(x, x), w, h, &kernel);
+    let yy = gaussian_blur(&product(y, y), w, h, &kernel);
+    let xy = gaussian_blur(&product(x, y), w, h, &kernel);
+
+    (0..x.len())
+        .map(|i| {
+            let (mx, my) = (mu_x[i], mu_y[i]);
+            let var_x = (xx[i] - mx * mx).max(0.0);
+            let var_y = (yy[i] - my * my).max(0.0);
+            let cov = xy[i] - mx * my;
+            let l = (2.0 * mx * my + c1) / (mx * mx + my * my + c1);
+            let cs = (2.0 * cov + c2) / (var_x + var_y + c2);
+            (l, cs)
+        })
+        .collect()
+}
+
+/// Structural similarity of the luma channels (1.0 for identical images)
+///
+/// Uses an 11x11 Gaussian window with sigma 1.5.
+pub fn ssim(a: &[u8], b: &[u8], width: u32, height: u32) -> Result<f64, String> {
+    check_pair(a, b, width, height)?;
+    if a.is_empty() {
+        return Err("Images are empty".to_string());
+    }
+
+    let terms = ssim_terms(&luma_plane(a), &luma_plane(b), width as usize, height as usize);
+    Ok(terms.iter().map(|(l, cs)| l * cs).sum::<f64>() / terms.len() as f64)
+}
+
+/// 2x box downsample (odd trailing rows/columns are dropped)
+fn downsample(plane: &[f64], w: usize, h: usize) -> (Vec<f64>, usize, usize) {
+    let (nw, nh) = (w / 2, h / 2);
+    let mut out = Vec::with_capacity(nw * nh);
+    for y in 0..nh {
+        for x in 0..nw {
+            let i = 2 * y * w + 2 * x;
+            out.push((plane[i] + plane[i + 1] + plane[i + w] + plane[i + w + 1]) / 4.0);
+        }
+    }
+    (out, nw, nh)
+}
+
+/// Multi-scale SSIM over up to five dyadic scales
+///
+/// Images too small for all five scales use as many as fit (the coarsest
+/// at least 11 pixels across), with the weights renormalized.
+pub fn ms_ssim(a: &[u8], b: &[u8], width: u32, height: u32) -> Result<f64, String> {
+    check_pair(a, b, width, height)?;
+    let min_side = 2 * SSIM_RADIUS + 1;
+    if (width.min(height) as usize) < min_side {
+        return Err(format!("Images must be at least {}x{} for MS-SSIM", min_side, min_side));
+    }
+
+    let (mut x, mut y) = (luma_plane(a), luma_plane(b));
+    let (mut w, mut h) = (width as usize, height as usize);
+    let mut scales = 1;
+    while scales < MS_SSIM_WEIGHTS.len() && (w.min(h) >> scales) >= min_side {
+        scales += 1;
+    }
+    let weights = &MS_SSIM_WEIGHTS[..scales];
+    let weight_sum: f64 = weights.iter().sum();
+
+    let mut result = 1.0;
+    for (i, &weight) in weights.iter().enumerate() {
+        let terms = ssim_terms(&x, &y, w, h);
+        let n = terms.len() as f64;
+        let l = terms.iter().map(|t| t.0).sum::<f64>() / n;
+        let cs = terms.iter().map(|t| t.1).sum::<f64>() / n;
+        let weight = weight / weight_sum;
+        result *= cs.max(0.0).powf(weight);
+        if i == scales - 1 {
+            result *= l.max(0.0).powf(weight);
+        } else {
+            let (nx, nw, nh) = downsample(&x, w, h);
+            let (ny, _, _) = downsample(&y, w, h);
+            (x, y, w, h) = (nx, ny, nw, nh);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(w: u32, h: u32, noise: u8) -> Vec<u8> {
+        let mut data = Vec::new();
+        for y in 0..h {
+            for x in 0..w {
+                let v = ((x * 7 + y * 13) % 256) as u8;
+                let n = if (x + y) % 2 == 0 { noise } else { 0 };
+                data.extend_from_slice(&[v.saturating_add(n), v, v / 2, 255]);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_identical_images() {
+        let a = pattern(64, 64, 0);
+        assert_eq!(psnr(&a, &a, 64, 64).unwrap(), f64::INFINITY);
+        assert!((ssim(&a, &a, 64, 64).unwrap() - 1.0).abs() < 1e-9);
+        assert!((ms_ssim(&a, &a, 64, 64).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_more_noise_scores_lower() {
+        let a = pattern(64, 64, 0);
+        let light = pattern(64, 64, 10);
+        let heavy = pattern(64, 64, 60);
+        assert!(psnr(&a, &light, 64, 64).unwrap() > psnr(&a, &heavy, 64, 64).unwrap());
+        assert!(ssim(&a, &light, 64, 64).unwrap() > ssim(&a, &heavy, 64, 64).unwrap());
+        assert!(ms_ssim(&a, &light, 64, 64).unwrap() > ms_ssim(&a, &heavy, 64, 64).unwrap());
+    }
+}
@@ -0,0 +1,61 @@
+//! Image comparison - quality metrics, perceptual hashes, and diff images
+
+pub mod diff;
+pub mod metrics;
+pub mod phash;
+
+pub use diff::{diff_image, DiffMode, DiffResult};
+pub use metrics::{ms_ssim, psnr, ssim};
+pub use phash::{hamming_distance, perceptual_hash, HashAlgorithm};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Peak signal-to-noise ratio in dB (Infinity for identical images)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = psnr)]
+pub fn psnr_js(a: &[u8], b: &[u8], width: u32, height: u32) -> Result<f64, JsError> {
+    psnr(a, b, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Structural similarity index in [-1, 1]
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = ssim)]
+pub fn ssim_js(a: &[u8], b: &[u8], width: u32, height: u32) -> Result<f64, JsError> {
+    ssim(a, b, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Multi-scale structural similarity in [0, 1]
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = msSsim)]
+pub fn ms_ssim_js(a: &[u8], b: &[u8], width: u32, height: u32) -> Result<f64, JsError> {
+    ms_ssim(a, b, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// 64-bit perceptual hash (aHash, dHash, or pHash) for duplicate detection
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = perceptualHash)]
+pub fn perceptual_hash_js(data: &[u8], width: u32, height: u32, algorithm: HashAlgorithm) -> Result<u64, JsError> {
+    perceptual_hash(data, width, height, algorithm).map_err(|e| JsError::new(&e))
+}
+
+/// Number of differing bits between two perceptual hashes
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = hammingDistance)]
+pub fn hamming_distance_js(a: u64, b: u64) -> u32 {
+    hamming_distance(a, b)
+}
+
+/// Visualize per-pixel differences and count changed pixels
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = diffImage)]
+pub fn diff_image_js(
+    a: &[u8],
+    b: &[u8],
+    width: u32,
+    height: u32,
+    threshold: f32,
+    mode: DiffMode,
+) -> Result<DiffResult, JsError> {
+    diff_image(a, b, width, height, threshold, mode).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,138 @@
+//! Perceptual hashes - average, difference, and DCT-based 64-bit fingerprints
+
+use crate::utils::{check_rgba, luma};
+use std::f64::consts::PI;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Perceptual hash algorithm
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// Average hash: 8x8 luma against its mean
+    Average = 0,
+    /// Difference hash: horizontal gradients of a 9x8 thumbnail
+    Difference = 1,
+    /// DCT hash: low frequencies of a 32x32 thumbnail against their median
+    Dct = 2,
+}
+
+/// Area-averaged luma thumbnail
+fn luma_thumbnail(data: &[u8], width: u32, height: u32, tw: usize, th: usize) -> Vec<f64> {
+    let w = width as usize;
+    let h = height as usize;
+    let mut out = vec![0f64; tw * th];
+
+    for (ty, row) in out.chunks_exact_mut(tw).enumerate() {
+        let y0 = ty * h / th;
+        let y1 = ((ty + 1) * h / th).max(y0 + 1).min(h);
+        for (tx, cell) in row.iter_mut().enumerate() {
+            let x0 = tx * w / tw;
+            let x1 = ((tx + 1) * w / tw).max(x0 + 1).min(w);
+            let mut sum = 0.0;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let i = (y * w + x) * 4;
+                    sum += luma(data[i], data[i + 1], data[i + 2]) as f64;
+                }
+            }
+            *cell = sum / ((y1 - y0) * (x1 - x0)) as f64;
+        }
+    }
+    out
+}
+
+/// Pack 64 booleans, first one in the most significant bit
+fn pack(bits: impl Iterator<Item = bool>) -> u64 {
+    bits.take(64).fold(0u64, |h, b| (h << 1) | b as u64)
+}
+
+fn average_hash(data: &[u8], width: u32, height: u32) -> u64 {
+    let thumb = luma_thumbnail(data, width, height, 8, 8);
+    let mean = thumb.iter().sum::<f64>() / 64.0;
+    pack(thumb.iter().map(|&v| v > mean))
+}
+
+fn difference_hash(data: &[u8], width: u32, height: u32) -> u64 {
+    let thumb = luma_thumbnail(data, width, height, 9, 8);
+    pack(thumb.chunks_exact(9).flat_map(|row| row.windows(2).map(|p| p[0] > p[1])))
+}
+
+fn dct_hash(data: &[u8], width: u32, height: u32) -> u64 {
+    const N: usize = 32;
+    let thumb = luma_thumbnail(data, width, height, N, N);
+
+    // Only the 8x8 lowest frequencies are needed
+    let basis: Vec<f64> = (0..8)
+        .flat_map(|k| (0..N).map(move |n| (PI * (2 * n + 1) as f64 * k as f64 / (2 * N) as f64).cos()))
+        .collect();
+    let mut rows = vec![0f64; N * 8];
+    for y in 0..N {
+        for k in 0..8 {
+            rows[y * 8 + k] = (0..N).map(|x| thumb[y * N + x] * basis[k * N + x]).sum();
+        }
+    }
+    let mut coeffs = [0f64; 64];
+    for v in 0..8 {
+        for u in 0..8 {
+            coeffs[v * 8 + u] = (0..N).map(|y| rows[y * 8 + u] * basis[v * N + y]).sum();
+        }
+    }
+
+    // Median excludes the DC term, which only reflects overall brightness
+    let mut ac: Vec<f64> = coeffs[1..].to_vec();
+    ac.sort_by(|a, b| a.total_cmp(b));
+    let median = ac[ac.len() / 2];
+    pack(coeffs.iter().map(|&c| c > median))
+}
+
+/// Compute a 64-bit perceptual hash of an RGBA image
+pub fn perceptual_hash(data: &[u8], width: u32, height: u32, algorithm: HashAlgorithm) -> Result<u64, String> {
+    check_rgba(data, width, height)?;
+    if width == 0 || height == 0 {
+        return Err("Image is empty".to_string());
+    }
+    Ok(match algorithm {
+        HashAlgorithm::Average => average_hash(data, width, height),
+        HashAlgorithm::Difference => difference_hash(data, width, height),
+        HashAlgorithm::Dct => dct_hash(data, width, height),
+    })
+}
+
+/// Number of differing bits between two hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(w: u32, h: u32, flip: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        for y in 0..h {
+            for x in 0..w {
+                let (u, t) = (x as f64 / w as f64, y as f64 / h as f64);
+                let v = (128.0 + 100.0 * (7.0 * u).sin() * (5.0 * t + 1.0).cos()) as u8;
+                let v = if flip { 255 - v } else { v };
+                data.extend_from_slice(&[v, v, v, 255]);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_resized_copy_is_close_and_inverse_is_far() {
+        let big = pattern(128, 96, false);
+        let small = pattern(64, 48, false);
+        let inverted = pattern(128, 96, true);
+
+        for algorithm in [HashAlgorithm::Average, HashAlgorithm::Difference, HashAlgorithm::Dct] {
+            let a = perceptual_hash(&big, 128, 96, algorithm).unwrap();
+            let b = perceptual_hash(&small, 64, 48, algorithm).unwrap();
+            let c = perceptual_hash(&inverted, 128, 96, algorithm).unwrap();
+            assert!(hamming_distance(a, b) <= 6, "{:?}", algorithm);
+            assert!(hamming_distance(a, c) >= 24, "{:?}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Laying images out on grids: contact sheets, tiles and zoom pyramids
+
+mod montage;
+mod pyramid;
+mod tiles;
+
+pub use montage::montage;
+pub use pyramid::{generate_pyramid, PyramidLayout};
+pub use tiles::{split_tiles, stitch_tiles, TileGrid};
+
+#[cfg(feature = "wasm")]
+use crate::convert::ImageFormat;
+use crate::transform::RgbaImage;
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Collects images, then lays them out as a contact sheet
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Montage {
+    columns: u32,
+    cell_size: u32,
+    gap: u32,
+    background: [u8; 4],
+    images: Vec<RgbaImage>,
+    labels: Vec<String>,
+}
+
+impl Montage {
+    /// Add an image with a caption; leave every caption empty for an unlabelled sheet
+    pub fn add(&mut self, image: RgbaImage, label: &str) -> Result<(), String> {
+        check_rgba(&image.data, image.width, image.height)?;
+        self.images.push(image);
+        self.labels.push(label.to_string());
+        Ok(())
+    }
+
+    /// Render the sheet
+    pub fn render(&self) -> Result<RgbaImage, String> {
+        let labels: Vec<&str> = if self.labels.iter().all(String::is_empty) { Vec::new() } else { self.labels.iter().map(String::as_str).collect() };
+        montage(&self.images, self.columns, self.cell_size, self.gap, self.background, &labels)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Montage {
+    /// `columns` cells of `cellSize` pixels per row, `gap` pixels apart
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(columns: u32, cell_size: u32, gap: u32, r: u8, g: u8, b: u8, a: u8) -> Montage {
+        Montage { columns, cell_size, gap, background: [r, g, b, a], images: Vec::new(), labels: Vec::new() }
+    }
+
+    /// Add an image with a caption; leave every caption empty for an unlabelled sheet
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = add)]
+    pub fn add_js(&mut self, data: Vec<u8>, width: u32, height: u32, label: &str) -> Result<(), JsError> {
+        self.add(RgbaImage { width, height, data }, label).map_err(|e| JsError::new(&e))
+    }
+
+    /// Render the sheet
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = render)]
+    pub fn render_js(&self) -> Result<RgbaImage, JsError> {
+        self.render().map_err(|e| JsError::new(&e))
+    }
+}
+
+/// Cut an image into `tileWidth` x `tileHeight` tiles
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = splitTiles)]
+pub fn split_tiles_js(data: &[u8], width: u32, height: u32, tile_width: u32, tile_height: u32) -> Result<TileGrid, JsError> {
+    split_tiles(data, width, height, tile_width, tile_height).map_err(|e| JsError::new(&e))
+}
+
+/// Cut an image into a tile pyramid, calling `onTile(path, bytes)` for each
+/// encoded tile, and return the `.dzi` or `info.json` descriptor
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = generatePyramid)]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_pyramid_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    overlap: u32,
+    format: ImageFormat,
+    layout: PyramidLayout,
+    on_tile: &js_sys::Function,
+) -> Result<String, JsError> {
+    generate_pyramid(data, width, height, tile_size, overlap, format, layout, |path, bytes| {
+        on_tile
+            .call2(&JsValue::NULL, &JsValue::from_str(path), &js_sys::Uint8Array::from(bytes))
+            .map(|_| ())
+            .map_err(|e| e.dyn_into::<js_sys::Error>().map_or_else(|_| "Tile callback failed".to_string(), |e| e.message().into()))
+    })
+    .map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,134 @@
+//! Contact sheets: many images fitted onto one labelled grid
+
+use crate::animation::{Canvas, Rect};
+use crate::font::BitmapFont;
+use crate::limits::Budget;
+use crate::resize::{resize, ResizeAlgorithm};
+use crate::transform::RgbaImage;
+use crate::utils::{check_rgba, luma};
+
+/// Margin above and below a label line
+const LABEL_MARGIN: u32 = 2;
+
+/// Fit `image` inside a `cell` x `cell` square, keeping its aspect ratio
+fn fit(image: &RgbaImage, cell: u32) -> (u32, u32, Vec<u8>) {
+    let scale = (cell as f64 / image.width as f64).min(cell as f64 / image.height as f64);
+    let width = ((image.width as f64 * scale).round() as u32).clamp(1, cell);
+    let height = ((image.height as f64 * scale).round() as u32).clamp(1, cell);
+    if (width, height) == (image.width, image.height) {
+        return (width, height, image.data.clone());
+    }
+    (width, height, resize(&image.data, image.width, image.height, width, height, ResizeAlgorithm::Bilinear))
+}
+
+/// Longest prefix of `label` that fits in `width` pixels
+fn truncate<'a>(font: &BitmapFont, label: &'a str, width: u32) -> &'a str {
+    let end = label.char_indices().map(|(i, c)| i + c.len_utf8()).take_while(|&end| font.text_width(&label[..end]) <= width).last();
+    &label[..end.unwrap_or(0)]
+}
+
+/// Lay `images` out left to right, top to bottom, `columns` per row
+///
+/// Each image is scaled to fit a `cell_size` square, centered in it and
+/// blended over `background`; cells are `gap` pixels apart and the sheet has
+/// a `gap` border. Empty images leave their cell blank. When `labels` is not empty it must hold one label per
+/// image, drawn centered under its cell in black or white, whichever reads
+/// better on the background, and cut short if wider than the cell.
+pub fn montage(images: &[RgbaImage], columns: u32, cell_size: u32, gap: u32, background: [u8; 4], labels: &[&str]) -> Result<RgbaImage, String> {
+    if images.is_empty() {
+        return Err("Montage needs at least one image".to_string());
+    }
+    if columns == 0 || cell_size == 0 {
+        return Err("Montage needs at least one column and a non-empty cell".to_string());
+    }
+    if !labels.is_empty() && labels.len() != images.len() {
+        return Err(format!("Got {} labels for {} images", labels.len(), images.len()));
+    }
+    for image in images {
+        check_rgba(&image.data, image.width, image.height)?;
+    }
+
+    let font = BitmapFont::builtin();
+    let label_height = if labels.is_empty() { 0 } else { font.ascent() as u32 + 2 * LABEL_MARGIN };
+    let (cell_width, cell_height) = (cell_size, cell_size + label_height);
+    let columns = columns.min(images.len() as u32);
+    let rows = (images.len() as u32).div_ceil(columns);
+    let size = |cells: u32, cell: u32| cells.checked_mul(cell.checked_add(gap)?)?.checked_add(gap);
+    let (Some(width), Some(height)) = (size(columns, cell_width), size(rows, cell_height)) else {
+        return Err("Montage is too large".to_string());
+    };
+    Budget::new().check_size(width as usize, height as usize)?;
+
+    let mut canvas = Canvas::new(width, height);
+    for pixel in canvas.data.chunks_exact_mut(4) {
+        pixel.copy_from_slice(&background);
+    }
+    let ink = if luma(background[0], background[1], background[2]) < 128.0 { [255, 255, 255, 255] } else { [0, 0, 0, 255] };
+
+    for (i, image) in images.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        let (left, top) = (gap + column * (cell_width + gap), gap + row * (cell_height + gap));
+        if image.width > 0 && image.height > 0 {
+            let (w, h, pixels) = fit(image, cell_size);
+            let rect = Rect { x: left + (cell_size - w) / 2, y: top + (cell_size - h) / 2, width: w, height: h };
+            canvas.draw(&pixels, rect, true);
+        }
+
+        if let Some(label) = labels.get(i) {
+            let label = truncate(&font, label, cell_width);
+            let x = left + (cell_width - font.text_width(label)) / 2;
+            font.draw(&mut canvas.data, width, height, label, x as i32, (top + cell_size + LABEL_MARGIN) as i32, 1, ink);
+        }
+    }
+    Ok(RgbaImage { width, height, data: canvas.data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+        RgbaImage { width, height, data: color.repeat((width * height) as usize) }
+    }
+
+    fn pixel(image: &RgbaImage, x: u32, y: u32) -> &[u8] {
+        let at = ((y * image.width + x) * 4) as usize;
+        &image.data[at..at + 4]
+    }
+
+    #[test]
+    fn test_montage_layout() {
+        let images = [solid(8, 4, [255, 0, 0, 255]), solid(2, 2, [0, 255, 0, 255]), solid(3, 3, [0, 0, 255, 255])];
+        let sheet = montage(&images, 2, 4, 1, [255, 255, 255, 255], &[]).unwrap();
+        assert_eq!((sheet.width, sheet.height), (11, 11));
+        // The wide image is shrunk to 4x2 and centered vertically
+        assert_eq!(pixel(&sheet, 1, 2), &[255, 0, 0, 255]);
+        assert_eq!(pixel(&sheet, 1, 1), &[255, 255, 255, 255]);
+        // The small one is enlarged to fill its cell
+        assert_eq!(pixel(&sheet, 6, 1), &[0, 255, 0, 255]);
+        assert_eq!(pixel(&sheet, 4, 9), &[0, 0, 255, 255]);
+        assert_eq!(pixel(&sheet, 9, 9), &[255, 255, 255, 255]);
+
+        let labelled = montage(&images, 3, 12, 0, [0, 0, 0, 255], &["a", "bb", "a very long name"]).unwrap();
+        assert_eq!((labelled.width, labelled.height), (36, 12 + 7 + 2 * LABEL_MARGIN));
+        let label_row = |x0: u32, x1: u32| (x0..x1).any(|x| (12..labelled.height).any(|y| pixel(&labelled, x, y) == [255, 255, 255, 255]));
+        assert!(label_row(0, 12) && label_row(12, 24) && label_row(24, 36));
+        assert_eq!(truncate(&BitmapFont::builtin(), "a very long name", 12), "a ");
+
+        assert!(montage(&images, 2, 4, 1, [0; 4], &["a"]).is_err());
+        assert!(montage(&[], 2, 4, 1, [0; 4], &[]).is_err());
+
+        // An empty image leaves its cell blank
+        let empty = RgbaImage { width: 0, height: 0, data: Vec::new() };
+        let sheet = montage(&[empty, solid(2, 2, [0, 255, 0, 255])], 2, 4, 1, [255, 255, 255, 255], &[]).unwrap();
+        assert_eq!(pixel(&sheet, 2, 2), &[255, 255, 255, 255]);
+        assert_eq!(pixel(&sheet, 7, 2), &[0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_montage_limits() {
+        // Checked against the default limits before the sheet is allocated
+        let images = [solid(1, 1, [0; 4])];
+        assert!(montage(&images, 1, 20000, 0, [0; 4], &[]).unwrap_err().contains("over the limit"));
+    }
+}
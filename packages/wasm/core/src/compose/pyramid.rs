@@ -0,0 +1,173 @@
+//! Deep-zoom tile pyramids (Deep Zoom and IIIF level 0 layouts)
+
+use crate::convert::{encode, ImageFormat};
+use crate::transform::{crop, CropRegion, RgbaImage};
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// File layout and descriptor of a tile pyramid
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PyramidLayout {
+    /// `{level}/{column}_{row}.{ext}` tiles with a `.dzi` XML descriptor;
+    /// level 0 is 1x1 and the last level is full size
+    DeepZoom = 0,
+    /// `{region}/{size}/0/default.{ext}` tiles with an IIIF Image API 3
+    /// `info.json`; overlap does not apply
+    Iiif = 1,
+}
+
+/// Halve an image with a 2x2 box filter, rounding odd sizes up
+fn halve(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = (image.width.div_ceil(2), image.height.div_ceil(2));
+    let (sw, sh) = (image.width as usize, image.height as usize);
+    let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height as usize {
+        let rows = [2 * y, (2 * y + 1).min(sh - 1)];
+        for x in 0..width as usize {
+            let columns = [2 * x, (2 * x + 1).min(sw - 1)];
+            for c in 0..4 {
+                let sum: u32 = rows.iter().flat_map(|&r| columns.iter().map(move |&col| image.data[(r * sw + col) * 4 + c] as u32)).sum();
+                data.push(((sum + 2) / 4) as u8);
+            }
+        }
+    }
+    RgbaImage { width, height, data }
+}
+
+fn tile(image: &RgbaImage, region: CropRegion, format: ImageFormat) -> Result<Vec<u8>, String> {
+    let data = crop(&image.data, image.width, image.height, region)?;
+    encode(&RgbaImage { width: region.width, height: region.height, data }, format)
+}
+
+/// Cut an image into a tile pyramid, handing each encoded tile to `emit`
+/// with its path as soon as it is ready, and return the descriptor
+///
+/// Levels are produced from full size down, each halving the one before,
+/// so only one level is held in memory at a time. The IIIF descriptor's
+/// `id` is left empty for the caller to fill in with the tiles' base URL.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_pyramid(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    overlap: u32,
+    format: ImageFormat,
+    layout: PyramidLayout,
+    mut emit: impl FnMut(&str, &[u8]) -> Result<(), String>,
+) -> Result<String, String> {
+    check_rgba(data, width, height)?;
+    if width == 0 || height == 0 {
+        return Err("Cannot tile an empty image".to_string());
+    }
+    if tile_size == 0 {
+        return Err("Tile size must be positive".to_string());
+    }
+    if format == ImageFormat::Jpeg {
+        return Err("JPEG encoding is not supported".to_string());
+    }
+    let ext = format.extension();
+    let mut image = RgbaImage { width, height, data: data.to_vec() };
+
+    match layout {
+        PyramidLayout::DeepZoom => {
+            let max_level = 32 - (width.max(height) - 1).leading_zeros();
+            for level in (0..=max_level).rev() {
+                for row in 0..image.height.div_ceil(tile_size) {
+                    for column in 0..image.width.div_ceil(tile_size) {
+                        let x = (column * tile_size).saturating_sub(overlap);
+                        let y = (row * tile_size).saturating_sub(overlap);
+                        let right = ((column + 1) * tile_size).saturating_add(overlap).min(image.width);
+                        let bottom = ((row + 1) * tile_size).saturating_add(overlap).min(image.height);
+                        let region = CropRegion { x, y, width: right - x, height: bottom - y };
+                        emit(&format!("{}/{}_{}.{}", level, column, row, ext), &tile(&image, region, format)?)?;
+                    }
+                }
+                if level > 0 {
+                    image = halve(&image);
+                }
+            }
+            Ok(format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" TileSize=\"{}\" Overlap=\"{}\" Format=\"{}\"><Size Width=\"{}\" Height=\"{}\"/></Image>\n",
+                tile_size, overlap, ext, width, height
+            ))
+        }
+        PyramidLayout::Iiif => {
+            let mut scale_factors = Vec::new();
+            let mut sizes = Vec::new();
+            for scale in (0..32).map(|shift| 1u64 << shift) {
+                scale_factors.push(scale.to_string());
+                // Tiles cover `tile_size * scale` full-size pixels
+                let span = tile_size as u64 * scale;
+                let single = span >= width as u64 && span >= height as u64;
+                for row in 0..image.height.div_ceil(tile_size) {
+                    for column in 0..image.width.div_ceil(tile_size) {
+                        let (x, y) = (column as u64 * span, row as u64 * span);
+                        let (w, h) = (span.min(width as u64 - x), span.min(height as u64 - y));
+                        let region = CropRegion { x: column * tile_size, y: row * tile_size, width: tile_size.min(image.width - column * tile_size), height: tile_size.min(image.height - row * tile_size) };
+                        let path = match (single, scale) {
+                            (true, 1) => format!("full/max/0/default.{}", ext),
+                            (true, _) => format!("full/{},{}/0/default.{}", region.width, region.height, ext),
+                            _ => format!("{},{},{},{}/{},{}/0/default.{}", x, y, w, h, region.width, region.height, ext),
+                        };
+                        emit(&path, &tile(&image, region, format)?)?;
+                    }
+                }
+                if single {
+                    sizes.push(format!("{{\"width\":{},\"height\":{}}}", image.width, image.height));
+                    break;
+                }
+                image = halve(&image);
+            }
+            Ok(format!(
+                "{{\"@context\":\"http://iiif.io/api/image/3/context.json\",\"id\":\"\",\"type\":\"ImageService3\",\"protocol\":\"http://iiif.io/api/image\",\"profile\":\"level0\",\"width\":{},\"height\":{},\"sizes\":[{}],\"tiles\":[{{\"width\":{},\"scaleFactors\":[{}]}}]}}",
+                width, height, sizes.join(","), tile_size, scale_factors.join(",")
+            ))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "bmp"))]
+mod tests {
+    use super::*;
+    use crate::convert::unpack;
+
+    fn generate(layout: PyramidLayout, overlap: u32) -> (String, Vec<(String, RgbaImage)>) {
+        let data: Vec<u8> = (0..10 * 5).flat_map(|i| [i as u8 * 5, 0, 0, 255]).collect();
+        let mut tiles = Vec::new();
+        let descriptor = generate_pyramid(&data, 10, 5, 4, overlap, ImageFormat::Bmp, layout, |path, bytes| {
+            tiles.push((path.to_string(), unpack(crate::bmp::decode_bmp(bytes)?)));
+            Ok(())
+        })
+        .unwrap();
+        (descriptor, tiles)
+    }
+
+    #[test]
+    fn test_deep_zoom() {
+        let (descriptor, tiles) = generate(PyramidLayout::DeepZoom, 1);
+        assert!(descriptor.contains("TileSize=\"4\" Overlap=\"1\" Format=\"bmp\"><Size Width=\"10\" Height=\"5\"/>"));
+        // Levels 4 (10x5, 3x2 tiles), 3 (5x3, 2x1), 2 (3x2), 1 (2x1), 0 (1x1)
+        assert_eq!(tiles.len(), 6 + 2 + 1 + 1 + 1);
+        let size = |path: &str| tiles.iter().find(|(p, _)| p == path).map(|(_, t)| (t.width, t.height)).unwrap();
+        assert_eq!(size("4/0_0.bmp"), (5, 5));
+        assert_eq!(size("4/1_0.bmp"), (6, 5));
+        assert_eq!(size("4/2_1.bmp"), (3, 2));
+        assert_eq!(size("3/1_0.bmp"), (2, 3));
+        assert_eq!(size("0/0_0.bmp"), (1, 1));
+        // Overlapping tiles start one pixel early
+        let (_, second) = tiles.iter().find(|(p, _)| p == "4/1_0.bmp").unwrap();
+        assert_eq!(second.data[0], 15);
+    }
+
+    #[test]
+    fn test_iiif() {
+        let (descriptor, tiles) = generate(PyramidLayout::Iiif, 1);
+        assert!(descriptor.contains("\"width\":10,\"height\":5,\"sizes\":[{\"width\":3,\"height\":2}],\"tiles\":[{\"width\":4,\"scaleFactors\":[1,2,4]}]"));
+        let paths: Vec<&str> = tiles.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["0,0,4,4/4,4/0/default.bmp", "4,0,4,4/4,4/0/default.bmp", "8,0,2,4/2,4/0/default.bmp", "0,4,4,1/4,1/0/default.bmp", "4,4,4,1/4,1/0/default.bmp", "8,4,2,1/2,1/0/default.bmp", "0,0,8,5/4,3/0/default.bmp", "8,0,2,5/1,3/0/default.bmp", "full/3,2/0/default.bmp"]);
+        assert!(generate_pyramid(&[0; 4], 1, 1, 4, 0, ImageFormat::Jpeg, PyramidLayout::Iiif, |_, _| Ok(())).is_err());
+    }
+}
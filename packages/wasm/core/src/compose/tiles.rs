@@ -0,0 +1,177 @@
+//! Cutting an image into a grid of tiles and joining tiles back together
+
+use crate::animation::{Canvas, Rect};
+use crate::transform::{crop, CropRegion, RgbaImage};
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Tiles of one image in row-major order
+///
+/// Tiles in a row share a height and tiles in a column share a width; the
+/// last row and column may be narrower than the rest.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileGrid {
+    columns: u32,
+    rows: u32,
+    tiles: Vec<RgbaImage>,
+}
+
+impl TileGrid {
+    pub fn tiles(&self) -> &[RgbaImage] {
+        &self.tiles
+    }
+
+    /// The tile at (`column`, `row`)
+    pub fn tile(&self, column: u32, row: u32) -> Result<&RgbaImage, String> {
+        Ok(&self.tiles[self.index(column, row)?])
+    }
+
+    fn index(&self, column: u32, row: u32) -> Result<usize, String> {
+        if column >= self.columns || row >= self.rows {
+            return Err(format!("Tile {},{} outside {}x{} grid", column, row, self.columns, self.rows));
+        }
+        Ok((row * self.columns + column) as usize)
+    }
+
+    /// Replace the tile at (`column`, `row`)
+    pub fn set(&mut self, column: u32, row: u32, tile: RgbaImage) -> Result<(), String> {
+        check_rgba(&tile.data, tile.width, tile.height)?;
+        let index = self.index(column, row)?;
+        self.tiles[index] = tile;
+        Ok(())
+    }
+
+    /// Join the tiles into one image
+    pub fn stitch(&self) -> Result<RgbaImage, String> {
+        stitch_tiles(&self.tiles, self.columns, self.rows)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TileGrid {
+    /// A grid of empty tiles, to be filled with `setTile`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(columns: u32, rows: u32) -> TileGrid {
+        let empty = RgbaImage { width: 0, height: 0, data: Vec::new() };
+        TileGrid { columns, rows, tiles: vec![empty; columns as usize * rows as usize] }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// The tile at (`column`, `row`)
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = tile)]
+    pub fn tile_js(&self, column: u32, row: u32) -> Result<RgbaImage, JsError> {
+        self.tile(column, row).cloned().map_err(|e| JsError::new(&e))
+    }
+
+    /// Replace the tile at (`column`, `row`)
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = setTile)]
+    pub fn set_js(&mut self, column: u32, row: u32, data: Vec<u8>, width: u32, height: u32) -> Result<(), JsError> {
+        self.set(column, row, RgbaImage { width, height, data }).map_err(|e| JsError::new(&e))
+    }
+
+    /// Join the tiles into one image
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = stitch)]
+    pub fn stitch_js(&self) -> Result<RgbaImage, JsError> {
+        self.stitch().map_err(|e| JsError::new(&e))
+    }
+}
+
+/// Cut an image into `tile_width` x `tile_height` tiles, row by row; tiles
+/// on the right and bottom edges are cut short
+pub fn split_tiles(data: &[u8], width: u32, height: u32, tile_width: u32, tile_height: u32) -> Result<TileGrid, String> {
+    check_rgba(data, width, height)?;
+    if width == 0 || height == 0 {
+        return Err("Cannot tile an empty image".to_string());
+    }
+    if tile_width == 0 || tile_height == 0 {
+        return Err("Tile size must be positive".to_string());
+    }
+    let mut grid = TileGrid::new(width.div_ceil(tile_width), height.div_ceil(tile_height));
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let (x, y) = (column * tile_width, row * tile_height);
+            let region = CropRegion { x, y, width: tile_width.min(width - x), height: tile_height.min(height - y) };
+            let tile = RgbaImage { width: region.width, height: region.height, data: crop(data, width, height, region)? };
+            grid.set(column, row, tile)?;
+        }
+    }
+    Ok(grid)
+}
+
+/// Join `columns` x `rows` tiles, given in row-major order, into one image
+///
+/// Tiles in a row must share a height and tiles in a column a width.
+pub fn stitch_tiles(tiles: &[RgbaImage], columns: u32, rows: u32) -> Result<RgbaImage, String> {
+    if columns == 0 || rows == 0 || tiles.len() as u64 != columns as u64 * rows as u64 {
+        return Err(format!("Got {} tiles for a {}x{} grid", tiles.len(), columns, rows));
+    }
+    let at = |column: u32, row: u32| &tiles[(row * columns + column) as usize];
+    let widths: Vec<u32> = (0..columns).map(|column| at(column, 0).width).collect();
+    let heights: Vec<u32> = (0..rows).map(|row| at(0, row).height).collect();
+    for (i, tile) in tiles.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        check_rgba(&tile.data, tile.width, tile.height)?;
+        if tile.width == 0 || (tile.width, tile.height) != (widths[column as usize], heights[row as usize]) {
+            return Err(format!("Tile {},{} is {}x{}, which does not line up with its row and column", column, row, tile.width, tile.height));
+        }
+    }
+    let (Some(width), Some(height)) = (widths.iter().try_fold(0u32, |sum, &w| sum.checked_add(w)), heights.iter().try_fold(0u32, |sum, &h| sum.checked_add(h))) else {
+        return Err("Stitched image is too large".to_string());
+    };
+
+    let mut canvas = Canvas::new(width, height);
+    let mut y = 0;
+    for row in 0..rows {
+        let mut x = 0;
+        for column in 0..columns {
+            let tile = at(column, row);
+            canvas.draw(&tile.data, Rect { x, y, width: tile.width, height: tile.height }, false);
+            x += tile.width;
+        }
+        y += heights[row as usize];
+    }
+    Ok(RgbaImage { width, height, data: canvas.data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_stitch() {
+        let (width, height) = (7, 5);
+        let data: Vec<u8> = (0..width * height).flat_map(|i| [i as u8, (i * 3) as u8, 0, 255]).collect();
+        let mut grid = split_tiles(&data, width, height, 3, 2).unwrap();
+        assert_eq!((grid.columns(), grid.rows()), (3, 3));
+        let corner = &grid.tiles()[8];
+        assert_eq!((corner.width, corner.height), (1, 1));
+        assert_eq!(corner.data, [34, 102, 0, 255]);
+        assert_eq!(grid.stitch().unwrap(), RgbaImage { width, height, data: data.clone() });
+
+        // Processed tiles go back in place
+        grid.set(1, 1, RgbaImage { width: 3, height: 2, data: [9; 24].to_vec() }).unwrap();
+        let stitched = grid.stitch().unwrap();
+        assert_eq!(&stitched.data[(2 * 7 + 3) * 4..(2 * 7 + 6) * 4], &[9; 12]);
+        assert_eq!(&stitched.data[(2 * 7 + 6) * 4..(2 * 7 + 7) * 4], &data[(2 * 7 + 6) * 4..(2 * 7 + 7) * 4]);
+
+        grid.set(1, 1, RgbaImage { width: 2, height: 2, data: vec![0; 16] }).unwrap();
+        assert!(grid.stitch().is_err());
+        assert!(stitch_tiles(grid.tiles(), 2, 3).is_err());
+        assert!(TileGrid::new(2, 2).stitch().is_err());
+        assert!(split_tiles(&data, width, height, 0, 2).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Bit I/O: LSB-first as used by DEFLATE, MSB-first as used by bzip2
+
+use alloc::{string::{String, ToString}, vec::Vec};
+
+/// Reads bits least-significant first from a byte slice
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buf: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0, buf: 0, count: 0 }
+    }
+
+    #[inline]
+    fn refill(&mut self) {
+        while self.count <= 56 {
+            let Some(&byte) = self.data.get(self.pos) else { break };
+            self.buf |= (byte as u64) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+    }
+
+    /// Look at the next `n` (<= 32) bits without consuming; missing bits read as zero
+    #[inline]
+    pub fn peek(&mut self, n: u32) -> u32 {
+        if self.count < n {
+            self.refill();
+        }
+        (self.buf & ((1u64 << n) - 1)) as u32
+    }
+
+    /// Drop `n` bits previously peeked
+    #[inline]
+    pub fn consume(&mut self, n: u32) -> Result<(), String> {
+        if self.count < n {
+            self.refill();
+            if self.count < n {
+                return Err("Unexpected end of compressed data".to_string());
+            }
+        }
+        self.buf >>= n;
+        self.count -= n;
+        Ok(())
+    }
+
+    /// Read `n` (<= 32) bits
+    #[inline]
+    pub fn bits(&mut self, n: u32) -> Result<u32, String> {
+        let v = self.peek(n);
+        self.consume(n)?;
+        Ok(v)
+    }
+
+    /// Discard bits up to the next byte boundary
+    pub fn align(&mut self) {
+        let drop = self.count % 8;
+        self.buf >>= drop;
+        self.count -= drop;
+    }
+
+    /// Number of bits consumed so far
+    pub fn bit_pos(&self) -> usize {
+        self.pos * 8 - self.count as usize
+    }
+
+    /// Byte offset of the next unread whole byte (after `align`)
+    pub fn byte_pos(&self) -> usize {
+        self.pos - (self.count / 8) as usize
+    }
+
+    /// Read `n` whole bytes after `align`
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let start = self.byte_pos();
+        let slice = self
+            .data
+            .get(start..start.checked_add(n).ok_or("Length overflow")?)
+            .ok_or("Unexpected end of compressed data")?;
+        self.pos = start + n;
+        self.buf = 0;
+        self.count = 0;
+        Ok(slice)
+    }
+}
+
+/// Writes bits least-significant first
+#[derive(Default)]
+pub struct BitWriter {
+    out: Vec<u8>,
+    buf: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the low `n` (<= 32) bits of `value`
+    #[inline]
+    pub fn write(&mut self, value: u32, n: u32) {
+        self.buf |= (value as u64 & ((1u64 << n) - 1)) << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.out.push(self.buf as u8);
+            self.buf >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Pad with zero bits to a byte boundary
+    pub fn align(&mut self) {
+        if self.count > 0 {
+            self.out.push(self.buf as u8);
+            self.buf = 0;
+            self.count = 0;
+        }
+    }
+
+    /// Append whole bytes (call `align` first)
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        debug_assert_eq!(self.count, 0);
+        self.out.extend_from_slice(bytes);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.out
+    }
+}
+
+/// Reads bits most-significant first from a byte slice
+pub struct MsbBitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Unread bits, left-aligned
+    buf: u64,
+    count: u32,
+}
+
+impl<'a> MsbBitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        MsbBitReader { data, pos: 0, buf: 0, count: 0 }
+    }
+
+    #[inline]
+    fn refill(&mut self) {
+        while self.count <= 56 {
+            let Some(&byte) = self.data.get(self.pos) else { break };
+            self.buf |= (byte as u64) << (56 - self.count);
+            self.pos += 1;
+            self.count += 8;
+        }
+    }
+
+    /// Read `n` (<= 32) bits
+    #[inline]
+    pub fn bits(&mut self, n: u32) -> Result<u32, String> {
+        if n == 0 {
+            return Ok(0);
+        }
+        if self.count < n {
+            self.refill();
+            if self.count < n {
+                return Err("Unexpected end of compressed data".to_string());
+            }
+        }
+        let v = (self.buf >> (64 - n)) as u32;
+        self.buf <<= n;
+        self.count -= n;
+        Ok(v)
+    }
+
+    #[inline]
+    pub fn bit(&mut self) -> Result<bool, String> {
+        Ok(self.bits(1)? == 1)
+    }
+
+    /// Discard bits up to the next byte boundary
+    pub fn align(&mut self) {
+        let drop = self.count % 8;
+        self.buf <<= drop;
+        self.count -= drop;
+    }
+
+    /// Byte offset of the next unread whole byte (after `align`)
+    pub fn byte_pos(&self) -> usize {
+        self.pos - (self.count / 8) as usize
+    }
+}
+
+/// Writes bits most-significant first
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+#[derive(Default)]
+pub struct MsbBitWriter {
+    out: Vec<u8>,
+    buf: u64,
+    count: u32,
+}
+
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+impl MsbBitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the low `n` (<= 32) bits of `value`, high bit first
+    #[inline]
+    pub fn write(&mut self, value: u32, n: u32) {
+        self.buf = (self.buf << n) | (value as u64 & ((1u64 << n) - 1));
+        self.count += n;
+        while self.count >= 8 {
+            self.count -= 8;
+            self.out.push((self.buf >> self.count) as u8);
+        }
+    }
+
+    /// Pad with zero bits to a byte boundary and return the bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push((self.buf << (8 - self.count)) as u8);
+        }
+        self.out
+    }
+}
@@ -0,0 +1,313 @@
+//! bzip2 decompression (Huffman, MTF/RLE2, inverse BWT, RLE1)
+
+use super::bits::MsbBitReader;
+use super::limit_error;
+use crate::limits::output_limit;
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+const BLOCK_MAGIC: u64 = 0x3141_5926_5359;
+const END_MAGIC: u64 = 0x1772_4538_5090;
+const GROUP_SIZE: usize = 50;
+const MAX_CODE_LEN: usize = 20;
+const MAX_SELECTORS: usize = 18002;
+
+/// CRC-32 as used by bzip2 (MSB-first, polynomial 0x04C11DB7)
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = (i as u32) << 24;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 0x8000_0000 != 0 { (c << 1) ^ 0x04C1_1DB7 } else { c << 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+fn block_crc(data: &[u8]) -> u32 {
+    !data
+        .iter()
+        .fold(!0u32, |c, &b| (c << 8) ^ CRC_TABLE[((c >> 24) ^ b as u32) as usize])
+}
+
+/// Canonical Huffman decoder for one coding table
+struct HuffmanTable {
+    /// Per code length: first code, first index into `symbols`, symbol count
+    first_code: [u32; MAX_CODE_LEN + 1],
+    first_index: [usize; MAX_CODE_LEN + 1],
+    count: [u32; MAX_CODE_LEN + 1],
+    symbols: Vec<u16>,
+}
+
+impl HuffmanTable {
+    fn new(lengths: &[u8]) -> HuffmanTable {
+        let mut table = HuffmanTable {
+            first_code: [0; MAX_CODE_LEN + 1],
+            first_index: [0; MAX_CODE_LEN + 1],
+            count: [0; MAX_CODE_LEN + 1],
+            symbols: Vec::with_capacity(lengths.len()),
+        };
+        let mut code = 0;
+        for len in 1..=MAX_CODE_LEN {
+            table.first_code[len] = code;
+            table.first_index[len] = table.symbols.len();
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l as usize == len) {
+                table.symbols.push(symbol as u16);
+            }
+            table.count[len] = (table.symbols.len() - table.first_index[len]) as u32;
+            code = (code + table.count[len]) << 1;
+        }
+        table
+    }
+
+    fn decode(&self, reader: &mut MsbBitReader) -> Result<u16, String> {
+        let mut code = 0;
+        for len in 1..=MAX_CODE_LEN {
+            code = (code << 1) | reader.bits(1)?;
+            let offset = code.wrapping_sub(self.first_code[len]);
+            if offset < self.count[len] {
+                return Ok(self.symbols[self.first_index[len] + offset as usize]);
+            }
+        }
+        Err("Invalid bzip2 Huffman code".to_string())
+    }
+}
+
+/// Decode one block (after its magic and CRC) into `out`, failing if `out`
+/// would pass `limit` bytes
+fn decode_block(reader: &mut MsbBitReader, max_size: usize, limit: usize, out: &mut Vec<u8>) -> Result<(), String> {
+    if reader.bit()? {
+        return Err("Randomized bzip2 blocks are not supported".to_string());
+    }
+    let orig_ptr = reader.bits(24)? as usize;
+
+    // Symbol map: which byte values occur in the block
+    let used = reader.bits(16)?;
+    let mut symbols = Vec::with_capacity(256);
+    for i in 0..16 {
+        if used & (0x8000 >> i) != 0 {
+            let bits = reader.bits(16)?;
+            symbols.extend((0..16).filter(|j| bits & (0x8000 >> j) != 0).map(|j| (i * 16 + j) as u8));
+        }
+    }
+    if symbols.is_empty() {
+        return Err("Empty bzip2 symbol map".to_string());
+    }
+    let alpha_size = symbols.len() + 2;
+    let end_of_block = (alpha_size - 1) as u16;
+
+    let groups = reader.bits(3)? as usize;
+    if !(2..=6).contains(&groups) {
+        return Err("Invalid number of bzip2 Huffman groups".to_string());
+    }
+    let selector_count = reader.bits(15)? as usize;
+    if selector_count == 0 {
+        return Err("Invalid number of bzip2 selectors".to_string());
+    }
+    let mut group_mtf: Vec<u8> = (0..groups as u8).collect();
+    let mut selectors = Vec::with_capacity(selector_count.min(MAX_SELECTORS));
+    for _ in 0..selector_count {
+        let mut j = 0;
+        while reader.bit()? {
+            j += 1;
+            if j >= groups {
+                return Err("Invalid bzip2 selector".to_string());
+            }
+        }
+        let group = group_mtf.remove(j);
+        group_mtf.insert(0, group);
+        if selectors.len() < MAX_SELECTORS {
+            selectors.push(group);
+        }
+    }
+
+    // Code lengths are delta coded
+    let mut tables = Vec::with_capacity(groups);
+    for _ in 0..groups {
+        let mut len = reader.bits(5)? as i32;
+        let mut lengths = vec![0u8; alpha_size];
+        for l in lengths.iter_mut() {
+            loop {
+                if !(1..=MAX_CODE_LEN as i32).contains(&len) {
+                    return Err("Invalid bzip2 code length".to_string());
+                }
+                if !reader.bit()? {
+                    break;
+                }
+                len += if reader.bit()? { -1 } else { 1 };
+            }
+            *l = len as u8;
+        }
+        tables.push(HuffmanTable::new(&lengths));
+    }
+
+    // Huffman + RUNA/RUNB run lengths + move-to-front
+    let mut mtf: Vec<u8> = (0..=255).collect();
+    let mut tt: Vec<u32> = Vec::with_capacity(max_size);
+    let mut counts = [0usize; 256];
+    let mut run = 0usize;
+    let mut run_weight = 1usize;
+    let mut table = &tables[0];
+    let mut selector = 0;
+    for n in 0.. {
+        if n % GROUP_SIZE == 0 {
+            table = &tables[*selectors.get(selector).ok_or("Ran out of bzip2 selectors")? as usize];
+            selector += 1;
+        }
+        let sym = table.decode(reader)?;
+
+        if sym <= 1 {
+            run += run_weight << sym;
+            run_weight <<= 1;
+            if run > max_size {
+                return Err("bzip2 block too large".to_string());
+            }
+            continue;
+        }
+        if run > 0 {
+            let byte = symbols[mtf[0] as usize];
+            counts[byte as usize] += run;
+            tt.extend(core::iter::repeat_n(byte as u32, run));
+            run = 0;
+            run_weight = 1;
+        }
+        if sym == end_of_block {
+            break;
+        }
+
+        let index = (sym - 1) as usize;
+        let value = mtf[index];
+        mtf.copy_within(..index, 1);
+        mtf[0] = value;
+        let byte = *symbols.get(value as usize).ok_or("Invalid bzip2 MTF symbol")?;
+        counts[byte as usize] += 1;
+        tt.push(byte as u32);
+        if tt.len() > max_size {
+            return Err("bzip2 block too large".to_string());
+        }
+    }
+    if orig_ptr >= tt.len() {
+        return Err("Invalid bzip2 origin pointer".to_string());
+    }
+
+    // Inverse BWT: link each position to its successor
+    let mut next = [0usize; 256];
+    let mut sum = 0;
+    for (b, &c) in counts.iter().enumerate() {
+        next[b] = sum;
+        sum += c;
+    }
+    for i in 0..tt.len() {
+        let b = (tt[i] & 0xFF) as usize;
+        tt[next[b]] |= (i as u32) << 8;
+        next[b] += 1;
+    }
+
+    // Undo the initial run-length encoding: 4 equal bytes are followed by a repeat count
+    let mut pos = (tt[orig_ptr] >> 8) as usize;
+    let mut last = None;
+    let mut same = 0;
+    let mut remaining = tt.len();
+    while remaining > 0 {
+        let entry = tt[pos];
+        let byte = entry as u8;
+        pos = (entry >> 8) as usize;
+        remaining -= 1;
+
+        // After four equal bytes, `byte` is a repeat count
+        if out.len() + if same == 4 { byte as usize } else { 1 } > limit {
+            return Err(limit_error(limit));
+        }
+        if same == 4 {
+            out.extend(core::iter::repeat_n(last.unwrap_or(0), byte as usize));
+            same = 0;
+            last = None;
+            continue;
+        }
+        out.push(byte);
+        if last == Some(byte) {
+            same += 1;
+        } else {
+            last = Some(byte);
+            same = 1;
+        }
+    }
+    Ok(())
+}
+
+/// Decompress bzip2 data, concatenating multiple streams, within the output
+/// byte limit set by `set_limits`
+pub fn bzip2_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let limit = output_limit();
+    let mut out = Vec::new();
+    let mut pos = 0;
+    loop {
+        let header = data.get(pos..pos + 4).ok_or("bzip2 data too small")?;
+        if &header[..3] != b"BZh" || !(b'1'..=b'9').contains(&header[3]) {
+            return Err("Invalid bzip2 signature".to_string());
+        }
+        let max_size = (header[3] - b'0') as usize * 100_000;
+        let mut reader = MsbBitReader::new(&data[pos + 4..]);
+        let mut combined = 0u32;
+
+        loop {
+            let magic = ((reader.bits(24)? as u64) << 24) | reader.bits(24)? as u64;
+            let crc = reader.bits(32)?;
+            if magic == END_MAGIC {
+                if crc != combined {
+                    return Err("bzip2 stream checksum mismatch".to_string());
+                }
+                break;
+            }
+            if magic != BLOCK_MAGIC {
+                return Err("Invalid bzip2 block signature".to_string());
+            }
+            let start = out.len();
+            decode_block(&mut reader, max_size, limit, &mut out)?;
+            if block_crc(&out[start..]) != crc {
+                return Err("bzip2 block checksum mismatch".to_string());
+            }
+            combined = combined.rotate_left(1) ^ crc;
+        }
+
+        reader.align();
+        pos += 4 + reader.byte_pos();
+        // Another stream may follow (e.g. from parallel compressors)
+        if !data[pos..].starts_with(b"BZh") {
+            return Ok(out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_decompress() {
+        let expected: String = (0..25).map(|i| format!("bzip2 block {}; ", i % 3)).collect::<String>() + &"a".repeat(20);
+        // `bzip2 -9` output
+        let bz = hex(concat!(
+            "425a6839314159265359aa0cf51000005e3980400040007008382cc010200050a60000295211e930d4fbd459cc5817c87b160678181c8cdc21ce03d8b5",
+            "17917916a2d45b45fc5dc914e14242a833d440"
+        ));
+        assert_eq!(bzip2_decompress(&bz).unwrap(), expected.as_bytes());
+
+        let mut two = bz.clone();
+        two.extend_from_slice(&bz);
+        assert_eq!(bzip2_decompress(&two).unwrap(), expected.repeat(2).as_bytes());
+
+        let mut corrupt = bz.clone();
+        corrupt[40] ^= 0x01;
+        assert!(bzip2_decompress(&corrupt).is_err());
+        assert_eq!(block_crc(b"123456789"), 0xFC89_1918);
+    }
+}
@@ -0,0 +1,339 @@
+//! DEFLATE encoder - hash-chain LZ77 with per-block Huffman code selection
+
+use super::{fixed_lengths, CODE_LENGTH_ORDER, DIST_BASE, DIST_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+use crate::compress::bits::BitWriter;
+use crate::compress::primitives::{canonical_codes, code_lengths, reverse_bits};
+use alloc::{vec, vec::Vec};
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+const NONE: u32 = u32::MAX;
+/// Tokens per block before the Huffman codes are re-chosen
+const BLOCK_TOKENS: usize = 16384;
+const MAX_STORED: usize = 65535;
+
+/// Match search effort for a compression level
+struct LevelConfig {
+    max_chain: usize,
+    nice_len: usize,
+    lazy: bool,
+}
+
+fn level_config(level: u8) -> LevelConfig {
+    let (max_chain, nice_len, lazy) = match level {
+        1 => (4, 8, false),
+        2 => (8, 16, false),
+        3 => (16, 32, false),
+        4 => (16, 32, true),
+        5 => (32, 64, true),
+        6 => (128, 128, true),
+        7 => (256, 192, true),
+        8 => (1024, 258, true),
+        _ => (4096, 258, true),
+    };
+    LevelConfig { max_chain, nice_len, lazy }
+}
+
+/// A literal byte (`dist == 0`) or a back-reference
+#[derive(Clone, Copy)]
+struct Token {
+    value: u16,
+    dist: u16,
+}
+
+struct Matcher<'a> {
+    data: &'a [u8],
+    head: Vec<u32>,
+    prev: Vec<u32>,
+    config: LevelConfig,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(data: &'a [u8], config: LevelConfig) -> Self {
+        Matcher {
+            data,
+            head: vec![NONE; 1 << HASH_BITS],
+            prev: vec![NONE; data.len()],
+            config,
+        }
+    }
+
+    #[inline]
+    fn hash(&self, pos: usize) -> usize {
+        let d = self.data;
+        let v = u32::from_le_bytes([d[pos], d[pos + 1], d[pos + 2], 0]);
+        (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    }
+
+    #[inline]
+    fn insert(&mut self, pos: usize) {
+        if pos + MIN_MATCH <= self.data.len() {
+            let h = self.hash(pos);
+            self.prev[pos] = self.head[h];
+            self.head[h] = pos as u32;
+        }
+    }
+
+    /// Longest earlier match for `pos` as (length, distance); length 0 if none
+    fn find(&self, pos: usize) -> (usize, usize) {
+        let d = self.data;
+        let max_len = MAX_MATCH.min(d.len() - pos);
+        if max_len < MIN_MATCH {
+            return (0, 0);
+        }
+
+        let (mut best_len, mut best_dist) = (0, 0);
+        let mut cand = self.head[self.hash(pos)];
+        let mut chain = self.config.max_chain;
+        while cand != NONE && chain > 0 {
+            let c = cand as usize;
+            if pos - c > WINDOW_SIZE {
+                break;
+            }
+            if d[c + best_len.min(max_len - 1)] == d[pos + best_len.min(max_len - 1)] {
+                let len = d[c..c + max_len].iter().zip(&d[pos..pos + max_len]).take_while(|(a, b)| a == b).count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = pos - c;
+                    if len >= self.config.nice_len || len == max_len {
+                        break;
+                    }
+                }
+            }
+            cand = self.prev[c];
+            chain -= 1;
+        }
+
+        // A 3-byte match far back costs more than three literals
+        if best_len < MIN_MATCH || (best_len == MIN_MATCH && best_dist > 4096) {
+            return (0, 0);
+        }
+        (best_len, best_dist)
+    }
+}
+
+#[inline]
+fn length_index(len: usize) -> usize {
+    LENGTH_BASE.partition_point(|&b| b as usize <= len) - 1
+}
+
+#[inline]
+fn dist_index(dist: usize) -> usize {
+    DIST_BASE.partition_point(|&b| b as usize <= dist) - 1
+}
+
+/// Run-length encode code lengths as (symbol, extra value) pairs using codes 16-18
+///
+/// Code 16 only ever follows a literal of the same length, so the output is
+/// also valid for VP8L, where 16 repeats the last non-zero length.
+pub(crate) fn rle_code_lengths(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let v = lengths[i];
+        let run = lengths[i..].iter().take_while(|&&l| l == v).count();
+        let mut rest = run;
+        if v == 0 {
+            while rest >= 11 {
+                let k = rest.min(138);
+                out.push((18, (k - 11) as u8));
+                rest -= k;
+            }
+            if rest >= 3 {
+                out.push((17, (rest - 3) as u8));
+                rest = 0;
+            }
+        } else {
+            out.push((v, 0));
+            rest -= 1;
+            while rest >= 3 {
+                let k = rest.min(6);
+                out.push((16, (k - 3) as u8));
+                rest -= k;
+            }
+        }
+        out.extend(core::iter::repeat_n((v, 0), rest));
+        i += run;
+    }
+    out
+}
+
+fn extra_bits(symbol: u8) -> u32 {
+    match symbol {
+        16 => 2,
+        17 => 3,
+        18 => 7,
+        _ => 0,
+    }
+}
+
+fn write_stored(w: &mut BitWriter, raw: &[u8], last: bool) {
+    let mut chunks = raw.chunks(MAX_STORED).peekable();
+    if raw.is_empty() {
+        w.write(last as u32, 3);
+        w.align();
+        w.write_bytes(&[0, 0, 0xFF, 0xFF]);
+        return;
+    }
+    while let Some(chunk) = chunks.next() {
+        w.write((last && chunks.peek().is_none()) as u32, 3);
+        w.align();
+        let len = chunk.len() as u16;
+        w.write_bytes(&len.to_le_bytes());
+        w.write_bytes(&(!len).to_le_bytes());
+        w.write_bytes(chunk);
+    }
+}
+
+fn write_tokens(w: &mut BitWriter, tokens: &[Token], litlen: &[u8], dist: &[u8]) {
+    let lit_codes = canonical_codes(litlen);
+    let dist_codes = canonical_codes(dist);
+    let put = |w: &mut BitWriter, codes: &[u16], lens: &[u8], sym: usize| {
+        w.write(reverse_bits(codes[sym], lens[sym]) as u32, lens[sym] as u32);
+    };
+    for t in tokens {
+        if t.dist == 0 {
+            put(w, &lit_codes, litlen, t.value as usize);
+            continue;
+        }
+        let li = length_index(t.value as usize);
+        put(w, &lit_codes, litlen, 257 + li);
+        w.write(t.value as u32 - LENGTH_BASE[li] as u32, LENGTH_EXTRA[li] as u32);
+        let di = dist_index(t.dist as usize);
+        put(w, &dist_codes, dist, di);
+        w.write(t.dist as u32 - DIST_BASE[di] as u32, DIST_EXTRA[di] as u32);
+    }
+    put(w, &lit_codes, litlen, 256);
+}
+
+/// Emit one block, choosing whichever of stored, fixed or dynamic codes is smallest
+fn write_block(w: &mut BitWriter, tokens: &[Token], raw: &[u8], last: bool) {
+    let mut lit_freq = [0u32; 286];
+    let mut dist_freq = [0u32; 30];
+    let mut extra = 0u64;
+    lit_freq[256] = 1;
+    for t in tokens {
+        if t.dist == 0 {
+            lit_freq[t.value as usize] += 1;
+        } else {
+            let li = length_index(t.value as usize);
+            let di = dist_index(t.dist as usize);
+            lit_freq[257 + li] += 1;
+            dist_freq[di] += 1;
+            extra += LENGTH_EXTRA[li] as u64 + DIST_EXTRA[di] as u64;
+        }
+    }
+    let cost = |freq: &[u32], lens: &[u8]| freq.iter().zip(lens).map(|(&f, &l)| f as u64 * l as u64).sum::<u64>();
+
+    // Dynamic code and its header
+    let lit_lens = code_lengths(&lit_freq, 15);
+    let dist_lens = code_lengths(&dist_freq, 15);
+    let hlit = 257.max(lit_lens.iter().rposition(|&l| l > 0).map_or(0, |p| p + 1));
+    let hdist = 1.max(dist_lens.iter().rposition(|&l| l > 0).map_or(0, |p| p + 1));
+    let all_lens: Vec<u8> = lit_lens[..hlit].iter().chain(&dist_lens[..hdist]).copied().collect();
+    let rle = rle_code_lengths(&all_lens);
+    let mut cl_freq = [0u32; 19];
+    for &(sym, _) in &rle {
+        cl_freq[sym as usize] += 1;
+    }
+    let cl_lens = code_lengths(&cl_freq, 7);
+    let hclen = 4.max(CODE_LENGTH_ORDER.iter().rposition(|&i| cl_lens[i] > 0).map_or(0, |p| p + 1));
+    let dynamic_cost = 3 + 14 + 3 * hclen as u64
+        + rle.iter().map(|&(s, _)| cl_lens[s as usize] as u64 + extra_bits(s) as u64).sum::<u64>()
+        + cost(&lit_freq, &lit_lens)
+        + cost(&dist_freq, &dist_lens)
+        + extra;
+
+    let (fixed_lit, fixed_dist) = fixed_lengths();
+    let fixed_cost = 3 + cost(&lit_freq, &fixed_lit) + cost(&dist_freq, &fixed_dist) + extra;
+
+    let stored_cost = raw.len() as u64 * 8 + 42 * raw.len().div_ceil(MAX_STORED).max(1) as u64;
+
+    if stored_cost <= fixed_cost.min(dynamic_cost) {
+        write_stored(w, raw, last);
+    } else if fixed_cost <= dynamic_cost {
+        w.write(last as u32 | (1 << 1), 3);
+        write_tokens(w, tokens, &fixed_lit, &fixed_dist);
+    } else {
+        w.write(last as u32 | (2 << 1), 3);
+        w.write(hlit as u32 - 257, 5);
+        w.write(hdist as u32 - 1, 5);
+        w.write(hclen as u32 - 4, 4);
+        for &i in &CODE_LENGTH_ORDER[..hclen] {
+            w.write(cl_lens[i] as u32, 3);
+        }
+        let cl_codes = canonical_codes(&cl_lens);
+        for &(sym, value) in &rle {
+            let s = sym as usize;
+            w.write(reverse_bits(cl_codes[s], cl_lens[s]) as u32, cl_lens[s] as u32);
+            w.write(value as u32, extra_bits(sym));
+        }
+        write_tokens(w, tokens, &lit_lens, &dist_lens);
+    }
+}
+
+/// Compress data as a raw DEFLATE stream
+///
+/// `level` ranges from 0 (stored, no compression) to 9 (slowest, smallest);
+/// values above 9 are treated as 9.
+pub fn deflate(data: &[u8], level: u8) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    if level == 0 {
+        write_stored(&mut w, data, true);
+        return w.finish();
+    }
+
+    let config = level_config(level);
+    let (lazy, nice_len) = (config.lazy, config.nice_len);
+    let mut matcher = Matcher::new(data, config);
+    let mut tokens = Vec::with_capacity(BLOCK_TOKENS + 2);
+    let mut block_start = 0;
+    // Match found at the previous position, held back in case this one is longer
+    let mut pending: Option<(usize, usize)> = None;
+    let mut i = 0;
+
+    while i < data.len() {
+        if pending.is_none() && tokens.len() >= BLOCK_TOKENS {
+            write_block(&mut w, &tokens, &data[block_start..i], false);
+            tokens.clear();
+            block_start = i;
+        }
+
+        let (len, dist) = matcher.find(i);
+        matcher.insert(i);
+
+        if let Some((plen, pdist)) = pending.take() {
+            if len <= plen {
+                tokens.push(Token { value: plen as u16, dist: pdist as u16 });
+                let end = i - 1 + plen;
+                for p in i + 1..end {
+                    matcher.insert(p);
+                }
+                i = end;
+                continue;
+            }
+            tokens.push(Token { value: data[i - 1] as u16, dist: 0 });
+        }
+
+        if len >= MIN_MATCH {
+            if lazy && len < nice_len && i + 1 < data.len() {
+                pending = Some((len, dist));
+                i += 1;
+            } else {
+                tokens.push(Token { value: len as u16, dist: dist as u16 });
+                for p in i + 1..i + len {
+                    matcher.insert(p);
+                }
+                i += len;
+            }
+        } else {
+            tokens.push(Token { value: data[i] as u16, dist: 0 });
+            i += 1;
+        }
+    }
+
+    write_block(&mut w, &tokens, &data[block_start..], true);
+    w.finish()
+}
@@ -0,0 +1,139 @@
+//! DEFLATE decoder
+
+use super::{fixed_lengths, CODE_LENGTH_ORDER, DIST_BASE, DIST_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+use crate::compress::bits::BitReader;
+use crate::compress::limit_error;
+use crate::compress::primitives::HuffmanDecoder as Huffman;
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+/// Result of decoding a raw DEFLATE stream
+pub struct Inflated {
+    pub data: Vec<u8>,
+    /// Compressed bytes consumed, including the final partial byte
+    pub consumed: usize,
+}
+
+fn read_dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let hlit = reader.bits(5)? as usize + 257;
+    let hdist = reader.bits(5)? as usize + 1;
+    let hclen = reader.bits(4)? as usize + 4;
+
+    let mut cl_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..hclen] {
+        cl_lengths[i] = reader.bits(3)? as u8;
+    }
+    let cl = Huffman::new(&cl_lengths)?;
+
+    let mut lengths = vec![0u8; hlit + hdist];
+    let mut i = 0;
+    while i < lengths.len() {
+        let (value, repeat) = match cl.decode(reader)? {
+            sym @ 0..=15 => (sym as u8, 1),
+            16 => {
+                let prev = *lengths[..i].last().ok_or("Repeat with no previous length")?;
+                (prev, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err("Code lengths overflow".to_string());
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err("Missing end-of-block code".to_string());
+    }
+
+    Ok((Huffman::new(&lengths[..hlit])?, Huffman::new(&lengths[hlit..])?))
+}
+
+/// Decode a raw DEFLATE stream, failing if the output would exceed `limit` bytes
+pub fn inflate_with_limit(data: &[u8], limit: usize) -> Result<Inflated, String> {
+    let mut reader = BitReader::new(data);
+    let mut out: Vec<u8> = Vec::with_capacity((data.len() * 3).min(limit));
+
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = reader.bytes(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err("Stored block length mismatch".to_string());
+                }
+                if out.len() + len as usize > limit {
+                    return Err(limit_error(limit));
+                }
+                out.extend_from_slice(reader.bytes(len as usize)?);
+            }
+            kind @ (1 | 2) => {
+                let (litlen, dist) = if kind == 1 {
+                    let (l, d) = fixed_lengths();
+                    (Huffman::new(&l)?, Huffman::new(&d)?)
+                } else {
+                    read_dynamic_tables(&mut reader)?
+                };
+
+                loop {
+                    let sym = litlen.decode(&mut reader)?;
+                    if sym < 256 {
+                        if out.len() >= limit {
+                            return Err(limit_error(limit));
+                        }
+                        out.push(sym as u8);
+                        continue;
+                    }
+                    if sym == 256 {
+                        break;
+                    }
+
+                    let li = (sym - 257) as usize;
+                    if li >= 29 {
+                        return Err("Invalid length code".to_string());
+                    }
+                    let len = LENGTH_BASE[li] as usize + reader.bits(LENGTH_EXTRA[li] as u32)? as usize;
+                    let di = dist.decode(&mut reader)? as usize;
+                    if di >= 30 {
+                        return Err("Invalid distance code".to_string());
+                    }
+                    let distance = DIST_BASE[di] as usize + reader.bits(DIST_EXTRA[di] as u32)? as usize;
+                    if distance > out.len() {
+                        return Err("Distance too far back".to_string());
+                    }
+                    if out.len() + len > limit {
+                        return Err(limit_error(limit));
+                    }
+
+                    let start = out.len() - distance;
+                    if distance >= len {
+                        out.extend_from_within(start..start + len);
+                    } else {
+                        // Overlapping copy repeats the last `distance` bytes
+                        for k in 0..len {
+                            out.push(out[start + k]);
+                        }
+                    }
+                }
+            }
+            _ => return Err("Invalid block type".to_string()),
+        }
+        if last {
+            break;
+        }
+    }
+
+    reader.align();
+    Ok(Inflated {
+        data: out,
+        consumed: reader.byte_pos(),
+    })
+}
+
+/// Decode a raw DEFLATE stream within the output byte limit set by `set_limits`
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    inflate_with_limit(data, crate::limits::output_limit()).map(|r| r.data)
+}
@@ -0,0 +1,72 @@
+//! DEFLATE (RFC 1951) compression and decompression
+
+mod encoder;
+mod inflate;
+
+pub use encoder::deflate;
+#[cfg(feature = "webp")]
+pub(crate) use encoder::rle_code_lengths;
+pub use inflate::{inflate, inflate_with_limit, Inflated};
+
+/// Base match length for length codes 257-285
+pub(crate) const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+pub(crate) const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+
+/// Base distance for distance codes 0-29
+pub(crate) const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+pub(crate) const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// Transmission order of code length code lengths
+pub(crate) const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Code lengths of the fixed literal/length and distance codes
+pub(crate) fn fixed_lengths() -> ([u8; 288], [u8; 30]) {
+    let mut litlen = [0u8; 288];
+    litlen[..144].fill(8);
+    litlen[144..256].fill(9);
+    litlen[256..280].fill(7);
+    litlen[280..].fill(8);
+    (litlen, [5u8; 30])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        let mut data = Vec::new();
+        for i in 0..4000u32 {
+            data.extend_from_slice(format!("line {} of some repetitive text {}\n", i % 97, i % 13).as_bytes());
+        }
+        data.extend((0..5000u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8));
+        data
+    }
+
+    #[test]
+    fn test_roundtrip_all_levels() {
+        let data = sample();
+        for level in 0..=9 {
+            let compressed = deflate(&data, level);
+            assert_eq!(inflate(&compressed).unwrap(), data, "level {}", level);
+            if level > 0 {
+                assert!(compressed.len() < data.len() / 4, "level {}: {}", level, compressed.len());
+            }
+        }
+        assert_eq!(inflate(&deflate(&[], 6)).unwrap(), Vec::<u8>::new());
+        assert_eq!(inflate(&deflate(b"a", 6)).unwrap(), b"a");
+    }
+
+    #[test]
+    fn test_inflate_fixed_block() {
+        // "Hello" compressed with fixed Huffman codes by zlib
+        let compressed = [0xF3, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00];
+        assert_eq!(inflate(&compressed).unwrap(), b"Hello");
+        assert!(inflate(&[0xF3, 0x48]).is_err());
+        assert!(inflate_with_limit(&deflate(&[0; 10000], 6), 100).is_err());
+    }
+}
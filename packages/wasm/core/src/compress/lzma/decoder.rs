@@ -0,0 +1,322 @@
+//! LZMA range decoder and literal/match decoding
+
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+const PROB_INIT: u16 = 1024;
+const NUM_STATES: usize = 12;
+const POS_STATES_MAX: usize = 16;
+const END_MARKER: u32 = u32::MAX;
+
+/// Binary range decoder over one compressed chunk
+pub struct RangeDecoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    range: u32,
+    code: u32,
+}
+
+impl<'a> RangeDecoder<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, String> {
+        if data.len() < 5 || data[0] != 0 {
+            return Err("Invalid LZMA range coder header".to_string());
+        }
+        Ok(RangeDecoder {
+            data,
+            pos: 5,
+            range: u32::MAX,
+            code: u32::from_be_bytes([data[1], data[2], data[3], data[4]]),
+        })
+    }
+
+    #[inline]
+    fn normalize(&mut self) {
+        if self.range < 1 << 24 {
+            self.range <<= 8;
+            self.code = (self.code << 8) | self.data.get(self.pos).copied().unwrap_or(0) as u32;
+            self.pos += 1;
+        }
+    }
+
+    #[inline]
+    fn bit(&mut self, prob: &mut u16) -> u32 {
+        self.normalize();
+        let bound = (self.range >> 11) * *prob as u32;
+        if self.code < bound {
+            self.range = bound;
+            *prob += (2048 - *prob) >> 5;
+            0
+        } else {
+            self.range -= bound;
+            self.code -= bound;
+            *prob -= *prob >> 5;
+            1
+        }
+    }
+
+    fn bit_tree(&mut self, probs: &mut [u16], bits: u32) -> u32 {
+        let mut m = 1;
+        for _ in 0..bits {
+            m = (m << 1) | self.bit(&mut probs[m]) as usize;
+        }
+        m as u32 - (1 << bits)
+    }
+
+    fn reverse_tree(&mut self, probs: &mut [u16], bits: u32) -> u32 {
+        let mut m = 1;
+        let mut symbol = 0;
+        for i in 0..bits {
+            let bit = self.bit(&mut probs[m]);
+            m = (m << 1) | bit as usize;
+            symbol |= bit << i;
+        }
+        symbol
+    }
+
+    fn direct(&mut self, bits: u32) -> u32 {
+        let mut value = 0;
+        for _ in 0..bits {
+            self.normalize();
+            self.range >>= 1;
+            let bit = (self.code >= self.range) as u32;
+            if bit == 1 {
+                self.code -= self.range;
+            }
+            value = (value << 1) | bit;
+        }
+        value
+    }
+
+    /// True if decoding read past the end of the input
+    pub fn overrun(&self) -> bool {
+        self.pos > self.data.len()
+    }
+}
+
+#[derive(Clone)]
+struct LenDecoder {
+    choice: u16,
+    choice2: u16,
+    low: [[u16; 8]; POS_STATES_MAX],
+    mid: [[u16; 8]; POS_STATES_MAX],
+    high: [u16; 256],
+}
+
+impl LenDecoder {
+    fn new() -> Self {
+        LenDecoder {
+            choice: PROB_INIT,
+            choice2: PROB_INIT,
+            low: [[PROB_INIT; 8]; POS_STATES_MAX],
+            mid: [[PROB_INIT; 8]; POS_STATES_MAX],
+            high: [PROB_INIT; 256],
+        }
+    }
+
+    /// Match length minus two
+    fn decode(&mut self, rc: &mut RangeDecoder, pos_state: usize) -> u32 {
+        if rc.bit(&mut self.choice) == 0 {
+            rc.bit_tree(&mut self.low[pos_state], 3)
+        } else if rc.bit(&mut self.choice2) == 0 {
+            8 + rc.bit_tree(&mut self.mid[pos_state], 3)
+        } else {
+            16 + rc.bit_tree(&mut self.high, 8)
+        }
+    }
+}
+
+/// Literal context (lc), literal position (lp) and position (pb) bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LzmaProperties {
+    pub lc: u32,
+    pub lp: u32,
+    pub pb: u32,
+}
+
+impl LzmaProperties {
+    /// Decode the packed `(pb * 5 + lp) * 9 + lc` properties byte
+    pub fn from_byte(byte: u8) -> Result<Self, String> {
+        if byte >= 9 * 5 * 5 {
+            return Err("Invalid LZMA properties".to_string());
+        }
+        let b = byte as u32;
+        Ok(LzmaProperties {
+            lc: b % 9,
+            lp: (b / 9) % 5,
+            pb: b / 45,
+        })
+    }
+}
+
+/// LZMA decoder state; output doubles as the dictionary
+pub struct LzmaDecoder {
+    props: LzmaProperties,
+    is_match: [u16; NUM_STATES * POS_STATES_MAX],
+    is_rep: [u16; NUM_STATES],
+    is_rep0: [u16; NUM_STATES],
+    is_rep1: [u16; NUM_STATES],
+    is_rep2: [u16; NUM_STATES],
+    is_rep0_long: [u16; NUM_STATES * POS_STATES_MAX],
+    literal: Vec<u16>,
+    dist_slot: [[u16; 64]; 4],
+    dist_special: [u16; 115],
+    align: [u16; 16],
+    len: LenDecoder,
+    rep_len: LenDecoder,
+    state: usize,
+    reps: [u32; 4],
+}
+
+impl LzmaDecoder {
+    pub fn new(props: LzmaProperties) -> Self {
+        LzmaDecoder {
+            props,
+            is_match: [PROB_INIT; NUM_STATES * POS_STATES_MAX],
+            is_rep: [PROB_INIT; NUM_STATES],
+            is_rep0: [PROB_INIT; NUM_STATES],
+            is_rep1: [PROB_INIT; NUM_STATES],
+            is_rep2: [PROB_INIT; NUM_STATES],
+            is_rep0_long: [PROB_INIT; NUM_STATES * POS_STATES_MAX],
+            literal: vec![PROB_INIT; 0x300 << (props.lc + props.lp)],
+            dist_slot: [[PROB_INIT; 64]; 4],
+            dist_special: [PROB_INIT; 115],
+            align: [PROB_INIT; 16],
+            len: LenDecoder::new(),
+            rep_len: LenDecoder::new(),
+            state: 0,
+            reps: [0; 4],
+        }
+    }
+
+    /// Reset probabilities and state, optionally switching properties
+    pub fn reset(&mut self, props: Option<LzmaProperties>) {
+        *self = LzmaDecoder::new(props.unwrap_or(self.props));
+    }
+
+    fn decode_literal(&mut self, rc: &mut RangeDecoder, out: &mut Vec<u8>, dict_start: usize) {
+        let LzmaProperties { lc, lp, .. } = self.props;
+        let pos = out.len() - dict_start;
+        let prev = if pos > 0 { out[out.len() - 1] as usize } else { 0 };
+        let ctx = ((pos & ((1 << lp) - 1)) << lc) + (prev >> (8 - lc));
+        let probs = &mut self.literal[0x300 * ctx..0x300 * (ctx + 1)];
+
+        let mut symbol = 1usize;
+        if self.state >= 7 {
+            // After a match, the byte at rep0 steers the first differing bits
+            let mut match_byte = out[out.len() - self.reps[0] as usize - 1] as usize;
+            while symbol < 0x100 {
+                let match_bit = (match_byte >> 7) & 1;
+                match_byte <<= 1;
+                let bit = rc.bit(&mut probs[((1 + match_bit) << 8) + symbol]) as usize;
+                symbol = (symbol << 1) | bit;
+                if match_bit != bit {
+                    break;
+                }
+            }
+        }
+        while symbol < 0x100 {
+            symbol = (symbol << 1) | rc.bit(&mut probs[symbol]) as usize;
+        }
+        out.push(symbol as u8);
+
+        self.state = match self.state {
+            0..=3 => 0,
+            4..=9 => self.state - 3,
+            _ => self.state - 6,
+        };
+    }
+
+    fn decode_distance(&mut self, rc: &mut RangeDecoder, len: u32) -> u32 {
+        let slot = rc.bit_tree(&mut self.dist_slot[len.min(3) as usize], 6);
+        if slot < 4 {
+            return slot;
+        }
+        let direct = (slot >> 1) - 1;
+        let base = (2 | (slot & 1)) << direct;
+        if slot < 14 {
+            base + rc.reverse_tree(&mut self.dist_special[(base - slot) as usize..], direct)
+        } else {
+            base.wrapping_add(rc.direct(direct - 4) << 4).wrapping_add(rc.reverse_tree(&mut self.align, 4))
+        }
+    }
+
+    /// Decode until `out` reaches `limit` bytes or an end marker is found
+    ///
+    /// Matches may not reach before `dict_start`. Returns true if the end
+    /// marker was seen.
+    pub fn decode(&mut self, rc: &mut RangeDecoder, out: &mut Vec<u8>, dict_start: usize, limit: usize) -> Result<bool, String> {
+        let pb_mask = (1usize << self.props.pb) - 1;
+        while out.len() < limit {
+            if rc.overrun() {
+                return Err("Truncated LZMA data".to_string());
+            }
+            let pos_state = (out.len() - dict_start) & pb_mask;
+            let s = self.state;
+
+            if rc.bit(&mut self.is_match[s * POS_STATES_MAX + pos_state]) == 0 {
+                self.decode_literal(rc, out, dict_start);
+                continue;
+            }
+
+            let len;
+            if rc.bit(&mut self.is_rep[s]) == 1 {
+                if out.len() == dict_start {
+                    return Err("LZMA repeat match before any data".to_string());
+                }
+                if rc.bit(&mut self.is_rep0[s]) == 0 {
+                    if rc.bit(&mut self.is_rep0_long[s * POS_STATES_MAX + pos_state]) == 0 {
+                        // Short rep: a single byte at rep0
+                        self.state = if s < 7 { 9 } else { 11 };
+                        out.push(out[out.len() - self.reps[0] as usize - 1]);
+                        continue;
+                    }
+                } else {
+                    let dist = if rc.bit(&mut self.is_rep1[s]) == 0 {
+                        self.reps[1]
+                    } else {
+                        let dist = if rc.bit(&mut self.is_rep2[s]) == 0 {
+                            self.reps[2]
+                        } else {
+                            let d = self.reps[3];
+                            self.reps[3] = self.reps[2];
+                            d
+                        };
+                        self.reps[2] = self.reps[1];
+                        dist
+                    };
+                    self.reps[1] = self.reps[0];
+                    self.reps[0] = dist;
+                }
+                len = self.rep_len.decode(rc, pos_state);
+                self.state = if s < 7 { 8 } else { 11 };
+            } else {
+                self.reps[3] = self.reps[2];
+                self.reps[2] = self.reps[1];
+                self.reps[1] = self.reps[0];
+                len = self.len.decode(rc, pos_state);
+                self.state = if s < 7 { 7 } else { 10 };
+                self.reps[0] = self.decode_distance(rc, len);
+                if self.reps[0] == END_MARKER {
+                    return Ok(true);
+                }
+            }
+
+            let distance = self.reps[0] as usize + 1;
+            if distance > out.len() - dict_start {
+                return Err("LZMA match distance out of range".to_string());
+            }
+            let count = (len as usize + 2).min(limit - out.len());
+            let start = out.len() - distance;
+            if distance >= count {
+                out.extend_from_within(start..start + count);
+            } else {
+                for k in 0..count {
+                    out.push(out[start + k]);
+                }
+            }
+        }
+        if rc.overrun() {
+            return Err("Truncated LZMA data".to_string());
+        }
+        Ok(false)
+    }
+}
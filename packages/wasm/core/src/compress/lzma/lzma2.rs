@@ -0,0 +1,77 @@
+//! LZMA2 chunk framing
+
+use super::decoder::{LzmaDecoder, LzmaProperties, RangeDecoder};
+use crate::compress::limit_error;
+use crate::limits::output_limit;
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
+/// Decode an LZMA2 stream, returning the output and bytes consumed, within
+/// the output byte limit set by `set_limits`
+pub fn lzma2_decode(data: &[u8]) -> Result<(Vec<u8>, usize), String> {
+    lzma2_decode_with_limit(data, output_limit())
+}
+
+/// Decode an LZMA2 stream, failing if the output would exceed `limit` bytes
+pub(crate) fn lzma2_decode_with_limit(data: &[u8], limit: usize) -> Result<(Vec<u8>, usize), String> {
+    let truncated = || "Truncated LZMA2 data".to_string();
+    let mut out = Vec::new();
+    let mut decoder: Option<LzmaDecoder> = None;
+    let mut dict_start = 0;
+    let mut pos = 0;
+
+    loop {
+        let control = *data.get(pos).ok_or_else(truncated)?;
+        pos += 1;
+        match control {
+            0x00 => return Ok((out, pos)),
+            0x01 | 0x02 => {
+                if control == 0x01 {
+                    dict_start = out.len();
+                }
+                let size = data.get(pos..pos + 2).ok_or_else(truncated)?;
+                let size = u16::from_be_bytes([size[0], size[1]]) as usize + 1;
+                if out.len() + size > limit {
+                    return Err(limit_error(limit));
+                }
+                out.extend_from_slice(data.get(pos + 2..pos + 2 + size).ok_or_else(truncated)?);
+                pos += 2 + size;
+            }
+            0x80..=0xFF => {
+                let header = data.get(pos..pos + 4).ok_or_else(truncated)?;
+                let unpacked = ((control as usize & 0x1F) << 16) + ((header[0] as usize) << 8) + header[1] as usize + 1;
+                let packed = ((header[2] as usize) << 8) + header[3] as usize + 1;
+                pos += 4;
+                if out.len() + unpacked > limit {
+                    return Err(limit_error(limit));
+                }
+
+                let reset = (control >> 5) & 3;
+                if reset == 3 {
+                    dict_start = out.len();
+                }
+                if reset >= 2 {
+                    let props = LzmaProperties::from_byte(*data.get(pos).ok_or_else(truncated)?)?;
+                    if props.lc + props.lp > 4 {
+                        return Err("Invalid LZMA2 properties".to_string());
+                    }
+                    pos += 1;
+                    match decoder.as_mut() {
+                        Some(d) => d.reset(Some(props)),
+                        None => decoder = Some(LzmaDecoder::new(props)),
+                    }
+                } else if reset == 1 {
+                    decoder.as_mut().ok_or("LZMA2 state reset without properties")?.reset(None);
+                }
+                let lzma = decoder.as_mut().ok_or("LZMA2 chunk without properties")?;
+
+                let mut rc = RangeDecoder::new(data.get(pos..pos + packed).ok_or_else(truncated)?)?;
+                let target = out.len() + unpacked;
+                if lzma.decode(&mut rc, &mut out, dict_start, target)? || out.len() != target {
+                    return Err("Unexpected end marker in LZMA2 chunk".to_string());
+                }
+                pos += packed;
+            }
+            _ => return Err(format!("Invalid LZMA2 control byte 0x{:02x}", control)),
+        }
+    }
+}
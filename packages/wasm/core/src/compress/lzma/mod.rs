@@ -0,0 +1,87 @@
+//! LZMA, LZMA2 and XZ decompression
+
+mod decoder;
+mod lzma2;
+#[cfg(feature = "std")]
+mod xz;
+
+pub use lzma2::lzma2_decode;
+#[cfg(feature = "archive")]
+pub(crate) use lzma2::lzma2_decode_with_limit;
+#[cfg(feature = "std")]
+pub use xz::xz_decompress;
+
+use crate::compress::limit_error;
+use crate::limits::output_limit;
+use alloc::{string::{String, ToString}, vec::Vec};
+use decoder::{LzmaDecoder, LzmaProperties, RangeDecoder};
+
+/// Decompress a legacy `.lzma` (LZMA-alone) file
+pub fn lzma_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 13 {
+        return Err("LZMA data too small".to_string());
+    }
+    let size = u64::from_le_bytes([data[5], data[6], data[7], data[8], data[9], data[10], data[11], data[12]]);
+    // All-ones size means unknown; the stream then ends with an end marker
+    lzma_decode_raw(&data[..5], &data[13..], (size != u64::MAX).then_some(size))
+}
+
+/// Decode a raw LZMA stream from its 5-byte properties, as embedded in ZIP and 7z
+///
+/// Without a known size the stream must end with an end marker. Either way
+/// the output stops at the byte limit set by `set_limits`.
+pub fn lzma_decode_raw(props: &[u8], data: &[u8], size: Option<u64>) -> Result<Vec<u8>, String> {
+    let props = LzmaProperties::from_byte(*props.first().ok_or("Missing LZMA properties")?)?;
+    let max = output_limit();
+    if size.is_some_and(|n| n > max as u64) {
+        return Err(limit_error(max));
+    }
+    let limit = size.map_or(max, |n| n as usize);
+    let mut out = Vec::with_capacity(limit.min(1 << 24));
+    let mut rc = RangeDecoder::new(data)?;
+    let ended = LzmaDecoder::new(props).decode(&mut rc, &mut out, 0, limit)?;
+    if !ended && size.is_none() {
+        return Err(if out.len() == max { limit_error(max) } else { "Missing LZMA end marker".to_string() });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn sample() -> String {
+        (0..30).map(|i| format!("xz stream {}, ", i % 4)).collect()
+    }
+
+    #[test]
+    fn test_xz_decompress() {
+        // `xz -6` output with a CRC-64 check
+        let xz = hex(concat!(
+            "fd377a585a000004e6d6b44604c02786032101160000000000000000e4764414e00185001f5d003c1e800632255de6a58335d52d36c5d9ffdd41306dfe88f9",
+            "6a6e6294dbef000000a924c966584960460001438603000000c3468a66b1c467fb020000000004595a"
+        ));
+        assert_eq!(xz_decompress(&xz).unwrap(), sample().as_bytes());
+
+        let mut two = xz.clone();
+        two.extend_from_slice(&[0; 4]);
+        two.extend_from_slice(&xz);
+        assert_eq!(xz_decompress(&two).unwrap(), sample().repeat(2).as_bytes());
+
+        let mut corrupt = xz.clone();
+        corrupt[60] ^= 0x10;
+        assert!(xz_decompress(&corrupt).is_err());
+    }
+
+    #[test]
+    fn test_lzma_alone_decompress() {
+        // `lzma -6` output: unknown size, terminated by an end marker
+        let lzma = hex("5d00008000ffffffffffffffff003c1e800632255de6a58335d52d36c5d9ffdd41306dfe88f96a6e62c007ceffffec520000");
+        assert_eq!(lzma_decompress(&lzma).unwrap(), sample().as_bytes());
+        assert!(lzma_decompress(&lzma[..30]).is_err());
+    }
+}
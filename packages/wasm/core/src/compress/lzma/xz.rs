@@ -0,0 +1,170 @@
+//! XZ container (stream header, blocks, index and footer)
+
+use super::lzma2::lzma2_decode_with_limit;
+use crate::checksum::{crc32, crc64};
+use crate::hash::sha256;
+use crate::compress::stream_limit_error;
+use crate::limits::output_limit;
+
+const HEADER_MAGIC: [u8; 6] = [0xFD, b'7', b'z', b'X', b'Z', 0x00];
+const FOOTER_MAGIC: [u8; 2] = [b'Y', b'Z'];
+
+const FILTER_DELTA: u64 = 0x03;
+const FILTER_LZMA2: u64 = 0x21;
+
+/// Read an XZ variable-length integer
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *data.get(*pos).ok_or("Truncated XZ integer")?;
+        *pos += 1;
+        value |= ((byte & 0x7F) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("XZ integer too long".to_string())
+}
+
+/// Size in bytes of the integrity check for a check type
+fn check_size(check: u8) -> usize {
+    match check {
+        0 => 0,
+        1..=3 => 4,
+        4..=6 => 8,
+        7..=9 => 16,
+        10..=12 => 32,
+        _ => 64,
+    }
+}
+
+fn verify_check(check: u8, data: &[u8], expected: &[u8]) -> Result<(), String> {
+    let ok = match check {
+        1 => crc32(data).to_le_bytes() == expected,
+        4 => crc64(data).to_le_bytes() == expected,
+        10 => sha256(data) == expected,
+        // Reserved check types are not verified
+        _ => true,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err("XZ block check mismatch".to_string())
+    }
+}
+
+/// Decode one block at `data[pos]` of at most `limit` bytes, returning its
+/// output and advancing `pos`
+fn decode_block(data: &[u8], pos: &mut usize, check: u8, limit: usize) -> Result<Vec<u8>, String> {
+    let start = *pos;
+    let header_size = (data[start] as usize + 1) * 4;
+    let header = data.get(start..start + header_size).ok_or("Truncated XZ block header")?;
+    let stored_crc = u32::from_le_bytes([header[header_size - 4], header[header_size - 3], header[header_size - 2], header[header_size - 1]]);
+    if crc32(&header[..header_size - 4]) != stored_crc {
+        return Err("XZ block header checksum mismatch".to_string());
+    }
+
+    let flags = header[1];
+    let mut hp = 2;
+    let compressed_size = if flags & 0x40 != 0 { Some(read_varint(header, &mut hp)?) } else { None };
+    let uncompressed_size = if flags & 0x80 != 0 { Some(read_varint(header, &mut hp)?) } else { None };
+    let mut filters = Vec::new();
+    for _ in 0..(flags & 3) + 1 {
+        let id = read_varint(header, &mut hp)?;
+        let size = read_varint(header, &mut hp)? as usize;
+        let props = header.get(hp..hp + size).ok_or("Truncated XZ filter properties")?;
+        hp += size;
+        filters.push((id, props));
+    }
+
+    let (last_id, _) = filters[filters.len() - 1];
+    if last_id != FILTER_LZMA2 {
+        return Err(format!("Unsupported XZ filter 0x{:02x}", last_id));
+    }
+    let body_start = start + header_size;
+    let (mut output, consumed) = lzma2_decode_with_limit(&data[body_start..], limit)?;
+    if compressed_size.is_some_and(|n| n != consumed as u64) || uncompressed_size.is_some_and(|n| n != output.len() as u64) {
+        return Err("XZ block size mismatch".to_string());
+    }
+
+    // Earlier filters in the chain are undone after LZMA2, last to first
+    for &(id, props) in filters[..filters.len() - 1].iter().rev() {
+        match id {
+            FILTER_DELTA => {
+                let distance = *props.first().ok_or("Missing delta filter distance")? as usize + 1;
+                for i in distance..output.len() {
+                    output[i] = output[i].wrapping_add(output[i - distance]);
+                }
+            }
+            _ => return Err(format!("Unsupported XZ filter 0x{:02x}", id)),
+        }
+    }
+
+    let check_start = (body_start + consumed).next_multiple_of(4);
+    let size = check_size(check);
+    let expected = data.get(check_start..check_start + size).ok_or("Truncated XZ block check")?;
+    verify_check(check, &output, expected)?;
+    *pos = check_start + size;
+    Ok(output)
+}
+
+/// Decompress an XZ file, concatenating all streams, within the output byte
+/// limit set by `set_limits`
+pub fn xz_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let limit = output_limit();
+    let mut out = Vec::new();
+    let mut pos = 0;
+    loop {
+        let header = data.get(pos..pos + 12).ok_or("XZ data too small")?;
+        if header[..6] != HEADER_MAGIC {
+            return Err("Invalid XZ signature".to_string());
+        }
+        if crc32(&header[6..8]) != u32::from_le_bytes([header[8], header[9], header[10], header[11]]) {
+            return Err("XZ stream header checksum mismatch".to_string());
+        }
+        let check = header[7] & 0x0F;
+        pos += 12;
+
+        let mut sizes = Vec::new();
+        while *data.get(pos).ok_or("Truncated XZ stream")? != 0 {
+            let left = limit - out.len();
+            let block = decode_block(data, &mut pos, check, left).map_err(|e| stream_limit_error(e, left, limit))?;
+            sizes.push(block.len() as u64);
+            out.extend_from_slice(&block);
+        }
+
+        // Index: must agree with the blocks actually decoded
+        let index_start = pos;
+        pos += 1;
+        let count = read_varint(data, &mut pos)? as usize;
+        if count != sizes.len() {
+            return Err("XZ index does not match blocks".to_string());
+        }
+        for &size in &sizes {
+            read_varint(data, &mut pos)?; // unpadded size
+            if read_varint(data, &mut pos)? != size {
+                return Err("XZ index does not match blocks".to_string());
+            }
+        }
+        pos = pos.next_multiple_of(4);
+        let index_crc = data.get(pos..pos + 4).ok_or("Truncated XZ index")?;
+        if crc32(&data[index_start..pos]) != u32::from_le_bytes([index_crc[0], index_crc[1], index_crc[2], index_crc[3]]) {
+            return Err("XZ index checksum mismatch".to_string());
+        }
+        pos += 4;
+
+        let footer = data.get(pos..pos + 12).ok_or("Truncated XZ stream footer")?;
+        if footer[10..] != FOOTER_MAGIC || footer[8..10] != header[6..8] {
+            return Err("Invalid XZ stream footer".to_string());
+        }
+        pos += 12;
+
+        // Stream padding, then possibly another stream
+        while data.get(pos..pos + 4) == Some(&[0, 0, 0, 0][..]) {
+            pos += 4;
+        }
+        if pos >= data.len() {
+            return Ok(out);
+        }
+    }
+}
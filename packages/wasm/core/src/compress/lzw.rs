@@ -0,0 +1,306 @@
+//! LZW with the variants used by GIF, TIFF and PDF
+
+use super::bits::{BitReader, BitWriter, MsbBitReader, MsbBitWriter};
+use crate::pool;
+use std::collections::HashMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Packing order of codes within bytes
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Least significant bit first (GIF)
+    Lsb = 0,
+    /// Most significant bit first (TIFF, PDF)
+    Msb = 1,
+}
+
+/// LZW code parameters
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LzwOptions {
+    /// Bits per input symbol; codes start one bit wider (GIF: 2-8, TIFF: 8)
+    pub min_code_size: u8,
+    /// Largest code width (12 for GIF and TIFF, at most 16)
+    pub max_code_size: u8,
+    pub bit_order: BitOrder,
+    /// Widen codes one code early, as TIFF and PDF encoders do
+    pub early_change: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl LzwOptions {
+    /// TIFF-style options: 8-bit symbols, MSB-first, early change
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> LzwOptions {
+        LzwOptions::tiff()
+    }
+
+    /// GIF-style options for the given minimum code size
+    pub fn gif(min_code_size: u8) -> LzwOptions {
+        LzwOptions {
+            min_code_size,
+            max_code_size: 12,
+            bit_order: BitOrder::Lsb,
+            early_change: false,
+        }
+    }
+
+    pub fn tiff() -> LzwOptions {
+        LzwOptions {
+            min_code_size: 8,
+            max_code_size: 12,
+            bit_order: BitOrder::Msb,
+            early_change: true,
+        }
+    }
+}
+
+impl Default for LzwOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LzwOptions {
+    fn validate(&self) -> Result<(), String> {
+        if self.min_code_size == 0 || self.min_code_size >= self.max_code_size || self.max_code_size > 16 {
+            return Err(format!("Invalid LZW code sizes {}..{}", self.min_code_size, self.max_code_size));
+        }
+        Ok(())
+    }
+
+    fn clear_code(&self) -> u32 {
+        1 << self.min_code_size
+    }
+
+    /// Code width to use once `next_code` codes are assigned
+    fn width_for(&self, next_code: u32, width: u32) -> u32 {
+        if next_code + self.early_change as u32 >= 1 << width && width < self.max_code_size as u32 {
+            width + 1
+        } else {
+            width
+        }
+    }
+}
+
+enum CodeWriter {
+    Lsb(BitWriter),
+    Msb(MsbBitWriter),
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u32, width: u32) {
+        match self {
+            CodeWriter::Lsb(w) => w.write(code, width),
+            CodeWriter::Msb(w) => w.write(code, width),
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        match self {
+            CodeWriter::Lsb(w) => w.finish(),
+            CodeWriter::Msb(w) => w.finish(),
+        }
+    }
+}
+
+/// Compress symbols, starting with a clear code and ending with end-of-information
+pub fn lzw_compress(data: &[u8], options: &LzwOptions) -> Result<Vec<u8>, String> {
+    options.validate()?;
+    let clear = options.clear_code();
+    let end = clear + 1;
+    let limit = 1u32 << options.max_code_size;
+    if let Some(&b) = data.iter().find(|&&b| b as u32 >= clear) {
+        return Err(format!("Symbol {} exceeds {}-bit code size", b, options.min_code_size));
+    }
+
+    let mut writer = match options.bit_order {
+        BitOrder::Lsb => CodeWriter::Lsb(BitWriter::new()),
+        BitOrder::Msb => CodeWriter::Msb(MsbBitWriter::new()),
+    };
+    let initial_width = options.min_code_size as u32 + 1;
+    let mut width = initial_width;
+    let mut next_code = end + 1;
+    let mut table: HashMap<(u32, u8), u32> = HashMap::new();
+    writer.write(clear, width);
+
+    let Some((&first, rest)) = data.split_first() else {
+        writer.write(end, width);
+        return Ok(writer.finish());
+    };
+    let mut current = first as u32;
+    // No code has followed the last clear yet, so the decoder adds no entry for `current`
+    let mut fresh = true;
+    for &b in rest {
+        if let Some(&code) = table.get(&(current, b)) {
+            current = code;
+            continue;
+        }
+        writer.write(current, width);
+        if next_code < limit {
+            table.insert((current, b), next_code);
+            next_code += 1;
+            // The decoder assigns each code one step later, hence `next_code - 1`
+            if !fresh {
+                width = options.width_for(next_code - 1, width);
+            }
+            fresh = false;
+        } else {
+            writer.write(clear, width);
+            table.clear();
+            next_code = end + 1;
+            width = initial_width;
+            fresh = true;
+        }
+        current = b as u32;
+    }
+    writer.write(current, width);
+    if !fresh {
+        width = options.width_for(next_code, width);
+    }
+    writer.write(end, width);
+    Ok(writer.finish())
+}
+
+enum CodeReader<'a> {
+    Lsb(BitReader<'a>),
+    Msb(MsbBitReader<'a>),
+}
+
+impl CodeReader<'_> {
+    fn read(&mut self, width: u32) -> Option<u32> {
+        match self {
+            CodeReader::Lsb(r) => r.bits(width).ok(),
+            CodeReader::Msb(r) => r.bits(width).ok(),
+        }
+    }
+}
+
+/// Decompress LZW codes until end-of-information or the end of the data
+pub fn lzw_decompress(data: &[u8], options: &LzwOptions) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    lzw_decompress_into(data, options, &mut out)?;
+    Ok(out)
+}
+
+/// `lzw_decompress`, appending to `out`, e.g. a pooled buffer
+pub(crate) fn lzw_decompress_into(data: &[u8], options: &LzwOptions, out: &mut Vec<u8>) -> Result<(), String> {
+    options.validate()?;
+    let clear = options.clear_code();
+    let end = clear + 1;
+    let limit = 1usize << options.max_code_size;
+
+    let mut reader = match options.bit_order {
+        BitOrder::Lsb => CodeReader::Lsb(BitReader::new(data)),
+        BitOrder::Msb => CodeReader::Msb(MsbBitReader::new(data)),
+    };
+    // Each code is its prefix code plus one final byte
+    let mut prefix = pool::take(limit, 0u16);
+    let mut suffix = pool::take(limit, 0u8);
+    let mut first = pool::take(limit, 0u8);
+    for code in 0..clear as usize {
+        suffix[code] = code as u8;
+        first[code] = code as u8;
+    }
+
+    let initial_width = options.min_code_size as u32 + 1;
+    let mut width = initial_width;
+    let mut next_code = end + 1;
+    let mut previous: Option<u32> = None;
+    let mut string = pool::take_empty(limit);
+
+    // Streams truncated before end-of-information keep what was decoded
+    while let Some(code) = reader.read(width) {
+        if code == clear {
+            width = initial_width;
+            next_code = end + 1;
+            previous = None;
+            continue;
+        }
+        if code == end {
+            break;
+        }
+
+        let Some(prev) = previous else {
+            if code >= clear {
+                return Err(format!("Invalid LZW code {}", code));
+            }
+            out.push(code as u8);
+            previous = Some(code);
+            continue;
+        };
+
+        if code > next_code || (code == next_code && next_code as usize >= limit) {
+            return Err(format!("Invalid LZW code {}", code));
+        }
+        // A code not yet in the table must be prev + first(prev)
+        let known = if code < next_code { code } else { prev };
+        string.clear();
+        let mut c = known as usize;
+        while c > end as usize {
+            string.push(suffix[c]);
+            c = prefix[c] as usize;
+        }
+        string.push(c as u8);
+        string.reverse();
+        if code == next_code {
+            string.push(first[prev as usize]);
+        }
+        out.extend_from_slice(&string);
+
+        if (next_code as usize) < limit {
+            let n = next_code as usize;
+            prefix[n] = prev as u16;
+            suffix[n] = string[0];
+            first[n] = first[prev as usize];
+            next_code += 1;
+            width = options.width_for(next_code, width);
+        }
+        previous = Some(code);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_variants() {
+        let text = b"TOBEORNOTTOBEORTOBEORNOT#".repeat(400);
+        let mut noise: Vec<u8> = (0..20000u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect();
+        noise.extend_from_slice(&text);
+        for options in [LzwOptions::tiff(), LzwOptions::gif(8)] {
+            for data in [&text[..], &noise[..], b"", b"a"] {
+                let compressed = lzw_compress(data, &options).unwrap();
+                assert_eq!(lzw_decompress(&compressed, &options).unwrap(), data);
+            }
+        }
+
+        let pixels: Vec<u8> = (0..5000).map(|i| ((i / 7) % 4) as u8).collect();
+        let gif = LzwOptions::gif(2);
+        assert_eq!(lzw_decompress(&lzw_compress(&pixels, &gif).unwrap(), &gif).unwrap(), pixels);
+        let bilevel = LzwOptions::gif(1);
+        for data in [&[1u8][..], &[0, 1, 1, 0, 1]] {
+            assert_eq!(lzw_decompress(&lzw_compress(data, &bilevel).unwrap(), &bilevel).unwrap(), data);
+        }
+        assert!(lzw_compress(&[4], &gif).is_err());
+    }
+
+    #[test]
+    fn test_known_gif_stream() {
+        // 10x10 sample image data from the GIF89a specification walkthrough
+        let data = [
+            0x8C, 0x2D, 0x99, 0x87, 0x2A, 0x1C, 0xDC, 0x33, 0xA0, 0x02, 0x75, 0xEC, 0x95, 0xFA, 0xA8, 0xDE, 0x60, 0x8C, 0x04, 0x91, 0x4C, 0x01,
+        ];
+        let pixels = lzw_decompress(&data, &LzwOptions::gif(2)).unwrap();
+        let expected = [
+            1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 0, 0, 0, 0, 2, 2, 2, 1, 1, 1, 0, 0,
+            0, 0, 2, 2, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1, 1, 2, 2, 2, 0, 0, 0, 0, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1,
+            2, 2, 2, 2, 2, 1, 1, 1, 1, 1,
+        ];
+        assert_eq!(pixels, expected);
+    }
+}
@@ -0,0 +1,201 @@
+//! General-purpose compression formats
+
+pub(crate) mod bits;
+pub mod bzip2;
+pub mod deflate;
+pub mod lzma;
+#[cfg(feature = "std")]
+pub mod lzw;
+pub mod primitives;
+pub mod snappy;
+pub mod zlib;
+pub mod zstd;
+
+pub use bzip2::bzip2_decompress;
+pub use deflate::{deflate, inflate};
+pub use lzma::{lzma2_decode, lzma_decode_raw, lzma_decompress};
+#[cfg(feature = "std")]
+pub use lzma::xz_decompress;
+#[cfg(feature = "std")]
+pub use lzw::{lzw_compress, lzw_decompress, BitOrder, LzwOptions};
+pub use primitives::{packbits_decode, packbits_encode, rle8_decode, rle8_encode};
+pub use snappy::{snappy_compress, snappy_decompress, snappy_frame_compress, snappy_frame_decompress};
+pub use zlib::{gzip_compress, gzip_decompress, gzip_decompress_with_limit, zlib_compress, zlib_decompress, zlib_decompress_with_limit};
+pub use zstd::{zstd_decompress, zstd_decompress_with_dict};
+
+use alloc::{format, string::String};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The error for output that would pass `limit` bytes
+pub(crate) fn limit_error(limit: usize) -> String {
+    format!("Decompressed size exceeds limit of {} bytes", limit)
+}
+
+/// Report a later member or frame, decoded with only `left` of the stream's
+/// `limit` bytes, running out as the whole stream passing `limit`
+pub(crate) fn stream_limit_error(error: String, left: usize, limit: usize) -> String {
+    if error == limit_error(left) {
+        limit_error(limit)
+    } else {
+        error
+    }
+}
+
+/// Decompress a raw DEFLATE stream
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = inflate)]
+pub fn inflate_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    inflate(data).map_err(|e| JsError::new(&e))
+}
+
+/// Compress to a raw DEFLATE stream (level 0-9)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = deflate)]
+pub fn deflate_js(data: &[u8], level: u8) -> Vec<u8> {
+    deflate(data, level)
+}
+
+/// Compress to a zlib stream (level 0-9)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = zlibCompress)]
+pub fn zlib_compress_js(data: &[u8], level: u8) -> Vec<u8> {
+    zlib_compress(data, level)
+}
+
+/// Decompress a zlib stream
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = zlibDecompress)]
+pub fn zlib_decompress_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    zlib_decompress(data).map_err(|e| JsError::new(&e))
+}
+
+/// Compress to a gzip stream (level 0-9)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = gzipCompress)]
+pub fn gzip_compress_js(data: &[u8], level: u8) -> Vec<u8> {
+    gzip_compress(data, level)
+}
+
+/// Decompress a gzip stream (multi-member streams are concatenated)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = gzipDecompress)]
+pub fn gzip_decompress_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    gzip_decompress(data).map_err(|e| JsError::new(&e))
+}
+
+/// Decompress bzip2 data
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = bzip2Decompress)]
+pub fn bzip2_decompress_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    bzip2_decompress(data).map_err(|e| JsError::new(&e))
+}
+
+/// Decompress a legacy `.lzma` (LZMA-alone) file
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = lzmaDecompress)]
+pub fn lzma_decompress_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    lzma_decompress(data).map_err(|e| JsError::new(&e))
+}
+
+/// Decompress an XZ file
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = xzDecompress)]
+pub fn xz_decompress_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    xz_decompress(data).map_err(|e| JsError::new(&e))
+}
+
+/// LZW-compress symbols with GIF, TIFF or custom parameters
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = lzwCompress)]
+pub fn lzw_compress_js(data: &[u8], options: &LzwOptions) -> Result<Vec<u8>, JsError> {
+    lzw_compress(data, options).map_err(|e| JsError::new(&e))
+}
+
+/// Decompress LZW codes with GIF, TIFF or custom parameters
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = lzwDecompress)]
+pub fn lzw_decompress_js(data: &[u8], options: &LzwOptions) -> Result<Vec<u8>, JsError> {
+    lzw_decompress(data, options).map_err(|e| JsError::new(&e))
+}
+
+/// Decode PackBits run-length data
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = packbitsDecode)]
+pub fn packbits_decode_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    packbits_decode(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode data with PackBits
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = packbitsEncode)]
+pub fn packbits_encode_js(data: &[u8]) -> Vec<u8> {
+    packbits_encode(data)
+}
+
+/// Decode BMP RLE8 data to palette indices
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = rle8Decode)]
+pub fn rle8_decode_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    rle8_decode(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Encode palette indices as BMP RLE8
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = rle8Encode)]
+pub fn rle8_encode_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    rle8_encode(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Length-limited Huffman code lengths for symbol frequencies
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = huffmanCodeLengths)]
+pub fn huffman_code_lengths_js(freq: &[u32], max_bits: u8) -> Result<Vec<u8>, JsError> {
+    let used = freq.iter().filter(|&&f| f > 0).count().max(2);
+    if !(1..=16).contains(&max_bits) || used > 1 << max_bits || freq.len() < 2 {
+        return Err(JsError::new("Cannot build a code with that many symbols in the given length limit"));
+    }
+    Ok(primitives::code_lengths(freq, max_bits as usize))
+}
+
+/// Compress to the raw Snappy format
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = snappyCompress)]
+pub fn snappy_compress_js(data: &[u8]) -> Vec<u8> {
+    snappy_compress(data)
+}
+
+/// Decompress the raw Snappy format
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = snappyDecompress)]
+pub fn snappy_decompress_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    snappy_decompress(data).map_err(|e| JsError::new(&e))
+}
+
+/// Compress to the Snappy framing format
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = snappyFrameCompress)]
+pub fn snappy_frame_compress_js(data: &[u8]) -> Vec<u8> {
+    snappy_frame_compress(data)
+}
+
+/// Decompress the Snappy framing format
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = snappyFrameDecompress)]
+pub fn snappy_frame_decompress_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    snappy_frame_decompress(data).map_err(|e| JsError::new(&e))
+}
+
+/// Decompress a Zstandard stream
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = zstdDecompress)]
+pub fn zstd_decompress_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    zstd_decompress(data).map_err(|e| JsError::new(&e))
+}
+
+/// Decompress a Zstandard stream using a raw-content or formatted dictionary
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = zstdDecompressWithDict)]
+pub fn zstd_decompress_with_dict_js(data: &[u8], dict: &[u8]) -> Result<Vec<u8>, JsError> {
+    zstd_decompress_with_dict(data, dict).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,194 @@
+//! Canonical Huffman code construction and LSB-first decoding
+
+use crate::compress::bits::BitReader;
+use alloc::collections::BinaryHeap;
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+use core::cmp::Reverse;
+
+/// Length-limited Huffman code lengths for the given symbol frequencies
+///
+/// Unused symbols get length 0. At least two symbols always receive a code
+/// so the result is a complete prefix code even for degenerate input.
+pub fn code_lengths(freq: &[u32], max_bits: usize) -> Vec<u8> {
+    let mut lengths = vec![0u8; freq.len()];
+    let used: Vec<usize> = (0..freq.len()).filter(|&i| freq[i] > 0).collect();
+    if used.len() < 2 {
+        let first = used.first().copied().unwrap_or(0);
+        lengths[first] = 1;
+        lengths[if first == 0 { 1 } else { 0 }] = 1;
+        return lengths;
+    }
+
+    // Build the tree: leaves are 0..used.len(), internal nodes follow
+    let mut children: Vec<(usize, usize)> = Vec::with_capacity(used.len());
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = used.iter().enumerate().map(|(i, &s)| Reverse((freq[s] as u64, i))).collect();
+    while heap.len() > 1 {
+        let Reverse((fa, a)) = heap.pop().unwrap();
+        let Reverse((fb, b)) = heap.pop().unwrap();
+        children.push((a, b));
+        heap.push(Reverse((fa + fb, used.len() + children.len() - 1)));
+    }
+
+    // Leaf depths
+    let mut depth = vec![0usize; used.len()];
+    let mut stack = vec![(used.len() + children.len() - 1, 0usize)];
+    while let Some((node, d)) = stack.pop() {
+        if node < used.len() {
+            depth[node] = d;
+        } else {
+            let (a, b) = children[node - used.len()];
+            stack.push((a, d + 1));
+            stack.push((b, d + 1));
+        }
+    }
+
+    // Limit code lengths (JPEG Annex K.3 adjustment of the length counts)
+    let max_depth = depth.iter().copied().max().unwrap_or(0).max(max_bits);
+    let mut bl_count = vec![0usize; max_depth + 1];
+    for &d in &depth {
+        bl_count[d] += 1;
+    }
+    for i in (max_bits + 1..=max_depth).rev() {
+        while bl_count[i] > 0 {
+            let mut j = i - 2;
+            while bl_count[j] == 0 {
+                j -= 1;
+            }
+            bl_count[i] -= 2;
+            bl_count[i - 1] += 1;
+            bl_count[j + 1] += 2;
+            bl_count[j] -= 1;
+        }
+    }
+
+    // Most frequent symbols get the shortest codes
+    let mut order = used;
+    order.sort_by_key(|&s| (Reverse(freq[s]), s));
+    let mut symbols = order.into_iter();
+    for (len, &count) in bl_count.iter().enumerate().take(max_bits + 1).skip(1) {
+        for s in symbols.by_ref().take(count) {
+            lengths[s] = len as u8;
+        }
+    }
+    lengths
+}
+
+/// Canonical codes for the given lengths (RFC 1951 3.2.2), MSB-first
+///
+/// Shorter codes sort first and codes of equal length follow symbol order.
+pub fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut bl_count = [0u16; 17];
+    for &l in lengths {
+        bl_count[l as usize] += 1;
+    }
+    bl_count[0] = 0;
+    let mut next = [0u16; 17];
+    let mut code = 0u16;
+    for bits in 1..17 {
+        code = code.wrapping_add(bl_count[bits - 1]) << 1;
+        next[bits] = code;
+    }
+    lengths
+        .iter()
+        .map(|&l| {
+            if l == 0 {
+                return 0;
+            }
+            let c = next[l as usize];
+            next[l as usize] += 1;
+            c
+        })
+        .collect()
+}
+
+/// Reverse the low `len` bits of `code`, for writing MSB-first codes to an LSB-first stream
+#[inline]
+pub fn reverse_bits(code: u16, len: u8) -> u16 {
+    code.reverse_bits() >> (16 - len as u32)
+}
+
+/// Table-driven canonical Huffman decoder for LSB-first streams
+pub struct HuffmanDecoder {
+    /// Indexed by the next `max_len` stream bits: (symbol, code length)
+    table: Vec<(u16, u8)>,
+    max_len: u32,
+}
+
+impl HuffmanDecoder {
+    /// Build from code lengths; over-subscribed lengths are rejected
+    pub fn new(lengths: &[u8]) -> Result<HuffmanDecoder, String> {
+        let max_len = lengths.iter().copied().max().unwrap_or(0) as u32;
+        if max_len == 0 {
+            // An empty code is legal when unused (e.g. no distances)
+            return Ok(HuffmanDecoder { table: vec![(0, 0)], max_len: 0 });
+        }
+        if max_len > 16 {
+            return Err("Huffman code too long".to_string());
+        }
+
+        let mut left = 1i32;
+        for len in 1..=max_len {
+            left = (left << 1) - lengths.iter().filter(|&&l| l as u32 == len).count() as i32;
+            if left < 0 {
+                return Err("Invalid Huffman code lengths".to_string());
+            }
+        }
+
+        let codes = canonical_codes(lengths);
+        let mut table = vec![(0u16, 0u8); 1 << max_len];
+        for (symbol, (&len, &code)) in lengths.iter().zip(&codes).enumerate() {
+            if len == 0 {
+                continue;
+            }
+            let rev = reverse_bits(code, len) as usize;
+            for entry in table.iter_mut().skip(rev).step_by(1 << len) {
+                *entry = (symbol as u16, len);
+            }
+        }
+        Ok(HuffmanDecoder { table, max_len })
+    }
+
+    #[inline]
+    pub fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (symbol, len) = self.table[reader.peek(self.max_len) as usize];
+        if len == 0 {
+            return Err("Invalid Huffman code".to_string());
+        }
+        reader.consume(len as u32)?;
+        Ok(symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress::bits::BitWriter;
+
+    #[test]
+    fn test_code_lengths_and_roundtrip() {
+        let freq = [1u32, 1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 0];
+        let lengths = code_lengths(&freq, 6);
+        assert!(lengths.iter().all(|&l| l <= 6));
+        assert_eq!(lengths[12], 0);
+        // Kraft equality: the code is complete
+        let kraft: f64 = lengths.iter().filter(|&&l| l > 0).map(|&l| 0.5f64.powi(l as i32)).sum();
+        assert!((kraft - 1.0).abs() < 1e-9);
+
+        let codes = canonical_codes(&lengths);
+        let mut writer = BitWriter::new();
+        let message = [11u16, 0, 5, 11, 1, 7];
+        for &s in &message {
+            let s = s as usize;
+            writer.write(reverse_bits(codes[s], lengths[s]) as u32, lengths[s] as u32);
+        }
+        let bytes = writer.finish();
+        let decoder = HuffmanDecoder::new(&lengths).unwrap();
+        let mut reader = BitReader::new(&bytes);
+        for &s in &message {
+            assert_eq!(decoder.decode(&mut reader).unwrap(), s);
+        }
+
+        assert_eq!(code_lengths(&[0, 5, 0], 15), vec![1, 1, 0]);
+        assert!(HuffmanDecoder::new(&[1, 1, 1]).is_err());
+    }
+}
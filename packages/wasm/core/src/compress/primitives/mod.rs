@@ -0,0 +1,7 @@
+//! Entropy-coding building blocks shared by codecs
+
+mod huffman;
+mod rle;
+
+pub use huffman::{canonical_codes, code_lengths, reverse_bits, HuffmanDecoder};
+pub use rle::{packbits_decode, packbits_encode, rle8_decode, rle8_encode};
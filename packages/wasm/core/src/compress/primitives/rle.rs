@@ -0,0 +1,179 @@
+//! Run-length encodings: PackBits (TIFF, PSD, ICNS) and BMP RLE8
+
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+/// Decode PackBits data until the input is exhausted
+pub fn packbits_decode(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(data.len() * 2);
+    let mut pos = 0;
+    while pos < data.len() {
+        let header = data[pos] as i8;
+        pos += 1;
+        match header {
+            0..=127 => {
+                let n = header as usize + 1;
+                out.extend_from_slice(data.get(pos..pos + n).ok_or("Truncated PackBits literal")?);
+                pos += n;
+            }
+            -127..=-1 => {
+                let byte = *data.get(pos).ok_or("Truncated PackBits run")?;
+                out.extend(core::iter::repeat_n(byte, (1 - header as isize) as usize));
+                pos += 1;
+            }
+            // -128 is a no-op
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Encode data with PackBits (runs of 2-128 bytes, literals of up to 128)
+pub fn packbits_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 128 + 1);
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..].iter().take(128).take_while(|&&b| b == data[i]).count();
+        if run >= 2 {
+            out.push((1 - run as isize) as u8);
+            out.push(data[i]);
+            i += run;
+            continue;
+        }
+        let start = i;
+        while i < data.len() && i - start < 128 && !(i + 1 < data.len() && data[i] == data[i + 1]) {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&data[start..i]);
+    }
+    out
+}
+
+/// Decode BMP RLE8 data to `width * height` palette indices in stream row order
+///
+/// Pixels skipped by delta escapes or early end-of-line are left as index 0.
+pub fn rle8_decode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let (w, h) = (width as usize, height as usize);
+    let mut out = vec![0u8; w * h];
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut pos = 0;
+    let truncated = || "Truncated RLE8 data".to_string();
+
+    while pos < data.len() && y < h {
+        let count = *data.get(pos).ok_or_else(truncated)? as usize;
+        let value = *data.get(pos + 1).ok_or_else(truncated)?;
+        pos += 2;
+        if count > 0 {
+            for _ in 0..count.min(w.saturating_sub(x)) {
+                out[y * w + x] = value;
+                x += 1;
+            }
+            continue;
+        }
+        match value {
+            0 => {
+                x = 0;
+                y += 1;
+            }
+            1 => break,
+            2 => {
+                let delta = data.get(pos..pos + 2).ok_or_else(truncated)?;
+                x += delta[0] as usize;
+                y += delta[1] as usize;
+                pos += 2;
+            }
+            n => {
+                let n = n as usize;
+                let literal = data.get(pos..pos + n).ok_or_else(truncated)?;
+                for &v in literal.iter().take(w.saturating_sub(x)) {
+                    out[y * w + x] = v;
+                    x += 1;
+                }
+                // Absolute runs are padded to a 16-bit boundary
+                pos += n + (n & 1);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Encode `width * height` palette indices (stream row order) as BMP RLE8
+pub fn rle8_encode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let (w, h) = (width as usize, height as usize);
+    if data.len() != w * h {
+        return Err(format!("Expected {} indices, got {}", w * h, data.len()));
+    }
+
+    if w == 0 || h == 0 {
+        return Ok(vec![0, 1]);
+    }
+
+    let mut out = Vec::with_capacity(data.len() / 2 + 2 * h);
+    for (y, row) in data.chunks(w).enumerate() {
+        let mut i = 0;
+        while i < row.len() {
+            let run = row[i..].iter().take(255).take_while(|&&b| b == row[i]).count();
+            if run >= 2 {
+                out.extend_from_slice(&[run as u8, row[i]]);
+                i += run;
+                continue;
+            }
+            // Gather a literal up to the next run
+            let start = i;
+            while i < row.len() && i - start < 255 && !(i + 1 < row.len() && row[i] == row[i + 1]) {
+                i += 1;
+            }
+            let literal = &row[start..i];
+            if literal.len() < 3 {
+                // Absolute mode needs at least three bytes
+                for &v in literal {
+                    out.extend_from_slice(&[1, v]);
+                }
+            } else {
+                out.extend_from_slice(&[0, literal.len() as u8]);
+                out.extend_from_slice(literal);
+                if literal.len() % 2 == 1 {
+                    out.push(0);
+                }
+            }
+        }
+        out.extend_from_slice(if y + 1 == h { &[0, 1] } else { &[0, 0] });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packbits() {
+        // Example from Apple Technical Note TN1023
+        let packed = [0xFE, 0xAA, 0x02, 0x80, 0x00, 0x2A, 0xFD, 0xAA, 0x03, 0x80, 0x00, 0x2A, 0x22, 0xF7, 0xAA];
+        let unpacked = [
+            0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0xAA, 0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0x22, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA,
+            0xAA, 0xAA, 0xAA,
+        ];
+        assert_eq!(packbits_decode(&packed).unwrap(), unpacked);
+        assert_eq!(packbits_decode(&packbits_encode(&unpacked)).unwrap(), unpacked);
+
+        let long: Vec<u8> = (0..1000u32).map(|i| if i % 300 < 200 { 7 } else { (i * 31) as u8 }).collect();
+        assert_eq!(packbits_decode(&packbits_encode(&long)).unwrap(), long);
+        assert!(packbits_decode(&[0x05, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_rle8() {
+        let (w, h) = (9u32, 3u32);
+        let indices: Vec<u8> = vec![1, 1, 1, 1, 2, 3, 4, 5, 5, 6, 7, 8, 6, 7, 8, 6, 7, 8, 0, 0, 0, 0, 0, 0, 0, 0, 9];
+        let encoded = rle8_encode(&indices, w, h).unwrap();
+        assert_eq!(&encoded[..4], &[4, 1, 0, 3]);
+        assert_eq!(rle8_decode(&encoded, w, h).unwrap(), indices);
+
+        // Delta escape skips pixels, leaving them at index 0
+        let delta = [2, 5, 0, 2, 3, 1, 1, 6, 0, 1];
+        let decoded = rle8_decode(&delta, 8, 2).unwrap();
+        assert_eq!(&decoded[..2], &[5, 5]);
+        assert_eq!(decoded[8 + 5], 6);
+    }
+}
@@ -0,0 +1,289 @@
+//! Snappy compression, raw and framed
+
+use super::limit_error;
+use crate::checksum::crc32c;
+use crate::limits::output_limit;
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+/// Snappy never matches across 64 KiB fragments; the framing format uses the same chunk size
+const FRAGMENT_SIZE: usize = 65536;
+const HASH_BITS: u32 = 14;
+const STREAM_IDENTIFIER: [u8; 10] = [0xFF, 0x06, 0x00, 0x00, b's', b'N', b'a', b'P', b'p', b'Y'];
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos).ok_or("Truncated Snappy length")?;
+        *pos += 1;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("Snappy length too long".to_string())
+}
+
+fn emit_literal(out: &mut Vec<u8>, literal: &[u8]) {
+    if literal.is_empty() {
+        return;
+    }
+    let n = literal.len() - 1;
+    if n < 60 {
+        out.push((n as u8) << 2);
+    } else {
+        let bytes = (64 - (n as u64).leading_zeros()).div_ceil(8) as usize;
+        out.push(((59 + bytes) as u8) << 2);
+        out.extend_from_slice(&(n as u32).to_le_bytes()[..bytes]);
+    }
+    out.extend_from_slice(literal);
+}
+
+fn emit_copy(out: &mut Vec<u8>, offset: usize, mut len: usize) {
+    // 2-byte-offset copies carry at most 64 bytes; keep the tail at least 4 long
+    while len >= 68 {
+        out.extend_from_slice(&[(63 << 2) | 2, offset as u8, (offset >> 8) as u8]);
+        len -= 64;
+    }
+    if len > 64 {
+        out.extend_from_slice(&[(59 << 2) | 2, offset as u8, (offset >> 8) as u8]);
+        len -= 60;
+    }
+    if len < 12 && offset < 2048 {
+        out.push(1 | (((len - 4) as u8) << 2) | (((offset >> 8) as u8) << 5));
+        out.push(offset as u8);
+    } else {
+        out.extend_from_slice(&[(((len - 1) as u8) << 2) | 2, offset as u8, (offset >> 8) as u8]);
+    }
+}
+
+#[inline]
+fn hash(data: &[u8], pos: usize) -> usize {
+    let v = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+    (v.wrapping_mul(0x1E35_A7BD) >> (32 - HASH_BITS)) as usize
+}
+
+fn compress_fragment(fragment: &[u8], table: &mut [u32], out: &mut Vec<u8>) {
+    table.fill(u32::MAX);
+    let mut literal_start = 0;
+    let mut i = 0;
+    let mut misses = 0;
+    while i + 4 <= fragment.len() {
+        let h = hash(fragment, i);
+        let candidate = table[h] as usize;
+        table[h] = i as u32;
+
+        if candidate < i && fragment[candidate..candidate + 4] == fragment[i..i + 4] {
+            emit_literal(out, &fragment[literal_start..i]);
+            let len = 4 + fragment[candidate + 4..].iter().zip(&fragment[i + 4..]).take_while(|(a, b)| a == b).count();
+            emit_copy(out, i - candidate, len);
+            i += len;
+            literal_start = i;
+            misses = 0;
+        } else {
+            // Skip ahead faster through incompressible data
+            misses += 1;
+            i += 1 + (misses >> 5);
+        }
+    }
+    emit_literal(out, &fragment[literal_start..]);
+}
+
+/// Compress to the raw Snappy format
+pub fn snappy_compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(32 + data.len() + data.len() / 6);
+    write_varint(&mut out, data.len() as u64);
+    let mut table = vec![u32::MAX; 1 << HASH_BITS];
+    for fragment in data.chunks(FRAGMENT_SIZE) {
+        compress_fragment(fragment, &mut table, &mut out);
+    }
+    out
+}
+
+/// Decompress the raw Snappy format within the output byte limit set by `set_limits`
+pub fn snappy_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut pos = 0;
+    let len = read_varint(data, &mut pos)? as usize;
+    let limit = output_limit();
+    if len > limit {
+        return Err(limit_error(limit));
+    }
+    // Each input byte expands to at most a few dozen output bytes
+    let mut out = Vec::with_capacity(len.min(data.len().saturating_mul(64)));
+    let truncated = || "Truncated Snappy data".to_string();
+
+    while pos < data.len() {
+        let tag = data[pos];
+        pos += 1;
+        let (length, offset) = match tag & 3 {
+            0 => {
+                let mut n = (tag >> 2) as usize;
+                if n >= 60 {
+                    let bytes = n - 59;
+                    let b = data.get(pos..pos + bytes).ok_or_else(truncated)?;
+                    n = b.iter().rev().fold(0usize, |v, &x| (v << 8) | x as usize);
+                    pos += bytes;
+                }
+                let literal = data.get(pos..pos + n + 1).ok_or_else(truncated)?;
+                out.extend_from_slice(literal);
+                pos += n + 1;
+                continue;
+            }
+            1 => {
+                let b = *data.get(pos).ok_or_else(truncated)? as usize;
+                pos += 1;
+                (4 + ((tag >> 2) & 7) as usize, ((tag as usize >> 5) << 8) | b)
+            }
+            2 => {
+                let b = data.get(pos..pos + 2).ok_or_else(truncated)?;
+                pos += 2;
+                ((tag >> 2) as usize + 1, u16::from_le_bytes([b[0], b[1]]) as usize)
+            }
+            _ => {
+                let b = data.get(pos..pos + 4).ok_or_else(truncated)?;
+                pos += 4;
+                ((tag >> 2) as usize + 1, u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            }
+        };
+
+        if offset == 0 || offset > out.len() {
+            return Err("Snappy copy offset out of range".to_string());
+        }
+        if out.len() + length > len {
+            return Err("Snappy data exceeds declared length".to_string());
+        }
+        let start = out.len() - offset;
+        if offset >= length {
+            out.extend_from_within(start..start + length);
+        } else {
+            for k in 0..length {
+                out.push(out[start + k]);
+            }
+        }
+    }
+
+    if out.len() != len {
+        return Err("Snappy data length mismatch".to_string());
+    }
+    Ok(out)
+}
+
+fn masked_crc(data: &[u8]) -> u32 {
+    let crc = crc32c(data);
+    crc.rotate_right(15).wrapping_add(0xA282_EAD8)
+}
+
+fn push_chunk(out: &mut Vec<u8>, kind: u8, crc: u32, body: &[u8]) {
+    let len = body.len() + 4;
+    out.extend_from_slice(&[kind, len as u8, (len >> 8) as u8, (len >> 16) as u8]);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(body);
+}
+
+/// Compress to the Snappy framing format (`.sz`)
+pub fn snappy_frame_compress(data: &[u8]) -> Vec<u8> {
+    let mut out = STREAM_IDENTIFIER.to_vec();
+    let mut table = vec![u32::MAX; 1 << HASH_BITS];
+    for chunk in data.chunks(FRAGMENT_SIZE) {
+        let mut compressed = Vec::with_capacity(chunk.len());
+        write_varint(&mut compressed, chunk.len() as u64);
+        compress_fragment(chunk, &mut table, &mut compressed);
+
+        // Store chunks that don't shrink by at least 1/8
+        let crc = masked_crc(chunk);
+        if compressed.len() < chunk.len() - chunk.len() / 8 {
+            push_chunk(&mut out, 0x00, crc, &compressed);
+        } else {
+            push_chunk(&mut out, 0x01, crc, chunk);
+        }
+    }
+    out
+}
+
+/// Decompress the Snappy framing format, verifying chunk checksums, within the
+/// output byte limit set by `set_limits`
+pub fn snappy_frame_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    if !data.starts_with(&STREAM_IDENTIFIER) {
+        return Err("Invalid Snappy stream identifier".to_string());
+    }
+    let limit = output_limit();
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let header = data.get(pos..pos + 4).ok_or("Truncated Snappy chunk header")?;
+        let kind = header[0];
+        let len = header[1] as usize | (header[2] as usize) << 8 | (header[3] as usize) << 16;
+        let body = data.get(pos + 4..pos + 4 + len).ok_or("Truncated Snappy chunk")?;
+        pos += 4 + len;
+
+        let chunk = match kind {
+            0x00 | 0x01 => {
+                if len < 4 {
+                    return Err("Snappy chunk too small".to_string());
+                }
+                let crc = u32::from_le_bytes([body[0], body[1], body[2], body[3]]);
+                let chunk = if kind == 0 { snappy_decompress(&body[4..])? } else { body[4..].to_vec() };
+                if chunk.len() > FRAGMENT_SIZE {
+                    return Err("Snappy chunk too large".to_string());
+                }
+                if masked_crc(&chunk) != crc {
+                    return Err("Snappy chunk checksum mismatch".to_string());
+                }
+                chunk
+            }
+            0xFF => {
+                if body != &STREAM_IDENTIFIER[4..] {
+                    return Err("Invalid Snappy stream identifier".to_string());
+                }
+                continue;
+            }
+            0x02..=0x7F => return Err(format!("Unsupported Snappy chunk type 0x{:02x}", kind)),
+            // Padding and skippable chunks
+            _ => continue,
+        };
+        if out.len() + chunk.len() > limit {
+            return Err(limit_error(limit));
+        }
+        out.extend_from_slice(&chunk);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_roundtrip() {
+        let mut data = b"snappy snappy snappy! ".repeat(5000);
+        data.extend((0..70000u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8));
+        let compressed = snappy_compress(&data);
+        assert!(compressed.len() < data.len() / 2);
+        assert_eq!(snappy_decompress(&compressed).unwrap(), data);
+        assert_eq!(snappy_decompress(&snappy_compress(&[])).unwrap(), Vec::<u8>::new());
+
+        // Literal "abcd" followed by an overlapping 1-byte-offset copy
+        let raw = [0x0C, 0x0C, b'a', b'b', b'c', b'd', 0x11, 0x04];
+        assert_eq!(snappy_decompress(&raw).unwrap(), b"abcdabcdabcd");
+        assert!(snappy_decompress(&raw[..7]).is_err());
+    }
+
+    #[test]
+    fn test_framed_roundtrip() {
+        let data = b"framed snappy data ".repeat(8000);
+        let framed = snappy_frame_compress(&data);
+        assert!(framed.starts_with(&STREAM_IDENTIFIER));
+        assert_eq!(snappy_frame_decompress(&framed).unwrap(), data);
+
+        let mut corrupt = framed.clone();
+        *corrupt.last_mut().unwrap() ^= 0xFF;
+        assert!(snappy_frame_decompress(&corrupt).is_err());
+    }
+}
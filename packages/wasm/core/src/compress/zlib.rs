@@ -0,0 +1,172 @@
+//! zlib (RFC 1950) and gzip (RFC 1952) wrappers around DEFLATE
+
+use super::deflate::{deflate, inflate_with_limit};
+use super::stream_limit_error;
+use crate::checksum::{adler32, crc32};
+use crate::limits::output_limit;
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+/// Wrap data in a zlib stream
+pub fn zlib_compress(data: &[u8], level: u8) -> Vec<u8> {
+    let flevel: u8 = match level {
+        0..=1 => 0,
+        2..=5 => 1,
+        6 => 2,
+        _ => 3,
+    };
+    let cmf = 0x78u8; // deflate, 32K window
+    let mut flg = flevel << 6;
+    flg |= ((31 - (cmf as u16 * 256 + flg as u16) % 31) % 31) as u8;
+
+    let mut out = vec![cmf, flg];
+    out.extend_from_slice(&deflate(data, level));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Decode a zlib stream, verifying its Adler-32 checksum, within the output
+/// byte limit set by `set_limits`
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    zlib_decompress_with_limit(data, output_limit())
+}
+
+/// Decode a zlib stream, failing if the output would exceed `limit` bytes
+pub fn zlib_decompress_with_limit(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 6 {
+        return Err("zlib data too small".to_string());
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 {
+        return Err("Unsupported zlib compression method".to_string());
+    }
+    if !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err("Invalid zlib header checksum".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".to_string());
+    }
+
+    let inflated = inflate_with_limit(&data[2..], limit)?;
+    let trailer = data
+        .get(2 + inflated.consumed..2 + inflated.consumed + 4)
+        .ok_or("Missing zlib checksum")?;
+    if u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) != adler32(&inflated.data) {
+        return Err("zlib checksum mismatch".to_string());
+    }
+    Ok(inflated.data)
+}
+
+/// Wrap data in a single-member gzip stream
+pub fn gzip_compress(data: &[u8], level: u8) -> Vec<u8> {
+    let xfl = match level {
+        0..=1 => 4, // fastest
+        9 => 2,     // maximum compression
+        _ => 0,
+    };
+    // No mtime, unknown OS
+    let mut out = vec![0x1F, 0x8B, 8, 0, 0, 0, 0, 0, xfl, 255];
+    out.extend_from_slice(&deflate(data, level));
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Decode a gzip stream, concatenating all members and verifying each CRC-32,
+/// within the output byte limit set by `set_limits`
+pub fn gzip_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    gzip_decompress_with_limit(data, output_limit())
+}
+
+/// Decode a gzip stream, failing if the output would exceed `limit` bytes
+pub fn gzip_decompress_with_limit(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+
+    let mut out = Vec::new();
+    let mut pos = 0;
+    loop {
+        let header = data.get(pos..pos + 10).ok_or("gzip data too small")?;
+        if header[0] != 0x1F || header[1] != 0x8B {
+            return Err("Invalid gzip signature".to_string());
+        }
+        if header[2] != 8 {
+            return Err("Unsupported gzip compression method".to_string());
+        }
+        let flags = header[3];
+        pos += 10;
+
+        if flags & FEXTRA != 0 {
+            let xlen = data.get(pos..pos + 2).ok_or("Truncated gzip header")?;
+            pos += 2 + u16::from_le_bytes([xlen[0], xlen[1]]) as usize;
+        }
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                let end = data.get(pos..).and_then(|d| d.iter().position(|&b| b == 0)).ok_or("Truncated gzip header")?;
+                pos += end + 1;
+            }
+        }
+        if flags & FHCRC != 0 {
+            pos += 2;
+        }
+
+        let left = limit - out.len();
+        let inflated = inflate_with_limit(data.get(pos..).ok_or("Truncated gzip header")?, left).map_err(|e| stream_limit_error(e, left, limit))?;
+        pos += inflated.consumed;
+        let trailer = data.get(pos..pos + 8).ok_or("Missing gzip trailer")?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        if crc != crc32(&inflated.data) || size != inflated.data.len() as u32 {
+            return Err("gzip checksum mismatch".to_string());
+        }
+        out.extend_from_slice(&inflated.data);
+        pos += 8;
+
+        // Further members follow directly; trailing zero padding is ignored
+        if data[pos..].iter().all(|&b| b == 0) {
+            return Ok(out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zlib_roundtrip() {
+        let data = b"zlib zlib zlib zlib zlib zlib".repeat(20);
+        let compressed = zlib_compress(&data, 6);
+        assert_eq!(&compressed[..2], &[0x78, 0x9C]);
+        assert_eq!(zlib_decompress(&compressed).unwrap(), data);
+
+        let mut corrupt = compressed.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(zlib_decompress(&corrupt).is_err());
+
+        // "hello" from zlib.compress
+        let reference = [0x78, 0x9C, 0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00, 0x06, 0x2C, 0x02, 0x15];
+        assert_eq!(zlib_decompress(&reference).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_gzip_members() {
+        let mut stream = gzip_compress(b"first ", 9);
+        stream.extend_from_slice(&gzip_compress(b"second", 1));
+        assert_eq!(gzip_decompress(&stream).unwrap(), b"first second");
+        assert!(gzip_decompress(&stream[..stream.len() - 3]).is_err());
+
+        // The limit covers all members together
+        assert_eq!(gzip_decompress_with_limit(&stream, 12).unwrap(), b"first second");
+        assert_eq!(gzip_decompress_with_limit(&stream, 11), Err("Decompressed size exceeds limit of 11 bytes".to_string()));
+    }
+
+    #[test]
+    fn test_zlib_limit() {
+        let bomb = zlib_compress(&[0; 100_000], 9);
+        assert!(bomb.len() < 1000);
+        assert_eq!(zlib_decompress_with_limit(&bomb, 100_000).unwrap().len(), 100_000);
+        assert_eq!(zlib_decompress_with_limit(&bomb, 99_999), Err("Decompressed size exceeds limit of 99999 bytes".to_string()));
+    }
+}
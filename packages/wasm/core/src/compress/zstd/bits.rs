@@ -0,0 +1,72 @@
+//! Backward bitstream used by zstd entropy-coded sections
+
+use alloc::string::{String, ToString};
+
+/// Reads a bitstream from its end towards its start, most-significant bits first
+///
+/// The final byte carries a 1-bit end marker above the last data bit. Reading
+/// past the start yields zero bits and leaves the reader in an overflowed state.
+pub struct BackwardReader<'a> {
+    data: &'a [u8],
+    /// Unread bits remaining; negative once the stream has been overrun
+    remaining: isize,
+}
+
+impl<'a> BackwardReader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, String> {
+        let last = *data.last().ok_or("Empty bitstream")?;
+        if last == 0 {
+            return Err("Missing bitstream end marker".to_string());
+        }
+        let marker = 7 - last.leading_zeros() as isize;
+        Ok(BackwardReader {
+            data,
+            remaining: (data.len() as isize - 1) * 8 + marker,
+        })
+    }
+
+    /// Bits `start..start + n` of the stream, bit 0 being the LSB of the first byte
+    #[inline]
+    fn extract(&self, start: usize, n: u32) -> u64 {
+        let byte = start / 8;
+        let mut buf = [0u8; 8];
+        let avail = &self.data[byte..(byte + 8).min(self.data.len())];
+        buf[..avail.len()].copy_from_slice(avail);
+        (u64::from_le_bytes(buf) >> (start % 8)) & ((1u64 << n) - 1)
+    }
+
+    /// Next `n` (<= 56) bits without consuming them
+    #[inline]
+    pub fn peek(&self, n: u32) -> u64 {
+        if n == 0 || self.remaining <= 0 {
+            return 0;
+        }
+        let start = self.remaining - n as isize;
+        if start >= 0 {
+            self.extract(start as usize, n)
+        } else {
+            self.extract(0, self.remaining as u32) << -start
+        }
+    }
+
+    #[inline]
+    pub fn consume(&mut self, n: u32) {
+        self.remaining -= n as isize;
+    }
+
+    #[inline]
+    pub fn read(&mut self, n: u32) -> u64 {
+        let v = self.peek(n);
+        self.consume(n);
+        v
+    }
+
+    /// Bits left to read (negative after overrun)
+    pub fn remaining(&self) -> isize {
+        self.remaining
+    }
+
+    pub fn overflowed(&self) -> bool {
+        self.remaining < 0
+    }
+}
@@ -0,0 +1,233 @@
+//! Compressed block decoding: literals and sequences sections
+
+use super::bits::BackwardReader;
+use super::fse::FseTable;
+use super::huffman::HuffmanTable;
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+const LL_DEFAULT: [i16; 36] = [4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1, -1, -1, -1, -1];
+const ML_DEFAULT: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1,
+    -1, -1, -1, -1, -1,
+];
+const OF_DEFAULT: [i16; 29] = [1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1];
+
+/// (baseline, extra bits) per literals length code
+const LL_CODES: [(u32, u8); 36] = [
+    (0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0), (9, 0), (10, 0), (11, 0), (12, 0), (13, 0), (14, 0), (15, 0),
+    (16, 1), (18, 1), (20, 1), (22, 1), (24, 2), (28, 2), (32, 3), (40, 3), (48, 4), (64, 6), (128, 7), (256, 8), (512, 9), (1024, 10),
+    (2048, 11), (4096, 12), (8192, 13), (16384, 14), (32768, 15), (65536, 16),
+];
+
+/// (baseline, extra bits) per match length code
+const ML_CODES: [(u32, u8); 53] = [
+    (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0), (9, 0), (10, 0), (11, 0), (12, 0), (13, 0), (14, 0), (15, 0), (16, 0), (17, 0), (18, 0),
+    (19, 0), (20, 0), (21, 0), (22, 0), (23, 0), (24, 0), (25, 0), (26, 0), (27, 0), (28, 0), (29, 0), (30, 0), (31, 0), (32, 0), (33, 0), (34, 0),
+    (35, 1), (37, 1), (39, 1), (41, 1), (43, 2), (47, 2), (51, 3), (59, 3), (67, 4), (83, 4), (99, 5), (131, 7), (259, 8), (515, 9), (1027, 10),
+    (2051, 11), (4099, 12), (8195, 13), (16387, 14), (32771, 15), (65539, 16),
+];
+
+/// Entropy tables and repeat offsets carried from block to block within a frame
+#[derive(Clone)]
+pub struct BlockState {
+    pub huffman: Option<HuffmanTable>,
+    pub ll: Option<FseTable>,
+    pub of: Option<FseTable>,
+    pub ml: Option<FseTable>,
+    pub rep: [usize; 3],
+}
+
+impl Default for BlockState {
+    fn default() -> Self {
+        BlockState {
+            huffman: None,
+            ll: None,
+            of: None,
+            ml: None,
+            rep: [1, 4, 8],
+        }
+    }
+}
+
+fn le_value(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |v, &b| (v << 8) | b as u64)
+}
+
+/// Decode the literals section, returning the literals and bytes consumed
+fn decode_literals(block: &[u8], state: &mut BlockState) -> Result<(Vec<u8>, usize), String> {
+    let truncated = || "Truncated literals section".to_string();
+    let b0 = *block.first().ok_or_else(truncated)?;
+    let kind = b0 & 3;
+    let size_format = (b0 >> 2) & 3;
+
+    if kind < 2 {
+        let (regenerated, header) = match size_format {
+            0 | 2 => ((b0 >> 3) as usize, 1),
+            1 => (le_value(block.get(..2).ok_or_else(truncated)?) as usize >> 4, 2),
+            _ => (le_value(block.get(..3).ok_or_else(truncated)?) as usize >> 4, 3),
+        };
+        return if kind == 0 {
+            let raw = block.get(header..header + regenerated).ok_or_else(truncated)?;
+            Ok((raw.to_vec(), header + regenerated))
+        } else {
+            let byte = *block.get(header).ok_or_else(truncated)?;
+            Ok((vec![byte; regenerated], header + 1))
+        };
+    }
+
+    let (header, bits, streams) = match size_format {
+        0 => (3, 10, 1),
+        1 => (3, 10, 4),
+        2 => (4, 14, 4),
+        _ => (5, 18, 4),
+    };
+    let h = le_value(block.get(..header).ok_or_else(truncated)?);
+    let mask = (1u64 << bits) - 1;
+    let regenerated = ((h >> 4) & mask) as usize;
+    let compressed = ((h >> (4 + bits)) & mask) as usize;
+    let mut data = block.get(header..header + compressed).ok_or_else(truncated)?;
+
+    if kind == 2 {
+        let (table, used) = HuffmanTable::read(data)?;
+        state.huffman = Some(table);
+        data = &data[used..];
+    }
+    let table = state.huffman.as_ref().ok_or("Treeless literals without a previous Huffman table")?;
+
+    let mut literals = Vec::with_capacity(regenerated);
+    if streams == 1 {
+        table.decode_stream(data, regenerated, &mut literals)?;
+    } else {
+        let jump = data.get(..6).ok_or_else(truncated)?;
+        let sizes = [le_value(&jump[0..2]) as usize, le_value(&jump[2..4]) as usize, le_value(&jump[4..6]) as usize];
+        let per_stream = regenerated.div_ceil(4);
+        let mut start = 6;
+        for (i, count) in [per_stream, per_stream, per_stream, regenerated.saturating_sub(3 * per_stream)].into_iter().enumerate() {
+            let end = if i < 3 { start + sizes[i] } else { data.len() };
+            table.decode_stream(data.get(start..end).ok_or_else(truncated)?, count, &mut literals)?;
+            start = end;
+        }
+        if literals.len() != regenerated {
+            return Err("Literals size mismatch".to_string());
+        }
+    }
+    Ok((literals, header + compressed))
+}
+
+/// Select the table for one sequence symbol type, updating the repeat slot
+fn read_table<'s>(
+    mode: u8,
+    data: &[u8],
+    pos: &mut usize,
+    slot: &'s mut Option<FseTable>,
+    default: &[i16],
+    default_log: u32,
+    max_log: u32,
+) -> Result<&'s FseTable, String> {
+    match mode {
+        0 => *slot = Some(FseTable::from_distribution(default, default_log)?),
+        1 => {
+            *slot = Some(FseTable::rle(*data.get(*pos).ok_or("Truncated sequences section")?));
+            *pos += 1;
+        }
+        2 => {
+            let (table, used) = FseTable::read(data.get(*pos..).unwrap_or(&[]), max_log, default.len() - 1)?;
+            *slot = Some(table);
+            *pos += used;
+        }
+        _ => {}
+    }
+    slot.as_ref().ok_or_else(|| "Repeat mode without a previous table".to_string())
+}
+
+/// Decode one compressed block, appending to `out`
+///
+/// `out` holds all previously decoded data of the frame (and any dictionary
+/// content) so that matches can reach back into it.
+pub fn decode_block(block: &[u8], state: &mut BlockState, out: &mut Vec<u8>) -> Result<(), String> {
+    let (literals, used) = decode_literals(block, state)?;
+    let data = &block[used..];
+
+    let b0 = *data.first().ok_or("Missing sequences section")? as usize;
+    let (count, mut pos) = match b0 {
+        0 => {
+            out.extend_from_slice(&literals);
+            return Ok(());
+        }
+        1..=127 => (b0, 1),
+        128..=254 => (((b0 - 128) << 8) + *data.get(1).ok_or("Truncated sequences section")? as usize, 2),
+        _ => (le_value(data.get(1..3).ok_or("Truncated sequences section")?) as usize + 0x7F00, 3),
+    };
+    let modes = *data.get(pos).ok_or("Truncated sequences section")?;
+    pos += 1;
+
+    let BlockState { ll, of, ml, rep, .. } = state;
+    let ll = read_table(modes >> 6, data, &mut pos, ll, &LL_DEFAULT, 6, 9)?;
+    let of = read_table((modes >> 4) & 3, data, &mut pos, of, &OF_DEFAULT, 5, 8)?;
+    let ml = read_table((modes >> 2) & 3, data, &mut pos, ml, &ML_DEFAULT, 6, 9)?;
+
+    let mut reader = BackwardReader::new(data.get(pos..).unwrap_or(&[]))?;
+    let mut ll_state = ll.init(&mut reader);
+    let mut of_state = of.init(&mut reader);
+    let mut ml_state = ml.init(&mut reader);
+    let mut lit_pos = 0;
+
+    for i in 0..count {
+        let of_code = of.symbol(of_state) as u32;
+        let (ml_base, ml_bits) = *ML_CODES.get(ml.symbol(ml_state) as usize).ok_or("Invalid match length code")?;
+        let (ll_base, ll_bits) = *LL_CODES.get(ll.symbol(ll_state) as usize).ok_or("Invalid literals length code")?;
+        if of_code > 31 {
+            return Err("Invalid offset code".to_string());
+        }
+        let offset_value = (1u64 << of_code) as usize + reader.read(of_code) as usize;
+        let match_len = (ml_base + reader.read(ml_bits as u32) as u32) as usize;
+        let lit_len = (ll_base + reader.read(ll_bits as u32) as u32) as usize;
+
+        // Values 1-3 select repeat offsets, shifted by one when there are no literals
+        let offset = if offset_value > 3 {
+            rep[2] = rep[1];
+            rep[1] = rep[0];
+            rep[0] = offset_value - 3;
+            rep[0]
+        } else {
+            let idx = offset_value - 1 + (lit_len == 0) as usize;
+            if idx > 0 {
+                let offset = if idx == 3 { rep[0].saturating_sub(1) } else { rep[idx] };
+                if idx != 1 {
+                    rep[2] = rep[1];
+                }
+                rep[1] = rep[0];
+                rep[0] = offset;
+            }
+            rep[0]
+        };
+
+        if i + 1 < count {
+            ll_state = ll.update(ll_state, &mut reader);
+            ml_state = ml.update(ml_state, &mut reader);
+            of_state = of.update(of_state, &mut reader);
+        }
+
+        let lits = literals.get(lit_pos..lit_pos + lit_len).ok_or("Literals overrun")?;
+        out.extend_from_slice(lits);
+        lit_pos += lit_len;
+
+        if offset == 0 || offset > out.len() {
+            return Err("Match offset out of range".to_string());
+        }
+        let start = out.len() - offset;
+        if offset >= match_len {
+            out.extend_from_within(start..start + match_len);
+        } else {
+            for k in 0..match_len {
+                out.push(out[start + k]);
+            }
+        }
+    }
+
+    if reader.remaining() != 0 {
+        return Err("Corrupted sequences bitstream".to_string());
+    }
+    out.extend_from_slice(&literals[lit_pos..]);
+    Ok(())
+}
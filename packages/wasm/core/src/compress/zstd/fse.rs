@@ -0,0 +1,148 @@
+//! Finite State Entropy (tANS) tables
+
+use super::bits::BackwardReader;
+use crate::compress::bits::BitReader;
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    symbol: u8,
+    bits: u8,
+    baseline: u16,
+}
+
+/// FSE decoding table
+#[derive(Clone)]
+pub struct FseTable {
+    log: u32,
+    entries: Vec<Entry>,
+}
+
+impl FseTable {
+    /// Table that always yields `symbol` and reads no bits
+    pub fn rle(symbol: u8) -> FseTable {
+        FseTable {
+            log: 0,
+            entries: vec![Entry { symbol, bits: 0, baseline: 0 }],
+        }
+    }
+
+    /// Parse a compact table description, returning the table and bytes consumed
+    pub fn read(data: &[u8], max_log: u32, max_symbol: usize) -> Result<(FseTable, usize), String> {
+        let mut reader = BitReader::new(data);
+        let log = reader.bits(4)? + 5;
+        if log > max_log {
+            return Err(format!("FSE accuracy log {} exceeds {}", log, max_log));
+        }
+
+        let mut norm = Vec::with_capacity(max_symbol + 1);
+        let mut remaining = (1i32 << log) + 1;
+        let mut threshold = 1i32 << log;
+        let mut nb_bits = log + 1;
+        while remaining > 1 {
+            if norm.len() > max_symbol {
+                return Err("Too many FSE symbols".to_string());
+            }
+            let max = 2 * threshold - 1 - remaining;
+            let value = reader.peek(nb_bits) as i32;
+            let mut count = if value & (threshold - 1) < max {
+                reader.consume(nb_bits - 1)?;
+                value & (threshold - 1)
+            } else {
+                reader.consume(nb_bits)?;
+                let v = value & (2 * threshold - 1);
+                if v >= threshold {
+                    v - max
+                } else {
+                    v
+                }
+            };
+            count -= 1;
+            remaining -= count.abs();
+            norm.push(count as i16);
+
+            if count == 0 {
+                // Runs of zero probabilities are sent as 2-bit repeat counts
+                loop {
+                    let repeat = reader.bits(2)?;
+                    norm.extend(core::iter::repeat_n(0, repeat as usize));
+                    if repeat != 3 {
+                        break;
+                    }
+                }
+            }
+            while remaining < threshold {
+                nb_bits -= 1;
+                threshold >>= 1;
+            }
+        }
+        if remaining != 1 || norm.len() > max_symbol + 1 {
+            return Err("Corrupted FSE table description".to_string());
+        }
+
+        Ok((FseTable::from_distribution(&norm, log)?, reader.bit_pos().div_ceil(8)))
+    }
+
+    /// Build the decoding table for a normalized distribution (-1 = "less than one")
+    pub fn from_distribution(norm: &[i16], log: u32) -> Result<FseTable, String> {
+        let size = 1usize << log;
+        let mut entries = vec![Entry::default(); size];
+        let mut next = vec![0u32; norm.len()];
+
+        // Low-probability symbols take the top slots
+        let mut high = size;
+        for (s, &n) in norm.iter().enumerate() {
+            if n == -1 {
+                high -= 1;
+                entries[high].symbol = s as u8;
+                next[s] = 1;
+            } else {
+                next[s] = n.max(0) as u32;
+            }
+        }
+
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mask = size - 1;
+        let mut pos = 0;
+        for (s, &n) in norm.iter().enumerate() {
+            for _ in 0..n.max(0) {
+                entries[pos].symbol = s as u8;
+                pos = (pos + step) & mask;
+                while pos >= high {
+                    pos = (pos + step) & mask;
+                }
+            }
+        }
+        if pos != 0 {
+            return Err("Invalid FSE distribution".to_string());
+        }
+
+        for entry in entries.iter_mut() {
+            let state = next[entry.symbol as usize];
+            next[entry.symbol as usize] += 1;
+            let bits = log - (31 - state.leading_zeros());
+            entry.bits = bits as u8;
+            entry.baseline = ((state << bits) - size as u32) as u16;
+        }
+
+        Ok(FseTable { log, entries })
+    }
+
+    /// Read the initial state
+    #[inline]
+    pub fn init(&self, reader: &mut BackwardReader) -> usize {
+        reader.read(self.log) as usize
+    }
+
+    #[inline]
+    pub fn symbol(&self, state: usize) -> u8 {
+        self.entries[state].symbol
+    }
+
+    /// Advance to the next state
+    #[inline]
+    pub fn update(&self, state: usize, reader: &mut BackwardReader) -> usize {
+        let e = self.entries[state];
+        e.baseline as usize + reader.read(e.bits as u32) as usize
+    }
+}
@@ -0,0 +1,103 @@
+//! Huffman-coded literals
+
+use super::bits::BackwardReader;
+use super::fse::FseTable;
+use alloc::{string::{String, ToString}, vec::Vec};
+
+/// Huffman decoding table indexed by the next `max_bits` stream bits
+#[derive(Clone)]
+pub struct HuffmanTable {
+    max_bits: u32,
+    /// (symbol, code length)
+    entries: Vec<(u8, u8)>,
+}
+
+impl HuffmanTable {
+    /// Parse a Huffman tree description, returning the table and bytes consumed
+    pub fn read(data: &[u8]) -> Result<(HuffmanTable, usize), String> {
+        let header = *data.first().ok_or("Missing Huffman tree description")? as usize;
+        let (weights, size) = if header < 128 {
+            let src = data.get(1..1 + header).ok_or("Truncated Huffman tree description")?;
+            (fse_weights(src)?, header)
+        } else {
+            let count = header - 127;
+            let size = count.div_ceil(2);
+            let src = data.get(1..1 + size).ok_or("Truncated Huffman tree description")?;
+            let weights = (0..count).map(|i| if i % 2 == 0 { src[i / 2] >> 4 } else { src[i / 2] & 0xF }).collect();
+            (weights, size)
+        };
+        Ok((HuffmanTable::from_weights(&weights)?, 1 + size))
+    }
+
+    /// Build from the weights of all but the last symbol, whose weight is implied
+    fn from_weights(weights: &[u8]) -> Result<HuffmanTable, String> {
+        if weights.iter().any(|&w| w > 11) {
+            return Err("Invalid Huffman weight".to_string());
+        }
+        let total: u32 = weights.iter().filter(|&&w| w > 0).map(|&w| 1 << (w - 1)).sum();
+        if total == 0 {
+            return Err("Empty Huffman table".to_string());
+        }
+        let max_bits = 32 - total.leading_zeros();
+        let leftover = (1u32 << max_bits) - total;
+        if max_bits > 11 || !leftover.is_power_of_two() {
+            return Err("Invalid Huffman weights".to_string());
+        }
+        let mut weights = weights.to_vec();
+        weights.push(leftover.trailing_zeros() as u8 + 1);
+        if weights.len() > 256 {
+            return Err("Too many Huffman symbols".to_string());
+        }
+
+        // Lower weights (longer codes) take the lowest code values
+        let mut entries = Vec::with_capacity(1 << max_bits);
+        for w in 1..=max_bits as u8 {
+            for (symbol, _) in weights.iter().enumerate().filter(|(_, &sw)| sw == w) {
+                let len = (max_bits + 1 - w as u32) as u8;
+                entries.extend(core::iter::repeat_n((symbol as u8, len), 1 << (w - 1)));
+            }
+        }
+
+        Ok(HuffmanTable { max_bits, entries })
+    }
+
+    /// Decode `count` literals from one backward bitstream
+    pub fn decode_stream(&self, data: &[u8], count: usize, out: &mut Vec<u8>) -> Result<(), String> {
+        let mut reader = BackwardReader::new(data)?;
+        for _ in 0..count {
+            let (symbol, len) = self.entries[reader.peek(self.max_bits) as usize];
+            reader.consume(len as u32);
+            out.push(symbol);
+        }
+        if reader.remaining() != 0 {
+            return Err("Corrupted Huffman stream".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Decode FSE-compressed Huffman weights
+fn fse_weights(data: &[u8]) -> Result<Vec<u8>, String> {
+    let (table, used) = FseTable::read(data, 6, 255)?;
+    let mut reader = BackwardReader::new(&data[used..])?;
+    let mut s1 = table.init(&mut reader);
+    let mut s2 = table.init(&mut reader);
+
+    // Two interleaved states; the stream ends when an update overruns it
+    let mut weights = Vec::new();
+    while weights.len() < 255 {
+        weights.push(table.symbol(s1));
+        s1 = table.update(s1, &mut reader);
+        if reader.overflowed() {
+            weights.push(table.symbol(s2));
+            break;
+        }
+        weights.push(table.symbol(s2));
+        s2 = table.update(s2, &mut reader);
+        if reader.overflowed() {
+            weights.push(table.symbol(s1));
+            break;
+        }
+    }
+    Ok(weights)
+}
@@ -0,0 +1,263 @@
+//! Zstandard (RFC 8878) decompression
+//!
+//! Supports all block types, FSE/Huffman entropy coding, repeat tables and
+//! offsets, multiple and skippable frames, and raw-content or formatted
+//! dictionaries. Content checksums are verified.
+
+mod bits;
+mod block;
+mod fse;
+mod huffman;
+
+use super::{limit_error, stream_limit_error};
+use crate::checksum::xxh64;
+use crate::limits::output_limit;
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use block::{decode_block, BlockState};
+use fse::FseTable;
+use huffman::HuffmanTable;
+
+const FRAME_MAGIC: u32 = 0xFD2F_B528;
+const DICT_MAGIC: u32 = 0xEC30_A437;
+const MAX_BLOCK_SIZE: usize = 128 * 1024;
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, String> {
+    let b = data.get(offset..offset + 4).ok_or("Truncated zstd data")?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// A zstd dictionary: prefix content plus optional pre-trained entropy tables
+pub struct ZstdDictionary {
+    id: u32,
+    content: Vec<u8>,
+    state: BlockState,
+}
+
+impl ZstdDictionary {
+    /// Parse a formatted dictionary, or treat any other data as raw content
+    pub fn parse(data: &[u8]) -> Result<ZstdDictionary, String> {
+        if data.len() < 8 || read_u32(data, 0)? != DICT_MAGIC {
+            return Ok(ZstdDictionary {
+                id: 0,
+                content: data.to_vec(),
+                state: BlockState::default(),
+            });
+        }
+
+        let id = read_u32(data, 4)?;
+        let mut pos = 8;
+        let (huffman, used) = HuffmanTable::read(&data[pos..])?;
+        pos += used;
+        let mut tables = Vec::with_capacity(3);
+        for (max_log, max_symbol) in [(8, 31), (9, 52), (9, 35)] {
+            let (table, used) = FseTable::read(data.get(pos..).unwrap_or(&[]), max_log, max_symbol)?;
+            tables.push(table);
+            pos += used;
+        }
+        let mut rep = [0usize; 3];
+        for r in rep.iter_mut() {
+            *r = read_u32(data, pos)? as usize;
+            pos += 4;
+        }
+        let content = data[pos..].to_vec();
+        if rep.iter().any(|&r| r == 0 || r > content.len()) {
+            return Err("Invalid dictionary repeat offsets".to_string());
+        }
+
+        let mut tables = tables.into_iter();
+        let (of, ml, ll) = (tables.next(), tables.next(), tables.next());
+        Ok(ZstdDictionary {
+            id,
+            content,
+            state: BlockState { huffman: Some(huffman), ll, of, ml, rep },
+        })
+    }
+
+    /// Dictionary ID (0 for raw-content dictionaries)
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+/// Decode the frame at the start of `data`, returning bytes consumed; fails
+/// once its content passes `limit` bytes
+fn decode_frame(data: &[u8], dict: Option<&ZstdDictionary>, limit: usize, out: &mut Vec<u8>) -> Result<usize, String> {
+    let descriptor = *data.get(4).ok_or("Truncated zstd frame header")?;
+    let fcs_flag = descriptor >> 6;
+    let single_segment = descriptor & 0x20 != 0;
+    let has_checksum = descriptor & 0x04 != 0;
+    if descriptor & 0x08 != 0 {
+        return Err("Reserved zstd frame header bit set".to_string());
+    }
+    let mut pos = 5;
+
+    if !single_segment {
+        pos += 1; // window descriptor; the whole frame is kept in memory
+    }
+
+    let dict_id_size = [0, 1, 2, 4][(descriptor & 3) as usize];
+    let dict_id = data.get(pos..pos + dict_id_size).ok_or("Truncated zstd frame header")?.iter().rev().fold(0u32, |v, &b| (v << 8) | b as u32);
+    pos += dict_id_size;
+
+    let fcs_size = match fcs_flag {
+        0 => single_segment as usize,
+        1 => 2,
+        2 => 4,
+        _ => 8,
+    };
+    let content_size = (fcs_size > 0)
+        .then(|| data.get(pos..pos + fcs_size))
+        .flatten()
+        .map(|b| b.iter().rev().fold(0u64, |v, &x| (v << 8) | x as u64) + if fcs_size == 2 { 256 } else { 0 });
+    if fcs_size > 0 && content_size.is_none() {
+        return Err("Truncated zstd frame header".to_string());
+    }
+    if content_size.is_some_and(|n| n > limit as u64) {
+        return Err(limit_error(limit));
+    }
+    pos += fcs_size;
+
+    let (mut state, prefix) = match dict {
+        Some(d) if dict_id == 0 || d.id == 0 || d.id == dict_id => (d.state.clone(), d.content.as_slice()),
+        Some(_) => return Err(format!("zstd frame requires dictionary {}", dict_id)),
+        None if dict_id != 0 => return Err(format!("zstd frame requires dictionary {}", dict_id)),
+        None => (BlockState::default(), &[][..]),
+    };
+
+    // Matches may reach into the dictionary content, so decode after it
+    let mut frame = Vec::with_capacity(prefix.len() + content_size.unwrap_or(0).min(1 << 24) as usize);
+    frame.extend_from_slice(prefix);
+
+    loop {
+        let header = data.get(pos..pos + 3).ok_or("Truncated zstd block header")?;
+        let header = header[0] as usize | (header[1] as usize) << 8 | (header[2] as usize) << 16;
+        let last = header & 1 != 0;
+        let size = header >> 3;
+        pos += 3;
+
+        match (header >> 1) & 3 {
+            0 => {
+                frame.extend_from_slice(data.get(pos..pos + size).ok_or("Truncated zstd block")?);
+                pos += size;
+            }
+            1 => {
+                let byte = *data.get(pos).ok_or("Truncated zstd block")?;
+                frame.resize(frame.len() + size, byte);
+                pos += 1;
+            }
+            2 => {
+                if size > MAX_BLOCK_SIZE {
+                    return Err("zstd block too large".to_string());
+                }
+                decode_block(data.get(pos..pos + size).ok_or("Truncated zstd block")?, &mut state, &mut frame)?;
+                pos += size;
+            }
+            _ => return Err("Reserved zstd block type".to_string()),
+        }
+        // Blocks are at most 2 MiB, so checking after each bounds the overshoot
+        if frame.len() - prefix.len() > limit {
+            return Err(limit_error(limit));
+        }
+        if last {
+            break;
+        }
+    }
+
+    let decoded = &frame[prefix.len()..];
+    if content_size.is_some_and(|n| n != decoded.len() as u64) {
+        return Err("zstd frame content size mismatch".to_string());
+    }
+    if has_checksum {
+        // Low 32 bits of the content's XXH64
+        let expected = data.get(pos..pos + 4).ok_or("Missing zstd content checksum")?;
+        if xxh64(decoded, 0) as u32 != u32::from_le_bytes([expected[0], expected[1], expected[2], expected[3]]) {
+            return Err("zstd content checksum mismatch".to_string());
+        }
+        pos += 4;
+    }
+    out.extend_from_slice(decoded);
+    Ok(pos)
+}
+
+fn decompress_frames(data: &[u8], dict: Option<&ZstdDictionary>) -> Result<Vec<u8>, String> {
+    let limit = output_limit();
+    let mut out = Vec::new();
+    let mut pos = 0;
+    if data.len() < 4 {
+        return Err("zstd data too small".to_string());
+    }
+    while pos < data.len() {
+        let magic = read_u32(data, pos)?;
+        if magic & 0xFFFF_FFF0 == 0x184D_2A50 {
+            pos += 8 + read_u32(data, pos + 4)? as usize;
+            continue;
+        }
+        if magic != FRAME_MAGIC {
+            return Err("Invalid zstd frame magic".to_string());
+        }
+        let left = limit - out.len();
+        pos += decode_frame(&data[pos..], dict, left, &mut out).map_err(|e| stream_limit_error(e, left, limit))?;
+    }
+    Ok(out)
+}
+
+/// Decompress a zstd stream (all frames are concatenated) within the output
+/// byte limit set by `set_limits`
+pub fn zstd_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    decompress_frames(data, None)
+}
+
+/// Decompress a zstd stream that was compressed with a dictionary
+pub fn zstd_decompress_with_dict(data: &[u8], dict: &[u8]) -> Result<Vec<u8>, String> {
+    let dict = ZstdDictionary::parse(dict)?;
+    decompress_frames(data, Some(&dict))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn sample() -> String {
+        (0..40).map(|i| format!("The quick brown fox {} jumps over the lazy dog {}. ", i % 7, i % 5)).collect()
+    }
+
+    #[test]
+    fn test_decompress_compressed_block() {
+        // `zstd -19` output: Huffman literals, FSE sequences and a checksum
+        let frame = hex(concat!(
+            "28b52ffd64a8063d04004247161670590750fa43e90fa58f77559dbab7943201ecff7f334f5deed5b957e7bbf3ae6ae66fbf75baa94f7fad2fddd7baa94f",
+            "ffd9edaa66fe081811a8406d131a34920a27e382908a1c6b4163061df860d034204328968446120445a821fcfaff19e035863512f8bf41005f90afd6ff0d",
+            "725b538baf471de8f093c34f8fbe5cc174d0e9ae02fd0b3f3c"
+        ));
+        assert_eq!(zstd_decompress(&frame).unwrap(), sample().as_bytes());
+
+        // Concatenated and skippable frames
+        let mut stream = frame.clone();
+        stream.extend_from_slice(&[0x50, 0x2A, 0x4D, 0x18, 2, 0, 0, 0, 0xAA, 0xBB]);
+        stream.extend_from_slice(&frame);
+        assert_eq!(zstd_decompress(&stream).unwrap(), sample().repeat(2).as_bytes());
+
+        assert!(zstd_decompress(&frame[..100]).is_err());
+
+        let mut corrupt = frame.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 1;
+        assert_eq!(zstd_decompress(&corrupt).unwrap_err(), "zstd content checksum mismatch");
+
+        // The declared content size is checked before decoding
+        let limit = sample().len() - 1;
+        assert_eq!(decode_frame(&frame, None, limit, &mut Vec::new()), Err(limit_error(limit)));
+    }
+
+    #[test]
+    fn test_raw_content_dictionary() {
+        let dict = b"The quick brown fox jumps over the lazy dog";
+        let frame = hex("28b52ffd242a450000104121010059a984145ce935");
+        assert_eq!(zstd_decompress_with_dict(&frame, dict).unwrap(), b"A quick brown fox jumps over the lazy dog!");
+        assert!(zstd_decompress(&frame).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! Converting many files in one call
+
+use super::{convert, ConvertOptions, ImageFormat};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Outcome of one batch item: the converted file, or why it failed
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchResult {
+    pub data: Option<Vec<u8>>,
+    pub error: Option<String>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BatchResult {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl From<Result<Vec<u8>, String>> for BatchResult {
+    fn from(result: Result<Vec<u8>, String>) -> BatchResult {
+        match result {
+            Ok(data) => BatchResult { data: Some(data), error: None },
+            Err(error) => BatchResult { data: None, error: Some(error) },
+        }
+    }
+}
+
+/// Convert every input to `format`, one result per input in order; a bad
+/// file fails only its own item
+///
+/// With the `threads` feature items are converted in parallel on the current
+/// rayon pool (on the web, the one started by `initThreadPool`), or one by
+/// one when no pool is available. The browser main thread cannot wait for a
+/// pool, so there it converts one by one; `ThreadPool.submitConvert` runs a
+/// batch on workers without blocking.
+pub fn convert_batch(inputs: &[Vec<u8>], format: ImageFormat, options: &ConvertOptions) -> Vec<BatchResult> {
+    let one = |data: &Vec<u8>| BatchResult::from(convert(data, format, options));
+    #[cfg(feature = "threads")]
+    if crate::parallel::available() {
+        use rayon::prelude::*;
+        return inputs.par_iter().map(one).collect();
+    }
+    inputs.iter().map(one).collect()
+}
+
+#[cfg(all(test, feature = "bmp", feature = "png"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_batch() {
+        let bmp = crate::bmp::encode_bmp(1, 1, &[1, 2, 3, 255]).unwrap();
+        let results = convert_batch(&[bmp.clone(), b"junk".to_vec(), bmp], ImageFormat::Png, &ConvertOptions::new());
+        assert_eq!(results.len(), 3);
+        assert!(results[0].ok() && results[2].ok() && !results[1].ok());
+        assert_eq!(results[0], results[2]);
+        assert_eq!(results[1], BatchResult { data: None, error: Some("Unknown or unsupported image format".to_string()) });
+        assert!(convert_batch(&[], ImageFormat::Png, &ConvertOptions::new()).is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+//! Format detection and decode/convert entry points
+
+mod batch;
+
+pub use batch::{convert_batch, BatchResult};
+
+#[cfg(feature = "gif")]
+use crate::animation::FrameSequence;
+use crate::metadata::container::PNG_SIGNATURE;
+use crate::metadata::{find_exif, Exif};
+#[cfg(feature = "gif")]
+use crate::quantize::QuantizeOptions;
+use crate::transform::{apply_orientation, RgbaImage};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Image formats with a decoder or encoder in this crate
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Bmp = 0,
+    /// Decode only
+    Jpeg = 1,
+    Png = 2,
+    /// Animations decode to their first frame; encoding quantizes to 256 colors
+    Gif = 3,
+    /// Lossless on encode
+    WebP = 4,
+}
+
+impl ImageFormat {
+    /// Usual file extension, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::Gif => "gif",
+            ImageFormat::WebP => "webp",
+        }
+    }
+
+    /// Format for a file extension or format name, ignoring case
+    pub fn from_extension(extension: &str) -> Option<ImageFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "bmp" | "dib" => Some(ImageFormat::Bmp),
+            "jpg" | "jpeg" | "jpe" | "jfif" => Some(ImageFormat::Jpeg),
+            "png" | "apng" => Some(ImageFormat::Png),
+            "gif" => Some(ImageFormat::Gif),
+            "webp" => Some(ImageFormat::WebP),
+            _ => None,
+        }
+    }
+}
+
+/// Options for `decode_auto` and `convert`
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConvertOptions {
+    /// Rotate/flip to upright using the EXIF orientation tag
+    pub auto_orient: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ConvertOptions {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> ConvertOptions {
+        ConvertOptions { auto_orient: true }
+    }
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Identify a supported format from its signature
+pub fn detect_format(data: &[u8]) -> Option<ImageFormat> {
+    if data.starts_with(b"BM") {
+        return Some(ImageFormat::Bmp);
+    }
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(ImageFormat::Jpeg);
+    }
+    if data.starts_with(&PNG_SIGNATURE) {
+        return Some(ImageFormat::Png);
+    }
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        return Some(ImageFormat::Gif);
+    }
+    if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        return Some(ImageFormat::WebP);
+    }
+    None
+}
+
+/// Error for a format whose codec was left out of this build
+pub(crate) fn not_built(format: impl std::fmt::Debug) -> String {
+    format!("{:?} support was left out of this build", format)
+}
+
+/// Split the `[width, height, rgba...]` layout used by the decoders
+#[cfg_attr(not(any(feature = "bmp", feature = "jpeg", feature = "png", feature = "webp")), allow(dead_code))]
+pub(crate) fn unpack(decoded: Vec<u8>) -> RgbaImage {
+    let width = u32::from_le_bytes([decoded[0], decoded[1], decoded[2], decoded[3]]);
+    let height = u32::from_le_bytes([decoded[4], decoded[5], decoded[6], decoded[7]]);
+    RgbaImage {
+        width,
+        height,
+        data: decoded[8..].to_vec(),
+    }
+}
+
+/// Decode an image known to be in `format`
+#[cfg_attr(not(any(feature = "bmp", feature = "jpeg", feature = "png", feature = "gif", feature = "webp")), allow(unused_variables))]
+fn decode_format(data: &[u8], format: ImageFormat) -> Result<RgbaImage, String> {
+    match format {
+        #[cfg(feature = "bmp")]
+        ImageFormat::Bmp => Ok(unpack(crate::bmp::decode_bmp(data)?)),
+        #[cfg(feature = "jpeg")]
+        ImageFormat::Jpeg => Ok(unpack(crate::jpeg::decode_jpeg(data)?)),
+        #[cfg(feature = "png")]
+        ImageFormat::Png => Ok(unpack(crate::png::decode_png(data)?)),
+        #[cfg(feature = "gif")]
+        ImageFormat::Gif => {
+            let sequence = crate::gif::decode_gif(data)?;
+            Ok(RgbaImage { width: sequence.width(), height: sequence.height(), data: sequence.frames()[0].data.clone() })
+        }
+        #[cfg(feature = "webp")]
+        ImageFormat::WebP => Ok(unpack(crate::webp::decode_webp(data)?)),
+        #[allow(unreachable_patterns)]
+        format => Err(not_built(format)),
+    }
+}
+
+/// Decode any supported format to RGBA
+pub fn decode_auto(data: &[u8], options: &ConvertOptions) -> Result<RgbaImage, String> {
+    let format = detect_format(data).ok_or("Unknown or unsupported image format")?;
+    let image = decode_format(data, format)?;
+
+    if options.auto_orient {
+        let orientation = find_exif(data).and_then(|tiff| Exif::parse(tiff).ok()).and_then(|exif| exif.orientation());
+        if let Some(orientation) = orientation.filter(|&o| o != 1) {
+            return apply_orientation(&image.data, image.width, image.height, orientation);
+        }
+    }
+    Ok(image)
+}
+
+/// Encode RGBA to the given format
+#[cfg_attr(not(any(feature = "bmp", feature = "png", feature = "gif", feature = "webp")), allow(unused_variables))]
+pub fn encode(image: &RgbaImage, format: ImageFormat) -> Result<Vec<u8>, String> {
+    match format {
+        #[cfg(feature = "bmp")]
+        ImageFormat::Bmp => crate::bmp::encode_bmp(image.width, image.height, &image.data),
+        ImageFormat::Jpeg => Err("JPEG encoding is not supported".to_string()),
+        #[cfg(feature = "png")]
+        ImageFormat::Png => crate::png::encode_png(image.width, image.height, &image.data),
+        #[cfg(feature = "gif")]
+        ImageFormat::Gif => {
+            let mut sequence = FrameSequence::new(image.width, image.height);
+            sequence.push(image.data.clone(), 0)?;
+            sequence.loop_count = 1;
+            crate::gif::encode_gif(&sequence, &QuantizeOptions::new(256))
+        }
+        #[cfg(feature = "webp")]
+        ImageFormat::WebP => crate::webp::encode_webp(image.width, image.height, &image.data),
+        #[allow(unreachable_patterns)]
+        format => Err(not_built(format)),
+    }
+}
+
+/// Decode any supported format and re-encode it as `format`
+pub fn convert(data: &[u8], format: ImageFormat, options: &ConvertOptions) -> Result<Vec<u8>, String> {
+    encode(&decode_auto(data, options)?, format)
+}
+
+/// Decode any supported image to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeAuto)]
+pub fn decode_auto_js(data: &[u8], options: &ConvertOptions) -> Result<RgbaImage, JsError> {
+    decode_auto(data, options).map_err(|e| JsError::new(&e))
+}
+
+/// Convert an image to another format
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = convert)]
+pub fn convert_js(data: &[u8], format: ImageFormat, options: &ConvertOptions) -> Result<Vec<u8>, JsError> {
+    convert(data, format, options).map_err(|e| JsError::new(&e))
+}
+
+/// Convert an array of encoded images, returning a result for each
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = convertBatch)]
+pub fn convert_batch_js(inputs: js_sys::Array, format: ImageFormat, options: &ConvertOptions) -> Vec<BatchResult> {
+    let inputs: Vec<Vec<u8>> = inputs.iter().map(|input| js_sys::Uint8Array::new(&input).to_vec()).collect();
+    convert_batch(&inputs, format, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "bmp")]
+    fn test_bmp_roundtrip() {
+        let data = [1u8, 2, 3, 255, 4, 5, 6, 255];
+        let bmp = crate::bmp::encode_bmp(2, 1, &data).unwrap();
+        assert_eq!(detect_format(&bmp), Some(ImageFormat::Bmp));
+
+        let image = decode_auto(&bmp, &ConvertOptions::new()).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.data, data);
+        assert_eq!(convert(&bmp, ImageFormat::Bmp, &ConvertOptions::new()).unwrap(), bmp);
+        assert!(decode_auto(b"nope", &ConvertOptions::new()).is_err());
+    }
+
+    #[test]
+    fn test_format_extensions() {
+        for format in [ImageFormat::Bmp, ImageFormat::Jpeg, ImageFormat::Png, ImageFormat::Gif, ImageFormat::WebP] {
+            assert_eq!(ImageFormat::from_extension(format.extension()), Some(format));
+        }
+        assert_eq!(ImageFormat::from_extension("JPEG"), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::from_extension("tiff"), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "bmp", feature = "png", feature = "gif", feature = "webp"))]
+    fn test_convert_between_formats() {
+        let data = [255u8, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0, 9, 9, 9, 255];
+        let png = crate::png::encode_png(2, 2, &data).unwrap();
+        for format in [ImageFormat::Png, ImageFormat::WebP, ImageFormat::Bmp] {
+            let converted = convert(&png, format, &ConvertOptions::new()).unwrap();
+            assert_eq!(detect_format(&converted), Some(format));
+            assert_eq!(decode_auto(&converted, &ConvertOptions::new()).unwrap().data, data);
+        }
+        let gif = convert(&png, ImageFormat::Gif, &ConvertOptions::new()).unwrap();
+        let image = decode_auto(&gif, &ConvertOptions::new()).unwrap();
+        assert_eq!(image.data, [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 0, 0, 9, 9, 9, 255]);
+    }
+}
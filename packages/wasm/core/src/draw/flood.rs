@@ -0,0 +1,77 @@
+//! Flood fill of contiguous regions
+
+use crate::utils::check_rgba;
+
+/// Replace the 4-connected region around (`x`, `y`) whose pixels differ from
+/// the seed by at most `tolerance` in every channel with `color`
+pub fn flood_fill(data: &[u8], width: u32, height: u32, x: u32, y: u32, color: [u8; 4], tolerance: u8) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if x >= width || y >= height {
+        return Err(format!("Seed ({}, {}) is outside the {}x{} image", x, y, width, height));
+    }
+    let (width, height) = (width as usize, height as usize);
+    let pixel = |i: usize| &data[i * 4..i * 4 + 4];
+    let seed: [u8; 4] = pixel(y as usize * width + x as usize).try_into().unwrap();
+    let matches = |i: usize| pixel(i).iter().zip(seed).all(|(&a, b)| a.abs_diff(b) <= tolerance);
+
+    let mut out = data.to_vec();
+    let mut filled = vec![false; width * height];
+    let mut stack = vec![(x as usize, y as usize)];
+    while let Some((x, y)) = stack.pop() {
+        let row = y * width;
+        if filled[row + x] {
+            continue;
+        }
+        // Extend the span left and right, then queue the rows above and below
+        let mut left = x;
+        while left > 0 && !filled[row + left - 1] && matches(row + left - 1) {
+            left -= 1;
+        }
+        let mut right = x + 1;
+        while right < width && !filled[row + right] && matches(row + right) {
+            right += 1;
+        }
+        for i in row + left..row + right {
+            filled[i] = true;
+            out[i * 4..i * 4 + 4].copy_from_slice(&color);
+        }
+        for ny in [y.wrapping_sub(1), y + 1].into_iter().filter(|&ny| ny < height) {
+            let mut inside = false;
+            for nx in left..right {
+                let i = ny * width + nx;
+                let open = !filled[i] && matches(i);
+                if open && !inside {
+                    stack.push((nx, ny));
+                }
+                inside = open;
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flood_fill() {
+        // A white ring splits the black image into an inside and an outside
+        let (w, b) = ([255, 255, 255, 255], [0, 0, 0, 255]);
+        let rows = ["#####", "#...#", "#.#.#", "#...#", "#####", "....."];
+        let data: Vec<u8> = rows.iter().flat_map(|r| r.bytes()).flat_map(|c| if c == b'#' { w } else { b }).collect();
+        let out = flood_fill(&data, 5, 6, 1, 1, [255, 0, 0, 255], 0).unwrap();
+        let red: Vec<bool> = out.chunks_exact(4).map(|p| p == [255, 0, 0, 255]).collect();
+        assert_eq!(red.iter().filter(|&&r| r).count(), 8);
+        assert!(red[6] && red[18] && !red[12] && !red[25]);
+
+        // Within tolerance the fill crosses the slightly different pixel
+        let mut gray = [100, 100, 100, 255].repeat(3);
+        gray[4] = 110;
+        assert_eq!(flood_fill(&gray, 3, 1, 0, 0, [0, 0, 0, 0], 5).unwrap()[8..], [100, 100, 100, 255]);
+        assert_eq!(flood_fill(&gray, 3, 1, 0, 0, [0, 0, 0, 0], 10).unwrap(), vec![0; 12]);
+        // Refilling with the seed color terminates
+        assert_eq!(flood_fill(&gray, 3, 1, 2, 0, [100, 100, 100, 255], 0).unwrap(), gray);
+        assert!(flood_fill(&gray, 3, 1, 3, 0, b, 0).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Linear and radial gradient fills
+
+/// Validate stops: at least one, offsets finite and non-decreasing
+fn check_stops(stops: &[(f32, [u8; 4])]) -> Result<(), String> {
+    if stops.is_empty() {
+        return Err("Gradient needs at least one color stop".to_string());
+    }
+    if stops.iter().any(|s| !s.0.is_finite()) || stops.windows(2).any(|w| w[1].0 < w[0].0) {
+        return Err("Gradient stop offsets must be finite and in order".to_string());
+    }
+    Ok(())
+}
+
+/// Color at `t` along the stops, interpolated with premultiplied alpha so a
+/// fade to transparent keeps its hue
+fn sample(stops: &[(f32, [u8; 4])], t: f32) -> [u8; 4] {
+    let next = stops.partition_point(|s| s.0 <= t);
+    if next == 0 {
+        return stops[0].1;
+    }
+    if next == stops.len() {
+        return stops[next - 1].1;
+    }
+    let ((o0, c0), (o1, c1)) = (stops[next - 1], stops[next]);
+    let f = (t - o0) / (o1 - o0);
+    let alpha = c0[3] as f32 * (1.0 - f) + c1[3] as f32 * f;
+    if alpha == 0.0 {
+        return [0; 4];
+    }
+    let channel = |c: usize| ((c0[c] as f32 * c0[3] as f32 * (1.0 - f) + c1[c] as f32 * c1[3] as f32 * f) / alpha).round() as u8;
+    [channel(0), channel(1), channel(2), alpha.round() as u8]
+}
+
+fn fill(width: u32, height: u32, stops: &[(f32, [u8; 4])], t: impl Fn(f32, f32) -> f32) -> Result<Vec<u8>, String> {
+    check_stops(stops)?;
+    if width == 0 || height == 0 {
+        return Err("Gradient size must be non-zero".to_string());
+    }
+    let mut out = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            out.extend_from_slice(&sample(stops, t(x as f32 + 0.5, y as f32 + 0.5)));
+        }
+    }
+    Ok(out)
+}
+
+/// A `width` x `height` image shaded from (`x0`, `y0`) to (`x1`, `y1`)
+///
+/// `stops` are `(offset, color)` pairs with offsets in order, 0 at the
+/// start point and 1 at the end; beyond them the nearest color extends.
+#[allow(clippy::too_many_arguments)]
+pub fn linear_gradient(width: u32, height: u32, x0: f32, y0: f32, x1: f32, y1: f32, stops: &[(f32, [u8; 4])]) -> Result<Vec<u8>, String> {
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let length = dx * dx + dy * dy;
+    if !(length > 0.0 && length.is_finite()) {
+        return Err("Gradient start and end points must differ".to_string());
+    }
+    fill(width, height, stops, |x, y| ((x - x0) * dx + (y - y0) * dy) / length)
+}
+
+/// A `width` x `height` image shaded outwards from (`cx`, `cy`), reaching
+/// offset 1 at `radius`
+pub fn radial_gradient(width: u32, height: u32, cx: f32, cy: f32, radius: f32, stops: &[(f32, [u8; 4])]) -> Result<Vec<u8>, String> {
+    if !(radius > 0.0 && radius.is_finite()) {
+        return Err("Gradient radius must be positive".to_string());
+    }
+    fill(width, height, stops, |x, y| (x - cx).hypot(y - cy) / radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gradients() {
+        let stops = [(0.0, [0, 0, 0, 255]), (1.0, [255, 255, 255, 255])];
+        let ramp = linear_gradient(4, 1, 0.0, 0.0, 4.0, 0.0, &stops).unwrap();
+        assert_eq!(ramp.chunks_exact(4).map(|p| p[0]).collect::<Vec<_>>(), [32, 96, 159, 223]);
+        // Vertical gradient rows are uniform
+        let vertical = linear_gradient(3, 2, 0.0, 0.0, 0.0, 2.0, &stops).unwrap();
+        assert_eq!(vertical[..12], [64, 64, 64, 255].repeat(3));
+
+        let radial = radial_gradient(5, 5, 2.5, 2.5, 2.0, &stops).unwrap();
+        assert_eq!(&radial[48..52], [0, 0, 0, 255]);
+        assert_eq!(&radial[..4], [255, 255, 255, 255]);
+
+        // Fading red to transparent stays red rather than darkening
+        let fade = linear_gradient(2, 1, 0.0, 0.0, 2.0, 0.0, &[(0.0, [255, 0, 0, 255]), (1.0, [0, 0, 0, 0])]).unwrap();
+        assert_eq!(&fade[4..], [255, 0, 0, 64]);
+
+        assert!(linear_gradient(2, 2, 1.0, 1.0, 1.0, 1.0, &stops).is_err());
+        assert!(radial_gradient(2, 2, 1.0, 1.0, 1.0, &[]).is_err());
+        assert!(radial_gradient(2, 2, 1.0, 1.0, 1.0, &[(1.0, [0; 4]), (0.5, [0; 4])]).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Shape masks and borders for avatars and thumbnails
+
+use super::raster::{Point, Rasterizer};
+use super::shapes::{circle_points, rounded_rect_points};
+use crate::animation::blend_over;
+use crate::transform::RgbaImage;
+use crate::utils::check_rgba;
+
+/// Scale each pixel's alpha by how much of it lies inside `contour`
+fn apply_mask(data: &[u8], width: u32, height: u32, contour: &[Point]) -> Vec<u8> {
+    let mut raster = Rasterizer::new(width as usize, height as usize);
+    for (i, &p) in contour.iter().enumerate() {
+        raster.line(p, contour[(i + 1) % contour.len()]);
+    }
+    let mut out = data.to_vec();
+    for (pixel, cover) in out.chunks_exact_mut(4).zip(raster.coverage()) {
+        pixel[3] = ((pixel[3] as u32 * cover as u32 + 127) / 255) as u8;
+    }
+    out
+}
+
+/// Make the corners transparent outside quarter circles of `radius`
+pub fn round_corners(data: &[u8], width: u32, height: u32, radius: f32) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if radius.is_nan() || radius < 0.0 {
+        return Err("Corner radius must not be negative".to_string());
+    }
+    Ok(apply_mask(data, width, height, &rounded_rect_points(0.0, 0.0, width as f32, height as f32, radius)))
+}
+
+/// Keep only the largest circle centred in the image
+pub fn circle_mask(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    let radius = width.min(height) as f32 / 2.0;
+    Ok(apply_mask(data, width, height, &circle_points(width as f32 / 2.0, height as f32 / 2.0, radius, false)))
+}
+
+/// Surround the image with a `border_width` frame of `color`
+///
+/// The frame's outer corners are rounded by `corner_radius + border_width`,
+/// so passing the radius given to `round_corners` (or half the side after
+/// `circle_mask`) keeps the frame an even width around the shape. The image
+/// is drawn over the frame, which shows through its transparent parts.
+pub fn add_border(data: &[u8], width: u32, height: u32, border_width: u32, color: [u8; 4], corner_radius: f32) -> Result<RgbaImage, String> {
+    check_rgba(data, width, height)?;
+    if width == 0 || height == 0 {
+        return Err("Image is empty".to_string());
+    }
+    if corner_radius.is_nan() || corner_radius < 0.0 {
+        return Err("Corner radius must not be negative".to_string());
+    }
+    let grow = |side: u32| side.checked_add(border_width.checked_mul(2)?).filter(|&s| s <= 1 << 16);
+    let (Some(out_width), Some(out_height)) = (grow(width), grow(height)) else {
+        return Err("Bordered image is too large".to_string());
+    };
+
+    let frame = color.repeat(out_width as usize * out_height as usize);
+    let radius = if corner_radius > 0.0 { corner_radius + border_width as f32 } else { 0.0 };
+    let mut out = apply_mask(&frame, out_width, out_height, &rounded_rect_points(0.0, 0.0, out_width as f32, out_height as f32, radius));
+    for (y, row) in data.chunks_exact(width as usize * 4).enumerate() {
+        let start = ((y + border_width as usize) * out_width as usize + border_width as usize) * 4;
+        for (dst, src) in out[start..start + row.len()].chunks_exact_mut(4).zip(row.chunks_exact(4)) {
+            blend_over(dst, src);
+        }
+    }
+    Ok(RgbaImage { width: out_width, height: out_height, data: out })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alphas(data: &[u8]) -> Vec<u8> {
+        data.chunks_exact(4).map(|p| p[3]).collect()
+    }
+
+    #[test]
+    fn test_round_and_circle() {
+        let white = [255; 4].repeat(100);
+        let rounded = alphas(&round_corners(&white, 10, 10, 4.0).unwrap());
+        assert_eq!((rounded[0], rounded[4], rounded[44], rounded[99]), (0, 255, 255, 0));
+        // The corner arc passes partly through the pixel on its diagonal
+        assert!(rounded[11] > 0 && rounded[11] < 255);
+        assert_eq!(round_corners(&white, 10, 10, 0.0).unwrap(), white);
+        assert!(round_corners(&white, 10, 10, -1.0).is_err());
+
+        let circle = alphas(&circle_mask(&white, 10, 10).unwrap());
+        assert_eq!((circle[0], circle[15], circle[55], circle[51]), (0, 255, 255, 255));
+        assert!(circle[5] > 192 && circle[5] < 255);
+        let covered: u32 = circle.iter().map(|&a| a as u32).sum();
+        assert!((covered as f32 / 255.0 - std::f32::consts::PI * 25.0).abs() < 0.5);
+        // Existing transparency is kept
+        let half = [255, 255, 255, 128].repeat(100);
+        assert_eq!(alphas(&circle_mask(&half, 10, 10).unwrap())[55], 128);
+    }
+
+    #[test]
+    fn test_border() {
+        let red = [255, 0, 0, 255];
+        let bordered = add_border(&[0, 0, 255, 255].repeat(4), 2, 2, 1, red, 0.0).unwrap();
+        assert_eq!((bordered.width, bordered.height), (4, 4));
+        assert_eq!(&bordered.data[..4], red);
+        assert_eq!(&bordered.data[20..24], [0, 0, 255, 255]);
+
+        // A circle's frame is a ring; its outside corner stays transparent
+        let disc = circle_mask(&[0, 0, 255, 255].repeat(64), 8, 8).unwrap();
+        let ringed = add_border(&disc, 8, 8, 2, red, 4.0).unwrap();
+        assert_eq!((ringed.width, ringed.height), (12, 12));
+        assert_eq!(ringed.data[3], 0);
+        assert_eq!(&ringed.data[(6 * 12 + 1) * 4..(6 * 12 + 2) * 4], red);
+        assert_eq!(&ringed.data[(6 * 12 + 6) * 4..(6 * 12 + 7) * 4], [0, 0, 255, 255]);
+        assert!(add_border(&disc, 8, 8, u32::MAX, red, 0.0).is_err());
+        for (width, height) in [(0, 0), (0, 3), (3, 0)] {
+            assert_eq!(add_border(&[], width, height, 2, red, 0.0).unwrap_err(), "Image is empty");
+            assert!(round_corners(&[], width, height, 2.0).unwrap().is_empty());
+            assert!(circle_mask(&[], width, height).unwrap().is_empty());
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! 2D drawing onto RGBA images: anti-aliased lines, rectangles, circles and
+//! polygons, flood fill, and rounded or circular masks with borders; plus
+//! gradient fills and test patterns generated from scratch
+//!
+//! Coordinates are in pixels with (0, 0) at the top-left corner of the first
+//! pixel, so a pixel's centre is at (x + 0.5, y + 0.5). Shapes blend over
+//! the image and may extend beyond it.
+
+mod flood;
+mod gradient;
+mod mask;
+mod pattern;
+pub(crate) mod raster;
+mod shapes;
+
+pub use flood::flood_fill;
+pub use gradient::{linear_gradient, radial_gradient};
+pub use mask::{add_border, circle_mask, round_corners};
+pub use pattern::{generate, TestPattern};
+pub use shapes::{draw_circle, draw_line, draw_polygon, draw_rect};
+
+#[cfg(feature = "wasm")]
+use crate::transform::RgbaImage;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+fn color_js(color: &[u8]) -> Result<[u8; 4], JsError> {
+    color.try_into().map_err(|_| JsError::new("Color must have 4 components"))
+}
+
+/// Pair stop offsets with `[r, g, b, a]` colors packed four bytes per stop
+#[cfg(feature = "wasm")]
+fn stops_js(offsets: &[f32], colors: &[u8]) -> Result<Vec<(f32, [u8; 4])>, JsError> {
+    if colors.len() != offsets.len() * 4 {
+        return Err(JsError::new("Each gradient stop needs 4 color components"));
+    }
+    Ok(offsets.iter().zip(colors.chunks_exact(4)).map(|(&o, c)| (o, [c[0], c[1], c[2], c[3]])).collect())
+}
+
+/// Draw a line `thickness` pixels wide; `color` is `[r, g, b, a]`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = drawLine)]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_line_js(data: &[u8], width: u32, height: u32, x0: f32, y0: f32, x1: f32, y1: f32, thickness: f32, color: &[u8]) -> Result<Vec<u8>, JsError> {
+    draw_line(data, width, height, x0, y0, x1, y1, thickness, color_js(color)?).map_err(|e| JsError::new(&e))
+}
+
+/// Draw a rectangle, filled when `strokeWidth` is 0
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = drawRect)]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_rect_js(data: &[u8], width: u32, height: u32, x: f32, y: f32, rect_width: f32, rect_height: f32, stroke_width: f32, color: &[u8]) -> Result<Vec<u8>, JsError> {
+    draw_rect(data, width, height, x, y, rect_width, rect_height, stroke_width, color_js(color)?).map_err(|e| JsError::new(&e))
+}
+
+/// Draw a circle, filled when `strokeWidth` is 0
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = drawCircle)]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_circle_js(data: &[u8], width: u32, height: u32, cx: f32, cy: f32, radius: f32, stroke_width: f32, color: &[u8]) -> Result<Vec<u8>, JsError> {
+    draw_circle(data, width, height, cx, cy, radius, stroke_width, color_js(color)?).map_err(|e| JsError::new(&e))
+}
+
+/// Fill a polygon given as flat `[x0, y0, x1, y1, ...]` coordinates
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = drawPolygon)]
+pub fn draw_polygon_js(data: &[u8], width: u32, height: u32, points: &[f32], color: &[u8]) -> Result<Vec<u8>, JsError> {
+    draw_polygon(data, width, height, points, color_js(color)?).map_err(|e| JsError::new(&e))
+}
+
+/// Flood fill the region around (`x`, `y`) within `tolerance` of its color
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = floodFill)]
+pub fn flood_fill_js(data: &[u8], width: u32, height: u32, x: u32, y: u32, color: &[u8], tolerance: u8) -> Result<Vec<u8>, JsError> {
+    flood_fill(data, width, height, x, y, color_js(color)?, tolerance).map_err(|e| JsError::new(&e))
+}
+
+/// Make the corners transparent outside quarter circles of `radius`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = roundCorners)]
+pub fn round_corners_js(data: &[u8], width: u32, height: u32, radius: f32) -> Result<Vec<u8>, JsError> {
+    round_corners(data, width, height, radius).map_err(|e| JsError::new(&e))
+}
+
+/// Keep only the largest circle centred in the image
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = circleMask)]
+pub fn circle_mask_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    circle_mask(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Surround the image with a frame; pass the corner radius used to round or
+/// mask it so the frame follows the shape
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = addBorder)]
+pub fn add_border_js(data: &[u8], width: u32, height: u32, border_width: u32, color: &[u8], corner_radius: f32) -> Result<RgbaImage, JsError> {
+    add_border(data, width, height, border_width, color_js(color)?, corner_radius).map_err(|e| JsError::new(&e))
+}
+
+/// Linear gradient image from (`x0`, `y0`) to (`x1`, `y1`); `colors` holds
+/// four bytes for each of the `offsets`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = linearGradient)]
+#[allow(clippy::too_many_arguments)]
+pub fn linear_gradient_js(width: u32, height: u32, x0: f32, y0: f32, x1: f32, y1: f32, offsets: &[f32], colors: &[u8]) -> Result<Vec<u8>, JsError> {
+    linear_gradient(width, height, x0, y0, x1, y1, &stops_js(offsets, colors)?).map_err(|e| JsError::new(&e))
+}
+
+/// Radial gradient image around (`cx`, `cy`) reaching offset 1 at `radius`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = radialGradient)]
+pub fn radial_gradient_js(width: u32, height: u32, cx: f32, cy: f32, radius: f32, offsets: &[f32], colors: &[u8]) -> Result<Vec<u8>, JsError> {
+    radial_gradient(width, height, cx, cy, radius, &stops_js(offsets, colors)?).map_err(|e| JsError::new(&e))
+}
+
+/// Render a test pattern or noise image
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = generate)]
+pub fn generate_js(pattern: TestPattern, width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    generate(pattern, width, height).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,160 @@
+//! Test patterns and procedural noise
+//!
+//! Noise is seeded with a fixed value, so each pattern is the same on every
+//! run and can serve as a test fixture.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Patterns that `generate` can produce
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestPattern {
+    /// Seven vertical 75% bars: white, yellow, cyan, green, magenta, red, blue
+    ColorBars = 0,
+    /// An 8x8 board of black and white squares
+    Checkerboard = 1,
+    /// Horizontal 0-255 ramps in four bands: gray, red, green, blue
+    GradientRamp = 2,
+    /// Smooth grayscale Perlin noise, four octaves
+    PerlinNoise = 3,
+    /// Independent random RGB per pixel
+    WhiteNoise = 4,
+}
+
+/// Deterministic xorshift noise
+struct Noise(u32);
+
+impl Noise {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
+
+/// Improved Perlin noise over a shuffled 256-entry permutation
+struct Perlin {
+    perm: [u8; 512],
+}
+
+impl Perlin {
+    fn new(noise: &mut Noise) -> Perlin {
+        let mut table: Vec<u8> = (0..=255).collect();
+        for i in (1..256).rev() {
+            table.swap(i, noise.next() as usize % (i + 1));
+        }
+        let mut perm = [0; 512];
+        for (i, p) in perm.iter_mut().enumerate() {
+            *p = table[i & 255];
+        }
+        Perlin { perm }
+    }
+
+    fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+        match hash & 7 {
+            0 => x + y,
+            1 => x - y,
+            2 => -x + y,
+            3 => -x - y,
+            4 => x,
+            5 => -x,
+            6 => y,
+            _ => -y,
+        }
+    }
+
+    /// Noise at a point, roughly in -1..1
+    fn at(&self, x: f32, y: f32) -> f32 {
+        let (xi, yi) = (x.floor() as i32 & 255, y.floor() as i32 & 255);
+        let (xf, yf) = (x - x.floor(), y - y.floor());
+        let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let (u, v) = (fade(xf), fade(yf));
+        let hash = |dx: i32, dy: i32| self.perm[self.perm[(xi + dx) as usize] as usize + (yi + dy) as usize];
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let top = lerp(Self::gradient(hash(0, 0), xf, yf), Self::gradient(hash(1, 0), xf - 1.0, yf), u);
+        let bottom = lerp(Self::gradient(hash(0, 1), xf, yf - 1.0), Self::gradient(hash(1, 1), xf - 1.0, yf - 1.0), u);
+        lerp(top, bottom, v)
+    }
+}
+
+/// Render `pattern` as a `width` x `height` opaque RGBA image
+pub fn generate(pattern: TestPattern, width: u32, height: u32) -> Result<Vec<u8>, String> {
+    if width == 0 || height == 0 {
+        return Err("Pattern size must be non-zero".to_string());
+    }
+    let (w, h) = (width as usize, height as usize);
+    let mut noise = Noise(0x9E37_79B9);
+    let perlin = (pattern == TestPattern::PerlinNoise).then(|| Perlin::new(&mut noise));
+    // Perlin lattice cells span an eighth of the longer side at the lowest octave
+    let cell = w.max(h) as f32 / 8.0;
+
+    let mut out = Vec::with_capacity(w * h * 4);
+    for y in 0..h {
+        for x in 0..w {
+            let rgb = match pattern {
+                TestPattern::ColorBars => {
+                    const BARS: [[u8; 3]; 7] = [[191, 191, 191], [191, 191, 0], [0, 191, 191], [0, 191, 0], [191, 0, 191], [191, 0, 0], [0, 0, 191]];
+                    BARS[x * 7 / w]
+                }
+                TestPattern::Checkerboard => {
+                    let value = if (x * 8 / w + y * 8 / h).is_multiple_of(2) { 255 } else { 0 };
+                    [value; 3]
+                }
+                TestPattern::GradientRamp => {
+                    let value = if w == 1 { 255 } else { (x * 255 / (w - 1)) as u8 };
+                    match y * 4 / h {
+                        0 => [value; 3],
+                        1 => [value, 0, 0],
+                        2 => [0, value, 0],
+                        _ => [0, 0, value],
+                    }
+                }
+                TestPattern::PerlinNoise => {
+                    let perlin = perlin.as_ref().unwrap();
+                    let (px, py) = ((x as f32 + 0.5) / cell, (y as f32 + 0.5) / cell);
+                    let sum: f32 = (0..4).map(|o| perlin.at(px * (1 << o) as f32, py * (1 << o) as f32) / (1 << o) as f32).sum();
+                    // The octaves' amplitudes add up to 1.875
+                    [((sum / 1.875 * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0).round() as u8; 3]
+                }
+                TestPattern::WhiteNoise => {
+                    let [r, g, b, _] = noise.next().to_le_bytes();
+                    [r, g, b]
+                }
+            };
+            out.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns() {
+        let at = |data: &[u8], w: usize, x: usize, y: usize| data[(y * w + x) * 4..(y * w + x) * 4 + 4].to_vec();
+        let bars = generate(TestPattern::ColorBars, 14, 2).unwrap();
+        assert_eq!((at(&bars, 14, 0, 1), at(&bars, 14, 13, 0)), (vec![191, 191, 191, 255], vec![0, 0, 191, 255]));
+
+        let board = generate(TestPattern::Checkerboard, 16, 16).unwrap();
+        assert_eq!((at(&board, 16, 1, 1)[0], at(&board, 16, 2, 0)[0], at(&board, 16, 2, 2)[0]), (255, 0, 255));
+
+        let ramp = generate(TestPattern::GradientRamp, 256, 4).unwrap();
+        assert_eq!((at(&ramp, 256, 128, 0), at(&ramp, 256, 255, 3)), (vec![128, 128, 128, 255], vec![0, 0, 255, 255]));
+
+        // Perlin noise is smooth between neighbours but varies across the image
+        let perlin = generate(TestPattern::PerlinNoise, 64, 64).unwrap();
+        let gray: Vec<i32> = perlin.chunks_exact(4).map(|p| p[0] as i32).collect();
+        assert!(gray.windows(2).enumerate().filter(|(i, _)| (i + 1) % 64 != 0).all(|(_, w)| (w[0] - w[1]).abs() < 40));
+        assert!(gray.iter().max().unwrap() - gray.iter().min().unwrap() > 100);
+
+        let white = generate(TestPattern::WhiteNoise, 32, 32).unwrap();
+        assert_eq!(white, generate(TestPattern::WhiteNoise, 32, 32).unwrap());
+        let mean = white.chunks_exact(4).map(|p| p[0] as f32).sum::<f32>() / 1024.0;
+        assert!((mean - 127.5).abs() < 12.0);
+        assert!(generate(TestPattern::ColorBars, 0, 4).is_err());
+    }
+}
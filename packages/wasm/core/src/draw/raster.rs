@@ -0,0 +1,158 @@
+//! Anti-aliased scanline rasterizer for shapes and glyph outlines
+//!
+//! Accumulates each edge's signed area per pixel, then a running sum along
+//! every row gives the coverage (the approach of font-rs). Overlapping
+//! contours follow the nonzero winding rule.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+pub(crate) struct Rasterizer {
+    width: usize,
+    height: usize,
+    /// Row stride, with slack columns for edges on the right border
+    stride: usize,
+    area: Vec<f32>,
+}
+
+impl Rasterizer {
+    pub fn new(width: usize, height: usize) -> Rasterizer {
+        let stride = width + 2;
+        Rasterizer { width, height, stride, area: vec![0.0; stride * height + 2] }
+    }
+
+    /// Add a straight edge; parts left or right of the raster are clipped
+    /// onto its sides, which leaves the coverage inside unchanged
+    pub fn line(&mut self, p0: Point, p1: Point) {
+        let right = self.width as f32;
+        let mut cuts = [0.0, 1.0, 1.0, 1.0];
+        for (i, edge) in [0.0, right].into_iter().enumerate() {
+            let t = (edge - p0.x) / (p1.x - p0.x);
+            if t > 0.0 && t < 1.0 {
+                cuts[i + 1] = t;
+            }
+        }
+        cuts.sort_by(f32::total_cmp);
+        let at = |t: f32| Point { x: (p0.x + (p1.x - p0.x) * t).clamp(0.0, right), y: p0.y + (p1.y - p0.y) * t };
+        for pair in cuts.windows(2).filter(|pair| pair[1] > pair[0]) {
+            self.edge(at(pair[0]), at(pair[1]));
+        }
+    }
+
+    /// Add an edge lying within 0 <= x <= width
+    fn edge(&mut self, p0: Point, p1: Point) {
+        if (p0.y - p1.y).abs() <= f32::EPSILON {
+            return;
+        }
+        let (dir, p0, p1) = if p0.y < p1.y { (1.0, p0, p1) } else { (-1.0, p1, p0) };
+        let dxdy = (p1.x - p0.x) / (p1.y - p0.y);
+        let mut x = p0.x;
+        if p0.y < 0.0 {
+            x -= p0.y * dxdy;
+        }
+        for y in p0.y.max(0.0) as usize..self.height.min(p1.y.ceil() as usize) {
+            let row = y * self.stride;
+            let dy = ((y + 1) as f32).min(p1.y) - (y as f32).max(p0.y);
+            let x_next = x + dxdy * dy;
+            let d = dy * dir;
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let (x0_floor, x1_ceil) = (x0.floor(), x1.ceil());
+            let (x0i, x1i) = (x0_floor as usize, x1_ceil as usize);
+            if x1i <= x0i + 1 {
+                let mid = 0.5 * (x + x_next) - x0_floor;
+                self.area[row + x0i] += d - d * mid;
+                self.area[row + x0i + 1] += d * mid;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0f = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+                let x1f = x1 - x1_ceil + 1.0;
+                let am = 0.5 * s * x1f * x1f;
+                self.area[row + x0i] += d * a0;
+                if x1i == x0i + 2 {
+                    self.area[row + x0i + 1] += d * (1.0 - a0 - am);
+                } else {
+                    let a1 = s * (1.5 - x0f);
+                    self.area[row + x0i + 1] += d * (a1 - a0);
+                    for xi in x0i + 2..x1i - 1 {
+                        self.area[row + xi] += d * s;
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                    self.area[row + x1i - 1] += d * (1.0 - a2 - am);
+                }
+                self.area[row + x1i] += d * am;
+            }
+            x = x_next;
+        }
+    }
+
+    /// Add a quadratic Bezier, flattened to lines
+    pub fn quad(&mut self, p0: Point, p1: Point, p2: Point) {
+        let (dx, dy) = (p0.x - 2.0 * p1.x + p2.x, p0.y - 2.0 * p1.y + p2.y);
+        let deviation = dx * dx + dy * dy;
+        if deviation < 0.333 {
+            self.line(p0, p2);
+            return;
+        }
+        let n = 1 + (3.0 * deviation).sqrt().sqrt().floor() as usize;
+        let mut prev = p0;
+        for i in 1..=n {
+            let t = i as f32 / n as f32;
+            let u = 1.0 - t;
+            let next = Point { x: u * u * p0.x + 2.0 * u * t * p1.x + t * t * p2.x, y: u * u * p0.y + 2.0 * u * t * p1.y + t * t * p2.y };
+            self.line(prev, next);
+            prev = next;
+        }
+    }
+
+    /// Coverage per pixel, 0-255
+    pub fn coverage(&self) -> Vec<u8> {
+        let mut sum = 0.0f32;
+        let mut out = Vec::with_capacity(self.width * self.height);
+        for row in self.area.chunks_exact(self.stride).take(self.height) {
+            for (x, a) in row.iter().enumerate() {
+                sum += a;
+                if x < self.width {
+                    out.push((sum.abs().min(1.0) * 255.0 + 0.5) as u8);
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage() {
+        // A 2x2 square offset by half a pixel covers quarters, halves and a whole pixel
+        let mut r = Rasterizer::new(4, 4);
+        let p = |x, y| Point { x, y };
+        let corners = [p(0.5, 0.5), p(2.5, 0.5), p(2.5, 2.5), p(0.5, 2.5)];
+        for i in 0..4 {
+            r.line(corners[i], corners[(i + 1) % 4]);
+        }
+        let coverage = r.coverage();
+        assert_eq!(&coverage[..4], [64, 128, 64, 0]);
+        assert_eq!(&coverage[4..8], [128, 255, 128, 0]);
+        assert_eq!(&coverage[12..], [0, 0, 0, 0]);
+
+        // Edges beyond either side are clipped without changing what is inside
+        let mut r = Rasterizer::new(2, 1);
+        let corners = [p(-3.0, 0.0), p(1.5, 0.0), p(1.5, 1.0), p(-3.0, 1.0)];
+        for i in 0..4 {
+            r.line(corners[i], corners[(i + 1) % 4]);
+        }
+        assert_eq!(r.coverage(), [255, 128]);
+        let mut r = Rasterizer::new(2, 1);
+        r.line(p(1.0, 0.0), p(5.0, 0.0));
+        r.line(p(5.0, 0.0), p(1.0, 1.0));
+        r.line(p(1.0, 1.0), p(1.0, 0.0));
+        assert_eq!(r.coverage(), [0, 223]);
+    }
+}
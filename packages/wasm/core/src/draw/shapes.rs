@@ -0,0 +1,197 @@
+//! Anti-aliased lines, rectangles, circles and polygons
+
+use super::raster::{Point, Rasterizer};
+use crate::animation::blend_over;
+use crate::utils::check_rgba;
+use std::f32::consts::PI;
+
+/// Fill closed contours with `color` by the nonzero rule, blending over the
+/// image; only the part of their bounding box inside the image is rasterized
+fn fill_contours(data: &[u8], width: u32, height: u32, contours: &[Vec<Point>], color: [u8; 4]) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    let mut out = data.to_vec();
+    let points = || contours.iter().flatten();
+    if points().any(|p| !p.x.is_finite() || !p.y.is_finite()) {
+        return Err("Shape coordinates must be finite".to_string());
+    }
+    let min = |f: fn(&Point) -> f32| points().map(f).fold(f32::INFINITY, f32::min);
+    let max = |f: fn(&Point) -> f32| points().map(f).fold(f32::NEG_INFINITY, f32::max);
+    let x0 = min(|p| p.x).floor().max(0.0) as u32;
+    let y0 = min(|p| p.y).floor().max(0.0) as u32;
+    let x1 = max(|p| p.x).ceil().min(width as f32).max(0.0) as u32;
+    let y1 = max(|p| p.y).ceil().min(height as f32).max(0.0) as u32;
+    if x0 >= x1 || y0 >= y1 {
+        return Ok(out);
+    }
+
+    let (w, h) = ((x1 - x0) as usize, (y1 - y0) as usize);
+    let mut raster = Rasterizer::new(w, h);
+    let shift = |p: &Point| Point { x: p.x - x0 as f32, y: p.y - y0 as f32 };
+    for contour in contours.iter().filter(|c| c.len() >= 3) {
+        for (i, p) in contour.iter().enumerate() {
+            raster.line(shift(p), shift(&contour[(i + 1) % contour.len()]));
+        }
+    }
+    for (i, &cover) in raster.coverage().iter().enumerate().filter(|(_, &c)| c != 0) {
+        let offset = (((y0 as usize + i / w) * width as usize) + x0 as usize + i % w) * 4;
+        let alpha = (color[3] as u32 * cover as u32 + 127) / 255;
+        blend_over(&mut out[offset..offset + 4], &[color[0], color[1], color[2], alpha as u8]);
+    }
+    Ok(out)
+}
+
+/// Segments for a full circle, enough that they stay within a fiftieth of a
+/// pixel of the true curve
+fn circle_segments(radius: f32) -> usize {
+    (radius.sqrt() * 16.0).ceil().max(8.0) as usize
+}
+
+/// Points around a circle; `reverse` flips the winding
+pub(super) fn circle_points(cx: f32, cy: f32, radius: f32, reverse: bool) -> Vec<Point> {
+    let segments = circle_segments(radius);
+    (0..segments)
+        .map(|i| {
+            let i = if reverse { segments - i } else { i };
+            let angle = 2.0 * PI * i as f32 / segments as f32;
+            Point { x: cx + radius * angle.cos(), y: cy + radius * angle.sin() }
+        })
+        .collect()
+}
+
+fn rect_points(x: f32, y: f32, width: f32, height: f32, reverse: bool) -> Vec<Point> {
+    let mut points = vec![Point { x, y }, Point { x: x + width, y }, Point { x: x + width, y: y + height }, Point { x, y: y + height }];
+    if reverse {
+        points.reverse();
+    }
+    points
+}
+
+/// Outline of a rectangle whose corners are quarter circles of `radius`,
+/// limited to half the shorter side
+pub(super) fn rounded_rect_points(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Vec<Point> {
+    let radius = radius.min(width / 2.0).min(height / 2.0);
+    if radius <= 0.0 {
+        return rect_points(x, y, width, height, false);
+    }
+    let quarter = circle_segments(radius).div_ceil(4);
+    let centres = [(x + width - radius, y + height - radius), (x + radius, y + height - radius), (x + radius, y + radius), (x + width - radius, y + radius)];
+    centres
+        .iter()
+        .enumerate()
+        .flat_map(|(corner, &(cx, cy))| {
+            (0..=quarter).map(move |i| {
+                let angle = (corner as f32 + i as f32 / quarter as f32) * PI / 2.0;
+                Point { x: cx + radius * angle.cos(), y: cy + radius * angle.sin() }
+            })
+        })
+        .collect()
+}
+
+/// Draw a line `thickness` pixels wide with flat ends
+#[allow(clippy::too_many_arguments)]
+pub fn draw_line(data: &[u8], width: u32, height: u32, x0: f32, y0: f32, x1: f32, y1: f32, thickness: f32, color: [u8; 4]) -> Result<Vec<u8>, String> {
+    let length = (x1 - x0).hypot(y1 - y0);
+    if thickness <= 0.0 || length == 0.0 {
+        return fill_contours(data, width, height, &[], color);
+    }
+    // Offset perpendicular to the line by half the thickness
+    let (nx, ny) = (-(y1 - y0) / length * thickness / 2.0, (x1 - x0) / length * thickness / 2.0);
+    let quad = vec![Point { x: x0 + nx, y: y0 + ny }, Point { x: x1 + nx, y: y1 + ny }, Point { x: x1 - nx, y: y1 - ny }, Point { x: x0 - nx, y: y0 - ny }];
+    fill_contours(data, width, height, &[quad], color)
+}
+
+/// Draw a rectangle, filled when `stroke_width` is 0 and otherwise outlined
+/// with a border of that width inside its edges
+#[allow(clippy::too_many_arguments)]
+pub fn draw_rect(data: &[u8], width: u32, height: u32, x: f32, y: f32, rect_width: f32, rect_height: f32, stroke_width: f32, color: [u8; 4]) -> Result<Vec<u8>, String> {
+    if rect_width < 0.0 || rect_height < 0.0 || stroke_width < 0.0 {
+        return Err("Rectangle size and stroke width must not be negative".to_string());
+    }
+    let mut contours = vec![rect_points(x, y, rect_width, rect_height, false)];
+    let (inner_width, inner_height) = (rect_width - 2.0 * stroke_width, rect_height - 2.0 * stroke_width);
+    if stroke_width > 0.0 && inner_width > 0.0 && inner_height > 0.0 {
+        contours.push(rect_points(x + stroke_width, y + stroke_width, inner_width, inner_height, true));
+    }
+    fill_contours(data, width, height, &contours, color)
+}
+
+/// Draw a circle, filled when `stroke_width` is 0 and otherwise outlined
+/// with a ring of that width inside its radius
+#[allow(clippy::too_many_arguments)]
+pub fn draw_circle(data: &[u8], width: u32, height: u32, cx: f32, cy: f32, radius: f32, stroke_width: f32, color: [u8; 4]) -> Result<Vec<u8>, String> {
+    if radius < 0.0 || stroke_width < 0.0 {
+        return Err("Radius and stroke width must not be negative".to_string());
+    }
+    let mut contours = vec![circle_points(cx, cy, radius, false)];
+    if stroke_width > 0.0 && stroke_width < radius {
+        contours.push(circle_points(cx, cy, radius - stroke_width, true));
+    }
+    fill_contours(data, width, height, &contours, color)
+}
+
+/// Fill a polygon given as flat `[x0, y0, x1, y1, ...]` coordinates;
+/// self-intersecting outlines are filled by the nonzero winding rule
+pub fn draw_polygon(data: &[u8], width: u32, height: u32, points: &[f32], color: [u8; 4]) -> Result<Vec<u8>, String> {
+    if !points.len().is_multiple_of(2) || points.len() < 6 {
+        return Err("Polygon needs at least three x, y pairs".to_string());
+    }
+    let contour = points.chunks_exact(2).map(|p| Point { x: p[0], y: p[1] }).collect();
+    fill_contours(data, width, height, &[contour], color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+
+    fn alpha(data: &[u8], width: u32, x: u32, y: u32) -> u8 {
+        data[((y * width + x) * 4 + 3) as usize]
+    }
+
+    #[test]
+    fn test_rect() {
+        let blank = vec![0; 8 * 8 * 4];
+        let filled = draw_rect(&blank, 8, 8, 1.0, 2.0, 3.0, 2.5, 0.0, RED).unwrap();
+        assert_eq!(&filled[(2 * 8 + 1) * 4..(2 * 8 + 2) * 4], RED);
+        assert_eq!(alpha(&filled, 8, 3, 4), 128);
+        assert_eq!(alpha(&filled, 8, 4, 2), 0);
+        assert_eq!(alpha(&filled, 8, 0, 2), 0);
+
+        let outline = draw_rect(&blank, 8, 8, 0.0, 0.0, 8.0, 8.0, 2.0, RED).unwrap();
+        assert_eq!((alpha(&outline, 8, 1, 4), alpha(&outline, 8, 2, 4), alpha(&outline, 8, 6, 6)), (255, 0, 255));
+
+        // Partly off the image and blended over what is there
+        let gray = [128, 128, 128, 255].repeat(4);
+        let out = draw_rect(&gray, 2, 2, -5.0, -5.0, 6.0, 6.0, 0.0, [255, 255, 255, 128]).unwrap();
+        assert_eq!(&out[..4], [192, 192, 192, 255]);
+        assert_eq!(&out[4..8], [128, 128, 128, 255]);
+        assert!(draw_rect(&blank, 8, 8, 0.0, 0.0, -1.0, 1.0, 0.0, RED).is_err());
+    }
+
+    #[test]
+    fn test_circle_line_polygon() {
+        let blank = vec![0; 21 * 21 * 4];
+        let disc = draw_circle(&blank, 21, 21, 10.5, 10.5, 8.0, 0.0, RED).unwrap();
+        assert_eq!((alpha(&disc, 21, 10, 10), alpha(&disc, 21, 0, 0), alpha(&disc, 21, 10, 3)), (255, 0, 255));
+        // The edge pixel straddling the radius is partly covered
+        let edge = alpha(&disc, 21, 18, 10);
+        assert!(edge > 64 && edge < 192, "{}", edge);
+        let covered: u32 = disc.chunks_exact(4).map(|p| p[3] as u32).sum();
+        assert!((covered as f32 / 255.0 - PI * 64.0).abs() < 1.0);
+
+        let ring = draw_circle(&blank, 21, 21, 10.5, 10.5, 8.0, 2.0, RED).unwrap();
+        assert_eq!((alpha(&ring, 21, 10, 10), alpha(&ring, 21, 10, 3)), (0, 255));
+
+        let line = draw_line(&blank, 21, 21, 0.0, 5.0, 21.0, 5.0, 2.0, RED).unwrap();
+        assert_eq!((alpha(&line, 21, 7, 4), alpha(&line, 21, 7, 5), alpha(&line, 21, 7, 6)), (255, 255, 0));
+        assert_eq!(draw_line(&blank, 21, 21, 3.0, 3.0, 3.0, 3.0, 2.0, RED).unwrap(), blank);
+
+        // A pentagram's centre is wound twice and stays filled
+        let star: Vec<f32> = (0..5).map(|i| (i * 144) as f32 * PI / 180.0).flat_map(|a| [10.5 + 10.0 * a.sin(), 10.5 - 10.0 * a.cos()]).collect();
+        let filled = draw_polygon(&blank, 21, 21, &star, RED).unwrap();
+        assert_eq!(alpha(&filled, 21, 10, 10), 255);
+        assert!(draw_polygon(&blank, 21, 21, &[1.0, 2.0, 3.0, 4.0], RED).is_err());
+        assert!(draw_polygon(&blank, 21, 21, &[1.0, 2.0, 3.0, 4.0, f32::NAN, 0.0], RED).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Error-correcting codes: Reed-Solomon over GF(256)
+
+mod reed_solomon;
+
+pub(crate) use reed_solomon::GaloisField;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Reed-Solomon codec over GF(256) with a configurable field and generator
+///
+/// Codewords are data bytes followed by `parity` check bytes, 255 bytes at
+/// most; up to `parity / 2` corrupted bytes can be corrected. QR uses
+/// polynomial 0x11d with generator base 0, Data Matrix 0x12d with base 1.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ReedSolomon {
+    field: GaloisField,
+    parity: usize,
+}
+
+impl ReedSolomon {
+    /// A codec adding `parity` check bytes, over the field reduced by the
+    /// degree-8 primitive polynomial `poly`, with generator roots starting
+    /// at alpha^`generator_base`
+    pub fn new(parity: usize, poly: u32, generator_base: usize) -> Result<ReedSolomon, String> {
+        if parity == 0 || parity >= 255 {
+            return Err("Parity length must be 1-254".to_string());
+        }
+        if !(0x100..0x200).contains(&poly) || !GaloisField::is_primitive(poly, 256) {
+            return Err(format!("{:#x} is not a primitive polynomial of degree 8", poly));
+        }
+        if generator_base > 254 {
+            return Err("Generator base must be 0-254".to_string());
+        }
+        Ok(ReedSolomon { field: GaloisField::new(poly, 256, generator_base), parity })
+    }
+
+    /// `data` followed by its check bytes
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() + self.parity > 255 {
+            return Err(format!("At most {} data bytes fit a codeword", 255 - self.parity));
+        }
+        let words: Vec<u16> = data.iter().map(|&b| b as u16).collect();
+        let mut out = data.to_vec();
+        out.extend(self.field.encode(&words, self.parity).into_iter().map(|w| w as u8));
+        Ok(out)
+    }
+
+    /// Corrected data bytes of a codeword, without the check bytes
+    pub fn decode(&self, codeword: &[u8]) -> Result<Vec<u8>, String> {
+        let mut words: Vec<u16> = codeword.iter().map(|&b| b as u16).collect();
+        self.field.decode(&mut words, self.parity)?;
+        Ok(words[..words.len() - self.parity].iter().map(|&w| w as u8).collect())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ReedSolomon {
+    /// A codec adding `parity` check bytes over the field reduced by `poly`
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new_js(parity: usize, poly: u32, generator_base: usize) -> Result<ReedSolomon, JsError> {
+        ReedSolomon::new(parity, poly, generator_base).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn parity(&self) -> usize {
+        self.parity
+    }
+
+    /// `data` followed by its check bytes
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = encode)]
+    pub fn encode_js(&self, data: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.encode(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// Corrected data bytes of a codeword; throws if it has too many errors
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = decode)]
+    pub fn decode_js(&self, codeword: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.decode(codeword).map_err(|e| JsError::new(&e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_codec() {
+        let rs = ReedSolomon::new(8, 0x11d, 0).unwrap();
+        let mut codeword = rs.encode(b"hello, world").unwrap();
+        assert_eq!(codeword.len(), 20);
+        codeword[0] = b'j';
+        codeword[19] ^= 0xff;
+        assert_eq!(rs.decode(&codeword).unwrap(), b"hello, world");
+        assert!(rs.encode(&[0; 248]).is_err());
+        assert!(ReedSolomon::new(8, 0x11b, 0).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Reed-Solomon error correction over GF(2^m)
+
+/// GF(2^m) arithmetic via log/antilog tables
+pub(crate) struct GaloisField {
+    size: usize,
+    exp: Vec<u16>,
+    log: Vec<u16>,
+    /// Power of the generator's first root (0 for QR, 1 for Data Matrix and Aztec)
+    generator_base: usize,
+}
+
+impl GaloisField {
+    /// Field of `size` elements reduced by the primitive polynomial `poly`
+    pub fn new(poly: u32, size: usize, generator_base: usize) -> GaloisField {
+        let mut exp = vec![0u16; size * 2];
+        let mut log = vec![0u16; size];
+        let mut x = 1u32;
+        for (i, e) in exp.iter_mut().take(size - 1).enumerate() {
+            *e = x as u16;
+            log[x as usize] = i as u16;
+            x <<= 1;
+            if x as usize >= size {
+                x ^= poly;
+            }
+        }
+        for i in size - 1..exp.len() {
+            exp[i] = exp[i - (size - 1)];
+        }
+        GaloisField { size, exp, log, generator_base }
+    }
+
+    /// Whether `poly` generates every non-zero element, as a primitive polynomial must
+    pub fn is_primitive(poly: u32, size: usize) -> bool {
+        let mut x = 1u32;
+        for i in 1..size {
+            x <<= 1;
+            if x as usize >= size {
+                x ^= poly;
+            }
+            if x == 1 {
+                return i == size - 1;
+            }
+            if x == 0 || x as usize >= size {
+                return false;
+            }
+        }
+        false
+    }
+
+    fn mul(&self, a: u16, b: u16) -> u16 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+    }
+
+    /// `a / b` for non-zero `b`
+    fn div(&self, a: u16, b: u16) -> u16 {
+        if a == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + self.size - 1 - self.log[b as usize] as usize]
+    }
+
+    /// The generator raised to `power` (any sign)
+    fn alpha(&self, power: isize) -> u16 {
+        self.exp[power.rem_euclid(self.size as isize - 1) as usize]
+    }
+
+    /// Evaluate a polynomial with its highest term first
+    fn eval(&self, poly: &[u16], x: u16) -> u16 {
+        poly.iter().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+
+    /// Generator polynomial with `degree` consecutive roots, highest term first
+    fn generator(&self, degree: usize) -> Vec<u16> {
+        let mut poly = vec![1u16];
+        for i in 0..degree {
+            let root = self.exp[self.generator_base + i];
+            let mut next = vec![0u16; poly.len() + 1];
+            for (j, &c) in poly.iter().enumerate() {
+                next[j] ^= c;
+                next[j + 1] ^= self.mul(c, root);
+            }
+            poly = next;
+        }
+        poly
+    }
+
+    /// Check symbols for `data`: the remainder of data * x^n divided by the generator
+    pub fn encode(&self, data: &[u16], ecc_len: usize) -> Vec<u16> {
+        let generator = self.generator(ecc_len);
+        let mut remainder = vec![0u16; ecc_len];
+        for &d in data {
+            let factor = d ^ remainder[0];
+            remainder.rotate_left(1);
+            remainder[ecc_len - 1] = 0;
+            for (r, &g) in remainder.iter_mut().zip(&generator[1..]) {
+                *r ^= self.mul(g, factor);
+            }
+        }
+        remainder
+    }
+
+    /// Correct up to `ecc_len / 2` symbol errors in a codeword (data followed
+    /// by `ecc_len` check symbols) in place, returning how many were fixed
+    pub fn decode(&self, codeword: &mut [u16], ecc_len: usize) -> Result<usize, String> {
+        if codeword.len() >= self.size || codeword.len() < ecc_len {
+            return Err(format!("Codeword length must be {}-{}", ecc_len, self.size - 1));
+        }
+        let syndromes: Vec<u16> = (0..ecc_len).map(|j| self.eval(codeword, self.exp[self.generator_base + j])).collect();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Ok(0);
+        }
+
+        // Berlekamp-Massey: error locator with its constant term first
+        let (mut locator, mut previous) = (vec![1u16], vec![1u16]);
+        let (mut errors, mut shift, mut last_discrepancy) = (0, 1, 1u16);
+        for n in 0..ecc_len {
+            let discrepancy = (0..=errors.min(locator.len() - 1)).fold(0, |d, i| d ^ self.mul(locator[i], syndromes[n - i]));
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let scale = self.div(discrepancy, last_discrepancy);
+            let mut next = locator.clone();
+            next.resize(next.len().max(previous.len() + shift), 0);
+            for (i, &p) in previous.iter().enumerate() {
+                next[i + shift] ^= self.mul(scale, p);
+            }
+            if 2 * errors <= n {
+                previous = std::mem::replace(&mut locator, next);
+                errors = n + 1 - errors;
+                last_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                locator = next;
+                shift += 1;
+            }
+        }
+        locator.truncate(errors + 1);
+        if errors * 2 > ecc_len {
+            return Err("Too many errors to correct".to_string());
+        }
+
+        // Chien search for roots X^-1; Forney for the magnitudes
+        let eval_low = |poly: &[u16], x: u16| poly.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c);
+        let evaluator: Vec<u16> = (0..ecc_len).map(|k| (0..=k.min(errors)).fold(0, |acc, i| acc ^ self.mul(locator[i], syndromes[k - i]))).collect();
+        let derivative: Vec<u16> = locator.iter().enumerate().skip(1).map(|(i, &c)| if i % 2 == 1 { c } else { 0 }).collect();
+        let mut fixed = 0;
+        for position in 0..codeword.len() {
+            let power = (codeword.len() - 1 - position) as isize;
+            let x_inv = self.alpha(-power);
+            if eval_low(&locator, x_inv) != 0 {
+                continue;
+            }
+            let denominator = eval_low(&derivative, x_inv);
+            if denominator == 0 {
+                return Err("Too many errors to correct".to_string());
+            }
+            let magnitude = self.mul(self.alpha(power * (1 - self.generator_base as isize)), self.div(eval_low(&evaluator, x_inv), denominator));
+            codeword[position] ^= magnitude;
+            fixed += 1;
+        }
+        if fixed != errors || (0..ecc_len).any(|j| self.eval(codeword, self.exp[self.generator_base + j]) != 0) {
+            return Err("Too many errors to correct".to_string());
+        }
+        Ok(fixed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_matrix_example() {
+        // ISO/IEC 16022 worked example: "123456" in a 10x10 symbol
+        let field = GaloisField::new(0x12d, 256, 1);
+        assert_eq!(field.encode(&[142, 164, 186], 5), [114, 25, 5, 88, 102]);
+    }
+
+    #[test]
+    fn test_decode() {
+        // QR version 1-M "HELLO WORLD"
+        let field = GaloisField::new(0x11d, 256, 0);
+        let data = [32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17];
+        let check = field.encode(&data, 10);
+        assert_eq!(check, [196, 35, 39, 119, 235, 215, 231, 226, 93, 23]);
+
+        let codeword: Vec<u16> = data.iter().chain(&check).copied().collect();
+        for errors in [0, 1, 3, 5] {
+            let mut damaged = codeword.clone();
+            for i in 0..errors {
+                damaged[i * 5 + 2] ^= 0x5a + i as u16;
+            }
+            assert_eq!(field.decode(&mut damaged, 10), Ok(errors));
+            assert_eq!(damaged, codeword);
+        }
+        let mut damaged = codeword.clone();
+        damaged[..6].fill(0);
+        assert!(field.decode(&mut damaged, 10).is_err());
+
+        // A 10-bit Aztec field with its non-zero generator base
+        let field = GaloisField::new(0x409, 1024, 1);
+        let mut codeword = vec![1000, 3, 517, 0, 42];
+        codeword.extend(field.encode(&codeword, 6));
+        let original = codeword.clone();
+        codeword[1] = 999;
+        codeword[9] ^= 1;
+        codeword[4] = 0;
+        assert_eq!(field.decode(&mut codeword, 6), Ok(3));
+        assert_eq!(codeword, original);
+        assert!(GaloisField::is_primitive(0x11d, 256) && !GaloisField::is_primitive(0x11b, 256));
+    }
+}
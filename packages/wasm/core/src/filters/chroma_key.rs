@@ -0,0 +1,83 @@
+//! Chroma keying - alpha from chroma distance to a key color, with spill suppression
+
+use crate::color::space::{rgb_to_ycbcr, YCbCrMatrix};
+use crate::utils::check_rgba;
+
+/// CbCr of an RGB color
+#[inline]
+fn chroma(r: u8, g: u8, b: u8) -> [f32; 2] {
+    let [_, cb, cr] = rgb_to_ycbcr([r as f32, g as f32, b as f32], YCbCrMatrix::Bt709);
+    [cb, cr]
+}
+
+/// Remove a key color (e.g. a green screen), producing alpha
+///
+/// Distance is measured in CbCr so shading on the backdrop keys evenly.
+/// Pixels within `tolerance` (fraction of full chroma range, 0-1) become
+/// transparent, and alpha ramps to opaque over a further `softness`. The
+/// key's dominant channel is then limited to the average of the other two
+/// to suppress color spill on the remaining foreground edges.
+pub fn chroma_key(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    key: [u8; 3],
+    tolerance: f32,
+    softness: f32,
+) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+
+    let key_chroma = chroma(key[0], key[1], key[2]);
+    let tolerance = tolerance.clamp(0.0, 1.0) * 255.0;
+    let softness = softness.max(0.0) * 255.0;
+
+    // Spill suppression only makes sense for a clearly saturated key
+    let dominant = (0..3).max_by_key(|&c| key[c]).unwrap_or(1);
+    let others = [(dominant + 1) % 3, (dominant + 2) % 3];
+    let saturated = key[dominant] as i32 - key[others[0]].max(key[others[1]]) as i32 > 32;
+
+    let mut output = data.to_vec();
+    for p in output.chunks_exact_mut(4) {
+        let [cb, cr] = chroma(p[0], p[1], p[2]);
+        let distance = ((cb - key_chroma[0]).powi(2) + (cr - key_chroma[1]).powi(2)).sqrt();
+
+        let coverage = if distance <= tolerance {
+            0.0
+        } else if softness > 0.0 && distance < tolerance + softness {
+            let t = (distance - tolerance) / softness;
+            t * t * (3.0 - 2.0 * t)
+        } else {
+            1.0
+        };
+        p[3] = (p[3] as f32 * coverage).round() as u8;
+
+        if saturated {
+            let limit = ((p[others[0]] as u16 + p[others[1]] as u16) / 2) as u8;
+            p[dominant] = p[dominant].min(limit);
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_green_screen() {
+        let data = [
+            0, 255, 0, 255, // Key color
+            20, 200, 30, 255, // Shaded backdrop
+            200, 60, 50, 255, // Foreground
+            120, 180, 110, 255, // Green-tinged edge
+        ];
+        let out = chroma_key(&data, 4, 1, [0, 255, 0], 0.2, 0.1).unwrap();
+
+        assert_eq!(out[3], 0);
+        assert_eq!(out[7], 0);
+        assert_eq!(&out[8..12], &data[8..12]);
+        // Spill suppressed on the edge pixel
+        assert!(out[13] <= 115);
+    }
+}
@@ -0,0 +1,155 @@
+//! Edge detection - Sobel and Scharr gradient operators
+
+use crate::pool;
+use crate::utils::{check_rgba, luma};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Gradient operator used for edge detection
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeOperator {
+    Sobel = 0,
+    Scharr = 1,
+}
+
+impl EdgeOperator {
+    /// Smoothing weights (outer, center) of the separable kernel
+    fn weights(self) -> (f32, f32) {
+        match self {
+            EdgeOperator::Sobel => (1.0, 2.0),
+            EdgeOperator::Scharr => (3.0, 10.0),
+        }
+    }
+
+    /// Largest possible gradient magnitude for 8-bit input
+    fn max_magnitude(self) -> f32 {
+        let (outer, center) = self.weights();
+        let axis = (2.0 * outer + center) * 255.0;
+        (2.0 * axis * axis).sqrt()
+    }
+}
+
+/// Horizontal and vertical luma gradients of an image
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+pub struct Gradients {
+    pub gx: Vec<f32>,
+    pub gy: Vec<f32>,
+}
+
+/// Compute X/Y luma gradients of an RGBA image
+///
+/// Borders are handled by clamping to the nearest edge pixel.
+pub fn gradients(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    operator: EdgeOperator,
+) -> Result<Gradients, String> {
+    check_rgba(data, width, height)?;
+
+    let w = width as usize;
+    let h = height as usize;
+    let mut lum = pool::take_empty(w * h);
+    lum.extend(data.chunks_exact(4).map(|p| luma(p[0], p[1], p[2])));
+
+    let (outer, center) = operator.weights();
+    let mut gx = vec![0f32; w * h];
+    let mut gy = vec![0f32; w * h];
+
+    for y in 0..h {
+        let ym = y.saturating_sub(1) * w;
+        let yc = y * w;
+        let yp = (y + 1).min(h - 1) * w;
+
+        for x in 0..w {
+            let xm = x.saturating_sub(1);
+            let xp = (x + 1).min(w - 1);
+
+            let tl = lum[ym + xm];
+            let tc = lum[ym + x];
+            let tr = lum[ym + xp];
+            let ml = lum[yc + xm];
+            let mr = lum[yc + xp];
+            let bl = lum[yp + xm];
+            let bc = lum[yp + x];
+            let br = lum[yp + xp];
+
+            gx[yc + x] = outer * (tr - tl) + center * (mr - ml) + outer * (br - bl);
+            gy[yc + x] = outer * (bl - tl) + center * (bc - tc) + outer * (br - tr);
+        }
+    }
+
+    Ok(Gradients { gx, gy })
+}
+
+/// Compute per-pixel gradient magnitude (unnormalized)
+///
+/// Suitable as an energy function for seam carving.
+pub fn gradient_magnitude(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    operator: EdgeOperator,
+) -> Result<Vec<f32>, String> {
+    let Gradients { gx, gy } = gradients(data, width, height, operator)?;
+    Ok(gx
+        .iter()
+        .zip(gy.iter())
+        .map(|(x, y)| (x * x + y * y).sqrt())
+        .collect())
+}
+
+/// Produce a grayscale RGBA edge map, normalized to the operator's full range
+pub fn edge_detect(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    operator: EdgeOperator,
+) -> Result<Vec<u8>, String> {
+    let magnitude = gradient_magnitude(data, width, height, operator)?;
+    let scale = 255.0 / operator.max_magnitude();
+
+    let mut output = Vec::with_capacity(magnitude.len() * 4);
+    for m in magnitude {
+        let v = (m * scale).round().clamp(0.0, 255.0) as u8;
+        output.extend_from_slice(&[v, v, v, 255]);
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertical_edge() -> Vec<u8> {
+        // 4x3 image: left half black, right half white
+        let mut data = Vec::new();
+        for _ in 0..3 {
+            for x in 0..4 {
+                let v = if x < 2 { 0 } else { 255 };
+                data.extend_from_slice(&[v, v, v, 255]);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_vertical_edge_gradients() {
+        let data = vertical_edge();
+        let g = gradients(&data, 4, 3, EdgeOperator::Sobel).unwrap();
+
+        // Strong horizontal gradient at the boundary, none vertically
+        assert!(g.gx[5] > 0.0);
+        assert_eq!(g.gx[4], 0.0);
+        assert!(g.gy.iter().all(|&v| v.abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_flat_image_has_no_edges() {
+        let data = vec![128u8; 5 * 5 * 4];
+        let edges = edge_detect(&data, 5, 5, EdgeOperator::Scharr).unwrap();
+        assert!(edges.chunks_exact(4).all(|p| p[0] == 0 && p[3] == 255));
+    }
+}
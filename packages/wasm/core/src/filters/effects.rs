@@ -0,0 +1,100 @@
+//! Simple per-pixel effects - grayscale, sepia, invert
+
+use crate::utils::{check_rgba, luma};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Preset per-pixel effects
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Grayscale = 0,
+    Sepia = 1,
+    Invert = 2,
+}
+
+/// Convert to grayscale using Rec.709 luma
+///
+/// With `single_channel` the output holds one byte per pixel instead of RGBA.
+pub fn to_grayscale(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    single_channel: bool,
+) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+
+    let pixels = data.chunks_exact(4);
+    if single_channel {
+        return Ok(pixels
+            .map(|p| luma(p[0], p[1], p[2]).round() as u8)
+            .collect());
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+    for p in pixels {
+        let y = luma(p[0], p[1], p[2]).round() as u8;
+        output.extend_from_slice(&[y, y, y, p[3]]);
+    }
+    Ok(output)
+}
+
+/// Apply a sepia tone; `amount` in [0, 1] blends from the original
+pub fn sepia(data: &[u8], width: u32, height: u32, amount: f32) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+
+    let amount = amount.clamp(0.0, 1.0);
+    let mut output = data.to_vec();
+    for p in output.chunks_exact_mut(4) {
+        let (r, g, b) = (p[0] as f32, p[1] as f32, p[2] as f32);
+        let sr = 0.393 * r + 0.769 * g + 0.189 * b;
+        let sg = 0.349 * r + 0.686 * g + 0.168 * b;
+        let sb = 0.272 * r + 0.534 * g + 0.131 * b;
+
+        p[0] = (r + (sr - r) * amount).round().min(255.0) as u8;
+        p[1] = (g + (sg - g) * amount).round().min(255.0) as u8;
+        p[2] = (b + (sb - b) * amount).round().min(255.0) as u8;
+    }
+    Ok(output)
+}
+
+/// Invert the RGB channels, preserving alpha
+pub fn invert(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+
+    let mut output = data.to_vec();
+    for p in output.chunks_exact_mut(4) {
+        p[0] = 255 - p[0];
+        p[1] = 255 - p[1];
+        p[2] = 255 - p[2];
+    }
+    Ok(output)
+}
+
+/// Apply a preset effect at full strength, producing RGBA
+pub fn apply_effect(data: &[u8], width: u32, height: u32, effect: Effect) -> Result<Vec<u8>, String> {
+    match effect {
+        Effect::Grayscale => to_grayscale(data, width, height, false),
+        Effect::Sepia => sepia(data, width, height, 1.0),
+        Effect::Invert => invert(data, width, height),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grayscale_single_channel() {
+        let data = [255, 255, 255, 255, 0, 0, 0, 0];
+        assert_eq!(to_grayscale(&data, 2, 1, true).unwrap(), vec![255, 0]);
+    }
+
+    #[test]
+    fn test_invert_twice_is_identity() {
+        let data = [1, 2, 3, 4, 250, 128, 0, 255];
+        let once = invert(&data, 2, 1).unwrap();
+        assert_eq!(once[..4], [254, 253, 252, 4]);
+        assert_eq!(invert(&once, 2, 1).unwrap(), data.to_vec());
+    }
+}
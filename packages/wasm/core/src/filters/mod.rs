@@ -0,0 +1,154 @@
+//! Image filters operating on RGBA buffers
+
+mod chroma_key;
+mod edge;
+mod effects;
+mod posterize;
+mod threshold;
+mod vignette;
+
+pub use chroma_key::chroma_key;
+pub use edge::{edge_detect, gradient_magnitude, gradients, EdgeOperator, Gradients};
+pub use effects::{apply_effect, invert, sepia, to_grayscale, Effect};
+pub use posterize::{posterize, reduce_colors};
+pub use threshold::{adaptive_threshold, otsu_level, threshold, threshold_otsu};
+pub use vignette::vignette;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Grayscale RGBA edge map using Sobel or Scharr
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = edgeDetect)]
+pub fn edge_detect_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    operator: EdgeOperator,
+) -> Result<Vec<u8>, JsError> {
+    edge_detect(data, width, height, operator).map_err(|e| JsError::new(&e))
+}
+
+/// Per-pixel gradient magnitude (e.g. seam-carving energy)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = gradientMagnitude)]
+pub fn gradient_magnitude_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    operator: EdgeOperator,
+) -> Result<Vec<f32>, JsError> {
+    gradient_magnitude(data, width, height, operator).map_err(|e| JsError::new(&e))
+}
+
+/// Horizontal and vertical luma gradient buffers
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = gradients)]
+pub fn gradients_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    operator: EdgeOperator,
+) -> Result<Gradients, JsError> {
+    gradients(data, width, height, operator).map_err(|e| JsError::new(&e))
+}
+
+/// Rec.709 grayscale; `singleChannel` returns one byte per pixel
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = toGrayscale)]
+pub fn to_grayscale_js(data: &[u8], width: u32, height: u32, single_channel: bool) -> Result<Vec<u8>, JsError> {
+    to_grayscale(data, width, height, single_channel).map_err(|e| JsError::new(&e))
+}
+
+/// Sepia tone with blend amount in [0, 1]
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = sepia)]
+pub fn sepia_js(data: &[u8], width: u32, height: u32, amount: f32) -> Result<Vec<u8>, JsError> {
+    sepia(data, width, height, amount).map_err(|e| JsError::new(&e))
+}
+
+/// Invert RGB channels
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = invert)]
+pub fn invert_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    invert(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Apply a preset effect
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = applyEffect)]
+pub fn apply_effect_js(data: &[u8], width: u32, height: u32, effect: Effect) -> Result<Vec<u8>, JsError> {
+    apply_effect(data, width, height, effect).map_err(|e| JsError::new(&e))
+}
+
+/// Binarize at a fixed luma level
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = threshold)]
+pub fn threshold_js(data: &[u8], width: u32, height: u32, level: u8) -> Result<Vec<u8>, JsError> {
+    threshold(data, width, height, level).map_err(|e| JsError::new(&e))
+}
+
+/// Compute the Otsu threshold level
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = otsuLevel)]
+pub fn otsu_level_js(data: &[u8], width: u32, height: u32) -> Result<u8, JsError> {
+    otsu_level(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Binarize using Otsu's automatic threshold
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = thresholdOtsu)]
+pub fn threshold_otsu_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    threshold_otsu(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Binarize against the local mean of a square window
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = adaptiveThreshold)]
+pub fn adaptive_threshold_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    block_size: u32,
+    offset: f32,
+) -> Result<Vec<u8>, JsError> {
+    adaptive_threshold(data, width, height, block_size, offset).map_err(|e| JsError::new(&e))
+}
+
+/// Reduce each channel to a fixed number of levels
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = posterize)]
+pub fn posterize_js(data: &[u8], width: u32, height: u32, levels: u8) -> Result<Vec<u8>, JsError> {
+    posterize(data, width, height, levels).map_err(|e| JsError::new(&e))
+}
+
+/// Limit the image to at most `maxColors` distinct colors
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = reduceColors)]
+pub fn reduce_colors_js(data: &[u8], width: u32, height: u32, max_colors: u32) -> Result<Vec<u8>, JsError> {
+    reduce_colors(data, width, height, max_colors).map_err(|e| JsError::new(&e))
+}
+
+/// Key out a backdrop color, producing alpha with spill suppression
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = chromaKey)]
+#[allow(clippy::too_many_arguments)]
+pub fn chroma_key_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    key_r: u8,
+    key_g: u8,
+    key_b: u8,
+    tolerance: f32,
+    softness: f32,
+) -> Result<Vec<u8>, JsError> {
+    chroma_key(data, width, height, [key_r, key_g, key_b], tolerance, softness).map_err(|e| JsError::new(&e))
+}
+
+/// Darken towards the corners by up to `amount`, starting at `radius`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = vignette)]
+pub fn vignette_js(data: &[u8], width: u32, height: u32, amount: f32, radius: f32) -> Result<Vec<u8>, JsError> {
+    vignette(data, width, height, amount, radius).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,55 @@
+//! Posterize and color-count reduction
+
+use crate::quantize::quantize;
+use crate::utils::check_rgba;
+
+/// Reduce each RGB channel to `levels` evenly spaced values
+pub fn posterize(data: &[u8], width: u32, height: u32, levels: u8) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if levels < 2 {
+        return Err("Posterize needs at least 2 levels".to_string());
+    }
+
+    let steps = (levels - 1) as f32;
+    let mut lut = [0u8; 256];
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let q = (i as f32 / 255.0 * steps).round();
+        *entry = (q / steps * 255.0).round() as u8;
+    }
+
+    let mut output = data.to_vec();
+    for p in output.chunks_exact_mut(4) {
+        p[0] = lut[p[0] as usize];
+        p[1] = lut[p[1] as usize];
+        p[2] = lut[p[2] as usize];
+    }
+    Ok(output)
+}
+
+/// Limit the image to at most `max_colors` distinct colors using the median-cut quantizer
+pub fn reduce_colors(data: &[u8], width: u32, height: u32, max_colors: u32) -> Result<Vec<u8>, String> {
+    Ok(quantize(data, width, height, max_colors.min(256))?.to_rgba())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_posterize_two_levels() {
+        let out = posterize(&[10, 128, 250, 99], 1, 1, 2).unwrap();
+        assert_eq!(out, vec![0, 255, 255, 99]);
+    }
+
+    #[test]
+    fn test_reduce_colors_limits_palette() {
+        let mut data = Vec::new();
+        for i in 0..64u32 {
+            data.extend_from_slice(&[(i * 4) as u8, (255 - i * 4) as u8, (i * 2) as u8, 255]);
+        }
+        let out = reduce_colors(&data, 8, 8, 4).unwrap();
+        let colors: HashSet<_> = out.chunks_exact(4).map(|p| [p[0], p[1], p[2]]).collect();
+        assert!(colors.len() <= 4);
+    }
+}
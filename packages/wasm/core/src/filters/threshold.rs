@@ -0,0 +1,148 @@
+//! Binarization - global, Otsu, and adaptive (local mean) thresholding
+
+use super::effects::to_grayscale;
+use crate::pool;
+use crate::utils::check_rgba;
+
+/// Expand a per-pixel on/off mask into black/white RGBA, keeping source alpha
+fn mask_to_rgba(data: &[u8], mask: impl Iterator<Item = bool>) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    for (p, on) in data.chunks_exact(4).zip(mask) {
+        let v = if on { 255 } else { 0 };
+        output.extend_from_slice(&[v, v, v, p[3]]);
+    }
+    output
+}
+
+/// Binarize with a fixed luma threshold (pixels >= `level` become white)
+pub fn threshold(data: &[u8], width: u32, height: u32, level: u8) -> Result<Vec<u8>, String> {
+    let gray = to_grayscale(data, width, height, true)?;
+    Ok(mask_to_rgba(data, gray.iter().map(|&y| y >= level)))
+}
+
+/// Compute the Otsu threshold maximizing between-class variance of luma
+pub fn otsu_level(data: &[u8], width: u32, height: u32) -> Result<u8, String> {
+    let gray = to_grayscale(data, width, height, true)?;
+
+    let mut hist = [0u64; 256];
+    for &y in &gray {
+        hist[y as usize] += 1;
+    }
+
+    let total = gray.len() as f64;
+    let sum_all: f64 = hist.iter().enumerate().map(|(i, &c)| i as f64 * c as f64).sum();
+
+    let mut best_level = 0u8;
+    let mut best_variance = -1.0;
+    let mut weight_bg = 0.0;
+    let mut sum_bg = 0.0;
+
+    for (t, &count) in hist.iter().enumerate() {
+        weight_bg += count as f64;
+        if weight_bg == 0.0 {
+            continue;
+        }
+        let weight_fg = total - weight_bg;
+        if weight_fg == 0.0 {
+            break;
+        }
+
+        sum_bg += t as f64 * count as f64;
+        let mean_bg = sum_bg / weight_bg;
+        let mean_fg = (sum_all - sum_bg) / weight_fg;
+        let variance = weight_bg * weight_fg * (mean_bg - mean_fg).powi(2);
+
+        if variance > best_variance {
+            best_variance = variance;
+            best_level = t as u8;
+        }
+    }
+
+    // Pixels strictly above the background class become foreground
+    Ok(best_level.saturating_add(1))
+}
+
+/// Binarize using the automatically chosen Otsu threshold
+pub fn threshold_otsu(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let level = otsu_level(data, width, height)?;
+    threshold(data, width, height, level)
+}
+
+/// Binarize against the local mean of a `block_size` window minus `offset`
+///
+/// Uses an integral image, so cost is independent of the window size.
+pub fn adaptive_threshold(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    block_size: u32,
+    offset: f32,
+) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if block_size == 0 {
+        return Err("Block size must be positive".to_string());
+    }
+
+    let gray = to_grayscale(data, width, height, true)?;
+    let w = width as usize;
+    let h = height as usize;
+
+    // Integral image with a zero row/column at the top/left
+    let iw = w + 1;
+    let mut integral = pool::take(iw * (h + 1), 0u64);
+    for y in 0..h {
+        let mut row_sum = 0u64;
+        for x in 0..w {
+            row_sum += gray[y * w + x] as u64;
+            integral[(y + 1) * iw + x + 1] = integral[y * iw + x + 1] + row_sum;
+        }
+    }
+
+    let radius = (block_size / 2) as usize;
+    let mask = (0..w * h).map(|i| {
+        let (x, y) = (i % w, i / w);
+        let x0 = x.saturating_sub(radius);
+        let y0 = y.saturating_sub(radius);
+        let x1 = (x + radius + 1).min(w);
+        let y1 = (y + radius + 1).min(h);
+
+        let area = ((x1 - x0) * (y1 - y0)) as f32;
+        let sum = integral[y1 * iw + x1] + integral[y0 * iw + x0]
+            - integral[y0 * iw + x1]
+            - integral[y1 * iw + x0];
+
+        gray[i] as f32 > sum as f32 / area - offset
+    });
+
+    Ok(mask_to_rgba(data, mask))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_otsu_separates_bimodal() {
+        let mut data = Vec::new();
+        for i in 0..16 {
+            let v = if i < 8 { 30 } else { 220 };
+            data.extend_from_slice(&[v, v, v, 255]);
+        }
+        let level = otsu_level(&data, 4, 4).unwrap();
+        assert!(level > 30 && level <= 220);
+
+        let out = threshold_otsu(&data, 4, 4).unwrap();
+        assert_eq!(out[0], 0);
+        assert_eq!(out[15 * 4], 255);
+    }
+
+    #[test]
+    fn test_adaptive_handles_gradient_background() {
+        // Dark text pixel on a bright background is picked out locally
+        let mut data = vec![200u8; 5 * 5 * 4];
+        data[12 * 4..12 * 4 + 3].copy_from_slice(&[20, 20, 20]);
+        let out = adaptive_threshold(&data, 5, 5, 3, 5.0).unwrap();
+        assert_eq!(out[12 * 4], 0);
+        assert_eq!(out[0], 255);
+    }
+}
@@ -0,0 +1,45 @@
+//! Vignette: darkening towards the corners
+
+use crate::utils::check_rgba;
+
+/// Darken the image towards its corners by up to `amount` (0-1)
+///
+/// The falloff starts at `radius` (0-1, as a fraction of the distance from
+/// the centre to a corner) and eases in smoothly to full strength at the
+/// corners. Alpha is left untouched.
+pub fn vignette(data: &[u8], width: u32, height: u32, amount: f32, radius: f32) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if !(0.0..=1.0).contains(&amount) || !(0.0..1.0).contains(&radius) {
+        return Err("Vignette amount must be 0-1 and radius 0 to below 1".to_string());
+    }
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let corner = cx.hypot(cy);
+    let mut output = data.to_vec();
+    for (i, p) in output.chunks_exact_mut(4).enumerate() {
+        let (x, y) = ((i % width as usize) as f32 + 0.5, (i / width as usize) as f32 + 0.5);
+        let t = (((x - cx).hypot(y - cy) / corner - radius) / (1.0 - radius)).clamp(0.0, 1.0);
+        let factor = 1.0 - amount * t * t * (3.0 - 2.0 * t);
+        for c in &mut p[..3] {
+            *c = (*c as f32 * factor).round() as u8;
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vignette() {
+        let white = [255; 4].repeat(81);
+        let out = vignette(&white, 9, 9, 0.8, 0.5).unwrap();
+        // The centre is untouched, corners darken most, alpha is kept
+        assert_eq!(&out[40 * 4..41 * 4], [255; 4]);
+        let (corner, edge) = (out[0], out[4 * 4]);
+        assert!(corner < edge && edge < 255 && corner > 51, "{} {}", corner, edge);
+        assert!(out.chunks_exact(4).all(|p| p[3] == 255));
+        assert_eq!(vignette(&white, 9, 9, 0.0, 0.5).unwrap(), white);
+        assert!(vignette(&white, 9, 9, 1.5, 0.5).is_err());
+    }
+}
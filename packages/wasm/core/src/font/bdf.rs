@@ -0,0 +1,141 @@
+//! BDF (Glyph Bitmap Distribution Format) font parser
+
+use super::bitmap::{BitmapFont, Glyph};
+use std::collections::BTreeMap;
+
+fn numbers(fields: &[&str]) -> Result<Vec<i32>, String> {
+    fields.iter().map(|f| f.parse::<i32>().map_err(|_| format!("Invalid BDF number '{}'", f))).collect()
+}
+
+/// A byte from two hex digits of a BITMAP row
+fn hex_byte(pair: &[u8]) -> Option<u8> {
+    let digit = |b: u8| (b as char).to_digit(16);
+    Some((digit(pair[0])? << 4 | digit(pair[1])?) as u8)
+}
+
+/// Parse a BDF font; glyph encodings are taken as Unicode code points,
+/// which also holds for ISO 8859-1 fonts
+pub fn parse_bdf(data: &[u8]) -> Result<BitmapFont, String> {
+    let text = String::from_utf8_lossy(data);
+    let mut lines = text.lines().map(str::trim);
+    if !lines.next().is_some_and(|l| l.starts_with("STARTFONT")) {
+        return Err("Not a BDF font".to_string());
+    }
+
+    let (mut ascent, mut descent, mut default_code) = (None, None, None);
+    let mut bounding_box = [0; 4];
+    let mut font_advance = 0;
+    let mut glyphs = BTreeMap::new();
+    while let Some(line) = lines.next() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["FONTBOUNDINGBOX", rest @ ..] if rest.len() == 4 => bounding_box.copy_from_slice(&numbers(rest)?),
+            ["FONT_ASCENT", n] => ascent = Some(numbers(&[n])?[0]),
+            ["FONT_DESCENT", n] => descent = Some(numbers(&[n])?[0]),
+            ["DEFAULT_CHAR", n] => default_code = Some(numbers(&[n])?[0]),
+            ["DWIDTH", dx, ..] => font_advance = numbers(&[dx])?[0],
+            ["STARTCHAR", ..] => {
+                let (mut code, mut advance, mut bbx) = (-1, font_advance, bounding_box);
+                loop {
+                    let line = lines.next().ok_or("Truncated BDF glyph")?;
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    match fields.as_slice() {
+                        ["ENCODING", n, ..] => code = numbers(&[n])?[0],
+                        ["DWIDTH", dx, ..] => advance = numbers(&[dx])?[0],
+                        ["BBX", rest @ ..] if rest.len() == 4 => bbx.copy_from_slice(&numbers(rest)?),
+                        ["BITMAP"] => break,
+                        ["ENDCHAR"] => return Err("BDF glyph has no BITMAP".to_string()),
+                        _ => {}
+                    }
+                }
+                let [w, h, x_offset, y_offset] = bbx;
+                if !(0..=1024).contains(&w) || !(0..=1024).contains(&h) {
+                    return Err(format!("Invalid BDF glyph size {}x{}", w, h));
+                }
+                let mut bits = Vec::with_capacity((w * h) as usize);
+                for _ in 0..h {
+                    let row = lines.next().ok_or("Truncated BDF bitmap")?;
+                    let bytes = row.as_bytes().chunks_exact(2).map(hex_byte).collect::<Option<Vec<u8>>>().ok_or_else(|| format!("Invalid BDF bitmap row '{}'", row))?;
+                    bits.extend((0..w as usize).map(|x| bytes.get(x / 8).is_some_and(|b| b >> (7 - x % 8) & 1 != 0)));
+                }
+                if lines.next() != Some("ENDCHAR") {
+                    return Err("BDF glyph bitmap has extra rows".to_string());
+                }
+                let top = y_offset.checked_add(h).ok_or("Invalid BDF glyph offset")?;
+                if let Some(c) = u32::try_from(code).ok().and_then(char::from_u32) {
+                    glyphs.insert(c, Glyph { width: w as u32, height: h as u32, left: x_offset, top, advance, bits });
+                }
+            }
+            _ => {}
+        }
+    }
+    if glyphs.is_empty() {
+        return Err("BDF font has no glyphs".to_string());
+    }
+    Ok(BitmapFont {
+        glyphs,
+        ascent: ascent.unwrap_or(bounding_box[1].saturating_add(bounding_box[3])),
+        descent: descent.unwrap_or(bounding_box[3].saturating_neg()),
+        default_char: default_code.and_then(|c| u32::try_from(c).ok()).and_then(char::from_u32),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "STARTFONT 2.1
+FONT -test-fixed-medium-r-normal--4-40-75-75-c-40-iso10646-1
+SIZE 4 75 75
+FONTBOUNDINGBOX 4 5 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 4
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 2
+STARTCHAR T
+ENCODING 84
+SWIDTH 1000 0
+DWIDTH 4 0
+BBX 3 4 0 0
+BITMAP
+E0
+40
+40
+40
+ENDCHAR
+STARTCHAR uni00B7
+ENCODING 183
+DWIDTH 2 0
+BBX 1 1 0 2
+BITMAP
+80
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn test_parse_bdf() {
+        let font = parse_bdf(FONT.as_bytes()).unwrap();
+        assert_eq!((font.glyph_count(), font.ascent(), font.line_height()), (2, 4, 5));
+        let t = &font.glyphs[&'T'];
+        assert_eq!((t.width, t.height, t.top, t.advance), (3, 4, 4, 4));
+        assert_eq!(t.bits, [true, true, true, false, true, false, false, true, false, false, true, false]);
+        assert_eq!(font.glyphs[&'\u{b7}'].top, 3);
+        assert_eq!(font.text_width("T\u{b7}T"), 9);
+        assert!(parse_bdf(b"STARTFONT 2.1\nENDFONT\n").is_err());
+    }
+
+    #[test]
+    fn test_malformed_glyphs() {
+        let non_ascii = FONT.replacen("E0\n", "\u{e9}0\n", 1);
+        assert_eq!(parse_bdf(non_ascii.as_bytes()).err(), Some("Invalid BDF bitmap row '\u{e9}0'".to_string()));
+        // Invalid UTF-8 becomes a 3-byte U+FFFD that a pair of digits would split
+        let mut invalid_utf8 = FONT.replacen("E0\n", "E0?\n", 1).into_bytes();
+        let at = invalid_utf8.iter().position(|&b| b == b'?').unwrap();
+        invalid_utf8[at] = 0xC7;
+        assert!(parse_bdf(&invalid_utf8).is_err());
+        let offset = FONT.replacen("BBX 3 4 0 0", "BBX 3 4 0 2147483647", 1);
+        assert_eq!(parse_bdf(offset.as_bytes()).err(), Some("Invalid BDF glyph offset".to_string()));
+    }
+}
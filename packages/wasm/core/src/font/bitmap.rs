@@ -0,0 +1,156 @@
+//! Bitmap fonts and drawing text with them
+
+use crate::animation::blend_over;
+use crate::utils::check_rgba;
+use std::collections::BTreeMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// One glyph's pixels and metrics, in font pixels
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Glyph {
+    pub width: u32,
+    pub height: u32,
+    /// Offset of the bitmap's left edge from the pen position
+    pub left: i32,
+    /// Height of the bitmap's top edge above the baseline
+    pub top: i32,
+    /// Pen movement after the glyph
+    pub advance: i32,
+    /// Row-major, true for ink
+    pub bits: Vec<bool>,
+}
+
+/// A monospace or proportional bitmap font
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitmapFont {
+    pub(crate) glyphs: BTreeMap<char, Glyph>,
+    pub(crate) ascent: i32,
+    pub(crate) descent: i32,
+    /// Drawn for characters the font lacks
+    pub(crate) default_char: Option<char>,
+}
+
+impl BitmapFont {
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.default_char.and_then(|d| self.glyphs.get(&d))).or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// Width in font pixels of the widest line of `text`
+    pub fn text_width(&self, text: &str) -> u32 {
+        let line_width = |line: &str| {
+            let glyphs: Vec<&Glyph> = line.chars().filter_map(|c| self.glyph(c)).collect();
+            let Some((last, rest)) = glyphs.split_last() else { return 0 };
+            (rest.iter().map(|g| g.advance).sum::<i32>() + last.left + last.width as i32).max(0) as u32
+        };
+        text.split('\n').map(line_width).max().unwrap_or(0)
+    }
+
+    /// Blend `text` onto an RGBA buffer, each font pixel a `scale`-pixel
+    /// square, with the top of the first line at (x, y); pixels off the
+    /// image are clipped
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn draw(&self, data: &mut [u8], width: u32, height: u32, text: &str, x: i32, y: i32, scale: u32, color: [u8; 4]) {
+        let scale = scale as i64;
+        let (x, y) = (x as i64, y as i64);
+        for (line, text) in text.split('\n').enumerate() {
+            let baseline = y + (line as i64 * self.line_height() as i64 + self.ascent as i64) * scale;
+            let mut pen = x;
+            for glyph in text.chars().filter_map(|c| self.glyph(c)) {
+                let (left, top) = (pen + glyph.left as i64 * scale, baseline - glyph.top as i64 * scale);
+                for (i, _) in glyph.bits.iter().enumerate().filter(|(_, &ink)| ink) {
+                    let (gx, gy) = (left + (i as u32 % glyph.width) as i64 * scale, top + (i as u32 / glyph.width) as i64 * scale);
+                    for py in gy.max(0)..(gy + scale).min(height as i64) {
+                        for px in gx.max(0)..(gx + scale).min(width as i64) {
+                            let at = (py as usize * width as usize + px as usize) * 4;
+                            blend_over(&mut data[at..at + 4], &color);
+                        }
+                    }
+                }
+                pen += glyph.advance as i64 * scale;
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BitmapFont {
+    /// The built-in 5x7 monospace font covering printable ASCII
+    pub fn builtin() -> BitmapFont {
+        super::builtin::builtin_font()
+    }
+
+    /// Parse a BDF (Glyph Bitmap Distribution Format) font
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = fromBdf)]
+    pub fn from_bdf_js(data: &[u8]) -> Result<BitmapFont, JsError> {
+        super::bdf::parse_bdf(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// Parse a PCF (Portable Compiled Format) font, optionally gzipped
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = fromPcf)]
+    pub fn from_pcf_js(data: &[u8]) -> Result<BitmapFont, JsError> {
+        super::pcf::parse_pcf(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// Pixels from the top of one line to the next, at scale 1
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = lineHeight))]
+    pub fn line_height(&self) -> u32 {
+        (self.ascent + self.descent).max(1) as u32
+    }
+
+    /// Pixels above the baseline, at scale 1
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ascent(&self) -> i32 {
+        self.ascent
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = glyphCount))]
+    pub fn glyph_count(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Width and height in pixels of `text` drawn at `size`
+    pub fn measure(&self, text: &str, size: u32) -> Vec<u32> {
+        vec![self.text_width(text) * size, text.split('\n').count() as u32 * self.line_height() * size]
+    }
+}
+
+/// Draw `text` in `color` with its first line's top-left corner at (x, y)
+///
+/// Each font pixel becomes a `size` x `size` block; `\n` starts a new line.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text(data: &[u8], width: u32, height: u32, text: &str, x: i32, y: i32, size: u32, color: [u8; 4], font: &BitmapFont) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if size == 0 {
+        return Err("Text size must be positive".to_string());
+    }
+    let mut output = data.to_vec();
+    font.draw(&mut output, width, height, text, x, y, size, color);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_text() {
+        let font = BitmapFont::builtin();
+        assert_eq!(font.text_width("ab"), 11);
+        assert_eq!(font.measure("ab\nc", 2), [22, 32]);
+
+        let blank = [0u8; 4].repeat(20 * 10);
+        let out = draw_text(&blank, 20, 10, "I", -1, 1, 1, [255, 0, 0, 255], &font).unwrap();
+        // 'I' is a bar in its middle column with serifs at the top and bottom
+        let ink = |x: usize, y: usize| out[(y * 20 + x) * 4 + 3] != 0;
+        assert!((1..8).all(|y| ink(1, y)) && ink(0, 1) && ink(2, 7) && !ink(1, 8) && !ink(3, 4));
+
+        // Scaled up, the bottom serif row falls off the image
+        let big = draw_text(&blank, 20, 10, "I", 0, 0, 2, [0, 0, 255, 255], &font).unwrap();
+        assert_eq!(big.chunks_exact(4).filter(|p| p[3] != 0).count(), 4 * 7);
+        assert!(draw_text(&blank, 20, 10, "I", 0, 0, 0, [0; 4], &font).is_err());
+    }
+}
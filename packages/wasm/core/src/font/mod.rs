@@ -0,0 +1,34 @@
+//! Fonts for drawing text onto images: a built-in bitmap font, BDF/PCF
+//! loading, and TrueType outlines rendered with anti-aliasing
+
+mod bdf;
+mod bitmap;
+mod builtin;
+mod pcf;
+mod truetype;
+
+pub use bdf::parse_bdf;
+pub use bitmap::{draw_text, BitmapFont};
+pub use pcf::parse_pcf;
+pub use truetype::{GlyphBitmap, TrueTypeFont};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Draw text onto an RGBA image; `color` is `[r, g, b, a]`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = drawText)]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text_js(data: &[u8], width: u32, height: u32, text: &str, x: i32, y: i32, size: u32, color: &[u8], font: &BitmapFont) -> Result<Vec<u8>, JsError> {
+    let color: [u8; 4] = color.try_into().map_err(|_| JsError::new("Color must have 4 components"))?;
+    draw_text(data, width, height, text, x, y, size, color, font).map_err(|e| JsError::new(&e))
+}
+
+/// Draw anti-aliased text in a TrueType font at `size` pixels per em
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = drawTextTrueType)]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text_truetype_js(data: &[u8], width: u32, height: u32, text: &str, x: i32, y: i32, size: f32, color: &[u8], font: &TrueTypeFont) -> Result<Vec<u8>, JsError> {
+    let color: [u8; 4] = color.try_into().map_err(|_| JsError::new("Color must have 4 components"))?;
+    font.draw(data, width, height, text, x, y, size, color).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,199 @@
+//! PCF (Portable Compiled Format) font parser, as shipped with X11
+
+use super::bitmap::{BitmapFont, Glyph};
+use crate::compress::gzip_decompress;
+use std::collections::BTreeMap;
+
+const ACCELERATORS: u32 = 1 << 1;
+const METRICS: u32 = 1 << 2;
+const BITMAPS: u32 = 1 << 3;
+const BDF_ENCODINGS: u32 = 1 << 5;
+const BDF_ACCELERATORS: u32 = 1 << 8;
+
+/// Table format bits
+const BYTE_MSB_FIRST: u32 = 1 << 2;
+const BIT_MSB_FIRST: u32 = 1 << 3;
+const COMPRESSED_METRICS: u32 = 0x100;
+
+/// Cursor over one table, honoring its byte order
+struct Table<'a> {
+    data: &'a [u8],
+    pos: usize,
+    format: u32,
+}
+
+impl<'a> Table<'a> {
+    /// The table at `offset`, which starts with its format word (always little-endian)
+    fn open(file: &'a [u8], offset: usize, size: usize) -> Result<Table<'a>, String> {
+        let data = file.get(offset..offset.saturating_add(size)).filter(|d| d.len() >= 4).ok_or("Truncated PCF table")?;
+        let format = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        Ok(Table { data, pos: 4, format })
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let bytes = self.data.get(self.pos..self.pos + n).ok_or("Truncated PCF table")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(if self.format & BYTE_MSB_FIRST != 0 { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) })
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Ok(if self.format & BYTE_MSB_FIRST != 0 { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    }
+}
+
+/// Per-glyph metrics: left and right bearing, advance, ascent, descent
+type Metrics = [i32; 5];
+
+fn read_metrics(table: &mut Table) -> Result<Vec<Metrics>, String> {
+    let compressed = table.format & COMPRESSED_METRICS != 0;
+    let count = if compressed { table.u16()? as usize } else { table.u32()? as usize };
+    if count > table.data.len() {
+        return Err("Invalid PCF glyph count".to_string());
+    }
+    (0..count)
+        .map(|_| {
+            let mut m = [0; 5];
+            for v in &mut m {
+                *v = if compressed { table.u8()? as i32 - 0x80 } else { table.u16()? as i16 as i32 };
+            }
+            if !compressed {
+                table.u16()?;
+            }
+            Ok(m)
+        })
+        .collect()
+}
+
+/// Glyph bitmaps, normalized to MSB-first bits in MSB-first bytes
+fn read_bitmaps(table: &mut Table, count: usize) -> Result<(Vec<usize>, Vec<u8>, usize), String> {
+    let format = table.format;
+    if table.u32()? as usize != count {
+        return Err("PCF bitmap and metrics counts differ".to_string());
+    }
+    let offsets = (0..count).map(|_| table.u32().map(|o| o as usize)).collect::<Result<Vec<_>, _>>()?;
+    let mut sizes = [0; 4];
+    for size in &mut sizes {
+        *size = table.u32()? as usize;
+    }
+    let mut bits = table.bytes(sizes[(format & 3) as usize])?.to_vec();
+    if format & BIT_MSB_FIRST == 0 {
+        bits.iter_mut().for_each(|b| *b = b.reverse_bits());
+    }
+    let unit = 1 << ((format >> 4) & 3);
+    if (format & BYTE_MSB_FIRST != 0) != (format & BIT_MSB_FIRST != 0) && unit > 1 {
+        bits.chunks_exact_mut(unit).for_each(|u| u.reverse());
+    }
+    Ok((offsets, bits, 1 << (format & 3)))
+}
+
+/// Parse a PCF font, gunzipping it first if needed
+pub fn parse_pcf(data: &[u8]) -> Result<BitmapFont, String> {
+    let unzipped;
+    let data = if data.starts_with(&[0x1f, 0x8b]) {
+        unzipped = gzip_decompress(data)?;
+        &unzipped[..]
+    } else {
+        data
+    };
+    if !data.starts_with(b"\x01fcp") || data.len() < 8 {
+        return Err("Not a PCF font".to_string());
+    }
+    let count = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let mut tables = BTreeMap::new();
+    for i in 0..count.min(64) {
+        let entry = data.get(8 + i * 16..24 + i * 16).ok_or("Truncated PCF table of contents")?;
+        let field = |k: usize| u32::from_le_bytes([entry[k * 4], entry[k * 4 + 1], entry[k * 4 + 2], entry[k * 4 + 3]]);
+        tables.insert(field(0), (field(3) as usize, field(2) as usize));
+    }
+    let open = |kind: u32| tables.get(&kind).map(|&(offset, size)| Table::open(data, offset, size)).transpose();
+
+    let metrics = read_metrics(&mut open(METRICS)?.ok_or("PCF font has no metrics")?)?;
+    let (offsets, bits, pad) = read_bitmaps(&mut open(BITMAPS)?.ok_or("PCF font has no bitmaps")?, metrics.len())?;
+
+    let mut encodings = open(BDF_ENCODINGS)?.ok_or("PCF font has no encodings")?;
+    let (min2, max2, min1, max1, default) = (encodings.u16()?, encodings.u16()?, encodings.u16()?, encodings.u16()?, encodings.u16()?);
+    let mut glyphs = BTreeMap::new();
+    for byte1 in min1..=max1 {
+        for byte2 in min2..=max2 {
+            let index = encodings.u16()? as usize;
+            let (Some(m), Some(c)) = (metrics.get(index), char::from_u32((byte1 as u32) << 8 | byte2 as u32)) else { continue };
+            let [left, right, advance, ascent, descent] = *m;
+            let (width, height) = ((right - left).max(0) as usize, (ascent + descent).max(0) as usize);
+            let stride = width.div_ceil(8).div_ceil(pad) * pad;
+            let start = offsets[index];
+            let rows = bits.get(start..start + stride * height).ok_or("PCF glyph bitmap out of range")?;
+            let bits = rows.chunks_exact(stride.max(1)).take(height).flat_map(|row| (0..width).map(move |x| row[x / 8] >> (7 - x % 8) & 1 != 0)).collect();
+            glyphs.insert(c, Glyph { width: width as u32, height: height as u32, left, top: ascent, advance, bits });
+        }
+    }
+    if glyphs.is_empty() {
+        return Err("PCF font has no glyphs".to_string());
+    }
+
+    // Font ascent and descent follow the accelerator's 8 flag bytes
+    let accelerators = match open(BDF_ACCELERATORS)? {
+        Some(table) => Some(table),
+        None => open(ACCELERATORS)?,
+    };
+    let (ascent, descent) = match accelerators {
+        Some(mut table) => {
+            table.bytes(8)?;
+            (table.u32()? as i32, table.u32()? as i32)
+        }
+        None => (metrics.iter().map(|m| m[3]).max().unwrap_or(0), metrics.iter().map(|m| m[4]).max().unwrap_or(0)),
+    };
+    let default_char = char::from_u32(default as u32).filter(|c| glyphs.contains_key(c));
+    Ok(BitmapFont { glyphs, ascent, descent, default_char })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-glyph PCF ('L', 3x4) with big-endian tables and 4-byte row padding
+    fn sample() -> Vec<u8> {
+        let format = BYTE_MSB_FIRST | BIT_MSB_FIRST | 2;
+        let table = |body: Vec<u8>, format: u32| [format.to_le_bytes().to_vec(), body].concat();
+        let metrics = table([&[0u8, 1][..], &[0x80, 0x83, 0x84, 0x84, 0x80]].concat(), format | COMPRESSED_METRICS);
+        let rows: Vec<u8> = [0x80u8, 0x80, 0x80, 0xe0].iter().flat_map(|&r| [r, 0, 0, 0]).collect();
+        let sizes: Vec<u8> = [4u32, 8, 16, 16].iter().flat_map(|s| s.to_be_bytes()).collect();
+        let bitmaps = table([&1u32.to_be_bytes()[..], &0u32.to_be_bytes(), &sizes, &rows].concat(), format);
+        let encodings = table([0x4cu16, 0x4c, 0, 0, 0x4c, 0].iter().flat_map(|v| v.to_be_bytes()).collect(), format);
+        let accel = table([&[0u8; 8][..], &5u32.to_be_bytes(), &1u32.to_be_bytes()].concat(), format);
+
+        let tables = [(METRICS, metrics), (BITMAPS, bitmaps), (BDF_ENCODINGS, encodings), (BDF_ACCELERATORS, accel)];
+        let mut out = b"\x01fcp".to_vec();
+        out.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+        let mut offset = 8 + 16 * tables.len();
+        for (kind, body) in &tables {
+            for v in [*kind, format, body.len() as u32, offset as u32] {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            offset += body.len();
+        }
+        tables.iter().for_each(|(_, body)| out.extend_from_slice(body));
+        out
+    }
+
+    #[test]
+    fn test_parse_pcf() {
+        let font = parse_pcf(&sample()).unwrap();
+        assert_eq!((font.glyph_count(), font.ascent(), font.line_height()), (1, 5, 6));
+        let l = &font.glyphs[&'L'];
+        assert_eq!((l.width, l.height, l.top, l.advance), (3, 4, 4, 4));
+        assert_eq!(l.bits, [true, false, false, true, false, false, true, false, false, true, true, true]);
+        assert_eq!(parse_pcf(&crate::compress::gzip_compress(&sample(), 6)).unwrap(), font);
+        assert!(parse_pcf(b"\x01fcp\0\0\0\0").is_err());
+    }
+}
@@ -0,0 +1,506 @@
+//! TrueType/OpenType fonts with `glyf` outlines: parsing and anti-aliased
+//! glyph rendering
+
+use crate::draw::raster::{Point, Rasterizer};
+use crate::animation::blend_over;
+use crate::utils::{check_rgba, read_u16_be, read_u32_be};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Nesting limit for composite glyphs
+const MAX_COMPONENT_DEPTH: usize = 8;
+
+fn read_i16(data: &[u8], pos: usize) -> i16 {
+    read_u16_be(data, pos) as i16
+}
+
+/// Glyph outline as closed contours of (point, on curve) in font units
+type Contours = Vec<Vec<(Point, bool)>>;
+
+/// An anti-aliased glyph image
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphBitmap {
+    pub width: u32,
+    pub height: u32,
+    /// Offset of the image's left edge from the pen position
+    pub left: i32,
+    /// Height of the image's top edge above the baseline
+    pub top: i32,
+    /// Pen movement after the glyph, in pixels
+    pub advance: f32,
+    /// Coverage per pixel, 0-255
+    pub coverage: Vec<u8>,
+}
+
+/// A parsed TrueType font (or OpenType font with TrueType outlines)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct TrueTypeFont {
+    data: Vec<u8>,
+    units_per_em: f32,
+    long_offsets: bool,
+    glyph_count: u16,
+    loca: usize,
+    glyf: (usize, usize),
+    hmtx: usize,
+    h_metric_count: u16,
+    /// Offset and format of the Unicode cmap subtable
+    cmap: (usize, u16),
+    ascender: i16,
+    descender: i16,
+    line_gap: i16,
+}
+
+impl TrueTypeFont {
+    /// Parse the font, or the first one of a TrueType collection
+    pub fn parse(data: &[u8]) -> Result<TrueTypeFont, String> {
+        let data = data.to_vec();
+        let start = if data.starts_with(b"ttcf") && data.len() >= 16 { read_u32_be(&data, 12) as usize } else { 0 };
+        let header = data.get(start..start + 12).ok_or("Truncated font header")?;
+        if !matches!(&header[..4], [0, 1, 0, 0] | b"true" | b"OTTO") {
+            return Err("Not a TrueType or OpenType font".to_string());
+        }
+        let table_count = read_u16_be(header, 4) as usize;
+        let directory = data.get(start + 12..start + 12 + table_count * 16).ok_or("Truncated font table directory")?;
+        let table = |tag: &[u8; 4]| -> Option<(usize, usize)> {
+            let entry = directory.chunks_exact(16).find(|e| &e[..4] == tag)?;
+            let (offset, len) = (read_u32_be(entry, 8) as usize, read_u32_be(entry, 12) as usize);
+            (offset.checked_add(len)? <= data.len()).then_some((offset, len))
+        };
+        let require = |tag: &[u8; 4], min: usize| {
+            table(tag).filter(|&(_, len)| len >= min).ok_or_else(|| format!("Font has no usable '{}' table", String::from_utf8_lossy(tag)))
+        };
+        if table(b"glyf").is_none() && table(b"CFF ").is_some() {
+            return Err("CFF (PostScript) outlines are not supported".to_string());
+        }
+        let (head, _) = require(b"head", 54)?;
+        let (maxp, _) = require(b"maxp", 6)?;
+        let (hhea, _) = require(b"hhea", 36)?;
+        let (hmtx, hmtx_len) = require(b"hmtx", 4)?;
+        let (loca, loca_len) = require(b"loca", 2)?;
+        let glyf = require(b"glyf", 0)?;
+        let (cmap, cmap_len) = require(b"cmap", 4)?;
+
+        let units_per_em = read_u16_be(&data, head + 18);
+        if units_per_em == 0 {
+            return Err("Font has zero units per em".to_string());
+        }
+        let long_offsets = read_i16(&data, head + 50) != 0;
+        let glyph_count = read_u16_be(&data, maxp + 4);
+        if loca_len < (glyph_count as usize + 1) * if long_offsets { 4 } else { 2 } {
+            return Err("Font 'loca' table is too short".to_string());
+        }
+        let h_metric_count = read_u16_be(&data, hhea + 34);
+        if h_metric_count == 0 || hmtx_len < h_metric_count as usize * 4 {
+            return Err("Font 'hmtx' table is too short".to_string());
+        }
+
+        // Prefer full Unicode (format 12), then the BMP (format 4)
+        let subtables = (0..read_u16_be(&data, cmap + 2) as usize).filter_map(|i| {
+            let entry = cmap + 4 + i * 8;
+            (entry + 8 <= cmap + cmap_len).then(|| (read_u16_be(&data, entry), read_u16_be(&data, entry + 2), cmap + read_u32_be(&data, entry + 4) as usize))
+        });
+        let best = subtables
+            .filter(|&(_, _, offset)| offset + 8 <= data.len())
+            .map(|(platform, encoding, offset)| (platform, encoding, offset, read_u16_be(&data, offset)))
+            .filter(|&(platform, encoding, _, format)| (platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10))) && (format == 4 || format == 12))
+            .max_by_key(|&(_, _, _, format)| format)
+            .ok_or("Font has no Unicode character map")?;
+
+        Ok(TrueTypeFont {
+            units_per_em: units_per_em as f32,
+            long_offsets,
+            glyph_count,
+            loca,
+            glyf,
+            hmtx,
+            h_metric_count,
+            cmap: (best.2, best.3),
+            ascender: read_i16(&data, hhea + 4),
+            descender: read_i16(&data, hhea + 6),
+            line_gap: read_i16(&data, hhea + 8),
+            data,
+        })
+    }
+
+    /// Glyph index for `c`, 0 (the missing glyph) if unmapped
+    pub fn glyph_index(&self, c: char) -> u16 {
+        let d = &self.data;
+        let (offset, format) = self.cmap;
+        let code = c as u32;
+        let found = match format {
+            4 => {
+                let segments = read_u16_be(d, offset + 6) as usize / 2;
+                let ends = offset + 14;
+                let (starts, deltas, ranges) = (ends + segments * 2 + 2, ends + segments * 4 + 2, ends + segments * 6 + 2);
+                if code > 0xffff || ranges + segments * 2 > d.len() {
+                    return 0;
+                }
+                (0..segments).find(|&i| read_u16_be(d, ends + i * 2) as u32 >= code).and_then(|i| {
+                    let start = read_u16_be(d, starts + i * 2) as u32;
+                    if code < start {
+                        return None;
+                    }
+                    let delta = read_u16_be(d, deltas + i * 2);
+                    let range = read_u16_be(d, ranges + i * 2) as usize;
+                    if range == 0 {
+                        return Some((code as u16).wrapping_add(delta));
+                    }
+                    let at = ranges + i * 2 + range + (code - start) as usize * 2;
+                    let glyph = d.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))?;
+                    (glyph != 0).then(|| glyph.wrapping_add(delta))
+                })
+            }
+            _ => {
+                let groups = read_u32_be(d, offset + 12) as usize;
+                (0..groups).map(|i| offset + 16 + i * 12).take_while(|&g| g + 12 <= d.len()).find_map(|g| {
+                    let (start, end) = (read_u32_be(d, g), read_u32_be(d, g + 4));
+                    (start..=end).contains(&code).then(|| (read_u32_be(d, g + 8) + code - start) as u16)
+                })
+            }
+        };
+        found.filter(|&g| g < self.glyph_count).unwrap_or(0)
+    }
+
+    /// Advance width of a glyph in font units
+    fn advance(&self, glyph: u16) -> f32 {
+        let index = glyph.min(self.h_metric_count - 1) as usize;
+        read_u16_be(&self.data, self.hmtx + index * 4) as f32
+    }
+
+    /// Outline data of a glyph within `glyf`, empty for blank glyphs
+    fn glyph_data(&self, glyph: u16) -> &[u8] {
+        let i = glyph as usize;
+        let (start, end) = if self.long_offsets {
+            (read_u32_be(&self.data, self.loca + i * 4) as usize, read_u32_be(&self.data, self.loca + i * 4 + 4) as usize)
+        } else {
+            (read_u16_be(&self.data, self.loca + i * 2) as usize * 2, read_u16_be(&self.data, self.loca + i * 2 + 2) as usize * 2)
+        };
+        let (glyf, len) = self.glyf;
+        if start >= end || end > len {
+            return &[];
+        }
+        &self.data[glyf + start..glyf + end]
+    }
+
+    fn outline(&self, glyph: u16, depth: usize, contours: &mut Contours) -> Result<(), String> {
+        let data = self.glyph_data(glyph);
+        if data.len() < 10 {
+            return Ok(());
+        }
+        let truncated = || "Truncated glyph outline".to_string();
+        let contour_count = read_i16(data, 0);
+        if contour_count < 0 {
+            return self.composite(&data[10..], depth, contours);
+        }
+
+        let contour_count = contour_count as usize;
+        let ends: Vec<usize> = (0..contour_count).map(|i| data.get(10 + i * 2..12 + i * 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)).collect::<Option<_>>().ok_or_else(truncated)?;
+        let point_count = ends.last().map_or(0, |&e| e + 1);
+        let instructions = 10 + contour_count * 2;
+        let mut pos = instructions + 2 + data.get(instructions..instructions + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize).ok_or_else(truncated)?;
+
+        let mut flags = Vec::with_capacity(point_count);
+        while flags.len() < point_count {
+            let flag = *data.get(pos).ok_or_else(truncated)?;
+            pos += 1;
+            let repeat = if flag & 0x08 != 0 {
+                pos += 1;
+                *data.get(pos - 1).ok_or_else(truncated)? as usize
+            } else {
+                0
+            };
+            flags.extend(std::iter::repeat_n(flag, repeat + 1));
+        }
+        flags.truncate(point_count);
+
+        // Coordinates are deltas: a short byte with a sign flag, a repeat, or an i16
+        let mut read_axis = |short: u8, same: u8| -> Result<Vec<f32>, String> {
+            let mut value = 0i32;
+            flags
+                .iter()
+                .map(|&flag| {
+                    if flag & short != 0 {
+                        let b = *data.get(pos).ok_or_else(truncated)? as i32;
+                        pos += 1;
+                        value += if flag & same != 0 { b } else { -b };
+                    } else if flag & same == 0 {
+                        value += data.get(pos..pos + 2).map(|b| i16::from_be_bytes([b[0], b[1]]) as i32).ok_or_else(truncated)?;
+                        pos += 2;
+                    }
+                    Ok(value as f32)
+                })
+                .collect()
+        };
+        let xs = read_axis(0x02, 0x10)?;
+        let ys = read_axis(0x04, 0x20)?;
+
+        let mut start = 0;
+        for &end in &ends {
+            if end < start || end >= point_count {
+                return Err("Invalid glyph contour".to_string());
+            }
+            contours.push((start..=end).map(|i| (Point { x: xs[i], y: ys[i] }, flags[i] & 1 != 0)).collect());
+            start = end + 1;
+        }
+        Ok(())
+    }
+
+    /// Components: flags, glyph, offset or point-match arguments, optional transform
+    fn composite(&self, mut data: &[u8], depth: usize, contours: &mut Contours) -> Result<(), String> {
+        if depth >= MAX_COMPONENT_DEPTH {
+            return Err("Composite glyphs nest too deeply".to_string());
+        }
+        let truncated = || "Truncated composite glyph".to_string();
+        loop {
+            let header = data.get(..4).ok_or_else(truncated)?;
+            let (flags, glyph) = (read_u16_be(header, 0), read_u16_be(header, 2));
+            let mut pos = 4;
+            let (dx, dy) = if flags & 0x01 != 0 {
+                pos += 4;
+                let b = data.get(4..8).ok_or_else(truncated)?;
+                (read_i16(b, 0) as f32, read_i16(b, 2) as f32)
+            } else {
+                pos += 2;
+                let b = data.get(4..6).ok_or_else(truncated)?;
+                (b[0] as i8 as f32, b[1] as i8 as f32)
+            };
+            // Point-matched components are placed without offset
+            let (dx, dy) = if flags & 0x02 != 0 { (dx, dy) } else { (0.0, 0.0) };
+            let f2dot14 = |at: usize| data.get(at..at + 2).map(|b| i16::from_be_bytes([b[0], b[1]]) as f32 / 16384.0).ok_or_else(truncated);
+            let (a, b, c, d) = if flags & 0x08 != 0 {
+                pos += 2;
+                let s = f2dot14(pos - 2)?;
+                (s, 0.0, 0.0, s)
+            } else if flags & 0x40 != 0 {
+                pos += 4;
+                (f2dot14(pos - 4)?, 0.0, 0.0, f2dot14(pos - 2)?)
+            } else if flags & 0x80 != 0 {
+                pos += 8;
+                (f2dot14(pos - 8)?, f2dot14(pos - 6)?, f2dot14(pos - 4)?, f2dot14(pos - 2)?)
+            } else {
+                (1.0, 0.0, 0.0, 1.0)
+            };
+
+            let mut component = Vec::new();
+            self.outline(glyph, depth + 1, &mut component)?;
+            for contour in &mut component {
+                for (p, _) in contour.iter_mut() {
+                    *p = Point { x: a * p.x + c * p.y + dx, y: b * p.x + d * p.y + dy };
+                }
+            }
+            contours.extend(component);
+
+            if flags & 0x20 == 0 {
+                return Ok(());
+            }
+            data = data.get(pos..).ok_or_else(truncated)?;
+        }
+    }
+
+    /// Render `glyph` at `size` pixels per em
+    pub fn render_glyph(&self, glyph: u16, size: f32) -> Result<GlyphBitmap, String> {
+        let mut contours = Vec::new();
+        self.outline(glyph, 0, &mut contours)?;
+        let scale = size / self.units_per_em;
+        let advance = self.advance(glyph) * scale;
+        let points = contours.iter().flatten().map(|(p, _)| p);
+        let (mut x_min, mut y_min, mut x_max, mut y_max) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+        for p in points {
+            (x_min, y_min, x_max, y_max) = (x_min.min(p.x), y_min.min(p.y), x_max.max(p.x), y_max.max(p.y));
+        }
+        if x_min > x_max {
+            return Ok(GlyphBitmap { width: 0, height: 0, left: 0, top: 0, advance, coverage: Vec::new() });
+        }
+        let (left, top) = ((x_min * scale).floor(), (y_max * scale).ceil());
+        let width = ((x_max * scale).ceil() - left).max(1.0) as usize;
+        let height = (top - (y_min * scale).floor()).max(1.0) as usize;
+        if width * height > 1 << 24 {
+            return Err("Glyph is too large to render".to_string());
+        }
+
+        // Font units, y up, to pixels, y down
+        let to_pixel = |p: Point| Point { x: p.x * scale - left, y: top - p.y * scale };
+        let mut raster = Rasterizer::new(width, height);
+        for contour in contours.iter().filter(|c| !c.is_empty()) {
+            // Start on an on-curve point; between two off-curve points one is implied
+            let n = contour.len();
+            let first = contour.iter().position(|&(_, on)| on);
+            let start = match first {
+                Some(i) => contour[i].0,
+                None => Point { x: (contour[0].0.x + contour[n - 1].0.x) / 2.0, y: (contour[0].0.y + contour[n - 1].0.y) / 2.0 },
+            };
+            let offset = first.unwrap_or(0);
+            let mut current = start;
+            let mut control: Option<Point> = None;
+            for k in 1..=n {
+                let (p, on) = if k == n && first.is_some() { (start, true) } else { contour[(offset + k) % n] };
+                match (control, on) {
+                    (None, true) => {
+                        raster.line(to_pixel(current), to_pixel(p));
+                        current = p;
+                    }
+                    (None, false) => control = Some(p),
+                    (Some(c), true) => {
+                        raster.quad(to_pixel(current), to_pixel(c), to_pixel(p));
+                        current = p;
+                        control = None;
+                    }
+                    (Some(c), false) => {
+                        let mid = Point { x: (c.x + p.x) / 2.0, y: (c.y + p.y) / 2.0 };
+                        raster.quad(to_pixel(current), to_pixel(c), to_pixel(mid));
+                        current = mid;
+                        control = Some(p);
+                    }
+                }
+            }
+            match control {
+                Some(c) => raster.quad(to_pixel(current), to_pixel(c), to_pixel(start)),
+                None => raster.line(to_pixel(current), to_pixel(start)),
+            }
+        }
+        Ok(GlyphBitmap { width: width as u32, height: height as u32, left: left as i32, top: top as i32, advance, coverage: raster.coverage() })
+    }
+
+    /// Draw `text` at `size` pixels per em with the top of its first line
+    /// at (x, y); `\n` starts a new line
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(&self, data: &[u8], width: u32, height: u32, text: &str, x: i32, y: i32, size: f32, color: [u8; 4]) -> Result<Vec<u8>, String> {
+        check_rgba(data, width, height)?;
+        if !(size > 0.0 && size <= 4096.0) {
+            return Err("Text size must be 0-4096 pixels".to_string());
+        }
+        let scale = size / self.units_per_em;
+        let mut output = data.to_vec();
+        for (line, text) in text.split('\n').enumerate() {
+            let baseline = y as f32 + (line as f32 * self.line_height() + self.ascender as f32) * scale;
+            let mut pen = x as f32;
+            for c in text.chars() {
+                let glyph = self.glyph_index(c);
+                let image = self.render_glyph(glyph, size)?;
+                let (gx, gy) = (pen.round() as i64 + image.left as i64, baseline.round() as i64 - image.top as i64);
+                for (i, &coverage) in image.coverage.iter().enumerate().filter(|(_, &c)| c > 0) {
+                    let (px, py) = (gx + (i as u32 % image.width) as i64, gy + (i as u32 / image.width) as i64);
+                    if px >= 0 && py >= 0 && px < width as i64 && py < height as i64 {
+                        let at = (py as usize * width as usize + px as usize) * 4;
+                        let alpha = (color[3] as u32 * coverage as u32 + 127) / 255;
+                        blend_over(&mut output[at..at + 4], &[color[0], color[1], color[2], alpha as u8]);
+                    }
+                }
+                pen += image.advance;
+            }
+        }
+        Ok(output)
+    }
+
+    /// Distance between baselines in font units
+    fn line_height(&self) -> f32 {
+        (self.ascender as i32 - self.descender as i32 + self.line_gap as i32) as f32
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TrueTypeFont {
+    /// Parse a TTF, OTF (TrueType outlines) or TTC file
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &[u8]) -> Result<TrueTypeFont, JsError> {
+        TrueTypeFont::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = glyphCount))]
+    pub fn glyph_count(&self) -> u16 {
+        self.glyph_count
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = unitsPerEm))]
+    pub fn units_per_em(&self) -> u16 {
+        self.units_per_em as u16
+    }
+
+    /// Width in pixels of the widest line of `text` at `size` pixels per em
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = measure))]
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        let scale = size / self.units_per_em;
+        text.split('\n').map(|line| line.chars().map(|c| self.advance(self.glyph_index(c))).sum::<f32>() * scale).fold(0.0, f32::max)
+    }
+
+    /// Pixels between baselines at `size` pixels per em
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = lineHeight))]
+    pub fn line_height_px(&self, size: f32) -> f32 {
+        self.line_height() * size / self.units_per_em
+    }
+
+    /// Anti-aliased coverage (0-255) of one character at `size` pixels per em
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = renderGlyph)]
+    pub fn render_glyph_js(&self, c: char, size: f32) -> Result<GlyphBitmap, JsError> {
+        if !(size > 0.0 && size <= 4096.0) {
+            return Err(JsError::new("Text size must be 0-4096 pixels"));
+        }
+        self.render_glyph(self.glyph_index(c), size).map_err(|e| JsError::new(&e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn be16(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|&v| (v as u16).to_be_bytes()).collect()
+    }
+
+    /// A 1000-unit font: 'A' is a 500-unit square, 'B' the same square as a
+    /// component shifted right by 500
+    fn sample() -> Vec<u8> {
+        let square = [be16(&[1, 0, 0, 500, 500, 3, 0]), vec![1; 4], be16(&[0, 500, 0, -500, 0, 0, 500, 0])].concat();
+        let composite = be16(&[-1, 0, 0, 1000, 500, 0x03, 1, 500, 0]);
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        let mut hhea = vec![0; 36];
+        hhea[4..10].copy_from_slice(&be16(&[800, -200, 0]));
+        hhea[34..36].copy_from_slice(&3u16.to_be_bytes());
+        let cmap = [be16(&[0, 1, 3, 1, 0, 12]), be16(&[4, 32, 0, 4, 4, 1, 0, 66, 0xffff, 0, 65, 0xffff, -64, 1, 0, 0])].concat();
+        let tables: [(&[u8; 4], Vec<u8>); 7] = [
+            (b"cmap", cmap),
+            (b"glyf", [square.clone(), composite.clone()].concat()),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", be16(&[600, 0, 600, 0, 1100, 0])),
+            (b"loca", be16(&[0, 0, square.len() as i32 / 2, (square.len() + composite.len()) as i32 / 2])),
+            (b"maxp", be16(&[0, 0x5000, 3])),
+        ];
+        let mut font = [&[0u8, 1, 0, 0][..], &be16(&[tables.len() as i32, 0, 0, 0])].concat();
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, body) in &tables {
+            font.extend_from_slice(&tag[..]);
+            font.extend_from_slice(&[0; 4]);
+            font.extend_from_slice(&(offset as u32).to_be_bytes());
+            font.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            offset += body.len();
+        }
+        tables.iter().for_each(|(_, body)| font.extend_from_slice(body));
+        font
+    }
+
+    #[test]
+    fn test_render() {
+        let font = TrueTypeFont::parse(&sample()).unwrap();
+        assert_eq!((font.glyph_index('A'), font.glyph_index('B'), font.glyph_index('C')), (1, 2, 0));
+        let square = font.render_glyph(1, 10.0).unwrap();
+        assert_eq!((square.width, square.height, square.left, square.top, square.advance), (5, 5, 0, 5, 6.0));
+        assert!(square.coverage.iter().all(|&c| c == 255));
+        let shifted = font.render_glyph(2, 10.0).unwrap();
+        assert_eq!((shifted.width, shifted.left), (5, 5));
+        // Half-pixel edges are half covered, the corner a quarter
+        let soft = font.render_glyph(1, 9.0).unwrap();
+        assert_eq!((soft.width, soft.coverage[4], soft.coverage[9], soft.coverage[20]), (5, 64, 128, 255));
+        assert!(font.render_glyph(0, 10.0).unwrap().coverage.is_empty());
+
+        assert_eq!(font.text_width("AB", 10.0), 17.0);
+        let image = font.draw(&[0; 20 * 12 * 4], 20, 12, "AB", 0, 0, 10.0, [0, 0, 0, 255]).unwrap();
+        let ink = |x: usize, y: usize| image[(y * 20 + x) * 4 + 3];
+        assert_eq!((ink(2, 5), ink(5, 5), ink(12, 5), ink(12, 8)), (255, 0, 255, 0));
+        assert!(TrueTypeFont::parse(b"OTTO\0\0\0\0\0\0\0\0").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! GIF decoder with frame compositing - pure Rust implementation
+
+use crate::animation::{Canvas, FrameSequence, Rect};
+use crate::compress::lzw::lzw_decompress_into;
+use crate::compress::LzwOptions;
+use crate::limits::Budget;
+use crate::pool::{self, Scratch};
+use crate::utils::read_u16_le;
+
+/// Graphic Control Extension state for the next image
+#[derive(Clone, Copy, Default)]
+struct Control {
+    disposal: u8,
+    /// Hundredths of a second
+    delay: u16,
+    transparent: Option<u8>,
+}
+
+/// Concatenate a chain of data sub-blocks starting at `pos`, returning the data and the end offset
+fn sub_blocks(data: &[u8], mut pos: usize) -> Result<(Scratch<u8>, usize), String> {
+    let mut out = pool::take_empty(0);
+    loop {
+        let len = *data.get(pos).ok_or("Truncated GIF data sub-blocks")? as usize;
+        pos += 1;
+        if len == 0 {
+            return Ok((out, pos));
+        }
+        out.extend_from_slice(data.get(pos..pos + len).ok_or("Truncated GIF data sub-blocks")?);
+        pos += len;
+    }
+}
+
+fn color_table(data: &[u8], pos: usize, packed: u8) -> Result<Vec<[u8; 3]>, String> {
+    let len = 3 << ((packed & 7) + 1);
+    let table = data.get(pos..pos + len).ok_or("Truncated GIF color table")?;
+    Ok(table.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect())
+}
+
+/// Row order of an interlaced image: every 8th from 0, every 8th from 4, every 4th from 2, every 2nd from 1
+fn interlaced_rows(height: usize) -> Vec<usize> {
+    [(0, 8), (4, 8), (2, 4), (1, 2)].into_iter().flat_map(|(start, step)| (start..height).step_by(step)).collect()
+}
+
+/// Decode a GIF into composited frames
+///
+/// Frames start on a transparent canvas, as browsers do, rather than the
+/// background color. A stream truncated after at least one frame keeps the
+/// frames decoded so far; hitting a limit is an error all the same.
+pub fn decode_gif(data: &[u8]) -> Result<FrameSequence, String> {
+    if data.len() < 13 || !(data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")) {
+        return Err("Invalid GIF signature".to_string());
+    }
+    let (width, height) = (read_u16_le(data, 6) as u32, read_u16_le(data, 8) as u32);
+    if width == 0 || height == 0 {
+        return Err("GIF has zero width or height".to_string());
+    }
+    let mut budget = Budget::new();
+    budget.check_size(width as usize, height as usize)?;
+    let packed = data[10];
+    let mut pos = 13;
+    let global = if packed & 0x80 != 0 {
+        let table = color_table(data, pos, packed)?;
+        pos += table.len() * 3;
+        table
+    } else {
+        Vec::new()
+    };
+
+    let mut sequence = FrameSequence::new(width, height);
+    // Without a NETSCAPE extension the animation plays once
+    sequence.loop_count = 1;
+    let mut canvas = Canvas::new(width, height);
+    let mut control = Control::default();
+
+    while let Some(&block) = data.get(pos) {
+        let result = match block {
+            0x21 => read_extension(data, pos, &mut control, &mut sequence),
+            0x2C => read_image(data, pos, &global, control, &mut canvas, &mut sequence, &mut budget).inspect(|_| control = Control::default()),
+            0x3B => break,
+            b => Err(format!("Unknown GIF block 0x{:02X}", b)),
+        };
+        match result {
+            Ok(next) => pos = next,
+            Err(e) if sequence.frames().is_empty() || budget.exceeded() => return Err(e),
+            Err(_) => break,
+        }
+    }
+    if sequence.frames().is_empty() {
+        return Err("GIF has no images".to_string());
+    }
+    Ok(sequence)
+}
+
+fn read_extension(data: &[u8], pos: usize, control: &mut Control, sequence: &mut FrameSequence) -> Result<usize, String> {
+    let label = *data.get(pos + 1).ok_or("Truncated GIF extension")?;
+    let (body, end) = sub_blocks(data, pos + 2)?;
+    match label {
+        0xF9 if body.len() >= 4 => {
+            *control = Control {
+                disposal: (body[0] >> 2) & 7,
+                delay: u16::from_le_bytes([body[1], body[2]]),
+                transparent: (body[0] & 1 != 0).then_some(body[3]),
+            };
+        }
+        0xFF => {
+            // The application identifier is the first sub-block, the loop count the second
+            let id_len = data[pos + 2] as usize;
+            let id = &body[..id_len.min(body.len())];
+            let rest = &body[id.len()..];
+            if (id == b"NETSCAPE2.0" || id == b"ANIMEXTS1.0") && rest.len() >= 3 && rest[0] == 1 {
+                // Repetitions after the first play; 0 means forever
+                sequence.loop_count = match u16::from_le_bytes([rest[1], rest[2]]) {
+                    0 => 0,
+                    n => n as u32 + 1,
+                };
+            }
+        }
+        _ => {}
+    }
+    Ok(end)
+}
+
+fn read_image(data: &[u8], pos: usize, global: &[[u8; 3]], control: Control, canvas: &mut Canvas, sequence: &mut FrameSequence, budget: &mut Budget) -> Result<usize, String> {
+    let descriptor = data.get(pos + 1..pos + 10).ok_or("Truncated GIF image descriptor")?;
+    let rect = Rect {
+        x: read_u16_le(descriptor, 0) as u32,
+        y: read_u16_le(descriptor, 2) as u32,
+        width: read_u16_le(descriptor, 4) as u32,
+        height: read_u16_le(descriptor, 6) as u32,
+    };
+    let packed = descriptor[8];
+    let mut pos = pos + 10;
+    let local;
+    let palette = if packed & 0x80 != 0 {
+        local = color_table(data, pos, packed)?;
+        pos += local.len() * 3;
+        &local[..]
+    } else {
+        global
+    };
+
+    let min_code_size = *data.get(pos).ok_or("Truncated GIF image data")?;
+    if !(1..=11).contains(&min_code_size) {
+        return Err(format!("Invalid LZW minimum code size {}", min_code_size));
+    }
+    let (w, h) = (rect.width as usize, rect.height as usize);
+    budget.check_size(w, h)?;
+    budget.rows(h)?;
+    budget.frame(canvas.data.len())?;
+    let (compressed, end) = sub_blocks(data, pos + 1)?;
+    let mut indices = pool::take_empty(w * h);
+    lzw_decompress_into(&compressed, &LzwOptions::gif(min_code_size), &mut indices)?;
+
+    let rows: Vec<usize> = if packed & 0x40 != 0 { interlaced_rows(h) } else { (0..h).collect() };
+    // Pixels missing from a short stream stay transparent
+    let mut pixels = pool::take(w * h * 4, 0u8);
+    for (row_indices, &y) in indices.chunks(w.max(1)).zip(&rows) {
+        for (x, &index) in row_indices.iter().enumerate() {
+            if control.transparent == Some(index) {
+                continue;
+            }
+            let [r, g, b] = palette.get(index as usize).copied().unwrap_or([0, 0, 0]);
+            pixels[(y * w + x) * 4..(y * w + x) * 4 + 4].copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+
+    let previous = (control.disposal == 3).then(|| {
+        let mut previous = pool::take_empty(canvas.data.len());
+        previous.extend_from_slice(&canvas.data);
+        previous
+    });
+    canvas.draw(&pixels, rect, true);
+    sequence.push(canvas.data.clone(), control.delay as u32 * 10)?;
+    match (control.disposal, previous) {
+        (2, _) => canvas.clear(rect),
+        (3, Some(previous)) => canvas.data.copy_from_slice(&previous),
+        _ => {}
+    }
+    Ok(end)
+}
@@ -0,0 +1,160 @@
+//! Animated GIF encoder - pure Rust implementation
+
+use crate::animation::{changed_rect, FrameSequence, Rect};
+use crate::compress::{lzw_compress, LzwOptions};
+use crate::quantize::{dither_to_palette, generate_palette, map_to_palette, DitherMethod, QuantizeOptions};
+
+/// Pixels with less alpha than this become the transparent index
+const ALPHA_THRESHOLD: u8 = 128;
+
+fn opaque(p: &[u8]) -> bool {
+    p[3] >= ALPHA_THRESHOLD
+}
+
+fn write_sub_blocks(out: &mut Vec<u8>, data: &[u8]) {
+    for block in data.chunks(255) {
+        out.push(block.len() as u8);
+        out.extend_from_slice(block);
+    }
+    out.push(0);
+}
+
+/// One quantized image ready to be written
+struct Image {
+    rect: Rect,
+    palette: Vec<[u8; 4]>,
+    indices: Vec<u8>,
+    transparent: Option<u8>,
+}
+
+/// Quantize the `rect` part of `frame`, leaving pixels marked in `skip`
+/// transparent so the previous frame shows through
+fn quantize_rect(frame: &[u8], width: u32, rect: Rect, skip: impl Fn(usize) -> bool, options: &QuantizeOptions) -> Result<Image, String> {
+    let (w, h) = (rect.width as usize, rect.height as usize);
+    let mut region = Vec::with_capacity(w * h * 4);
+    let mut hidden = Vec::with_capacity(w * h);
+    for y in rect.y as usize..rect.y as usize + h {
+        for x in rect.x as usize..rect.x as usize + w {
+            let at = y * width as usize + x;
+            let p = &frame[at * 4..at * 4 + 4];
+            hidden.push(!opaque(p) || skip(at));
+            region.extend_from_slice(&[p[0], p[1], p[2], 255]);
+        }
+    }
+
+    let transparent = hidden.iter().any(|&t| t);
+    let colors = (options.max_colors.clamp(2, 256) as usize - transparent as usize).max(1);
+    let visible: Vec<u8> = region.chunks_exact(4).zip(&hidden).filter(|(_, &t)| !t).flat_map(|(p, _)| p.iter().copied()).collect();
+    let mut palette = generate_palette(&visible, colors, options.method);
+    if palette.is_empty() {
+        palette.push([0, 0, 0, 255]);
+    }
+    let mut indices = match options.dither {
+        DitherMethod::None => map_to_palette(&region, &palette),
+        dither => dither_to_palette(&region, rect.width, rect.height, &palette, dither)?,
+    };
+
+    let transparent = transparent.then(|| {
+        palette.push([0, 0, 0, 0]);
+        let index = palette.len() as u8 - 1;
+        for (i, _) in hidden.iter().enumerate().filter(|(_, &t)| t) {
+            indices[i] = index;
+        }
+        index
+    });
+    Ok(Image { rect, palette, indices, transparent })
+}
+
+/// Encode a frame sequence as an animated GIF
+///
+/// Each frame gets its own palette from `options`; alpha below 128 becomes
+/// transparent. Frames store only the rectangle that changed, with unchanged
+/// pixels left transparent, unless some pixel turns transparent after being
+/// opaque: then every frame is stored whole and disposed to background.
+pub fn encode_gif(sequence: &FrameSequence, options: &QuantizeOptions) -> Result<Vec<u8>, String> {
+    let (width, height) = (sequence.width(), sequence.height());
+    let frames = sequence.frames();
+    if frames.is_empty() {
+        return Err("Animation has no frames".to_string());
+    }
+    if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(format!("GIF dimensions must be 1-65535, got {}x{}", width, height));
+    }
+    if !(1..=256).contains(&options.max_colors) {
+        return Err(format!("Palette size must be 1-256, got {}", options.max_colors));
+    }
+    let clears = frames
+        .windows(2)
+        .any(|pair| pair[0].data.chunks_exact(4).zip(pair[1].data.chunks_exact(4)).any(|(a, b)| opaque(a) && !opaque(b)));
+    let disposal = if clears { 2 } else { 1 };
+
+    let mut out = b"GIF89a".to_vec();
+    out.extend_from_slice(&(width as u16).to_le_bytes());
+    out.extend_from_slice(&(height as u16).to_le_bytes());
+    // No global color table
+    out.extend_from_slice(&[0, 0, 0]);
+    if sequence.loop_count != 1 {
+        let repeats = sequence.loop_count.saturating_sub(1).min(u16::MAX as u32) as u16;
+        out.extend_from_slice(&[0x21, 0xFF, 11]);
+        out.extend_from_slice(b"NETSCAPE2.0");
+        out.extend_from_slice(&[3, 1]);
+        out.extend_from_slice(&repeats.to_le_bytes());
+        out.push(0);
+    }
+
+    let full = Rect { x: 0, y: 0, width, height };
+    // What a decoder shows after each frame, to diff the next one against
+    let mut shown = vec![0u8; width as usize * height as usize * 4];
+    let mut elapsed = 0u64;
+    for (i, frame) in frames.iter().enumerate() {
+        let image = if clears || i == 0 {
+            quantize_rect(&frame.data, width, full, |_| false, options)?
+        } else {
+            // Compare as displayed: all transparent pixels are alike
+            let normalized: Vec<u8> = frame.data.chunks_exact(4).flat_map(|p| if opaque(p) { [p[0], p[1], p[2], 255] } else { [0; 4] }).collect();
+            let rect = changed_rect(&shown, &normalized, width).unwrap_or(Rect { width: 1, height: 1, ..full });
+            quantize_rect(&frame.data, width, rect, |at| shown[at * 4..at * 4 + 4] == normalized[at * 4..at * 4 + 4], options)?
+        };
+
+        // Round the running time so delays do not drift
+        let start = (elapsed + 5) / 10;
+        elapsed += frame.duration as u64;
+        let delay = ((elapsed + 5) / 10 - start).min(u16::MAX as u64) as u16;
+        write_image(&mut out, &image, disposal, delay)?;
+
+        if clears {
+            continue;
+        }
+        let Rect { x, y, width: w, .. } = image.rect;
+        for (i, &index) in image.indices.iter().enumerate().filter(|(_, &index)| Some(index) != image.transparent) {
+            let at = ((y + i as u32 / w) * width + x + i as u32 % w) as usize * 4;
+            shown[at..at + 4].copy_from_slice(&image.palette[index as usize]);
+        }
+    }
+    out.push(0x3B);
+    Ok(out)
+}
+
+fn write_image(out: &mut Vec<u8>, image: &Image, disposal: u8, delay: u16) -> Result<(), String> {
+    // Graphic Control Extension
+    out.extend_from_slice(&[0x21, 0xF9, 4, disposal << 2 | image.transparent.is_some() as u8]);
+    out.extend_from_slice(&delay.to_le_bytes());
+    out.extend_from_slice(&[image.transparent.unwrap_or(0), 0]);
+
+    // Image descriptor with a local color table of 2^bits entries
+    let bits = (usize::BITS - (image.palette.len() - 1).leading_zeros()).max(1);
+    out.push(0x2C);
+    for v in [image.rect.x, image.rect.y, image.rect.width, image.rect.height] {
+        out.extend_from_slice(&(v as u16).to_le_bytes());
+    }
+    out.push(0x80 | (bits - 1) as u8);
+    for i in 0..1usize << bits {
+        let [r, g, b, _] = image.palette.get(i).copied().unwrap_or([0, 0, 0, 0]);
+        out.extend_from_slice(&[r, g, b]);
+    }
+
+    let min_code_size = bits.max(2) as u8;
+    out.push(min_code_size);
+    write_sub_blocks(out, &lzw_compress(&image.indices, &LzwOptions::gif(min_code_size))?);
+    Ok(())
+}
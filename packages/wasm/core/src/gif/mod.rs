@@ -0,0 +1,61 @@
+//! GIF codec implementation in pure Rust
+
+mod decoder;
+mod encoder;
+
+pub use decoder::decode_gif;
+pub use encoder::encode_gif;
+
+#[cfg(feature = "wasm")]
+use crate::animation::FrameSequence;
+#[cfg(feature = "wasm")]
+use crate::quantize::QuantizeOptions;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Decode a GIF to composited frames
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeGif)]
+pub fn decode_gif_js(data: &[u8]) -> Result<FrameSequence, JsError> {
+    decode_gif(data).map_err(|e| JsError::new(&e))
+}
+
+/// Quantize each frame and encode an animated GIF
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeGif)]
+pub fn encode_gif_js(sequence: &FrameSequence, options: &QuantizeOptions) -> Result<Vec<u8>, JsError> {
+    encode_gif(sequence, options).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::FrameSequence;
+    use crate::quantize::QuantizeOptions;
+
+    #[test]
+    fn test_decode_transparent_pixel() {
+        // The classic 1x1 transparent GIF
+        let gif = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\xff\xff\x00\x00\x00!\xf9\x04\x01\x00\x00\x00\x00,\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02D\x01\x00;";
+        let sequence = decode_gif(gif).unwrap();
+        assert_eq!((sequence.width(), sequence.height(), sequence.loop_count), (1, 1, 1));
+        assert_eq!(sequence.frames()[0].data, [0, 0, 0, 0]);
+        assert!(decode_gif(&gif[..20]).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip_with_clearing() {
+        // The second frame turns an opaque pixel transparent, forcing full frames
+        let mut sequence = FrameSequence::new(3, 1);
+        sequence.push([255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255].to_vec(), 70).unwrap();
+        sequence.push([255, 0, 0, 255, 0, 0, 0, 0, 9, 9, 9, 255].to_vec(), 30).unwrap();
+        sequence.loop_count = 0;
+        let gif = encode_gif(&sequence, &QuantizeOptions::new(16)).unwrap();
+        assert_eq!(decode_gif(&gif).unwrap(), sequence);
+
+        // Two colors per frame: the palette shrinks but timing survives
+        let reduced = decode_gif(&encode_gif(&sequence, &QuantizeOptions::new(2)).unwrap()).unwrap();
+        assert_eq!(reduced.frames()[1].data[4..8], [0, 0, 0, 0]);
+        assert_eq!(reduced.total_duration(), 100);
+    }
+}
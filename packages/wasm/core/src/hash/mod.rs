@@ -0,0 +1,87 @@
+//! Cryptographic hashes - MD5, SHA-1 and SHA-256
+//!
+//! Each hash has a one-shot function and an incremental hasher
+//! (`new` / `update` / `digest`) for data that arrives in chunks.
+//! MD5 and SHA-1 are broken for security use; they are provided for
+//! deduplication, ETags and legacy formats.
+
+pub mod md5;
+pub mod sha1;
+pub mod sha256;
+
+pub use md5::{md5, Md5};
+pub use sha1::{sha1, Sha1};
+pub use sha256::{sha256, Sha256};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Buffers input into 64-byte blocks and applies Merkle-Damgård padding
+#[derive(Clone)]
+pub(crate) struct BlockBuffer {
+    block: [u8; 64],
+    len: usize,
+    total: u64,
+}
+
+impl BlockBuffer {
+    pub(crate) fn new() -> Self {
+        BlockBuffer { block: [0; 64], len: 0, total: 0 }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8], mut compress: impl FnMut(&[u8; 64])) {
+        self.total = self.total.wrapping_add(data.len() as u64);
+        if self.len > 0 {
+            let take = data.len().min(64 - self.len);
+            self.block[self.len..self.len + take].copy_from_slice(&data[..take]);
+            self.len += take;
+            data = &data[take..];
+            if self.len < 64 {
+                return;
+            }
+            compress(&self.block);
+            self.len = 0;
+        }
+        let mut blocks = data.chunks_exact(64);
+        for block in &mut blocks {
+            compress(block.try_into().unwrap());
+        }
+        let rest = blocks.remainder();
+        self.block[..rest.len()].copy_from_slice(rest);
+        self.len = rest.len();
+    }
+
+    /// Append `0x80`, zeros and the message length in bits
+    pub(crate) fn finish(&mut self, big_endian: bool, mut compress: impl FnMut(&[u8; 64])) {
+        let bits = self.total.wrapping_mul(8);
+        self.block[self.len] = 0x80;
+        self.block[self.len + 1..].fill(0);
+        if self.len >= 56 {
+            compress(&self.block);
+            self.block.fill(0);
+        }
+        self.block[56..].copy_from_slice(&if big_endian { bits.to_be_bytes() } else { bits.to_le_bytes() });
+        compress(&self.block);
+    }
+}
+
+/// MD5 digest of a byte buffer
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = md5)]
+pub fn md5_js(data: &[u8]) -> Vec<u8> {
+    md5(data).to_vec()
+}
+
+/// SHA-1 digest of a byte buffer
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = sha1)]
+pub fn sha1_js(data: &[u8]) -> Vec<u8> {
+    sha1(data).to_vec()
+}
+
+/// SHA-256 digest of a byte buffer
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = sha256)]
+pub fn sha256_js(data: &[u8]) -> Vec<u8> {
+    sha256(data).to_vec()
+}
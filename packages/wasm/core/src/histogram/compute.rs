@@ -0,0 +1,59 @@
+//! Per-channel and luma histogram computation
+
+use crate::utils::{check_rgba, luma};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// 256-bin histograms for each RGBA channel plus Rec.709 luma
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    pub red: Vec<u32>,
+    pub green: Vec<u32>,
+    pub blue: Vec<u32>,
+    pub alpha: Vec<u32>,
+    pub luma: Vec<u32>,
+}
+
+/// Compute all channel histograms in a single pass
+pub fn histogram(data: &[u8], width: u32, height: u32) -> Result<Histogram, String> {
+    check_rgba(data, width, height)?;
+
+    let mut red = vec![0u32; 256];
+    let mut green = vec![0u32; 256];
+    let mut blue = vec![0u32; 256];
+    let mut alpha = vec![0u32; 256];
+    let mut lum = vec![0u32; 256];
+
+    for p in data.chunks_exact(4) {
+        red[p[0] as usize] += 1;
+        green[p[1] as usize] += 1;
+        blue[p[2] as usize] += 1;
+        alpha[p[3] as usize] += 1;
+        lum[luma(p[0], p[1], p[2]).round() as usize] += 1;
+    }
+
+    Ok(Histogram {
+        red,
+        green,
+        blue,
+        alpha,
+        luma: lum,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_counts() {
+        let data = [255, 0, 0, 255, 255, 255, 255, 128];
+        let h = histogram(&data, 2, 1).unwrap();
+        assert_eq!(h.red[255], 2);
+        assert_eq!(h.green[0], 1);
+        assert_eq!(h.alpha[128], 1);
+        assert_eq!(h.luma[255], 1);
+        assert_eq!(h.luma.iter().sum::<u32>(), 2);
+    }
+}
@@ -0,0 +1,182 @@
+//! Global histogram equalization and CLAHE
+//!
+//! Both operate on BT.709 luma and keep chroma, so colors are not shifted.
+
+use crate::color::space::{rgb_to_ycbcr, ycbcr_to_rgb, YCbCrMatrix};
+use crate::utils::check_rgba;
+
+/// Replace the luma of every pixel using `map(pixel_index, luma) -> new_luma`
+fn remap_luma(data: &[u8], width: u32, map: impl Fn(usize, usize, u8) -> u8) -> Vec<u8> {
+    let w = width as usize;
+    let mut output = data.to_vec();
+
+    for (i, p) in output.chunks_exact_mut(4).enumerate() {
+        let [y, cb, cr] = rgb_to_ycbcr([p[0] as f32, p[1] as f32, p[2] as f32], YCbCrMatrix::Bt709);
+        let ny = map(i % w, i / w, y.round().clamp(0.0, 255.0) as u8) as f32;
+        let rgb = ycbcr_to_rgb([ny, cb, cr], YCbCrMatrix::Bt709);
+
+        for (dst, v) in p.iter_mut().zip(rgb) {
+            *dst = v.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    output
+}
+
+fn luma_of(p: &[u8]) -> u8 {
+    crate::utils::luma(p[0], p[1], p[2]).round().clamp(0.0, 255.0) as u8
+}
+
+/// Build an equalizing lookup table from a histogram
+fn cdf_lut(hist: &[u32; 256]) -> [u8; 256] {
+    let total: u32 = hist.iter().sum();
+    let mut lut = [0u8; 256];
+    if total == 0 {
+        return lut;
+    }
+
+    let cdf_min = hist.iter().copied().find(|&c| c > 0).unwrap_or(0);
+    let denom = (total - cdf_min).max(1) as f32;
+    let mut cdf = 0u32;
+    for (i, &count) in hist.iter().enumerate() {
+        cdf += count;
+        lut[i] = ((cdf.saturating_sub(cdf_min)) as f32 / denom * 255.0).round() as u8;
+    }
+    lut
+}
+
+/// Spread luma over the full range using the global cumulative histogram
+pub fn equalize(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+
+    let mut hist = [0u32; 256];
+    for p in data.chunks_exact(4) {
+        hist[luma_of(p) as usize] += 1;
+    }
+    let lut = cdf_lut(&hist);
+
+    Ok(remap_luma(data, width, |_, _, y| lut[y as usize]))
+}
+
+/// Contrast-limited adaptive histogram equalization
+///
+/// The image is split into a `tiles_x` x `tiles_y` grid; each tile's histogram
+/// is clipped at `clip_limit` times the mean bin count (2-4 is typical; 0
+/// disables clipping), and the resulting mappings are bilinearly interpolated
+/// between tile centers.
+pub fn clahe(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    tiles_x: u32,
+    tiles_y: u32,
+    clip_limit: f32,
+) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if tiles_x == 0 || tiles_y == 0 || tiles_x > width || tiles_y > height {
+        return Err(format!("Invalid tile grid: {}x{}", tiles_x, tiles_y));
+    }
+
+    let w = width as usize;
+    let h = height as usize;
+    let tx = tiles_x as usize;
+    let ty = tiles_y as usize;
+    let lum: Vec<u8> = data.chunks_exact(4).map(luma_of).collect();
+
+    // Tile bounds: tile i covers [i*size/n, (i+1)*size/n)
+    let bound = |i: usize, n: usize, size: usize| i * size / n;
+
+    let mut luts = vec![[0u8; 256]; tx * ty];
+    for j in 0..ty {
+        for i in 0..tx {
+            let (x0, x1) = (bound(i, tx, w), bound(i + 1, tx, w));
+            let (y0, y1) = (bound(j, ty, h), bound(j + 1, ty, h));
+
+            let mut hist = [0u32; 256];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    hist[lum[y * w + x] as usize] += 1;
+                }
+            }
+
+            let area = ((x1 - x0) * (y1 - y0)) as f32;
+            if clip_limit > 0.0 {
+                let limit = ((clip_limit * area / 256.0).ceil() as u32).max(1);
+                let mut excess = 0u32;
+                for count in hist.iter_mut() {
+                    if *count > limit {
+                        excess += *count - limit;
+                        *count = limit;
+                    }
+                }
+                let share = excess / 256;
+                let remainder = (excess % 256) as usize;
+                for (k, count) in hist.iter_mut().enumerate() {
+                    *count += share + u32::from(k < remainder);
+                }
+            }
+
+            luts[j * tx + i] = cdf_lut(&hist);
+        }
+    }
+
+    // Position of a pixel relative to tile centers: (lower tile, upper tile, weight)
+    let locate = |p: usize, n: usize, size: usize| -> (usize, usize, f32) {
+        let tile_size = size as f32 / n as f32;
+        let f = (p as f32 + 0.5) / tile_size - 0.5;
+        if f <= 0.0 {
+            (0, 0, 0.0)
+        } else if f >= (n - 1) as f32 {
+            (n - 1, n - 1, 0.0)
+        } else {
+            let lo = f.floor() as usize;
+            (lo, lo + 1, f - lo as f32)
+        }
+    };
+
+    Ok(remap_luma(data, width, |x, y, v| {
+        let (i0, i1, fx) = locate(x, tx, w);
+        let (j0, j1, fy) = locate(y, ty, h);
+        let v = v as usize;
+
+        let top = luts[j0 * tx + i0][v] as f32 * (1.0 - fx) + luts[j0 * tx + i1][v] as f32 * fx;
+        let bottom = luts[j1 * tx + i0][v] as f32 * (1.0 - fx) + luts[j1 * tx + i1][v] as f32 * fx;
+        (top * (1.0 - fy) + bottom * fy).round() as u8
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn low_contrast(w: u32, h: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        for i in 0..w * h {
+            let v = 100 + (i % 20) as u8;
+            data.extend_from_slice(&[v, v, v, 255]);
+        }
+        data
+    }
+
+    #[test]
+    fn test_equalize_stretches_range() {
+        let data = low_contrast(10, 10);
+        let out = equalize(&data, 10, 10).unwrap();
+        let min = out.chunks_exact(4).map(|p| p[0]).min().unwrap();
+        let max = out.chunks_exact(4).map(|p| p[0]).max().unwrap();
+        assert_eq!(min, 0);
+        assert_eq!(max, 255);
+    }
+
+    #[test]
+    fn test_clahe_preserves_gray_and_alpha() {
+        let data = low_contrast(16, 16);
+        let out = clahe(&data, 16, 16, 2, 2, 2.0).unwrap();
+        for p in out.chunks_exact(4) {
+            assert_eq!(p[0], p[1]);
+            assert_eq!(p[1], p[2]);
+            assert_eq!(p[3], 255);
+        }
+        assert!(clahe(&data, 16, 16, 0, 2, 2.0).is_err());
+    }
+}
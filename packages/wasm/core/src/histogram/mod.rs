@@ -0,0 +1,38 @@
+//! Histogram analysis and contrast enhancement
+
+mod compute;
+mod equalize;
+
+pub use compute::{histogram, Histogram};
+pub use equalize::{clahe, equalize};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Compute 256-bin RGBA and luma histograms
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = histogram)]
+pub fn histogram_js(data: &[u8], width: u32, height: u32) -> Result<Histogram, JsError> {
+    histogram(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Global histogram equalization of luma
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = equalize)]
+pub fn equalize_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    equalize(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Contrast-limited adaptive histogram equalization
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = clahe)]
+pub fn clahe_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    tiles_x: u32,
+    tiles_y: u32,
+    clip_limit: f32,
+) -> Result<Vec<u8>, JsError> {
+    clahe(data, width, height, tiles_x, tiles_y, clip_limit).map_err(|e| JsError::new(&e))
+}
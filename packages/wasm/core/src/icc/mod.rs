@@ -0,0 +1,117 @@
+//! ICC color profile support
+//!
+//! Parses ICC v2/v4 profiles and converts device colors to sRGB through the
+//! profile connection space, so wide-gamut images display correctly.
+
+pub mod parser;
+pub mod transform;
+pub mod writer;
+
+pub use parser::{DeviceModel, IccProfile, Pcs};
+pub use transform::{Clut, Curve, IccTransform, Stage};
+pub use writer::{matrix_trc_profile, BuiltinProfile};
+
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Summary of an ICC profile header and tags
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+pub struct IccInfo {
+    pub description: String,
+    /// Data color space signature, e.g. "RGB", "GRAY", "CMYK"
+    pub color_space: String,
+    /// Profile class signature, e.g. "mntr", "prtr"
+    pub class: String,
+    pub version: String,
+    /// Whether the profile has a supported device-to-PCS transform
+    pub has_transform: bool,
+}
+
+/// Convert an RGBA image described by an ICC profile to sRGB
+///
+/// Gray profiles read the red channel. Alpha is preserved.
+pub fn apply_icc_profile(data: &[u8], width: u32, height: u32, profile: &[u8]) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    let profile = IccProfile::parse(profile)?;
+    IccTransform::new(&profile)?.apply_rgba(data)
+}
+
+/// Convert 4-byte CMYK pixels to sRGB RGBA using a CMYK ICC profile
+pub fn cmyk_to_rgba_icc(data: &[u8], width: u32, height: u32, profile: &[u8], inverted: bool) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    let profile = IccProfile::parse(profile)?;
+    IccTransform::new(&profile)?.apply_cmyk(data, inverted)
+}
+
+/// Parse an ICC profile and describe it
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = parseIccProfile)]
+pub fn parse_icc_profile_js(profile: &[u8]) -> Result<IccInfo, JsError> {
+    let p = IccProfile::parse(profile).map_err(|e| JsError::new(&e))?;
+    Ok(IccInfo {
+        description: p.description.clone().unwrap_or_default(),
+        color_space: p.color_space.trim().to_string(),
+        class: p.class.trim().to_string(),
+        version: format!("{}.{}", p.version_major, p.version_minor),
+        has_transform: p.model.is_some(),
+    })
+}
+
+/// Convert an RGBA image from an ICC profile's color space to sRGB
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = applyIccProfile)]
+pub fn apply_icc_profile_js(data: &[u8], width: u32, height: u32, profile: &[u8]) -> Result<Vec<u8>, JsError> {
+    apply_icc_profile(data, width, height, profile).map_err(|e| JsError::new(&e))
+}
+
+/// Convert CMYK pixels to RGBA through a CMYK ICC profile
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = cmykToRgbaIcc)]
+pub fn cmyk_to_rgba_icc_js(data: &[u8], width: u32, height: u32, profile: &[u8], inverted: bool) -> Result<Vec<u8>, JsError> {
+    cmyk_to_rgba_icc(data, width, height, profile, inverted).map_err(|e| JsError::new(&e))
+}
+
+/// Serialize a built-in RGB profile for embedding in encoded images
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = builtinIccProfile)]
+pub fn builtin_icc_profile_js(profile: BuiltinProfile) -> Vec<u8> {
+    profile.to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_profile_is_identity() {
+        let profile = BuiltinProfile::Srgb.to_bytes();
+        let data = [200u8, 40, 90, 128, 0, 0, 0, 255, 255, 255, 255, 255, 18, 150, 240, 10];
+        let out = apply_icc_profile(&data, 4, 1, &profile).unwrap();
+        for (a, b) in data.iter().zip(&out) {
+            assert!((*a as i32 - *b as i32).abs() <= 2, "{:?} vs {:?}", data, out);
+        }
+    }
+
+    #[test]
+    fn test_display_p3_is_more_saturated() {
+        let profile = BuiltinProfile::DisplayP3.to_bytes();
+        let info = IccProfile::parse(&profile).unwrap();
+        assert!(matches!(info.model, Some(DeviceModel::MatrixTrc { .. })));
+        assert_eq!(info.description.as_deref(), Some("Display P3"));
+
+        // A muted P3 green maps to a more saturated sRGB green
+        let data = [100u8, 180, 100, 255];
+        let out = apply_icc_profile(&data, 1, 1, &profile).unwrap();
+        assert!(out[1] > 180 && out[0] < 100, "{:?}", out);
+        assert_eq!(out[3], 255);
+    }
+
+    #[test]
+    fn test_huge_tag_count() {
+        let mut profile = BuiltinProfile::Srgb.to_bytes();
+        profile[128..132].fill(0xFF);
+        assert_eq!(IccProfile::parse(&profile).err(), Some("ICC tag table is truncated".to_string()));
+        assert!(apply_icc_profile(&[0, 0, 0, 255], 1, 1, &profile).is_err());
+    }
+}
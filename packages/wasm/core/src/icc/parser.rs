@@ -0,0 +1,341 @@
+//! ICC profile parser (v2 and v4)
+//!
+//! Supports matrix/TRC RGB, gray TRC, and LUT-based (`mft1`, `mft2`, `mAB `)
+//! device-to-PCS transforms.
+
+use super::transform::{Clut, Curve, Stage};
+
+/// Profile connection space
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pcs {
+    Xyz,
+    Lab,
+}
+
+/// Device-to-PCS model extracted from a profile
+#[derive(Clone, Debug)]
+pub enum DeviceModel {
+    /// RGB colorants (D50 XYZ columns) plus per-channel tone curves
+    MatrixTrc { matrix: [[f32; 3]; 3], curves: [Curve; 3] },
+    /// Single tone curve scaled onto the D50 white point
+    GrayTrc(Curve),
+    /// Multi-stage pipeline from an A2B0 tag; `legacy_lab` marks v2 16-bit Lab encoding
+    Lut { stages: Vec<Stage>, legacy_lab: bool },
+}
+
+/// Parsed ICC profile
+#[derive(Clone, Debug)]
+pub struct IccProfile {
+    pub version_major: u8,
+    pub version_minor: u8,
+    /// Device class signature, e.g. "mntr" or "prtr"
+    pub class: String,
+    /// Data color space signature, e.g. "RGB " or "CMYK"
+    pub color_space: String,
+    pub pcs: Pcs,
+    pub description: Option<String>,
+    pub white_point: Option<[f32; 3]>,
+    pub model: Option<DeviceModel>,
+}
+
+#[inline]
+fn be_u16(d: &[u8], o: usize) -> Result<u16, String> {
+    d.get(o..o + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| "ICC profile truncated".to_string())
+}
+
+#[inline]
+fn be_u32(d: &[u8], o: usize) -> Result<u32, String> {
+    d.get(o..o + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "ICC profile truncated".to_string())
+}
+
+#[inline]
+fn s15f16(d: &[u8], o: usize) -> Result<f32, String> {
+    Ok(be_u32(d, o)? as i32 as f32 / 65536.0)
+}
+
+fn sig(d: &[u8], o: usize) -> Result<String, String> {
+    d.get(o..o + 4)
+        .map(|b| b.iter().map(|&c| c as char).collect())
+        .ok_or_else(|| "ICC profile truncated".to_string())
+}
+
+fn slice(d: &[u8], offset: usize, size: usize) -> Result<&[u8], String> {
+    d.get(offset..offset.saturating_add(size))
+        .ok_or_else(|| "ICC tag out of bounds".to_string())
+}
+
+/// Parse a `curv` or `para` element, returning it and its byte length (unpadded)
+fn parse_curve(d: &[u8]) -> Result<(Curve, usize), String> {
+    match &sig(d, 0)?[..] {
+        "curv" => {
+            let count = be_u32(d, 8)? as usize;
+            let curve = match count {
+                0 => Curve::Identity,
+                1 => Curve::Gamma(be_u16(d, 12)? as f32 / 256.0),
+                _ => {
+                    let mut table = Vec::with_capacity(count);
+                    for i in 0..count {
+                        table.push(be_u16(d, 12 + i * 2)? as f32 / 65535.0);
+                    }
+                    Curve::Table(table)
+                }
+            };
+            Ok((curve, 12 + count * 2))
+        }
+        "para" => {
+            let kind = be_u16(d, 8)?;
+            let count = match kind {
+                0 => 1,
+                1 => 3,
+                2 => 4,
+                3 => 5,
+                4 => 7,
+                _ => return Err(format!("Unsupported parametric curve type: {}", kind)),
+            };
+            let mut params = [0f32; 7];
+            for (i, p) in params.iter_mut().enumerate().take(count) {
+                *p = s15f16(d, 12 + i * 4)?;
+            }
+            Ok((Curve::Parametric { kind, params }, 12 + count * 4))
+        }
+        other => Err(format!("Unsupported curve type: {}", other)),
+    }
+}
+
+/// Parse `n` consecutive 4-byte aligned curves
+fn parse_curve_set(d: &[u8], n: usize) -> Result<Vec<Curve>, String> {
+    let mut curves = Vec::with_capacity(n);
+    let mut offset = 0;
+    for _ in 0..n {
+        let (curve, len) = parse_curve(d.get(offset..).ok_or("ICC curve out of bounds")?)?;
+        curves.push(curve);
+        offset += len.div_ceil(4) * 4;
+    }
+    Ok(curves)
+}
+
+/// Parse an XYZ tag's first value
+fn parse_xyz(d: &[u8]) -> Result<[f32; 3], String> {
+    Ok([s15f16(d, 8)?, s15f16(d, 12)?, s15f16(d, 16)?])
+}
+
+/// Parse `desc` (v2) or `mluc` (v4) text
+fn parse_text(d: &[u8]) -> Result<String, String> {
+    match &sig(d, 0)?[..] {
+        "desc" => {
+            let len = be_u32(d, 8)? as usize;
+            let bytes = slice(d, 12, len)?;
+            Ok(bytes.iter().take_while(|&&c| c != 0).map(|&c| c as char).collect())
+        }
+        "mluc" => {
+            let count = be_u32(d, 8)?;
+            if count == 0 {
+                return Ok(String::new());
+            }
+            let len = be_u32(d, 20)? as usize;
+            let offset = be_u32(d, 24)? as usize;
+            let units: Vec<u16> = slice(d, offset, len)?
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            Ok(String::from_utf16_lossy(&units))
+        }
+        "text" => Ok(d[8..].iter().take_while(|&&c| c != 0).map(|&c| c as char).collect()),
+        other => Err(format!("Unsupported text type: {}", other)),
+    }
+}
+
+fn parse_clut(d: &[u8], inputs: usize, outputs: usize, grid: &[usize], precision: usize) -> Result<Clut, String> {
+    let points: usize = grid.iter().product();
+    let count = points * outputs;
+    let raw = slice(d, 0, count * precision)?;
+    let values = match precision {
+        1 => raw.iter().map(|&v| v as f32 / 255.0).collect(),
+        _ => raw
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]) as f32 / 65535.0)
+            .collect(),
+    };
+    Ok(Clut {
+        grid: grid[..inputs].to_vec(),
+        outputs,
+        values,
+    })
+}
+
+/// Parse legacy `mft1`/`mft2` LUTs
+fn parse_mft(d: &[u8], wide: bool) -> Result<Vec<Stage>, String> {
+    if d.len() < 52 {
+        return Err("ICC LUT too small".to_string());
+    }
+    let inputs = d[8] as usize;
+    let outputs = d[9] as usize;
+    let grid = d[10] as usize;
+    if inputs == 0 || outputs == 0 || inputs > 8 || grid < 2 {
+        return Err("Invalid ICC LUT dimensions".to_string());
+    }
+
+    let (in_entries, out_entries, mut offset, precision) = if wide {
+        (be_u16(d, 48)? as usize, be_u16(d, 50)? as usize, 52, 2)
+    } else {
+        (256, 256, 48, 1)
+    };
+
+    let read_tables = |offset: &mut usize, n: usize, entries: usize| -> Result<Vec<Curve>, String> {
+        let mut curves = Vec::with_capacity(n);
+        for _ in 0..n {
+            let raw = slice(d, *offset, entries * precision)?;
+            *offset += entries * precision;
+            let table = if wide {
+                raw.chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]) as f32 / 65535.0)
+                    .collect()
+            } else {
+                raw.iter().map(|&v| v as f32 / 255.0).collect()
+            };
+            curves.push(Curve::Table(table));
+        }
+        Ok(curves)
+    };
+
+    let input_curves = read_tables(&mut offset, inputs, in_entries)?;
+    let clut = parse_clut(d.get(offset..).unwrap_or(&[]), inputs, outputs, &vec![grid; inputs], precision)?;
+    offset += clut.values.len() * precision;
+    let output_curves = read_tables(&mut offset, outputs, out_entries)?;
+
+    Ok(vec![
+        Stage::Curves(input_curves),
+        Stage::Clut(clut),
+        Stage::Curves(output_curves),
+    ])
+}
+
+/// Parse a v4 `mAB ` (lutAToBType) pipeline
+fn parse_mab(d: &[u8]) -> Result<Vec<Stage>, String> {
+    let inputs = d[8] as usize;
+    let outputs = d[9] as usize;
+    let b_offset = be_u32(d, 12)? as usize;
+    let matrix_offset = be_u32(d, 16)? as usize;
+    let m_offset = be_u32(d, 20)? as usize;
+    let clut_offset = be_u32(d, 24)? as usize;
+    let a_offset = be_u32(d, 28)? as usize;
+
+    let mut stages = Vec::new();
+    if a_offset != 0 {
+        stages.push(Stage::Curves(parse_curve_set(&d[a_offset..], inputs)?));
+    }
+    if clut_offset != 0 {
+        let c = &d[clut_offset..];
+        let grid: Vec<usize> = c.get(..16).ok_or("ICC CLUT truncated")?.iter().map(|&g| g as usize).collect();
+        let precision = *c.get(16).ok_or("ICC CLUT truncated")? as usize;
+        stages.push(Stage::Clut(parse_clut(&c[20..], inputs, outputs, &grid, precision)?));
+    }
+    if m_offset != 0 {
+        stages.push(Stage::Curves(parse_curve_set(&d[m_offset..], outputs)?));
+    }
+    if matrix_offset != 0 {
+        let mut m = [0f32; 12];
+        for (i, v) in m.iter_mut().enumerate() {
+            *v = s15f16(d, matrix_offset + i * 4)?;
+        }
+        stages.push(Stage::Matrix(m));
+    }
+    if b_offset != 0 {
+        stages.push(Stage::Curves(parse_curve_set(&d[b_offset..], outputs)?));
+    }
+    Ok(stages)
+}
+
+impl IccProfile {
+    /// Parse an ICC profile from raw bytes
+    pub fn parse(data: &[u8]) -> Result<IccProfile, String> {
+        if data.len() < 132 {
+            return Err("ICC profile too small".to_string());
+        }
+        if &data[36..40] != b"acsp" {
+            return Err("Invalid ICC profile signature".to_string());
+        }
+
+        let pcs = match &sig(data, 20)?[..] {
+            "XYZ " => Pcs::Xyz,
+            "Lab " => Pcs::Lab,
+            other => return Err(format!("Unsupported PCS: {}", other)),
+        };
+
+        let tag_count = be_u32(data, 128)? as usize;
+        if tag_count > (data.len() - 132) / 12 {
+            return Err("ICC tag table is truncated".to_string());
+        }
+        let mut tags = Vec::with_capacity(tag_count);
+        for i in 0..tag_count {
+            let at = 132 + i * 12;
+            let name = sig(data, at)?;
+            let offset = be_u32(data, at + 4)? as usize;
+            let size = be_u32(data, at + 8)? as usize;
+            tags.push((name, offset, size));
+        }
+        let tag = |name: &str| -> Option<&[u8]> {
+            tags.iter()
+                .find(|(n, _, _)| n == name)
+                .and_then(|&(_, offset, size)| slice(data, offset, size).ok())
+        };
+
+        let color_space = sig(data, 16)?;
+        let description = tag("desc").and_then(|d| parse_text(d).ok());
+        let white_point = tag("wtpt").and_then(|d| parse_xyz(d).ok());
+
+        // Prefer the A2B0 LUT, then fall back to matrix/TRC or gray TRC
+        let lut = tag("A2B0").map(|d| -> Result<DeviceModel, String> {
+            match &sig(d, 0)?[..] {
+                "mft1" => Ok(DeviceModel::Lut { stages: parse_mft(d, false)?, legacy_lab: false }),
+                "mft2" => Ok(DeviceModel::Lut { stages: parse_mft(d, true)?, legacy_lab: true }),
+                "mAB " => Ok(DeviceModel::Lut { stages: parse_mab(d)?, legacy_lab: false }),
+                other => Err(format!("Unsupported LUT type: {}", other)),
+            }
+        });
+
+        let matrix_trc = || -> Option<DeviceModel> {
+            let r = parse_xyz(tag("rXYZ")?).ok()?;
+            let g = parse_xyz(tag("gXYZ")?).ok()?;
+            let b = parse_xyz(tag("bXYZ")?).ok()?;
+            let curves = [
+                parse_curve(tag("rTRC")?).ok()?.0,
+                parse_curve(tag("gTRC")?).ok()?.0,
+                parse_curve(tag("bTRC")?).ok()?.0,
+            ];
+            let matrix = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+            Some(DeviceModel::MatrixTrc { matrix, curves })
+        };
+
+        let model = match lut {
+            Some(Ok(model)) => Some(model),
+            _ if color_space == "RGB " => matrix_trc(),
+            _ if color_space == "GRAY" => tag("kTRC").and_then(|d| parse_curve(d).ok()).map(|(c, _)| DeviceModel::GrayTrc(c)),
+            _ => None,
+        };
+
+        Ok(IccProfile {
+            version_major: data[8],
+            version_minor: data[9] >> 4,
+            class: sig(data, 12)?,
+            color_space,
+            pcs,
+            description,
+            white_point,
+            model,
+        })
+    }
+
+    /// Number of device channels for the profile's data color space
+    pub fn channels(&self) -> usize {
+        match self.color_space.as_str() {
+            "GRAY" => 1,
+            "CMYK" => 4,
+            _ => 3,
+        }
+    }
+}
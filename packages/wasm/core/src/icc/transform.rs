@@ -0,0 +1,277 @@
+//! ICC device-to-sRGB transforms
+
+use super::parser::{DeviceModel, IccProfile, Pcs};
+use crate::color::space::{lab_to_xyz_with_white, linear_to_srgb, xyz_to_linear_rgb};
+use std::collections::HashMap;
+
+/// D50 reference white (ICC profile connection space illuminant)
+pub const D50_WHITE: [f32; 3] = [0.9642, 1.0, 0.8249];
+
+/// Bradford chromatic adaptation from D50 to D65
+const BRADFORD_D50_TO_D65: [[f32; 3]; 3] = [
+    [0.9555766, -0.0230393, 0.0631636],
+    [-0.0282895, 1.0099416, 0.0210077],
+    [0.0122982, -0.020483, 1.3299098],
+];
+
+/// One-dimensional tone curve
+#[derive(Clone, Debug)]
+pub enum Curve {
+    Identity,
+    Gamma(f32),
+    /// Samples evenly spaced over [0, 1]
+    Table(Vec<f32>),
+    /// ICC parametric function types 0-4 with parameters g, a, b, c, d, e, f
+    Parametric { kind: u16, params: [f32; 7] },
+}
+
+impl Curve {
+    pub fn eval(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Curve::Identity => x,
+            Curve::Gamma(g) => x.powf(*g),
+            Curve::Table(t) => {
+                if t.is_empty() {
+                    return x;
+                }
+                let pos = x * (t.len() - 1) as f32;
+                let i = (pos as usize).min(t.len() - 1);
+                let j = (i + 1).min(t.len() - 1);
+                let f = pos - i as f32;
+                t[i] + (t[j] - t[i]) * f
+            }
+            Curve::Parametric { kind, params } => {
+                let [g, a, b, c, d, e, f] = *params;
+                let y = match kind {
+                    0 => x.powf(g),
+                    1 => {
+                        if x >= -b / a {
+                            (a * x + b).max(0.0).powf(g)
+                        } else {
+                            0.0
+                        }
+                    }
+                    2 => {
+                        if x >= -b / a {
+                            (a * x + b).max(0.0).powf(g) + c
+                        } else {
+                            c
+                        }
+                    }
+                    3 => {
+                        if x >= d {
+                            (a * x + b).max(0.0).powf(g)
+                        } else {
+                            c * x
+                        }
+                    }
+                    _ => {
+                        if x >= d {
+                            (a * x + b).max(0.0).powf(g) + e
+                        } else {
+                            c * x + f
+                        }
+                    }
+                };
+                y.clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+/// Multidimensional color lookup table
+#[derive(Clone, Debug)]
+pub struct Clut {
+    /// Grid points per input dimension (first dimension varies slowest)
+    pub grid: Vec<usize>,
+    pub outputs: usize,
+    /// Normalized output samples
+    pub values: Vec<f32>,
+}
+
+impl Clut {
+    /// Multilinear interpolation of the table
+    pub fn eval(&self, input: &[f32], output: &mut [f32]) {
+        let n = self.grid.len();
+        let mut base = [0usize; 8];
+        let mut frac = [0f32; 8];
+        let mut strides = [0usize; 8];
+
+        let mut stride = self.outputs;
+        for d in (0..n).rev() {
+            strides[d] = stride;
+            stride *= self.grid[d];
+        }
+        for d in 0..n {
+            let pos = input[d].clamp(0.0, 1.0) * (self.grid[d] - 1) as f32;
+            let i = (pos as usize).min(self.grid[d].saturating_sub(2));
+            base[d] = i;
+            frac[d] = pos - i as f32;
+        }
+
+        output[..self.outputs].iter_mut().for_each(|o| *o = 0.0);
+        for corner in 0..1usize << n {
+            let mut weight = 1.0;
+            let mut index = 0;
+            for d in 0..n {
+                let upper = (corner >> d) & 1 == 1 && self.grid[d] > 1;
+                weight *= if upper { frac[d] } else { 1.0 - frac[d] };
+                index += (base[d] + upper as usize) * strides[d];
+            }
+            if weight == 0.0 {
+                continue;
+            }
+            for (o, out) in output[..self.outputs].iter_mut().enumerate() {
+                *out += weight * self.values.get(index + o).copied().unwrap_or(0.0);
+            }
+        }
+    }
+}
+
+/// A processing element of a LUT-based transform
+#[derive(Clone, Debug)]
+pub enum Stage {
+    Curves(Vec<Curve>),
+    Clut(Clut),
+    /// 3x3 matrix (row-major) followed by 3 offsets
+    Matrix([f32; 12]),
+}
+
+fn mul3(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+/// Convert D50 PCS XYZ to gamma-encoded sRGB in [0, 1]
+pub fn pcs_xyz_to_srgb(xyz: [f32; 3]) -> [f32; 3] {
+    let d65 = mul3(&BRADFORD_D50_TO_D65, xyz);
+    xyz_to_linear_rgb(d65).map(|v| linear_to_srgb(v.clamp(0.0, 1.0)))
+}
+
+/// Convert sRGB-encoded float to a byte
+#[inline]
+fn to_byte(v: f32) -> u8 {
+    (v * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+/// Prepared transform from a profile's device space to sRGB
+pub struct IccTransform {
+    profile_pcs: Pcs,
+    model: DeviceModel,
+    channels: usize,
+}
+
+impl IccTransform {
+    pub fn new(profile: &IccProfile) -> Result<IccTransform, String> {
+        let model = profile
+            .model
+            .clone()
+            .ok_or_else(|| format!("No supported device transform in {} profile", profile.color_space.trim()))?;
+        Ok(IccTransform {
+            profile_pcs: profile.pcs,
+            model,
+            channels: profile.channels(),
+        })
+    }
+
+    /// Device channel count expected by `to_srgb`
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Map normalized device values to gamma-encoded sRGB in [0, 1]
+    pub fn to_srgb(&self, input: &[f32]) -> [f32; 3] {
+        match &self.model {
+            DeviceModel::MatrixTrc { matrix, curves } => {
+                let linear = [curves[0].eval(input[0]), curves[1].eval(input[1]), curves[2].eval(input[2])];
+                pcs_xyz_to_srgb(mul3(matrix, linear))
+            }
+            DeviceModel::GrayTrc(curve) => {
+                let y = curve.eval(input[0]);
+                pcs_xyz_to_srgb(D50_WHITE.map(|w| w * y))
+            }
+            DeviceModel::Lut { stages, legacy_lab } => {
+                let mut buf = [0f32; 16];
+                let mut next = [0f32; 16];
+                buf[..input.len()].copy_from_slice(input);
+
+                for stage in stages {
+                    match stage {
+                        Stage::Curves(curves) => {
+                            for (v, c) in buf.iter_mut().zip(curves) {
+                                *v = c.eval(*v);
+                            }
+                        }
+                        Stage::Clut(clut) => {
+                            clut.eval(&buf[..clut.grid.len()], &mut next);
+                            buf = next;
+                        }
+                        Stage::Matrix(m) => {
+                            let v = [buf[0], buf[1], buf[2]];
+                            for r in 0..3 {
+                                buf[r] = (m[r * 3] * v[0] + m[r * 3 + 1] * v[1] + m[r * 3 + 2] * v[2] + m[9 + r]).clamp(0.0, 1.0);
+                            }
+                        }
+                    }
+                }
+
+                let xyz = match self.profile_pcs {
+                    Pcs::Xyz => [buf[0], buf[1], buf[2]].map(|v| v * 65535.0 / 32768.0),
+                    Pcs::Lab => {
+                        let scale = if *legacy_lab { 65535.0 / 65280.0 } else { 1.0 };
+                        let lab = [
+                            buf[0] * scale * 100.0,
+                            buf[1] * scale * 255.0 - 128.0,
+                            buf[2] * scale * 255.0 - 128.0,
+                        ];
+                        lab_to_xyz_with_white(lab, D50_WHITE)
+                    }
+                };
+                pcs_xyz_to_srgb(xyz)
+            }
+        }
+    }
+
+    /// Convert an interleaved 8-bit device buffer with `stride` bytes per pixel to sRGB bytes
+    fn convert_bytes(&self, data: &[u8], stride: usize, invert: bool, mut emit: impl FnMut(usize, [u8; 3])) {
+        let n = self.channels;
+        let mut cache: HashMap<u32, [u8; 3]> = HashMap::new();
+        let mut input = [0f32; 4];
+
+        for (i, p) in data.chunks_exact(stride).enumerate() {
+            let key = p[..n].iter().fold(0u32, |k, &v| (k << 8) | v as u32);
+            let rgb = *cache.entry(key).or_insert_with(|| {
+                for (dst, &v) in input.iter_mut().zip(&p[..n]) {
+                    let v = if invert { 255 - v } else { v };
+                    *dst = v as f32 / 255.0;
+                }
+                self.to_srgb(&input[..n]).map(to_byte)
+            });
+            emit(i, rgb);
+        }
+    }
+
+    /// Convert an RGBA (or gray-in-RGBA) buffer to sRGB, preserving alpha
+    pub fn apply_rgba(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if self.channels == 4 {
+            return Err("CMYK profiles require CMYK input".to_string());
+        }
+        let mut output = data.to_vec();
+        self.convert_bytes(data, 4, false, |i, rgb| output[i * 4..i * 4 + 3].copy_from_slice(&rgb));
+        Ok(output)
+    }
+
+    /// Convert a 4-byte-per-pixel CMYK buffer to opaque sRGB RGBA
+    pub fn apply_cmyk(&self, data: &[u8], inverted: bool) -> Result<Vec<u8>, String> {
+        if self.channels != 4 {
+            return Err("Profile is not a CMYK profile".to_string());
+        }
+        let mut output = vec![255u8; data.len()];
+        self.convert_bytes(data, 4, inverted, |i, rgb| output[i * 4..i * 4 + 3].copy_from_slice(&rgb));
+        Ok(output)
+    }
+}
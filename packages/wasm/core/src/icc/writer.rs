@@ -0,0 +1,138 @@
+//! ICC profile serialization for embedding on encode
+
+use super::transform::{Curve, D50_WHITE};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Built-in RGB profiles that can be embedded without supplying bytes
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuiltinProfile {
+    Srgb = 0,
+    DisplayP3 = 1,
+    AdobeRgb = 2,
+}
+
+/// sRGB transfer function as ICC parametric type 3 parameters
+const SRGB_TRC: [f32; 7] = [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045, 0.0, 0.0];
+
+impl BuiltinProfile {
+    fn description(self) -> &'static str {
+        match self {
+            BuiltinProfile::Srgb => "sRGB",
+            BuiltinProfile::DisplayP3 => "Display P3",
+            BuiltinProfile::AdobeRgb => "Adobe RGB (1998)",
+        }
+    }
+
+    /// Chromatically adapted (D50) XYZ of the red, green, and blue primaries
+    fn primaries(self) -> [[f32; 3]; 3] {
+        match self {
+            BuiltinProfile::Srgb => [[0.43607, 0.22250, 0.01393], [0.38506, 0.71688, 0.09710], [0.14308, 0.06062, 0.71417]],
+            BuiltinProfile::DisplayP3 => [[0.51512, 0.24120, -0.00105], [0.29198, 0.69225, 0.04189], [0.15710, 0.06657, 0.78407]],
+            BuiltinProfile::AdobeRgb => [[0.60974, 0.31111, 0.01947], [0.20528, 0.62567, 0.06087], [0.14919, 0.06322, 0.74457]],
+        }
+    }
+
+    fn curve(self) -> Curve {
+        match self {
+            BuiltinProfile::AdobeRgb => Curve::Gamma(563.0 / 256.0),
+            _ => Curve::Parametric { kind: 3, params: SRGB_TRC },
+        }
+    }
+
+    /// Serialize the profile as ICC v4 bytes
+    pub fn to_bytes(self) -> Vec<u8> {
+        matrix_trc_profile(self.description(), self.primaries(), &self.curve())
+    }
+}
+
+fn s15f16(v: f32) -> [u8; 4] {
+    ((v * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn xyz_tag(v: [f32; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    v.iter().for_each(|&c| tag.extend_from_slice(&s15f16(c)));
+    tag
+}
+
+fn mluc_tag(text: &str) -> Vec<u8> {
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let mut tag = b"mluc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&1u32.to_be_bytes()); // Record count
+    tag.extend_from_slice(&12u32.to_be_bytes()); // Record size
+    tag.extend_from_slice(b"enUS");
+    tag.extend_from_slice(&(units.len() as u32 * 2).to_be_bytes());
+    tag.extend_from_slice(&28u32.to_be_bytes());
+    units.iter().for_each(|u| tag.extend_from_slice(&u.to_be_bytes()));
+    tag
+}
+
+fn curve_tag(curve: &Curve) -> Vec<u8> {
+    match curve {
+        Curve::Identity => b"curv\0\0\0\0\0\0\0\0".to_vec(),
+        Curve::Gamma(g) => {
+            let mut tag = b"curv\0\0\0\0\0\0\0\x01".to_vec();
+            tag.extend_from_slice(&((g * 256.0).round() as u16).to_be_bytes());
+            tag
+        }
+        Curve::Table(table) => {
+            let mut tag = b"curv\0\0\0\0".to_vec();
+            tag.extend_from_slice(&(table.len() as u32).to_be_bytes());
+            table.iter().for_each(|v| tag.extend_from_slice(&((v.clamp(0.0, 1.0) * 65535.0).round() as u16).to_be_bytes()));
+            tag
+        }
+        Curve::Parametric { kind, params } => {
+            let count = [1, 3, 4, 5, 7][(*kind as usize).min(4)];
+            let mut tag = b"para\0\0\0\0".to_vec();
+            tag.extend_from_slice(&kind.to_be_bytes());
+            tag.extend_from_slice(&[0, 0]);
+            params[..count].iter().for_each(|&p| tag.extend_from_slice(&s15f16(p)));
+            tag
+        }
+    }
+}
+
+/// Build an ICC v4 display profile from D50 primaries and a shared tone curve
+pub fn matrix_trc_profile(description: &str, primaries: [[f32; 3]; 3], curve: &Curve) -> Vec<u8> {
+    let trc = curve_tag(curve);
+    let tags: [(&[u8; 4], Vec<u8>); 8] = [
+        (b"desc", mluc_tag(description)),
+        (b"wtpt", xyz_tag(D50_WHITE)),
+        (b"rXYZ", xyz_tag(primaries[0])),
+        (b"gXYZ", xyz_tag(primaries[1])),
+        (b"bXYZ", xyz_tag(primaries[2])),
+        (b"rTRC", trc.clone()),
+        (b"gTRC", trc.clone()),
+        (b"bTRC", trc),
+    ];
+
+    let mut profile = vec![0u8; 128];
+    profile[8..12].copy_from_slice(&0x0430_0000u32.to_be_bytes()); // Version 4.3
+    profile[12..16].copy_from_slice(b"mntr");
+    profile[16..20].copy_from_slice(b"RGB ");
+    profile[20..24].copy_from_slice(b"XYZ ");
+    profile[36..40].copy_from_slice(b"acsp");
+    for (i, &c) in D50_WHITE.iter().enumerate() {
+        profile[68 + i * 4..72 + i * 4].copy_from_slice(&s15f16(c)); // PCS illuminant
+    }
+
+    profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    let mut offset = 132 + tags.len() * 12;
+    let mut body = Vec::new();
+    for (name, data) in &tags {
+        profile.extend_from_slice(*name);
+        profile.extend_from_slice(&(offset as u32).to_be_bytes());
+        profile.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        body.extend_from_slice(data);
+        // Tag data is 4-byte aligned
+        body.resize(body.len().next_multiple_of(4), 0);
+        offset = 132 + tags.len() * 12 + body.len();
+    }
+    profile.extend(body);
+
+    let len = profile.len() as u32;
+    profile[0..4].copy_from_slice(&len.to_be_bytes());
+    profile
+}
@@ -0,0 +1,723 @@
+//! JPEG decoder - pure Rust implementation
+//!
+//! Decodes 8-bit baseline, extended and progressive Huffman-coded JPEGs
+//! with one (gray), three (YCbCr or RGB) or four (CMYK or YCCK)
+//! components. Frames without Huffman tables, as written by many MJPEG
+//! cameras, fall back to the standard tables. Subsampled chroma is
+//! upsampled bilinearly.
+
+use crate::color::space::{ycbcr_to_rgb, YCbCrMatrix};
+use crate::icc::apply_icc_profile;
+use crate::limits::Budget;
+use crate::pool::{self, Scratch};
+use std::f32::consts::PI;
+
+/// Natural (row-major) position of each zigzag index
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// Standard Huffman tables from ITU-T T.81 Annex K.3: (code counts per
+/// length, symbols)
+const DC_LUMA: ([u8; 16], &[u8]) = ([0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0], &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+const DC_CHROMA: ([u8; 16], &[u8]) = ([0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0], &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+const AC_LUMA: ([u8; 16], &[u8]) = (
+    [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7D],
+    &[
+        0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07, 0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xA1, 0x08, 0x23, 0x42, 0xB1, 0xC1, 0x15, 0x52, 0xD1, 0xF0, 0x24, 0x33, 0x62, 0x72,
+        0x82, 0x09, 0x0A, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59,
+        0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3,
+        0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE1, 0xE2,
+        0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA,
+    ],
+);
+const AC_CHROMA: ([u8; 16], &[u8]) = (
+    [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77],
+    &[
+        0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71, 0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xA1, 0xB1, 0xC1, 0x09, 0x23, 0x33, 0x52, 0xF0, 0x15, 0x62, 0x72, 0xD1,
+        0x0A, 0x16, 0x24, 0x34, 0xE1, 0x25, 0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58,
+        0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A,
+        0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA,
+        0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA,
+    ],
+);
+
+/// A canonical Huffman table with a lookup for codes of up to 8 bits
+#[derive(Clone)]
+struct Huffman {
+    /// `length << 8 | symbol` indexed by the next 8 bits; 0 for longer codes
+    fast: [u16; 256],
+    /// Largest code of each length, -1 when there is none
+    max_code: [i32; 17],
+    /// Index in `symbols` of the first code of each length, minus that code
+    offset: [i32; 17],
+    symbols: Vec<u8>,
+}
+
+impl Huffman {
+    fn new(counts: &[u8; 16], symbols: &[u8]) -> Result<Huffman, String> {
+        if counts.iter().map(|&c| c as usize).sum::<usize>() != symbols.len() {
+            return Err("JPEG Huffman table is malformed".to_string());
+        }
+        let mut table = Huffman { fast: [0; 256], max_code: [-1; 17], offset: [0; 17], symbols: symbols.to_vec() };
+        let mut code = 0i32;
+        let mut index = 0i32;
+        for len in 1..=16 {
+            let count = counts[len - 1] as i32;
+            table.offset[len] = index - code;
+            for _ in 0..count {
+                if code >= 1 << len {
+                    return Err("JPEG Huffman table is malformed".to_string());
+                }
+                if len <= 8 {
+                    let shift = 8 - len;
+                    let entry = (len as u16) << 8 | symbols[index as usize] as u16;
+                    let first = (code << shift) as usize;
+                    table.fast[first..first + (1 << shift)].fill(entry);
+                }
+                code += 1;
+                index += 1;
+            }
+            if count > 0 {
+                table.max_code[len] = code - 1;
+            }
+            code <<= 1;
+        }
+        Ok(table)
+    }
+
+    fn decode(&self, bits: &mut EntropyReader) -> Result<u8, String> {
+        let peek = bits.peek(16);
+        let entry = self.fast[(peek >> 8) as usize];
+        if entry != 0 {
+            bits.consume((entry >> 8) as u32);
+            return Ok(entry as u8);
+        }
+        for len in 9..=16 {
+            let code = (peek >> (16 - len)) as i32;
+            if code <= self.max_code[len] {
+                bits.consume(len as u32);
+                return self.symbols.get((self.offset[len] + code) as usize).copied().ok_or_else(|| CORRUPT.to_string());
+            }
+        }
+        Err(CORRUPT.to_string())
+    }
+}
+
+const CORRUPT: &str = "Corrupt JPEG entropy-coded data";
+
+/// Bits of entropy-coded data, with stuffed zero bytes removed; reads past
+/// a marker or the end of data yield zeros
+struct EntropyReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    acc: u64,
+    count: u32,
+    at_marker: bool,
+}
+
+impl<'a> EntropyReader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> EntropyReader<'a> {
+        EntropyReader { data, pos, acc: 0, count: 0, at_marker: false }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 56 {
+            let mut byte = 0;
+            if !self.at_marker {
+                match self.data.get(self.pos) {
+                    Some(0xFF) if self.data.get(self.pos + 1) == Some(&0) => {
+                        byte = 0xFF;
+                        self.pos += 2;
+                    }
+                    Some(0xFF) | None => self.at_marker = true,
+                    Some(&b) => {
+                        byte = b;
+                        self.pos += 1;
+                    }
+                }
+            }
+            self.acc |= (byte as u64) << (56 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn peek(&mut self, n: u32) -> u32 {
+        if self.count < n {
+            self.fill();
+        }
+        (self.acc >> (64 - n)) as u32
+    }
+
+    fn consume(&mut self, n: u32) {
+        self.acc <<= n;
+        self.count -= n;
+    }
+
+    fn bits(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        let value = self.peek(n);
+        self.consume(n);
+        value
+    }
+
+    /// A `size`-bit magnitude category value, sign-extended
+    fn extend(&mut self, size: u8) -> Result<i32, String> {
+        if size == 0 {
+            return Ok(0);
+        }
+        if size > 16 {
+            return Err(CORRUPT.to_string());
+        }
+        let value = self.bits(size as u32) as i32;
+        Ok(if value < 1 << (size - 1) { value - (1 << size) + 1 } else { value })
+    }
+
+    /// Drop buffered bits and step over an RSTn marker
+    fn restart(&mut self) {
+        self.acc = 0;
+        self.count = 0;
+        self.at_marker = false;
+        while self.data.get(self.pos) == Some(&0xFF) && self.data.get(self.pos + 1) == Some(&0xFF) {
+            self.pos += 1;
+        }
+        if self.data.get(self.pos) == Some(&0xFF) && matches!(self.data.get(self.pos + 1), Some(0xD0..=0xD7)) {
+            self.pos += 2;
+        }
+    }
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant: usize,
+    /// Size in blocks of the coefficient grid, padded to whole MCUs
+    blocks_w: usize,
+    blocks_h: usize,
+    /// 64 coefficients per block in natural order, quantized
+    coefficients: Scratch<i16>,
+    dc_table: usize,
+    ac_table: usize,
+    dc_pred: i32,
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    progressive: bool,
+    h_max: usize,
+    v_max: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+    components: Vec<Component>,
+}
+
+fn parse_frame(segment: &[u8], progressive: bool, budget: &mut Budget) -> Result<Frame, String> {
+    if segment.len() < 6 {
+        return Err("JPEG frame header is truncated".to_string());
+    }
+    if segment[0] != 8 {
+        return Err(format!("Unsupported JPEG sample precision: {} bits", segment[0]));
+    }
+    let height = u16::from_be_bytes([segment[1], segment[2]]) as usize;
+    let width = u16::from_be_bytes([segment[3], segment[4]]) as usize;
+    let count = segment[5] as usize;
+    if width == 0 || height == 0 {
+        return Err("JPEG image has no size".to_string());
+    }
+    budget.check_size(width, height)?;
+    if !matches!(count, 1 | 3 | 4) {
+        return Err(format!("Unsupported JPEG component count: {}", count));
+    }
+    let specs = segment.get(6..6 + count * 3).ok_or("JPEG frame header is truncated")?;
+    let mut components = Vec::with_capacity(count);
+    for spec in specs.chunks_exact(3) {
+        let (h, v) = ((spec[1] >> 4) as usize, (spec[1] & 15) as usize);
+        if !(1..=4).contains(&h) || !(1..=4).contains(&v) || spec[2] > 3 {
+            return Err("Invalid JPEG component parameters".to_string());
+        }
+        components.push(Component { id: spec[0], h, v, quant: spec[2] as usize, blocks_w: 0, blocks_h: 0, coefficients: Scratch::default(), dc_table: 0, ac_table: 0, dc_pred: 0 });
+    }
+    let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+    let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+    let mcus_x = width.div_ceil(8 * h_max);
+    let mcus_y = height.div_ceil(8 * v_max);
+    for c in &mut components {
+        c.blocks_w = mcus_x * c.h;
+        c.blocks_h = mcus_y * c.v;
+        c.coefficients = pool::take(c.blocks_w * c.blocks_h * 64, 0);
+    }
+    Ok(Frame { width, height, progressive, h_max, v_max, mcus_x, mcus_y, components })
+}
+
+/// Spectral selection and successive approximation of a scan
+#[derive(Clone, Copy)]
+struct Scan {
+    start: usize,
+    end: usize,
+    high: u8,
+    low: u8,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn decode_block(bits: &mut EntropyReader, c: &mut Component, bx: usize, by: usize, dc: Option<&Huffman>, ac: Option<&Huffman>, scan: Scan, progressive: bool, eob_run: &mut u32) -> Result<(), String> {
+    const MISSING: &str = "JPEG scan uses an undefined Huffman table";
+    let start = (by * c.blocks_w + bx) * 64;
+    let block = &mut c.coefficients[start..start + 64];
+
+    if scan.start == 0 {
+        if scan.high == 0 || !progressive {
+            let size = dc.ok_or(MISSING)?.decode(bits)?;
+            c.dc_pred = c.dc_pred.wrapping_add(bits.extend(size)?);
+            block[0] = (c.dc_pred << scan.low) as i16;
+        } else if bits.bits(1) == 1 {
+            block[0] |= 1 << scan.low;
+        }
+        if progressive {
+            return Ok(());
+        }
+    }
+
+    let ac = ac.ok_or(MISSING)?;
+    if !progressive || scan.high == 0 {
+        if *eob_run > 0 {
+            *eob_run -= 1;
+            return Ok(());
+        }
+        let mut k = scan.start.max(1);
+        while k <= scan.end {
+            let rs = ac.decode(bits)?;
+            let (run, size) = ((rs >> 4) as usize, rs & 15);
+            if size == 0 {
+                if run < 15 {
+                    if progressive {
+                        *eob_run = (1 << run) + bits.bits(run as u32) - 1;
+                    }
+                    break;
+                }
+                k += 16;
+                continue;
+            }
+            k += run;
+            if k > 63 {
+                return Err(CORRUPT.to_string());
+            }
+            block[ZIGZAG[k]] = (bits.extend(size)? << scan.low) as i16;
+            k += 1;
+        }
+        return Ok(());
+    }
+
+    // Refinement of AC coefficients
+    let p1 = 1i16 << scan.low;
+    let m1 = -1i16 << scan.low;
+    let refine = |bits: &mut EntropyReader, coef: &mut i16| {
+        if bits.bits(1) == 1 && *coef & p1 == 0 {
+            *coef += if *coef >= 0 { p1 } else { m1 };
+        }
+    };
+    let mut k = scan.start;
+    if *eob_run > 0 {
+        *eob_run -= 1;
+        for &z in &ZIGZAG[k..=scan.end] {
+            if block[z] != 0 {
+                refine(bits, &mut block[z]);
+            }
+        }
+        return Ok(());
+    }
+    while k <= scan.end {
+        let rs = ac.decode(bits)?;
+        let (mut run, size) = ((rs >> 4) as usize, rs & 15);
+        let mut value = 0;
+        if size == 0 {
+            if run < 15 {
+                *eob_run = (1 << run) + bits.bits(run as u32) - 1;
+                // Refine the rest of this block, then stop
+                run = 64;
+            }
+        } else {
+            value = if bits.bits(1) == 1 { p1 } else { m1 };
+        }
+        while k <= scan.end {
+            let z = ZIGZAG[k];
+            k += 1;
+            if block[z] != 0 {
+                refine(bits, &mut block[z]);
+            } else if run == 0 {
+                block[z] = value;
+                break;
+            } else {
+                run -= 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Huffman tables by destination: DC then AC
+type Tables = [Option<Huffman>; 8];
+
+fn decode_scan(data: &[u8], pos: usize, frame: &mut Frame, selected: &[usize], scan: Scan, tables: &Tables, restart_interval: usize) -> Result<usize, String> {
+    let mut bits = EntropyReader::new(data, pos);
+    let mut eob_run = 0;
+    for &i in selected {
+        frame.components[i].dc_pred = 0;
+    }
+    let (units_x, units_y) = match selected {
+        [only] => {
+            let c = &frame.components[*only];
+            ((frame.width * c.h).div_ceil(frame.h_max).div_ceil(8), (frame.height * c.v).div_ceil(frame.v_max).div_ceil(8))
+        }
+        _ => (frame.mcus_x, frame.mcus_y),
+    };
+    let progressive = frame.progressive;
+    for unit in 0..units_x * units_y {
+        if restart_interval > 0 && unit > 0 && unit % restart_interval == 0 {
+            bits.restart();
+            eob_run = 0;
+            for &i in selected {
+                frame.components[i].dc_pred = 0;
+            }
+        }
+        let (ux, uy) = (unit % units_x, unit / units_x);
+        for &i in selected {
+            let c = &mut frame.components[i];
+            let (dc, ac) = (tables[c.dc_table].as_ref(), tables[4 + c.ac_table].as_ref());
+            if selected.len() == 1 {
+                decode_block(&mut bits, c, ux, uy, dc, ac, scan, progressive, &mut eob_run)?;
+                continue;
+            }
+            for v in 0..c.v {
+                for h in 0..c.h {
+                    decode_block(&mut bits, c, ux * c.h + h, uy * c.v + v, dc, ac, scan, progressive, &mut eob_run)?;
+                }
+            }
+        }
+    }
+    Ok(bits.pos)
+}
+
+/// Dequantize and inverse-DCT a component into 8-bit samples
+fn component_samples(c: &Component, quant: &[u16; 64]) -> Scratch<u8> {
+    let mut basis = [[0f32; 8]; 8];
+    for (x, row) in basis.iter_mut().enumerate() {
+        for (u, b) in row.iter_mut().enumerate() {
+            let scale = if u == 0 { std::f32::consts::FRAC_1_SQRT_2 } else { 1.0 };
+            *b = scale / 2.0 * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+        }
+    }
+    let stride = c.blocks_w * 8;
+    let mut samples = pool::take(stride * c.blocks_h * 8, 0u8);
+    let mut coef = [0f32; 64];
+    let mut rows = [0f32; 64];
+    for (index, block) in c.coefficients.chunks_exact(64).enumerate() {
+        for i in 0..64 {
+            coef[i] = block[i] as f32 * quant[i] as f32;
+        }
+        for v in 0..8 {
+            for x in 0..8 {
+                rows[v * 8 + x] = (0..8).map(|u| basis[x][u] * coef[v * 8 + u]).sum();
+            }
+        }
+        let (bx, by) = (index % c.blocks_w, index / c.blocks_w);
+        for y in 0..8 {
+            for x in 0..8 {
+                let value: f32 = (0..8).map(|v| basis[y][v] * rows[v * 8 + x]).sum();
+                samples[(by * 8 + y) * stride + bx * 8 + x] = (value + 128.0).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    samples
+}
+
+/// Neighboring samples and blend weight of an upsampled position
+type Tap = (usize, usize, f32);
+
+/// Bilinear taps placing each of `size` outputs at its center within a
+/// component subsampled by `factor / max`, as libjpeg's fancy upsampling does
+fn taps(size: usize, factor: usize, max: usize) -> Vec<Tap> {
+    let last = (size * factor).div_ceil(max) - 1;
+    (0..size)
+        .map(|i| {
+            let pos = ((i as f32 + 0.5) * factor as f32 / max as f32 - 0.5).max(0.0);
+            let i0 = (pos as usize).min(last);
+            (i0, (i0 + 1).min(last), pos - pos.floor())
+        })
+        .collect()
+}
+
+/// Reassemble an ICC profile from its APP2 chunks, given as (sequence number, data)
+fn icc_profile(mut chunks: Vec<(u8, &[u8])>) -> Option<Vec<u8>> {
+    if chunks.is_empty() {
+        return None;
+    }
+    chunks.sort_by_key(|&(sequence, _)| sequence);
+    Some(chunks.into_iter().flat_map(|(_, data)| data.iter().copied()).collect())
+}
+
+/// Decode a JPEG to RGBA
+///
+/// Returns: [width (4 bytes), height (4 bytes), rgba_data...]
+pub fn decode_jpeg(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+        return Err("Invalid JPEG signature".to_string());
+    }
+    let mut quant = [[1u16; 64]; 4];
+    let mut tables: Tables = Default::default();
+    for (slot, (counts, symbols)) in [(0, DC_LUMA), (1, DC_CHROMA), (4, AC_LUMA), (5, AC_CHROMA)] {
+        tables[slot] = Some(Huffman::new(&counts, symbols)?);
+    }
+    let mut frame = None;
+    let mut restart_interval = 0;
+    let mut adobe_transform = None;
+    let mut icc_chunks = Vec::new();
+    let mut scans = 0;
+    let mut budget = Budget::new();
+
+    let mut pos = 2;
+    loop {
+        // Find the next marker, skipping fill bytes and any stray data
+        while pos < data.len() && data[pos] != 0xFF {
+            pos += 1;
+        }
+        while data.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let Some(&marker) = data.get(pos + 1) else { break };
+        pos += 2;
+        match marker {
+            0xD9 => break,
+            0x00 | 0x01 | 0xD0..=0xD8 => continue,
+            _ => {}
+        }
+        let len = match data.get(pos..pos + 2) {
+            Some(b) => u16::from_be_bytes([b[0], b[1]]) as usize,
+            None => break,
+        };
+        let segment = data.get(pos + 2..pos + len.max(2)).ok_or("JPEG segment is truncated")?;
+        pos += len;
+        match marker {
+            0xC0..=0xC2 => frame = Some(parse_frame(segment, marker == 0xC2, &mut budget)?),
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return Err("Unsupported JPEG coding process (lossless, hierarchical or arithmetic)".to_string()),
+            0xC4 => {
+                let mut rest = segment;
+                while !rest.is_empty() {
+                    let class_id = rest[0];
+                    let counts: [u8; 16] = rest.get(1..17).ok_or("JPEG Huffman table is truncated")?.try_into().unwrap_or_default();
+                    let total: usize = counts.iter().map(|&c| c as usize).sum();
+                    let symbols = rest.get(17..17 + total).ok_or("JPEG Huffman table is truncated")?;
+                    let slot = (class_id >> 4).min(1) as usize * 4 + (class_id & 3) as usize;
+                    tables[slot] = Some(Huffman::new(&counts, symbols)?);
+                    rest = &rest[17 + total..];
+                }
+            }
+            0xDB => {
+                let mut rest = segment;
+                while !rest.is_empty() {
+                    let wide = rest[0] >> 4 != 0;
+                    let table = &mut quant[(rest[0] & 3) as usize];
+                    let size = if wide { 128 } else { 64 };
+                    let values = rest.get(1..1 + size).ok_or("JPEG quantization table is truncated")?;
+                    for (k, &z) in ZIGZAG.iter().enumerate() {
+                        table[z] = if wide { u16::from_be_bytes([values[2 * k], values[2 * k + 1]]) } else { values[k] as u16 };
+                    }
+                    rest = &rest[1 + size..];
+                }
+            }
+            0xDD => restart_interval = segment.get(..2).map_or(0, |b| u16::from_be_bytes([b[0], b[1]]) as usize),
+            // ICC_PROFILE, then the chunk's sequence number and the chunk count
+            0xE2 if segment.starts_with(b"ICC_PROFILE\0") && segment.len() >= 14 => icc_chunks.push((segment[12], &segment[14..])),
+            0xEE if segment.starts_with(b"Adobe") && segment.len() >= 12 => adobe_transform = Some(segment[11]),
+            0xDA => {
+                let frame = frame.as_mut().ok_or("JPEG scan precedes the frame header")?;
+                let count = *segment.first().ok_or("JPEG scan header is truncated")? as usize;
+                let specs = segment.get(1..1 + 2 * count + 3).ok_or("JPEG scan header is truncated")?;
+                let mut selected = Vec::with_capacity(count);
+                for spec in specs[..2 * count].chunks_exact(2) {
+                    let index = frame.components.iter().position(|c| c.id == spec[0]).ok_or("JPEG scan names an unknown component")?;
+                    let c = &mut frame.components[index];
+                    c.dc_table = (spec[1] >> 4 & 3) as usize;
+                    c.ac_table = (spec[1] & 3) as usize;
+                    selected.push(index);
+                }
+                let params = &specs[2 * count..];
+                let scan = Scan { start: params[0] as usize, end: (params[1] as usize).min(63), high: params[2] >> 4, low: params[2] & 15 };
+                if selected.is_empty() || scan.start > scan.end || (frame.progressive && scan.start > 0 && selected.len() > 1) {
+                    return Err("Invalid JPEG scan parameters".to_string());
+                }
+                // Progressive files may hold any number of scans, each a pass over every row
+                budget.rows(frame.height)?;
+                pos = decode_scan(data, pos, frame, &selected, scan, &tables, restart_interval)?;
+                scans += 1;
+            }
+            _ => {}
+        }
+    }
+
+    let frame = frame.ok_or("JPEG has no frame header")?;
+    if scans == 0 {
+        return Err("JPEG has no image data".to_string());
+    }
+    budget.rows(frame.height)?;
+    let planes: Vec<Scratch<u8>> = frame.components.iter().map(|c| component_samples(c, &quant[c.quant])).collect();
+    let (width, height) = (frame.width, frame.height);
+    let mut out = Vec::with_capacity(8 + width * height * 4);
+    out.extend_from_slice(&(width as u32).to_le_bytes());
+    out.extend_from_slice(&(height as u32).to_le_bytes());
+    // JFIF YCbCr unless an Adobe marker or the component IDs say RGB
+    let ids: Vec<u8> = frame.components.iter().map(|c| c.id).collect();
+    let transform = adobe_transform.unwrap_or(if ids == b"RGB" { 0 } else { 1 });
+    // Where each output column and row falls in each component
+    let transpose = |per_component: Vec<Vec<Tap>>, size: usize| -> Vec<Vec<Tap>> { (0..size).map(|i| per_component.iter().map(|t| t[i]).collect()).collect() };
+    let columns = transpose(frame.components.iter().map(|c| taps(width, c.h, frame.h_max)).collect(), width);
+    let rows = transpose(frame.components.iter().map(|c| taps(height, c.v, frame.v_max)).collect(), height);
+    for row in &rows {
+        for column in &columns {
+            let mut s = [0f32; 4];
+            for (i, (c, plane)) in frame.components.iter().zip(&planes).enumerate() {
+                let stride = c.blocks_w * 8;
+                let ((x0, x1, fx), (y0, y1, fy)) = (column[i], row[i]);
+                let at = |row: usize, col: usize| plane[row * stride + col] as f32;
+                let top = at(y0, x0) + (at(y0, x1) - at(y0, x0)) * fx;
+                let bottom = at(y1, x0) + (at(y1, x1) - at(y1, x0)) * fx;
+                s[i] = top + (bottom - top) * fy;
+            }
+            let rgb = match frame.components.len() {
+                1 => [s[0]; 3],
+                3 if transform == 0 => [s[0], s[1], s[2]],
+                3 => ycbcr_to_rgb([s[0], s[1], s[2]], YCbCrMatrix::Bt601),
+                // Adobe CMYK is stored inverted, so each channel times K is the color
+                _ => {
+                    let cmy = if transform == 2 { ycbcr_to_rgb([s[0], s[1], s[2]], YCbCrMatrix::Bt601) } else { [s[0], s[1], s[2]] };
+                    cmy.map(|v| v.clamp(0.0, 255.0) * s[3] / 255.0)
+                }
+            };
+            out.extend_from_slice(&[rgb[0].round().clamp(0.0, 255.0) as u8, rgb[1].round().clamp(0.0, 255.0) as u8, rgb[2].round().clamp(0.0, 255.0) as u8, 255]);
+        }
+    }
+
+    // Convert wide-gamut pixels to sRGB; an unusable profile, such as a CMYK
+    // one, leaves data untouched
+    if let Some(profile) = icc_profile(icc_chunks) {
+        if let Ok(converted) = apply_icc_profile(&out[8..], width as u32, height as u32, &profile) {
+            out[8..].copy_from_slice(&converted);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Canonical (code, length) of `symbol` in a standard table
+    fn code(table: ([u8; 16], &[u8]), symbol: u8) -> (u32, u32) {
+        let (counts, symbols) = table;
+        let (mut code, mut index) = (0, 0);
+        for (len, &count) in (1..).zip(&counts) {
+            for _ in 0..count {
+                if symbols[index] == symbol {
+                    return (code, len);
+                }
+                code += 1;
+                index += 1;
+            }
+            code <<= 1;
+        }
+        panic!("symbol {} not in table", symbol)
+    }
+
+    /// A single-color 4:4:4 baseline JPEG, DC-only and without Huffman
+    /// tables, like an MJPEG frame
+    pub(crate) fn flat_jpeg(width: u16, height: u16, ycc: [u8; 3]) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xD8, 0xFF, 0xDB, 0, 67, 0];
+        out.extend_from_slice(&[1; 64]);
+        out.extend_from_slice(&[0xFF, 0xC0, 0, 17, 8]);
+        out.extend_from_slice(&height.to_be_bytes());
+        out.extend_from_slice(&width.to_be_bytes());
+        out.extend_from_slice(&[3, 1, 0x11, 0, 2, 0x11, 0, 3, 0x11, 0]);
+        out.extend_from_slice(&[0xFF, 0xDA, 0, 12, 3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]);
+
+        let mut bits: Vec<bool> = Vec::new();
+        let mut put = |value: u32, len: u32| bits.extend((0..len).rev().map(|i| value >> i & 1 == 1));
+        let blocks = (width as usize).div_ceil(8) * (height as usize).div_ceil(8);
+        for block in 0..blocks {
+            for (i, &sample) in ycc.iter().enumerate() {
+                let (dc, ac) = if i == 0 { (DC_LUMA, AC_LUMA) } else { (DC_CHROMA, AC_CHROMA) };
+                // A flat block's DC coefficient is 8 times its level-shifted value
+                let diff = if block == 0 { 8 * (sample as i32 - 128) } else { 0 };
+                let size = 32 - diff.unsigned_abs().leading_zeros();
+                let (c, len) = code(dc, size as u8);
+                put(c, len);
+                put(if diff < 0 { (diff + (1 << size) - 1) as u32 } else { diff as u32 }, size);
+                let (c, len) = code(ac, 0x00);
+                put(c, len);
+            }
+        }
+        bits.resize(bits.len().div_ceil(8) * 8, true);
+        for byte in bits.chunks(8) {
+            let byte = byte.iter().fold(0u8, |b, &bit| b << 1 | bit as u8);
+            out.push(byte);
+            if byte == 0xFF {
+                out.push(0);
+            }
+        }
+        out.extend_from_slice(&[0xFF, 0xD9]);
+        out
+    }
+
+    #[test]
+    fn test_flat_mjpeg_frame() {
+        let jpeg = flat_jpeg(20, 10, [150, 90, 200]);
+        let decoded = decode_jpeg(&jpeg).unwrap();
+        assert_eq!(&decoded[..8], &[20, 0, 0, 0, 10, 0, 0, 0]);
+        let expected = ycbcr_to_rgb([150.0, 90.0, 200.0], YCbCrMatrix::Bt601).map(|v| v.round().clamp(0.0, 255.0) as u8);
+        for pixel in decoded[8..].chunks_exact(4) {
+            assert_eq!(pixel, [expected[0], expected[1], expected[2], 255]);
+        }
+        assert_eq!(decoded.len(), 8 + 20 * 10 * 4);
+
+        // Huffman tables of the standard are complete prefix codes
+        for (counts, symbols) in [DC_LUMA, DC_CHROMA, AC_LUMA, AC_CHROMA] {
+            assert!(Huffman::new(&counts, symbols).is_ok());
+        }
+        // A cut-off scan decodes as far as it goes
+        assert!(decode_jpeg(&jpeg[..jpeg.len() - 3]).is_ok());
+        assert!(decode_jpeg(&jpeg[..20]).is_err());
+        assert!(decode_jpeg(b"\xFF\xD8\xFF\xC3\0\x02").is_err());
+    }
+
+    #[test]
+    fn test_embedded_icc_profile() {
+        let plain = decode_jpeg(&flat_jpeg(8, 8, [150, 90, 200])).unwrap();
+        let profile = crate::icc::BuiltinProfile::DisplayP3.to_bytes();
+        let expected = apply_icc_profile(&plain[8..], 8, 8, &profile).unwrap();
+        assert_ne!(expected, plain[8..]);
+
+        // Split over two APP2 chunks, stored out of order
+        let (first, second) = profile.split_at(profile.len() / 2);
+        let mut jpeg = flat_jpeg(8, 8, [150, 90, 200]);
+        for (sequence, chunk) in [(2u8, second), (1, first)] {
+            let mut segment = vec![0xFF, 0xE2];
+            segment.extend_from_slice(&(16 + chunk.len() as u16).to_be_bytes());
+            segment.extend_from_slice(b"ICC_PROFILE\0");
+            segment.extend_from_slice(&[sequence, 2]);
+            segment.extend_from_slice(chunk);
+            jpeg.splice(2..2, segment);
+        }
+        assert_eq!(decode_jpeg(&jpeg).unwrap()[8..], expected);
+    }
+
+    #[test]
+    fn test_default_limits() {
+        // A corrupt SOF asking for 65535x65535 would need 8 GiB of coefficients
+        let mut jpeg = flat_jpeg(8, 8, [150, 90, 200]);
+        jpeg[76..80].fill(0xFF);
+        let error = decode_jpeg(&jpeg).unwrap_err();
+        assert!(error.starts_with("Image is 65535x65535, over the limit of"), "{}", error);
+    }
+}
@@ -0,0 +1,19 @@
+//! JPEG decoder implementation in pure Rust
+
+mod decoder;
+
+pub use decoder::decode_jpeg;
+
+// AVI tests build MJPEG frames with it
+#[cfg(all(test, feature = "video"))]
+pub(crate) use decoder::tests::flat_jpeg;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Decode a baseline or progressive JPEG to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeJpeg)]
+pub fn decode_jpeg_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    decode_jpeg(data).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,87 @@
+//! mconv-core: Pure Rust codec implementations
+//!
+//! Zero external dependencies for codec implementations.
+//! All codecs written from scratch. Each codec family sits behind a cargo
+//! feature of the same name (all on by default through `full`), so builds
+//! that need only part of the crate can leave the rest out.
+//!
+//! Nothing here depends on a JS runtime: the `wasm` feature adds the
+//! wasm-bindgen exports that mconv-wasm ships, and native users leave it off.
+//!
+//! Without the default `std` feature the crate is `no_std` + `alloc`: only
+//! the BMP and PNG codecs and the integer-only helpers they share are built,
+//! so the same decoders run on microcontrollers. Code that needs float math
+//! from std (colour management, resampling, frame retiming) is left out.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod animation;
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "audio")]
+pub mod audio;
+#[cfg(feature = "barcode")]
+pub mod barcode;
+#[cfg(feature = "std")]
+pub mod binary;
+#[cfg(feature = "bmp")]
+pub mod bmp;
+pub mod checksum;
+#[cfg(feature = "std")]
+pub mod color;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod compose;
+pub mod compress;
+#[cfg(feature = "std")]
+pub mod convert;
+#[cfg(feature = "std")]
+pub mod draw;
+#[cfg(feature = "std")]
+pub mod ecc;
+#[cfg(feature = "std")]
+pub mod filters;
+#[cfg(feature = "std")]
+pub mod font;
+#[cfg(feature = "gif")]
+pub mod gif;
+#[cfg(feature = "std")]
+pub mod hash;
+#[cfg(feature = "std")]
+pub mod histogram;
+#[cfg(feature = "std")]
+pub mod icc;
+#[cfg(feature = "jpeg")]
+pub mod jpeg;
+pub mod limits;
+#[cfg(feature = "std")]
+pub mod lut;
+#[cfg(feature = "std")]
+pub mod memory;
+pub mod metadata;
+#[cfg(feature = "threads")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod placeholder;
+#[cfg(feature = "png")]
+pub mod png;
+pub mod pool;
+#[cfg(feature = "std")]
+pub mod probe;
+#[cfg(feature = "std")]
+pub mod quantize;
+#[cfg(feature = "std")]
+pub mod raw;
+#[cfg(feature = "std")]
+pub mod resize;
+#[cfg(feature = "std")]
+pub mod text;
+pub mod transform;
+pub mod utils;
+#[cfg(feature = "video")]
+pub mod video;
+#[cfg(feature = "webp")]
+pub mod webp;
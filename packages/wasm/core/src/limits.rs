@@ -0,0 +1,252 @@
+//! Resource limits for decoding untrusted files
+//!
+//! One set of limits applies to the whole process: set it once with
+//! `set_limits` (`setLimits` in JS) and every decoder enforces it.
+//! Sizes are checked from the headers before any pixel memory is allocated,
+//! and work is counted in pixel rows before it is done, so a hostile upload
+//! fails with an error instead of exhausting memory or hanging the page.
+//! Decompressors, archive readers and audio decoders, which produce bytes
+//! rather than pixels, stop at the output byte limit.
+//! By default images are capped at `DEFAULT_MAX_PIXELS` pixels and output at
+//! `DEFAULT_MAX_OUTPUT_BYTES` bytes, so a corrupt header cannot ask for
+//! gigabytes before anyone sets limits; a limit of 0 means no limit.
+
+use alloc::{format, string::String};
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Default pixel limit: 128 megapixels, e.g. 16384x8192
+pub const DEFAULT_MAX_PIXELS: usize = 1 << 27;
+/// Default output limit: 512 MiB, the RGBA of `DEFAULT_MAX_PIXELS` pixels
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 1 << 29;
+
+static MAX_PIXELS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_PIXELS);
+static MAX_OUTPUT_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_OUTPUT_BYTES);
+static MAX_FRAMES: AtomicUsize = AtomicUsize::new(0);
+static MAX_ROWS: AtomicUsize = AtomicUsize::new(0);
+
+/// Limits applied by every decoder; 0 means unlimited
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Pixels in one image, animation canvas or frame
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = maxPixels))]
+    pub max_pixels: usize,
+    /// Bytes of decoded RGBA, summed over every frame of an animation, and
+    /// bytes one decompression, archive entry or audio decode may produce
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = maxOutputBytes))]
+    pub max_output_bytes: usize,
+    /// Frames in one animation
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = maxFrames))]
+    pub max_frames: usize,
+    /// Pixel rows one decode may process, counting every frame, interlace
+    /// pass and progressive scan; a timeout that is the same on every machine
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = maxRows))]
+    pub max_rows: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Limits {
+    /// The default limits; set the fields to change them, 0 to lift one
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Limits {
+        Limits::default()
+    }
+
+    /// No limits at all, for trusted input
+    pub fn unlimited() -> Limits {
+        Limits { max_pixels: 0, max_output_bytes: 0, max_frames: 0, max_rows: 0 }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits { max_pixels: DEFAULT_MAX_PIXELS, max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES, ..Limits::unlimited() }
+    }
+}
+
+/// Replace the limits for every later decode, on all threads
+pub fn set_limits(limits: &Limits) {
+    MAX_PIXELS.store(limits.max_pixels, Ordering::Relaxed);
+    MAX_OUTPUT_BYTES.store(limits.max_output_bytes, Ordering::Relaxed);
+    MAX_FRAMES.store(limits.max_frames, Ordering::Relaxed);
+    MAX_ROWS.store(limits.max_rows, Ordering::Relaxed);
+}
+
+/// The limits in effect
+pub fn limits() -> Limits {
+    Limits {
+        max_pixels: MAX_PIXELS.load(Ordering::Relaxed),
+        max_output_bytes: MAX_OUTPUT_BYTES.load(Ordering::Relaxed),
+        max_frames: MAX_FRAMES.load(Ordering::Relaxed),
+        max_rows: MAX_ROWS.load(Ordering::Relaxed),
+    }
+}
+
+/// Set the limits for every later decode, e.g. once before handling uploads
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = setLimits)]
+pub fn set_limits_js(limits: &Limits) {
+    set_limits(limits)
+}
+
+/// The limits in effect
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = getLimits)]
+pub fn limits_js() -> Limits {
+    limits()
+}
+
+/// A limit with 0 read as unlimited
+fn cap(limit: usize) -> usize {
+    if limit == 0 {
+        usize::MAX
+    } else {
+        limit
+    }
+}
+
+/// The output byte limit in effect, for decoders that produce bytes
+pub(crate) fn output_limit() -> usize {
+    cap(MAX_OUTPUT_BYTES.load(Ordering::Relaxed))
+}
+
+/// What one decode has used so far, checked against the limits it started with
+#[cfg_attr(not(any(feature = "std", feature = "bmp", feature = "png")), allow(dead_code))]
+pub(crate) struct Budget {
+    limits: Limits,
+    rows: usize,
+    frames: usize,
+    output_bytes: usize,
+    exceeded: bool,
+}
+
+#[cfg_attr(not(any(feature = "std", feature = "bmp", feature = "png")), allow(dead_code))]
+impl Budget {
+    /// A budget under the current global limits
+    pub(crate) fn new() -> Budget {
+        Budget::with_limits(limits())
+    }
+
+    pub(crate) fn with_limits(limits: Limits) -> Budget {
+        Budget { limits, rows: 0, frames: 0, output_bytes: 0, exceeded: false }
+    }
+
+    fn fail(&mut self, error: String) -> Result<(), String> {
+        self.exceeded = true;
+        Err(error)
+    }
+
+    /// Check a `width` x `height` image, canvas or frame before allocating its pixels
+    pub(crate) fn check_size(&mut self, width: usize, height: usize) -> Result<(), String> {
+        let pixels = width.saturating_mul(height);
+        if pixels > cap(self.limits.max_pixels) {
+            return self.fail(format!("Image is {}x{}, over the limit of {} pixels", width, height, self.limits.max_pixels));
+        }
+        if pixels.saturating_mul(4) > cap(self.limits.max_output_bytes) {
+            return self.fail(format!("Image is {}x{}, over the limit of {} output bytes", width, height, self.limits.max_output_bytes));
+        }
+        Ok(())
+    }
+
+    /// Count `rows` pixel rows of work before doing it
+    #[cfg_attr(not(any(feature = "bmp", feature = "png", feature = "jpeg", feature = "gif", feature = "webp")), allow(dead_code))]
+    pub(crate) fn rows(&mut self, rows: usize) -> Result<(), String> {
+        self.rows = self.rows.saturating_add(rows);
+        if self.rows > cap(self.limits.max_rows) {
+            return self.fail(format!("Decoding needs more than the limit of {} rows", self.limits.max_rows));
+        }
+        Ok(())
+    }
+
+    /// Count one more decoded frame of `bytes` bytes before keeping it
+    #[cfg_attr(not(any(feature = "png", feature = "gif", feature = "webp")), allow(dead_code))]
+    pub(crate) fn frame(&mut self, bytes: usize) -> Result<(), String> {
+        self.frames += 1;
+        self.output_bytes = self.output_bytes.saturating_add(bytes);
+        if self.frames > cap(self.limits.max_frames) {
+            return self.fail(format!("Animation has more than the limit of {} frames", self.limits.max_frames));
+        }
+        if self.output_bytes > cap(self.limits.max_output_bytes) {
+            return self.fail(format!("Decoded frames take more than the limit of {} bytes", self.limits.max_output_bytes));
+        }
+        Ok(())
+    }
+
+    /// Count `bytes` more bytes of output, such as decoded audio samples,
+    /// before producing them
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    pub(crate) fn output(&mut self, bytes: usize) -> Result<(), String> {
+        self.output_bytes = self.output_bytes.saturating_add(bytes);
+        if self.output_bytes > cap(self.limits.max_output_bytes) {
+            return self.fail(format!("Decoded output takes more than the limit of {} bytes", self.limits.max_output_bytes));
+        }
+        Ok(())
+    }
+
+    /// Whether a limit was hit, for decoders that keep the frames decoded
+    /// before other errors
+    #[cfg_attr(not(feature = "gif"), allow(dead_code))]
+    pub(crate) fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited() {
+        let mut budget = Budget::with_limits(Limits::unlimited());
+        assert!(budget.check_size(usize::MAX, usize::MAX).is_ok());
+        assert!(budget.rows(usize::MAX).and(budget.rows(1)).is_ok());
+        assert!(budget.frame(usize::MAX).is_ok());
+        assert!(!budget.exceeded());
+    }
+
+    #[test]
+    fn test_default() {
+        let mut budget = Budget::with_limits(Limits::new());
+        assert!(budget.check_size(16384, 8192).is_ok());
+        assert_eq!(budget.check_size(65535, 65535), Err("Image is 65535x65535, over the limit of 134217728 pixels".into()));
+        assert!(Budget::new().output(DEFAULT_MAX_OUTPUT_BYTES + 1).is_err());
+    }
+
+    #[test]
+    fn test_budget() {
+        let limits = Limits { max_pixels: 100, max_output_bytes: 1000, max_frames: 2, max_rows: 30 };
+        let mut budget = Budget::with_limits(limits);
+        assert!(budget.check_size(10, 10).is_ok());
+        assert_eq!(budget.check_size(10, 11), Err("Image is 10x11, over the limit of 100 pixels".into()));
+        assert!(budget.exceeded());
+
+        let mut budget = Budget::with_limits(Limits { max_pixels: 0, ..limits });
+        assert_eq!(budget.check_size(20, 20), Err("Image is 20x20, over the limit of 1000 output bytes".into()));
+        let mut budget = Budget::with_limits(limits);
+        assert!(budget.rows(20).and(budget.rows(10)).is_ok());
+        assert!(budget.rows(1).is_err());
+
+        let mut budget = Budget::with_limits(limits);
+        assert!(budget.frame(400).and(budget.frame(400)).is_ok());
+        assert_eq!(budget.frame(0), Err("Animation has more than the limit of 2 frames".into()));
+        let mut budget = Budget::with_limits(limits);
+        assert_eq!(budget.frame(1001), Err("Decoded frames take more than the limit of 1000 bytes".into()));
+        let mut budget = Budget::with_limits(limits);
+        assert!(budget.output(600).is_ok());
+        assert_eq!(budget.output(401), Err("Decoded output takes more than the limit of 1000 bytes".into()));
+    }
+
+    #[test]
+    fn test_set_limits() {
+        // Other tests decode concurrently, so set limits none of them reach
+        // while still rejecting what the default limits reject
+        let generous = Limits { max_pixels: DEFAULT_MAX_PIXELS - 1, max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES - 2, max_frames: 1 << 20, max_rows: 1 << 30 };
+        set_limits(&generous);
+        assert_eq!(limits(), generous);
+        assert_eq!(Budget::new().limits, generous);
+        set_limits(&Limits::new());
+        assert_eq!(limits(), Limits::default());
+    }
+}
@@ -0,0 +1,107 @@
+//! False-color maps for single-channel data
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Built-in colormaps
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colormap {
+    /// Perceptually uniform dark blue to yellow (matplotlib's default)
+    Viridis = 0,
+    /// Perceptually uniform black through purple to pale yellow
+    Magma = 1,
+    /// The classic MATLAB rainbow, blue through cyan, yellow to red
+    Jet = 2,
+    /// Google's improved rainbow, smoother than jet
+    Turbo = 3,
+}
+
+/// Degree-6 polynomial fits of the matplotlib maps, lowest power first
+const VIRIDIS: [[f64; 3]; 7] = [
+    [0.277_727_327_223_417_7, 0.005_407_344_544_966_578, 0.334_099_805_335_306_1],
+    [0.105_093_043_108_577_4, 1.404_613_529_898_575, 1.384_590_162_594_685],
+    [-0.330_861_828_725_556_3, 0.214_847_559_468_213, 0.095_095_163_028_236_59],
+    [-4.634_230_498_983_486, -5.799_100_973_351_585, -19.332_440_956_279_87],
+    [6.228_269_936_347_081, 14.179_933_366_805_09, 56.690_552_600_681_05],
+    [4.776_384_997_670_288, -13.745_145_377_746_01, -65.353_032_633_372_34],
+    [-5.435_455_855_934_631, 4.645_852_612_178_535, 26.312_435_249_583_2],
+];
+const MAGMA: [[f64; 3]; 7] = [
+    [-0.002_136_485_053_939_582, -0.000_749_655_052_795_221, -0.005_386_127_855_323_933],
+    [0.251_660_540_737_164_2, 0.677_523_243_683_766_8, 2.494_026_599_312_351],
+    [8.353_717_279_216_625, -3.577_719_514_958_484, 0.314_467_903_013_257_3],
+    [-27.668_733_085_768_66, 14.264_730_780_965_33, -13.649_213_188_139_22],
+    [52.176_139_812_340_68, -27.943_606_071_683_51, 12.944_169_442_383_94],
+    [-50.768_525_364_735_88, 29.046_582_821_272_91, 4.234_152_993_845_98],
+    [18.655_705_065_918_83, -11.489_773_519_977_11, -5.601_961_508_734_096],
+];
+/// Degree-5 polynomial approximation of turbo, slightly duller than the
+/// reference table at the dark end
+const TURBO: [[f64; 3]; 6] = [
+    [0.135_721_38, 0.091_402_61, 0.106_673_3],
+    [4.615_392_6, 2.194_188_39, 12.641_946_08],
+    [-42.660_322_58, 4.842_966_58, -60.582_048_36],
+    [132.131_082_34, -14.185_033_33, 110.362_767_71],
+    [-152.942_393_96, 4.277_298_57, -89.903_109_12],
+    [59.286_379_43, 2.829_566_04, 27.348_249_73],
+];
+
+fn polynomial(coefficients: &[[f64; 3]], t: f64) -> [f64; 3] {
+    let mut rgb = [0.0; 3];
+    for c in coefficients.iter().rev() {
+        for (channel, k) in rgb.iter_mut().zip(c) {
+            *channel = *channel * t + k;
+        }
+    }
+    rgb
+}
+
+impl Colormap {
+    /// The map's color for each input level
+    fn table(self) -> [[u8; 3]; 256] {
+        let mut table = [[0; 3]; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let t = i as f64 / 255.0;
+            let rgb = match self {
+                Colormap::Viridis => polynomial(&VIRIDIS, t),
+                Colormap::Magma => polynomial(&MAGMA, t),
+                Colormap::Turbo => polynomial(&TURBO, t),
+                Colormap::Jet => [1.5 - (4.0 * t - 3.0).abs(), 1.5 - (4.0 * t - 2.0).abs(), 1.5 - (4.0 * t - 1.0).abs()],
+            };
+            *entry = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+        table
+    }
+}
+
+/// Color one-byte-per-pixel data with `map`, producing opaque RGBA
+pub fn apply_colormap(gray_data: &[u8], width: u32, height: u32, map: Colormap) -> Result<Vec<u8>, String> {
+    let expected_len = width as usize * height as usize;
+    if gray_data.len() != expected_len {
+        return Err(format!("Data length mismatch: expected {}, got {}", expected_len, gray_data.len()));
+    }
+    let table = map.table();
+    Ok(gray_data.iter().flat_map(|&v| table[v as usize].into_iter().chain([255])).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colormaps() {
+        let close = |a: &[u8], b: [u8; 3]| a.iter().zip(b).all(|(&x, y)| x.abs_diff(y) <= 6);
+        // Endpoints and midpoints of the reference tables
+        let viridis = apply_colormap(&[0, 128, 255], 3, 1, Colormap::Viridis).unwrap();
+        assert!(close(&viridis[..3], [68, 1, 84]) && close(&viridis[4..7], [33, 145, 140]) && close(&viridis[8..11], [253, 231, 37]), "{:?}", viridis);
+        let magma = apply_colormap(&[0, 255], 2, 1, Colormap::Magma).unwrap();
+        assert!(close(&magma[..3], [0, 0, 4]) && close(&magma[4..7], [252, 253, 191]), "{:?}", magma);
+        // Turbo starts dark, peaks green in the middle and ends dark red
+        let turbo = apply_colormap(&[0, 128, 255], 3, 1, Colormap::Turbo).unwrap();
+        assert!(turbo[..3].iter().all(|&c| c < 64) && turbo[5] > 240 && turbo[8] > 100 && turbo[9] < 20, "{:?}", turbo);
+        let jet = apply_colormap(&[0, 128, 255], 3, 1, Colormap::Jet).unwrap();
+        assert_eq!(jet, [0, 0, 128, 255, 130, 255, 126, 255, 128, 0, 0, 255]);
+        assert!(apply_colormap(&[0; 5], 2, 2, Colormap::Jet).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Adobe/Resolve `.cube` 3D LUT parsing and trilinear application
+
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A 3D color lookup table
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut3d {
+    size: usize,
+    title: String,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    /// RGB triples with red varying fastest
+    table: Vec<f32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Lut3d {
+    /// Grid points per axis
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+}
+
+impl Lut3d {
+    /// Build a LUT from RGB triples ordered with red varying fastest
+    pub fn new(size: usize, table: Vec<f32>) -> Result<Lut3d, String> {
+        if size < 2 {
+            return Err(format!("Invalid LUT size: {}", size));
+        }
+        if table.len() != size * size * size * 3 {
+            return Err(format!("LUT has {} values, expected {}", table.len(), size * size * size * 3));
+        }
+        Ok(Lut3d {
+            size,
+            title: String::new(),
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            table,
+        })
+    }
+
+    #[inline]
+    fn at(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        let i = ((b * self.size + g) * self.size + r) * 3;
+        [self.table[i], self.table[i + 1], self.table[i + 2]]
+    }
+
+    /// Look up a color with trilinear interpolation
+    pub fn sample(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let max = (self.size - 1) as f32;
+        let mut base = [0usize; 3];
+        let mut frac = [0f32; 3];
+        for c in 0..3 {
+            let range = self.domain_max[c] - self.domain_min[c];
+            let t = if range > 0.0 { (rgb[c] - self.domain_min[c]) / range } else { 0.0 };
+            let pos = t.clamp(0.0, 1.0) * max;
+            let i = (pos as usize).min(self.size - 2);
+            base[c] = i;
+            frac[c] = pos - i as f32;
+        }
+
+        let [r, g, b] = base;
+        let [fr, fg, fb] = frac;
+        let lerp = |a: [f32; 3], b: [f32; 3], t: f32| [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t];
+
+        let c00 = lerp(self.at(r, g, b), self.at(r + 1, g, b), fr);
+        let c10 = lerp(self.at(r, g + 1, b), self.at(r + 1, g + 1, b), fr);
+        let c01 = lerp(self.at(r, g, b + 1), self.at(r + 1, g, b + 1), fr);
+        let c11 = lerp(self.at(r, g + 1, b + 1), self.at(r + 1, g + 1, b + 1), fr);
+        lerp(lerp(c00, c10, fg), lerp(c01, c11, fg), fb)
+    }
+}
+
+fn parse_triple(parts: &[&str], line: usize) -> Result<[f32; 3], String> {
+    if parts.len() != 3 {
+        return Err(format!("Line {}: expected 3 values", line));
+    }
+    let mut v = [0f32; 3];
+    for (dst, s) in v.iter_mut().zip(parts) {
+        *dst = s.parse().map_err(|_| format!("Line {}: invalid number '{}'", line, s))?;
+    }
+    Ok(v)
+}
+
+/// Parse a `.cube` file
+pub fn parse_cube(text: &str) -> Result<Lut3d, String> {
+    let mut size = 0usize;
+    let mut title = String::new();
+    let mut domain_min = [0.0; 3];
+    let mut domain_max = [1.0; 3];
+    let mut table = Vec::new();
+
+    for (n, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parts[0] {
+            "TITLE" => title = line[5..].trim().trim_matches('"').to_string(),
+            "LUT_3D_SIZE" => {
+                size = parts
+                    .get(1)
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&s| (2..=256).contains(&s))
+                    .ok_or_else(|| format!("Line {}: invalid LUT_3D_SIZE", n + 1))?;
+                table.reserve(size * size * size * 3);
+            }
+            "LUT_1D_SIZE" => return Err("1D LUTs are not supported".to_string()),
+            "DOMAIN_MIN" => domain_min = parse_triple(&parts[1..], n + 1)?,
+            "DOMAIN_MAX" => domain_max = parse_triple(&parts[1..], n + 1)?,
+            "LUT_3D_INPUT_RANGE" => {
+                let bound = |i: usize| -> Result<f32, String> {
+                    parts.get(i).and_then(|s| s.parse().ok()).ok_or_else(|| format!("Line {}: invalid input range", n + 1))
+                };
+                domain_min = [bound(1)?; 3];
+                domain_max = [bound(2)?; 3];
+            }
+            keyword if keyword.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) => {
+                // Unknown keywords are ignored for forward compatibility
+            }
+            _ => table.extend_from_slice(&parse_triple(&parts, n + 1)?),
+        }
+    }
+
+    if size == 0 {
+        return Err("Missing LUT_3D_SIZE".to_string());
+    }
+    let mut lut = Lut3d::new(size, table)?;
+    lut.title = title;
+    lut.domain_min = domain_min;
+    lut.domain_max = domain_max;
+    Ok(lut)
+}
+
+/// Apply a 3D LUT to an RGBA image, preserving alpha
+pub fn apply_lut3d(data: &[u8], width: u32, height: u32, lut: &Lut3d) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+
+    let mut output = Vec::with_capacity(data.len());
+    for p in data.chunks_exact(4) {
+        let rgb = lut.sample([p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0]);
+        for v in rgb {
+            output.push((v * 255.0).round().clamp(0.0, 255.0) as u8);
+        }
+        output.push(p[3]);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity_cube(size: usize) -> String {
+        let mut text = format!("TITLE \"Identity\"\n# comment\nLUT_3D_SIZE {}\n", size);
+        let max = (size - 1) as f32;
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    text += &format!("{} {} {}\n", r as f32 / max, g as f32 / max, b as f32 / max);
+                }
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn test_identity_lut() {
+        let lut = parse_cube(&identity_cube(5)).unwrap();
+        assert_eq!(lut.size(), 5);
+        assert_eq!(lut.title(), "Identity");
+
+        let data = [12u8, 200, 77, 9, 255, 0, 128, 255];
+        assert_eq!(apply_lut3d(&data, 2, 1, &lut).unwrap(), data);
+    }
+
+    #[test]
+    fn test_rejects_short_table() {
+        assert!(parse_cube("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(parse_cube("0 0 0\n").is_err());
+    }
+}
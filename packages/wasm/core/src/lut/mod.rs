@@ -0,0 +1,31 @@
+//! Color grading lookup tables and false-color maps
+
+pub mod colormap;
+pub mod cube;
+
+pub use colormap::{apply_colormap, Colormap};
+pub use cube::{apply_lut3d, parse_cube, Lut3d};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Parse a `.cube` 3D LUT
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = parseCube)]
+pub fn parse_cube_js(text: &str) -> Result<Lut3d, JsError> {
+    parse_cube(text).map_err(|e| JsError::new(&e))
+}
+
+/// Apply a 3D LUT to an RGBA image with trilinear interpolation
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = applyLut3d)]
+pub fn apply_lut3d_js(data: &[u8], width: u32, height: u32, lut: &Lut3d) -> Result<Vec<u8>, JsError> {
+    apply_lut3d(data, width, height, lut).map_err(|e| JsError::new(&e))
+}
+
+/// Turn one-byte-per-pixel data into RGBA through a colormap
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = applyColormap)]
+pub fn apply_colormap_js(gray_data: &[u8], width: u32, height: u32, map: Colormap) -> Result<Vec<u8>, JsError> {
+    apply_colormap(gray_data, width, height, map).map_err(|e| JsError::new(&e))
+}
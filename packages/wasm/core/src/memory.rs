@@ -0,0 +1,158 @@
+//! Heap accounting: a global allocator wrapper that counts live and peak
+//! bytes, alongside the buffer pool's share of them
+//!
+//! Counting only happens once the final binary installs `TrackingAllocator`
+//! with `#[global_allocator]`, as mconv-wasm does; until then every count
+//! stays at zero.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, keeping count of the bytes it hands out
+pub struct TrackingAllocator;
+
+fn grow(bytes: usize) {
+    let now = CURRENT.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK.fetch_max(now, Ordering::Relaxed);
+}
+
+fn shrink(bytes: usize) {
+    CURRENT.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+            LIVE.fetch_add(1, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+            LIVE.fetch_add(1, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        shrink(layout.size());
+        LIVE.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        // On failure the old block stays allocated, so nothing changes
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                grow(new_size - layout.size());
+            } else {
+                shrink(layout.size() - new_size);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Heap use as seen by `TrackingAllocator`
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Bytes currently allocated
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = currentBytes))]
+    pub current_bytes: usize,
+    /// Most bytes allocated at once since startup or `reset_peak`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = peakBytes))]
+    pub peak_bytes: usize,
+    /// Allocations not yet freed
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = liveAllocations))]
+    pub live_allocations: usize,
+    /// Size of WebAssembly linear memory, which never shrinks; 0 natively
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = heapBytes))]
+    pub heap_bytes: usize,
+    /// Scratch buffers kept in the buffer pool for reuse
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = poolIdleBytes))]
+    pub pool_idle_bytes: usize,
+    /// Scratch buffers lent out to running operations
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = poolLentBytes))]
+    pub pool_lent_bytes: usize,
+}
+
+/// Current heap use
+pub fn memory_stats() -> MemoryStats {
+    #[cfg(target_arch = "wasm32")]
+    let heap_bytes = core::arch::wasm32::memory_size(0) * 65536;
+    #[cfg(not(target_arch = "wasm32"))]
+    let heap_bytes = 0;
+    let (pool_idle_bytes, pool_lent_bytes) = crate::pool::pool_bytes();
+
+    MemoryStats {
+        current_bytes: CURRENT.load(Ordering::Relaxed),
+        peak_bytes: PEAK.load(Ordering::Relaxed),
+        live_allocations: LIVE.load(Ordering::Relaxed),
+        heap_bytes,
+        pool_idle_bytes,
+        pool_lent_bytes,
+    }
+}
+
+/// Start measuring the peak again from the current use
+pub fn reset_peak() {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Current heap use; counts stay at zero unless the tracking allocator is installed
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = memoryStats)]
+pub fn memory_stats_js() -> MemoryStats {
+    memory_stats()
+}
+
+/// Start measuring `peakBytes` again from the current use, e.g. before
+/// decoding one file
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = resetPeakMemory)]
+pub fn reset_peak_js() {
+    reset_peak()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tests run on the default allocator, so only calls made here are counted
+    #[test]
+    fn test_tracking_allocator() {
+        let before = memory_stats();
+        let layout = Layout::from_size_align(1000, 8).unwrap();
+        unsafe {
+            let ptr = TrackingAllocator.alloc(layout);
+            assert_eq!(memory_stats().current_bytes, before.current_bytes + 1000);
+            assert_eq!(memory_stats().live_allocations, before.live_allocations + 1);
+
+            let ptr = TrackingAllocator.realloc(ptr, layout, 4000);
+            let grown = memory_stats();
+            assert_eq!(grown.current_bytes, before.current_bytes + 4000);
+            assert!(grown.peak_bytes >= grown.current_bytes);
+
+            TrackingAllocator.dealloc(ptr, Layout::from_size_align(4000, 8).unwrap());
+        }
+        let after = memory_stats();
+        assert_eq!((after.current_bytes, after.live_allocations), (before.current_bytes, before.live_allocations));
+        assert!(after.peak_bytes >= before.current_bytes + 4000);
+
+        reset_peak();
+        assert_eq!(memory_stats().peak_bytes, after.current_bytes);
+    }
+}
@@ -0,0 +1,794 @@
+//! Audio file tags - ID3v2 frames and FLAC/Ogg Vorbis comments
+//!
+//! ID3v2.2 to 2.4 tags are read with unsynchronization and frame
+//! compression undone, and are always written back as ID3v2.4. Vorbis
+//! comments are read from FLAC and Ogg Vorbis/Opus files and written into
+//! FLAC.
+
+#[cfg(feature = "audio")]
+use crate::audio::ogg::{read_packets, read_pages};
+use crate::compress::zlib_decompress;
+#[cfg(feature = "audio")]
+use crate::text::base64::base64_decode;
+use crate::text::unicode::decode_utf16;
+use crate::utils::{read_u32_be, read_u32_le};
+#[cfg(feature = "video")]
+use crate::video::matroska::{read_matroska, EBML_MAGIC};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// ID3 and FLAC picture type of the front cover
+pub const PICTURE_FRONT_COVER: u8 = 3;
+
+const FLAC_VORBIS_COMMENT: u8 = 4;
+const FLAC_PICTURE: u8 = 6;
+const FLAC_PADDING: u8 = 1;
+
+/// Common field names with their ID3v2.4 frame and Vorbis comment field
+const FIELDS: &[(&str, &str, &str)] = &[
+    ("title", "TIT2", "TITLE"),
+    ("artist", "TPE1", "ARTIST"),
+    ("album", "TALB", "ALBUM"),
+    ("albumArtist", "TPE2", "ALBUMARTIST"),
+    ("date", "TDRC", "DATE"),
+    ("track", "TRCK", "TRACKNUMBER"),
+    ("disc", "TPOS", "DISCNUMBER"),
+    ("genre", "TCON", "GENRE"),
+    ("composer", "TCOM", "COMPOSER"),
+    ("comment", "COMM", "COMMENT"),
+];
+
+/// ID3v2.2 frames that have a v2.4 equivalent; others are dropped
+const V22_FRAMES: &[(&[u8], &str)] = &[
+    (b"TT2", "TIT2"),
+    (b"TP1", "TPE1"),
+    (b"TP2", "TPE2"),
+    (b"TAL", "TALB"),
+    (b"TYE", "TDRC"),
+    (b"TRK", "TRCK"),
+    (b"TPA", "TPOS"),
+    (b"TCO", "TCON"),
+    (b"TCM", "TCOM"),
+    (b"COM", "COMM"),
+    (b"TXX", "TXXX"),
+    (b"PIC", "APIC"),
+];
+
+/// Embedded picture from an ID3 APIC frame or a FLAC PICTURE block
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Picture {
+    /// MIME type, e.g. `image/jpeg`
+    pub mime: String,
+    /// Picture type; 3 is the front cover
+    pub picture_type: u8,
+    pub description: String,
+    /// Encoded image bytes, ready for the image decoders
+    pub data: Vec<u8>,
+}
+
+/// One ID3v2 frame with flags undone, keyed by its v2.4 ID
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Id3Frame {
+    pub id: String,
+    pub data: Vec<u8>,
+}
+
+/// An ID3v2 tag as a list of frames
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Id3Tag {
+    /// Major version the tag was read from (2, 3, or 4)
+    pub version: u8,
+    pub frames: Vec<Id3Frame>,
+}
+
+fn synchsafe(b: &[u8]) -> usize {
+    b[..4].iter().fold(0, |acc, &x| (acc << 7) | (x & 0x7F) as usize)
+}
+
+fn write_synchsafe(value: usize) -> [u8; 4] {
+    [(value >> 21) as u8 & 0x7F, (value >> 14) as u8 & 0x7F, (value >> 7) as u8 & 0x7F, value as u8 & 0x7F]
+}
+
+/// Undo unsynchronization, which inserts a zero after every 0xFF
+fn resync(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut prev = 0;
+    for &b in data {
+        if !(prev == 0xFF && b == 0) {
+            out.push(b);
+        }
+        prev = b;
+    }
+    out
+}
+
+/// Size of a leading ID3v2 tag including header and footer
+pub fn id3_len(data: &[u8]) -> Option<usize> {
+    if data.len() < 10 || &data[..3] != b"ID3" || data[6..10].iter().any(|&b| b >= 0x80) {
+        return None;
+    }
+    let footer = if data[5] & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + synchsafe(&data[6..10]) + footer)
+}
+
+fn decode_text(encoding: u8, data: &[u8]) -> String {
+    let text = match encoding {
+        0 => data.iter().map(|&b| b as char).collect(),
+        // Every value of a UTF-16 list carries its own BOM
+        1 | 2 => decode_utf16(data, encoding == 2, false).unwrap_or_default().replace('\u{FEFF}', ""),
+        _ => String::from_utf8_lossy(data).into_owned(),
+    };
+    text.trim_end_matches('\0').to_string()
+}
+
+/// Split off a string terminated by the encoding's NUL
+fn split_terminated(encoding: u8, data: &[u8]) -> (String, &[u8]) {
+    let end = if matches!(encoding, 1 | 2) {
+        (0..data.len() / 2).map(|i| i * 2).find(|&i| data[i] == 0 && data[i + 1] == 0).map(|i| (i, 2))
+    } else {
+        data.iter().position(|&b| b == 0).map(|i| (i, 1))
+    };
+    match end {
+        Some((end, width)) => (decode_text(encoding, &data[..end]), &data[end + width..]),
+        None => (decode_text(encoding, data), &[]),
+    }
+}
+
+/// Encoding byte and bytes of a value: Latin-1 when ASCII, else UTF-8
+fn encode_text(value: &str) -> Vec<u8> {
+    let mut out = vec![if value.is_ascii() { 0 } else { 3 }];
+    out.extend_from_slice(value.as_bytes());
+    out
+}
+
+/// Rewrite a v2.2 PIC frame (three-letter image format) as an APIC frame
+fn pic_to_apic(data: &[u8]) -> Vec<u8> {
+    if data.len() < 4 {
+        return data.to_vec();
+    }
+    let format = String::from_utf8_lossy(&data[1..4]).to_ascii_lowercase();
+    let mime = if format == "jpg" { "image/jpeg".to_string() } else { format!("image/{}", format) };
+    let mut out = vec![data[0]];
+    out.extend_from_slice(mime.as_bytes());
+    out.push(0);
+    out.extend_from_slice(&data[4..]);
+    out
+}
+
+impl Id3Tag {
+    /// Parse a tag at the start of `data`; `None` when there is no tag
+    pub fn parse(data: &[u8]) -> Result<Option<Id3Tag>, String> {
+        let Some(len) = id3_len(data) else {
+            return Ok(None);
+        };
+        let (version, tag_flags) = (data[3], data[5]);
+        if !(2..=4).contains(&version) {
+            return Err(format!("Unsupported ID3v2.{} tag", version));
+        }
+        let body = data.get(10..len - if tag_flags & 0x10 != 0 { 10 } else { 0 }).ok_or("ID3v2 tag is truncated")?;
+        // Before v2.4, unsynchronization covers the whole tag
+        let body = if version < 4 && tag_flags & 0x80 != 0 { resync(body) } else { body.to_vec() };
+
+        let mut pos = 0;
+        if tag_flags & 0x40 != 0 {
+            pos = match version {
+                2 => return Err("Compressed ID3v2.2 tags are not supported".to_string()),
+                3 => 4 + read_u32_be(&body, 0) as usize,
+                _ => synchsafe(body.get(..4).ok_or("ID3v2 tag is truncated")?),
+            };
+        }
+
+        let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+        let mut frames = Vec::new();
+        while pos + header_len <= body.len() && body[pos] != 0 {
+            let raw_id = &body[pos..pos + id_len];
+            if !raw_id.iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit()) {
+                break;
+            }
+            let h = &body[pos + id_len..pos + header_len];
+            let (size, flags) = match version {
+                2 => (((h[0] as usize) << 16) | ((h[1] as usize) << 8) | h[2] as usize, 0),
+                3 => (read_u32_be(h, 0) as usize, u16::from_be_bytes([h[4], h[5]])),
+                _ => (synchsafe(h), u16::from_be_bytes([h[4], h[5]])),
+            };
+            let start = pos + header_len;
+            let Some(content) = body.get(start..start + size) else { break };
+            pos = start + size;
+
+            // Grouping, encryption, and size fields precede the content
+            let (compressed, encrypted, extra) = match version {
+                3 => (flags & 0x80 != 0, flags & 0x40 != 0, 4 * (flags >> 7 & 1) + (flags >> 6 & 1) + (flags >> 5 & 1)),
+                4 => (flags & 0x08 != 0, flags & 0x04 != 0, (flags >> 6 & 1) + (flags >> 2 & 1) + 4 * (flags & 1)),
+                _ => (false, false, 0),
+            };
+            if encrypted {
+                continue;
+            }
+            let content = content.get(extra as usize..).unwrap_or(&[]);
+            let mut content = if version == 4 && (flags & 0x02 != 0 || tag_flags & 0x80 != 0) { resync(content) } else { content.to_vec() };
+            if compressed {
+                content = zlib_decompress(&content)?;
+            }
+
+            let id = match version {
+                2 => match V22_FRAMES.iter().find(|(v22, _)| *v22 == raw_id) {
+                    Some((_, id)) => *id,
+                    None => continue,
+                },
+                // TYER holds the year alone, which is a valid TDRC
+                _ if raw_id == b"TYER" => "TDRC",
+                _ => std::str::from_utf8(raw_id).unwrap_or_default(),
+            };
+            if version == 2 && id == "APIC" {
+                content = pic_to_apic(&content);
+            }
+            frames.push(Id3Frame { id: id.to_string(), data: content });
+        }
+        Ok(Some(Id3Tag { version, frames }))
+    }
+
+    /// Value of a text frame (`T...`), multiple values joined with "; "
+    pub fn text(&self, id: &str) -> Option<String> {
+        let frame = self.frames.iter().find(|f| f.id == id && f.id.starts_with('T') && f.id != "TXXX")?;
+        let (&encoding, text) = frame.data.split_first()?;
+        let text = decode_text(encoding, text);
+        let values: Vec<&str> = text.split('\0').filter(|s| !s.is_empty()).map(str::trim).collect();
+        Some(values.join("; "))
+    }
+
+    /// Text of the first comment (COMM) frame
+    pub fn comment(&self) -> Option<String> {
+        let frame = self.frames.iter().find(|f| f.id == "COMM")?;
+        let (&encoding, rest) = frame.data.split_first()?;
+        let (_description, text) = split_terminated(encoding, rest.get(3..)?);
+        Some(decode_text(encoding, text))
+    }
+
+    /// User-defined text (TXXX) frames as (description, value) pairs
+    pub fn user_text(&self) -> Vec<(String, String)> {
+        self.frames
+            .iter()
+            .filter(|f| f.id == "TXXX")
+            .filter_map(|f| {
+                let (&encoding, rest) = f.data.split_first()?;
+                let (description, value) = split_terminated(encoding, rest);
+                Some((description, decode_text(encoding, value)))
+            })
+            .collect()
+    }
+
+    /// Attached pictures (APIC frames)
+    pub fn pictures(&self) -> Vec<Picture> {
+        self.frames
+            .iter()
+            .filter(|f| f.id == "APIC")
+            .filter_map(|f| {
+                let (&encoding, rest) = f.data.split_first()?;
+                let (mime, rest) = split_terminated(0, rest);
+                let (&picture_type, rest) = rest.split_first()?;
+                let (description, data) = split_terminated(encoding, rest);
+                Some(Picture { mime, picture_type, description, data: data.to_vec() })
+            })
+            .collect()
+    }
+
+    /// Set a text frame, replacing any existing one
+    pub fn set_text(&mut self, id: &str, value: &str) {
+        self.remove(id);
+        self.frames.push(Id3Frame { id: id.to_string(), data: encode_text(value) });
+    }
+
+    /// Set the comment, replacing all existing comment frames
+    pub fn set_comment(&mut self, value: &str) {
+        self.remove("COMM");
+        let text = encode_text(value);
+        // Encoding, language, empty description, text
+        let mut data = vec![text[0]];
+        data.extend_from_slice(b"eng\0");
+        data.extend_from_slice(&text[1..]);
+        self.frames.push(Id3Frame { id: "COMM".to_string(), data });
+    }
+
+    /// Attach a picture, replacing any of the same picture type
+    pub fn set_picture(&mut self, picture: &Picture) {
+        let kept: Vec<Id3Frame> = self
+            .frames
+            .drain(..)
+            .filter(|f| f.id != "APIC" || f.data.get(1..).and_then(|d| split_terminated(0, d).1.first().copied()) != Some(picture.picture_type))
+            .collect();
+        self.frames = kept;
+        let description = encode_text(&picture.description);
+        let mut data = vec![description[0]];
+        data.extend_from_slice(picture.mime.as_bytes());
+        data.push(0);
+        data.push(picture.picture_type);
+        data.extend_from_slice(&description[1..]);
+        data.push(0);
+        data.extend_from_slice(&picture.data);
+        self.frames.push(Id3Frame { id: "APIC".to_string(), data });
+    }
+
+    /// Remove every frame with an ID; returns whether any existed
+    pub fn remove(&mut self, id: &str) -> bool {
+        let before = self.frames.len();
+        self.frames.retain(|f| f.id != id);
+        self.frames.len() != before
+    }
+
+    /// Serialize as an ID3v2.4 tag without padding
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for frame in &self.frames {
+            body.extend_from_slice(frame.id.as_bytes());
+            body.extend_from_slice(&write_synchsafe(frame.data.len()));
+            body.extend_from_slice(&[0, 0]);
+            body.extend_from_slice(&frame.data);
+        }
+        let mut out = b"ID3\x04\x00\x00".to_vec();
+        out.extend_from_slice(&write_synchsafe(body.len()));
+        out.extend_from_slice(&body);
+        out
+    }
+}
+
+/// Replace the leading ID3v2 tag(s) of a file; an empty tag just strips them
+pub fn write_id3(file: &[u8], tag: &Id3Tag) -> Vec<u8> {
+    let mut audio = file;
+    while let Some(len) = id3_len(audio) {
+        audio = audio.get(len..).unwrap_or(&[]);
+    }
+    let mut out = if tag.frames.is_empty() { Vec::new() } else { tag.to_bytes() };
+    out.extend_from_slice(audio);
+    out
+}
+
+/// Read a 32-bit length-prefixed field and advance past it
+fn take_field<'a>(data: &'a [u8], pos: &mut usize, big_endian: bool) -> Option<&'a [u8]> {
+    data.get(*pos..*pos + 4)?;
+    let len = if big_endian { read_u32_be(data, *pos) } else { read_u32_le(data, *pos) } as usize;
+    let field = data.get(*pos + 4..(*pos + 4).checked_add(len)?)?;
+    *pos += 4 + len;
+    Some(field)
+}
+
+/// A Vorbis comment block: vendor string and `FIELD=value` pairs
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VorbisComments {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+impl VorbisComments {
+    /// Parse a comment block (without the Vorbis/Opus packet header)
+    pub fn parse(data: &[u8]) -> Result<VorbisComments, String> {
+        let truncated = || "Vorbis comment block is truncated".to_string();
+        let mut pos = 0;
+        let vendor = String::from_utf8_lossy(take_field(data, &mut pos, false).ok_or_else(truncated)?).into_owned();
+        let count = data.get(pos..pos + 4).map(|_| read_u32_le(data, pos)).ok_or_else(truncated)?;
+        pos += 4;
+        let mut comments = Vec::new();
+        for _ in 0..count {
+            let comment = String::from_utf8_lossy(take_field(data, &mut pos, false).ok_or_else(truncated)?);
+            if let Some((key, value)) = comment.split_once('=') {
+                comments.push((key.to_string(), value.to_string()));
+            }
+        }
+        Ok(VorbisComments { vendor, comments })
+    }
+
+    /// All values of a field, compared case-insensitively
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.comments.iter().filter(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.as_str()).collect()
+    }
+
+    /// Set a field, replacing all existing values
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.remove(key);
+        self.comments.push((key.to_ascii_uppercase(), value.to_string()));
+    }
+
+    /// Remove every value of a field; returns whether any existed
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.comments.len();
+        self.comments.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+        self.comments.len() != before
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.vendor.len() as u32).to_le_bytes());
+        out.extend_from_slice(self.vendor.as_bytes());
+        out.extend_from_slice(&(self.comments.len() as u32).to_le_bytes());
+        for (key, value) in &self.comments {
+            out.extend_from_slice(&((key.len() + 1 + value.len()) as u32).to_le_bytes());
+            out.extend_from_slice(key.as_bytes());
+            out.push(b'=');
+            out.extend_from_slice(value.as_bytes());
+        }
+        out
+    }
+}
+
+/// Parse a FLAC PICTURE block (also the METADATA_BLOCK_PICTURE comment)
+pub fn parse_flac_picture(data: &[u8]) -> Result<Picture, String> {
+    let truncated = || "FLAC picture block is truncated".to_string();
+    let picture_type = data.get(..4).map(|_| read_u32_be(data, 0)).ok_or_else(truncated)?;
+    let mut pos = 4;
+    let mime = String::from_utf8_lossy(take_field(data, &mut pos, true).ok_or_else(truncated)?).into_owned();
+    let description = String::from_utf8_lossy(take_field(data, &mut pos, true).ok_or_else(truncated)?).into_owned();
+    // Skip width, height, depth, and palette size
+    pos += 16;
+    let bytes = take_field(data, &mut pos, true).ok_or_else(truncated)?.to_vec();
+    Ok(Picture { mime, picture_type: picture_type.min(255) as u8, description, data: bytes })
+}
+
+fn flac_picture_block(picture: &Picture) -> Vec<u8> {
+    let mut out = (picture.picture_type as u32).to_be_bytes().to_vec();
+    for field in [picture.mime.as_bytes(), picture.description.as_bytes()] {
+        out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        out.extend_from_slice(field);
+    }
+    // Dimensions, depth, and palette size are optional; zero means unknown
+    out.extend_from_slice(&[0; 16]);
+    out.extend_from_slice(&(picture.data.len() as u32).to_be_bytes());
+    out.extend_from_slice(&picture.data);
+    out
+}
+
+/// A FLAC metadata block type and body
+type FlacBlock<'a> = (u8, &'a [u8]);
+
+/// FLAC metadata blocks, and the offset of the audio frames
+fn flac_blocks(data: &[u8]) -> Result<(Vec<FlacBlock<'_>>, usize), String> {
+    let start = id3_len(data).unwrap_or(0);
+    if data.get(start..start + 4) != Some(b"fLaC") {
+        return Err("Not a FLAC file".to_string());
+    }
+    let mut blocks = Vec::new();
+    let mut pos = start + 4;
+    loop {
+        let header = data.get(pos..pos + 4).ok_or("FLAC metadata is truncated")?;
+        let len = read_u32_be(header, 0) as usize & 0xFF_FFFF;
+        let body = data.get(pos + 4..pos + 4 + len).ok_or("FLAC metadata is truncated")?;
+        blocks.push((header[0] & 0x7F, body));
+        pos += 4 + len;
+        if header[0] & 0x80 != 0 {
+            return Ok((blocks, pos));
+        }
+    }
+}
+
+/// Tags of an audio file in the format it carries them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AudioTags {
+    Id3(Id3Tag),
+    /// FLAC or Ogg Vorbis comments, with FLAC PICTURE blocks or
+    /// METADATA_BLOCK_PICTURE comments decoded into `pictures`
+    Vorbis { comments: VorbisComments, pictures: Vec<Picture> },
+}
+
+#[cfg(feature = "audio")]
+fn read_ogg_comments(data: &[u8]) -> Result<Option<AudioTags>, String> {
+    let pages = read_pages(data);
+    let Some(first) = pages.first() else {
+        return Ok(None);
+    };
+    let packets = read_packets(&pages, first.serial);
+    let Some(packet) = packets.get(1) else {
+        return Ok(None);
+    };
+    let body = if packet.data.starts_with(b"\x03vorbis") {
+        &packet.data[7..]
+    } else if packet.data.starts_with(b"OpusTags") {
+        &packet.data[8..]
+    } else {
+        return Ok(None);
+    };
+    let mut comments = VorbisComments::parse(body)?;
+    let mut pictures = Vec::new();
+    comments.comments.retain(|(key, value)| {
+        if !key.eq_ignore_ascii_case("METADATA_BLOCK_PICTURE") {
+            return true;
+        }
+        if let Some(picture) = base64_decode(value).ok().and_then(|b| parse_flac_picture(&b).ok()) {
+            pictures.push(picture);
+        }
+        false
+    });
+    Ok(Some(AudioTags::Vorbis { comments, pictures }))
+}
+
+/// Read the tags of an MP3 (or any ID3v2-prefixed), FLAC, or Ogg file
+///
+/// Returns `None` when the file has no tags.
+pub fn read_audio_tags(data: &[u8]) -> Result<Option<AudioTags>, String> {
+    #[cfg(feature = "audio")]
+    if data.starts_with(b"OggS") {
+        return read_ogg_comments(data);
+    }
+    if let Ok((blocks, _)) = flac_blocks(data) {
+        let mut comments = None;
+        let mut pictures = Vec::new();
+        for (kind, body) in blocks {
+            match kind {
+                FLAC_VORBIS_COMMENT => comments = Some(VorbisComments::parse(body)?),
+                FLAC_PICTURE => pictures.push(parse_flac_picture(body)?),
+                _ => {}
+            }
+        }
+        if comments.is_some() || !pictures.is_empty() {
+            return Ok(Some(AudioTags::Vorbis { comments: comments.unwrap_or_default(), pictures }));
+        }
+    }
+    Ok(Id3Tag::parse(data)?.map(AudioTags::Id3))
+}
+
+impl AudioTags {
+    /// Empty tags of the kind a file takes: Vorbis comments for FLAC,
+    /// ID3v2 otherwise
+    pub fn empty_for(file: &[u8]) -> AudioTags {
+        if flac_blocks(file).is_ok() {
+            AudioTags::Vorbis { comments: VorbisComments::default(), pictures: Vec::new() }
+        } else {
+            AudioTags::Id3(Id3Tag { version: 4, frames: Vec::new() })
+        }
+    }
+
+    /// The frame ID or field name a common field name maps to; other
+    /// names are used as-is
+    fn key(&self, field: &str) -> String {
+        let mapped = FIELDS.iter().find(|(name, _, _)| *name == field).map(|&(_, id3, vorbis)| match self {
+            AudioTags::Id3(_) => id3,
+            AudioTags::Vorbis { .. } => vorbis,
+        });
+        mapped.unwrap_or(field).to_string()
+    }
+
+    /// Value of a common field (`title`, `artist`, `album`, `albumArtist`,
+    /// `date`, `track`, `disc`, `genre`, `composer`, `comment`), or of a raw
+    /// ID3 frame ID or Vorbis field name
+    pub fn get(&self, field: &str) -> Option<String> {
+        let key = self.key(field);
+        match self {
+            AudioTags::Id3(tag) if key == "COMM" => tag.comment(),
+            AudioTags::Id3(tag) => tag.text(&key),
+            AudioTags::Vorbis { comments, .. } => {
+                let values = comments.get_all(&key);
+                (!values.is_empty()).then(|| values.join("; "))
+            }
+        }
+    }
+
+    pub fn set(&mut self, field: &str, value: &str) {
+        let key = self.key(field);
+        match self {
+            AudioTags::Id3(tag) if key == "COMM" => tag.set_comment(value),
+            AudioTags::Id3(tag) => tag.set_text(&key, value),
+            AudioTags::Vorbis { comments, .. } => comments.set(&key, value),
+        }
+    }
+
+    /// Remove a field; returns whether it existed
+    pub fn remove(&mut self, field: &str) -> bool {
+        let key = self.key(field);
+        match self {
+            AudioTags::Id3(tag) => tag.remove(&key),
+            AudioTags::Vorbis { comments, .. } => comments.remove(&key),
+        }
+    }
+
+    /// Every text field as (name, value), in file order
+    pub fn entries(&self) -> Vec<(String, String)> {
+        match self {
+            AudioTags::Id3(tag) => {
+                let mut entries: Vec<(String, String)> = tag
+                    .frames
+                    .iter()
+                    .filter(|f| f.id.starts_with('T') && f.id != "TXXX")
+                    .filter_map(|f| Some((f.id.clone(), tag.text(&f.id)?)))
+                    .collect();
+                entries.extend(tag.comment().map(|c| ("COMM".to_string(), c)));
+                entries.extend(tag.user_text());
+                entries
+            }
+            AudioTags::Vorbis { comments, .. } => comments.comments.clone(),
+        }
+    }
+
+    pub fn pictures(&self) -> Vec<Picture> {
+        match self {
+            AudioTags::Id3(tag) => tag.pictures(),
+            AudioTags::Vorbis { pictures, .. } => pictures.clone(),
+        }
+    }
+
+    /// Attach a picture, replacing any of the same picture type
+    pub fn set_picture(&mut self, picture: Picture) {
+        match self {
+            AudioTags::Id3(tag) => tag.set_picture(&picture),
+            AudioTags::Vorbis { pictures, .. } => {
+                pictures.retain(|p| p.picture_type != picture.picture_type);
+                pictures.push(picture);
+            }
+        }
+    }
+
+    pub fn remove_pictures(&mut self) {
+        match self {
+            AudioTags::Id3(tag) => {
+                tag.remove("APIC");
+            }
+            AudioTags::Vorbis { pictures, .. } => pictures.clear(),
+        }
+    }
+
+    /// Write the tags into a file, replacing its existing tags
+    ///
+    /// ID3v2 tags can be written to any file; Vorbis comments only into FLAC.
+    pub fn write(&self, file: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            AudioTags::Id3(tag) => Ok(write_id3(file, tag)),
+            AudioTags::Vorbis { comments, pictures } => {
+                let (blocks, audio) = flac_blocks(file).map_err(|_| "Vorbis comments can only be written into FLAC files".to_string())?;
+                let mut kept: Vec<(u8, Vec<u8>)> = blocks
+                    .into_iter()
+                    .filter(|(kind, _)| !matches!(*kind, FLAC_VORBIS_COMMENT | FLAC_PICTURE | FLAC_PADDING))
+                    .map(|(kind, body)| (kind, body.to_vec()))
+                    .collect();
+                kept.push((FLAC_VORBIS_COMMENT, comments.to_bytes()));
+                kept.extend(pictures.iter().map(|p| (FLAC_PICTURE, flac_picture_block(p))));
+
+                let start = id3_len(file).unwrap_or(0);
+                let mut out = file[..start + 4].to_vec();
+                for (i, (kind, body)) in kept.iter().enumerate() {
+                    if body.len() > 0xFF_FFFF {
+                        return Err("FLAC metadata block exceeds 16 MiB".to_string());
+                    }
+                    let last = if i + 1 == kept.len() { 0x80 } else { 0 };
+                    out.push(kind | last);
+                    out.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+                    out.extend_from_slice(body);
+                }
+                out.extend_from_slice(&file[audio..]);
+                Ok(out)
+            }
+        }
+    }
+}
+
+/// The front cover of a tagged audio file, or else its first picture;
+/// Matroska and WebM files give their attached cover image
+pub fn cover_art(data: &[u8]) -> Option<Picture> {
+    #[cfg(feature = "video")]
+    if data.starts_with(&EBML_MAGIC) {
+        return read_matroska(data).ok()?.cover_art();
+    }
+    let pictures = read_audio_tags(data).ok()??.pictures();
+    let front = pictures.iter().position(|p| p.picture_type == PICTURE_FRONT_COVER).unwrap_or(0);
+    pictures.into_iter().nth(front)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_v23(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn flac(blocks: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut out = b"fLaC".to_vec();
+        for (i, (kind, body)) in blocks.iter().enumerate() {
+            out.push(kind | if i + 1 == blocks.len() { 0x80 } else { 0 });
+            out.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+            out.extend_from_slice(body);
+        }
+        out.extend_from_slice(b"\xFF\xF8audio");
+        out
+    }
+
+    #[test]
+    fn test_read_id3v23() {
+        let mut body = frame_v23(b"TIT2", b"\x01\xFF\xFEH\0i\0");
+        body.extend(frame_v23(b"TPE1", b"\0Ann\0Bo"));
+        body.extend(frame_v23(b"TYER", b"\x001999"));
+        body.extend(frame_v23(b"COMM", b"\0engdesc\0Nice"));
+        body.extend(frame_v23(b"APIC", b"\0image/png\0\x03cover\0\x89PNG\xFF\x00"));
+        body.extend([0; 16]);
+        let mut file = b"ID3\x03\x00\x00".to_vec();
+        file.extend_from_slice(&write_synchsafe(body.len()));
+        file.extend_from_slice(&body);
+        file.extend_from_slice(b"\xFF\xFBmp3");
+
+        let tags = read_audio_tags(&file).unwrap().unwrap();
+        assert_eq!(tags.get("title").as_deref(), Some("Hi"));
+        assert_eq!(tags.get("artist").as_deref(), Some("Ann; Bo"));
+        assert_eq!(tags.get("date").as_deref(), Some("1999"));
+        assert_eq!(tags.get("comment").as_deref(), Some("Nice"));
+        let cover = cover_art(&file).unwrap();
+        assert_eq!((cover.mime.as_str(), cover.description.as_str()), ("image/png", "cover"));
+        assert_eq!(cover.data, b"\x89PNG\xFF\x00");
+    }
+
+    #[test]
+    fn test_unsynchronized_v22() {
+        // v2.2 three-letter frames; the tag-wide unsynchronization turns
+        // the picture's 0xFF 0x00 0xD8 back into 0xFF 0xD8
+        let mut body = b"TT2\0\0\x04\0Hey".to_vec();
+        body.extend_from_slice(b"PIC\0\0\x09\0JPG\x03\0\xFF\x00\xD8\xFF\x00");
+        let mut file = b"ID3\x02\x00\x80".to_vec();
+        file.extend_from_slice(&write_synchsafe(body.len()));
+        file.extend_from_slice(&body);
+
+        let tag = Id3Tag::parse(&file).unwrap().unwrap();
+        assert_eq!(tag.text("TIT2").as_deref(), Some("Hey"));
+        let pictures = tag.pictures();
+        assert_eq!(pictures[0].mime, "image/jpeg");
+        assert_eq!(pictures[0].data, [0xFF, 0xD8, 0xFF]);
+    }
+
+    #[test]
+    fn test_edit_id3() {
+        let mp3 = b"\xFF\xFBaudio";
+        let mut tags = AudioTags::empty_for(mp3);
+        tags.set("title", "Über");
+        tags.set("comment", "first");
+        tags.set("comment", "second");
+        tags.set_picture(Picture { mime: "image/jpeg".into(), picture_type: 3, description: String::new(), data: vec![1, 2] });
+        let file = tags.write(mp3).unwrap();
+        assert!(file.ends_with(mp3));
+
+        let mut read = read_audio_tags(&file).unwrap().unwrap();
+        assert_eq!(read.get("title").as_deref(), Some("Über"));
+        assert_eq!(read.get("comment").as_deref(), Some("second"));
+        assert_eq!(read.pictures()[0].data, [1, 2]);
+        assert!(read.remove("title"));
+        read.remove("comment");
+        read.remove_pictures();
+        // Removing every frame strips the tag entirely
+        assert_eq!(read.write(&file).unwrap(), mp3);
+    }
+
+    #[test]
+    fn test_flac_comments() {
+        let comments = VorbisComments { vendor: "ref".into(), comments: vec![("Title".into(), "A".into()), ("ARTIST".into(), "B".into())] };
+        let picture = Picture { mime: "image/png".into(), picture_type: 3, description: "d".into(), data: vec![9; 5] };
+        let file = flac(&[(0, vec![0; 34]), (FLAC_VORBIS_COMMENT, comments.to_bytes()), (FLAC_PADDING, vec![0; 8])]);
+
+        let mut tags = read_audio_tags(&file).unwrap().unwrap();
+        assert_eq!(tags.get("title").as_deref(), Some("A"));
+        tags.set("artist", "C");
+        tags.set("TRACKNUMBER", "7");
+        tags.set_picture(picture.clone());
+        let written = tags.write(&file).unwrap();
+        assert!(written.ends_with(b"\xFF\xF8audio"));
+
+        let read = read_audio_tags(&written).unwrap().unwrap();
+        assert_eq!(read.get("artist").as_deref(), Some("C"));
+        assert_eq!(read.get("track").as_deref(), Some("7"));
+        assert_eq!(cover_art(&written), Some(picture));
+        let (blocks, _) = flac_blocks(&written).unwrap();
+        assert_eq!(blocks.iter().map(|b| b.0).collect::<Vec<_>>(), [0, FLAC_VORBIS_COMMENT, FLAC_PICTURE]);
+    }
+
+    #[test]
+    #[cfg(feature = "audio")]
+    fn test_ogg_comments() {
+        use crate::audio::ogg::tests::page;
+        let comments = VorbisComments { vendor: "v".into(), comments: vec![("ALBUM".into(), "X".into())] };
+        let mut header = b"\x03vorbis".to_vec();
+        header.extend(comments.to_bytes());
+        header.push(1);
+        let mut file = page(2, 0, 7, 0, &[4], b"\x01abc");
+        file.extend(page(0, 0, 7, 1, &[header.len() as u8], &header));
+
+        let tags = read_audio_tags(&file).unwrap().unwrap();
+        assert_eq!(tags.get("album").as_deref(), Some("X"));
+        assert!(tags.write(&file).is_err());
+    }
+}
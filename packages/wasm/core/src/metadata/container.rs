@@ -0,0 +1,221 @@
+//! Locating metadata blocks inside JPEG, PNG, RIFF and ISOBMFF containers
+
+use alloc::vec::Vec;
+
+/// PNG file signature
+pub const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Identifier preceding TIFF data in a JPEG APP1 EXIF segment
+pub const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// A JPEG marker segment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JpegSegment<'a> {
+    pub marker: u8,
+    /// Offset of the 0xFF marker byte in the file
+    pub offset: usize,
+    /// Payload after the length field
+    pub data: &'a [u8],
+}
+
+/// Marker segments of a JPEG file, up to start of scan
+pub fn jpeg_segments(data: &[u8]) -> Vec<JpegSegment<'_>> {
+    let mut segments = Vec::new();
+    if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+        return segments;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            break;
+        }
+        let marker = data[pos + 1];
+        if marker == 0xFF {
+            // Fill byte
+            pos += 1;
+            continue;
+        }
+        if marker == 0xD8 || marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+        if marker == 0xD9 {
+            break;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        if len < 2 || pos + 2 + len > data.len() {
+            break;
+        }
+        segments.push(JpegSegment {
+            marker,
+            offset: pos,
+            data: &data[pos + 4..pos + 2 + len],
+        });
+        if marker == 0xDA {
+            break;
+        }
+        pos += 2 + len;
+    }
+    segments
+}
+
+/// A PNG chunk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PngChunk<'a> {
+    pub kind: [u8; 4],
+    /// Offset of the length field in the file
+    pub offset: usize,
+    pub data: &'a [u8],
+}
+
+/// Chunks of a PNG file, in order
+pub fn png_chunks(data: &[u8]) -> Vec<PngChunk<'_>> {
+    let mut chunks = Vec::new();
+    if data.len() < 8 || data[..8] != PNG_SIGNATURE {
+        return chunks;
+    }
+
+    let mut pos = 8;
+    while pos + 12 <= data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let kind = [data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]];
+        let end = match (pos + 8).checked_add(len) {
+            Some(end) if end + 4 <= data.len() => end,
+            _ => break,
+        };
+        chunks.push(PngChunk {
+            kind,
+            offset: pos,
+            data: &data[pos + 8..end],
+        });
+        if &kind == b"IEND" {
+            break;
+        }
+        pos = end + 4;
+    }
+    chunks
+}
+
+/// A RIFF chunk (WebP, WAV, AVI)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RiffChunk<'a> {
+    pub fourcc: [u8; 4],
+    pub data: &'a [u8],
+}
+
+/// Top-level chunks of a RIFF file with the given form type (e.g. `WEBP`)
+pub fn riff_chunks<'a>(data: &'a [u8], form: &[u8; 4]) -> Vec<RiffChunk<'a>> {
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != form {
+        return Vec::new();
+    }
+    riff_sub_chunks(&data[12..])
+}
+
+/// A run of RIFF chunks with no file header, such as the payload of a WebP `ANMF` chunk
+pub fn riff_sub_chunks(data: &[u8]) -> Vec<RiffChunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let fourcc = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
+        let len = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+        let Some(chunk) = data.get(pos + 8..(pos + 8).saturating_add(len)) else { break };
+        chunks.push(RiffChunk { fourcc, data: chunk });
+        // Chunks are padded to even length
+        pos += 8 + len + (len & 1);
+    }
+    chunks
+}
+
+/// An ISO base media file format box (MP4, MOV, HEIF, AVIF)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IsoBox<'a> {
+    pub kind: [u8; 4],
+    /// Offset of the size field within the data that was walked
+    pub offset: usize,
+    /// Payload after the header; `uuid` boxes keep their 16-byte user type
+    pub data: &'a [u8],
+}
+
+/// Boxes laid out back to back in a file or in a container box's payload
+///
+/// Walking stops at the first box that does not fit in `data`.
+pub fn iso_boxes(data: &[u8]) -> Vec<IsoBox<'_>> {
+    let mut boxes = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let size = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as u64;
+        let kind = [data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]];
+        let (header, size) = match size {
+            // Extends to the end of the enclosing data
+            0 => (8, (data.len() - pos) as u64),
+            1 => match data.get(pos + 8..pos + 16) {
+                Some(large) => (16, u64::from_be_bytes(large.try_into().unwrap_or_default())),
+                None => break,
+            },
+            _ => (8, size),
+        };
+        if size < header as u64 || size > (data.len() - pos) as u64 {
+            break;
+        }
+        let end = pos + size as usize;
+        boxes.push(IsoBox { kind, offset: pos, data: &data[pos + header..end] });
+        pos = end;
+    }
+    boxes
+}
+
+/// Find the TIFF-structured EXIF block in a JPEG, PNG, WebP, or bare TIFF/EXIF buffer
+pub fn find_exif(data: &[u8]) -> Option<&[u8]> {
+    if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        return Some(data);
+    }
+    if let Some(tiff) = data.strip_prefix(EXIF_HEADER) {
+        return Some(tiff);
+    }
+    if let Some(segment) = jpeg_segments(data)
+        .into_iter()
+        .find(|s| s.marker == 0xE1 && s.data.starts_with(EXIF_HEADER))
+    {
+        return Some(&segment.data[EXIF_HEADER.len()..]);
+    }
+    if let Some(chunk) = riff_chunks(data, b"WEBP").into_iter().find(|c| &c.fourcc == b"EXIF") {
+        return Some(chunk.data.strip_prefix(EXIF_HEADER).unwrap_or(chunk.data));
+    }
+    png_chunks(data)
+        .into_iter()
+        .find(|c| &c.kind == b"eXIf")
+        .map(|c| c.data.strip_prefix(EXIF_HEADER).unwrap_or(c.data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_exif_in_jpeg() {
+        let tiff = b"II\x2a\0\x08\0\0\0";
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&((2 + EXIF_HEADER.len() + tiff.len()) as u16).to_be_bytes());
+        jpeg.extend_from_slice(EXIF_HEADER);
+        jpeg.extend_from_slice(tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+
+        let segments = jpeg_segments(&jpeg);
+        assert_eq!(segments.iter().map(|s| s.marker).collect::<Vec<_>>(), [0xE0, 0xE1]);
+        assert_eq!(find_exif(&jpeg), Some(&tiff[..]));
+        assert_eq!(find_exif(b"not an image"), None);
+    }
+
+    #[test]
+    fn test_iso_boxes() {
+        let mut data = b"\0\0\0\x0cftypavif".to_vec();
+        data.extend_from_slice(b"\0\0\0\x01free\0\0\0\0\0\0\0\x12ab");
+        data.extend_from_slice(b"\0\0\0\0mdat12345");
+        let boxes = iso_boxes(&data);
+        let summary: Vec<_> = boxes.iter().map(|b| (&b.kind, b.offset, b.data)).collect();
+        assert_eq!(summary, [(b"ftyp", 0, &b"avif"[..]), (b"free", 12, b"ab"), (b"mdat", 30, b"12345")]);
+        // A box running past the end stops the walk
+        assert_eq!(iso_boxes(b"\0\0\0\x10moov\0\0").len(), 0);
+    }
+}
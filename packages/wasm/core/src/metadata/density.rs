@@ -0,0 +1,134 @@
+//! Physical pixel density (DPI) in BMP, PNG, JPEG, and EXIF
+
+use super::container::{jpeg_segments, png_chunks, PNG_SIGNATURE};
+use super::exif::{Exif, Ifd};
+use super::find_exif;
+use crate::checksum::crc32;
+use crate::utils::{read_u32_le, write_u32_le};
+
+const INCH_PER_METER: f32 = 0.0254;
+
+/// Horizontal and vertical dots per inch, when the file records them
+pub fn read_dpi(data: &[u8]) -> Option<(f32, f32)> {
+    let container = if data.starts_with(b"BM") && data.len() >= 46 {
+        let (x, y) = (read_u32_le(data, 38), read_u32_le(data, 42));
+        (x > 0 && y > 0).then_some((x as f32 * INCH_PER_METER, y as f32 * INCH_PER_METER))
+    } else if let Some(phys) = png_chunks(data).into_iter().find(|c| &c.kind == b"pHYs") {
+        let d = phys.data;
+        // Unit 1 is meters; unit 0 only gives an aspect ratio
+        (d.len() >= 9 && d[8] == 1).then(|| {
+            let x = u32::from_be_bytes([d[0], d[1], d[2], d[3]]);
+            let y = u32::from_be_bytes([d[4], d[5], d[6], d[7]]);
+            (x as f32 * INCH_PER_METER, y as f32 * INCH_PER_METER)
+        })
+    } else if let Some(app0) = jpeg_segments(data)
+        .into_iter()
+        .find(|s| s.marker == 0xE0 && s.data.starts_with(b"JFIF\0") && s.data.len() >= 12)
+    {
+        let d = app0.data;
+        let x = u16::from_be_bytes([d[8], d[9]]) as f32;
+        let y = u16::from_be_bytes([d[10], d[11]]) as f32;
+        match d[7] {
+            1 => Some((x, y)),
+            2 => Some((x * 2.54, y * 2.54)),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    container.or_else(|| {
+        let exif = Exif::parse(find_exif(data)?).ok()?;
+        let x = exif.get(Ifd::Primary, 0x011A)?.as_f64(0)? as f32;
+        let y = exif.get(Ifd::Primary, 0x011B)?.as_f64(0)? as f32;
+        // ResolutionUnit: 2 = inch (default), 3 = centimeter
+        match exif.get(Ifd::Primary, 0x0128).and_then(|u| u.as_u32(0)).unwrap_or(2) {
+            3 => Some((x * 2.54, y * 2.54)),
+            2 => Some((x, y)),
+            _ => None,
+        }
+    })
+}
+
+/// Rewrite the pixel density of a BMP, PNG, or JPEG file without re-encoding
+///
+/// PNG gets a `pHYs` chunk and JPEG a JFIF APP0 density, replacing or
+/// inserting them as needed.
+pub fn set_dpi(data: &[u8], dpi_x: f32, dpi_y: f32) -> Result<Vec<u8>, String> {
+    if !(dpi_x > 0.0 && dpi_y > 0.0) {
+        return Err(format!("Invalid DPI: {}x{}", dpi_x, dpi_y));
+    }
+    let ppm = |dpi: f32| (dpi / INCH_PER_METER).round() as u32;
+
+    if data.starts_with(b"BM") && data.len() >= 46 {
+        let mut output = data.to_vec();
+        write_u32_le(&mut output, 38, ppm(dpi_x));
+        write_u32_le(&mut output, 42, ppm(dpi_y));
+        return Ok(output);
+    }
+
+    let chunks = png_chunks(data);
+    if !chunks.is_empty() {
+        let mut phys = b"pHYs".to_vec();
+        phys.extend_from_slice(&ppm(dpi_x).to_be_bytes());
+        phys.extend_from_slice(&ppm(dpi_y).to_be_bytes());
+        phys.push(1);
+        let crc = crc32(&phys);
+
+        let mut output = PNG_SIGNATURE.to_vec();
+        for c in chunks.iter().filter(|c| &c.kind != b"pHYs") {
+            output.extend_from_slice(&data[c.offset..c.offset + 12 + c.data.len()]);
+            // pHYs must precede IDAT; placing it right after IHDR is always valid
+            if &c.kind == b"IHDR" {
+                output.extend_from_slice(&9u32.to_be_bytes());
+                output.extend_from_slice(&phys);
+                output.extend_from_slice(&crc.to_be_bytes());
+            }
+        }
+        return Ok(output);
+    }
+
+    let segments = jpeg_segments(data);
+    if !segments.is_empty() {
+        let density = |dpi: f32| (dpi.round().clamp(1.0, 65535.0) as u16).to_be_bytes();
+        let mut output = data.to_vec();
+        match segments.iter().find(|s| s.marker == 0xE0 && s.data.starts_with(b"JFIF\0") && s.data.len() >= 12) {
+            Some(app0) => {
+                let at = app0.offset + 4;
+                output[at + 7] = 1; // Dots per inch
+                output[at + 8..at + 10].copy_from_slice(&density(dpi_x));
+                output[at + 10..at + 12].copy_from_slice(&density(dpi_y));
+            }
+            None => {
+                let mut app0 = vec![0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0, 1, 2, 1];
+                app0.extend_from_slice(&density(dpi_x));
+                app0.extend_from_slice(&density(dpi_y));
+                app0.extend_from_slice(&[0, 0]); // No thumbnail
+                output.splice(2..2, app0);
+            }
+        }
+        return Ok(output);
+    }
+
+    Err("Unsupported format for DPI".to_string())
+}
+
+#[cfg(all(test, feature = "bmp"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_bmp_and_jpeg() {
+        let bmp = crate::bmp::encode_bmp(1, 1, &[0, 0, 0, 255]).unwrap();
+        let (x, _) = read_dpi(&bmp).unwrap();
+        assert!((x - 72.0).abs() < 0.1);
+        let bmp = set_dpi(&bmp, 300.0, 300.0).unwrap();
+        let (x, y) = read_dpi(&bmp).unwrap();
+        assert!((x - 300.0).abs() < 0.1 && (y - 300.0).abs() < 0.1);
+
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9];
+        assert_eq!(read_dpi(&jpeg), None);
+        let jpeg = set_dpi(&jpeg, 300.0, 150.0).unwrap();
+        assert_eq!(read_dpi(&jpeg), Some((300.0, 150.0)));
+    }
+}
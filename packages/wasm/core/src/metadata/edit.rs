@@ -0,0 +1,168 @@
+//! Writing EXIF into JPEG files and stripping metadata
+
+use super::container::{jpeg_segments, png_chunks, EXIF_HEADER, PNG_SIGNATURE};
+use super::exif::{Exif, Ifd};
+
+/// Largest APP1 payload (segment length is a u16 including itself)
+const MAX_APP1_PAYLOAD: usize = 65533;
+
+fn is_exif_segment(marker: u8, data: &[u8]) -> bool {
+    marker == 0xE1 && data.starts_with(EXIF_HEADER)
+}
+
+/// Replace (or insert) the EXIF APP1 segment of a JPEG
+///
+/// The new segment goes where the old one was, or after SOI/JFIF when the
+/// file had none.
+pub fn write_jpeg_exif(jpeg: &[u8], exif: &Exif) -> Result<Vec<u8>, String> {
+    let segments = jpeg_segments(jpeg);
+    if segments.is_empty() {
+        return Err("Not a JPEG file".to_string());
+    }
+
+    let tiff = exif.to_bytes();
+    let payload_len = EXIF_HEADER.len() + tiff.len();
+    if payload_len > MAX_APP1_PAYLOAD {
+        return Err(format!("EXIF too large for a JPEG segment: {} bytes", payload_len));
+    }
+    let mut app1 = vec![0xFF, 0xE1];
+    app1.extend_from_slice(&((payload_len + 2) as u16).to_be_bytes());
+    app1.extend_from_slice(EXIF_HEADER);
+    app1.extend_from_slice(&tiff);
+
+    let insert_at = segments
+        .iter()
+        .find(|s| is_exif_segment(s.marker, s.data))
+        .or_else(|| segments.iter().take_while(|s| s.marker == 0xE0).last())
+        .map(|s| if is_exif_segment(s.marker, s.data) { s.offset } else { s.offset + 4 + s.data.len() })
+        .unwrap_or(2);
+
+    let mut output = Vec::with_capacity(jpeg.len() + app1.len());
+    let mut pos = 0;
+    let mut inserted = false;
+    for s in &segments {
+        let end = s.offset + 4 + s.data.len();
+        if !inserted && s.offset >= insert_at {
+            output.extend_from_slice(&jpeg[pos..insert_at]);
+            output.extend_from_slice(&app1);
+            pos = insert_at;
+            inserted = true;
+        }
+        if is_exif_segment(s.marker, s.data) {
+            output.extend_from_slice(&jpeg[pos..s.offset]);
+            pos = end;
+        }
+    }
+    if !inserted {
+        output.extend_from_slice(&jpeg[pos..insert_at]);
+        output.extend_from_slice(&app1);
+        pos = insert_at;
+    }
+    output.extend_from_slice(&jpeg[pos..]);
+    Ok(output)
+}
+
+/// Remove metadata from a JPEG or PNG file without re-encoding pixels
+///
+/// Drops EXIF, XMP, IPTC, comments, and text chunks. The embedded ICC
+/// profile is kept when `keep_color_profile` is set, since removing it
+/// changes how wide-gamut images render. Other formats pass through
+/// unchanged, as they carry no metadata this crate writes.
+pub fn strip_metadata(data: &[u8], keep_color_profile: bool) -> Vec<u8> {
+    let segments = jpeg_segments(data);
+    if !segments.is_empty() {
+        let mut output = Vec::with_capacity(data.len());
+        let mut pos = 0;
+        for s in &segments {
+            let is_icc = s.marker == 0xE2 && s.data.starts_with(b"ICC_PROFILE\0");
+            // APP1 (EXIF/XMP), APP2-APP15 except ICC and Adobe (APP14), and COM
+            let drop = match s.marker {
+                0xE2 => !(is_icc && keep_color_profile),
+                0xEE => false,
+                0xE1..=0xEF | 0xFE => true,
+                _ => false,
+            };
+            if drop {
+                output.extend_from_slice(&data[pos..s.offset]);
+                pos = s.offset + 4 + s.data.len();
+            }
+        }
+        output.extend_from_slice(&data[pos..]);
+        return output;
+    }
+
+    let chunks = png_chunks(data);
+    if !chunks.is_empty() {
+        let mut output = PNG_SIGNATURE.to_vec();
+        for c in &chunks {
+            let drop = match &c.kind {
+                b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME" => true,
+                b"iCCP" => !keep_color_profile,
+                _ => false,
+            };
+            if !drop {
+                output.extend_from_slice(&data[c.offset..c.offset + 12 + c.data.len()]);
+            }
+        }
+        return output;
+    }
+
+    data.to_vec()
+}
+
+/// Remove GPS tags from a JPEG's EXIF, keeping everything else
+pub fn strip_gps(jpeg: &[u8]) -> Result<Vec<u8>, String> {
+    let Some(tiff) = jpeg_segments(jpeg)
+        .into_iter()
+        .find(|s| is_exif_segment(s.marker, s.data))
+        .map(|s| &s.data[EXIF_HEADER.len()..])
+    else {
+        return Ok(jpeg.to_vec());
+    };
+    let mut exif = Exif::parse(tiff)?;
+    exif.remove_ifd(Ifd::Gps);
+    write_jpeg_exif(jpeg, &exif)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::exif::{ExifValue, TAG_ORIENTATION};
+    use crate::metadata::find_exif;
+
+    fn minimal_jpeg() -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x07, b'J', b'F', b'I', b'F', 0]);
+        jpeg.extend_from_slice(&[0xFF, 0xFE, 0x00, 0x04, b'h', b'i']);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_write_then_strip() {
+        let mut exif = Exif::default();
+        exif.set(Ifd::Primary, TAG_ORIENTATION, ExifValue::Short(vec![6]));
+        exif.set(Ifd::Gps, 1, ExifValue::Ascii("N".to_string()));
+        exif.set(Ifd::Gps, 2, ExifValue::Rational(vec![(10, 1), (0, 1), (0, 1)]));
+        exif.set(Ifd::Gps, 3, ExifValue::Ascii("E".to_string()));
+        exif.set(Ifd::Gps, 4, ExifValue::Rational(vec![(20, 1), (0, 1), (0, 1)]));
+
+        let jpeg = write_jpeg_exif(&minimal_jpeg(), &exif).unwrap();
+        let markers: Vec<u8> = jpeg_segments(&jpeg).iter().map(|s| s.marker).collect();
+        assert_eq!(markers, [0xE0, 0xE1, 0xFE, 0xDA]);
+
+        // Rewriting replaces rather than duplicates
+        let again = write_jpeg_exif(&jpeg, &exif).unwrap();
+        assert_eq!(again, jpeg);
+
+        let no_gps = strip_gps(&jpeg).unwrap();
+        let parsed = Exif::parse(find_exif(&no_gps).unwrap()).unwrap();
+        assert_eq!(parsed.orientation(), Some(6));
+        assert!(parsed.gps().is_none());
+
+        let stripped = strip_metadata(&jpeg, true);
+        let markers: Vec<u8> = jpeg_segments(&stripped).iter().map(|s| s.marker).collect();
+        assert_eq!(markers, [0xE0, 0xDA]);
+        assert!(stripped.ends_with(&[0x12, 0x34, 0xFF, 0xD9]));
+    }
+}
@@ -0,0 +1,772 @@
+//! EXIF parsing - TIFF-structured IFDs with the Exif, GPS, and Interop sub-IFDs
+
+use crate::text::{decode_utf16, decode_utf8};
+use std::collections::HashSet;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Sub-IFD pointer tags
+pub const TAG_EXIF_IFD: u16 = 0x8769;
+pub const TAG_GPS_IFD: u16 = 0x8825;
+pub const TAG_INTEROP_IFD: u16 = 0xA005;
+
+pub const TAG_ORIENTATION: u16 = 0x0112;
+
+/// Upper bound on entries per IFD, guarding against corrupt counts
+const MAX_IFD_ENTRIES: usize = 1024;
+
+/// Image file directory an entry belongs to
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Ifd {
+    /// IFD0, describing the main image
+    Primary = 0,
+    /// IFD1, describing the embedded thumbnail
+    Thumbnail = 1,
+    Exif = 2,
+    Gps = 3,
+    Interop = 4,
+}
+
+/// A typed EXIF value
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExifValue {
+    Byte(Vec<u8>),
+    Ascii(String),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    SByte(Vec<i8>),
+    Undefined(Vec<u8>),
+    SShort(Vec<i16>),
+    SLong(Vec<i32>),
+    SRational(Vec<(i32, i32)>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl ExifValue {
+    /// TIFF field type code
+    pub fn type_code(&self) -> u16 {
+        match self {
+            ExifValue::Byte(_) => 1,
+            ExifValue::Ascii(_) => 2,
+            ExifValue::Short(_) => 3,
+            ExifValue::Long(_) => 4,
+            ExifValue::Rational(_) => 5,
+            ExifValue::SByte(_) => 6,
+            ExifValue::Undefined(_) => 7,
+            ExifValue::SShort(_) => 8,
+            ExifValue::SLong(_) => 9,
+            ExifValue::SRational(_) => 10,
+            ExifValue::Float(_) => 11,
+            ExifValue::Double(_) => 12,
+        }
+    }
+
+    /// Number of components
+    pub fn len(&self) -> usize {
+        match self {
+            ExifValue::Byte(v) | ExifValue::Undefined(v) => v.len(),
+            // Count includes the NUL terminator
+            ExifValue::Ascii(s) => s.len() + 1,
+            ExifValue::Short(v) => v.len(),
+            ExifValue::Long(v) => v.len(),
+            ExifValue::Rational(v) => v.len(),
+            ExifValue::SByte(v) => v.len(),
+            ExifValue::SShort(v) => v.len(),
+            ExifValue::SLong(v) => v.len(),
+            ExifValue::SRational(v) => v.len(),
+            ExifValue::Float(v) => v.len(),
+            ExifValue::Double(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Component `i` as a number (rationals are divided out)
+    pub fn as_f64(&self, i: usize) -> Option<f64> {
+        let ratio = |n: f64, d: f64| if d == 0.0 { None } else { Some(n / d) };
+        match self {
+            ExifValue::Byte(v) | ExifValue::Undefined(v) => v.get(i).map(|&x| x as f64),
+            ExifValue::Ascii(_) => None,
+            ExifValue::Short(v) => v.get(i).map(|&x| x as f64),
+            ExifValue::Long(v) => v.get(i).map(|&x| x as f64),
+            ExifValue::Rational(v) => v.get(i).and_then(|&(n, d)| ratio(n as f64, d as f64)),
+            ExifValue::SByte(v) => v.get(i).map(|&x| x as f64),
+            ExifValue::SShort(v) => v.get(i).map(|&x| x as f64),
+            ExifValue::SLong(v) => v.get(i).map(|&x| x as f64),
+            ExifValue::SRational(v) => v.get(i).and_then(|&(n, d)| ratio(n as f64, d as f64)),
+            ExifValue::Float(v) => v.get(i).map(|&x| x as f64),
+            ExifValue::Double(v) => v.get(i).copied(),
+        }
+    }
+
+    /// Component `i` as an unsigned integer (integral types only)
+    pub fn as_u32(&self, i: usize) -> Option<u32> {
+        match self {
+            ExifValue::Byte(v) | ExifValue::Undefined(v) => v.get(i).map(|&x| x as u32),
+            ExifValue::Short(v) => v.get(i).map(|&x| x as u32),
+            ExifValue::Long(v) => v.get(i).copied(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ExifValue::Ascii(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// One tag of an IFD
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExifEntry {
+    pub ifd: Ifd,
+    pub tag: u16,
+    pub value: ExifValue,
+}
+
+/// Decoded GPS position in decimal degrees
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters relative to sea level, when recorded
+    pub altitude: Option<f64>,
+}
+
+/// Parsed EXIF data
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Exif {
+    pub little_endian: bool,
+    pub entries: Vec<ExifEntry>,
+}
+
+/// Byte-order aware reader over a TIFF block
+struct Reader<'a> {
+    data: &'a [u8],
+    le: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Option<[u8; N]> {
+        let mut b: [u8; N] = self.data.get(offset..offset.checked_add(N)?)?.try_into().ok()?;
+        if !self.le {
+            b.reverse();
+        }
+        Some(b)
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        self.bytes(offset).map(u16::from_le_bytes)
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+
+    fn value(&self, kind: u16, count: usize, offset: usize) -> Option<ExifValue> {
+        let raw = |size: usize| self.data.get(offset..offset.checked_add(size.checked_mul(count)?)?);
+        let each = |size: usize| (0..count).map(move |i| offset + i * size);
+        Some(match kind {
+            1 => ExifValue::Byte(raw(1)?.to_vec()),
+            2 => {
+                let bytes = raw(1)?;
+                let end = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+                ExifValue::Ascii(String::from_utf8_lossy(&bytes[..end]).into_owned())
+            }
+            3 => ExifValue::Short(each(2).map(|o| self.u16(o)).collect::<Option<_>>()?),
+            4 => ExifValue::Long(each(4).map(|o| self.u32(o)).collect::<Option<_>>()?),
+            5 => ExifValue::Rational(each(8).map(|o| Some((self.u32(o)?, self.u32(o + 4)?))).collect::<Option<_>>()?),
+            6 => ExifValue::SByte(raw(1)?.iter().map(|&b| b as i8).collect()),
+            7 => ExifValue::Undefined(raw(1)?.to_vec()),
+            8 => ExifValue::SShort(each(2).map(|o| self.u16(o).map(|v| v as i16)).collect::<Option<_>>()?),
+            9 => ExifValue::SLong(each(4).map(|o| self.u32(o).map(|v| v as i32)).collect::<Option<_>>()?),
+            10 => ExifValue::SRational(
+                each(8)
+                    .map(|o| Some((self.u32(o)? as i32, self.u32(o + 4)? as i32)))
+                    .collect::<Option<_>>()?,
+            ),
+            11 => ExifValue::Float(each(4).map(|o| self.u32(o).map(f32::from_bits)).collect::<Option<_>>()?),
+            12 => ExifValue::Double(
+                each(8)
+                    .map(|o| self.bytes::<8>(o).map(|b| f64::from_bits(u64::from_le_bytes(b))))
+                    .collect::<Option<_>>()?,
+            ),
+            _ => return None,
+        })
+    }
+}
+
+/// Byte size of one component of a TIFF field type
+pub fn type_size(kind: u16) -> usize {
+    match kind {
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+impl Exif {
+    /// Parse a TIFF-structured EXIF block (starting at the byte-order mark)
+    pub fn parse(tiff: &[u8]) -> Result<Exif, String> {
+        let le = match tiff.get(..2) {
+            Some(b"II") => true,
+            Some(b"MM") => false,
+            _ => return Err("Invalid EXIF byte order".to_string()),
+        };
+        let reader = Reader { data: tiff, le };
+        if reader.u16(2) != Some(42) {
+            return Err("Invalid EXIF TIFF header".to_string());
+        }
+
+        let mut exif = Exif { little_endian: le, entries: Vec::new() };
+        let mut visited = HashSet::new();
+        let ifd0 = reader.u32(4).ok_or("Truncated EXIF header")? as usize;
+        let ifd1 = exif.read_ifd(&reader, ifd0, Ifd::Primary, &mut visited);
+        if let Some(offset) = ifd1.filter(|&o| o != 0) {
+            exif.read_ifd(&reader, offset, Ifd::Thumbnail, &mut visited);
+        }
+        Ok(exif)
+    }
+
+    /// Read one IFD (and its sub-IFDs), returning the next-IFD offset
+    fn read_ifd(&mut self, reader: &Reader, offset: usize, ifd: Ifd, visited: &mut HashSet<usize>) -> Option<usize> {
+        if !visited.insert(offset) {
+            return None;
+        }
+        let count = (reader.u16(offset)? as usize).min(MAX_IFD_ENTRIES);
+
+        for i in 0..count {
+            let at = offset + 2 + i * 12;
+            let (Some(tag), Some(kind), Some(n)) = (reader.u16(at), reader.u16(at + 2), reader.u32(at + 4)) else {
+                break;
+            };
+            let n = n as usize;
+            let size = type_size(kind).saturating_mul(n);
+            let value_offset = if size <= 4 { at + 8 } else { reader.u32(at + 8)? as usize };
+            // Skip malformed entries rather than failing the whole block
+            let Some(value) = reader.value(kind, n, value_offset) else {
+                continue;
+            };
+
+            let sub_ifd = match (ifd, tag) {
+                (Ifd::Primary, TAG_EXIF_IFD) => Some(Ifd::Exif),
+                (Ifd::Primary, TAG_GPS_IFD) => Some(Ifd::Gps),
+                (Ifd::Exif, TAG_INTEROP_IFD) => Some(Ifd::Interop),
+                _ => None,
+            };
+            match (sub_ifd, value.as_u32(0)) {
+                (Some(sub), Some(pointer)) => {
+                    self.read_ifd(reader, pointer as usize, sub, visited);
+                }
+                _ => self.entries.push(ExifEntry { ifd, tag, value }),
+            }
+        }
+
+        reader.u32(offset + 2 + count * 12).map(|o| o as usize)
+    }
+
+    /// Look up a tag in an IFD
+    pub fn get(&self, ifd: Ifd, tag: u16) -> Option<&ExifValue> {
+        self.entries.iter().find(|e| e.ifd == ifd && e.tag == tag).map(|e| &e.value)
+    }
+
+    fn text(&self, ifd: Ifd, tag: u16) -> Option<&str> {
+        self.get(ifd, tag).and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty())
+    }
+
+    /// Orientation (1-8), defaulting to none when absent or out of range
+    pub fn orientation(&self) -> Option<u16> {
+        self.get(Ifd::Primary, TAG_ORIENTATION)
+            .and_then(|v| v.as_u32(0))
+            .filter(|o| (1..=8).contains(o))
+            .map(|o| o as u16)
+    }
+
+    /// Pixel dimensions from the Exif IFD, falling back to IFD0
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        let pair = |ifd, w, h| Some((self.get(ifd, w)?.as_u32(0)?, self.get(ifd, h)?.as_u32(0)?));
+        pair(Ifd::Exif, 0xA002, 0xA003).or_else(|| pair(Ifd::Primary, 0x0100, 0x0101))
+    }
+
+    pub fn make(&self) -> Option<&str> {
+        self.text(Ifd::Primary, 0x010F)
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.text(Ifd::Primary, 0x0110)
+    }
+
+    pub fn lens_model(&self) -> Option<&str> {
+        self.text(Ifd::Exif, 0xA434)
+    }
+
+    pub fn software(&self) -> Option<&str> {
+        self.text(Ifd::Primary, 0x0131)
+    }
+
+    /// Modification timestamp, "YYYY:MM:DD HH:MM:SS"
+    pub fn date_time(&self) -> Option<&str> {
+        self.text(Ifd::Primary, 0x0132)
+    }
+
+    /// Capture timestamp, "YYYY:MM:DD HH:MM:SS"
+    pub fn date_time_original(&self) -> Option<&str> {
+        self.text(Ifd::Exif, 0x9003)
+    }
+
+    /// UserComment text, decoded according to its character code prefix
+    ///
+    /// JIS-coded comments are not supported.
+    pub fn user_comment(&self) -> Option<String> {
+        let bytes = match self.get(Ifd::Exif, 0x9286)? {
+            ExifValue::Undefined(v) | ExifValue::Byte(v) => v,
+            ExifValue::Ascii(s) => return Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+            _ => return None,
+        };
+        let (code, body) = bytes.split_at_checked(8)?;
+        let text = match code {
+            b"UNICODE\0" => decode_utf16(body, !self.little_endian, false).ok()?,
+            b"ASCII\0\0\0" | b"\0\0\0\0\0\0\0\0" => decode_utf8(body, false).ok()?,
+            _ => return None,
+        };
+        let text = text.trim_end_matches('\0').trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    /// Exposure time in seconds
+    pub fn exposure_time(&self) -> Option<f64> {
+        self.get(Ifd::Exif, 0x829A)?.as_f64(0)
+    }
+
+    pub fn f_number(&self) -> Option<f64> {
+        self.get(Ifd::Exif, 0x829D)?.as_f64(0)
+    }
+
+    pub fn iso(&self) -> Option<u32> {
+        self.get(Ifd::Exif, 0x8827)?.as_u32(0)
+    }
+
+    /// Focal length in millimeters
+    pub fn focal_length(&self) -> Option<f64> {
+        self.get(Ifd::Exif, 0x920A)?.as_f64(0)
+    }
+
+    /// GPS position in signed decimal degrees
+    pub fn gps(&self) -> Option<GpsPosition> {
+        let degrees = |tag: u16, ref_tag: u16, negative: char| -> Option<f64> {
+            let v = self.get(Ifd::Gps, tag)?;
+            let deg = v.as_f64(0)? + v.as_f64(1).unwrap_or(0.0) / 60.0 + v.as_f64(2).unwrap_or(0.0) / 3600.0;
+            let sign = match self.get(Ifd::Gps, ref_tag).and_then(|r| r.as_str()) {
+                Some(r) if r.starts_with(negative) => -1.0,
+                _ => 1.0,
+            };
+            Some(deg * sign)
+        };
+        let altitude = self.get(Ifd::Gps, 6).and_then(|v| v.as_f64(0)).map(|a| {
+            let below = self.get(Ifd::Gps, 5).and_then(|v| v.as_u32(0)) == Some(1);
+            if below {
+                -a
+            } else {
+                a
+            }
+        });
+        Some(GpsPosition {
+            latitude: degrees(2, 1, 'S')?,
+            longitude: degrees(4, 3, 'W')?,
+            altitude,
+        })
+    }
+}
+
+/// Embedded JPEG thumbnail referenced by IFD1 of a JPEG/PNG/WebP/TIFF EXIF block
+///
+/// Returns a slice of `data`, avoiding a full decode and resize when a small
+/// preview is enough.
+pub fn extract_exif_thumbnail(data: &[u8]) -> Option<&[u8]> {
+    let tiff = super::find_exif(data)?;
+    let exif = Exif::parse(tiff).ok()?;
+    let offset = exif.get(Ifd::Thumbnail, 0x0201)?.as_u32(0)? as usize;
+    let length = exif.get(Ifd::Thumbnail, 0x0202)?.as_u32(0)? as usize;
+    tiff.get(offset..offset.checked_add(length)?)
+        .filter(|thumb| thumb.starts_with(&[0xFF, 0xD8]))
+}
+
+/// Serialization helpers
+impl ExifValue {
+    /// Append the value's components in the given byte order
+    fn write(&self, out: &mut Vec<u8>, le: bool) {
+        macro_rules! put {
+            ($v:expr) => {{
+                let v = $v;
+                out.extend_from_slice(&if le { v.to_le_bytes() } else { v.to_be_bytes() });
+            }};
+        }
+        match self {
+            ExifValue::Byte(v) | ExifValue::Undefined(v) => out.extend_from_slice(v),
+            ExifValue::Ascii(s) => {
+                out.extend_from_slice(s.as_bytes());
+                out.push(0);
+            }
+            ExifValue::Short(v) => v.iter().for_each(|&x| put!(x)),
+            ExifValue::Long(v) => v.iter().for_each(|&x| put!(x)),
+            ExifValue::Rational(v) => v.iter().for_each(|&(n, d)| {
+                put!(n);
+                put!(d);
+            }),
+            ExifValue::SByte(v) => out.extend(v.iter().map(|&x| x as u8)),
+            ExifValue::SShort(v) => v.iter().for_each(|&x| put!(x)),
+            ExifValue::SLong(v) => v.iter().for_each(|&x| put!(x)),
+            ExifValue::SRational(v) => v.iter().for_each(|&(n, d)| {
+                put!(n);
+                put!(d);
+            }),
+            ExifValue::Float(v) => v.iter().for_each(|&x| put!(x.to_bits())),
+            ExifValue::Double(v) => v.iter().for_each(|&x| put!(x.to_bits())),
+        }
+    }
+
+    fn byte_len(&self) -> usize {
+        type_size(self.type_code()) * self.len()
+    }
+}
+
+impl Exif {
+    /// Insert or replace a tag
+    pub fn set(&mut self, ifd: Ifd, tag: u16, value: ExifValue) {
+        match self.entries.iter_mut().find(|e| e.ifd == ifd && e.tag == tag) {
+            Some(entry) => entry.value = value,
+            None => self.entries.push(ExifEntry { ifd, tag, value }),
+        }
+    }
+
+    /// Remove a tag, returning whether it was present
+    pub fn remove(&mut self, ifd: Ifd, tag: u16) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| !(e.ifd == ifd && e.tag == tag));
+        self.entries.len() != before
+    }
+
+    /// Remove every tag of an IFD (e.g. all GPS data)
+    pub fn remove_ifd(&mut self, ifd: Ifd) {
+        self.entries.retain(|e| e.ifd != ifd);
+    }
+
+    /// Serialize to a TIFF-structured EXIF block
+    ///
+    /// The thumbnail IFD is dropped, since its image data is not retained.
+    /// Sub-IFD pointers are regenerated for the Exif, GPS, and Interop IFDs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let le = self.little_endian;
+        let entries_of = |ifd: Ifd| -> Vec<(u16, ExifValue)> {
+            self.entries.iter().filter(|e| e.ifd == ifd).map(|e| (e.tag, e.value.clone())).collect()
+        };
+
+        let mut primary = entries_of(Ifd::Primary);
+        let mut exif = entries_of(Ifd::Exif);
+        let mut gps = entries_of(Ifd::Gps);
+        let mut interop = entries_of(Ifd::Interop);
+
+        // Pointers are placeholders until offsets are known; sizes don't depend on them
+        if !interop.is_empty() {
+            exif.push((TAG_INTEROP_IFD, ExifValue::Long(vec![0])));
+        }
+        if !exif.is_empty() {
+            primary.push((TAG_EXIF_IFD, ExifValue::Long(vec![0])));
+        }
+        if !gps.is_empty() {
+            primary.push((TAG_GPS_IFD, ExifValue::Long(vec![0])));
+        }
+        for ifd in [&mut primary, &mut exif, &mut gps, &mut interop] {
+            ifd.sort_by_key(|(tag, _)| *tag);
+        }
+
+        let size = |entries: &[(u16, ExifValue)]| {
+            6 + entries.len() * 12
+                + entries
+                    .iter()
+                    .map(|(_, v)| if v.byte_len() > 4 { v.byte_len().next_multiple_of(2) } else { 0 })
+                    .sum::<usize>()
+        };
+        let primary_at = 8;
+        let exif_at = primary_at + size(&primary);
+        let interop_at = exif_at + if exif.is_empty() { 0 } else { size(&exif) };
+        let gps_at = interop_at + if interop.is_empty() { 0 } else { size(&interop) };
+
+        let patch = |entries: &mut [(u16, ExifValue)], tag: u16, offset: usize| {
+            if let Some((_, v)) = entries.iter_mut().find(|(t, _)| *t == tag) {
+                *v = ExifValue::Long(vec![offset as u32]);
+            }
+        };
+        patch(&mut primary, TAG_EXIF_IFD, exif_at);
+        patch(&mut primary, TAG_GPS_IFD, gps_at);
+        patch(&mut exif, TAG_INTEROP_IFD, interop_at);
+
+        let mut out = Vec::with_capacity(gps_at + size(&gps));
+        out.extend_from_slice(if le { b"II\x2a\0" } else { b"MM\0\x2a" });
+        out.extend_from_slice(&if le { 8u32.to_le_bytes() } else { 8u32.to_be_bytes() });
+        for (entries, at) in [(&primary, primary_at), (&exif, exif_at), (&interop, interop_at), (&gps, gps_at)] {
+            if at == primary_at || !entries.is_empty() {
+                write_ifd(&mut out, entries, le);
+            }
+        }
+        out
+    }
+}
+
+/// Append an IFD whose first byte lands at `out.len()`
+fn write_ifd(out: &mut Vec<u8>, entries: &[(u16, ExifValue)], le: bool) {
+    let u16b = |v: u16| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+    let u32b = |v: u32| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+
+    let start = out.len();
+    let mut data_at = start + 6 + entries.len() * 12;
+    let mut data = Vec::new();
+
+    out.extend_from_slice(&u16b(entries.len() as u16));
+    for (tag, value) in entries {
+        out.extend_from_slice(&u16b(*tag));
+        out.extend_from_slice(&u16b(value.type_code()));
+        out.extend_from_slice(&u32b(value.len() as u32));
+
+        let mut bytes = Vec::with_capacity(value.byte_len());
+        value.write(&mut bytes, le);
+        if bytes.len() <= 4 {
+            bytes.resize(4, 0);
+            out.extend_from_slice(&bytes);
+        } else {
+            out.extend_from_slice(&u32b(data_at as u32));
+            if bytes.len() % 2 == 1 {
+                bytes.push(0);
+            }
+            data_at += bytes.len();
+            data.extend(bytes);
+        }
+    }
+    out.extend_from_slice(&[0; 4]); // No next IFD
+    out.extend(data);
+}
+
+/// Standard name of a tag, if known
+pub fn tag_name(ifd: Ifd, tag: u16) -> Option<&'static str> {
+    let name = match ifd {
+        Ifd::Primary | Ifd::Thumbnail => match tag {
+            0x0100 => "ImageWidth",
+            0x0101 => "ImageLength",
+            0x0102 => "BitsPerSample",
+            0x0103 => "Compression",
+            0x0106 => "PhotometricInterpretation",
+            0x010E => "ImageDescription",
+            0x010F => "Make",
+            0x0110 => "Model",
+            0x0111 => "StripOffsets",
+            0x0112 => "Orientation",
+            0x0115 => "SamplesPerPixel",
+            0x011A => "XResolution",
+            0x011B => "YResolution",
+            0x0128 => "ResolutionUnit",
+            0x0131 => "Software",
+            0x0132 => "DateTime",
+            0x013B => "Artist",
+            0x013E => "WhitePoint",
+            0x013F => "PrimaryChromaticities",
+            0x0201 => "JPEGInterchangeFormat",
+            0x0202 => "JPEGInterchangeFormatLength",
+            0x0211 => "YCbCrCoefficients",
+            0x0213 => "YCbCrPositioning",
+            0x8298 => "Copyright",
+            _ => return None,
+        },
+        Ifd::Exif => match tag {
+            0x829A => "ExposureTime",
+            0x829D => "FNumber",
+            0x8822 => "ExposureProgram",
+            0x8827 => "ISOSpeedRatings",
+            0x9000 => "ExifVersion",
+            0x9003 => "DateTimeOriginal",
+            0x9004 => "DateTimeDigitized",
+            0x9010 => "OffsetTime",
+            0x9011 => "OffsetTimeOriginal",
+            0x9012 => "OffsetTimeDigitized",
+            0x9101 => "ComponentsConfiguration",
+            0x9201 => "ShutterSpeedValue",
+            0x9202 => "ApertureValue",
+            0x9203 => "BrightnessValue",
+            0x9204 => "ExposureBiasValue",
+            0x9205 => "MaxApertureValue",
+            0x9206 => "SubjectDistance",
+            0x9207 => "MeteringMode",
+            0x9208 => "LightSource",
+            0x9209 => "Flash",
+            0x920A => "FocalLength",
+            0x927C => "MakerNote",
+            0x9286 => "UserComment",
+            0x9290 => "SubSecTime",
+            0x9291 => "SubSecTimeOriginal",
+            0x9292 => "SubSecTimeDigitized",
+            0xA000 => "FlashpixVersion",
+            0xA001 => "ColorSpace",
+            0xA002 => "PixelXDimension",
+            0xA003 => "PixelYDimension",
+            0xA217 => "SensingMethod",
+            0xA300 => "FileSource",
+            0xA301 => "SceneType",
+            0xA401 => "CustomRendered",
+            0xA402 => "ExposureMode",
+            0xA403 => "WhiteBalance",
+            0xA404 => "DigitalZoomRatio",
+            0xA405 => "FocalLengthIn35mmFilm",
+            0xA406 => "SceneCaptureType",
+            0xA420 => "ImageUniqueID",
+            0xA430 => "CameraOwnerName",
+            0xA431 => "BodySerialNumber",
+            0xA432 => "LensSpecification",
+            0xA433 => "LensMake",
+            0xA434 => "LensModel",
+            0xA435 => "LensSerialNumber",
+            _ => return None,
+        },
+        Ifd::Gps => match tag {
+            0x00 => "GPSVersionID",
+            0x01 => "GPSLatitudeRef",
+            0x02 => "GPSLatitude",
+            0x03 => "GPSLongitudeRef",
+            0x04 => "GPSLongitude",
+            0x05 => "GPSAltitudeRef",
+            0x06 => "GPSAltitude",
+            0x07 => "GPSTimeStamp",
+            0x08 => "GPSSatellites",
+            0x09 => "GPSStatus",
+            0x0A => "GPSMeasureMode",
+            0x0B => "GPSDOP",
+            0x0C => "GPSSpeedRef",
+            0x0D => "GPSSpeed",
+            0x0E => "GPSTrackRef",
+            0x0F => "GPSTrack",
+            0x10 => "GPSImgDirectionRef",
+            0x11 => "GPSImgDirection",
+            0x12 => "GPSMapDatum",
+            0x17 => "GPSDestBearingRef",
+            0x18 => "GPSDestBearing",
+            0x1B => "GPSProcessingMethod",
+            0x1D => "GPSDateStamp",
+            0x1F => "GPSHPositioningError",
+            _ => return None,
+        },
+        Ifd::Interop => match tag {
+            0x01 => "InteroperabilityIndex",
+            0x02 => "InteroperabilityVersion",
+            _ => return None,
+        },
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Big-endian TIFF with Make, Orientation, and a GPS sub-IFD
+    fn sample_tiff() -> Vec<u8> {
+        let mut t = b"MM\0\x2a\0\0\0\x08".to_vec();
+        let entry = |t: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: [u8; 4]| {
+            t.extend_from_slice(&tag.to_be_bytes());
+            t.extend_from_slice(&kind.to_be_bytes());
+            t.extend_from_slice(&count.to_be_bytes());
+            t.extend_from_slice(&value);
+        };
+
+        t.extend_from_slice(&3u16.to_be_bytes());
+        entry(&mut t, 0x010F, 2, 6, 50u32.to_be_bytes());
+        entry(&mut t, TAG_ORIENTATION, 3, 1, [0, 6, 0, 0]);
+        entry(&mut t, TAG_GPS_IFD, 4, 1, 56u32.to_be_bytes());
+        t.extend_from_slice(&[0; 4]);
+        t.extend_from_slice(b"Canon\0");
+
+        t.extend_from_slice(&4u16.to_be_bytes());
+        entry(&mut t, 1, 2, 2, *b"N\0\0\0");
+        entry(&mut t, 2, 5, 3, 110u32.to_be_bytes());
+        entry(&mut t, 3, 2, 2, *b"W\0\0\0");
+        entry(&mut t, 4, 5, 3, 134u32.to_be_bytes());
+        t.extend_from_slice(&[0; 4]);
+        for (n, d) in [(40u32, 1u32), (30, 1), (0, 1), (74, 1), (0, 1), (36, 1)] {
+            t.extend_from_slice(&n.to_be_bytes());
+            t.extend_from_slice(&d.to_be_bytes());
+        }
+        t
+    }
+
+    #[test]
+    fn test_parse_big_endian() {
+        let exif = Exif::parse(&sample_tiff()).unwrap();
+        assert!(!exif.little_endian);
+        assert_eq!(exif.make(), Some("Canon"));
+        assert_eq!(exif.orientation(), Some(6));
+
+        let gps = exif.gps().unwrap();
+        assert!((gps.latitude - 40.5).abs() < 1e-9);
+        assert!((gps.longitude + 74.01).abs() < 1e-9);
+        assert_eq!(gps.altitude, None);
+        assert_eq!(tag_name(Ifd::Gps, 2), Some("GPSLatitude"));
+    }
+
+    #[test]
+    fn test_write_roundtrip() {
+        let mut exif = Exif::parse(&sample_tiff()).unwrap();
+        exif.set(Ifd::Exif, 0x9003, ExifValue::Ascii("2024:05:01 10:00:00".to_string()));
+        exif.set(Ifd::Primary, TAG_ORIENTATION, ExifValue::Short(vec![1]));
+
+        let reparsed = Exif::parse(&exif.to_bytes()).unwrap();
+        assert_eq!(reparsed.orientation(), Some(1));
+        assert_eq!(reparsed.date_time_original(), Some("2024:05:01 10:00:00"));
+        assert_eq!(reparsed.make(), Some("Canon"));
+        assert_eq!(reparsed.gps(), exif.gps());
+
+        exif.remove_ifd(Ifd::Gps);
+        assert!(Exif::parse(&exif.to_bytes()).unwrap().gps().is_none());
+    }
+
+    #[test]
+    fn test_user_comment() {
+        let mut exif = Exif::parse(&sample_tiff()).unwrap();
+        let mut comment = b"UNICODE\0".to_vec();
+        comment.extend(crate::text::encode_utf16("Café 😀\0", true, false));
+        exif.set(Ifd::Exif, 0x9286, ExifValue::Undefined(comment));
+        assert_eq!(Exif::parse(&exif.to_bytes()).unwrap().user_comment().as_deref(), Some("Café 😀"));
+
+        exif.set(Ifd::Exif, 0x9286, ExifValue::Undefined(b"ASCII\0\0\0hello   ".to_vec()));
+        assert_eq!(exif.user_comment().as_deref(), Some("hello"));
+        exif.set(Ifd::Exif, 0x9286, ExifValue::Undefined([0; 16].to_vec()));
+        assert_eq!(exif.user_comment(), None);
+    }
+
+    #[test]
+    fn test_extract_thumbnail() {
+        // IFD0 is empty and links to IFD1, which points at the thumbnail
+        let mut t = b"II\x2a\0\x08\0\0\0".to_vec();
+        t.extend_from_slice(&[0, 0, 14, 0, 0, 0]);
+        t.extend_from_slice(&[2, 0]);
+        t.extend_from_slice(&[0x01, 0x02, 4, 0, 1, 0, 0, 0, 44, 0, 0, 0]);
+        t.extend_from_slice(&[0x02, 0x02, 4, 0, 1, 0, 0, 0, 4, 0, 0, 0]);
+        t.extend_from_slice(&[0; 4]);
+        t.extend_from_slice(&[0xFF, 0xD8, 0xFF, 0xD9]);
+
+        assert_eq!(extract_exif_thumbnail(&t), Some(&[0xFF, 0xD8, 0xFF, 0xD9][..]));
+        assert_eq!(extract_exif_thumbnail(&sample_tiff()), None);
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(Exif::parse(b"XX\0\x2a").is_err());
+        // Self-referencing IFD terminates
+        let mut looped = b"II\x2a\0\x08\0\0\0\0\0\x08\0\0\0".to_vec();
+        looped.extend_from_slice(&[0; 4]);
+        assert!(Exif::parse(&looped).unwrap().entries.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+//! IPTC-IIM extraction from Photoshop image resource blocks
+
+use super::container::jpeg_segments;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Identifier of a JPEG APP13 Photoshop segment
+pub const PHOTOSHOP_HEADER: &[u8] = b"Photoshop 3.0\0";
+/// Image resource ID of the IPTC-NAA record
+const RESOURCE_IPTC: u16 = 0x0404;
+
+/// One IIM dataset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IptcDataset {
+    pub record: u8,
+    pub dataset: u8,
+    pub data: Vec<u8>,
+}
+
+/// Commonly used IPTC application record (2:xx) fields
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Iptc {
+    /// 2:05 Object Name
+    pub title: Option<String>,
+    /// 2:80 By-line
+    pub creators: Vec<String>,
+    /// 2:25 Keywords
+    pub keywords: Vec<String>,
+    /// 2:120 Caption/Abstract
+    pub caption: Option<String>,
+    /// 2:116 Copyright Notice
+    pub copyright: Option<String>,
+    /// 2:55 Date Created, CCYYMMDD
+    pub date_created: Option<String>,
+    /// 2:90 City
+    pub city: Option<String>,
+    /// 2:101 Country/Primary Location Name
+    pub country: Option<String>,
+}
+
+/// Find the IPTC-IIM block in a JPEG's APP13 segment or a raw 8BIM resource stream
+pub fn find_iptc(data: &[u8]) -> Option<&[u8]> {
+    let resources = jpeg_segments(data)
+        .into_iter()
+        .find(|s| s.marker == 0xED && s.data.starts_with(PHOTOSHOP_HEADER))
+        .map(|s| &s.data[PHOTOSHOP_HEADER.len()..])
+        .unwrap_or(data);
+
+    // 8BIM resources: signature, id, padded Pascal name, size, padded data
+    let mut pos = 0;
+    while pos + 12 <= resources.len() && &resources[pos..pos + 4] == b"8BIM" {
+        let id = u16::from_be_bytes([resources[pos + 4], resources[pos + 5]]);
+        let name_len = resources[pos + 6] as usize;
+        let name_total = (name_len + 1).next_multiple_of(2);
+        let size_at = pos + 6 + name_total;
+        let size_bytes = resources.get(size_at..size_at + 4)?;
+        let size = u32::from_be_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]) as usize;
+        let start = size_at + 4;
+        let block = resources.get(start..start.checked_add(size)?)?;
+        if id == RESOURCE_IPTC {
+            return Some(block);
+        }
+        pos = start + size.next_multiple_of(2);
+    }
+    if data.first() == Some(&0x1C) {
+        return Some(data);
+    }
+    None
+}
+
+/// Split an IIM block into datasets
+pub fn parse_iim(data: &[u8]) -> Vec<IptcDataset> {
+    let mut datasets = Vec::new();
+    let mut pos = 0;
+    while pos + 5 <= data.len() && data[pos] == 0x1C {
+        let record = data[pos + 1];
+        let dataset = data[pos + 2];
+        let mut len = u16::from_be_bytes([data[pos + 3], data[pos + 4]]) as usize;
+        pos += 5;
+        if len & 0x8000 != 0 {
+            // Extended dataset: the low bits give the length-of-length
+            let n = len & 0x7FFF;
+            let Some(bytes) = data.get(pos..pos + n).filter(|_| n <= 4) else { break };
+            len = bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+            pos += n;
+        }
+        let Some(value) = data.get(pos..pos + len) else { break };
+        datasets.push(IptcDataset {
+            record,
+            dataset,
+            data: value.to_vec(),
+        });
+        pos += len;
+    }
+    datasets
+}
+
+impl Iptc {
+    /// Collect the common application-record fields
+    pub fn from_datasets(datasets: &[IptcDataset]) -> Iptc {
+        // 1:90 "ESC % G" declares UTF-8; otherwise assume Latin-1
+        let utf8 = datasets
+            .iter()
+            .any(|d| d.record == 1 && d.dataset == 90 && d.data == b"\x1b%G");
+        let text = |bytes: &[u8]| -> String {
+            let s = match std::str::from_utf8(bytes) {
+                Ok(s) if utf8 || s.is_ascii() => s.to_string(),
+                _ => bytes.iter().map(|&b| b as char).collect(),
+            };
+            s.trim_end_matches('\0').trim().to_string()
+        };
+        let all = |id: u8| -> Vec<String> {
+            datasets
+                .iter()
+                .filter(|d| d.record == 2 && d.dataset == id)
+                .map(|d| text(&d.data))
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        let first = |id: u8| all(id).into_iter().next();
+
+        Iptc {
+            title: first(5),
+            creators: all(80),
+            keywords: all(25),
+            caption: first(120),
+            copyright: first(116),
+            date_created: first(55),
+            city: first(90),
+            country: first(101),
+        }
+    }
+}
+
+/// Extract and parse IPTC from a JPEG or Photoshop resource block
+pub fn read_iptc(data: &[u8]) -> Option<Iptc> {
+    let block = find_iptc(data)?;
+    Some(Iptc::from_datasets(&parse_iim(block)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_from_app13() {
+        let mut iim = Vec::new();
+        for (dataset, value) in [(5u8, "Harbor"), (25, "boats"), (25, "fog"), (80, "Grace")] {
+            iim.extend_from_slice(&[0x1C, 2, dataset]);
+            iim.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            iim.extend_from_slice(value.as_bytes());
+        }
+        let mut resource = b"8BIM\x04\x04\0\0".to_vec();
+        resource.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+        resource.extend_from_slice(&iim);
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xED];
+        jpeg.extend_from_slice(&((2 + PHOTOSHOP_HEADER.len() + resource.len()) as u16).to_be_bytes());
+        jpeg.extend_from_slice(PHOTOSHOP_HEADER);
+        jpeg.extend_from_slice(&resource);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+
+        let iptc = read_iptc(&jpeg).unwrap();
+        assert_eq!(iptc.title.as_deref(), Some("Harbor"));
+        assert_eq!(iptc.keywords, ["boats", "fog"]);
+        assert_eq!(iptc.creators, ["Grace"]);
+        assert_eq!(iptc.caption, None);
+    }
+}
@@ -0,0 +1,366 @@
+//! Image metadata - EXIF, XMP, and IPTC reading, EXIF writing, and stripping -
+//! and audio file tags
+
+#[cfg(feature = "std")]
+pub mod audio;
+pub mod container;
+#[cfg(feature = "std")]
+pub mod density;
+#[cfg(feature = "std")]
+pub mod edit;
+#[cfg(feature = "std")]
+pub mod exif;
+#[cfg(feature = "std")]
+pub mod iptc;
+#[cfg(feature = "std")]
+pub mod xmp;
+
+#[cfg(feature = "std")]
+pub use audio::{cover_art, read_audio_tags, write_id3, AudioTags, Id3Frame, Id3Tag, Picture, VorbisComments};
+pub use container::find_exif;
+#[cfg(feature = "std")]
+pub use density::{read_dpi, set_dpi};
+#[cfg(feature = "std")]
+pub use edit::{strip_gps, strip_metadata, write_jpeg_exif};
+#[cfg(feature = "std")]
+pub use exif::{extract_exif_thumbnail, tag_name, Exif, ExifEntry, ExifValue, GpsPosition, Ifd};
+#[cfg(feature = "std")]
+pub use iptc::{read_iptc, Iptc};
+#[cfg(feature = "std")]
+pub use xmp::{read_xmp, Xmp};
+
+#[cfg(feature = "wasm")]
+use js_sys::{Array, Object, Reflect};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+fn set(target: &Object, key: &str, value: JsValue) {
+    // Reflect::set on a plain object cannot fail
+    let _ = Reflect::set(target, &JsValue::from_str(key), &value);
+}
+
+#[cfg(feature = "wasm")]
+fn value_to_js(value: &ExifValue) -> JsValue {
+    match value {
+        ExifValue::Ascii(s) => JsValue::from_str(s),
+        ExifValue::Undefined(bytes) => js_sys::Uint8Array::from(&bytes[..]).into(),
+        _ => {
+            let numbers: Vec<f64> = (0..value.len()).filter_map(|i| value.as_f64(i)).collect();
+            match numbers.as_slice() {
+                [single] => JsValue::from_f64(*single),
+                _ => numbers.iter().map(|&n| JsValue::from_f64(n)).collect::<Array>().into(),
+            }
+        }
+    }
+}
+
+/// Read EXIF from a JPEG, PNG, or bare TIFF/EXIF buffer
+///
+/// Returns `null` when no EXIF is present. Otherwise an object with
+/// convenience fields (`orientation`, `width`, `height`, `make`, `model`,
+/// `dateTime`, `dateTimeOriginal`, `userComment`, `latitude`, `longitude`,
+/// `altitude`, ...) and a `tags` map of every primary/Exif/GPS tag by its
+/// standard name.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = readExif)]
+pub fn read_exif_js(data: &[u8]) -> Result<JsValue, JsError> {
+    let Some(tiff) = find_exif(data) else {
+        return Ok(JsValue::NULL);
+    };
+    let exif = Exif::parse(tiff).map_err(|e| JsError::new(&e))?;
+
+    let result = Object::new();
+    let text = |key: &str, value: Option<&str>| {
+        if let Some(v) = value {
+            set(&result, key, JsValue::from_str(v));
+        }
+    };
+    text("make", exif.make());
+    text("model", exif.model());
+    text("lensModel", exif.lens_model());
+    text("software", exif.software());
+    text("dateTime", exif.date_time());
+    text("dateTimeOriginal", exif.date_time_original());
+    text("userComment", exif.user_comment().as_deref());
+
+    let number = |key: &str, value: Option<f64>| {
+        if let Some(v) = value {
+            set(&result, key, JsValue::from_f64(v));
+        }
+    };
+    number("orientation", exif.orientation().map(f64::from));
+    number("width", exif.dimensions().map(|d| d.0 as f64));
+    number("height", exif.dimensions().map(|d| d.1 as f64));
+    number("exposureTime", exif.exposure_time());
+    number("fNumber", exif.f_number());
+    number("iso", exif.iso().map(f64::from));
+    number("focalLength", exif.focal_length());
+    if let Some(gps) = exif.gps() {
+        number("latitude", Some(gps.latitude));
+        number("longitude", Some(gps.longitude));
+        number("altitude", gps.altitude);
+    }
+
+    let tags = Object::new();
+    for entry in exif.entries.iter().filter(|e| e.ifd != Ifd::Thumbnail) {
+        let name = tag_name(entry.ifd, entry.tag)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("0x{:04X}", entry.tag));
+        set(&tags, &name, value_to_js(&entry.value));
+    }
+    set(&result, "tags", tags.into());
+
+    Ok(result.into())
+}
+
+/// Editable EXIF tag set that can be written into JPEG files
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ExifEditor {
+    exif: Exif,
+}
+
+#[cfg(feature = "std")]
+impl ExifEditor {
+    /// Load the existing EXIF of a JPEG, PNG, or TIFF/EXIF buffer (empty if none)
+    pub fn from_image(data: &[u8]) -> Result<ExifEditor, String> {
+        match find_exif(data) {
+            Some(tiff) => Ok(ExifEditor { exif: Exif::parse(tiff)? }),
+            None => Ok(ExifEditor::new()),
+        }
+    }
+
+    /// Write the tags into a JPEG, replacing any existing EXIF
+    pub fn write_to_jpeg(&self, jpeg: &[u8]) -> Result<Vec<u8>, String> {
+        write_jpeg_exif(jpeg, &self.exif)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ExifEditor {
+    /// Start with an empty (little-endian) tag set
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> ExifEditor {
+        ExifEditor {
+            exif: Exif {
+                little_endian: true,
+                entries: Vec::new(),
+            },
+        }
+    }
+
+    /// Load the existing EXIF of a JPEG, PNG, or TIFF/EXIF buffer (empty if none)
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = fromImage)]
+    pub fn from_image_js(data: &[u8]) -> Result<ExifEditor, JsError> {
+        ExifEditor::from_image(data).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = setText))]
+    pub fn set_text(&mut self, ifd: Ifd, tag: u16, value: &str) {
+        self.exif.set(ifd, tag, ExifValue::Ascii(value.to_string()));
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = setShort))]
+    pub fn set_short(&mut self, ifd: Ifd, tag: u16, value: u16) {
+        self.exif.set(ifd, tag, ExifValue::Short(vec![value]));
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = setLong))]
+    pub fn set_long(&mut self, ifd: Ifd, tag: u16, value: u32) {
+        self.exif.set(ifd, tag, ExifValue::Long(vec![value]));
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = setRational))]
+    pub fn set_rational(&mut self, ifd: Ifd, tag: u16, numerator: u32, denominator: u32) {
+        self.exif.set(ifd, tag, ExifValue::Rational(vec![(numerator, denominator)]));
+    }
+
+    /// Remove one tag; returns whether it existed
+    pub fn remove(&mut self, ifd: Ifd, tag: u16) -> bool {
+        self.exif.remove(ifd, tag)
+    }
+
+    /// Remove all GPS tags
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = removeGps))]
+    pub fn remove_gps(&mut self) {
+        self.exif.remove_ifd(Ifd::Gps);
+    }
+
+    /// Serialize as a TIFF-structured EXIF block
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = toBytes))]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.exif.to_bytes()
+    }
+
+    /// Write the tags into a JPEG, replacing any existing EXIF
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = writeToJpeg)]
+    pub fn write_to_jpeg_js(&self, jpeg: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.write_to_jpeg(jpeg).map_err(|e| JsError::new(&e))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for ExifEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Remove EXIF/XMP/IPTC/comments from a JPEG or PNG without re-encoding
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = stripMetadata)]
+pub fn strip_metadata_js(data: &[u8], keep_color_profile: bool) -> Vec<u8> {
+    strip_metadata(data, keep_color_profile)
+}
+
+/// Remove GPS location tags from a JPEG's EXIF
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = stripGps)]
+pub fn strip_gps_js(jpeg: &[u8]) -> Result<Vec<u8>, JsError> {
+    strip_gps(jpeg).map_err(|e| JsError::new(&e))
+}
+
+/// Read the XMP packet (raw XML plus title/creators/keywords/rating)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = readXmp)]
+pub fn read_xmp_js(data: &[u8]) -> Option<Xmp> {
+    read_xmp(data)
+}
+
+/// Read IPTC-IIM fields from a JPEG APP13 block
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = readIptc)]
+pub fn read_iptc_js(data: &[u8]) -> Option<Iptc> {
+    read_iptc(data)
+}
+
+/// Set the physical resolution of a BMP, PNG, or JPEG without re-encoding
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = setDpi)]
+pub fn set_dpi_js(data: &[u8], dpi_x: f32, dpi_y: f32) -> Result<Vec<u8>, JsError> {
+    set_dpi(data, dpi_x, dpi_y).map_err(|e| JsError::new(&e))
+}
+
+/// Embedded EXIF thumbnail JPEG bytes, if the image has one
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = extractExifThumbnail)]
+pub fn extract_exif_thumbnail_js(data: &[u8]) -> Option<Vec<u8>> {
+    extract_exif_thumbnail(data).map(<[u8]>::to_vec)
+}
+
+/// Read the tags of an MP3, FLAC, or Ogg Vorbis/Opus file
+///
+/// Returns `null` when the file has no tags. Otherwise an object with
+/// `format` (`"id3v2.3"`, `"vorbis"`, ...), the common fields (`title`,
+/// `artist`, `album`, `albumArtist`, `date`, `track`, `disc`, `genre`,
+/// `composer`, `comment`) that are present, a `tags` map of every text
+/// frame or comment field, and the number of `pictures`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = readAudioTags)]
+pub fn read_audio_tags_js(data: &[u8]) -> Result<JsValue, JsError> {
+    let Some(tags) = read_audio_tags(data).map_err(|e| JsError::new(&e))? else {
+        return Ok(JsValue::NULL);
+    };
+    let result = Object::new();
+    let format = match &tags {
+        AudioTags::Id3(tag) => format!("id3v2.{}", tag.version),
+        AudioTags::Vorbis { .. } => "vorbis".to_string(),
+    };
+    set(&result, "format", JsValue::from_str(&format));
+    for field in ["title", "artist", "album", "albumArtist", "date", "track", "disc", "genre", "composer", "comment"] {
+        if let Some(value) = tags.get(field) {
+            set(&result, field, JsValue::from_str(&value));
+        }
+    }
+    let map = Object::new();
+    for (key, value) in tags.entries() {
+        set(&map, &key, JsValue::from_str(&value));
+    }
+    set(&result, "tags", map.into());
+    set(&result, "pictures", JsValue::from_f64(tags.pictures().len() as f64));
+    Ok(result.into())
+}
+
+/// Embedded cover art of an audio or Matroska file: the front cover, or
+/// else the first picture
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = extractCoverArt)]
+pub fn extract_cover_art_js(data: &[u8]) -> Option<Picture> {
+    cover_art(data)
+}
+
+/// Editable tags of an audio file: ID3v2 for MP3 and others, Vorbis
+/// comments for FLAC
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct AudioTagEditor {
+    file: Vec<u8>,
+    tags: AudioTags,
+}
+
+#[cfg(feature = "std")]
+impl AudioTagEditor {
+    /// Load the existing tags of a file (empty if none)
+    pub fn new(file: &[u8]) -> Result<AudioTagEditor, String> {
+        let tags = read_audio_tags(file)?.unwrap_or_else(|| AudioTags::empty_for(file));
+        Ok(AudioTagEditor { file: file.to_vec(), tags })
+    }
+
+    /// The file with its tags replaced by the edited ones
+    pub fn write(&self) -> Result<Vec<u8>, String> {
+        self.tags.write(&self.file)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AudioTagEditor {
+    /// Load the existing tags of a file (empty if none)
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new_js(file: &[u8]) -> Result<AudioTagEditor, JsError> {
+        AudioTagEditor::new(file).map_err(|e| JsError::new(&e))
+    }
+
+    /// Value of a common field name, ID3 frame ID, or Vorbis field name
+    pub fn get(&self, field: &str) -> Option<String> {
+        self.tags.get(field)
+    }
+
+    pub fn set(&mut self, field: &str, value: &str) {
+        self.tags.set(field, value);
+    }
+
+    /// Remove a field; returns whether it existed
+    pub fn remove(&mut self, field: &str) -> bool {
+        self.tags.remove(field)
+    }
+
+    /// Set the front cover, replacing any existing one
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = setCoverArt))]
+    pub fn set_cover_art(&mut self, mime: &str, data: Vec<u8>) {
+        self.tags.set_picture(Picture {
+            mime: mime.to_string(),
+            picture_type: audio::PICTURE_FRONT_COVER,
+            description: String::new(),
+            data,
+        });
+    }
+
+    /// Remove every embedded picture
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = removePictures))]
+    pub fn remove_pictures(&mut self) {
+        self.tags.remove_pictures();
+    }
+
+    /// The file with its tags replaced by the edited ones
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = write)]
+    pub fn write_js(&self) -> Result<Vec<u8>, JsError> {
+        self.write().map_err(|e| JsError::new(&e))
+    }
+}
@@ -0,0 +1,202 @@
+//! XMP packet extraction and a minimal Dublin Core / XMP basic reader
+
+use super::container::{jpeg_segments, png_chunks};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Namespace header of a JPEG APP1 XMP segment
+pub const XMP_JPEG_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// Keyword of the PNG iTXt chunk holding XMP
+pub const XMP_PNG_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+/// Commonly used XMP properties plus the raw packet
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Xmp {
+    /// Complete XML packet
+    pub raw: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub creators: Vec<String>,
+    pub keywords: Vec<String>,
+    /// xmp:Rating, -1 (rejected) to 5
+    pub rating: Option<i32>,
+}
+
+/// Find the XMP packet in a JPEG, PNG, or any buffer containing `<x:xmpmeta`
+pub fn find_xmp(data: &[u8]) -> Option<&[u8]> {
+    if let Some(segment) = jpeg_segments(data)
+        .into_iter()
+        .find(|s| s.marker == 0xE1 && s.data.starts_with(XMP_JPEG_HEADER))
+    {
+        return Some(&segment.data[XMP_JPEG_HEADER.len()..]);
+    }
+
+    for chunk in png_chunks(data).into_iter().filter(|c| &c.kind == b"iTXt") {
+        let Some(rest) = chunk.data.strip_prefix(XMP_PNG_KEYWORD).and_then(|r| r.strip_prefix(b"\0")) else {
+            continue;
+        };
+        // Compression flag and method, then NUL-terminated language tag and translated keyword
+        if rest.len() < 2 || rest[0] != 0 {
+            continue;
+        }
+        let mut text = &rest[2..];
+        for _ in 0..2 {
+            let nul = text.iter().position(|&c| c == 0)?;
+            text = &text[nul + 1..];
+        }
+        return Some(text);
+    }
+
+    let start = find(data, b"<x:xmpmeta")?;
+    let end = find(&data[start..], b"</x:xmpmeta>").map(|e| start + e + 12)?;
+    Some(&data[start..end])
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Decode the predefined and numeric XML entities
+pub fn unescape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|h| u32::from_str_radix(h, 16).ok())
+                .unwrap_or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Inner XML of the first `<name ...>...</name>` element
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}", name);
+    let mut from = 0;
+    while let Some(pos) = xml[from..].find(&open) {
+        let start = from + pos;
+        let after = &xml[start + open.len()..];
+        // Make sure this is the whole tag name, not a prefix of another
+        if after.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            let tag_end = start + open.len() + after.find('>')?;
+            if xml[..tag_end].ends_with('/') {
+                return Some("");
+            }
+            let close = format!("</{}>", name);
+            let end = xml[tag_end..].find(&close)?;
+            return Some(&xml[tag_end + 1..tag_end + end]);
+        }
+        from = start + open.len();
+    }
+    None
+}
+
+/// `name="value"` attribute anywhere in the packet
+fn attribute<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    for quote in ['"', '\''] {
+        let pattern = format!("{}={}", name, quote);
+        if let Some(pos) = xml.find(&pattern) {
+            let rest = &xml[pos + pattern.len()..];
+            return rest.find(quote).map(|end| &rest[..end]);
+        }
+    }
+    None
+}
+
+/// Text of every `rdf:li` inside an element, or the element's text itself
+fn items(xml: &str, name: &str) -> Vec<String> {
+    let Some(inner) = element(xml, name) else {
+        return attribute(xml, name).map(|v| vec![unescape_xml(v)]).unwrap_or_default();
+    };
+    let mut values = Vec::new();
+    let mut rest = inner;
+    while let Some(li) = element(rest, "rdf:li") {
+        let text = unescape_xml(li.trim());
+        if !text.is_empty() {
+            values.push(text);
+        }
+        // Advance past this item's closing tag
+        let Some(end) = rest.find("</rdf:li>") else { break };
+        rest = &rest[end + 9..];
+    }
+    if values.is_empty() && !inner.contains('<') && !inner.trim().is_empty() {
+        values.push(unescape_xml(inner.trim()));
+    }
+    values
+}
+
+impl Xmp {
+    /// Parse a packet's common properties
+    pub fn parse(packet: &[u8]) -> Xmp {
+        let raw = String::from_utf8_lossy(packet).trim_end_matches('\0').to_string();
+        let rating = element(&raw, "xmp:Rating")
+            .or_else(|| attribute(&raw, "xmp:Rating"))
+            .and_then(|r| r.trim().parse::<f32>().ok())
+            .map(|r| r.round() as i32);
+        Xmp {
+            title: items(&raw, "dc:title").into_iter().next(),
+            description: items(&raw, "dc:description").into_iter().next(),
+            creators: items(&raw, "dc:creator"),
+            keywords: items(&raw, "dc:subject"),
+            rating,
+            raw,
+        }
+    }
+}
+
+/// Extract and parse XMP from a JPEG, PNG, or other buffer
+pub fn read_xmp(data: &[u8]) -> Option<Xmp> {
+    find_xmp(data).map(Xmp::parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKET: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF>
+<rdf:Description rdf:about="" xmp:Rating="4">
+ <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Sunset &amp; Sea</rdf:li></rdf:Alt></dc:title>
+ <dc:creator><rdf:Seq><rdf:li>Ada</rdf:li></rdf:Seq></dc:creator>
+ <dc:subject><rdf:Bag><rdf:li>beach</rdf:li><rdf:li>sun</rdf:li></rdf:Bag></dc:subject>
+</rdf:Description></rdf:RDF></x:xmpmeta>"#;
+
+    #[test]
+    fn test_parse_packet_in_jpeg() {
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&((2 + XMP_JPEG_HEADER.len() + PACKET.len()) as u16).to_be_bytes());
+        jpeg.extend_from_slice(XMP_JPEG_HEADER);
+        jpeg.extend_from_slice(PACKET.as_bytes());
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+
+        let xmp = read_xmp(&jpeg).unwrap();
+        assert_eq!(xmp.title.as_deref(), Some("Sunset & Sea"));
+        assert_eq!(xmp.creators, ["Ada"]);
+        assert_eq!(xmp.keywords, ["beach", "sun"]);
+        assert_eq!(xmp.rating, Some(4));
+        assert!(xmp.raw.starts_with("<x:xmpmeta"));
+    }
+}
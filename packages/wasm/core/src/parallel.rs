@@ -0,0 +1,33 @@
+//! When parallel code paths may use rayon (the `threads` feature)
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static POOL_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Record that the global rayon pool exists; in WebAssembly it cannot start
+/// on demand, so whoever builds it (mconv-wasm's `initThreadPool`) says so
+pub fn mark_pool_started() {
+    POOL_STARTED.store(true, Ordering::Release);
+}
+
+/// Whether this thread may block on pool work; rayon waits with
+/// `Atomics.wait`, which traps on the browser main thread (the one with a
+/// `document`), so callers there must use a non-blocking path such as
+/// mconv-wasm's `ThreadPool.submitConvert`
+fn can_block() -> bool {
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    {
+        !js_sys::Reflect::has(&js_sys::global(), &"document".into()).unwrap_or(false)
+    }
+    #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+    {
+        true
+    }
+}
+
+/// Whether rayon can run work here: on a pool thread, or on a thread that
+/// may block once the pool exists (natively it starts on demand; in
+/// WebAssembly it must be marked started)
+pub fn available() -> bool {
+    rayon::current_thread_index().is_some() || (can_block() && (!cfg!(target_arch = "wasm32") || POOL_STARTED.load(Ordering::Acquire)))
+}
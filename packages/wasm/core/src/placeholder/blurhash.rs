@@ -0,0 +1,186 @@
+//! BlurHash encoder/decoder (https://blurha.sh)
+
+use crate::color::space;
+use crate::limits::Budget;
+use crate::utils::check_rgba;
+use std::f32::consts::PI;
+
+const BASE83: &[u8; 83] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+fn srgb_to_linear(v: u8) -> f32 {
+    space::srgb_to_linear(v as f32 / 255.0)
+}
+
+fn linear_to_srgb(v: f32) -> u8 {
+    (space::linear_to_srgb(v.clamp(0.0, 1.0)) * 255.0 + 0.5) as u8
+}
+
+fn sign_pow(v: f32, exp: f32) -> f32 {
+    v.abs().powf(exp).copysign(v)
+}
+
+fn encode83(value: u32, length: usize, out: &mut String) {
+    for i in 1..=length {
+        let digit = (value / 83u32.pow((length - i) as u32)) % 83;
+        out.push(BASE83[digit as usize] as char);
+    }
+}
+
+fn decode83(s: &[u8]) -> Result<u32, String> {
+    s.iter().try_fold(0u32, |acc, &c| {
+        let digit = BASE83
+            .iter()
+            .position(|&b| b == c)
+            .ok_or_else(|| format!("Invalid BlurHash character: {}", c as char))?;
+        Ok(acc * 83 + digit as u32)
+    })
+}
+
+/// Encode an RGBA image to a BlurHash string with `cx` x `cy` components (1-9 each)
+pub fn blurhash_encode(data: &[u8], width: u32, height: u32, cx: u32, cy: u32) -> Result<String, String> {
+    check_rgba(data, width, height)?;
+    if !(1..=9).contains(&cx) || !(1..=9).contains(&cy) {
+        return Err(format!("BlurHash components must be 1-9, got {}x{}", cx, cy));
+    }
+
+    let w = width as usize;
+    let h = height as usize;
+    let linear: Vec<[f32; 3]> = data
+        .chunks_exact(4)
+        .map(|p| [srgb_to_linear(p[0]), srgb_to_linear(p[1]), srgb_to_linear(p[2])])
+        .collect();
+
+    let mut factors = Vec::with_capacity((cx * cy) as usize);
+    for j in 0..cy as usize {
+        let basis_y: Vec<f32> = (0..h).map(|y| (PI * j as f32 * y as f32 / h as f32).cos()).collect();
+        for i in 0..cx as usize {
+            let basis_x: Vec<f32> = (0..w).map(|x| (PI * i as f32 * x as f32 / w as f32).cos()).collect();
+
+            let mut sum = [0f32; 3];
+            for y in 0..h {
+                for x in 0..w {
+                    let basis = basis_x[x] * basis_y[y];
+                    let c = linear[y * w + x];
+                    sum[0] += basis * c[0];
+                    sum[1] += basis * c[1];
+                    sum[2] += basis * c[2];
+                }
+            }
+
+            let norm = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+            let scale = norm / (w * h) as f32;
+            factors.push(sum.map(|s| s * scale));
+        }
+    }
+
+    let mut hash = String::new();
+    encode83((cx - 1) + (cy - 1) * 9, 1, &mut hash);
+
+    let ac = &factors[1..];
+    let max_value = if ac.is_empty() {
+        encode83(0, 1, &mut hash);
+        1.0
+    } else {
+        let actual_max = ac.iter().flat_map(|f| f.iter()).fold(0f32, |m, v| m.max(v.abs()));
+        let quantized = ((actual_max * 166.0 - 0.5).floor() as i32).clamp(0, 82) as u32;
+        encode83(quantized, 1, &mut hash);
+        (quantized + 1) as f32 / 166.0
+    };
+
+    let dc = factors[0];
+    let dc_value = ((linear_to_srgb(dc[0]) as u32) << 16) | ((linear_to_srgb(dc[1]) as u32) << 8) | linear_to_srgb(dc[2]) as u32;
+    encode83(dc_value, 4, &mut hash);
+
+    for f in ac {
+        let q = f.map(|v| ((sign_pow(v / max_value, 0.5) * 9.0 + 9.5).floor() as i32).clamp(0, 18) as u32);
+        encode83(q[0] * 19 * 19 + q[1] * 19 + q[2], 2, &mut hash);
+    }
+
+    Ok(hash)
+}
+
+/// Decode a BlurHash to a `width` x `height` RGBA image, within the pixel
+/// limits set by `set_limits`
+///
+/// `punch` scales the AC components to increase contrast (1.0 is neutral).
+pub fn blurhash_decode(hash: &str, width: u32, height: u32, punch: f32) -> Result<Vec<u8>, String> {
+    let bytes = hash.as_bytes();
+    if bytes.len() < 6 {
+        return Err("BlurHash too short".to_string());
+    }
+
+    let size_flag = decode83(&bytes[0..1])?;
+    let cy = (size_flag / 9 + 1) as usize;
+    let cx = (size_flag % 9 + 1) as usize;
+    if bytes.len() != 4 + 2 * cx * cy {
+        return Err(format!("BlurHash length mismatch: expected {}, got {}", 4 + 2 * cx * cy, bytes.len()));
+    }
+
+    let max_value = (decode83(&bytes[1..2])? + 1) as f32 / 166.0;
+    let mut colors = Vec::with_capacity(cx * cy);
+
+    let dc = decode83(&bytes[2..6])?;
+    colors.push([
+        srgb_to_linear((dc >> 16) as u8),
+        srgb_to_linear((dc >> 8) as u8),
+        srgb_to_linear(dc as u8),
+    ]);
+    for i in 1..cx * cy {
+        let value = decode83(&bytes[4 + i * 2..6 + i * 2])?;
+        let q = [value / (19 * 19), (value / 19) % 19, value % 19];
+        colors.push(q.map(|v| sign_pow((v as f32 - 9.0) / 9.0, 2.0) * max_value * punch));
+    }
+
+    let w = width as usize;
+    let h = height as usize;
+    Budget::new().check_size(w, h)?;
+    let mut output = Vec::with_capacity(w * h * 4);
+    for y in 0..h {
+        let basis_y: Vec<f32> = (0..cy).map(|j| (PI * y as f32 * j as f32 / h as f32).cos()).collect();
+        for x in 0..w {
+            let mut c = [0f32; 3];
+            for (j, by) in basis_y.iter().enumerate() {
+                for i in 0..cx {
+                    let basis = (PI * x as f32 * i as f32 / w as f32).cos() * by;
+                    let color = colors[j * cx + i];
+                    c[0] += color[0] * basis;
+                    c[1] += color[1] * basis;
+                    c[2] += color[2] * basis;
+                }
+            }
+            output.extend_from_slice(&[linear_to_srgb(c[0]), linear_to_srgb(c[1]), linear_to_srgb(c[2]), 255]);
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solid_color_roundtrip() {
+        let data: Vec<u8> = [40u8, 120, 200, 255].repeat(32 * 32);
+        let hash = blurhash_encode(&data, 32, 32, 4, 3).unwrap();
+        assert_eq!(hash.len(), 4 + 2 * 4 * 3);
+
+        let decoded = blurhash_decode(&hash, 32, 32, 1.0).unwrap();
+        let center = &decoded[(16 * 32 + 16) * 4..][..4];
+        assert!((center[0] as i32 - 40).abs() <= 6, "{:?}", center);
+        assert!((center[2] as i32 - 200).abs() <= 6, "{:?}", center);
+        assert_eq!(center[3], 255);
+    }
+
+    #[test]
+    fn test_rejects_bad_length() {
+        assert!(blurhash_decode("LEHV6nWB2yk8pyo0adR*.7kCMdn", 4, 4, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        // The output size is checked before anything is allocated
+        assert_eq!(blurhash_decode("00TSUA", 2, 2, 1.0).unwrap().len(), 16);
+        assert!(blurhash_decode("00TSUA", 1 << 16, 1 << 16, 1.0).unwrap_err().contains("over the limit"));
+    }
+}
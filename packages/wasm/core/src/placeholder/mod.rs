@@ -0,0 +1,44 @@
+//! Compact image placeholders - BlurHash and ThumbHash
+
+mod blurhash;
+mod thumbhash;
+
+pub use blurhash::{blurhash_decode, blurhash_encode};
+pub use thumbhash::{thumbhash_aspect_ratio, thumbhash_decode, thumbhash_encode, ThumbHashImage};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Encode RGBA to a BlurHash string
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = blurhashEncode)]
+pub fn blurhash_encode_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    components_x: u32,
+    components_y: u32,
+) -> Result<String, JsError> {
+    blurhash_encode(data, width, height, components_x, components_y).map_err(|e| JsError::new(&e))
+}
+
+/// Decode a BlurHash string to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = blurhashDecode)]
+pub fn blurhash_decode_js(hash: &str, width: u32, height: u32, punch: f32) -> Result<Vec<u8>, JsError> {
+    blurhash_decode(hash, width, height, punch).map_err(|e| JsError::new(&e))
+}
+
+/// Encode RGBA to ThumbHash bytes
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = thumbhashEncode)]
+pub fn thumbhash_encode_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    thumbhash_encode(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Decode ThumbHash bytes to a small RGBA image
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = thumbhashDecode)]
+pub fn thumbhash_decode_js(hash: &[u8]) -> Result<ThumbHashImage, JsError> {
+    thumbhash_decode(hash).map_err(|e| JsError::new(&e))
+}
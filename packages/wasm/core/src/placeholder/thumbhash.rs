@@ -0,0 +1,317 @@
+//! ThumbHash encoder/decoder (https://evanw.github.io/thumbhash/)
+//!
+//! Bit-compatible with the reference implementation.
+
+use crate::resize::{resize, ResizeAlgorithm};
+use crate::utils::check_rgba;
+use std::f32::consts::PI;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Largest input dimension; larger images are downscaled first
+const MAX_SIZE: u32 = 100;
+
+/// Decoded ThumbHash placeholder image
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug)]
+pub struct ThumbHashImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// DCT-encode a channel into (DC, normalized AC terms, AC scale)
+fn encode_channel(channel: &[f32], w: usize, h: usize, nx: usize, ny: usize) -> (f32, Vec<f32>, f32) {
+    let mut dc = 0.0;
+    let mut ac = Vec::new();
+    let mut scale = 0f32;
+    let mut fx = vec![0f32; w];
+
+    for cy in 0..ny {
+        let mut cx = 0;
+        while cx * ny < nx * (ny - cy) {
+            for (x, f) in fx.iter_mut().enumerate() {
+                *f = (PI / w as f32 * cx as f32 * (x as f32 + 0.5)).cos();
+            }
+            let mut f = 0.0;
+            for y in 0..h {
+                let fy = (PI / h as f32 * cy as f32 * (y as f32 + 0.5)).cos();
+                for x in 0..w {
+                    f += channel[x + y * w] * fx[x] * fy;
+                }
+            }
+            f /= (w * h) as f32;
+
+            if cx > 0 || cy > 0 {
+                ac.push(f);
+                scale = scale.max(f.abs());
+            } else {
+                dc = f;
+            }
+            cx += 1;
+        }
+    }
+
+    if scale > 0.0 {
+        for v in ac.iter_mut() {
+            *v = 0.5 + 0.5 / scale * *v;
+        }
+    }
+    (dc, ac, scale)
+}
+
+/// Encode an RGBA image to a ThumbHash
+pub fn thumbhash_encode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if width == 0 || height == 0 {
+        return Err("Image is empty".to_string());
+    }
+
+    // Downscale large inputs; the hash only captures low frequencies anyway
+    let (w, h, pixels) = if width > MAX_SIZE || height > MAX_SIZE {
+        let scale = MAX_SIZE as f32 / width.max(height) as f32;
+        let w = ((width as f32 * scale).round() as u32).max(1);
+        let h = ((height as f32 * scale).round() as u32).max(1);
+        (w, h, resize(data, width, height, w, h, ResizeAlgorithm::Bilinear))
+    } else {
+        (width, height, data.to_vec())
+    };
+    let (w, h) = (w as usize, h as usize);
+
+    // Average color, weighted by alpha
+    let (mut avg_r, mut avg_g, mut avg_b, mut avg_a) = (0f32, 0f32, 0f32, 0f32);
+    for p in pixels.chunks_exact(4) {
+        let alpha = p[3] as f32 / 255.0;
+        avg_r += alpha / 255.0 * p[0] as f32;
+        avg_g += alpha / 255.0 * p[1] as f32;
+        avg_b += alpha / 255.0 * p[2] as f32;
+        avg_a += alpha;
+    }
+    if avg_a > 0.0 {
+        avg_r /= avg_a;
+        avg_g /= avg_a;
+        avg_b /= avg_a;
+    }
+
+    let has_alpha = avg_a < (w * h) as f32;
+    let l_limit = if has_alpha { 5.0 } else { 7.0 };
+    let max_dim = w.max(h) as f32;
+    let lx = ((l_limit * w as f32 / max_dim).round() as usize).max(1);
+    let ly = ((l_limit * h as f32 / max_dim).round() as usize).max(1);
+
+    // Convert to LPQA, composited atop the average color
+    let n = w * h;
+    let (mut l, mut p, mut q, mut a) = (vec![0f32; n], vec![0f32; n], vec![0f32; n], vec![0f32; n]);
+    for (i, px) in pixels.chunks_exact(4).enumerate() {
+        let alpha = px[3] as f32 / 255.0;
+        let r = avg_r * (1.0 - alpha) + alpha / 255.0 * px[0] as f32;
+        let g = avg_g * (1.0 - alpha) + alpha / 255.0 * px[1] as f32;
+        let b = avg_b * (1.0 - alpha) + alpha / 255.0 * px[2] as f32;
+        l[i] = (r + g + b) / 3.0;
+        p[i] = (r + g) / 2.0 - b;
+        q[i] = r - g;
+        a[i] = alpha;
+    }
+
+    let (l_dc, l_ac, l_scale) = encode_channel(&l, w, h, lx.max(3), ly.max(3));
+    let (p_dc, p_ac, p_scale) = encode_channel(&p, w, h, 3, 3);
+    let (q_dc, q_ac, q_scale) = encode_channel(&q, w, h, 3, 3);
+    let alpha_channel = has_alpha.then(|| encode_channel(&a, w, h, 5, 5));
+
+    let is_landscape = w > h;
+    let header24 = (63.0 * l_dc).round() as u32
+        | ((31.5 + 31.5 * p_dc).round() as u32) << 6
+        | ((31.5 + 31.5 * q_dc).round() as u32) << 12
+        | ((31.0 * l_scale).round() as u32) << 18
+        | (has_alpha as u32) << 23;
+    let header16 = (if is_landscape { ly } else { lx }) as u32
+        | ((63.0 * p_scale).round() as u32) << 3
+        | ((63.0 * q_scale).round() as u32) << 9
+        | (is_landscape as u32) << 15;
+
+    let mut hash = vec![
+        header24 as u8,
+        (header24 >> 8) as u8,
+        (header24 >> 16) as u8,
+        header16 as u8,
+        (header16 >> 8) as u8,
+    ];
+    if let Some((a_dc, _, a_scale)) = &alpha_channel {
+        hash.push((15.0 * a_dc).round() as u8 | ((15.0 * a_scale).round() as u8) << 4);
+    }
+
+    let mut acs = vec![l_ac, p_ac, q_ac];
+    if let Some((_, a_ac, _)) = alpha_channel {
+        acs.push(a_ac);
+    }
+    let ac_start = hash.len();
+    for (index, f) in acs.iter().flatten().enumerate() {
+        let at = ac_start + (index >> 1);
+        if at >= hash.len() {
+            hash.push(0);
+        }
+        hash[at] |= ((15.0 * f).round() as u8) << ((index & 1) << 2);
+    }
+
+    Ok(hash)
+}
+
+/// Approximate aspect ratio (width / height) stored in a ThumbHash
+pub fn thumbhash_aspect_ratio(hash: &[u8]) -> Result<f32, String> {
+    if hash.len() < 5 {
+        return Err("ThumbHash too short".to_string());
+    }
+    let header = hash[3];
+    let has_alpha = hash[2] & 0x80 != 0;
+    let is_landscape = hash[4] & 0x80 != 0;
+    let lx = if is_landscape { if has_alpha { 5 } else { 7 } } else { header & 7 };
+    let ly = if is_landscape { header & 7 } else if has_alpha { 5 } else { 7 };
+    Ok(lx as f32 / ly.max(1) as f32)
+}
+
+/// Decode a ThumbHash to a placeholder image (at most 32 pixels per side)
+pub fn thumbhash_decode(hash: &[u8]) -> Result<ThumbHashImage, String> {
+    let ratio = thumbhash_aspect_ratio(hash)?;
+
+    let header24 = hash[0] as u32 | (hash[1] as u32) << 8 | (hash[2] as u32) << 16;
+    let header16 = hash[3] as u32 | (hash[4] as u32) << 8;
+    let l_dc = (header24 & 63) as f32 / 63.0;
+    let p_dc = ((header24 >> 6) & 63) as f32 / 31.5 - 1.0;
+    let q_dc = ((header24 >> 12) & 63) as f32 / 31.5 - 1.0;
+    let l_scale = ((header24 >> 18) & 31) as f32 / 31.0;
+    let has_alpha = header24 >> 23 != 0;
+    let p_scale = ((header16 >> 3) & 63) as f32 / 63.0;
+    let q_scale = ((header16 >> 9) & 63) as f32 / 63.0;
+    let is_landscape = header16 >> 15 != 0;
+    let l_max = if has_alpha { 5 } else { 7 };
+    let lx = (if is_landscape { l_max } else { (header16 & 7) as usize }).max(3);
+    let ly = (if is_landscape { (header16 & 7) as usize } else { l_max }).max(3);
+
+    let ac_start = if has_alpha { 6 } else { 5 };
+    if hash.len() < ac_start {
+        return Err("ThumbHash too short".to_string());
+    }
+    let (a_dc, a_scale) = if has_alpha {
+        ((hash[5] & 15) as f32 / 15.0, (hash[5] >> 4) as f32 / 15.0)
+    } else {
+        (1.0, 0.0)
+    };
+
+    // Saturation is boosted 1.25x to compensate for quantization
+    let mut ac_index = 0usize;
+    let mut decode_channel = |nx: usize, ny: usize, scale: f32| -> Result<Vec<f32>, String> {
+        let mut ac = Vec::new();
+        for cy in 0..ny {
+            let mut cx = if cy > 0 { 0 } else { 1 };
+            while cx * ny < nx * (ny - cy) {
+                let byte = *hash
+                    .get(ac_start + (ac_index >> 1))
+                    .ok_or_else(|| "ThumbHash truncated".to_string())?;
+                let nibble = (byte >> ((ac_index & 1) << 2)) & 15;
+                ac.push((nibble as f32 / 7.5 - 1.0) * scale);
+                ac_index += 1;
+                cx += 1;
+            }
+        }
+        Ok(ac)
+    };
+    let l_ac = decode_channel(lx, ly, l_scale)?;
+    let p_ac = decode_channel(3, 3, p_scale * 1.25)?;
+    let q_ac = decode_channel(3, 3, q_scale * 1.25)?;
+    let a_ac = if has_alpha { decode_channel(5, 5, a_scale)? } else { Vec::new() };
+
+    let w = (if ratio > 1.0 { 32.0 } else { 32.0 * ratio }).round() as usize;
+    let h = (if ratio > 1.0 { 32.0 / ratio } else { 32.0 }).round() as usize;
+    let nfx = lx.max(if has_alpha { 5 } else { 3 });
+    let nfy = ly.max(if has_alpha { 5 } else { 3 });
+
+    let mut data = Vec::with_capacity(w * h * 4);
+    let mut fx = vec![0f32; nfx];
+    let mut fy = vec![0f32; nfy];
+    for y in 0..h {
+        for x in 0..w {
+            let (mut l, mut p, mut q, mut a) = (l_dc, p_dc, q_dc, a_dc);
+
+            for (cx, f) in fx.iter_mut().enumerate() {
+                *f = (PI / w as f32 * (x as f32 + 0.5) * cx as f32).cos();
+            }
+            for (cy, f) in fy.iter_mut().enumerate() {
+                *f = (PI / h as f32 * (y as f32 + 0.5) * cy as f32).cos();
+            }
+
+            let mut j = 0;
+            for (cy, &fyv) in fy.iter().enumerate().take(ly) {
+                let fy2 = fyv * 2.0;
+                let mut cx = if cy > 0 { 0 } else { 1 };
+                while cx * ly < lx * (ly - cy) {
+                    l += l_ac[j] * fx[cx] * fy2;
+                    cx += 1;
+                    j += 1;
+                }
+            }
+
+            let mut j = 0;
+            for (cy, &fyv) in fy.iter().enumerate().take(3) {
+                let fy2 = fyv * 2.0;
+                for &fxv in &fx[usize::from(cy == 0)..3 - cy] {
+                    let f = fxv * fy2;
+                    p += p_ac[j] * f;
+                    q += q_ac[j] * f;
+                    j += 1;
+                }
+            }
+
+            if has_alpha {
+                let mut j = 0;
+                for (cy, &fyv) in fy.iter().enumerate().take(5) {
+                    let fy2 = fyv * 2.0;
+                    for &fxv in &fx[usize::from(cy == 0)..5 - cy] {
+                        a += a_ac[j] * fxv * fy2;
+                        j += 1;
+                    }
+                }
+            }
+
+            let b = l - 2.0 / 3.0 * p;
+            let r = (3.0 * l - b + q) / 2.0;
+            let g = r - q;
+            data.extend([r, g, b, a].map(|v| (255.0 * v.min(1.0)).max(0.0) as u8));
+        }
+    }
+
+    Ok(ThumbHashImage {
+        width: w as u32,
+        height: h as u32,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_preserves_color_and_aspect() {
+        // 40x20 opaque orange image
+        let data: Vec<u8> = [230u8, 120, 30, 255].repeat(40 * 20);
+        let hash = thumbhash_encode(&data, 40, 20).unwrap();
+        assert_eq!(hash[2] & 0x80, 0);
+
+        let image = thumbhash_decode(&hash).unwrap();
+        assert!(image.width > image.height);
+        let p = &image.data[..4];
+        assert!((p[0] as i32 - 230).abs() <= 8, "{:?}", p);
+        assert!((p[1] as i32 - 120).abs() <= 8, "{:?}", p);
+        assert!((p[2] as i32 - 30).abs() <= 8, "{:?}", p);
+        assert_eq!(p[3], 255);
+    }
+
+    #[test]
+    fn test_transparent_image_sets_alpha_flag() {
+        let mut data: Vec<u8> = [0u8, 0, 255, 255].repeat(16);
+        data[3] = 0;
+        let hash = thumbhash_encode(&data, 4, 4).unwrap();
+        assert_ne!(hash[2] & 0x80, 0);
+        assert!(thumbhash_decode(&hash).is_ok());
+    }
+}
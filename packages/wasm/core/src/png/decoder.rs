@@ -0,0 +1,396 @@
+//! PNG and APNG decoder - pure Rust implementation
+
+use super::paeth;
+use crate::animation::{Canvas, FrameSequence, Rect};
+use crate::compress::zlib_decompress_with_limit;
+use crate::limits::Budget;
+use crate::metadata::container::{png_chunks, PNG_SIGNATURE};
+use crate::pool::{self, Scratch};
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+
+/// Adam7 passes: (x start, y start, x step, y step)
+const ADAM7: [(usize, usize, usize, usize); 7] = [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+#[derive(Clone, Copy)]
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(ihdr: &[u8]) -> Result<Header, String> {
+        if ihdr.len() < 13 {
+            return Err("IHDR chunk too short".to_string());
+        }
+        let be32 = |o: usize| u32::from_be_bytes([ihdr[o], ihdr[o + 1], ihdr[o + 2], ihdr[o + 3]]);
+        let header = Header {
+            width: be32(0),
+            height: be32(4),
+            bit_depth: ihdr[8],
+            color_type: ihdr[9],
+            interlaced: ihdr[12] == 1,
+        };
+        let depths: &[u8] = match header.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            t => return Err(format!("Invalid PNG color type {}", t)),
+        };
+        if !depths.contains(&header.bit_depth) {
+            return Err(format!("Invalid bit depth {} for color type {}", header.bit_depth, header.color_type));
+        }
+        if ihdr[10] != 0 || ihdr[11] != 0 || ihdr[12] > 1 {
+            return Err("Unsupported PNG compression, filter or interlace method".to_string());
+        }
+        if header.width == 0 || header.height == 0 {
+            return Err("PNG has zero width or height".to_string());
+        }
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    /// Bytes of filtered scanlines in a `width` x `height` image, each
+    /// interlace pass's rows included
+    fn filtered_size(&self, width: usize, height: usize) -> usize {
+        let size = |(w, h): (usize, usize)| if w == 0 { 0 } else { (self.row_bytes(w) + 1).saturating_mul(h) };
+        if self.interlaced {
+            ADAM7.iter().map(|pass| size(pass_size(width, height, pass))).fold(0, usize::saturating_add)
+        } else {
+            size((width, height))
+        }
+    }
+}
+
+/// Width and height of one Adam7 pass over a `width` x `height` image
+fn pass_size(width: usize, height: usize, &(x0, y0, dx, dy): &(usize, usize, usize, usize)) -> (usize, usize) {
+    ((width + dx - 1 - x0) / dx, (height + dy - 1 - y0) / dy)
+}
+
+/// PLTE, tRNS and iCCP contents
+#[derive(Default)]
+struct Colors {
+    palette: Vec<[u8; 4]>,
+    /// Transparent sample values for gray (one) or RGB (three) images
+    key: Option<Vec<u16>>,
+    /// Inflated ICC profile; no_std builds cannot apply one
+    #[cfg(feature = "std")]
+    profile: Option<Vec<u8>>,
+}
+
+impl Colors {
+    fn set_palette(&mut self, plte: &[u8]) {
+        self.palette = plte.chunks_exact(3).map(|c| [c[0], c[1], c[2], 255]).collect();
+    }
+
+    fn set_transparency(&mut self, trns: &[u8], color_type: u8) {
+        match color_type {
+            3 => {
+                for (entry, &a) in self.palette.iter_mut().zip(trns) {
+                    entry[3] = a;
+                }
+            }
+            0 | 2 => self.key = Some(trns.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect()),
+            _ => {}
+        }
+    }
+
+    /// Keep the profile from an iCCP chunk: name, NUL, method 0, zlib data
+    #[cfg(feature = "std")]
+    fn set_profile(&mut self, iccp: &[u8]) {
+        let Some(nul) = iccp.iter().position(|&b| b == 0) else { return };
+        if iccp.get(nul + 1) == Some(&0) {
+            self.profile = crate::compress::zlib_decompress(&iccp[nul + 2..]).ok();
+        }
+    }
+}
+
+/// Undo per-row filtering of `rows` rows of `row_bytes` each
+fn unfilter(raw: &[u8], row_bytes: usize, rows: usize, bpp: usize) -> Result<Scratch<u8>, String> {
+    if raw.len() < (row_bytes + 1) * rows {
+        return Err("Truncated PNG image data".to_string());
+    }
+    let mut out = pool::take(row_bytes * rows, 0u8);
+    let zero = pool::take(row_bytes, 0u8);
+    for (y, line) in raw.chunks_exact(row_bytes + 1).take(rows).enumerate() {
+        let (done, rest) = out.split_at_mut(y * row_bytes);
+        let prev = if y == 0 { &zero[..] } else { &done[(y - 1) * row_bytes..] };
+        let cur = &mut rest[..row_bytes];
+        let (filter, line) = (line[0], &line[1..]);
+        for i in 0..row_bytes {
+            let a = if i >= bpp { cur[i - bpp] } else { 0 };
+            let c = if i >= bpp { prev[i - bpp] } else { 0 };
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => prev[i],
+                3 => ((a as u16 + prev[i] as u16) / 2) as u8,
+                4 => paeth(a, prev[i], c),
+                f => return Err(format!("Invalid PNG filter type {}", f)),
+            };
+            cur[i] = line[i].wrapping_add(predicted);
+        }
+    }
+    Ok(out)
+}
+
+/// Convert unfiltered rows of `width` pixels to RGBA8, appending to `out`
+fn expand(pixels: &[u8], width: usize, header: &Header, colors: &Colors, out: &mut Vec<u8>) {
+    let row_bytes = header.row_bytes(width);
+    let depth = header.bit_depth as usize;
+    let channels = header.channels();
+    let max = (1u32 << depth.min(8)) - 1;
+    out.reserve(pixels.len() / row_bytes.max(1) * width * 4);
+
+    for row in pixels.chunks_exact(row_bytes) {
+        let sample = |i: usize| -> u16 {
+            match depth {
+                16 => u16::from_be_bytes([row[2 * i], row[2 * i + 1]]),
+                8 => row[i] as u16,
+                d => ((row[i * d / 8] >> (8 - d - (i * d) % 8)) as u32 & max) as u16,
+            }
+        };
+        // Samples narrowed to 8 bits
+        let scale = |v: u16| -> u8 {
+            match depth {
+                16 => (v >> 8) as u8,
+                8 => v as u8,
+                _ => (v as u32 * 255 / max) as u8,
+            }
+        };
+        for x in 0..width {
+            let s = |c: usize| sample(x * channels + c);
+            let px = match header.color_type {
+                0 => {
+                    let g = s(0);
+                    let a = if colors.key.as_deref().is_some_and(|k| k.first() == Some(&g)) { 0 } else { 255 };
+                    [scale(g), scale(g), scale(g), a]
+                }
+                2 => {
+                    let rgb = [s(0), s(1), s(2)];
+                    let a = if colors.key.as_deref() == Some(&rgb[..]) { 0 } else { 255 };
+                    [scale(rgb[0]), scale(rgb[1]), scale(rgb[2]), a]
+                }
+                3 => colors.palette.get(s(0) as usize).copied().unwrap_or([0, 0, 0, 255]),
+                4 => {
+                    let g = scale(s(0));
+                    [g, g, g, scale(s(1))]
+                }
+                _ => [scale(s(0)), scale(s(1)), scale(s(2)), scale(s(3))],
+            };
+            out.extend_from_slice(&px);
+        }
+    }
+}
+
+/// Decode one `width` x `height` image and convert it to sRGB through the
+/// iCCP profile, if any
+fn decode_image(zdata: &[u8], width: u32, height: u32, header: &Header, colors: &Colors, budget: &mut Budget) -> Result<Vec<u8>, String> {
+    let pixels = decode_pixels(zdata, width, height, header, colors, budget)?;
+    // An unusable profile leaves data untouched, and no_std builds, which
+    // lack the float math, skip it
+    #[cfg(feature = "std")]
+    if let Some(profile) = &colors.profile {
+        if let Ok(converted) = crate::icc::apply_icc_profile(&pixels, width, height, profile) {
+            return Ok(converted);
+        }
+    }
+    Ok(pixels)
+}
+
+/// Inflate, unfilter and de-interlace one `width` x `height` image
+fn decode_pixels(zdata: &[u8], width: u32, height: u32, header: &Header, colors: &Colors, budget: &mut Budget) -> Result<Vec<u8>, String> {
+    let (width, height) = (width as usize, height as usize);
+    budget.check_size(width, height)?;
+    budget.rows(height)?;
+    // Nothing past the scanlines is needed, so a deflate bomb stops there
+    let raw = zlib_decompress_with_limit(zdata, header.filtered_size(width, height))?;
+    let bpp = header.bits_per_pixel().div_ceil(8);
+    if !header.interlaced {
+        let pixels = unfilter(&raw, header.row_bytes(width), height, bpp)?;
+        let mut out = Vec::new();
+        expand(&pixels, width, header, colors, &mut out);
+        return Ok(out);
+    }
+
+    let mut out = vec![0u8; width * height * 4];
+    let mut pass = pool::take_empty(width * height.div_ceil(2) * 4);
+    let mut pos = 0;
+    for &(x0, y0, dx, dy) in &ADAM7 {
+        let (pw, ph) = pass_size(width, height, &(x0, y0, dx, dy));
+        if pw == 0 || ph == 0 {
+            continue;
+        }
+        let row_bytes = header.row_bytes(pw);
+        let pixels = unfilter(raw.get(pos..).unwrap_or(&[]), row_bytes, ph, bpp)?;
+        pos += (row_bytes + 1) * ph;
+        pass.clear();
+        expand(&pixels, pw, header, colors, &mut pass);
+        for (i, px) in pass.chunks_exact(4).enumerate() {
+            let (x, y) = (x0 + (i % pw) * dx, y0 + (i / pw) * dy);
+            let at = (y * width + x) * 4;
+            out[at..at + 4].copy_from_slice(px);
+        }
+    }
+    Ok(out)
+}
+
+/// An APNG fcTL chunk
+struct FrameControl {
+    rect: Rect,
+    /// Milliseconds
+    duration: u32,
+    dispose: u8,
+    blend: bool,
+}
+
+impl FrameControl {
+    fn parse(data: &[u8], header: &Header) -> Result<FrameControl, String> {
+        if data.len() < 26 {
+            return Err("fcTL chunk too short".to_string());
+        }
+        let be32 = |o: usize| u32::from_be_bytes([data[o], data[o + 1], data[o + 2], data[o + 3]]);
+        let rect = Rect { width: be32(4), height: be32(8), x: be32(12), y: be32(16) };
+        if rect.width == 0
+            || rect.height == 0
+            || rect.x as u64 + rect.width as u64 > header.width as u64
+            || rect.y as u64 + rect.height as u64 > header.height as u64
+        {
+            return Err("APNG frame outside the canvas".to_string());
+        }
+        let num = u16::from_be_bytes([data[20], data[21]]) as u32;
+        // A zero denominator means hundredths of a second
+        let den = match u16::from_be_bytes([data[22], data[23]]) {
+            0 => 100,
+            d => d as u32,
+        };
+        Ok(FrameControl {
+            rect,
+            duration: (num * 1000 + den / 2) / den,
+            dispose: data[24],
+            blend: data[25] == 1,
+        })
+    }
+}
+
+struct Png {
+    header: Header,
+    colors: Colors,
+    /// Concatenated IDAT data
+    image: Vec<u8>,
+    /// num_plays from acTL, if animated
+    plays: Option<u32>,
+    /// Frames with their concatenated IDAT or fdAT data
+    frames: Vec<(FrameControl, Vec<u8>)>,
+}
+
+fn read_png(data: &[u8]) -> Result<Png, String> {
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return Err("Invalid PNG signature".to_string());
+    }
+    let chunks = png_chunks(data);
+    let ihdr = chunks.first().filter(|c| &c.kind == b"IHDR").ok_or("Missing IHDR chunk")?;
+    let header = Header::parse(ihdr.data)?;
+
+    let mut png = Png { header, colors: Colors::default(), image: Vec::new(), plays: None, frames: Vec::new() };
+    let mut seen_idat = false;
+    for chunk in &chunks[1..] {
+        match &chunk.kind {
+            b"PLTE" => png.colors.set_palette(chunk.data),
+            b"tRNS" => png.colors.set_transparency(chunk.data, header.color_type),
+            #[cfg(feature = "std")]
+            b"iCCP" => png.colors.set_profile(chunk.data),
+            b"acTL" if chunk.data.len() >= 8 && !seen_idat => {
+                png.plays = Some(u32::from_be_bytes([chunk.data[4], chunk.data[5], chunk.data[6], chunk.data[7]]));
+            }
+            b"fcTL" if png.plays.is_some() => png.frames.push((FrameControl::parse(chunk.data, &header)?, Vec::new())),
+            b"IDAT" => {
+                png.image.extend_from_slice(chunk.data);
+                // The default image is the first frame only when an fcTL precedes it
+                if let Some((_, frame)) = png.frames.last_mut() {
+                    frame.extend_from_slice(chunk.data);
+                }
+                seen_idat = true;
+            }
+            b"fdAT" if chunk.data.len() >= 4 => {
+                if let Some((_, frame)) = png.frames.last_mut() {
+                    frame.extend_from_slice(&chunk.data[4..]);
+                }
+            }
+            _ => {}
+        }
+    }
+    if png.image.is_empty() {
+        return Err("Missing IDAT chunk".to_string());
+    }
+    if header.color_type == 3 && png.colors.palette.is_empty() {
+        return Err("Missing PLTE chunk".to_string());
+    }
+    Ok(png)
+}
+
+/// Decode a PNG to RGBA (`[width u32 LE, height u32 LE, rgba...]`)
+///
+/// For APNG this is the default image.
+pub fn decode_png(data: &[u8]) -> Result<Vec<u8>, String> {
+    let png = read_png(data)?;
+    let Header { width, height, .. } = png.header;
+    let pixels = decode_image(&png.image, width, height, &png.header, &png.colors, &mut Budget::new())?;
+
+    let mut output = Vec::with_capacity(8 + pixels.len());
+    output.extend_from_slice(&width.to_le_bytes());
+    output.extend_from_slice(&height.to_le_bytes());
+    output.extend_from_slice(&pixels);
+    Ok(output)
+}
+
+/// Decode an APNG into composited frames; a still PNG becomes one frame
+pub fn decode_apng(data: &[u8]) -> Result<FrameSequence, String> {
+    let png = read_png(data)?;
+    let Header { width, height, .. } = png.header;
+    let mut sequence = FrameSequence::new(width, height);
+    let mut budget = Budget::new();
+    budget.check_size(width as usize, height as usize)?;
+    let frame_bytes = (width as usize).saturating_mul(height as usize).saturating_mul(4);
+
+    let Some(plays) = png.plays.filter(|_| !png.frames.is_empty()) else {
+        budget.frame(frame_bytes)?;
+        sequence.push(decode_image(&png.image, width, height, &png.header, &png.colors, &mut budget)?, 0)?;
+        return Ok(sequence);
+    };
+    sequence.loop_count = plays;
+
+    let mut canvas = Canvas::new(width, height);
+    for (control, zdata) in &png.frames {
+        let rect = control.rect;
+        budget.frame(frame_bytes)?;
+        let pixels = decode_image(zdata, rect.width, rect.height, &png.header, &png.colors, &mut budget)?;
+        let previous = (control.dispose == 2).then(|| canvas.data.clone());
+        canvas.draw(&pixels, rect, control.blend);
+        sequence.push(canvas.data.clone(), control.duration)?;
+        match (control.dispose, previous) {
+            (1, _) => canvas.clear(rect),
+            (2, Some(previous)) => canvas.data = previous,
+            _ => {}
+        }
+    }
+    Ok(sequence)
+}
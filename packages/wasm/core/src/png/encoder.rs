@@ -0,0 +1,157 @@
+//! PNG and APNG encoder - pure Rust implementation
+
+use super::paeth;
+use crate::animation::{changed_rect, FrameSequence, Rect};
+use crate::checksum::{crc32, crc32_update};
+use crate::compress::zlib_compress;
+use crate::metadata::container::PNG_SIGNATURE;
+use crate::transform::{crop, CropRegion};
+use crate::utils::check_rgba;
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc32_update(crc32(kind), data).to_be_bytes());
+}
+
+/// Filter each row with whichever of the five filters minimizes the sum of
+/// absolute signed residuals, the usual libpng heuristic
+fn filter(pixels: &[u8], row_bytes: usize, bpp: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(pixels.len() + pixels.len() / row_bytes);
+    let zero = vec![0u8; row_bytes];
+    let mut candidate = vec![0u8; row_bytes];
+    let mut best = vec![0u8; row_bytes];
+    for (y, cur) in pixels.chunks_exact(row_bytes).enumerate() {
+        let prev = if y == 0 { &zero[..] } else { &pixels[(y - 1) * row_bytes..y * row_bytes] };
+        let mut best_type = 0;
+        let mut best_cost = u64::MAX;
+        for kind in 0..5u8 {
+            for i in 0..row_bytes {
+                let a = if i >= bpp { cur[i - bpp] } else { 0 };
+                let c = if i >= bpp { prev[i - bpp] } else { 0 };
+                let predicted = match kind {
+                    0 => 0,
+                    1 => a,
+                    2 => prev[i],
+                    3 => ((a as u16 + prev[i] as u16) / 2) as u8,
+                    _ => paeth(a, prev[i], c),
+                };
+                candidate[i] = cur[i].wrapping_sub(predicted);
+            }
+            let cost: u64 = candidate.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_type = kind;
+                core::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        out.push(best_type);
+        out.extend_from_slice(&best);
+    }
+    out
+}
+
+/// Compressed image data for a `width`-pixel-wide RGBA image, dropping alpha when `opaque`
+fn image_data(rgba: &[u8], width: u32, opaque: bool) -> Vec<u8> {
+    let channels = if opaque { 3 } else { 4 };
+    let pixels: Vec<u8> = if opaque { rgba.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect() } else { rgba.to_vec() };
+    zlib_compress(&filter(&pixels, width as usize * channels, channels), 6)
+}
+
+fn header(width: u32, height: u32, opaque: bool) -> Vec<u8> {
+    let mut out = PNG_SIGNATURE.to_vec();
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // 8-bit truecolor, with alpha unless every pixel is opaque
+    ihdr.extend_from_slice(&[8, if opaque { 2 } else { 6 }, 0, 0, 0]);
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    out
+}
+
+/// Encode RGBA to an 8-bit truecolor PNG
+pub fn encode_png(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+    encode_png_rgba(width, height, data, None)
+}
+
+/// Encode RGBA to an 8-bit truecolor PNG with an embedded ICC profile
+pub fn encode_png_with_profile(width: u32, height: u32, data: &[u8], profile: &[u8]) -> Result<Vec<u8>, String> {
+    if profile.is_empty() {
+        return Err("ICC profile is empty".to_string());
+    }
+    encode_png_rgba(width, height, data, Some(profile))
+}
+
+fn encode_png_rgba(width: u32, height: u32, data: &[u8], profile: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if width == 0 || height == 0 {
+        return Err("PNG dimensions must be non-zero".to_string());
+    }
+    let opaque = data.chunks_exact(4).all(|p| p[3] == 255);
+    let mut out = header(width, height, opaque);
+    if let Some(profile) = profile {
+        // Profile name, NUL, compression method 0, then the zlib stream
+        let mut iccp = b"ICC profile\0\0".to_vec();
+        iccp.extend_from_slice(&zlib_compress(profile, 6));
+        write_chunk(&mut out, b"iCCP", &iccp);
+    }
+    write_chunk(&mut out, b"IDAT", &image_data(data, width, opaque));
+    write_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+/// Encode a frame sequence as APNG
+///
+/// The first frame is the default image; later frames store only the
+/// rectangle that changed and replace it outright (APNG_BLEND_OP_SOURCE).
+pub fn encode_apng(sequence: &FrameSequence) -> Result<Vec<u8>, String> {
+    let (width, height) = (sequence.width(), sequence.height());
+    let frames = sequence.frames();
+    if frames.is_empty() {
+        return Err("Animation has no frames".to_string());
+    }
+    if width == 0 || height == 0 {
+        return Err("PNG dimensions must be non-zero".to_string());
+    }
+    let opaque = frames.iter().all(|f| f.data.chunks_exact(4).all(|p| p[3] == 255));
+
+    let mut out = header(width, height, opaque);
+    let mut actl = (frames.len() as u32).to_be_bytes().to_vec();
+    actl.extend_from_slice(&sequence.loop_count.to_be_bytes());
+    write_chunk(&mut out, b"acTL", &actl);
+
+    let mut sequence_number = 0u32;
+    for (i, frame) in frames.iter().enumerate() {
+        // Unchanged frames still need a non-empty rectangle
+        let full = Rect { x: 0, y: 0, width, height };
+        let rect = match i {
+            0 => full,
+            _ => changed_rect(&frames[i - 1].data, &frame.data, width).unwrap_or(Rect { width: 1, height: 1, ..full }),
+        };
+        let mut fctl = Vec::with_capacity(26);
+        for v in [sequence_number, rect.width, rect.height, rect.x, rect.y] {
+            fctl.extend_from_slice(&v.to_be_bytes());
+        }
+        // Delays are 16-bit fractions; larger ones fall back to hundredths
+        let (num, den) = if frame.duration <= u16::MAX as u32 { (frame.duration, 1000) } else { ((frame.duration / 10).min(u16::MAX as u32), 100) };
+        fctl.extend_from_slice(&(num as u16).to_be_bytes());
+        fctl.extend_from_slice(&(den as u16).to_be_bytes());
+        fctl.extend_from_slice(&[0, 0]);
+        write_chunk(&mut out, b"fcTL", &fctl);
+        sequence_number += 1;
+
+        if i == 0 {
+            write_chunk(&mut out, b"IDAT", &image_data(&frame.data, width, opaque));
+            continue;
+        }
+        let region = crop(&frame.data, width, height, CropRegion { x: rect.x, y: rect.y, width: rect.width, height: rect.height })?;
+        let mut fdat = sequence_number.to_be_bytes().to_vec();
+        fdat.extend_from_slice(&image_data(&region, rect.width, opaque));
+        write_chunk(&mut out, b"fdAT", &fdat);
+        sequence_number += 1;
+    }
+    write_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
@@ -0,0 +1,165 @@
+//! PNG and APNG codec implementation in pure Rust
+
+mod decoder;
+mod encoder;
+
+pub use decoder::{decode_apng, decode_png};
+pub use encoder::{encode_apng, encode_png, encode_png_with_profile};
+
+#[cfg(feature = "wasm")]
+use crate::animation::FrameSequence;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Paeth predictor (PNG filter type 4)
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Decode PNG to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodePng)]
+pub fn decode_png_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    decode_png(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode RGBA to PNG
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodePng)]
+pub fn encode_png_js(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, JsError> {
+    encode_png(width, height, data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode RGBA to PNG with an embedded ICC profile
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodePngWithProfile)]
+pub fn encode_png_with_profile_js(width: u32, height: u32, data: &[u8], profile: &[u8]) -> Result<Vec<u8>, JsError> {
+    encode_png_with_profile(width, height, data, profile).map_err(|e| JsError::new(&e))
+}
+
+/// Decode an APNG (or still PNG) to composited frames
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeApng)]
+pub fn decode_apng_js(data: &[u8]) -> Result<FrameSequence, JsError> {
+    decode_apng(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode frames as APNG
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeApng)]
+pub fn encode_apng_js(sequence: &FrameSequence) -> Result<Vec<u8>, JsError> {
+    encode_apng(sequence).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::{crc32, crc32_update};
+    use crate::compress::zlib_compress;
+    use crate::metadata::container::PNG_SIGNATURE;
+
+    fn png(ihdr: [u8; 13], extra: &[(&[u8; 4], &[u8])], raw: &[u8]) -> Vec<u8> {
+        let mut out = PNG_SIGNATURE.to_vec();
+        let idat = zlib_compress(raw, 6);
+        let chunks = [(b"IHDR", &ihdr[..])].into_iter().chain(extra.iter().copied()).chain([(b"IDAT", &idat[..]), (b"IEND", &[][..])]);
+        for (kind, data) in chunks {
+            out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            out.extend_from_slice(kind);
+            out.extend_from_slice(data);
+            out.extend_from_slice(&crc32_update(crc32(kind), data).to_be_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let data: Vec<u8> = (0..5 * 3).flat_map(|i| [i as u8 * 17, 255 - i as u8, (i * i) as u8, if i == 4 { 0 } else { 255 }]).collect();
+        let encoded = encode_png(5, 3, &data).unwrap();
+        let decoded = decode_png(&encoded).unwrap();
+        assert_eq!(&decoded[..8], &[5, 0, 0, 0, 3, 0, 0, 0]);
+        assert_eq!(&decoded[8..], &data[..]);
+        assert!(decode_png(&encoded[..encoded.len() - 20]).is_err());
+    }
+
+    #[test]
+    fn test_low_bit_depths() {
+        // 2-bit palette, 3x2, with a transparent entry
+        let ihdr = [0, 0, 0, 3, 0, 0, 0, 2, 2, 3, 0, 0, 0];
+        let plte = [255, 0, 0, 0, 255, 0, 0, 0, 255];
+        let raw = [0, 0b00_01_10_00, 0, 0b10_00_01_00];
+        let decoded = decode_png(&png(ihdr, &[(b"PLTE", &plte), (b"tRNS", &[255, 128])], &raw)).unwrap();
+        assert_eq!(&decoded[8..20], &[255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 255]);
+        assert_eq!(&decoded[20..24], &[0, 0, 255, 255]);
+
+        // 16-bit gray with a transparent key, Sub and Up filtered
+        let ihdr = [0, 0, 0, 2, 0, 0, 0, 2, 16, 0, 0, 0, 0];
+        let raw = [1, 0x12, 0x34, 0x01, 0x00, 2, 0x10, 0x00, 0x00, 0x00];
+        let decoded = decode_png(&png(ihdr, &[(b"tRNS", &[0x13, 0x34])], &raw)).unwrap();
+        assert_eq!(&decoded[8..], &[0x12, 0x12, 0x12, 255, 0x13, 0x13, 0x13, 0, 0x22, 0x22, 0x22, 255, 0x13, 0x13, 0x13, 0]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_embedded_icc_profile() {
+        let data: Vec<u8> = (0..4).flat_map(|i| [200, 60 * i as u8, 40, 255]).collect();
+        let plain = encode_png(2, 2, &data).unwrap();
+        let profile = crate::icc::BuiltinProfile::DisplayP3.to_bytes();
+        let expected = crate::icc::apply_icc_profile(&data, 2, 2, &profile).unwrap();
+        assert_ne!(expected, data);
+
+        let mut iccp = b"Display P3\0\0".to_vec();
+        iccp.extend_from_slice(&zlib_compress(&profile, 6));
+        let raw: Vec<u8> = data.chunks_exact(8).flat_map(|row| [0].into_iter().chain(row.iter().copied())).collect();
+        let ihdr = [0, 0, 0, 2, 0, 0, 0, 2, 8, 6, 0, 0, 0];
+        assert_eq!(decode_png(&png(ihdr, &[(b"iCCP", &iccp)], &raw)).unwrap()[8..], expected);
+        assert_eq!(decode_png(&plain).unwrap()[8..], data);
+
+        // A profile claiming 4 billion tags is ignored rather than allocated for
+        let mut corrupt = profile.clone();
+        corrupt[128..132].fill(0xFF);
+        let mut iccp = b"Display P3\0\0".to_vec();
+        iccp.extend_from_slice(&zlib_compress(&corrupt, 6));
+        assert_eq!(decode_png(&png(ihdr, &[(b"iCCP", &iccp)], &raw)).unwrap()[8..], data);
+
+        // The encoder's iCCP chunk reads back as the same profile
+        let encoded = encode_png_with_profile(2, 2, &data, &profile).unwrap();
+        assert_eq!(decode_png(&encoded).unwrap()[8..], expected);
+        assert!(encode_png_with_profile(2, 2, &data, &[]).is_err());
+    }
+
+    #[test]
+    fn test_adam7() {
+        // 3x3 8-bit gray: passes 2 and 3 are empty, pass 6 has two rows
+        let ihdr = [0, 0, 0, 3, 0, 0, 0, 3, 8, 0, 0, 0, 1];
+        let raw = [0, 0, 0, 2, 0, 6, 8, 0, 1, 0, 7, 0, 3, 4, 5];
+        let decoded = decode_png(&png(ihdr, &[], &raw)).unwrap();
+        let gray: Vec<u8> = decoded[8..].chunks_exact(4).map(|p| p[0]).collect();
+        assert_eq!(gray, [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_deflate_bomb() {
+        // Inflation stops at the scanlines the header calls for
+        let bomb = vec![0u8; 1 << 20];
+        let ihdr = [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0];
+        assert_eq!(decode_png(&png(ihdr, &[], &bomb)), Err("Decompressed size exceeds limit of 2 bytes".to_string()));
+        let ihdr = [0, 0, 0, 3, 0, 0, 0, 3, 8, 0, 0, 0, 1];
+        assert_eq!(decode_png(&png(ihdr, &[], &bomb)), Err("Decompressed size exceeds limit of 15 bytes".to_string()));
+    }
+
+    #[test]
+    fn test_default_limits() {
+        // A corrupt IHDR asking for 16 GiB of pixels fails before any allocation
+        let ihdr = [0, 0, 0xFF, 0xFF, 0, 0, 0xFF, 0xFF, 8, 6, 0, 0, 0];
+        let error = decode_png(&png(ihdr, &[], &[0])).unwrap_err();
+        assert!(error.starts_with("Image is 65535x65535, over the limit of"), "{}", error);
+    }
+}
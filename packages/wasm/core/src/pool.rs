@@ -0,0 +1,224 @@
+//! Pool of scratch buffers reused across operations
+//!
+//! Decoders, resizing and filters need large temporary planes (JPEG
+//! coefficients, PNG scanlines, VP8 planes, resampled rows, integral images)
+//! that die as soon as the call returns.
+//! Allocating them fresh for every video frame fragments the WASM heap,
+//! which can only grow, so they are drawn from a per-thread pool instead:
+//! `take` hands out a buffer and dropping it gives the buffer back for the
+//! next call. `reset` releases the idle buffers to the allocator, e.g.
+//! between unrelated jobs.
+//!
+//! Without `std` there are no thread locals to keep idle buffers in, so
+//! `take` allocates and dropping frees, and callers need no separate path.
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+use core::fmt;
+use core::mem::size_of;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Idle buffers kept per element type and thread; returning one more frees the smallest
+const MAX_IDLE: usize = 4;
+
+static IDLE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Element types with a pool
+pub trait Poolable: Copy + 'static {
+    #[doc(hidden)]
+    fn with_idle<R>(f: impl FnOnce(&mut Vec<Vec<Self>>) -> R) -> Option<R>;
+}
+
+macro_rules! poolable {
+    ($($t:ty),*) => {
+        $(
+            impl Poolable for $t {
+                #[cfg(feature = "std")]
+                fn with_idle<R>(f: impl FnOnce(&mut Vec<Vec<Self>>) -> R) -> Option<R> {
+                    thread_local! {
+                        static IDLE: RefCell<Vec<Vec<$t>>> = const { RefCell::new(Vec::new()) };
+                    }
+                    // Fails only while the thread is shutting down
+                    IDLE.try_with(|idle| f(&mut idle.borrow_mut())).ok()
+                }
+
+                #[cfg(not(feature = "std"))]
+                fn with_idle<R>(_: impl FnOnce(&mut Vec<Vec<Self>>) -> R) -> Option<R> {
+                    None
+                }
+            }
+        )*
+
+        /// Release this thread's idle buffers to the allocator
+        pub fn reset() {
+            $(
+                let freed: usize = <$t>::with_idle(core::mem::take).unwrap_or_default().iter().map(bytes).sum();
+                IDLE_BYTES.fetch_sub(freed, Ordering::Relaxed);
+            )*
+        }
+    };
+}
+
+// The tuple is a resampling tap: two source indices and a blend weight
+poolable!(u8, i16, u16, i32, u32, u64, f32, f64, (usize, usize, f32));
+
+fn bytes<T>(buf: &Vec<T>) -> usize {
+    buf.capacity() * size_of::<T>()
+}
+
+/// A pooled buffer, returned to the pool when dropped
+pub struct Scratch<T: Poolable> {
+    buf: Vec<T>,
+    /// Bytes counted as lent out, from the capacity when taken
+    lent: usize,
+}
+
+impl<T: Poolable> Scratch<T> {
+    /// Keep the contents past the scratch's lifetime; the buffer leaves the pool
+    pub fn into_vec(mut self) -> Vec<T> {
+        LENT_BYTES.fetch_sub(self.lent, Ordering::Relaxed);
+        self.lent = 0;
+        core::mem::take(&mut self.buf)
+    }
+}
+
+impl<T: Poolable> Default for Scratch<T> {
+    fn default() -> Self {
+        Scratch { buf: Vec::new(), lent: 0 }
+    }
+}
+
+impl<T: Poolable> Deref for Scratch<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.buf
+    }
+}
+
+impl<T: Poolable> DerefMut for Scratch<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.buf
+    }
+}
+
+impl<T: Poolable + fmt::Debug> fmt::Debug for Scratch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.buf.fmt(f)
+    }
+}
+
+impl<T: Poolable> Drop for Scratch<T> {
+    fn drop(&mut self) {
+        LENT_BYTES.fetch_sub(self.lent, Ordering::Relaxed);
+        let buf = core::mem::take(&mut self.buf);
+        if buf.capacity() == 0 {
+            return;
+        }
+        let returned = bytes(&buf);
+        let freed = T::with_idle(|idle| {
+            idle.push(buf);
+            if idle.len() <= MAX_IDLE {
+                return 0;
+            }
+            let smallest = (0..idle.len()).min_by_key(|&i| idle[i].capacity()).unwrap_or(0);
+            bytes(&idle.swap_remove(smallest))
+        });
+        // A thread shutting down drops the buffer instead
+        IDLE_BYTES.fetch_add(freed.map_or(0, |freed| returned - freed), Ordering::Relaxed);
+    }
+}
+
+/// An idle buffer able to hold `capacity` elements, or a new one
+fn take_buffer<T: Poolable>(capacity: usize) -> Scratch<T> {
+    let buf = T::with_idle(|idle| {
+        // Best fit, else grow the largest so small buffers stay free for small requests
+        let fit = (0..idle.len()).filter(|&i| idle[i].capacity() >= capacity).min_by_key(|&i| idle[i].capacity());
+        let pick = fit.or_else(|| (0..idle.len()).max_by_key(|&i| idle[i].capacity()));
+        pick.map(|i| idle.swap_remove(i))
+    })
+    .flatten();
+
+    let mut buf = match buf {
+        Some(buf) => {
+            IDLE_BYTES.fetch_sub(bytes(&buf), Ordering::Relaxed);
+            buf
+        }
+        None => Vec::with_capacity(capacity),
+    };
+    buf.clear();
+    buf.reserve(capacity);
+    let lent = bytes(&buf);
+    LENT_BYTES.fetch_add(lent, Ordering::Relaxed);
+    Scratch { buf, lent }
+}
+
+/// A scratch buffer of `len` copies of `value`, like `vec![value; len]`
+pub fn take<T: Poolable>(len: usize, value: T) -> Scratch<T> {
+    let mut scratch = take_buffer(len);
+    scratch.resize(len, value);
+    scratch
+}
+
+/// An empty scratch buffer with room for `capacity` elements, like `Vec::with_capacity`
+pub fn take_empty<T: Poolable>(capacity: usize) -> Scratch<T> {
+    take_buffer(capacity)
+}
+
+/// Bytes in pooled buffers across all threads: (idle, lent out)
+pub fn pool_bytes() -> (usize, usize) {
+    (IDLE_BYTES.load(Ordering::Relaxed), LENT_BYTES.load(Ordering::Relaxed))
+}
+
+/// Free the scratch buffers kept for reuse, e.g. after a video is done
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = resetBufferPool)]
+pub fn reset_js() {
+    reset()
+}
+
+// Without std nothing is pooled
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    // Pools are per thread, so each test sees only its own buffers
+    #[test]
+    fn test_reuse() {
+        let first = take(1000, 7u32);
+        assert_eq!(first.len(), 1000);
+        assert!(first.iter().all(|&v| v == 7));
+        let ptr = first.as_ptr();
+        drop(first);
+
+        let second = take(500, 0u32);
+        assert_eq!(second.as_ptr(), ptr);
+        assert!(second.iter().all(|&v| v == 0));
+        let other = take_empty::<u32>(10);
+        assert_ne!(other.as_ptr(), ptr);
+        assert!(other.is_empty() && other.capacity() >= 10);
+
+        let kept = second.into_vec();
+        assert_eq!(kept.len(), 500);
+        drop(other);
+        reset();
+        // `kept` left the pool, so its buffer is not handed out again
+        assert_ne!(take(10, 0u32).as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_idle_limit() {
+        let buffers: Vec<_> = (1..=MAX_IDLE + 2).map(|n| take(n * 100, 0u16)).collect();
+        drop(buffers);
+        let idle = u16::with_idle(|idle| idle.iter().map(|b| b.capacity()).collect::<Vec<_>>()).unwrap();
+        assert_eq!(idle.len(), MAX_IDLE);
+        assert!(idle.iter().all(|&c| c >= 300));
+        reset();
+        assert_eq!(u16::with_idle(|idle| idle.len()), Some(0));
+    }
+}
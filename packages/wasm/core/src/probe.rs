@@ -0,0 +1,275 @@
+//! Header-only image inspection (format, dimensions, depth, frames, color and density metadata)
+
+use crate::metadata::container::{jpeg_segments, png_chunks, riff_chunks};
+use crate::metadata::{find_exif, read_dpi, Exif};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Basic facts about an image, read without decoding pixels
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageInfo {
+    /// Lowercase format name, e.g. "png"
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    /// Bits per channel sample (or per index for palette images)
+    pub bit_depth: u8,
+    /// Color channels including alpha
+    pub channels: u8,
+    /// Number of frames (1 for still images)
+    pub frames: u32,
+    pub has_icc: bool,
+    /// EXIF orientation (1-8), when present
+    pub orientation: Option<u16>,
+    /// Horizontal dots per inch, when recorded
+    pub dpi_x: Option<f32>,
+    /// Vertical dots per inch, when recorded
+    pub dpi_y: Option<f32>,
+}
+
+impl ImageInfo {
+    fn new(format: &str, width: u32, height: u32, bit_depth: u8, channels: u8) -> ImageInfo {
+        ImageInfo {
+            format: format.to_string(),
+            width,
+            height,
+            bit_depth,
+            channels,
+            frames: 1,
+            has_icc: false,
+            orientation: None,
+            dpi_x: None,
+            dpi_y: None,
+        }
+    }
+}
+
+fn be16(d: &[u8], o: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(d.get(o..o + 2)?.try_into().ok()?) as u32)
+}
+
+fn be32(d: &[u8], o: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(d.get(o..o + 4)?.try_into().ok()?))
+}
+
+fn le16(d: &[u8], o: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(d.get(o..o + 2)?.try_into().ok()?) as u32)
+}
+
+fn le24(d: &[u8], o: usize) -> Option<u32> {
+    let b = d.get(o..o + 3)?;
+    Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+}
+
+fn le32(d: &[u8], o: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(d.get(o..o + 4)?.try_into().ok()?))
+}
+
+fn probe_bmp(data: &[u8]) -> Option<ImageInfo> {
+    let dib_size = le32(data, 14)?;
+    let width = (le32(data, 18)? as i32).unsigned_abs();
+    let height = (le32(data, 22)? as i32).unsigned_abs();
+    let bpp = le16(data, 28)? as u8;
+    let (bit_depth, channels) = match bpp {
+        1 | 4 | 8 => (bpp, 3),
+        16 => (5, 3),
+        32 if dib_size >= 56 || le32(data, 30)? == 3 => (8, 4),
+        _ => (8, 3),
+    };
+    #[cfg_attr(not(feature = "bmp"), allow(unused_mut))]
+    let mut info = ImageInfo::new("bmp", width, height, bit_depth, channels);
+    #[cfg(feature = "bmp")]
+    {
+        info.has_icc = crate::bmp::embedded_icc_profile(data).is_some();
+    }
+    Some(info)
+}
+
+fn probe_png(data: &[u8]) -> Option<ImageInfo> {
+    let chunks = png_chunks(data);
+    let ihdr = chunks.first().filter(|c| &c.kind == b"IHDR")?.data;
+    let has_trns = chunks.iter().any(|c| &c.kind == b"tRNS");
+    let channels = match *ihdr.get(9)? {
+        0 => 1 + has_trns as u8,
+        2 => 3 + has_trns as u8,
+        3 => 3 + has_trns as u8,
+        4 => 2,
+        6 => 4,
+        _ => return None,
+    };
+    let mut info = ImageInfo::new("png", be32(ihdr, 0)?, be32(ihdr, 4)?, ihdr[8], channels);
+    info.has_icc = chunks.iter().any(|c| &c.kind == b"iCCP");
+    if let Some(actl) = chunks.iter().find(|c| &c.kind == b"acTL") {
+        info.format = "apng".to_string();
+        info.frames = be32(actl.data, 0)?;
+    }
+    Some(info)
+}
+
+fn probe_jpeg(data: &[u8]) -> Option<ImageInfo> {
+    let segments = jpeg_segments(data);
+    // SOF0-SOF15, excluding DHT (C4), JPG (C8), and DAC (CC)
+    let sof = segments
+        .iter()
+        .find(|s| (0xC0..=0xCF).contains(&s.marker) && ![0xC4, 0xC8, 0xCC].contains(&s.marker))?;
+    let mut info = ImageInfo::new("jpeg", be16(sof.data, 3)?, be16(sof.data, 1)?, sof.data[0], *sof.data.get(5)?);
+    info.has_icc = segments
+        .iter()
+        .any(|s| s.marker == 0xE2 && s.data.starts_with(b"ICC_PROFILE\0"));
+    Some(info)
+}
+
+/// Skip a chain of GIF data sub-blocks starting at `pos`
+fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *data.get(pos)? as usize;
+        pos += 1 + len;
+        if len == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+fn probe_gif(data: &[u8]) -> Option<ImageInfo> {
+    let packed = *data.get(10)?;
+    let mut info = ImageInfo::new("gif", le16(data, 6)?, le16(data, 8)?, (packed & 7) + 1, 3);
+    info.frames = 0;
+
+    let mut pos = 13;
+    if packed & 0x80 != 0 {
+        pos += 3 << ((packed & 7) + 1);
+    }
+    while let Some(&block) = data.get(pos) {
+        match block {
+            0x21 => {
+                // Graphic control extension with the transparency flag set
+                if data.get(pos + 1) == Some(&0xF9) && data.get(pos + 3).is_some_and(|f| f & 1 != 0) {
+                    info.channels = 4;
+                }
+                if data.get(pos + 1) == Some(&0xFF) && data.get(pos + 3..pos + 14) == Some(b"ICCRGBG1012") {
+                    info.has_icc = true;
+                }
+                pos = skip_sub_blocks(data, pos + 2)?;
+            }
+            0x2C => {
+                info.frames += 1;
+                let local = *data.get(pos + 9)?;
+                pos += 10;
+                if local & 0x80 != 0 {
+                    pos += 3 << ((local & 7) + 1);
+                }
+                pos = skip_sub_blocks(data, pos + 1)?;
+            }
+            _ => break,
+        }
+    }
+    info.frames = info.frames.max(1);
+    Some(info)
+}
+
+fn probe_webp(data: &[u8]) -> Option<ImageInfo> {
+    let chunks = riff_chunks(data, b"WEBP");
+    let first = chunks.first()?;
+    let mut info = match &first.fourcc {
+        b"VP8 " => {
+            let d = first.data;
+            if d.get(3..6) != Some(&[0x9D, 0x01, 0x2A]) {
+                return None;
+            }
+            ImageInfo::new("webp", le16(d, 6)? & 0x3FFF, le16(d, 8)? & 0x3FFF, 8, 3)
+        }
+        b"VP8L" => {
+            let d = first.data;
+            if d.first() != Some(&0x2F) {
+                return None;
+            }
+            let bits = le32(d, 1)?;
+            let alpha = (bits >> 28) & 1 != 0;
+            ImageInfo::new("webp", (bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1, 8, 3 + alpha as u8)
+        }
+        b"VP8X" => {
+            let d = first.data;
+            let flags = *d.first()?;
+            let mut info = ImageInfo::new("webp", le24(d, 4)? + 1, le24(d, 7)? + 1, 8, if flags & 0x10 != 0 { 4 } else { 3 });
+            info.has_icc = flags & 0x20 != 0;
+            if flags & 0x02 != 0 {
+                info.frames = chunks.iter().filter(|c| &c.fourcc == b"ANMF").count() as u32;
+            }
+            info
+        }
+        _ => return None,
+    };
+    info.frames = info.frames.max(1);
+    Some(info)
+}
+
+/// Inspect an image header without decoding pixel data
+pub fn probe(data: &[u8]) -> Result<ImageInfo, String> {
+    let info = if data.starts_with(b"BM") {
+        probe_bmp(data)
+    } else if data.starts_with(&crate::metadata::container::PNG_SIGNATURE) {
+        probe_png(data)
+    } else if data.starts_with(&[0xFF, 0xD8]) {
+        probe_jpeg(data)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        probe_gif(data)
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        probe_webp(data)
+    } else {
+        return Err("Unknown or unsupported image format".to_string());
+    };
+
+    let mut info = info.ok_or("Truncated or malformed image header")?;
+    info.orientation = find_exif(data)
+        .and_then(|tiff| Exif::parse(tiff).ok())
+        .and_then(|exif| exif.orientation());
+    if let Some((x, y)) = read_dpi(data) {
+        info.dpi_x = Some(x);
+        info.dpi_y = Some(y);
+    }
+    Ok(info)
+}
+
+/// Inspect an image header without decoding pixel data
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = probe)]
+pub fn probe_js(data: &[u8]) -> Result<ImageInfo, JsError> {
+    probe(data).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "bmp")]
+    fn test_probe_bmp_and_png() {
+        let bmp = crate::bmp::encode_bmp(3, 2, &[0; 24]).unwrap();
+        let info = probe(&bmp).unwrap();
+        assert_eq!((info.format.as_str(), info.width, info.height, info.channels), ("bmp", 3, 2, 4));
+        assert!(!info.has_icc);
+
+        let mut png = crate::metadata::container::PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(&13u32.to_be_bytes());
+        png.extend_from_slice(b"IHDR");
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+        png.extend_from_slice(&[16, 2, 0, 0, 0, 0, 0, 0, 0]);
+        let info = probe(&png).unwrap();
+        assert_eq!((info.width, info.height, info.bit_depth, info.channels, info.frames), (640, 480, 16, 3, 1));
+    }
+
+    #[test]
+    fn test_probe_gif_frames() {
+        let mut gif = b"GIF89a\x02\0\x01\0\x80\0\0".to_vec();
+        gif.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+        for _ in 0..3 {
+            gif.extend_from_slice(&[0x21, 0xF9, 4, 1, 0, 0, 0, 0]);
+            gif.extend_from_slice(&[0x2C, 0, 0, 0, 0, 2, 0, 1, 0, 0, 2, 2, 0x44, 0x01, 0]);
+        }
+        gif.push(0x3B);
+        let info = probe(&gif).unwrap();
+        assert_eq!((info.width, info.height, info.frames, info.channels), (2, 1, 3, 4));
+    }
+}
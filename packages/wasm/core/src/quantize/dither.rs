@@ -0,0 +1,185 @@
+//! Error-diffusion (Floyd–Steinberg) and ordered (Bayer) dithering
+
+use super::nearest_index;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Dithering algorithm
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DitherMethod {
+    None = 0,
+    FloydSteinberg = 1,
+    Bayer4 = 2,
+    Bayer8 = 3,
+}
+
+/// Strength of ordered dithering against an arbitrary palette, in 8-bit units
+const PALETTE_SPREAD: f32 = 32.0;
+
+/// Bayer threshold in (-0.5, 0.5) for a pixel
+fn bayer_offset(x: usize, y: usize, size: usize) -> f32 {
+    // Interleave bits of x^y and y, least significant coordinate bits first
+    let bits = size.trailing_zeros();
+    let xc = x ^ y;
+    let mut v = 0usize;
+    for bit in 0..bits {
+        v = (v << 2) | (((xc >> bit) & 1) << 1) | ((y >> bit) & 1);
+    }
+    (v as f32 + 0.5) / (size * size) as f32 - 0.5
+}
+
+/// Shared driver: `pick(index, color)` snaps a color and returns the value it chose
+fn diffuse(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    method: DitherMethod,
+    spread: f32,
+    mut pick: impl FnMut(usize, [f32; 4]) -> [f32; 4],
+) {
+    match method {
+        DitherMethod::None => {
+            for (i, p) in data.chunks_exact(4).enumerate() {
+                pick(i, p_to_f32(p));
+            }
+        }
+        DitherMethod::Bayer4 | DitherMethod::Bayer8 => {
+            let size = if method == DitherMethod::Bayer4 { 4 } else { 8 };
+            for (i, p) in data.chunks_exact(4).enumerate() {
+                let offset = bayer_offset(i % width, i / width, size) * spread;
+                let mut c = p_to_f32(p);
+                for v in c.iter_mut().take(3) {
+                    *v = (*v + offset).clamp(0.0, 255.0);
+                }
+                pick(i, c);
+            }
+        }
+        DitherMethod::FloydSteinberg => {
+            let mut buf: Vec<f32> = data.iter().map(|&v| v as f32).collect();
+            for y in 0..height {
+                // Serpentine scan reduces directional artifacts
+                let reverse = y % 2 == 1;
+                for step in 0..width {
+                    let x = if reverse { width - 1 - step } else { step };
+                    let i = y * width + x;
+                    let mut c = [0f32; 4];
+                    for (ch, v) in c.iter_mut().enumerate() {
+                        *v = buf[i * 4 + ch].clamp(0.0, 255.0);
+                    }
+
+                    let chosen = pick(i, c);
+                    let err: [f32; 4] = std::array::from_fn(|ch| c[ch] - chosen[ch]);
+
+                    let forward: isize = if reverse { -1 } else { 1 };
+                    let mut spread_to = |dx: isize, dy: usize, weight: f32| {
+                        let nx = x as isize + dx;
+                        let ny = y + dy;
+                        if nx < 0 || nx >= width as isize || ny >= height {
+                            return;
+                        }
+                        let j = (ny * width + nx as usize) * 4;
+                        for (ch, e) in err.iter().enumerate() {
+                            buf[j + ch] += e * weight;
+                        }
+                    };
+                    spread_to(forward, 0, 7.0 / 16.0);
+                    spread_to(-forward, 1, 3.0 / 16.0);
+                    spread_to(0, 1, 5.0 / 16.0);
+                    spread_to(forward, 1, 1.0 / 16.0);
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn p_to_f32(p: &[u8]) -> [f32; 4] {
+    [p[0] as f32, p[1] as f32, p[2] as f32, p[3] as f32]
+}
+
+#[inline]
+fn to_u8(c: [f32; 4]) -> [u8; 4] {
+    c.map(|v| v.round().clamp(0.0, 255.0) as u8)
+}
+
+/// Map pixels to indices into a palette of 1-256 colors with dithering
+pub fn dither_to_palette(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    palette: &[[u8; 4]],
+    method: DitherMethod,
+) -> Result<Vec<u8>, String> {
+    crate::utils::check_rgba(data, width, height)?;
+    if !(1..=256).contains(&palette.len()) {
+        return Err(format!("Palette size must be 1-256, got {}", palette.len()));
+    }
+
+    let mut indices = vec![0u8; (width * height) as usize];
+    diffuse(data, width as usize, height as usize, method, PALETTE_SPREAD, |i, c| {
+        let index = nearest_index(palette, to_u8(c));
+        indices[i] = index as u8;
+        p_to_f32(&palette[index])
+    });
+    Ok(indices)
+}
+
+/// Reduce each RGB channel to `bits` bits (1-8) with dithering, producing RGBA
+pub fn dither_bit_depth(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bits: u8,
+    method: DitherMethod,
+) -> Result<Vec<u8>, String> {
+    crate::utils::check_rgba(data, width, height)?;
+    if !(1..=8).contains(&bits) {
+        return Err(format!("Bit depth must be 1-8, got {}", bits));
+    }
+
+    let step = 255.0 / ((1u32 << bits) - 1) as f32;
+    let mut output = data.to_vec();
+    diffuse(data, width as usize, height as usize, method, step, |i, c| {
+        let mut q = c;
+        for v in q.iter_mut().take(3) {
+            *v = (*v / step).round() * step;
+        }
+        output[i * 4..i * 4 + 4].copy_from_slice(&to_u8(q));
+        q
+    });
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bayer_matrix_is_permutation() {
+        let mut seen: Vec<f32> = (0..16).map(|i| bayer_offset(i % 4, i / 4, 4)).collect();
+        seen.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for (i, v) in seen.iter().enumerate() {
+            assert!((v - ((i as f32 + 0.5) / 16.0 - 0.5)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_floyd_steinberg_preserves_mean() {
+        // Mid-gray dithered to black/white should be roughly half white
+        let data = vec![128u8; 16 * 16 * 4];
+        let palette = [[0, 0, 0, 128], [255, 255, 255, 128]];
+        let indices = dither_to_palette(&data, 16, 16, &palette, DitherMethod::FloydSteinberg).unwrap();
+        let white = indices.iter().filter(|&&i| i == 1).count();
+        assert!((112..=144).contains(&white), "white = {}", white);
+    }
+
+    #[test]
+    fn test_dither_to_palette_errors() {
+        let data = vec![128u8; 4 * 4 * 4];
+        assert_eq!(dither_to_palette(&data, 4, 4, &[], DitherMethod::Bayer4).unwrap_err(), "Palette size must be 1-256, got 0");
+        assert!(dither_to_palette(&data, 4, 4, &[[0; 4]; 257], DitherMethod::Bayer4).is_err());
+        assert!(dither_to_palette(&data, 4, 5, &[[0; 4]], DitherMethod::Bayer4).is_err());
+        assert_eq!(dither_to_palette(&data, 4, 4, &[[9; 4]], DitherMethod::Bayer4).unwrap(), [0; 16]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Median-cut palette generation
+
+use std::collections::HashMap;
+
+/// A box of distinct colors with their pixel counts
+struct ColorBox {
+    colors: Vec<([u8; 4], u32)>,
+}
+
+impl ColorBox {
+    /// Widest channel and its range
+    fn widest_channel(&self) -> (usize, u8) {
+        let mut best = (0, 0);
+        for c in 0..4 {
+            let min = self.colors.iter().map(|(p, _)| p[c]).min().unwrap_or(0);
+            let max = self.colors.iter().map(|(p, _)| p[c]).max().unwrap_or(0);
+            if max - min > best.1 {
+                best = (c, max - min);
+            }
+        }
+        best
+    }
+
+    fn population(&self) -> u64 {
+        self.colors.iter().map(|&(_, n)| n as u64).sum()
+    }
+
+    /// Population-weighted mean color
+    fn average(&self) -> [u8; 4] {
+        let total = self.population().max(1);
+        let mut sum = [0u64; 4];
+        for (p, n) in &self.colors {
+            for c in 0..4 {
+                sum[c] += p[c] as u64 * *n as u64;
+            }
+        }
+        sum.map(|s| ((s + total / 2) / total) as u8)
+    }
+
+    /// Split at the population median along the widest channel
+    fn split(mut self) -> (ColorBox, ColorBox) {
+        let (channel, _) = self.widest_channel();
+        self.colors.sort_unstable_by_key(|(p, _)| p[channel]);
+
+        let half = self.population() / 2;
+        let mut acc = 0u64;
+        let mut at = 1;
+        for (i, &(_, n)) in self.colors.iter().enumerate() {
+            acc += n as u64;
+            if acc >= half {
+                at = (i + 1).clamp(1, self.colors.len() - 1);
+                break;
+            }
+        }
+
+        let upper = self.colors.split_off(at);
+        (self, ColorBox { colors: upper })
+    }
+}
+
+/// Count distinct RGBA colors, in color order so palettes do not depend on
+/// hash order
+pub(crate) fn color_counts(data: &[u8]) -> Vec<([u8; 4], u32)> {
+    let mut counts: HashMap<[u8; 4], u32> = HashMap::new();
+    for p in data.chunks_exact(4) {
+        *counts.entry([p[0], p[1], p[2], p[3]]).or_insert(0) += 1;
+    }
+    let mut counts: Vec<([u8; 4], u32)> = counts.into_iter().collect();
+    counts.sort_unstable();
+    counts
+}
+
+/// Generate a palette of at most `max_colors` RGBA entries by median cut
+pub fn median_cut_palette(data: &[u8], max_colors: usize) -> Vec<[u8; 4]> {
+    let colors = color_counts(data);
+    if colors.is_empty() {
+        return Vec::new();
+    }
+
+    let mut boxes = vec![ColorBox { colors }];
+    while boxes.len() < max_colors {
+        // Split the box with the widest range, weighting ties by population
+        let candidate = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.colors.len() > 1)
+            .max_by_key(|(_, b)| (b.widest_channel().1, b.population()))
+            .map(|(i, _)| i);
+
+        let Some(i) = candidate else { break };
+        let (a, b) = boxes.swap_remove(i).split();
+        boxes.push(a);
+        boxes.push(b);
+    }
+
+    boxes.iter().map(ColorBox::average).collect()
+}
@@ -0,0 +1,242 @@
+//! Color quantization - palette generation and index mapping
+
+mod dither;
+mod median_cut;
+mod neuquant;
+mod octree;
+mod palette;
+
+pub use dither::{dither_bit_depth, dither_to_palette, DitherMethod};
+pub use median_cut::median_cut_palette;
+pub use neuquant::neuquant_palette;
+pub use octree::octree_palette;
+pub use palette::{dominant_colors, DominantColors};
+
+use median_cut::color_counts;
+
+use crate::utils::check_rgba;
+use std::collections::HashMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Palette generation algorithm
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuantizeMethod {
+    /// Recursive box splitting at the population median; fast, good for flat art
+    MedianCut = 0,
+    /// RGB octree with smallest-node merging; fast with bounded memory
+    Octree = 1,
+    /// Kohonen self-organizing map; slower, best on gradients and photos
+    NeuQuant = 2,
+}
+
+/// Quantization settings
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuantizeOptions {
+    /// Palette size, 1-256
+    pub max_colors: u32,
+    pub method: QuantizeMethod,
+    pub dither: DitherMethod,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl QuantizeOptions {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(max_colors: u32) -> QuantizeOptions {
+        QuantizeOptions {
+            max_colors,
+            method: QuantizeMethod::MedianCut,
+            dither: DitherMethod::None,
+        }
+    }
+}
+
+/// Palette and per-pixel indices produced by quantization
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug)]
+pub struct Quantized {
+    /// RGBA palette entries, 4 bytes each
+    pub palette: Vec<u8>,
+    /// One palette index per pixel
+    pub indices: Vec<u8>,
+}
+
+impl Quantized {
+    /// Expand back to an RGBA buffer
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(self.indices.len() * 4);
+        for &i in &self.indices {
+            let at = i as usize * 4;
+            output.extend_from_slice(&self.palette[at..at + 4]);
+        }
+        output
+    }
+}
+
+/// Index of the palette entry closest to `color` in squared RGBA distance
+pub fn nearest_index(palette: &[[u8; 4]], color: [u8; 4]) -> usize {
+    let mut best = 0;
+    let mut best_dist = u32::MAX;
+    for (i, p) in palette.iter().enumerate() {
+        let mut dist = 0u32;
+        for c in 0..4 {
+            let d = p[c] as i32 - color[c] as i32;
+            dist += (d * d) as u32;
+        }
+        if dist < best_dist {
+            best_dist = dist;
+            best = i;
+        }
+    }
+    best
+}
+
+/// Map every pixel to its nearest palette entry
+pub fn map_to_palette(data: &[u8], palette: &[[u8; 4]]) -> Vec<u8> {
+    let mut cache: HashMap<[u8; 4], u8> = HashMap::new();
+    data.chunks_exact(4)
+        .map(|p| {
+            let color = [p[0], p[1], p[2], p[3]];
+            *cache
+                .entry(color)
+                .or_insert_with(|| nearest_index(palette, color) as u8)
+        })
+        .collect()
+}
+
+/// Generate a palette of at most `max_colors` entries with the given method
+pub fn generate_palette(data: &[u8], max_colors: usize, method: QuantizeMethod) -> Vec<[u8; 4]> {
+    // Images that already fit keep their exact colors
+    if method != QuantizeMethod::MedianCut {
+        let colors = color_counts(data);
+        if colors.len() <= max_colors {
+            return colors.into_iter().map(|(c, _)| c).collect();
+        }
+    }
+
+    match method {
+        QuantizeMethod::MedianCut => median_cut_palette(data, max_colors),
+        QuantizeMethod::Octree => octree_palette(data, max_colors),
+        QuantizeMethod::NeuQuant => neuquant_palette(data, max_colors, 10),
+    }
+}
+
+/// Quantize an RGBA image with the given palette method and dithering
+pub fn quantize_with_options(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    options: &QuantizeOptions,
+) -> Result<Quantized, String> {
+    check_rgba(data, width, height)?;
+    if !(1..=256).contains(&options.max_colors) {
+        return Err(format!("Palette size must be 1-256, got {}", options.max_colors));
+    }
+
+    let palette = generate_palette(data, options.max_colors as usize, options.method);
+    let indices = match options.dither {
+        DitherMethod::None => map_to_palette(data, &palette),
+        // An empty image has an empty palette and nothing to dither
+        _ if data.is_empty() => Vec::new(),
+        dither => dither_to_palette(data, width, height, &palette, dither)?,
+    };
+
+    Ok(Quantized {
+        palette: palette.concat(),
+        indices,
+    })
+}
+
+/// Quantize an RGBA image to at most `max_colors` (1-256) colors using median cut
+pub fn quantize(data: &[u8], width: u32, height: u32, max_colors: u32) -> Result<Quantized, String> {
+    quantize_with_options(data, width, height, &QuantizeOptions::new(max_colors))
+}
+
+/// Quantize an RGBA image to a palette and indices
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = quantize)]
+pub fn quantize_js(data: &[u8], width: u32, height: u32, max_colors: u32) -> Result<Quantized, JsError> {
+    quantize(data, width, height, max_colors).map_err(|e| JsError::new(&e))
+}
+
+/// Quantize an RGBA image with explicit method and dithering
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = quantizeWithOptions)]
+pub fn quantize_with_options_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    options: &QuantizeOptions,
+) -> Result<Quantized, JsError> {
+    quantize_with_options(data, width, height, options).map_err(|e| JsError::new(&e))
+}
+
+/// Reduce RGB channels to a per-channel bit depth with dithering
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = ditherBitDepth)]
+pub fn dither_bit_depth_js(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bits: u8,
+    method: DitherMethod,
+) -> Result<Vec<u8>, JsError> {
+    dither_bit_depth(data, width, height, bits, method).map_err(|e| JsError::new(&e))
+}
+
+/// Extract the top-k dominant colors with their populations
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = dominantColors)]
+pub fn dominant_colors_js(data: &[u8], width: u32, height: u32, k: u32) -> Result<DominantColors, JsError> {
+    dominant_colors(data, width, height, k).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_palette_when_few_colors() {
+        let data = [255, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0, 255, 0, 0, 255, 255];
+        let q = quantize(&data, 2, 2, 16).unwrap();
+        assert_eq!(q.palette.len(), 8);
+        assert_eq!(q.to_rgba(), data.to_vec());
+    }
+
+    #[test]
+    fn test_exact_colors_for_every_method() {
+        let colors = [[255, 0, 0, 255], [0, 128, 0, 255], [0, 0, 255, 128], [17, 34, 51, 0]];
+        let data: Vec<u8> = colors.iter().cycle().take(64).flatten().copied().collect();
+        for method in [QuantizeMethod::Octree, QuantizeMethod::NeuQuant] {
+            let mut palette = generate_palette(&data, 4, method);
+            palette.sort();
+            let mut expected = colors.to_vec();
+            expected.sort();
+            assert_eq!(palette, expected, "{:?}", method);
+            let options = QuantizeOptions { method, ..QuantizeOptions::new(4) };
+            assert_eq!(quantize_with_options(&data, 8, 8, &options).unwrap().to_rgba(), data);
+        }
+    }
+
+    #[test]
+    fn test_gradient_limited_to_max_colors() {
+        let mut data = Vec::new();
+        for i in 0..256u32 {
+            data.extend_from_slice(&[i as u8, (i / 2) as u8, 255 - i as u8, 255]);
+        }
+        for method in [QuantizeMethod::MedianCut, QuantizeMethod::Octree, QuantizeMethod::NeuQuant] {
+            let options = QuantizeOptions {
+                method,
+                dither: DitherMethod::FloydSteinberg,
+                ..QuantizeOptions::new(8)
+            };
+            let q = quantize_with_options(&data, 16, 16, &options).unwrap();
+            assert!(q.palette.len() <= 8 * 4, "{:?}", method);
+            let again = quantize_with_options(&data, 16, 16, &options).unwrap();
+            assert_eq!((&q.palette, &q.indices), (&again.palette, &again.indices), "{:?}", method);
+            assert!(q.indices.iter().all(|&i| (i as usize) < q.palette.len() / 4));
+        }
+    }
+}
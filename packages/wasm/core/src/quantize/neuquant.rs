@@ -0,0 +1,162 @@
+//! NeuQuant neural-network palette generation (after Anthony Dekker, 1994)
+//!
+//! A one-dimensional self-organizing map is trained on a sample of the image;
+//! it tends to preserve smooth gradients better than box-splitting methods.
+
+const NCYCLES: usize = 100;
+const GAMMA: f64 = 1024.0;
+const BETA: f64 = 1.0 / 1024.0;
+const RADIUS_DEC: f64 = 30.0;
+const PRIMES: [usize; 4] = [499, 491, 487, 503];
+
+struct Network {
+    neurons: Vec<[f64; 4]>,
+    freq: Vec<f64>,
+    bias: Vec<f64>,
+}
+
+impl Network {
+    fn new(size: usize) -> Self {
+        let neurons = (0..size)
+            .map(|i| {
+                let v = (i as f64 * 256.0) / size as f64;
+                [v, v, v, 255.0]
+            })
+            .collect();
+        Network {
+            neurons,
+            freq: vec![1.0 / size as f64; size],
+            bias: vec![0.0; size],
+        }
+    }
+
+    /// Find the best neuron (biased by frequency) and update frequencies
+    fn contest(&mut self, color: [f64; 4]) -> usize {
+        let mut best_dist = f64::MAX;
+        let mut best_bias_dist = f64::MAX;
+        let mut best = 0;
+        let mut best_bias = 0;
+
+        for i in 0..self.neurons.len() {
+            let n = self.neurons[i];
+            let dist: f64 = (0..4).map(|c| (n[c] - color[c]).abs()).sum();
+            if dist < best_dist {
+                best_dist = dist;
+                best = i;
+            }
+            let bias_dist = dist - self.bias[i];
+            if bias_dist < best_bias_dist {
+                best_bias_dist = bias_dist;
+                best_bias = i;
+            }
+
+            let beta_freq = self.freq[i] * BETA;
+            self.freq[i] -= beta_freq;
+            self.bias[i] += beta_freq * GAMMA;
+        }
+
+        self.freq[best] += BETA;
+        self.bias[best] -= BETA * GAMMA;
+        best_bias
+    }
+
+    /// Move neuron `i` and its neighbors within `radius` toward `color`
+    fn alter(&mut self, alpha: f64, radius: usize, i: usize, color: [f64; 4]) {
+        let lo = i.saturating_sub(radius);
+        let hi = (i + radius + 1).min(self.neurons.len());
+        for j in lo..hi {
+            let d = j.abs_diff(i) as f64;
+            let a = if radius == 0 {
+                alpha
+            } else {
+                alpha * (1.0 - (d * d) / (radius * radius) as f64)
+            };
+            for (n, &c) in self.neurons[j].iter_mut().zip(color.iter()) {
+                *n -= a * (*n - c);
+            }
+        }
+    }
+}
+
+/// Generate a palette of `max_colors` RGBA entries with NeuQuant
+///
+/// `sample_factor` in 1-30 trades quality (1) for speed (30).
+pub fn neuquant_palette(data: &[u8], max_colors: usize, sample_factor: usize) -> Vec<[u8; 4]> {
+    let pixel_count = data.len() / 4;
+    let size = max_colors.clamp(1, 256);
+    if pixel_count == 0 {
+        return Vec::new();
+    }
+
+    let sample_factor = sample_factor.clamp(1, 30);
+    let mut net = Network::new(size);
+
+    let alpha_dec = 30.0 + (sample_factor - 1) as f64 / 3.0;
+    let samples = (pixel_count / sample_factor).max(1);
+    let delta = (samples / NCYCLES).max(1);
+    let mut alpha = 1.0;
+    let mut radius = (size as f64 / 8.0).max(1.0);
+
+    let step = PRIMES
+        .iter()
+        .copied()
+        .find(|&p| !pixel_count.is_multiple_of(p))
+        .unwrap_or(1);
+
+    let mut pos = 0usize;
+    for i in 0..samples {
+        let p = &data[pos * 4..pos * 4 + 4];
+        let color = [p[0] as f64, p[1] as f64, p[2] as f64, p[3] as f64];
+
+        let winner = net.contest(color);
+        let rad = if radius <= 1.0 { 0 } else { radius as usize };
+        net.alter(alpha, rad, winner, color);
+
+        pos = (pos + step) % pixel_count;
+        if (i + 1) % delta == 0 {
+            alpha -= alpha / alpha_dec;
+            radius -= radius / RADIUS_DEC;
+        }
+    }
+
+    net.neurons
+        .iter()
+        .map(|n| n.map(|v| v.round().clamp(0.0, 255.0) as u8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient() -> Vec<u8> {
+        (0..4096u32).flat_map(|i| [(i % 256) as u8, (i / 16) as u8, 255 - (i % 256) as u8, 255]).collect()
+    }
+
+    #[test]
+    fn test_palette_size() {
+        let data = gradient();
+        for max_colors in [1, 2, 16, 256] {
+            assert_eq!(neuquant_palette(&data, max_colors, 10).len(), max_colors);
+        }
+        assert_eq!(neuquant_palette(&data, 1000, 10).len(), 256);
+        assert!(neuquant_palette(&[], 16, 10).is_empty());
+    }
+
+    #[test]
+    fn test_learns_colors() {
+        // Each of two flat colors should end up next to some neuron
+        let data: Vec<u8> = [[255, 0, 0, 255], [0, 0, 255, 255]].iter().cycle().take(2000).flatten().copied().collect();
+        let palette = neuquant_palette(&data, 4, 1);
+        for color in [[255, 0, 0, 255], [0, 0, 255, 255]] {
+            let nearest = palette.iter().map(|p| (0..4).map(|c| p[c].abs_diff(color[c]) as u32).sum::<u32>()).min().unwrap();
+            assert!(nearest <= 8, "{:?} in {:?}", color, palette);
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let data = gradient();
+        assert_eq!(neuquant_palette(&data, 32, 5), neuquant_palette(&data, 32, 5));
+    }
+}
@@ -0,0 +1,170 @@
+//! Octree palette generation
+
+use super::median_cut::color_counts;
+
+const MAX_DEPTH: usize = 8;
+
+#[derive(Default)]
+struct Node {
+    children: [Option<usize>; 8],
+    count: u64,
+    sum: [u64; 4],
+    leaf: bool,
+}
+
+/// Child slot for a color at the given depth (one bit each of R, G, B)
+#[inline]
+fn child_index(color: [u8; 4], depth: usize) -> usize {
+    let shift = 7 - depth;
+    (((color[0] >> shift) & 1) << 2 | ((color[1] >> shift) & 1) << 1 | ((color[2] >> shift) & 1)) as usize
+}
+
+/// Generate a palette of at most `max_colors` RGBA entries using an octree
+///
+/// The tree splits on RGB; alpha is averaged within each leaf.
+pub fn octree_palette(data: &[u8], max_colors: usize) -> Vec<[u8; 4]> {
+    let mut nodes = vec![Node::default()];
+    // Internal nodes per depth, candidates for merging
+    let mut levels: Vec<Vec<usize>> = vec![Vec::new(); MAX_DEPTH];
+    let mut leaf_count = 0usize;
+
+    for (color, n) in color_counts(data) {
+        let mut node = 0;
+        for depth in 0..=MAX_DEPTH {
+            if depth == MAX_DEPTH || nodes[node].leaf {
+                let entry = &mut nodes[node];
+                if !entry.leaf {
+                    entry.leaf = true;
+                    leaf_count += 1;
+                }
+                entry.count += n as u64;
+                for (s, &c) in entry.sum.iter_mut().zip(color.iter()) {
+                    *s += c as u64 * n as u64;
+                }
+                break;
+            }
+
+            let slot = child_index(color, depth);
+            node = match nodes[node].children[slot] {
+                Some(child) => child,
+                None => {
+                    let child = nodes.len();
+                    nodes.push(Node::default());
+                    nodes[node].children[slot] = Some(child);
+                    if depth + 1 < MAX_DEPTH {
+                        levels[depth + 1].push(child);
+                    }
+                    child
+                }
+            };
+        }
+    }
+    levels[0].push(0);
+
+    // Merge the smallest deepest internal nodes until the palette fits
+    let max_colors = max_colors.max(1);
+    for depth in (0..MAX_DEPTH).rev() {
+        if leaf_count <= max_colors {
+            break;
+        }
+        let mut candidates = std::mem::take(&mut levels[depth]);
+        candidates.sort_by_key(|&i| subtree_count(&nodes, i));
+
+        for node in candidates {
+            if leaf_count <= max_colors {
+                break;
+            }
+            let children: Vec<usize> = nodes[node].children.iter().flatten().copied().collect();
+            if children.is_empty() {
+                continue;
+            }
+
+            let mut count = 0;
+            let mut sum = [0u64; 4];
+            for &child in &children {
+                count += nodes[child].count;
+                for (s, &c) in sum.iter_mut().zip(nodes[child].sum.iter()) {
+                    *s += c;
+                }
+            }
+
+            let entry = &mut nodes[node];
+            entry.children = [None; 8];
+            entry.count = count;
+            entry.sum = sum;
+            entry.leaf = true;
+            leaf_count = leaf_count + 1 - children.len();
+        }
+    }
+
+    let mut palette = Vec::with_capacity(leaf_count);
+    collect_leaves(&nodes, 0, &mut palette);
+    palette
+}
+
+/// Pixel count of all leaves under a node
+fn subtree_count(nodes: &[Node], node: usize) -> u64 {
+    if nodes[node].leaf {
+        return nodes[node].count;
+    }
+    nodes[node]
+        .children
+        .iter()
+        .flatten()
+        .map(|&c| subtree_count(nodes, c))
+        .sum()
+}
+
+fn collect_leaves(nodes: &[Node], node: usize, palette: &mut Vec<[u8; 4]>) {
+    let entry = &nodes[node];
+    if entry.leaf {
+        if entry.count > 0 {
+            let n = entry.count;
+            palette.push(entry.sum.map(|s| ((s + n / 2) / n) as u8));
+        }
+        return;
+    }
+    for &child in entry.children.iter().flatten() {
+        collect_leaves(nodes, child, palette);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4096 opaque colors spread over the RGB cube
+    fn cube() -> Vec<u8> {
+        (0..4096u32).flat_map(|i| [(i % 16 * 17) as u8, (i / 16 % 16 * 17) as u8, (i / 256 * 17) as u8, 255]).collect()
+    }
+
+    fn sorted(mut colors: Vec<[u8; 4]>) -> Vec<[u8; 4]> {
+        colors.sort();
+        colors
+    }
+
+    #[test]
+    fn test_palette_size() {
+        let data = cube();
+        for max_colors in [1, 2, 7, 16, 64, 256] {
+            let palette = octree_palette(&data, max_colors);
+            assert!(!palette.is_empty() && palette.len() <= max_colors, "{} colors for {}", palette.len(), max_colors);
+        }
+        assert_eq!(octree_palette(&data, 0).len(), 1);
+        assert!(octree_palette(&[], 16).is_empty());
+    }
+
+    #[test]
+    fn test_exact_colors_kept() {
+        let colors = [[255, 0, 0, 255], [0, 128, 0, 255], [0, 0, 255, 255], [17, 34, 51, 255], [255, 255, 255, 255]];
+        let data: Vec<u8> = colors.iter().cycle().take(40).flatten().copied().collect();
+        assert_eq!(sorted(octree_palette(&data, 5)), sorted(colors.to_vec()));
+        assert_eq!(sorted(octree_palette(&data, 256)), sorted(colors.to_vec()));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let data = cube();
+        assert_eq!(octree_palette(&data, 32), octree_palette(&data, 32));
+    }
+}
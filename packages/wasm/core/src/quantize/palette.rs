@@ -0,0 +1,118 @@
+//! Dominant-color extraction via k-means seeded from median cut
+
+use super::median_cut::{color_counts, median_cut_palette};
+use crate::utils::check_rgba;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const MAX_ITERATIONS: usize = 10;
+
+/// Dominant colors sorted by descending population
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug)]
+pub struct DominantColors {
+    /// RGB triplets, 3 bytes per color
+    pub colors: Vec<u8>,
+    /// Pixel count of each color
+    pub populations: Vec<u32>,
+}
+
+/// Find the `k` most representative opaque colors
+///
+/// Pixels with alpha below 128 are ignored.
+pub fn dominant_colors(data: &[u8], width: u32, height: u32, k: u32) -> Result<DominantColors, String> {
+    check_rgba(data, width, height)?;
+    if k == 0 {
+        return Err("Color count must be positive".to_string());
+    }
+
+    let opaque: Vec<u8> = data
+        .chunks_exact(4)
+        .filter(|p| p[3] >= 128)
+        .flat_map(|p| [p[0], p[1], p[2], 255])
+        .collect();
+    let samples: Vec<([f32; 3], f32)> = color_counts(&opaque)
+        .into_iter()
+        .map(|(c, n)| ([c[0] as f32, c[1] as f32, c[2] as f32], n as f32))
+        .collect();
+
+    let mut centers: Vec<[f32; 3]> = median_cut_palette(&opaque, k.min(256) as usize)
+        .into_iter()
+        .map(|c| [c[0] as f32, c[1] as f32, c[2] as f32])
+        .collect();
+
+    let nearest = |centers: &[[f32; 3]], c: &[f32; 3]| -> usize {
+        let mut best = (0, f32::MAX);
+        for (i, m) in centers.iter().enumerate() {
+            let d = (m[0] - c[0]).powi(2) + (m[1] - c[1]).powi(2) + (m[2] - c[2]).powi(2);
+            if d < best.1 {
+                best = (i, d);
+            }
+        }
+        best.0
+    };
+
+    let mut populations = vec![0f32; centers.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut sums = vec![[0f32; 3]; centers.len()];
+        populations.iter_mut().for_each(|p| *p = 0.0);
+
+        for (c, n) in &samples {
+            let i = nearest(&centers, c);
+            populations[i] += n;
+            for ch in 0..3 {
+                sums[i][ch] += c[ch] * n;
+            }
+        }
+
+        let mut moved = false;
+        for (i, center) in centers.iter_mut().enumerate() {
+            if populations[i] == 0.0 {
+                continue;
+            }
+            let updated = sums[i].map(|s| s / populations[i]);
+            if updated.iter().zip(center.iter()).any(|(a, b)| (a - b).abs() > 0.5) {
+                moved = true;
+            }
+            *center = updated;
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    let mut ranked: Vec<(usize, f32)> = populations.iter().copied().enumerate().filter(|&(_, n)| n > 0.0).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut colors = Vec::with_capacity(ranked.len() * 3);
+    let mut counts = Vec::with_capacity(ranked.len());
+    for (i, n) in ranked {
+        colors.extend(centers[i].map(|v| v.round().clamp(0.0, 255.0) as u8));
+        counts.push(n as u32);
+    }
+
+    Ok(DominantColors {
+        colors,
+        populations: counts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dominant_colors_ranked() {
+        let mut data = Vec::new();
+        for i in 0..100 {
+            let c = if i < 70 { [200, 10, 10, 255] } else { [10, 10, 200, 255] };
+            data.extend_from_slice(&c);
+        }
+        // Transparent pixels are ignored
+        data.extend_from_slice(&[0, 255, 0, 0]);
+
+        let d = dominant_colors(&data, 101, 1, 3).unwrap();
+        assert_eq!(d.populations, vec![70, 30]);
+        assert_eq!(&d.colors[..3], &[200, 10, 10]);
+    }
+}
@@ -0,0 +1,80 @@
+//! RGBA to 4:2:0 YCbCr for video encoders
+
+use crate::color::space::{rgb_to_ycbcr, YCbCrMatrix};
+use crate::utils::check_rgba;
+
+/// Luma plane and the averaged Cb and Cr of each 2x2 block
+fn planes(data: &[u8], width: u32, height: u32, matrix: YCbCrMatrix, full_range: bool) -> Result<[Vec<u8>; 3], String> {
+    check_rgba(data, width, height)?;
+    let (w, h) = (width as usize, height as usize);
+    let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+    let compress = |v: f32, luma: bool| -> u8 {
+        let v = match (full_range, luma) {
+            (true, _) => v,
+            (false, true) => 16.0 + v * 219.0 / 255.0,
+            (false, false) => 128.0 + (v - 128.0) * 224.0 / 255.0,
+        };
+        v.round().clamp(0.0, 255.0) as u8
+    };
+
+    let mut luma = Vec::with_capacity(w * h);
+    let mut sums = vec![[0.0f32; 3]; cw * ch];
+    for (i, p) in data.chunks_exact(4).enumerate() {
+        let [y, cb, cr] = rgb_to_ycbcr([p[0] as f32, p[1] as f32, p[2] as f32], matrix);
+        luma.push(compress(y, true));
+        let sum = &mut sums[(i / w / 2) * cw + (i % w) / 2];
+        sum[0] += cb;
+        sum[1] += cr;
+        sum[2] += 1.0;
+    }
+    let cb = sums.iter().map(|s| compress(s[0] / s[2], false)).collect();
+    let cr = sums.iter().map(|s| compress(s[1] / s[2], false)).collect();
+    Ok([luma, cb, cr])
+}
+
+/// Convert RGBA to planar 4:2:0 (I420): luma, then Cb, then Cr at half
+/// width and height (rounded up), tightly packed and ignoring alpha
+///
+/// This is the `I420` layout WebCodecs `VideoFrame` accepts. Chroma is the
+/// average of each 2x2 block; samples are limited range unless `full_range`.
+pub fn to_yuv420(data: &[u8], width: u32, height: u32, matrix: YCbCrMatrix, full_range: bool) -> Result<Vec<u8>, String> {
+    let [mut out, cb, cr] = planes(data, width, height, matrix, full_range)?;
+    out.extend(cb);
+    out.extend(cr);
+    Ok(out)
+}
+
+/// Convert RGBA to NV12: the luma plane, then Cb and Cr interleaved at half
+/// width and height, tightly packed; otherwise as `to_yuv420`
+pub fn to_nv12(data: &[u8], width: u32, height: u32, matrix: YCbCrMatrix, full_range: bool) -> Result<Vec<u8>, String> {
+    let [mut out, cb, cr] = planes(data, width, height, matrix, full_range)?;
+    out.extend(cb.into_iter().zip(cr).flat_map(|(b, r)| [b, r]));
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::{import_raw, RawFormat};
+
+    #[test]
+    fn test_yuv420_and_nv12() {
+        // Black and white columns over a red row, 3x2 so the chroma is rounded up
+        let rgba = [0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 255, 0, 0, 255, 255, 0, 0, 255, 255, 0, 0, 255];
+        let i420 = to_yuv420(&rgba, 3, 2, YCbCrMatrix::Bt709, false).unwrap();
+        assert_eq!(i420.len(), 6 + 2 + 2);
+        assert_eq!(&i420[..3], [16, 235, 16]);
+        let nv12 = to_nv12(&rgba, 3, 2, YCbCrMatrix::Bt709, false).unwrap();
+        assert_eq!(nv12[..6], i420[..6]);
+        assert_eq!(nv12[6..], [i420[6], i420[8], i420[7], i420[9]]);
+
+        // A flat color survives the trip through either layout
+        let flat = [40, 160, 90, 255].repeat(16);
+        for (format, full_range) in [(RawFormat::Yuv420, false), (RawFormat::Nv12, true)] {
+            let yuv = if format == RawFormat::Nv12 { to_nv12(&flat, 4, 4, YCbCrMatrix::Bt601, full_range) } else { to_yuv420(&flat, 4, 4, YCbCrMatrix::Bt601, full_range) }.unwrap();
+            let back = import_raw(&yuv, 4, 4, 0, format, YCbCrMatrix::Bt601, full_range).unwrap();
+            assert!(back.iter().zip(&flat).all(|(a, b)| a.abs_diff(*b) <= 2), "{:?}", &back[..4]);
+        }
+        assert!(to_nv12(&rgba, 2, 2, YCbCrMatrix::Bt601, false).is_err());
+    }
+}
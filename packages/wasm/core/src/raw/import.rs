@@ -0,0 +1,174 @@
+//! Normalizing raw pixel dumps to RGBA
+
+use crate::color::space::{ycbcr_to_rgb, YCbCrMatrix};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Layouts of raw pixel buffers
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawFormat {
+    /// 8-bit R, G, B
+    Rgb = 0,
+    /// 8-bit B, G, R
+    Bgr = 1,
+    /// 8-bit R, G, B, A
+    Rgba = 2,
+    /// 8-bit B, G, R, A
+    Bgra = 3,
+    /// 8-bit luma
+    Gray = 4,
+    /// 16-bit little-endian, 5 bits red (high), 6 green, 5 blue
+    Rgb565 = 5,
+    /// Luma plane, then one plane of interleaved Cb, Cr at half width and height
+    Nv12 = 6,
+    /// Packed 4:2:2, Y0 Cb Y1 Cr per pair of pixels
+    Yuyv = 7,
+    /// Planar 4:2:0 (I420): luma, then Cb and Cr planes at half width and height
+    Yuv420 = 8,
+}
+
+impl RawFormat {
+    /// Bytes per pixel in the first (or only) plane
+    fn pixel_size(self) -> usize {
+        match self {
+            RawFormat::Rgb | RawFormat::Bgr => 3,
+            RawFormat::Rgba | RawFormat::Bgra => 4,
+            RawFormat::Gray | RawFormat::Nv12 | RawFormat::Yuv420 => 1,
+            RawFormat::Rgb565 | RawFormat::Yuyv => 2,
+        }
+    }
+}
+
+/// Scale one YCbCr sample to full range when the source is limited range
+fn expand(v: u8, luma: bool, full_range: bool) -> f32 {
+    match (full_range, luma) {
+        (true, _) => v as f32,
+        (false, true) => (v as f32 - 16.0) * 255.0 / 219.0,
+        (false, false) => (v as f32 - 128.0) * 255.0 / 224.0 + 128.0,
+    }
+}
+
+/// Take `len` bytes at `start`, or fail on a short buffer
+fn slice(data: &[u8], start: usize, len: usize) -> Result<&[u8], String> {
+    data.get(start..start + len).ok_or_else(|| format!("Raw buffer is too short: need {} bytes, got {}", start + len, data.len()))
+}
+
+/// Convert a raw pixel buffer to RGBA
+///
+/// `stride` is the length in bytes of each row of the first plane, with 0
+/// meaning tightly packed rows. The chroma plane of NV12 shares that stride;
+/// the Cb and Cr planes of YUV420 use half of it (rounded up), and every
+/// plane follows the previous one directly. YCbCr formats are converted with
+/// `matrix` and are limited range (16-235) unless `full_range` is set; their
+/// chroma is replicated across the pixels sharing a sample.
+pub fn import_raw(data: &[u8], width: u32, height: u32, stride: u32, format: RawFormat, matrix: YCbCrMatrix, full_range: bool) -> Result<Vec<u8>, String> {
+    if width == 0 || height == 0 {
+        return Err("Raw image size must be non-zero".to_string());
+    }
+    let (w, h) = (width as usize, height as usize);
+    let row_len = match format {
+        RawFormat::Yuyv => w.div_ceil(2) * 4,
+        _ => w * format.pixel_size(),
+    };
+    let stride = if stride == 0 { row_len } else { stride as usize };
+    if stride < row_len {
+        return Err(format!("Stride {} is shorter than a {}-byte row", stride, row_len));
+    }
+
+    let mut out = Vec::with_capacity(w * h * 4);
+    let yuv = |y: u8, cb: u8, cr: u8| {
+        let rgb = ycbcr_to_rgb([expand(y, true, full_range), expand(cb, false, full_range), expand(cr, false, full_range)], matrix);
+        let [r, g, b] = rgb.map(|v| v.round().clamp(0.0, 255.0) as u8);
+        [r, g, b, 255]
+    };
+    match format {
+        RawFormat::Nv12 | RawFormat::Yuv420 => {
+            let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+            let luma_size = stride * h;
+            let chroma_stride = if format == RawFormat::Nv12 { stride } else { stride.div_ceil(2) };
+            for y in 0..h {
+                let luma = slice(data, y * stride, w)?;
+                let chroma_row = luma_size + (y / 2) * chroma_stride;
+                let (cb, cr): (Vec<u8>, Vec<u8>) = if format == RawFormat::Nv12 {
+                    slice(data, chroma_row, cw * 2)?.chunks_exact(2).map(|c| (c[0], c[1])).unzip()
+                } else {
+                    let cr_start = luma_size + chroma_stride * ch;
+                    (slice(data, chroma_row, cw)?.to_vec(), slice(data, cr_start + (y / 2) * chroma_stride, cw)?.to_vec())
+                };
+                for (x, &l) in luma.iter().enumerate() {
+                    out.extend_from_slice(&yuv(l, cb[x / 2], cr[x / 2]));
+                }
+            }
+        }
+        _ => {
+            for y in 0..h {
+                let row = slice(data, y * stride, row_len)?;
+                match format {
+                    RawFormat::Rgb => row.chunks_exact(3).for_each(|p| out.extend_from_slice(&[p[0], p[1], p[2], 255])),
+                    RawFormat::Bgr => row.chunks_exact(3).for_each(|p| out.extend_from_slice(&[p[2], p[1], p[0], 255])),
+                    RawFormat::Rgba => out.extend_from_slice(row),
+                    RawFormat::Bgra => row.chunks_exact(4).for_each(|p| out.extend_from_slice(&[p[2], p[1], p[0], p[3]])),
+                    RawFormat::Gray => row.iter().for_each(|&v| out.extend_from_slice(&[v, v, v, 255])),
+                    RawFormat::Rgb565 => row.chunks_exact(2).for_each(|p| {
+                        let v = u16::from_le_bytes([p[0], p[1]]);
+                        let (r, g, b) = ((v >> 11) as u8, (v >> 5 & 0x3f) as u8, (v & 0x1f) as u8);
+                        out.extend_from_slice(&[r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255]);
+                    }),
+                    _ => {
+                        // YUYV: the last pair of an odd width is only half used
+                        for (x, pair) in row.chunks_exact(4).enumerate() {
+                            out.extend_from_slice(&yuv(pair[0], pair[1], pair[3]));
+                            if x * 2 + 1 < w {
+                                out.extend_from_slice(&yuv(pair[2], pair[1], pair[3]));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(data: &[u8], width: u32, height: u32, stride: u32, format: RawFormat) -> Vec<u8> {
+        import_raw(data, width, height, stride, format, YCbCrMatrix::Bt601, false).unwrap()
+    }
+
+    #[test]
+    fn test_packed_formats() {
+        // Two pixels per row with two bytes of padding
+        let bgr = [0, 0, 255, 0, 255, 0, 9, 9, 255, 0, 0, 1, 2, 3, 9, 9];
+        assert_eq!(import(&bgr, 2, 2, 8, RawFormat::Bgr), [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 3, 2, 1, 255]);
+        assert_eq!(import(&[10, 20], 2, 1, 0, RawFormat::Gray), [10, 10, 10, 255, 20, 20, 20, 255]);
+        // Pure red, green and white in RGB565
+        assert_eq!(import(&[0x00, 0xf8, 0xe0, 0x07, 0xff, 0xff], 3, 1, 0, RawFormat::Rgb565), [255, 0, 0, 255, 0, 255, 0, 255, 255, 255, 255, 255]);
+        assert_eq!(import(&[1, 2, 3, 4], 1, 1, 0, RawFormat::Bgra), [3, 2, 1, 4]);
+
+        assert!(import_raw(&[0; 5], 2, 1, 0, RawFormat::Rgb, YCbCrMatrix::Bt601, false).is_err());
+        assert!(import_raw(&[0; 12], 2, 2, 2, RawFormat::Rgb, YCbCrMatrix::Bt601, false).is_err());
+    }
+
+    #[test]
+    fn test_yuv_formats() {
+        // Limited-range black and white luma with neutral chroma
+        let gray = |v: u8| [v, v, v, 255];
+        let black_white: Vec<u8> = [gray(0), gray(255)].concat();
+        assert_eq!(import(&[16, 128, 235, 128], 2, 1, 0, RawFormat::Yuyv), black_white);
+
+        let i420 = [16, 235, 16, 235, 128, 128];
+        assert_eq!(import(&i420, 2, 2, 0, RawFormat::Yuv420), black_white.repeat(2));
+        let nv12 = [16, 235, 16, 235, 128, 128];
+        assert_eq!(import(&nv12, 2, 2, 0, RawFormat::Nv12), black_white.repeat(2));
+
+        // Full-range BT.601 red on a padded 3x1 image, whose last chroma pair covers one pixel
+        let (y, cb, cr) = (76, 85, 255);
+        let red = import_raw(&[y, y, y, 0, cb, cr, cb, cr], 3, 1, 4, RawFormat::Nv12, YCbCrMatrix::Bt601, true).unwrap();
+        assert!(red.chunks_exact(4).all(|p| p[0] > 250 && p[1] < 3 && p[2] < 3), "{:?}", red);
+        assert!(import_raw(&i420[..5], 2, 2, 0, RawFormat::Yuv420, YCbCrMatrix::Bt601, false).is_err());
+    }
+}
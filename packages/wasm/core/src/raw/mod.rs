@@ -0,0 +1,34 @@
+//! Raw pixel buffers from cameras, framebuffers and video APIs, and 4:2:0
+//! YCbCr output for video encoders
+
+mod export;
+mod import;
+
+pub use export::{to_nv12, to_yuv420};
+pub use import::{import_raw, RawFormat};
+
+#[cfg(feature = "wasm")]
+use crate::color::space::YCbCrMatrix;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Convert a raw pixel buffer to RGBA; `stride` 0 means tightly packed rows
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = importRaw)]
+pub fn import_raw_js(data: &[u8], width: u32, height: u32, stride: u32, format: RawFormat, matrix: YCbCrMatrix, full_range: bool) -> Result<Vec<u8>, JsError> {
+    import_raw(data, width, height, stride, format, matrix, full_range).map_err(|e| JsError::new(&e))
+}
+
+/// Convert RGBA to planar I420 for WebCodecs and other video encoders
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = toYuv420)]
+pub fn to_yuv420_js(data: &[u8], width: u32, height: u32, matrix: YCbCrMatrix, full_range: bool) -> Result<Vec<u8>, JsError> {
+    to_yuv420(data, width, height, matrix, full_range).map_err(|e| JsError::new(&e))
+}
+
+/// Convert RGBA to NV12 (luma plane, then interleaved chroma)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = toNv12)]
+pub fn to_nv12_js(data: &[u8], width: u32, height: u32, matrix: YCbCrMatrix, full_range: bool) -> Result<Vec<u8>, JsError> {
+    to_nv12(data, width, height, matrix, full_range).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,254 @@
+//! Image resize operations
+//!
+//! High-performance resize algorithms optimized for WASM.
+
+mod nine_patch;
+
+pub use nine_patch::{scale_9patch, NinePatchInsets};
+
+use crate::pool::{self, Scratch};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Resize algorithm
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeAlgorithm {
+    Nearest = 0,
+    Bilinear = 1,
+    Bicubic = 2,
+    Lanczos = 3,
+}
+
+/// Resize an RGBA image
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn resize(
+    data: &[u8],
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+    algorithm: ResizeAlgorithm,
+) -> Vec<u8> {
+    match algorithm {
+        ResizeAlgorithm::Nearest => resize_nearest(data, src_width, src_height, dst_width, dst_height),
+        ResizeAlgorithm::Bilinear => resize_bilinear(data, src_width, src_height, dst_width, dst_height),
+        ResizeAlgorithm::Bicubic => resize_bicubic(data, src_width, src_height, dst_width, dst_height),
+        ResizeAlgorithm::Lanczos => resize_lanczos(data, src_width, src_height, dst_width, dst_height),
+    }
+}
+
+/// Scale a nine-patch image, keeping its `left`, `top`, `right` and `bottom`
+/// borders at their original size
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = scale9Patch)]
+#[allow(clippy::too_many_arguments)]
+pub fn scale_9patch_js(data: &[u8], width: u32, height: u32, left: u32, top: u32, right: u32, bottom: u32, target_width: u32, target_height: u32) -> Result<Vec<u8>, JsError> {
+    let insets = NinePatchInsets { left, top, right, bottom };
+    scale_9patch(data, width, height, insets, target_width, target_height).map_err(|e| JsError::new(&e))
+}
+
+fn resize_nearest(
+    data: &[u8],
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+) -> Vec<u8> {
+    let mut output = vec![0u8; (dst_width * dst_height * 4) as usize];
+
+    let x_ratio = src_width as f64 / dst_width as f64;
+    let y_ratio = src_height as f64 / dst_height as f64;
+
+    for y in 0..dst_height {
+        for x in 0..dst_width {
+            let src_x = (x as f64 * x_ratio) as u32;
+            let src_y = (y as f64 * y_ratio) as u32;
+
+            let src_idx = ((src_y * src_width + src_x) * 4) as usize;
+            let dst_idx = ((y * dst_width + x) * 4) as usize;
+
+            output[dst_idx] = data[src_idx];
+            output[dst_idx + 1] = data[src_idx + 1];
+            output[dst_idx + 2] = data[src_idx + 2];
+            output[dst_idx + 3] = data[src_idx + 3];
+        }
+    }
+
+    output
+}
+
+fn resize_bilinear(
+    data: &[u8],
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+) -> Vec<u8> {
+    let taps = |src_len: u32, dst_len: u32| {
+        let ratio = (src_len as f64 - 1.0) / dst_len as f64;
+        axis_taps(dst_len, 2, |d, taps| {
+            let src = d as f64 * ratio;
+            let i0 = src.floor() as u32;
+            let f = src - i0 as f64;
+            taps[0] = (i0, 1.0 - f);
+            taps[1] = ((i0 + 1).min(src_len - 1), f);
+        })
+    };
+    resample(data, src_width, src_height, &taps(src_width, dst_width), &taps(src_height, dst_height))
+}
+
+fn resize_bicubic(
+    data: &[u8],
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+) -> Vec<u8> {
+    let taps = |src_len: u32, dst_len: u32| {
+        let ratio = src_len as f64 / dst_len as f64;
+        axis_taps(dst_len, 4, |d, taps| {
+            let src = d as f64 * ratio;
+            let i0 = src.floor() as i32;
+            let f = src - i0 as f64;
+            for (tap, i) in taps.iter_mut().zip(-1..=2) {
+                *tap = ((i0 + i).clamp(0, src_len as i32 - 1) as u32, cubic_weight(i as f64 - f));
+            }
+        })
+    };
+    resample(data, src_width, src_height, &taps(src_width, dst_width), &taps(src_height, dst_height))
+}
+
+#[inline]
+fn cubic_weight(x: f64) -> f64 {
+    let x = x.abs();
+    if x < 1.0 {
+        (1.5 * x - 2.5) * x * x + 1.0
+    } else if x < 2.0 {
+        ((-0.5 * x + 2.5) * x - 4.0) * x + 2.0
+    } else {
+        0.0
+    }
+}
+
+fn resize_lanczos(
+    data: &[u8],
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+) -> Vec<u8> {
+    const A: i32 = 3; // Lanczos-3
+
+    let taps = |src_len: u32, dst_len: u32| {
+        let ratio = src_len as f64 / dst_len as f64;
+        axis_taps(dst_len, 2 * A as usize, |d, taps| {
+            let src = d as f64 * ratio;
+            let i0 = src.floor() as i32;
+            let f = src - i0 as f64;
+            for (tap, i) in taps.iter_mut().zip(-A + 1..=A) {
+                *tap = ((i0 + i).clamp(0, src_len as i32 - 1) as u32, lanczos_weight(i as f64 - f, A as f64));
+            }
+            // Normalised per axis, which is the same as over the 2D window
+            let sum: f64 = taps.iter().map(|&(_, w)| w).sum();
+            for tap in taps.iter_mut() {
+                tap.1 = if sum > 0.0 { tap.1 / sum } else { 0.0 };
+            }
+        })
+    };
+    resample(data, src_width, src_height, &taps(src_width, dst_width), &taps(src_height, dst_height))
+}
+
+/// Source positions and weights for each output position along one axis,
+/// `count` to a position
+struct AxisTaps {
+    count: usize,
+    index: Scratch<u32>,
+    weight: Scratch<f64>,
+}
+
+fn axis_taps(dst_len: u32, count: usize, mut fill: impl FnMut(u32, &mut [(u32, f64)])) -> AxisTaps {
+    let mut index = pool::take_empty(dst_len as usize * count);
+    let mut weight = pool::take_empty(dst_len as usize * count);
+    let mut taps = vec![(0, 0.0); count];
+    for d in 0..dst_len {
+        fill(d, &mut taps);
+        for &(i, w) in &taps {
+            index.push(i);
+            weight.push(w);
+        }
+    }
+    AxisTaps { count, index, weight }
+}
+
+/// Filter rows into a pooled intermediate, then columns into the output;
+/// the kernels are separable, so this matches filtering the 2D window
+fn resample(data: &[u8], src_width: u32, src_height: u32, x: &AxisTaps, y: &AxisTaps) -> Vec<u8> {
+    let (src_width, src_height) = (src_width as usize, src_height as usize);
+    let dst_width = x.index.len() / x.count;
+    let dst_height = y.index.len() / y.count;
+
+    let mut rows = pool::take(src_height * dst_width * 4, 0f64);
+    for sy in 0..src_height {
+        let src = &data[sy * src_width * 4..(sy + 1) * src_width * 4];
+        let row = &mut rows[sy * dst_width * 4..(sy + 1) * dst_width * 4];
+        for (dx, out) in row.chunks_exact_mut(4).enumerate() {
+            let taps = dx * x.count..(dx + 1) * x.count;
+            for (&sx, &w) in x.index[taps.clone()].iter().zip(&x.weight[taps]) {
+                let p = &src[sx as usize * 4..sx as usize * 4 + 4];
+                for c in 0..4 {
+                    out[c] += p[c] as f64 * w;
+                }
+            }
+        }
+    }
+
+    let mut output = vec![0u8; dst_width * dst_height * 4];
+    let mut sum = pool::take(dst_width * 4, 0f64);
+    for (dy, out) in output.chunks_exact_mut(dst_width * 4).enumerate() {
+        sum.fill(0.0);
+        let taps = dy * y.count..(dy + 1) * y.count;
+        for (&sy, &w) in y.index[taps.clone()].iter().zip(&y.weight[taps]) {
+            let row = &rows[sy as usize * dst_width * 4..(sy as usize + 1) * dst_width * 4];
+            for (s, &p) in sum.iter_mut().zip(row) {
+                *s += p * w;
+            }
+        }
+        for (o, &s) in out.iter_mut().zip(sum.iter()) {
+            *o = s.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    output
+}
+
+#[inline]
+fn lanczos_weight(x: f64, a: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else if x.abs() < a {
+        let pi_x = std::f64::consts::PI * x;
+        (a * (pi_x).sin() * (pi_x / a).sin()) / (pi_x * pi_x)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resize_algorithms() {
+        let flat: Vec<u8> = [10u8, 200, 30, 255].repeat(5 * 3);
+        for algorithm in [ResizeAlgorithm::Nearest, ResizeAlgorithm::Bilinear, ResizeAlgorithm::Bicubic, ResizeAlgorithm::Lanczos] {
+            let out = resize(&flat, 5, 3, 8, 7, algorithm);
+            assert_eq!(out, [10u8, 200, 30, 255].repeat(8 * 7), "{:?}", algorithm);
+        }
+
+        // Halfway between black and white on the first column only
+        let ramp = [0u8, 0, 0, 255, 255, 255, 255, 255];
+        assert_eq!(resize(&ramp, 2, 1, 2, 1, ResizeAlgorithm::Bilinear), [0, 0, 0, 255, 128, 128, 128, 255]);
+        assert_eq!(resize(&ramp, 2, 1, 1, 1, ResizeAlgorithm::Bicubic), [0, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,100 @@
+//! Nine-patch (9-slice) scaling for UI assets
+
+use crate::pool::{self, Scratch};
+use crate::utils::check_rgba;
+
+/// Widths of the fixed borders of a nine-patch image, in source pixels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NinePatchInsets {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+/// For each target pixel along one axis, the two source pixels to blend and
+/// the weight of the second; samples never cross between the three slices
+fn axis_map(src_len: u32, start: u32, end: u32, dst_len: u32) -> Result<Scratch<(usize, usize, f32)>, String> {
+    let (fixed, centre) = (start + end, src_len - start - end);
+    // Borders keep their size unless the target is too small for them
+    let (dst_start, dst_end) = if dst_len >= fixed {
+        (start, end)
+    } else {
+        let dst_start = (start as u64 * dst_len as u64 + fixed as u64 / 2) / fixed as u64;
+        (dst_start as u32, dst_len - dst_start as u32)
+    };
+    let dst_centre = dst_len - dst_start - dst_end;
+    if centre == 0 && dst_centre > 0 {
+        return Err("Nine-patch has no stretchable centre".to_string());
+    }
+
+    let slices = [(0, start, dst_start), (start, centre, dst_centre), (start + centre, end, dst_end)];
+    let mut map = pool::take_empty(dst_len as usize);
+    for (src_start, src_len, dst_len) in slices {
+        for i in 0..dst_len {
+            let pos = ((i as f32 + 0.5) * src_len as f32 / dst_len as f32 - 0.5).clamp(0.0, (src_len - 1) as f32);
+            let (low, frac) = (pos.floor(), pos.fract());
+            let (low, high) = (src_start + low as u32, src_start + (low as u32 + 1).min(src_len - 1));
+            map.push((low as usize, high as usize, frac));
+        }
+    }
+    Ok(map)
+}
+
+/// Scale a nine-patch image to `target_width` x `target_height`
+///
+/// Corners keep their size, edges stretch along their length and the centre
+/// stretches both ways. A target smaller than the borders shrinks them
+/// proportionally and drops the centre.
+pub fn scale_9patch(data: &[u8], width: u32, height: u32, insets: NinePatchInsets, target_width: u32, target_height: u32) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if insets.left.saturating_add(insets.right) > width || insets.top.saturating_add(insets.bottom) > height {
+        return Err(format!("Insets do not fit a {}x{} image", width, height));
+    }
+    if target_width == 0 || target_height == 0 {
+        return Err("Target size must be non-zero".to_string());
+    }
+    let columns = axis_map(width, insets.left, insets.right, target_width)?;
+    let rows = axis_map(height, insets.top, insets.bottom, target_height)?;
+
+    let pixel = |x: usize, y: usize, c: usize| data[(y * width as usize + x) * 4 + c] as f32;
+    let mut out = Vec::with_capacity(target_width as usize * target_height as usize * 4);
+    for &(y0, y1, fy) in rows.iter() {
+        for &(x0, x1, fx) in columns.iter() {
+            for c in 0..4 {
+                let top = pixel(x0, y0, c) * (1.0 - fx) + pixel(x1, y0, c) * fx;
+                let bottom = pixel(x0, y1, c) * (1.0 - fx) + pixel(x1, y1, c) * fx;
+                out.push((top * (1.0 - fy) + bottom * fy).round() as u8);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_9patch() {
+        // A 4x4 image with a one-pixel red frame around a 2x2 blue/green centre
+        let (r, b, g) = ([255, 0, 0, 255], [0, 0, 255, 255], [0, 255, 0, 255]);
+        let rows = [[r, r, r, r], [r, b, g, r], [r, b, g, r], [r, r, r, r]];
+        let data: Vec<u8> = rows.iter().flatten().flatten().copied().collect();
+        let insets = NinePatchInsets { left: 1, top: 1, right: 1, bottom: 1 };
+
+        assert_eq!(scale_9patch(&data, 4, 4, insets, 4, 4).unwrap(), data);
+        let big = scale_9patch(&data, 4, 4, insets, 10, 6).unwrap();
+        let at = |x: usize, y: usize| &big[(y * 10 + x) * 4..(y * 10 + x) * 4 + 4];
+        // The frame stays one pixel wide; the centre stretches without the frame bleeding in
+        assert!((0..10).all(|x| at(x, 0) == r && at(x, 5) == r));
+        assert!((1..5).all(|y| at(0, y) == r && at(9, y) == r));
+        assert!((1..5).all(|y| at(1, y) == b && at(8, y) == g));
+        assert_eq!(at(5, 2), [0, 159, 96, 255]);
+
+        let small = scale_9patch(&data, 4, 4, insets, 2, 2).unwrap();
+        assert_eq!(small, r.repeat(4));
+        assert!(scale_9patch(&data, 4, 4, NinePatchInsets { left: 2, top: 1, right: 2, bottom: 1 }, 6, 4).is_err());
+        assert!(scale_9patch(&data, 4, 4, NinePatchInsets { left: 3, top: 1, right: 2, bottom: 1 }, 4, 4).is_err());
+    }
+}
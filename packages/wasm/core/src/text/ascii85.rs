@@ -0,0 +1,90 @@
+//! Ascii85 (Adobe variant, as used by PDF and PostScript)
+
+/// Encode bytes as Ascii85, optionally wrapped in `<~` `~>` delimiters
+///
+/// Groups of four zero bytes are written as `z`.
+pub fn ascii85_encode(data: &[u8], delimiters: bool) -> String {
+    let mut out = String::with_capacity(data.len() * 5 / 4 + 4);
+    if delimiters {
+        out.push_str("<~");
+    }
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(group);
+        if value == 0 && chunk.len() == 4 {
+            out.push('z');
+            continue;
+        }
+        let mut digits = [0u8; 5];
+        for d in digits.iter_mut().rev() {
+            *d = (value % 85) as u8 + b'!';
+            value /= 85;
+        }
+        // A partial group of n bytes needs only n + 1 digits
+        out.extend(digits[..chunk.len() + 1].iter().map(|&d| d as char));
+    }
+    if delimiters {
+        out.push_str("~>");
+    }
+    out
+}
+
+/// Decode Ascii85, with or without delimiters, ignoring whitespace
+pub fn ascii85_decode(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    let text = text.strip_prefix("<~").unwrap_or(text);
+    let text = text.strip_suffix("~>").unwrap_or(text);
+
+    let mut out = Vec::with_capacity(text.len() * 4 / 5);
+    let mut value = 0u64;
+    let mut count = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        match c {
+            b'z' if count == 0 => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                value = value * 85 + (c - b'!') as u64;
+                count += 1;
+                if count == 5 {
+                    let group = u32::try_from(value).map_err(|_| "Ascii85 group out of range")?;
+                    out.extend_from_slice(&group.to_be_bytes());
+                    value = 0;
+                    count = 0;
+                }
+            }
+            _ => return Err(format!("Invalid Ascii85 character: {:?}", c as char)),
+        }
+    }
+    match count {
+        0 => {}
+        1 => return Err("Truncated Ascii85 data".to_string()),
+        n => {
+            // Pad with the highest digit, then keep n - 1 bytes
+            for _ in n..5 {
+                value = value * 85 + 84;
+            }
+            let group = u32::try_from(value).map_err(|_| "Ascii85 group out of range")?;
+            out.extend_from_slice(&group.to_be_bytes()[..n - 1]);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii85_vectors() {
+        let vectors: [(&[u8], &str); 4] = [(b"Man is distinguished", "9jqo^BlbD-BleB1DJ+*+F(f,q"), (b"\0\0\0\0\x01", "z!<"), (b"abc", "@:E^"), (b"", "")];
+        for (plain, encoded) in vectors {
+            assert_eq!(ascii85_encode(plain, false), encoded);
+            assert_eq!(ascii85_decode(&format!("<~{}~>", encoded)).unwrap(), plain);
+            assert_eq!(ascii85_decode(encoded).unwrap(), plain);
+        }
+        assert_eq!(ascii85_encode(b"abc", true), "<~@:E^~>");
+        assert_eq!(ascii85_decode("9jqo^ Blb\nD-").unwrap(), b"Man is d");
+        assert!(ascii85_decode("s8W-\"").is_err());
+        assert!(ascii85_decode("!!!!!!").is_err());
+    }
+}
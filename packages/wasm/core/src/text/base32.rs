@@ -0,0 +1,101 @@
+//! Base32 and Base32hex (RFC 4648)
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const STANDARD: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const HEX: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+/// Base32 alphabet
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base32Alphabet {
+    /// `A-Z2-7`
+    Standard = 0,
+    /// `0-9A-V`, preserves sort order
+    Hex = 1,
+}
+
+impl Base32Alphabet {
+    fn table(self) -> &'static [u8; 32] {
+        match self {
+            Base32Alphabet::Standard => STANDARD,
+            Base32Alphabet::Hex => HEX,
+        }
+    }
+}
+
+/// Encode bytes as upper-case Base32
+pub fn base32_encode(data: &[u8], alphabet: Base32Alphabet, pad: bool) -> String {
+    let table = alphabet.table();
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &b in data {
+        acc = acc << 8 | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(table[(acc >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(table[(acc << (5 - bits)) as usize & 31] as char);
+    }
+    if pad {
+        while !out.len().is_multiple_of(8) {
+            out.push('=');
+        }
+    }
+    out
+}
+
+/// Decode Base32, case-insensitively and ignoring whitespace; padding is optional
+pub fn base32_decode(text: &str, alphabet: Base32Alphabet) -> Result<Vec<u8>, String> {
+    let table = alphabet.table();
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut chars = 0usize;
+    let mut padded = false;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        if c == b'=' {
+            padded = true;
+            continue;
+        }
+        if padded {
+            return Err("Base32 data after padding".to_string());
+        }
+        let value = table.iter().position(|&t| t == c.to_ascii_uppercase()).ok_or_else(|| format!("Invalid Base32 character: {:?}", c as char))?;
+        acc = acc << 5 | value as u32;
+        bits += 5;
+        chars += 1;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    // A final group can only stop after 2, 4, 5 or 7 characters
+    if matches!(chars % 8, 1 | 3 | 6) {
+        return Err("Truncated Base32 data".to_string());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors = [("f", "MY======", "CO======"), ("fo", "MZXQ====", "CPNG===="), ("foob", "MZXW6YQ=", "CPNMUOG="), ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======")];
+        for (plain, standard, hex) in vectors {
+            assert_eq!(base32_encode(plain.as_bytes(), Base32Alphabet::Standard, true), standard);
+            assert_eq!(base32_encode(plain.as_bytes(), Base32Alphabet::Hex, true), hex);
+            assert_eq!(base32_decode(standard, Base32Alphabet::Standard).unwrap(), plain.as_bytes());
+            assert_eq!(base32_decode(&hex.to_lowercase().replace('=', ""), Base32Alphabet::Hex).unwrap(), plain.as_bytes());
+        }
+        assert!(base32_decode("MZX", Base32Alphabet::Standard).is_err());
+        assert!(base32_decode("MY==MY==", Base32Alphabet::Standard).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! Base64 and Base64url (RFC 4648)
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+const INVALID: u8 = 0xFF;
+const SKIP: u8 = 0xFE;
+const PAD: u8 = 0xFD;
+
+/// Decode table accepting both alphabets, whitespace and padding
+const DECODE: [u8; 256] = {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < 64 {
+        table[STANDARD[i] as usize] = i as u8;
+        table[URL_SAFE[i] as usize] = i as u8;
+        i += 1;
+    }
+    table[b' ' as usize] = SKIP;
+    table[b'\t' as usize] = SKIP;
+    table[b'\r' as usize] = SKIP;
+    table[b'\n' as usize] = SKIP;
+    table[b'=' as usize] = PAD;
+    table
+};
+
+/// Base64 alphabet
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base64Alphabet {
+    /// `+` and `/`
+    Standard = 0,
+    /// `-` and `_`, safe in URLs and file names
+    UrlSafe = 1,
+}
+
+impl Base64Alphabet {
+    fn table(self) -> &'static [u8; 64] {
+        match self {
+            Base64Alphabet::Standard => STANDARD,
+            Base64Alphabet::UrlSafe => URL_SAFE,
+        }
+    }
+}
+
+fn encode_into(data: &[u8], table: &[u8; 64], pad: bool, out: &mut String) {
+    out.reserve(data.len().div_ceil(3) * 4);
+    let mut chunks = data.chunks_exact(3);
+    for c in &mut chunks {
+        let n = (c[0] as u32) << 16 | (c[1] as u32) << 8 | c[2] as u32;
+        for shift in [18, 12, 6, 0] {
+            out.push(table[(n >> shift) as usize & 63] as char);
+        }
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        let n = (rest[0] as u32) << 16 | rest.get(1).map_or(0, |&b| (b as u32) << 8);
+        let chars = rest.len() + 1;
+        for &shift in &[18, 12, 6][..chars] {
+            out.push(table[(n >> shift) as usize & 63] as char);
+        }
+        if pad {
+            out.push_str(&"=="[..3 - rest.len()]);
+        }
+    }
+}
+
+/// Encode bytes as Base64
+pub fn base64_encode(data: &[u8], alphabet: Base64Alphabet, pad: bool) -> String {
+    let mut out = String::new();
+    encode_into(data, alphabet.table(), pad, &mut out);
+    out
+}
+
+/// Decode Base64 or Base64url text
+///
+/// Whitespace is ignored and padding is optional.
+pub fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut decoder = Base64Decoder::new();
+    let mut out = decoder.feed(text.as_bytes())?;
+    out.extend(decoder.finish()?);
+    Ok(out)
+}
+
+/// Build a `data:` URL for the given bytes
+pub fn data_url(data: &[u8], mime: &str) -> String {
+    let mut out = format!("data:{};base64,", mime);
+    encode_into(data, STANDARD, true, &mut out);
+    out
+}
+
+/// Streaming Base64 encoder; chunks may split 3-byte groups anywhere
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Base64Encoder {
+    table: &'static [u8; 64],
+    pad: bool,
+    pending: Vec<u8>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Base64Encoder {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(alphabet: Base64Alphabet, pad: bool) -> Base64Encoder {
+        Base64Encoder { table: alphabet.table(), pad, pending: Vec::with_capacity(2) }
+    }
+
+    /// Encode a chunk, holding back any incomplete trailing group
+    pub fn update(&mut self, data: &[u8]) -> String {
+        self.pending.extend_from_slice(data);
+        let whole = self.pending.len() / 3 * 3;
+        let mut out = String::new();
+        encode_into(&self.pending[..whole], self.table, false, &mut out);
+        self.pending.drain(..whole);
+        out
+    }
+
+    /// Encode the held-back bytes with optional padding
+    pub fn finish(&mut self) -> String {
+        let mut out = String::new();
+        encode_into(&self.pending, self.table, self.pad, &mut out);
+        self.pending.clear();
+        out
+    }
+}
+
+/// Streaming Base64 decoder accepting either alphabet
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Base64Decoder {
+    acc: u32,
+    count: u8,
+    padding: u8,
+}
+
+impl Default for Base64Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Base64Decoder {
+    /// Decode a chunk of text
+    pub fn feed(&mut self, text: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::with_capacity(text.len() / 4 * 3);
+        for &c in text {
+            match DECODE[c as usize] {
+                SKIP => {}
+                PAD => {
+                    self.padding += 1;
+                    if self.count < 2 || self.count as usize + self.padding as usize > 4 {
+                        return Err("Invalid Base64 padding".to_string());
+                    }
+                }
+                INVALID => return Err(format!("Invalid Base64 character: {:?}", c as char)),
+                _ if self.padding > 0 => return Err("Base64 data after padding".to_string()),
+                v => {
+                    self.acc = self.acc << 6 | v as u32;
+                    self.count += 1;
+                    if self.count == 4 {
+                        out.extend_from_slice(&self.acc.to_be_bytes()[1..]);
+                        self.acc = 0;
+                        self.count = 0;
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Flush a final unpadded group
+    pub fn finish(&mut self) -> Result<Vec<u8>, String> {
+        let out = match self.count {
+            0 => Vec::new(),
+            2 => vec![(self.acc >> 4) as u8],
+            3 => vec![(self.acc >> 10) as u8, (self.acc >> 2) as u8],
+            _ => return Err("Truncated Base64 data".to_string()),
+        };
+        *self = Base64Decoder::new();
+        Ok(out)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Base64Decoder {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Base64Decoder {
+        Base64Decoder { acc: 0, count: 0, padding: 0 }
+    }
+
+    /// Decode a chunk of text
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = update)]
+    pub fn update_js(&mut self, text: &str) -> Result<Vec<u8>, JsError> {
+        self.feed(text.as_bytes()).map_err(|e| JsError::new(&e))
+    }
+
+    /// Flush a final unpadded group
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = finish)]
+    pub fn finish_js(&mut self) -> Result<Vec<u8>, JsError> {
+        self.finish().map_err(|e| JsError::new(&e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy")];
+        for (plain, encoded) in vectors {
+            assert_eq!(base64_encode(plain.as_bytes(), Base64Alphabet::Standard, true), encoded);
+            assert_eq!(base64_decode(encoded).unwrap(), plain.as_bytes());
+            assert_eq!(base64_decode(encoded.trim_end_matches('=')).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_url_safe_and_whitespace() {
+        let data = [0xFB, 0xFF, 0xBF];
+        assert_eq!(base64_encode(&data, Base64Alphabet::Standard, true), "+/+/");
+        assert_eq!(base64_encode(&data, Base64Alphabet::UrlSafe, false), "-_-_");
+        assert_eq!(base64_decode("-_\r\n-/ ").unwrap(), data);
+        assert!(base64_decode("Zg==Zg==").is_err());
+        assert!(base64_decode("Z").is_err());
+        assert!(base64_decode("Zm9v!").is_err());
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+        let expected = base64_encode(&data, Base64Alphabet::UrlSafe, true);
+
+        let mut encoder = Base64Encoder::new(Base64Alphabet::UrlSafe, true);
+        let mut encoded: String = data.chunks(7).map(|c| encoder.update(c)).collect();
+        encoded.push_str(&encoder.finish());
+        assert_eq!(encoded, expected);
+
+        let mut decoder = Base64Decoder::new();
+        let mut decoded = Vec::new();
+        for chunk in expected.as_bytes().chunks(5) {
+            decoded.extend(decoder.feed(chunk).unwrap());
+        }
+        decoded.extend(decoder.finish().unwrap());
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_data_url() {
+        assert_eq!(data_url(b"hi", "text/plain"), "data:text/plain;base64,aGk=");
+    }
+}
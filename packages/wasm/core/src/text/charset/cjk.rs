@@ -0,0 +1,234 @@
+//! Double-byte CJK encodings: Shift_JIS, EUC-JP, GBK and Big5
+//!
+//! Decoding follows the WHATWG Encoding Standard: an invalid trail byte in
+//! the ASCII range is not consumed, so it decodes as itself after the error.
+
+use super::Output;
+
+const JIS0208: &[u8] = include_bytes!("tables/jis0208.bin");
+const JIS0212: &[u8] = include_bytes!("tables/jis0212.bin");
+const GB18030: &[u8] = include_bytes!("tables/gb18030.bin");
+const GB18030_RANGES: &[u8] = include_bytes!("tables/gb18030_ranges.bin");
+const BIG5: &[u8] = include_bytes!("tables/big5.bin");
+/// Bit per Big5 pointer whose code point is in plane 2 rather than the BMP
+const BIG5_ASTRAL: &[u8] = include_bytes!("tables/big5_astral.bin");
+
+/// Entry for `pointer` in a u16 table; 0 marks unmapped pointers
+fn entry(table: &[u8], pointer: usize) -> u32 {
+    table.get(pointer * 2..pointer * 2 + 2).map_or(0, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+/// Code point for `pointer` in a u16 table
+fn lookup(table: &[u8], pointer: usize) -> Option<char> {
+    match entry(table, pointer) {
+        0 => None,
+        cp => char::from_u32(cp),
+    }
+}
+
+/// Bytes to skip after an invalid two-byte sequence
+fn skip_invalid(trail: u8) -> usize {
+    if trail < 0x80 {
+        1
+    } else {
+        2
+    }
+}
+
+/// Length of a two-byte sequence that decoded to `c`; an unmapped one keeps
+/// an ASCII trail byte
+fn pair_len(c: Option<char>, trail: u8) -> usize {
+    if c.is_some() {
+        2
+    } else {
+        skip_invalid(trail)
+    }
+}
+
+pub(super) fn decode_shift_jis(data: &[u8], out: &mut Output) -> Result<(), String> {
+    let mut i = 0;
+    while i < data.len() {
+        let b = data[i];
+        match b {
+            0x00..=0x80 => out.text.push(b as char),
+            0xA1..=0xDF => out.text.push(char::from_u32(0xFF61 + (b - 0xA1) as u32).unwrap()),
+            0x81..=0x9F | 0xE0..=0xFC => {
+                let Some(&trail) = data.get(i + 1) else {
+                    out.error(i)?;
+                    break;
+                };
+                let lead_offset = if b < 0xA0 { 0x81 } else { 0xC1 };
+                let trail_offset = if trail < 0x7F { 0x40 } else { 0x41 };
+                let c = matches!(trail, 0x40..=0x7E | 0x80..=0xFC)
+                    .then(|| match (b - lead_offset) as usize * 188 + (trail - trail_offset) as usize {
+                        // User-defined characters map to the Private Use Area
+                        pointer @ 8836..=10715 => char::from_u32(0xE000 + pointer as u32 - 8836),
+                        pointer => lookup(JIS0208, pointer),
+                    })
+                    .flatten();
+                match c {
+                    Some(c) => {
+                        out.text.push(c);
+                        i += 2;
+                    }
+                    None => {
+                        out.error(i)?;
+                        i += skip_invalid(trail);
+                    }
+                }
+                continue;
+            }
+            _ => out.error(i)?,
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+pub(super) fn decode_euc_jp(data: &[u8], out: &mut Output) -> Result<(), String> {
+    let mut i = 0;
+    while i < data.len() {
+        let b = data[i];
+        if b < 0x80 {
+            out.text.push(b as char);
+            i += 1;
+            continue;
+        }
+        let trail = data.get(i + 1).copied();
+        let (c, len) = match (b, trail) {
+            // Half-width katakana
+            (0x8E, Some(t @ 0xA1..=0xDF)) => (char::from_u32(0xFF61 + (t - 0xA1) as u32), 2),
+            // JIS X 0212 three-byte sequence
+            (0x8F, Some(t @ 0xA1..=0xFE)) => match data.get(i + 2) {
+                Some(&t2 @ 0xA1..=0xFE) => (lookup(JIS0212, (t - 0xA1) as usize * 94 + (t2 - 0xA1) as usize), 3),
+                Some(&t2) => (None, 2 + skip_invalid(t2) - 1),
+                None => (None, 2),
+            },
+            (0xA1..=0xFE, Some(t @ 0xA1..=0xFE)) => (lookup(JIS0208, (b - 0xA1) as usize * 94 + (t - 0xA1) as usize), 2),
+            (0x8E | 0x8F | 0xA1..=0xFE, Some(t)) => (None, skip_invalid(t)),
+            _ => (None, 1),
+        };
+        match c {
+            Some(c) => out.text.push(c),
+            None => out.error(i)?,
+        }
+        i += len;
+    }
+    Ok(())
+}
+
+/// GB18030 four-byte pointer to code point
+fn gb18030_four_byte(pointer: usize) -> Option<char> {
+    if pointer >= 189_000 {
+        return (pointer <= 1_237_575).then(|| char::from_u32((0x10000 + pointer - 189_000) as u32)).flatten();
+    }
+    if pointer == 7457 {
+        return Some('\u{E7C7}');
+    }
+    if pointer >= 39_420 {
+        return None;
+    }
+    // Last run starting at or before the pointer
+    let runs = GB18030_RANGES.len() / 8;
+    let run = |i: usize| {
+        let r = &GB18030_RANGES[i * 8..i * 8 + 8];
+        (u32::from_le_bytes([r[0], r[1], r[2], r[3]]) as usize, u32::from_le_bytes([r[4], r[5], r[6], r[7]]) as usize)
+    };
+    let (mut lo, mut hi) = (0, runs);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if run(mid).0 <= pointer {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    let (start, cp) = run(lo.checked_sub(1)?);
+    char::from_u32((cp + pointer - start) as u32)
+}
+
+pub(super) fn decode_gbk(data: &[u8], out: &mut Output) -> Result<(), String> {
+    let mut i = 0;
+    while i < data.len() {
+        let b = data[i];
+        let (c, len) = match b {
+            0x00..=0x7F => (Some(b as char), 1),
+            0x80 => (Some('\u{20AC}'), 1),
+            0x81..=0xFE => match data.get(i + 1) {
+                Some(&t @ 0x30..=0x39) => match (data.get(i + 2), data.get(i + 3)) {
+                    (Some(&b3 @ 0x81..=0xFE), Some(&b4 @ 0x30..=0x39)) => {
+                        let pointer = (((b - 0x81) as usize * 10 + (t - 0x30) as usize) * 126 + (b3 - 0x81) as usize) * 10 + (b4 - 0x30) as usize;
+                        (gb18030_four_byte(pointer), 4)
+                    }
+                    // A four-byte sequence cut off by the end of the data is one error
+                    (None, _) | (Some(0x81..=0xFE), None) => (None, data.len() - i),
+                    _ => (None, 1),
+                },
+                Some(&t @ (0x40..=0x7E | 0x80..=0xFE)) => {
+                    let offset = if t < 0x7F { 0x40 } else { 0x41 };
+                    let c = lookup(GB18030, (b - 0x81) as usize * 190 + (t - offset) as usize);
+                    (c, pair_len(c, t))
+                }
+                Some(&t) => (None, skip_invalid(t)),
+                None => (None, 1),
+            },
+            _ => (None, 1),
+        };
+        match c {
+            Some(c) => out.text.push(c),
+            None => out.error(i)?,
+        }
+        i += len;
+    }
+    Ok(())
+}
+
+/// Big5 code point for `pointer`, from the BMP table and the plane 2 bits
+fn big5_lookup(pointer: usize) -> Option<char> {
+    let astral = BIG5_ASTRAL.get(pointer / 8).is_some_and(|&bits| bits >> (pointer % 8) & 1 != 0);
+    match entry(BIG5, pointer) {
+        0 => None,
+        cp if astral => char::from_u32(0x20000 | cp),
+        cp => char::from_u32(cp),
+    }
+}
+
+pub(super) fn decode_big5(data: &[u8], out: &mut Output) -> Result<(), String> {
+    let mut i = 0;
+    while i < data.len() {
+        let b = data[i];
+        let (c, len) = match b {
+            0x00..=0x7F => (Some(b as char), 1),
+            0x81..=0xFE => match data.get(i + 1) {
+                Some(&t @ (0x40..=0x7E | 0xA1..=0xFE)) => {
+                    let offset = if t < 0x7F { 0x40 } else { 0x62 };
+                    let pointer = (b - 0x81) as usize * 157 + (t - offset) as usize;
+                    // Four pointers decode to a letter and a combining mark
+                    let pair = match pointer {
+                        1133 => Some("\u{CA}\u{304}"),
+                        1135 => Some("\u{CA}\u{30C}"),
+                        1164 => Some("\u{EA}\u{304}"),
+                        1166 => Some("\u{EA}\u{30C}"),
+                        _ => None,
+                    };
+                    if let Some(pair) = pair {
+                        out.text.push_str(pair);
+                        i += 2;
+                        continue;
+                    }
+                    let c = big5_lookup(pointer);
+                    (c, pair_len(c, t))
+                }
+                Some(&t) => (None, skip_invalid(t)),
+                None => (None, 1),
+            },
+            _ => (None, 1),
+        };
+        match c {
+            Some(c) => out.text.push(c),
+            None => out.error(i)?,
+        }
+        i += len;
+    }
+    Ok(())
+}
@@ -0,0 +1,230 @@
+//! Legacy charset decoding to UTF-8
+//!
+//! Decoders follow the WHATWG Encoding Standard. Lookup tables are generated
+//! from its indexes, and single-byte code pages from Python's codecs, by
+//! `scripts/gen_charset_tables.py` and embedded at build time.
+//!
+//! Labels resolve as in browsers: `iso-8859-1`, `iso-8859-9` and
+//! `iso-8859-11` select Windows-1252, Windows-1254 and Windows-874. The
+//! `Iso8859_x` variants of those three decode the ISO code page itself.
+
+mod cjk;
+mod single_byte;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Legacy character encoding
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charset {
+    Windows1252 = 0,
+    Iso8859_1 = 1,
+    Iso8859_2 = 2,
+    Iso8859_3 = 3,
+    Iso8859_4 = 4,
+    Iso8859_5 = 5,
+    Iso8859_6 = 6,
+    Iso8859_7 = 7,
+    Iso8859_8 = 8,
+    Iso8859_9 = 9,
+    Iso8859_10 = 10,
+    Iso8859_11 = 11,
+    Iso8859_13 = 13,
+    Iso8859_14 = 14,
+    Iso8859_15 = 15,
+    Iso8859_16 = 16,
+    /// Windows-31J superset, as used by Japanese Windows
+    ShiftJis = 20,
+    /// Including JIS X 0212
+    EucJp = 21,
+    /// Including GB18030 four-byte sequences
+    Gbk = 22,
+    /// Big5-HKSCS
+    Big5 = 23,
+    /// Thai, a superset of ISO-8859-11
+    Windows874 = 24,
+    /// Turkish, a superset of ISO-8859-9
+    Windows1254 = 25,
+}
+
+impl Charset {
+    /// Look up a charset by label (`"shift_jis"`, `"latin2"`, `"cp936"`, ...)
+    ///
+    /// As in browsers, `iso-8859-1`, `latin1` and `ascii` select Windows-1252,
+    /// `iso-8859-9` Windows-1254 and `iso-8859-11` Windows-874.
+    pub fn from_label(label: &str) -> Option<Charset> {
+        use Charset::*;
+        let label = label.trim().to_ascii_lowercase();
+        let iso = [
+            Iso8859_1, Iso8859_2, Iso8859_3, Iso8859_4, Iso8859_5, Iso8859_6, Iso8859_7, Iso8859_8, Iso8859_9, Iso8859_10, Iso8859_11, Iso8859_1, Iso8859_13, Iso8859_14,
+            Iso8859_15, Iso8859_16,
+        ];
+        let part = ["iso-8859-", "iso8859-", "iso_8859-", "iso8859"].iter().find_map(|p| label.strip_prefix(p));
+        if let Some(n) = part.and_then(|n| n.parse::<usize>().ok()) {
+            return match n {
+                1 => Some(Windows1252),
+                9 => Some(Windows1254),
+                11 => Some(Windows874),
+                12 => None,
+                _ => iso.get(n.wrapping_sub(1)).copied(),
+            };
+        }
+        Some(match label.as_str() {
+            "windows-1252" | "cp1252" | "x-cp1252" | "latin1" | "l1" | "ascii" | "us-ascii" => Windows1252,
+            "latin2" | "l2" => Iso8859_2,
+            "latin3" | "l3" => Iso8859_3,
+            "latin4" | "l4" => Iso8859_4,
+            "cyrillic" => Iso8859_5,
+            "arabic" => Iso8859_6,
+            "greek" => Iso8859_7,
+            "hebrew" => Iso8859_8,
+            "latin5" | "l5" | "windows-1254" | "cp1254" | "x-cp1254" => Windows1254,
+            "latin6" | "l6" => Iso8859_10,
+            "tis-620" | "thai" | "windows-874" | "dos-874" => Windows874,
+            "latin7" | "l7" => Iso8859_13,
+            "latin8" | "l8" => Iso8859_14,
+            "latin9" | "l9" => Iso8859_15,
+            "latin10" | "l10" => Iso8859_16,
+            "shift_jis" | "shift-jis" | "sjis" | "ms_kanji" | "windows-31j" | "cp932" | "x-sjis" | "csshiftjis" => ShiftJis,
+            "euc-jp" | "eucjp" | "x-euc-jp" | "cseucpkdfmtjapanese" => EucJp,
+            "gbk" | "gb2312" | "gb18030" | "cp936" | "x-gbk" | "chinese" | "csgb2312" | "euc-cn" => Gbk,
+            "big5" | "big5-hkscs" | "cn-big5" | "csbig5" | "x-x-big5" | "cp950" => Big5,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Charset::Windows1252 => "windows-1252",
+            Charset::Windows874 => "windows-874",
+            Charset::Windows1254 => "windows-1254",
+            Charset::ShiftJis => "Shift_JIS",
+            Charset::EucJp => "EUC-JP",
+            Charset::Gbk => "GBK",
+            Charset::Big5 => "Big5",
+            _ => "ISO-8859",
+        }
+    }
+}
+
+/// Decoded text plus the error policy for malformed input
+struct Output {
+    text: String,
+    fatal: bool,
+    charset: Charset,
+}
+
+impl Output {
+    /// Record an invalid sequence at byte `offset`
+    fn error(&mut self, offset: usize) -> Result<(), String> {
+        if self.fatal {
+            return Err(format!("Invalid {} sequence at offset {}", self.charset.name(), offset));
+        }
+        self.text.push('\u{FFFD}');
+        Ok(())
+    }
+}
+
+/// Decode legacy-encoded bytes to a string
+///
+/// Malformed or unmapped sequences become U+FFFD, or an error when `fatal`.
+pub fn charset_decode(data: &[u8], charset: Charset, fatal: bool) -> Result<String, String> {
+    let mut out = Output { text: String::with_capacity(data.len()), fatal, charset };
+    match charset {
+        Charset::ShiftJis => cjk::decode_shift_jis(data, &mut out)?,
+        Charset::EucJp => cjk::decode_euc_jp(data, &mut out)?,
+        Charset::Gbk => cjk::decode_gbk(data, &mut out)?,
+        Charset::Big5 => cjk::decode_big5(data, &mut out)?,
+        _ => single_byte::decode(data, charset, &mut out)?,
+    }
+    Ok(out.text)
+}
+
+/// Decode legacy-encoded bytes to a string
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = charsetDecode)]
+pub fn charset_decode_js(data: &[u8], charset: Charset, fatal: bool) -> Result<String, JsError> {
+    charset_decode(data, charset, fatal).map_err(|e| JsError::new(&e))
+}
+
+/// Look up a charset by label, e.g. `"shift_jis"` or `"latin2"`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = charsetFromLabel)]
+pub fn charset_from_label_js(label: &str) -> Option<Charset> {
+    Charset::from_label(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_single_byte() {
+        assert_eq!(charset_decode(&[0x80, 0x91, 0x92, 0x81], Charset::Windows1252, false).unwrap(), "\u{20AC}\u{2018}\u{2019}\u{81}");
+        assert_eq!(charset_decode(&[0x80, 0xE9], Charset::Iso8859_1, false).unwrap(), "\u{80}\u{E9}");
+        assert_eq!(charset_decode(&hex("bfe0d8d2d5e2"), Charset::Iso8859_5, false).unwrap(), "Привет");
+        assert_eq!(charset_decode(&[0xA1], Charset::Iso8859_2, false).unwrap(), "Ą");
+        assert_eq!(charset_decode(&[0xA4], Charset::Iso8859_15, false).unwrap(), "€");
+        assert!(charset_decode(&[0xFF], Charset::Iso8859_7, true).is_err());
+    }
+
+    #[test]
+    fn test_cjk() {
+        let japanese = "日本語のﾃｷｽﾄ";
+        assert_eq!(charset_decode(&hex("93fa967b8cea82cc c3b7bdc4".replace(' ', "").as_str()), Charset::ShiftJis, false).unwrap(), japanese);
+        assert_eq!(charset_decode(&hex("c6fccbdcb8eca4ce8ec38eb78ebd8ec4"), Charset::EucJp, false).unwrap(), japanese);
+        assert_eq!(charset_decode(&hex("8fb0a1"), Charset::EucJp, false).unwrap(), "丂");
+        assert_eq!(charset_decode(&hex("d6d0cec4b2e2cad4"), Charset::Gbk, false).unwrap(), "中文测试");
+        assert_eq!(charset_decode(&hex("813084368130d330"), Charset::Gbk, false).unwrap(), "\u{A5}\u{452}");
+        assert_eq!(charset_decode(&hex("9439fc36"), Charset::Gbk, false).unwrap(), "\u{1F600}");
+        assert_eq!(charset_decode(&hex("a4a4a4e5b4fab8d5"), Charset::Big5, false).unwrap(), "中文測試");
+
+        // HKSCS characters past the BMP, and pointers with a combining mark
+        assert_eq!(charset_decode(&hex("8fda8862"), Charset::Big5, false).unwrap(), "\u{23F8F}\u{CA}\u{304}");
+        // JIS X 0212 tilde is the full-width one; user-defined Shift_JIS is private use
+        assert_eq!(charset_decode(&hex("8fa2b7"), Charset::EucJp, false).unwrap(), "\u{FF5E}");
+        assert_eq!(charset_decode(&hex("f040"), Charset::ShiftJis, false).unwrap(), "\u{E000}");
+        assert_eq!(charset_decode(&hex("a3a0a6d9"), Charset::Gbk, false).unwrap(), "\u{3000}\u{FE10}");
+    }
+
+    #[test]
+    fn test_invalid_sequences() {
+        // An ASCII trail byte is not swallowed by the failed sequence
+        assert_eq!(charset_decode(&[0x81, 0x20], Charset::ShiftJis, false).unwrap(), "\u{FFFD} ");
+        assert_eq!(charset_decode(&[0xA4], Charset::Big5, false).unwrap(), "\u{FFFD}");
+        assert_eq!(charset_decode(b"\x81<", Charset::Big5, false).unwrap(), "\u{FFFD}<");
+        assert_eq!(charset_decode(b"\x81\xA1", Charset::Big5, false).unwrap(), "\u{FFFD}");
+        assert_eq!(charset_decode(b"\x8F\xA1A", Charset::EucJp, false).unwrap(), "\u{FFFD}A");
+        assert_eq!(charset_decode(&[0x41, 0x81, 0x20], Charset::Gbk, true).unwrap_err(), "Invalid GBK sequence at offset 1");
+
+        // A four-byte GB18030 sequence cut short by the data is one error,
+        // one broken by another byte leaves that byte and the ones before it
+        assert_eq!(charset_decode(b"\x81\x30", Charset::Gbk, false).unwrap(), "\u{FFFD}");
+        assert_eq!(charset_decode(b"\x81\x30\x81", Charset::Gbk, false).unwrap(), "\u{FFFD}");
+        assert_eq!(charset_decode(b"\x81\x30A", Charset::Gbk, false).unwrap(), "\u{FFFD}0A");
+        assert_eq!(charset_decode(b"\x81\x30\x81 ", Charset::Gbk, false).unwrap(), "\u{FFFD}0\u{FFFD} ");
+        assert_eq!(charset_decode(b"\x84\x31\xA5\x30", Charset::Gbk, false).unwrap(), "\u{FFFD}");
+    }
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(Charset::from_label(" Shift_JIS "), Some(Charset::ShiftJis));
+        assert_eq!(Charset::from_label("latin1"), Some(Charset::Windows1252));
+        assert_eq!(Charset::from_label("ISO-8859-2"), Some(Charset::Iso8859_2));
+        assert_eq!(Charset::from_label("iso8859-15"), Some(Charset::Iso8859_15));
+        assert_eq!(Charset::from_label("iso-8859-12"), None);
+        assert_eq!(Charset::from_label("gb2312"), Some(Charset::Gbk));
+        assert_eq!(Charset::from_label("tis-620"), Some(Charset::Windows874));
+        assert_eq!(Charset::from_label("ISO-8859-11"), Some(Charset::Windows874));
+        assert_eq!(Charset::from_label("latin5"), Some(Charset::Windows1254));
+        assert_eq!(charset_decode(&[0x80, 0xA1], Charset::Windows874, false).unwrap(), "\u{20AC}\u{E01}");
+        assert_eq!(charset_decode(&[0x80, 0xA1], Charset::Iso8859_11, false).unwrap(), "\u{80}\u{E01}");
+        assert_eq!(charset_decode(&[0x8A, 0xF0], Charset::Windows1254, false).unwrap(), "\u{160}\u{11F}");
+        assert_eq!(Charset::from_label("utf-9"), None);
+    }
+}
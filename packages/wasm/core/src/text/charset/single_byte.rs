@@ -0,0 +1,37 @@
+//! Single-byte code pages: Windows-1252, -874, -1254 and ISO-8859-x
+
+use super::{Charset, Output};
+
+/// Upper halves (0x80-0xFF) of each code page, in `table_index` order
+const TABLES: &[u8] = include_bytes!("tables/single_byte.bin");
+
+/// Position of a code page in `TABLES`
+fn table_index(charset: Charset) -> Option<usize> {
+    use Charset::*;
+    let order = [
+        Windows1252, Iso8859_2, Iso8859_3, Iso8859_4, Iso8859_5, Iso8859_6, Iso8859_7, Iso8859_8, Iso8859_9, Iso8859_10, Iso8859_11, Iso8859_13, Iso8859_14,
+        Iso8859_15, Iso8859_16, Windows874, Windows1254,
+    ];
+    order.iter().position(|&c| c == charset)
+}
+
+pub(super) fn decode(data: &[u8], charset: Charset, out: &mut Output) -> Result<(), String> {
+    // ISO-8859-1 maps every byte to the code point of the same value
+    let Some(index) = table_index(charset) else {
+        out.text.extend(data.iter().map(|&b| b as char));
+        return Ok(());
+    };
+    let table = &TABLES[index * 256..(index + 1) * 256];
+    for (i, &b) in data.iter().enumerate() {
+        if b < 0x80 {
+            out.text.push(b as char);
+            continue;
+        }
+        let offset = (b as usize - 0x80) * 2;
+        match u16::from_le_bytes([table[offset], table[offset + 1]]) {
+            0 => out.error(i)?,
+            cp => out.text.push(char::from_u32(cp as u32).unwrap_or('\u{FFFD}')),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+//! Hexadecimal (base16)
+
+/// Encode bytes as hex digits
+pub fn hex_encode(data: &[u8], uppercase: bool) -> String {
+    let digits = if uppercase { b"0123456789ABCDEF" } else { b"0123456789abcdef" };
+    let mut out = String::with_capacity(data.len() * 2);
+    for &b in data {
+        out.push(digits[(b >> 4) as usize] as char);
+        out.push(digits[(b & 15) as usize] as char);
+    }
+    out
+}
+
+/// Decode hex digits in either case, ignoring whitespace
+pub fn hex_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len() / 2);
+    let mut high: Option<u8> = None;
+    for c in text.chars().filter(|c| !c.is_ascii_whitespace()) {
+        let nibble = c.to_digit(16).ok_or_else(|| format!("Invalid hex digit: {:?}", c))? as u8;
+        match high.take() {
+            Some(h) => out.push(h << 4 | nibble),
+            None => high = Some(nibble),
+        }
+    }
+    if high.is_some() {
+        return Err("Odd number of hex digits".to_string());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_roundtrip() {
+        assert_eq!(hex_encode(&[0x00, 0xAB, 0x7F], false), "00ab7f");
+        assert_eq!(hex_encode(&[0x00, 0xAB, 0x7F], true), "00AB7F");
+        assert_eq!(hex_decode("00 aB\n7f").unwrap(), [0x00, 0xAB, 0x7F]);
+        assert!(hex_decode("abc").is_err());
+        assert!(hex_decode("0g").is_err());
+    }
+}
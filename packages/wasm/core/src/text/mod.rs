@@ -0,0 +1,185 @@
+//! Text and binary-to-text encodings
+
+pub mod ascii85;
+pub mod base32;
+pub mod base64;
+pub mod charset;
+pub mod hex;
+pub mod percent;
+pub mod punycode;
+pub mod quoted_printable;
+pub mod unicode;
+
+pub use ascii85::{ascii85_decode, ascii85_encode};
+pub use base32::{base32_decode, base32_encode, Base32Alphabet};
+pub use charset::{charset_decode, Charset};
+pub use base64::{base64_decode, base64_encode, data_url, Base64Alphabet, Base64Decoder, Base64Encoder};
+pub use hex::{hex_decode, hex_encode};
+pub use percent::{percent_decode, percent_encode, PercentMode};
+pub use punycode::{domain_to_ascii, domain_to_unicode, punycode_decode, punycode_encode};
+pub use quoted_printable::{quoted_printable_decode, quoted_printable_encode};
+pub use unicode::{decode_unicode, decode_utf16, decode_utf32, decode_utf8, detect_bom, encode_utf16, encode_utf32, UnicodeEncoding};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Encode bytes as Base64
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = base64Encode)]
+pub fn base64_encode_js(data: &[u8], alphabet: Base64Alphabet, pad: bool) -> String {
+    base64_encode(data, alphabet, pad)
+}
+
+/// Decode Base64 or Base64url text, ignoring whitespace
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = base64Decode)]
+pub fn base64_decode_js(text: &str) -> Result<Vec<u8>, JsError> {
+    base64_decode(text).map_err(|e| JsError::new(&e))
+}
+
+/// Build a `data:` URL for the given bytes
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = dataUrl)]
+pub fn data_url_js(data: &[u8], mime: &str) -> String {
+    data_url(data, mime)
+}
+
+/// Encode bytes as hex digits
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = hexEncode)]
+pub fn hex_encode_js(data: &[u8], uppercase: bool) -> String {
+    hex_encode(data, uppercase)
+}
+
+/// Decode hex digits, ignoring whitespace
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = hexDecode)]
+pub fn hex_decode_js(text: &str) -> Result<Vec<u8>, JsError> {
+    hex_decode(text).map_err(|e| JsError::new(&e))
+}
+
+/// Encode bytes as upper-case Base32
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = base32Encode)]
+pub fn base32_encode_js(data: &[u8], alphabet: Base32Alphabet, pad: bool) -> String {
+    base32_encode(data, alphabet, pad)
+}
+
+/// Decode Base32, case-insensitively
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = base32Decode)]
+pub fn base32_decode_js(text: &str, alphabet: Base32Alphabet) -> Result<Vec<u8>, JsError> {
+    base32_decode(text, alphabet).map_err(|e| JsError::new(&e))
+}
+
+/// Encode bytes as Ascii85
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = ascii85Encode)]
+pub fn ascii85_encode_js(data: &[u8], delimiters: bool) -> String {
+    ascii85_encode(data, delimiters)
+}
+
+/// Decode Ascii85, with or without `<~` `~>` delimiters
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = ascii85Decode)]
+pub fn ascii85_decode_js(text: &str) -> Result<Vec<u8>, JsError> {
+    ascii85_decode(text).map_err(|e| JsError::new(&e))
+}
+
+/// Percent-encode text for use in a URL
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = percentEncode)]
+pub fn percent_encode_js(text: &str, mode: PercentMode) -> String {
+    percent_encode(text, mode)
+}
+
+/// Decode percent-encoded URL text
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = percentDecode)]
+pub fn percent_decode_js(text: &str, mode: PercentMode) -> Result<String, JsError> {
+    percent_decode(text, mode).map_err(|e| JsError::new(&e))
+}
+
+/// Encode bytes as quoted-printable text
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = quotedPrintableEncode)]
+pub fn quoted_printable_encode_js(data: &[u8]) -> String {
+    quoted_printable_encode(data)
+}
+
+/// Decode quoted-printable text
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = quotedPrintableDecode)]
+pub fn quoted_printable_decode_js(text: &str) -> Result<Vec<u8>, JsError> {
+    quoted_printable_decode(text).map_err(|e| JsError::new(&e))
+}
+
+/// Detect the Unicode encoding of data from its byte order mark
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = detectBom)]
+pub fn detect_bom_js(data: &[u8]) -> Option<UnicodeEncoding> {
+    detect_bom(data).map(|(encoding, _)| encoding)
+}
+
+/// Decode UTF-8, UTF-16 or UTF-32 text by its BOM, defaulting to UTF-8
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = unicodeDecode)]
+pub fn decode_unicode_js(data: &[u8], fatal: bool) -> Result<String, JsError> {
+    decode_unicode(data, fatal).map_err(|e| JsError::new(&e))
+}
+
+/// Decode UTF-16 text; a BOM overrides the byte order
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = utf16Decode)]
+pub fn decode_utf16_js(data: &[u8], big_endian: bool, fatal: bool) -> Result<String, JsError> {
+    decode_utf16(data, big_endian, fatal).map_err(|e| JsError::new(&e))
+}
+
+/// Encode text as UTF-16
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = utf16Encode)]
+pub fn encode_utf16_js(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+    encode_utf16(text, big_endian, bom)
+}
+
+/// Decode UTF-32 text; a BOM overrides the byte order
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = utf32Decode)]
+pub fn decode_utf32_js(data: &[u8], big_endian: bool, fatal: bool) -> Result<String, JsError> {
+    decode_utf32(data, big_endian, fatal).map_err(|e| JsError::new(&e))
+}
+
+/// Encode text as UTF-32
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = utf32Encode)]
+pub fn encode_utf32_js(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+    encode_utf32(text, big_endian, bom)
+}
+
+/// Encode a label as Punycode, without the `xn--` prefix
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = punycodeEncode)]
+pub fn punycode_encode_js(text: &str) -> Result<String, JsError> {
+    punycode_encode(text).map_err(|e| JsError::new(&e))
+}
+
+/// Decode a Punycode label, without the `xn--` prefix
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = punycodeDecode)]
+pub fn punycode_decode_js(text: &str) -> Result<String, JsError> {
+    punycode_decode(text).map_err(|e| JsError::new(&e))
+}
+
+/// Convert an internationalized domain name to its ASCII form
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = domainToAscii)]
+pub fn domain_to_ascii_js(domain: &str) -> Result<String, JsError> {
+    domain_to_ascii(domain).map_err(|e| JsError::new(&e))
+}
+
+/// Convert an ASCII domain name to Unicode, decoding `xn--` labels
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = domainToUnicode)]
+pub fn domain_to_unicode_js(domain: &str) -> Result<String, JsError> {
+    domain_to_unicode(domain).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,90 @@
+//! URL percent-encoding (RFC 3986)
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Which characters percent-encoding leaves alone
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PercentMode {
+    /// A single path segment or query value, like `encodeURIComponent`
+    Component = 0,
+    /// A full URL, keeping its delimiters, like `encodeURI`
+    Url = 1,
+    /// `application/x-www-form-urlencoded`: spaces become `+`
+    Form = 2,
+}
+
+fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-_.!~*'()".contains(&b)
+}
+
+fn keep(b: u8, mode: PercentMode) -> bool {
+    match mode {
+        PercentMode::Component => is_unreserved(b),
+        PercentMode::Url => is_unreserved(b) || b";,/?:@&=+$#".contains(&b),
+        PercentMode::Form => b.is_ascii_alphanumeric() || b"-_.*".contains(&b),
+    }
+}
+
+/// Percent-encode the UTF-8 bytes of `text`
+pub fn percent_encode(text: &str, mode: PercentMode) -> String {
+    let mut out = String::with_capacity(text.len());
+    for &b in text.as_bytes() {
+        if keep(b, mode) {
+            out.push(b as char);
+        } else if b == b' ' && mode == PercentMode::Form {
+            out.push('+');
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes (and `+` in form mode) into UTF-8 text
+pub fn percent_decode(text: &str, mode: PercentMode) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let byte = bytes
+                    .get(i + 1..i + 3)
+                    // from_str_radix alone would accept a sign, as in "%+F"
+                    .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| format!("Invalid percent escape at offset {}", i))?;
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            b'+' if mode == PercentMode::Form => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(out).map_err(|_| "Percent-decoded text is not valid UTF-8".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_modes() {
+        let text = "a b/c?d=é&x";
+        assert_eq!(percent_encode(text, PercentMode::Component), "a%20b%2Fc%3Fd%3D%C3%A9%26x");
+        assert_eq!(percent_encode(text, PercentMode::Url), "a%20b/c?d=%C3%A9&x");
+        assert_eq!(percent_encode(text, PercentMode::Form), "a+b%2Fc%3Fd%3D%C3%A9%26x");
+        for mode in [PercentMode::Component, PercentMode::Url, PercentMode::Form] {
+            assert_eq!(percent_decode(&percent_encode(text, mode), mode).unwrap(), text);
+        }
+        assert_eq!(percent_decode("a+b", PercentMode::Component).unwrap(), "a+b");
+        assert!(percent_decode("%2", PercentMode::Component).is_err());
+        assert!(percent_decode("%+F", PercentMode::Component).is_err());
+        assert!(percent_decode("%FF", PercentMode::Component).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+//! Punycode (RFC 3492) and IDNA domain name conversion
+//!
+//! Labels are mapped as UTS #46 nontransitional processing does, as in
+//! browsers: NFKC_Casefold, so full-width and compatibility characters
+//! become their plain forms, while ß, ς, ZWJ and ZWNJ are kept. The
+//! ideographic full stops separate labels. Controls, private use and
+//! unassigned code points are rejected. There is no full NFC step, so a
+//! label whose mapping could still change under NFC is rejected too. The
+//! tables are generated by `scripts/gen_idna_tables.py`.
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 0x80;
+
+/// IDNA label prefix for Punycode-encoded labels
+const ACE_PREFIX: &str = "xn--";
+
+/// Longest label allowed in a DNS name
+const MAX_LABEL: usize = 63;
+
+/// UTS #46 mapping as (first, last, value) runs
+const MAP: &[u8] = include_bytes!("tables/idna_map.bin");
+/// UTF-8 targets of the mappings to other than one code point
+const MAP_STRINGS: &[u8] = include_bytes!("tables/idna_strings.bin");
+/// Combining class, NFC composition and disallowed runs
+const PROPS: &[u8] = include_bytes!("tables/idna_props.bin");
+const COMPOSES: u32 = 0x100;
+const DISALLOWED: u32 = 0x200;
+
+fn props(c: char) -> u32 {
+    find_run(PROPS, c).map_or(0, |(props, _)| props)
+}
+
+fn adapt(mut delta: u32, points: u32, first: bool) -> u32 {
+    delta /= if first { DAMP } else { 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    k.saturating_sub(bias).clamp(T_MIN, T_MAX)
+}
+
+fn digit_char(d: u32) -> char {
+    (if d < 26 { b'a' + d as u8 } else { b'0' + (d - 26) as u8 }) as char
+}
+
+fn char_digit(c: char) -> Option<u32> {
+    match c {
+        'a'..='z' => Some(c as u32 - 'a' as u32),
+        'A'..='Z' => Some(c as u32 - 'A' as u32),
+        '0'..='9' => Some(c as u32 - '0' as u32 + 26),
+        _ => None,
+    }
+}
+
+/// Encode a label as Punycode, without the `xn--` prefix
+pub fn punycode_encode(input: &str) -> Result<String, String> {
+    let chars: Vec<u32> = input.chars().map(u32::from).collect();
+    let mut out: String = input.chars().filter(char::is_ascii).collect();
+    let basic = out.len() as u32;
+    if basic > 0 {
+        out.push('-');
+    }
+
+    let overflow = || "Punycode overflow".to_string();
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut handled = basic;
+    while (handled as usize) < chars.len() {
+        let m = chars.iter().copied().filter(|&c| c >= n).min().unwrap_or(n);
+        delta = (m - n).checked_mul(handled + 1).and_then(|d| d.checked_add(delta)).ok_or_else(overflow)?;
+        n = m;
+        for &c in &chars {
+            if c < n {
+                delta = delta.checked_add(1).ok_or_else(overflow)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = threshold(k, bias);
+                    if q < t {
+                        break;
+                    }
+                    out.push(digit_char(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                out.push(digit_char(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Ok(out)
+}
+
+/// Decode a Punycode label, without the `xn--` prefix
+pub fn punycode_decode(input: &str) -> Result<String, String> {
+    let (basic, encoded) = match input.rfind('-') {
+        Some(i) => (&input[..i], &input[i + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return Err("Punycode basic code points must be ASCII".to_string());
+    }
+    let mut out: Vec<char> = basic.chars().collect();
+
+    let invalid = || "Invalid Punycode".to_string();
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut digits = encoded.chars();
+    while !digits.as_str().is_empty() {
+        let old_i = i;
+        let mut w: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let d = digits.next().and_then(char_digit).ok_or_else(invalid)?;
+            i = d.checked_mul(w).and_then(|x| x.checked_add(i)).ok_or_else(invalid)?;
+            let t = threshold(k, bias);
+            if d < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t).ok_or_else(invalid)?;
+            k += BASE;
+        }
+        let len = out.len() as u32 + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len).ok_or_else(invalid)?;
+        i %= len;
+        let c = char::from_u32(n).filter(|c| !c.is_ascii()).ok_or_else(invalid)?;
+        out.insert(i as usize, c);
+        i += 1;
+    }
+    Ok(out.into_iter().collect())
+}
+
+/// Value of the run of `table` holding `c`, and the offset of `c` in the run
+fn find_run(table: &[u8], c: char) -> Option<(u32, u32)> {
+    let cp = c as u32;
+    let run = |i: usize| [0, 4, 8].map(|at| u32::from_le_bytes([table[i * 12 + at], table[i * 12 + at + 1], table[i * 12 + at + 2], table[i * 12 + at + 3]]));
+    // Last run starting at or before the code point
+    let (mut lo, mut hi) = (0, table.len() / 12);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if run(mid)[0] <= cp {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    let [first, last, value] = run(lo.checked_sub(1)?);
+    (cp <= last).then_some((value, cp - first))
+}
+
+/// Append the UTS #46 mapping of `c`
+fn map_char(c: char, out: &mut String) {
+    match find_run(MAP, c) {
+        Some((value, offset)) if value & 0x8000_0000 == 0 => out.extend(char::from_u32(value + offset)),
+        Some((value, _)) => {
+            let (start, len) = ((value & 0x7FFF_FFFF) as usize >> 8, value as usize & 0xFF);
+            out.push_str(core::str::from_utf8(&MAP_STRINGS[start..start + len]).unwrap_or_default());
+        }
+        None => out.extend(c.to_lowercase()),
+    }
+}
+
+/// Whether `label` is certainly in NFC: combining marks in canonical order
+/// and none that could compose with what precedes them
+fn is_nfc(label: &str) -> bool {
+    let mut last_class = 0;
+    for c in label.chars() {
+        let props = props(c);
+        let class = props & 0xFF;
+        if props & COMPOSES != 0 || (class != 0 && last_class > class) {
+            return false;
+        }
+        last_class = class;
+    }
+    true
+}
+
+/// Map a label as UTS #46 does
+fn map_label(label: &str) -> Result<String, String> {
+    let mut mapped = String::with_capacity(label.len());
+    for c in label.chars() {
+        map_char(c, &mut mapped);
+    }
+    // Characters like U+2488 map to text with a full stop, which UTS #46 disallows
+    if mapped.contains('.') || mapped.chars().any(|c| props(c) & DISALLOWED != 0) {
+        return Err(format!("Label '{}' contains a disallowed character", label));
+    }
+    if !is_nfc(&mapped) {
+        return Err(format!("Label '{}' is not in Unicode normalization form C", label));
+    }
+    Ok(mapped)
+}
+
+fn labels(domain: &str) -> impl Iterator<Item = &str> {
+    domain.split(['.', '\u{3002}', '\u{FF0E}', '\u{FF61}'])
+}
+
+fn check_label(label: &str, domain: &str, last: bool) -> Result<(), String> {
+    if label.is_empty() && !last {
+        return Err(format!("Empty label in domain '{}'", domain));
+    }
+    if label.len() > MAX_LABEL {
+        return Err(format!("Label '{}' is longer than {} bytes", label, MAX_LABEL));
+    }
+    Ok(())
+}
+
+/// Convert a domain name to its ASCII (`xn--`) form
+pub fn domain_to_ascii(domain: &str) -> Result<String, String> {
+    let parts: Vec<&str> = labels(domain).collect();
+    let mut out = Vec::with_capacity(parts.len());
+    for (i, label) in parts.iter().enumerate() {
+        let label = map_label(label)?;
+        let ascii = if label.is_ascii() { label } else { format!("{}{}", ACE_PREFIX, punycode_encode(&label)?) };
+        check_label(&ascii, domain, i + 1 == parts.len())?;
+        out.push(ascii);
+    }
+    Ok(out.join("."))
+}
+
+/// Convert a domain name to Unicode, decoding `xn--` labels
+pub fn domain_to_unicode(domain: &str) -> Result<String, String> {
+    let parts: Vec<&str> = labels(domain).collect();
+    let mut out = Vec::with_capacity(parts.len());
+    for (i, label) in parts.iter().enumerate() {
+        check_label(label, domain, i + 1 == parts.len())?;
+        let label = map_label(label)?;
+        match label.strip_prefix(ACE_PREFIX) {
+            Some(encoded) => out.push(punycode_decode(encoded)?),
+            None => out.push(label),
+        }
+    }
+    Ok(out.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc3492_samples() {
+        let samples = [
+            // (A) Arabic (Egyptian)
+            ("\u{644}\u{64A}\u{647}\u{645}\u{627}\u{628}\u{62A}\u{643}\u{644}\u{645}\u{648}\u{634}\u{639}\u{631}\u{628}\u{64A}\u{61F}", "egbpdaj6bu4bxfgehfvwxn"),
+            // (B) Chinese (simplified)
+            ("他们为什么不说中文", "ihqwcrb4cv8a8dqg056pqjye"),
+            // (L) Japanese, mixed with ASCII
+            ("3年B組金八先生", "3B-ww4c5e180e575a65lsy2b"),
+            // (S) all basic code points
+            ("-> $1.00 <-", "-> $1.00 <--"),
+        ];
+        for (text, encoded) in samples {
+            assert_eq!(punycode_encode(text).unwrap(), encoded);
+            assert_eq!(punycode_decode(encoded).unwrap(), text);
+        }
+        assert!(punycode_decode("99999999999").is_err());
+        assert!(punycode_decode("a!").is_err());
+    }
+
+    #[test]
+    fn test_domains() {
+        assert_eq!(domain_to_ascii("Bücher.example").unwrap(), "xn--bcher-kva.example");
+        assert_eq!(domain_to_ascii("例え。テスト").unwrap(), "xn--r8jz45g.xn--zckzah");
+        assert_eq!(domain_to_unicode("XN--bcher-kva.example.").unwrap(), "bücher.example.");
+        assert!(domain_to_ascii("a..b").is_err());
+        assert!(domain_to_ascii(&"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_uts46_mapping() {
+        // Full-width and compatibility characters map to their plain forms
+        assert_eq!(domain_to_ascii("ｅｘａｍｐｌｅ.com").unwrap(), "example.com");
+        assert_eq!(domain_to_ascii("Ⅻ.com").unwrap(), "xii.com");
+        assert_eq!(domain_to_ascii("ＢÜＣＨＥＲ.ｅｘａｍｐｌｅ").unwrap(), "xn--bcher-kva.example");
+        assert_eq!(domain_to_ascii("ﬁle.com").unwrap(), "file.com");
+        assert_eq!(domain_to_ascii("ex\u{AD}ample.com").unwrap(), "example.com");
+        assert_eq!(domain_to_ascii("ẞ.de").unwrap(), "xn--zca.de");
+        assert_eq!(domain_to_unicode("ＥＸＡＭＰＬＥ.com").unwrap(), "example.com");
+        // Nontransitional processing keeps the deviation characters
+        assert_eq!(domain_to_ascii("faß.de").unwrap(), "xn--fa-hia.de");
+
+        assert_eq!(domain_to_ascii("a\u{2488}.com").unwrap_err(), "Label 'a\u{2488}' contains a disallowed character");
+        assert!(domain_to_ascii("a\u{E000}.com").is_err());
+        assert!(domain_to_ascii("a\u{85}.com").is_err());
+        assert!(domain_to_ascii("a\u{50000}.com").is_err());
+        assert_eq!(domain_to_ascii("e\u{301}.com").unwrap_err(), "Label 'e\u{301}' is not in Unicode normalization form C");
+        assert!(domain_to_ascii("a\u{301}\u{323}.com").is_err());
+        assert_eq!(domain_to_ascii("\u{E9}.com").unwrap(), "xn--9ca.com");
+    }
+}
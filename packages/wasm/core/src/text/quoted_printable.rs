@@ -0,0 +1,90 @@
+//! Quoted-printable (RFC 2045)
+
+/// Longest encoded line, excluding the CRLF
+const MAX_LINE: usize = 76;
+
+/// Encode bytes as quoted-printable text
+///
+/// Input line breaks (`\n` or `\r\n`) become CRLF; longer lines are wrapped
+/// with soft breaks.
+pub fn quoted_printable_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() + data.len() / 8);
+    let mut lines = data.split(|&b| b == b'\n').peekable();
+    while let Some(line) = lines.next() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let mut width = 0;
+        for (i, &b) in line.iter().enumerate() {
+            // Trailing whitespace would be stripped in transit
+            let literal = (b == b' ' || b == b'\t') && i + 1 < line.len() || (33..=126).contains(&b) && b != b'=';
+            let len = if literal { 1 } else { 3 };
+            if width + len > MAX_LINE - 1 {
+                out.push_str("=\r\n");
+                width = 0;
+            }
+            if literal {
+                out.push(b as char);
+            } else {
+                out.push_str(&format!("={:02X}", b));
+            }
+            width += len;
+        }
+        if lines.peek().is_some() {
+            out.push_str("\r\n");
+        }
+    }
+    out
+}
+
+/// Decode quoted-printable text, keeping hard line breaks as CRLF
+pub fn quoted_printable_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut lines = text.split('\n').peekable();
+    while let Some(line) = lines.next() {
+        let line = line.strip_suffix('\r').unwrap_or(line).trim_end_matches([' ', '\t']).as_bytes();
+        let (line, soft) = match line.strip_suffix(b"=") {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+        let mut i = 0;
+        while i < line.len() {
+            if line[i] == b'=' {
+                let byte = line
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or("Invalid quoted-printable escape")?;
+                out.push(byte);
+                i += 3;
+            } else {
+                out.push(line[i]);
+                i += 1;
+            }
+        }
+        if !soft && lines.peek().is_some() {
+            out.extend_from_slice(b"\r\n");
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoted_printable() {
+        let text = "Caf\u{e9} = 100%  \nnext line\t";
+        let encoded = quoted_printable_encode(text.as_bytes());
+        assert_eq!(encoded, "Caf=C3=A9 =3D 100% =20\r\nnext line=09");
+        assert_eq!(quoted_printable_decode(&encoded).unwrap(), text.replace('\n', "\r\n").as_bytes());
+        assert!(quoted_printable_decode("bad =G1").is_err());
+    }
+
+    #[test]
+    fn test_soft_line_breaks() {
+        let long = "x".repeat(200);
+        let encoded = quoted_printable_encode(long.as_bytes());
+        assert!(encoded.split("\r\n").all(|line| line.len() <= MAX_LINE));
+        assert_eq!(quoted_printable_decode(&encoded).unwrap(), long.as_bytes());
+    }
+}
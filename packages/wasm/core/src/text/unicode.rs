@@ -0,0 +1,190 @@
+//! UTF-8, UTF-16 and UTF-32 transcoding with byte order mark handling
+//!
+//! Decoders either replace malformed input with U+FFFD (lossy) or fail at the
+//! first bad code unit (fatal). A leading BOM always wins over the requested
+//! byte order and is not part of the decoded text.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Unicode encoding form and byte order
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnicodeEncoding {
+    Utf8 = 0,
+    Utf16Le = 1,
+    Utf16Be = 2,
+    Utf32Le = 3,
+    Utf32Be = 4,
+}
+
+/// Detect a byte order mark, returning the encoding and BOM length
+pub fn detect_bom(data: &[u8]) -> Option<(UnicodeEncoding, usize)> {
+    // UTF-32LE must be tested before UTF-16LE, whose BOM is its prefix
+    match data {
+        [0xEF, 0xBB, 0xBF, ..] => Some((UnicodeEncoding::Utf8, 3)),
+        [0xFF, 0xFE, 0, 0, ..] => Some((UnicodeEncoding::Utf32Le, 4)),
+        [0, 0, 0xFE, 0xFF, ..] => Some((UnicodeEncoding::Utf32Be, 4)),
+        [0xFF, 0xFE, ..] => Some((UnicodeEncoding::Utf16Le, 2)),
+        [0xFE, 0xFF, ..] => Some((UnicodeEncoding::Utf16Be, 2)),
+        _ => None,
+    }
+}
+
+fn invalid(what: &str, offset: usize) -> String {
+    format!("Invalid {} at byte {}", what, offset)
+}
+
+/// Decode UTF-8, skipping a BOM
+pub fn decode_utf8(data: &[u8], fatal: bool) -> Result<String, String> {
+    let start = if data.starts_with(&[0xEF, 0xBB, 0xBF]) { 3 } else { 0 };
+    let data = &data[start..];
+    match std::str::from_utf8(data) {
+        Ok(s) => Ok(s.to_string()),
+        Err(e) if fatal => Err(invalid("UTF-8", start + e.valid_up_to())),
+        Err(_) => Ok(String::from_utf8_lossy(data).into_owned()),
+    }
+}
+
+/// Decode UTF-16; a BOM overrides `big_endian`
+pub fn decode_utf16(data: &[u8], big_endian: bool, fatal: bool) -> Result<String, String> {
+    let (big_endian, start) = match detect_bom(data) {
+        Some((UnicodeEncoding::Utf16Le, n)) => (false, n),
+        Some((UnicodeEncoding::Utf16Be, n)) => (true, n),
+        _ => (big_endian, 0),
+    };
+    let unit = |i: usize| {
+        let b = [data[i], data[i + 1]];
+        if big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) }
+    };
+    let mut out = String::with_capacity(data.len() / 2);
+    let mut i = start;
+    while i + 1 < data.len() {
+        let u = unit(i);
+        match u {
+            0xD800..=0xDBFF if i + 3 < data.len() && (0xDC00..=0xDFFF).contains(&unit(i + 2)) => {
+                let c = 0x10000 + (((u as u32) - 0xD800) << 10) + (unit(i + 2) as u32 - 0xDC00);
+                out.push(char::from_u32(c).unwrap_or('\u{FFFD}'));
+                i += 4;
+                continue;
+            }
+            0xD800..=0xDFFF if fatal => return Err(invalid("UTF-16 surrogate", i)),
+            0xD800..=0xDFFF => out.push('\u{FFFD}'),
+            _ => out.push(char::from_u32(u as u32).unwrap_or('\u{FFFD}')),
+        }
+        i += 2;
+    }
+    if i < data.len() {
+        if fatal {
+            return Err(invalid("trailing UTF-16 byte", i));
+        }
+        out.push('\u{FFFD}');
+    }
+    Ok(out)
+}
+
+/// Decode UTF-32; a BOM overrides `big_endian`
+pub fn decode_utf32(data: &[u8], big_endian: bool, fatal: bool) -> Result<String, String> {
+    let (big_endian, start) = match detect_bom(data) {
+        Some((UnicodeEncoding::Utf32Le, n)) => (false, n),
+        Some((UnicodeEncoding::Utf32Be, n)) => (true, n),
+        _ => (big_endian, 0),
+    };
+    let body = &data[start..];
+    let mut out = String::with_capacity(body.len() / 4);
+    for (i, c) in body.chunks_exact(4).enumerate() {
+        let b = [c[0], c[1], c[2], c[3]];
+        let v = if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) };
+        match char::from_u32(v) {
+            Some(ch) => out.push(ch),
+            None if fatal => return Err(invalid("UTF-32 code point", start + i * 4)),
+            None => out.push('\u{FFFD}'),
+        }
+    }
+    if !body.len().is_multiple_of(4) {
+        if fatal {
+            return Err(invalid("trailing UTF-32 bytes", data.len() - body.len() % 4));
+        }
+        out.push('\u{FFFD}');
+    }
+    Ok(out)
+}
+
+/// Decode text in any Unicode encoding form, sniffing the BOM and
+/// defaulting to UTF-8
+pub fn decode_unicode(data: &[u8], fatal: bool) -> Result<String, String> {
+    match detect_bom(data).map(|(e, _)| e) {
+        Some(UnicodeEncoding::Utf16Le) => decode_utf16(data, false, fatal),
+        Some(UnicodeEncoding::Utf16Be) => decode_utf16(data, true, fatal),
+        Some(UnicodeEncoding::Utf32Le) => decode_utf32(data, false, fatal),
+        Some(UnicodeEncoding::Utf32Be) => decode_utf32(data, true, fatal),
+        _ => decode_utf8(data, fatal),
+    }
+}
+
+/// Encode text as UTF-16, optionally prefixed with a BOM
+pub fn encode_utf16(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len() * 2 + 2);
+    let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+    for u in units {
+        out.extend_from_slice(&if big_endian { u.to_be_bytes() } else { u.to_le_bytes() });
+    }
+    out
+}
+
+/// Encode text as UTF-32, optionally prefixed with a BOM
+pub fn encode_utf32(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len() * 4 + 4);
+    let chars = bom.then_some(0xFEFF).into_iter().chain(text.chars().map(u32::from));
+    for c in chars {
+        out.extend_from_slice(&if big_endian { c.to_be_bytes() } else { c.to_le_bytes() });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf16_roundtrip() {
+        let text = "héllo 😀 世界";
+        for be in [false, true] {
+            for bom in [false, true] {
+                let bytes = encode_utf16(text, be, bom);
+                assert_eq!(decode_utf16(&bytes, be, true).unwrap(), text);
+                // The BOM decides the byte order when present
+                if bom {
+                    assert_eq!(decode_utf16(&bytes, !be, true).unwrap(), text);
+                    assert_eq!(decode_unicode(&bytes, true).unwrap(), text);
+                }
+            }
+        }
+        assert_eq!(encode_utf16("😀", true, true), [0xFE, 0xFF, 0xD8, 0x3D, 0xDE, 0x00]);
+    }
+
+    #[test]
+    fn test_utf32_roundtrip() {
+        let text = "a😀";
+        assert_eq!(encode_utf32(text, false, true), [0xFF, 0xFE, 0, 0, 0x61, 0, 0, 0, 0x00, 0xF6, 0x01, 0]);
+        for be in [false, true] {
+            let bytes = encode_utf32(text, be, true);
+            assert_eq!(decode_unicode(&bytes, true).unwrap(), text);
+            assert_eq!(decode_utf32(&bytes[4..], be, true).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        // Lone high surrogate followed by 'A', then an odd trailing byte
+        let bad = [0x00, 0xD8, 0x41, 0x00, 0x42];
+        assert_eq!(decode_utf16(&bad, false, false).unwrap(), "\u{FFFD}A\u{FFFD}");
+        assert_eq!(decode_utf16(&bad, false, true).unwrap_err(), "Invalid UTF-16 surrogate at byte 0");
+        assert_eq!(decode_utf32(&[0, 0xD8, 0, 0, 0x41, 0, 0, 0], false, false).unwrap(), "\u{FFFD}A");
+        assert!(decode_utf32(&[0, 0, 0x11, 0], false, true).is_err());
+        assert_eq!(decode_utf8(b"\xEF\xBB\xBFok\xFF", false).unwrap(), "ok\u{FFFD}");
+        assert_eq!(decode_utf8(b"ok\xFF", true).unwrap_err(), "Invalid UTF-8 at byte 2");
+        assert_eq!(detect_bom(&[0xFF, 0xFE, 0, 0]), Some((UnicodeEncoding::Utf32Le, 4)));
+        assert_eq!(detect_bom(b"plain"), None);
+    }
+}
@@ -0,0 +1,40 @@
+//! Rectangular crops
+
+use crate::utils::check_rgba;
+use alloc::{format, string::String, vec::Vec};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A crop rectangle in pixels
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CropRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Copy a rectangle out of an RGBA image
+pub fn crop(data: &[u8], width: u32, height: u32, region: CropRegion) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    if region.width == 0
+        || region.height == 0
+        || region.x as u64 + region.width as u64 > width as u64
+        || region.y as u64 + region.height as u64 > height as u64
+    {
+        return Err(format!(
+            "Crop {}x{}+{}+{} outside {}x{} image",
+            region.width, region.height, region.x, region.y, width, height
+        ));
+    }
+
+    let stride = width as usize * 4;
+    let row_len = region.width as usize * 4;
+    let mut output = Vec::with_capacity(row_len * region.height as usize);
+    for y in region.y as usize..(region.y + region.height) as usize {
+        let start = y * stride + region.x as usize * 4;
+        output.extend_from_slice(&data[start..start + row_len]);
+    }
+    Ok(output)
+}
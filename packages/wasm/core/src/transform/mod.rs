@@ -0,0 +1,95 @@
+//! Geometric transforms
+
+pub mod crop;
+pub mod orientation;
+#[cfg(feature = "std")]
+pub mod smart_crop;
+
+pub use crop::{crop, CropRegion};
+pub use orientation::{apply_orientation, flip_horizontal, flip_vertical, rotate90};
+#[cfg(feature = "std")]
+pub use smart_crop::{saliency_map, smart_crop, smart_crop_region};
+
+#[cfg(feature = "wasm")]
+use crate::utils::to_array_buffer;
+use alloc::vec::Vec;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// An RGBA image whose dimensions may differ from the input's
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl RgbaImage {
+    /// Move the pixels into a transferable `ArrayBuffer`, freeing the WASM
+    /// copy; read `width` and `height` first, as the image is consumed
+    ///
+    /// Reading `data` copies the pixels on every access, while this copies
+    /// once and lets a worker post the buffer to another thread for free.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = intoTransferable)]
+    pub fn into_transferable(self) -> js_sys::ArrayBuffer {
+        to_array_buffer(&self.data)
+    }
+}
+
+/// Correct an image for its EXIF orientation (1-8)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = applyOrientation)]
+pub fn apply_orientation_js(data: &[u8], width: u32, height: u32, orientation: u16) -> Result<RgbaImage, JsError> {
+    apply_orientation(data, width, height, orientation).map_err(|e| JsError::new(&e))
+}
+
+/// Rotate clockwise by `quarterTurns` x 90 degrees
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = rotate90)]
+pub fn rotate90_js(data: &[u8], width: u32, height: u32, quarter_turns: u32) -> Result<RgbaImage, JsError> {
+    rotate90(data, width, height, quarter_turns).map_err(|e| JsError::new(&e))
+}
+
+/// Mirror left-to-right
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = flipHorizontal)]
+pub fn flip_horizontal_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    flip_horizontal(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Mirror top-to-bottom
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = flipVertical)]
+pub fn flip_vertical_js(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    flip_vertical(data, width, height).map_err(|e| JsError::new(&e))
+}
+
+/// Copy a rectangle out of an RGBA image
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = crop)]
+pub fn crop_js(data: &[u8], width: u32, height: u32, x: u32, y: u32, crop_width: u32, crop_height: u32) -> Result<Vec<u8>, JsError> {
+    let region = CropRegion {
+        x,
+        y,
+        width: crop_width,
+        height: crop_height,
+    };
+    crop(data, width, height, region).map_err(|e| JsError::new(&e))
+}
+
+/// Pick the most salient crop window for the target aspect ratio
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = smartCropRegion)]
+pub fn smart_crop_region_js(data: &[u8], width: u32, height: u32, target_width: u32, target_height: u32) -> Result<CropRegion, JsError> {
+    smart_crop_region(data, width, height, target_width, target_height).map_err(|e| JsError::new(&e))
+}
+
+/// Crop to the most salient region and resize to the target size
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = smartCrop)]
+pub fn smart_crop_js(data: &[u8], width: u32, height: u32, target_width: u32, target_height: u32) -> Result<RgbaImage, JsError> {
+    smart_crop(data, width, height, target_width, target_height).map_err(|e| JsError::new(&e))
+}
@@ -0,0 +1,98 @@
+//! Flips, right-angle rotations, and EXIF orientation correction
+
+use super::RgbaImage;
+use crate::utils::check_rgba;
+use alloc::{string::String, vec::Vec};
+
+/// Remap pixels so output (x, y) reads source `map(x, y)`
+fn remap(data: &[u8], width: u32, height: u32, swap: bool, map: impl Fn(usize, usize) -> (usize, usize)) -> RgbaImage {
+    let w = width as usize;
+    let (out_w, out_h) = if swap { (height, width) } else { (width, height) };
+
+    let mut output = Vec::with_capacity(data.len());
+    for y in 0..out_h as usize {
+        for x in 0..out_w as usize {
+            let (sx, sy) = map(x, y);
+            let i = (sy * w + sx) * 4;
+            output.extend_from_slice(&data[i..i + 4]);
+        }
+    }
+    RgbaImage {
+        width: out_w,
+        height: out_h,
+        data: output,
+    }
+}
+
+/// Mirror left-to-right
+pub fn flip_horizontal(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    apply_orientation(data, width, height, 2).map(|img| img.data)
+}
+
+/// Mirror top-to-bottom
+pub fn flip_vertical(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    apply_orientation(data, width, height, 4).map(|img| img.data)
+}
+
+/// Rotate clockwise by a multiple of 90 degrees
+pub fn rotate90(data: &[u8], width: u32, height: u32, quarter_turns: u32) -> Result<RgbaImage, String> {
+    let orientation = [1, 6, 3, 8][(quarter_turns % 4) as usize];
+    apply_orientation(data, width, height, orientation)
+}
+
+/// Transform an image stored with EXIF `orientation` (1-8) to upright
+///
+/// Orientations 5-8 swap width and height. Unknown values leave the image
+/// unchanged.
+pub fn apply_orientation(data: &[u8], width: u32, height: u32, orientation: u16) -> Result<RgbaImage, String> {
+    check_rgba(data, width, height)?;
+    let w = width as usize;
+    let h = height as usize;
+
+    Ok(match orientation {
+        2 => remap(data, width, height, false, |x, y| (w - 1 - x, y)),
+        3 => remap(data, width, height, false, |x, y| (w - 1 - x, h - 1 - y)),
+        4 => remap(data, width, height, false, |x, y| (x, h - 1 - y)),
+        5 => remap(data, width, height, true, |x, y| (y, x)),
+        6 => remap(data, width, height, true, |x, y| (y, h - 1 - x)),
+        7 => remap(data, width, height, true, |x, y| (w - 1 - y, h - 1 - x)),
+        8 => remap(data, width, height, true, |x, y| (w - 1 - y, x)),
+        _ => RgbaImage {
+            width,
+            height,
+            data: data.to_vec(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2x1 image: red, green
+    const RG: [u8; 8] = [255, 0, 0, 255, 0, 255, 0, 255];
+
+    #[test]
+    fn test_rotate_90_cw() {
+        // Orientation 6 is stored rotated 90 CCW; correcting rotates CW
+        let img = apply_orientation(&RG, 2, 1, 6).unwrap();
+        assert_eq!((img.width, img.height), (1, 2));
+        assert_eq!(&img.data[..4], &RG[..4]);
+
+        let img = apply_orientation(&RG, 2, 1, 8).unwrap();
+        assert_eq!(&img.data[..4], &RG[4..]);
+    }
+
+    #[test]
+    fn test_inverse_pairs() {
+        let data: Vec<u8> = (0..6 * 4).map(|i| i as u8).collect();
+        let cw = rotate90(&data, 3, 2, 1).unwrap();
+        let back = rotate90(&cw.data, cw.width, cw.height, 3).unwrap();
+        assert_eq!(back.data, data);
+
+        let once = flip_horizontal(&data, 3, 2).unwrap();
+        assert_eq!(flip_horizontal(&once, 3, 2).unwrap(), data);
+        let t = apply_orientation(&data, 3, 2, 5).unwrap();
+        assert_eq!(apply_orientation(&t.data, t.width, t.height, 5).unwrap().data, data);
+    }
+}
@@ -0,0 +1,147 @@
+//! Content-aware cropping driven by an edge, skin-tone, and saturation saliency map
+
+use super::crop::{crop, CropRegion};
+use super::RgbaImage;
+use crate::filters::{gradient_magnitude, EdgeOperator};
+use crate::resize::{resize, ResizeAlgorithm};
+use crate::utils::check_rgba;
+
+/// Longest side of the analysis map; larger images are box-downsampled first
+const ANALYSIS_SIZE: u32 = 256;
+
+const EDGE_WEIGHT: f32 = 1.0;
+const SKIN_WEIGHT: f32 = 1.8;
+const SATURATION_WEIGHT: f32 = 0.3;
+
+/// Kovac et al. RGB skin-tone rule
+fn is_skin(r: u8, g: u8, b: u8) -> bool {
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    r > 95 && g > 40 && b > 20 && r > g && r > b && r - g.min(b) > 15 && (r - g).abs() > 15
+}
+
+/// Per-pixel saliency in roughly [0, 1 + SKIN_WEIGHT + SATURATION_WEIGHT]
+pub fn saliency_map(data: &[u8], width: u32, height: u32) -> Result<Vec<f32>, String> {
+    check_rgba(data, width, height)?;
+    let edges = gradient_magnitude(data, width, height, EdgeOperator::Sobel)?;
+    // Sobel magnitude of a full-contrast step is about 1020
+    let edge_scale = 1.0 / 1020.0;
+
+    Ok(data
+        .chunks_exact(4)
+        .zip(&edges)
+        .map(|(p, &e)| {
+            let max = p[0].max(p[1]).max(p[2]) as f32;
+            let min = p[0].min(p[1]).min(p[2]) as f32;
+            let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+            let skin = is_skin(p[0], p[1], p[2]) as u8 as f32;
+            let alpha = p[3] as f32 / 255.0;
+            alpha * (EDGE_WEIGHT * (e * edge_scale).min(1.0) + SKIN_WEIGHT * skin + SATURATION_WEIGHT * saturation)
+        })
+        .collect())
+}
+
+/// Choose the most salient window with the target aspect ratio
+///
+/// The window is the largest that fits the image; only its position varies.
+pub fn smart_crop_region(data: &[u8], width: u32, height: u32, target_width: u32, target_height: u32) -> Result<CropRegion, String> {
+    check_rgba(data, width, height)?;
+    if width == 0 || height == 0 || target_width == 0 || target_height == 0 {
+        return Err("Dimensions must be non-zero".to_string());
+    }
+
+    // Largest window with the target aspect ratio
+    let aspect = target_width as f64 / target_height as f64;
+    let (crop_w, crop_h) = if width as f64 / height as f64 > aspect {
+        (((height as f64 * aspect).round() as u32).clamp(1, width), height)
+    } else {
+        (width, ((width as f64 / aspect).round() as u32).clamp(1, height))
+    };
+
+    // Analyze a downsampled copy for speed
+    let scale = (ANALYSIS_SIZE as f64 / width.max(height) as f64).min(1.0);
+    let (mw, mh) = (((width as f64 * scale).round() as u32).max(1), ((height as f64 * scale).round() as u32).max(1));
+    let small = if scale < 1.0 { resize(data, width, height, mw, mh, ResizeAlgorithm::Bilinear) } else { data.to_vec() };
+    let saliency = saliency_map(&small, mw, mh)?;
+
+    // Summed-area table for O(1) window sums
+    let (w, h) = (mw as usize, mh as usize);
+    let mut integral = vec![0f64; (w + 1) * (h + 1)];
+    for y in 0..h {
+        let mut row = 0.0;
+        for x in 0..w {
+            row += saliency[y * w + x] as f64;
+            integral[(y + 1) * (w + 1) + x + 1] = integral[y * (w + 1) + x + 1] + row;
+        }
+    }
+    let window_sum = |x: usize, y: usize, cw: usize, ch: usize| {
+        integral[(y + ch) * (w + 1) + x + cw] - integral[y * (w + 1) + x + cw] - integral[(y + ch) * (w + 1) + x] + integral[y * (w + 1) + x]
+    };
+
+    let cw = ((crop_w as f64 * scale).round() as usize).clamp(1, w);
+    let ch = ((crop_h as f64 * scale).round() as usize).clamp(1, h);
+    let (cx, cy) = ((w - cw) as f64 / 2.0, (h - ch) as f64 / 2.0);
+
+    let mut best = (f64::MIN, f64::MAX, 0usize, 0usize);
+    for y in 0..=h - ch {
+        for x in 0..=w - cw {
+            let score = window_sum(x, y, cw, ch);
+            // Ties go to the window nearest the center
+            let dist = (x as f64 - cx).abs() + (y as f64 - cy).abs();
+            if score > best.0 + 1e-9 || ((score - best.0).abs() <= 1e-9 && dist < best.1) {
+                best = (score, dist, x, y);
+            }
+        }
+    }
+
+    let x = ((best.2 as f64 / scale).round() as u32).min(width - crop_w);
+    let y = ((best.3 as f64 / scale).round() as u32).min(height - crop_h);
+    Ok(CropRegion {
+        x,
+        y,
+        width: crop_w,
+        height: crop_h,
+    })
+}
+
+/// Crop to the most salient region and resize to the target dimensions
+pub fn smart_crop(data: &[u8], width: u32, height: u32, target_width: u32, target_height: u32) -> Result<RgbaImage, String> {
+    let region = smart_crop_region(data, width, height, target_width, target_height)?;
+    let cropped = crop(data, width, height, region)?;
+    let data = if (region.width, region.height) == (target_width, target_height) {
+        cropped
+    } else {
+        resize(&cropped, region.width, region.height, target_width, target_height, ResizeAlgorithm::Lanczos)
+    };
+    Ok(RgbaImage {
+        width: target_width,
+        height: target_height,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crop_follows_subject() {
+        // Flat gray 300x100 with a skin-toned, textured subject near the right edge
+        let (w, h) = (300u32, 100u32);
+        let mut data = vec![0u8; (w * h * 4) as usize];
+        for y in 0..h {
+            for x in 0..w {
+                let i = ((y * w + x) * 4) as usize;
+                let subject = (230..270).contains(&x) && (30..70).contains(&y);
+                let px = if subject && (x + y) % 4 < 2 { [220, 160, 130, 255] } else if subject { [180, 120, 90, 255] } else { [128, 128, 128, 255] };
+                data[i..i + 4].copy_from_slice(&px);
+            }
+        }
+
+        let region = smart_crop_region(&data, w, h, 100, 100).unwrap();
+        assert_eq!((region.width, region.height), (100, 100));
+        assert!(region.x <= 230 && region.x + 100 >= 270, "{:?}", region);
+
+        let thumb = smart_crop(&data, w, h, 50, 50).unwrap();
+        assert_eq!(thumb.data.len(), 50 * 50 * 4);
+    }
+}
@@ -0,0 +1,96 @@
+//! Byte-order and buffer helpers shared by the codecs
+
+use alloc::{format, string::String};
+
+/// Read u16 little-endian from slice
+#[inline]
+pub fn read_u16_le(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Read u32 little-endian from slice
+#[inline]
+pub fn read_u32_le(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Read u16 big-endian from slice
+#[inline]
+pub fn read_u16_be(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+/// Read u32 big-endian from slice
+#[inline]
+pub fn read_u32_be(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Read i32 little-endian from slice
+#[inline]
+pub fn read_i32_le(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Write u16 little-endian to slice
+#[inline]
+pub fn write_u16_le(data: &mut [u8], offset: usize, value: u16) {
+    let bytes = value.to_le_bytes();
+    data[offset] = bytes[0];
+    data[offset + 1] = bytes[1];
+}
+
+/// Copy bytes into a new `ArrayBuffer` outside WASM memory
+///
+/// Unlike a view of WASM memory, the buffer can be listed in `postMessage`'s
+/// transfer list and so moves between threads without another copy.
+#[cfg(feature = "wasm")]
+pub fn to_array_buffer(data: &[u8]) -> js_sys::ArrayBuffer {
+    let array = js_sys::Uint8Array::new_with_length(data.len() as u32);
+    array.copy_from(data);
+    array.buffer()
+}
+
+/// Write u32 little-endian to slice
+#[inline]
+pub fn write_u32_le(data: &mut [u8], offset: usize, value: u32) {
+    let bytes = value.to_le_bytes();
+    data[offset] = bytes[0];
+    data[offset + 1] = bytes[1];
+    data[offset + 2] = bytes[2];
+    data[offset + 3] = bytes[3];
+}
+
+/// Validate that an RGBA buffer matches the given dimensions
+pub fn check_rgba(data: &[u8], width: u32, height: u32) -> Result<(), String> {
+    let expected_len = width as usize * height as usize * 4;
+    if data.len() != expected_len {
+        return Err(format!(
+            "Data length mismatch: expected {}, got {}",
+            expected_len,
+            data.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Rec.709 luma of an sRGB pixel, in the 0-255 range
+#[inline]
+pub fn luma(r: u8, g: u8, b: u8) -> f32 {
+    0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32
+}
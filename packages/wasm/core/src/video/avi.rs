@@ -0,0 +1,463 @@
+//! AVI demuxing and MJPEG frame decoding
+//!
+//! Chunks are located through the `idx1` index when there is one, and by
+//! walking the `movi` list otherwise; OpenDML `AVIX` extensions beyond
+//! 1 GB are always walked. Chunks found by walking carry no key-frame flag
+//! and are all treated as key frames, which holds for MJPEG.
+
+use super::mp4::TrackKind;
+use crate::convert::unpack;
+use crate::jpeg::decode_jpeg;
+use crate::transform::RgbaImage;
+use crate::utils::{read_i32_le, read_u16_le, read_u32_le};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Index flag of chunks that are key frames
+const AVIIF_KEYFRAME: u32 = 0x10;
+
+/// Facts about one stream, from its `strh` and `strf` headers
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AviStream {
+    pub kind: TrackKind,
+    /// Video compression FourCC (`MJPG`, `H264`, `DIB` when uncompressed), or the audio
+    /// format: `pcm`, `float`, `mp3`, `aac`, `ac3`, `alaw`, `mulaw`,
+    /// `ms_adpcm`, `ima_adpcm` or the hex format tag
+    pub codec: String,
+    /// Handler FourCC from the stream header
+    pub handler: String,
+    /// Chunks per second for video, blocks per second for audio
+    pub rate: f64,
+    pub width: u32,
+    pub height: u32,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub chunk_count: u32,
+}
+
+/// Location of one chunk's payload: a video frame or a block of audio
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AviChunk {
+    /// Byte offset in the file
+    pub offset: u64,
+    pub size: u32,
+    pub keyframe: bool,
+}
+
+impl AviChunk {
+    /// This chunk's bytes within the file
+    pub fn data<'a>(&self, file: &'a [u8]) -> Result<&'a [u8], String> {
+        let start = usize::try_from(self.offset).map_err(|_| "AVI chunk is out of range")?;
+        file.get(start..start + self.size as usize).ok_or_else(|| "AVI chunk lies outside the file".to_string())
+    }
+}
+
+/// A parsed AVI file: streams and each stream's chunks
+#[derive(Clone, Debug, PartialEq)]
+pub struct Avi {
+    pub width: u32,
+    pub height: u32,
+    /// Duration in seconds of the longest video stream, or from the main header
+    pub duration: f64,
+    pub streams: Vec<AviStream>,
+    /// Chunks of each stream, in file order
+    pub chunks: Vec<Vec<AviChunk>>,
+}
+
+/// A RIFF chunk with where its payload starts
+struct Chunk<'a> {
+    fourcc: [u8; 4],
+    offset: usize,
+    data: &'a [u8],
+}
+
+/// Chunks laid out back to back in `data[start..end]`; a final chunk cut
+/// off by the end of the file keeps what is there
+fn chunks(data: &[u8], start: usize, end: usize) -> Vec<Chunk<'_>> {
+    let mut out = Vec::new();
+    let mut pos = start;
+    while pos + 8 <= end {
+        let fourcc = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
+        let len = read_u32_le(data, pos + 4) as usize;
+        let offset = pos + 8;
+        let stop = offset.saturating_add(len).min(end);
+        out.push(Chunk { fourcc, offset, data: &data[offset..stop] });
+        // Chunks are padded to even length
+        pos = offset.saturating_add(len + (len & 1));
+    }
+    out
+}
+
+/// The chunks of a `LIST` payload of the given type
+fn list_chunks<'a>(data: &'a [u8], list: &Chunk, kind: &[u8; 4]) -> Option<Vec<Chunk<'a>>> {
+    (&list.fourcc == b"LIST" && list.data.get(..4) == Some(kind)).then(|| chunks(data, list.offset + 4, list.offset + list.data.len()))
+}
+
+fn fourcc_string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches(['\0', ' ']).to_string()
+}
+
+/// Stream number of a `##dc`-style chunk ID
+fn stream_number(id: &[u8; 4]) -> Option<usize> {
+    let digit = |b: u8| (b as char).to_digit(10).map(|d| d as usize);
+    Some(digit(id[0])? * 10 + digit(id[1])?)
+}
+
+/// Whether a movi chunk ID holds stream data rather than a palette change
+fn is_stream_data(id: &[u8; 4]) -> bool {
+    stream_number(id).is_some() && &id[2..] != b"pc"
+}
+
+fn parse_stream(strl: &[Chunk]) -> Result<(AviStream, f64), String> {
+    let strh = strl.iter().find(|c| &c.fourcc == b"strh").filter(|c| c.data.len() >= 36).ok_or("AVI stream header is missing")?;
+    let h = strh.data;
+    let kind = match &h[..4] {
+        b"vids" => TrackKind::Video,
+        b"auds" => TrackKind::Audio,
+        b"txts" => TrackKind::Text,
+        _ => TrackKind::Other,
+    };
+    let (scale, rate) = (read_u32_le(h, 20), read_u32_le(h, 24));
+    let mut stream = AviStream {
+        kind,
+        codec: String::new(),
+        handler: fourcc_string(&h[4..8]),
+        rate: if scale > 0 { rate as f64 / scale as f64 } else { 0.0 },
+        width: 0,
+        height: 0,
+        sample_rate: 0,
+        channels: 0,
+        bits_per_sample: 0,
+        chunk_count: 0,
+    };
+    let length = read_u32_le(h, 32) as f64;
+    if let Some(strf) = strl.iter().find(|c| &c.fourcc == b"strf") {
+        let f = strf.data;
+        match kind {
+            TrackKind::Video if f.len() >= 20 => {
+                stream.width = read_u32_le(f, 4);
+                stream.height = read_i32_le(f, 8).unsigned_abs();
+                stream.bits_per_sample = read_u16_le(f, 14);
+                stream.codec = match read_u32_le(f, 16) {
+                    0 => "DIB".to_string(),
+                    _ => fourcc_string(&f[16..20]),
+                };
+            }
+            TrackKind::Audio if f.len() >= 16 => {
+                let tag = read_u16_le(f, 0);
+                stream.channels = read_u16_le(f, 2);
+                stream.sample_rate = read_u32_le(f, 4);
+                stream.bits_per_sample = read_u16_le(f, 14);
+                stream.codec = match tag {
+                    0x0001 => "pcm".to_string(),
+                    0x0002 => "ms_adpcm".to_string(),
+                    0x0003 => "float".to_string(),
+                    0x0006 => "alaw".to_string(),
+                    0x0007 => "mulaw".to_string(),
+                    0x0011 => "ima_adpcm".to_string(),
+                    0x0055 => "mp3".to_string(),
+                    0x00FF | 0x1610 => "aac".to_string(),
+                    0x2000 => "ac3".to_string(),
+                    _ => format!("0x{:04x}", tag),
+                };
+            }
+            _ => {}
+        }
+    }
+    if stream.codec.is_empty() {
+        stream.codec = stream.handler.clone();
+    }
+    Ok((stream, length))
+}
+
+/// Chunks of a `movi` list in file order, descending into `rec ` lists
+fn walk_movi(data: &[u8], movi: &[Chunk], chunks_out: &mut [Vec<AviChunk>]) {
+    for chunk in movi {
+        if let Some(rec) = list_chunks(data, chunk, b"rec ") {
+            walk_movi(data, &rec, chunks_out);
+            continue;
+        }
+        if !is_stream_data(&chunk.fourcc) {
+            continue;
+        }
+        if let Some(stream) = stream_number(&chunk.fourcc).and_then(|n| chunks_out.get_mut(n)) {
+            stream.push(AviChunk { offset: chunk.offset as u64, size: chunk.data.len() as u32, keyframe: true });
+        }
+    }
+}
+
+/// Chunks listed in an `idx1` index, or `None` if the index does not match
+/// the file
+fn read_idx1(data: &[u8], idx1: &[u8], movi_start: usize, stream_count: usize) -> Option<Vec<Vec<AviChunk>>> {
+    let entries: Vec<&[u8]> = idx1.chunks_exact(16).filter(|e| is_stream_data(&[e[0], e[1], e[2], e[3]])).collect();
+    let first = entries.first()?;
+    // Offsets are normally from the `movi` list type, but some writers use file offsets
+    let points_at = |base: usize| data.get(base + read_u32_le(first, 8) as usize..).is_some_and(|d| d.starts_with(&first[..4]));
+    let base = if points_at(movi_start) {
+        movi_start
+    } else if points_at(0) {
+        0
+    } else {
+        return None;
+    };
+    let mut out = vec![Vec::new(); stream_count];
+    for entry in entries {
+        let offset = base + read_u32_le(entry, 8) as usize + 8;
+        let size = read_u32_le(entry, 12);
+        if offset + size as usize > data.len() {
+            break;
+        }
+        if let Some(stream) = stream_number(&[entry[0], entry[1], entry[2], entry[3]]).and_then(|n| out.get_mut(n)) {
+            stream.push(AviChunk { offset: offset as u64, size, keyframe: read_u32_le(entry, 4) & AVIIF_KEYFRAME != 0 });
+        }
+    }
+    Some(out)
+}
+
+/// Parse the streams and chunk index of an AVI file
+pub fn read_avi(data: &[u8]) -> Result<Avi, String> {
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != b"AVI " {
+        return Err("Not an AVI file".to_string());
+    }
+    let riffs = chunks(data, 0, data.len());
+    let body = chunks(data, 12, riffs[0].offset + riffs[0].data.len());
+
+    let hdrl = body.iter().find_map(|c| list_chunks(data, c, b"hdrl")).ok_or("AVI header list is missing")?;
+    let avih = hdrl.iter().find(|c| &c.fourcc == b"avih").filter(|c| c.data.len() >= 40).ok_or("AVI main header is missing")?;
+    let micros_per_frame = read_u32_le(avih.data, 0) as f64;
+    let total_frames = read_u32_le(avih.data, 16) as f64;
+    let (width, height) = (read_u32_le(avih.data, 32), read_u32_le(avih.data, 36));
+
+    let mut streams = Vec::new();
+    let mut lengths = Vec::new();
+    for strl in hdrl.iter().filter_map(|c| list_chunks(data, c, b"strl")) {
+        let (stream, length) = parse_stream(&strl)?;
+        streams.push(stream);
+        lengths.push(length);
+    }
+    if streams.is_empty() {
+        return Err("AVI file has no streams".to_string());
+    }
+
+    let movi = body.iter().find(|c| list_chunks(data, c, b"movi").is_some());
+    let idx1 = body.iter().find(|c| &c.fourcc == b"idx1");
+    let mut index = match (movi, idx1) {
+        (Some(movi), Some(idx1)) => read_idx1(data, idx1.data, movi.offset, streams.len()),
+        _ => None,
+    };
+    if index.is_none() {
+        let mut walked = vec![Vec::new(); streams.len()];
+        if let Some(list) = movi.and_then(|m| list_chunks(data, m, b"movi")) {
+            walk_movi(data, &list, &mut walked);
+        }
+        index = Some(walked);
+    }
+    let mut chunks_out = index.unwrap_or_default();
+    // OpenDML extension RIFFs each hold another movi list
+    for riff in riffs.iter().skip(1).filter(|r| &r.fourcc == b"RIFF" && r.data.starts_with(b"AVIX")) {
+        for list in chunks(data, riff.offset + 4, riff.offset + riff.data.len()).iter().filter_map(|c| list_chunks(data, c, b"movi")) {
+            walk_movi(data, &list, &mut chunks_out);
+        }
+    }
+
+    let mut duration: f64 = 0.0;
+    for ((stream, stream_chunks), length) in streams.iter_mut().zip(&chunks_out).zip(&lengths) {
+        stream.chunk_count = stream_chunks.len() as u32;
+        if stream.rate > 0.0 && stream.kind == TrackKind::Video {
+            duration = duration.max(stream.chunk_count as f64 / stream.rate);
+        } else if stream.rate > 0.0 && stream.kind == TrackKind::Audio {
+            duration = duration.max(length / stream.rate);
+        }
+    }
+    if duration == 0.0 {
+        duration = micros_per_frame * total_frames / 1e6;
+    }
+    Ok(Avi { width, height, duration, streams, chunks: chunks_out })
+}
+
+/// An AVI file whose chunks can be read and MJPEG frames decoded
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct AviDemuxer {
+    data: Vec<u8>,
+    avi: Avi,
+}
+
+impl AviDemuxer {
+    pub fn parse(data: Vec<u8>) -> Result<AviDemuxer, String> {
+        let avi = read_avi(&data)?;
+        Ok(AviDemuxer { data, avi })
+    }
+
+    pub fn avi(&self) -> &Avi {
+        &self.avi
+    }
+
+    /// Bytes of chunk `index` of stream `stream`
+    pub fn read(&self, stream: usize, index: usize) -> Result<&[u8], String> {
+        let chunk = self.avi.chunks.get(stream).and_then(|c| c.get(index)).ok_or_else(|| format!("No AVI chunk {} in stream {}", index, stream))?;
+        chunk.data(&self.data)
+    }
+
+    /// Decode frame `index` of the first video stream, which must be MJPEG
+    ///
+    /// Empty chunks, which AVI uses for dropped frames, repeat the frame
+    /// before them.
+    pub fn decode(&self, index: usize) -> Result<RgbaImage, String> {
+        let stream = self.avi.streams.iter().position(|s| s.kind == TrackKind::Video).ok_or("AVI file has no video stream")?;
+        let frames = &self.avi.chunks[stream];
+        if index >= frames.len() {
+            return Err(format!("No AVI frame {}", index));
+        }
+        let shown = (0..=index).rev().find(|&i| frames[i].size > 0).ok_or("AVI frame is empty")?;
+        let frame = frames[shown].data(&self.data)?;
+        if !frame.starts_with(&[0xFF, 0xD8]) {
+            return Err(format!("Cannot decode {} video frames", self.avi.streams[stream].codec));
+        }
+        Ok(unpack(decode_jpeg(frame)?))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AviDemuxer {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<AviDemuxer, JsError> {
+        AviDemuxer::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn width(&self) -> u32 {
+        self.avi.width
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn height(&self) -> u32 {
+        self.avi.height
+    }
+
+    /// Duration in seconds
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn duration(&self) -> f64 {
+        self.avi.duration
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = streamCount))]
+    pub fn stream_count(&self) -> usize {
+        self.avi.streams.len()
+    }
+
+    pub fn stream(&self, index: usize) -> Option<AviStream> {
+        self.avi.streams.get(index).cloned()
+    }
+
+    /// Index of the first stream of this kind
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = findStream))]
+    pub fn find_stream(&self, kind: TrackKind) -> Option<usize> {
+        self.avi.streams.iter().position(|s| s.kind == kind)
+    }
+
+    /// Location of chunk `index` of stream `stream`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = chunkInfo))]
+    pub fn chunk_info(&self, stream: usize, index: usize) -> Option<AviChunk> {
+        self.avi.chunks.get(stream)?.get(index).copied()
+    }
+
+    /// Bytes of chunk `index` of stream `stream`
+    #[cfg(feature = "wasm")]
+    pub fn chunk(&self, stream: usize, index: usize) -> Result<Vec<u8>, JsError> {
+        self.read(stream, index).map(|d| d.to_vec()).map_err(|e| JsError::new(&e))
+    }
+
+    /// Decode an MJPEG video frame to RGBA
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = decodeFrame)]
+    pub fn decode_frame(&self, index: usize) -> Result<RgbaImage, JsError> {
+        self.decode(index).map_err(|e| JsError::new(&e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jpeg::flat_jpeg;
+
+    fn chunk(fourcc: &[u8; 4], parts: &[&[u8]]) -> Vec<u8> {
+        let payload = parts.concat();
+        let mut out = fourcc.to_vec();
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&payload);
+        if payload.len() % 2 == 1 {
+            out.push(0);
+        }
+        out
+    }
+
+    fn list(kind: &[u8; 4], parts: &[&[u8]]) -> Vec<u8> {
+        chunk(b"LIST", &[kind, &parts.concat()])
+    }
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// An MJPEG AVI with a PCM audio stream; `frames` are video chunk payloads
+    fn mjpeg_avi(frames: &[&[u8]], index: bool) -> Vec<u8> {
+        let avih = chunk(b"avih", &[&words(&[40_000, 0, 0, 0x10, frames.len() as u32, 0, 2, 0, 16, 8]), &[0; 16]]);
+        let video = list(b"strl", &[
+            &chunk(b"strh", &[b"vidsMJPG", &words(&[0, 0, 0, 1, 25, 0, frames.len() as u32, 0, 0, 0]), &[0; 8]]),
+            &chunk(b"strf", &[&words(&[40, 16, 8]), &[1, 0, 24, 0], b"MJPG", &words(&[0; 5])]),
+        ]);
+        let audio = list(b"strl", &[
+            &chunk(b"strh", &[b"auds\0\0\0\0", &words(&[0, 0, 0, 1, 8000, 0, 4, 0, 0, 2]), &[0; 8]]),
+            &chunk(b"strf", &[&[1, 0, 1, 0], &words(&[8000, 16000]), &[2, 0, 16, 0]]),
+        ]);
+        let hdrl = list(b"hdrl", &[&avih, &video, &audio]);
+        let mut movi_chunks: Vec<Vec<u8>> = frames.iter().map(|f| chunk(b"00dc", &[f])).collect();
+        movi_chunks.insert(1, chunk(b"01wb", &[&[1, 2, 3, 4, 5, 6, 7, 8]]));
+        let movi = list(b"movi", &[&movi_chunks.concat()]);
+        let mut idx1 = Vec::new();
+        // Offsets from the movi list type
+        let mut offset = 4;
+        for c in &movi_chunks {
+            let flags = if &c[..4] == b"00dc" && offset == 4 { AVIIF_KEYFRAME } else { 0 };
+            idx1.extend_from_slice(&c[..4]);
+            idx1.extend_from_slice(&words(&[flags, offset, read_u32_le(c, 4)]));
+            offset += c.len() as u32;
+        }
+        let tail = if index { chunk(b"idx1", &[&idx1]) } else { Vec::new() };
+        chunk(b"RIFF", &[b"AVI ", &hdrl, &movi, &tail])
+    }
+
+    #[test]
+    fn test_mjpeg_avi() {
+        let red = flat_jpeg(16, 8, [82, 90, 240]);
+        let blue = flat_jpeg(16, 8, [41, 240, 110]);
+        let file = mjpeg_avi(&[&red, &blue, &[]], true);
+        let demuxer = AviDemuxer::parse(file.clone()).unwrap();
+        let avi = demuxer.avi();
+        assert_eq!((avi.width, avi.height, avi.duration), (16, 8, 0.12));
+        let video = &avi.streams[0];
+        assert_eq!((video.kind, video.codec.as_str(), video.rate, video.width, video.chunk_count), (TrackKind::Video, "MJPG", 25.0, 16, 3));
+        let audio = &avi.streams[1];
+        assert_eq!((audio.kind, audio.codec.as_str(), audio.sample_rate, audio.channels, audio.chunk_count), (TrackKind::Audio, "pcm", 8000, 1, 1));
+        assert_eq!(demuxer.read(1, 0).unwrap(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(avi.chunks[0].iter().map(|c| c.keyframe).collect::<Vec<_>>(), [true, false, false]);
+
+        let first = demuxer.decode(0).unwrap();
+        assert_eq!((first.width, first.height), (16, 8));
+        assert!(first.data[0] > 200 && first.data[2] < 60);
+        let second = demuxer.decode(1).unwrap();
+        assert!(second.data[2] > 200 && second.data[0] < 60);
+        // The dropped third frame repeats the second
+        assert_eq!(demuxer.decode(2).unwrap(), second);
+        assert!(demuxer.decode(3).is_err());
+
+        // Without idx1 the movi list is walked
+        let walked = read_avi(&mjpeg_avi(&[&red, &blue, &[]], false)).unwrap();
+        assert_eq!(walked.chunks, avi.chunks.iter().map(|c| c.iter().map(|c| AviChunk { keyframe: true, ..*c }).collect()).collect::<Vec<Vec<_>>>());
+        assert!(read_avi(b"RIFF\x04\0\0\0WAVE").is_err());
+    }
+}
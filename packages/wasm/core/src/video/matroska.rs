@@ -0,0 +1,719 @@
+//! Matroska/WebM demuxing - tracks, frames and attachments
+//!
+//! Walks the EBML element tree of the first segment, including live-style
+//! segments and clusters of unknown size. Block lacing is split into
+//! individual frames, and header stripping and zlib content compression
+//! are undone when frames are read. Encrypted tracks are not supported.
+
+use super::mp4::TrackKind;
+use crate::compress::zlib_decompress;
+use crate::metadata::audio::{Picture, PICTURE_FRONT_COVER};
+use std::borrow::Cow;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The first four bytes of every Matroska and WebM file
+pub const EBML_MAGIC: [u8; 4] = [0x1A, 0x45, 0xDF, 0xA3];
+
+const EBML: u32 = 0x1A45_DFA3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x1853_8067;
+const SEEK_HEAD: u32 = 0x114D_9B74;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const TITLE: u32 = 0x7BA9;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const CODEC_PRIVATE: u32 = 0x63A2;
+const NAME: u32 = 0x536E;
+const LANGUAGE: u32 = 0x22_B59C;
+const LANGUAGE_BCP47: u32 = 0x22_B59D;
+const DEFAULT_DURATION: u32 = 0x23_E383;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const CONTENT_ENCODINGS: u32 = 0x6D80;
+const CONTENT_ENCODING: u32 = 0x6240;
+const CONTENT_COMPRESSION: u32 = 0x5034;
+const CONTENT_COMP_ALGO: u32 = 0x4254;
+const CONTENT_COMP_SETTINGS: u32 = 0x4255;
+const CONTENT_ENCRYPTION: u32 = 0x5035;
+const CLUSTER: u32 = 0x1F43_B675;
+const CLUSTER_TIMESTAMP: u32 = 0xE7;
+const SIMPLE_BLOCK: u32 = 0xA3;
+const BLOCK_GROUP: u32 = 0xA0;
+const BLOCK: u32 = 0xA1;
+const BLOCK_DURATION: u32 = 0x9B;
+const REFERENCE_BLOCK: u32 = 0xFB;
+const CUES: u32 = 0x1C53_BB6B;
+const ATTACHMENTS: u32 = 0x1941_A469;
+const ATTACHED_FILE: u32 = 0x61A7;
+const FILE_DESCRIPTION: u32 = 0x467E;
+const FILE_NAME: u32 = 0x466E;
+const FILE_MIME_TYPE: u32 = 0x4660;
+const FILE_DATA: u32 = 0x465C;
+const CHAPTERS: u32 = 0x1043_A770;
+const TAGS: u32 = 0x1254_C367;
+
+/// Elements that may directly follow a cluster of unknown size
+const TOP_LEVEL: [u32; 10] = [EBML, SEGMENT, SEEK_HEAD, INFO, TRACKS, CLUSTER, CUES, ATTACHMENTS, CHAPTERS, TAGS];
+
+/// How a track's frames are stored
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Compression {
+    None,
+    Zlib,
+    /// Bytes removed from the start of every frame
+    HeaderStripping(Vec<u8>),
+}
+
+/// Facts about one track, read without touching frame data
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MkvTrack {
+    /// Track number that blocks refer to
+    pub number: u32,
+    pub kind: TrackKind,
+    /// Codec ID, e.g. `V_VP9`, `V_MPEG4/ISO/AVC`, `A_OPUS`, `A_AAC`
+    pub codec: String,
+    /// Decoder configuration, e.g. the `OpusHead` packet or an `avcC`
+    /// record; empty when there is none
+    pub codec_private: Vec<u8>,
+    pub name: String,
+    /// Language tag, BCP 47 when the file gives one, else ISO 639-2
+    pub language: String,
+    /// Nominal frame duration in seconds, 0 when unspecified
+    pub frame_duration: f64,
+    pub width: u32,
+    pub height: u32,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub frame_count: u32,
+    compression: Compression,
+    encrypted: bool,
+}
+
+/// Location and timing of one frame
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MkvFrame {
+    /// Byte offset in the file
+    pub offset: u64,
+    /// Stored size, before any content compression is undone
+    pub size: u32,
+    /// Presentation time in seconds
+    pub time: f64,
+    /// Duration in seconds, 0 when unknown
+    pub duration: f64,
+    /// A random access point
+    pub keyframe: bool,
+}
+
+/// A file attached to the segment: cover art, fonts and the like
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MkvAttachment {
+    pub name: String,
+    /// MIME type, e.g. `image/jpeg`
+    pub mime: String,
+    pub description: String,
+    pub data: Vec<u8>,
+}
+
+/// A parsed segment: tracks, each track's frame index and attachments
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matroska {
+    /// `matroska` or `webm`
+    pub doc_type: String,
+    pub title: String,
+    /// Duration in seconds, from the segment info or else the last frame
+    pub duration: f64,
+    pub tracks: Vec<MkvTrack>,
+    /// Frames of each track, in file order
+    pub frames: Vec<Vec<MkvFrame>>,
+    pub attachments: Vec<MkvAttachment>,
+}
+
+/// An element's ID and payload; `offset` is where the payload starts
+#[derive(Clone, Copy)]
+struct Element<'a> {
+    id: u32,
+    offset: usize,
+    data: &'a [u8],
+}
+
+/// A variable-length integer with its length marker removed, and its length
+fn vint(data: &[u8], pos: usize) -> Option<(u64, usize)> {
+    let first = *data.get(pos)?;
+    if first == 0 {
+        return None;
+    }
+    let len = first.leading_zeros() as usize + 1;
+    let bytes = data.get(pos..pos + len)?;
+    let value = bytes[1..].iter().fold((first & (0xFF >> len)) as u64, |v, &b| v << 8 | b as u64);
+    Some((value, len))
+}
+
+/// An element header at `pos`: (ID, payload start, payload size), where a
+/// size of `None` is unknown
+fn header(data: &[u8], pos: usize) -> Option<(u32, usize, Option<usize>)> {
+    let (_, id_len) = vint(data, pos).filter(|&(_, len)| len <= 4)?;
+    let id = data[pos..pos + id_len].iter().fold(0, |id, &b| id << 8 | b as u32);
+    let (size, size_len) = vint(data, pos + id_len)?;
+    let unknown = size == (1 << (7 * size_len)) - 1;
+    Some((id, pos + id_len + size_len, if unknown { None } else { usize::try_from(size).ok() }))
+}
+
+/// Elements laid out back to back in `data[start..end]`
+///
+/// An element of unknown size runs to the next top-level element, or to the
+/// end for a segment. Walking stops at the first element that does not fit,
+/// except that a truncated segment or cluster keeps what is there.
+fn children(data: &[u8], start: usize, end: usize) -> Vec<Element<'_>> {
+    let mut elements = Vec::new();
+    let mut pos = start;
+    while pos < end {
+        let Some((id, offset, size)) = header(&data[..end], pos) else { break };
+        let stop = match size {
+            Some(size) if offset.saturating_add(size) <= end => offset + size,
+            Some(_) if id == SEGMENT || id == CLUSTER => end,
+            Some(_) => break,
+            None if id == SEGMENT => end,
+            None => unknown_end(data, offset, end),
+        };
+        elements.push(Element { id, offset, data: &data[offset..stop] });
+        pos = stop;
+    }
+    elements
+}
+
+/// Where an element of unknown size starting at `start` ends
+fn unknown_end(data: &[u8], start: usize, end: usize) -> usize {
+    let mut pos = start;
+    while pos < end {
+        match header(&data[..end], pos) {
+            Some((id, _, _)) if TOP_LEVEL.contains(&id) => return pos,
+            Some((_, offset, Some(size))) if offset.saturating_add(size) <= end => pos = offset + size,
+            _ => return end,
+        }
+    }
+    end
+}
+
+fn elements_of<'a>(data: &'a [u8], parent: &Element) -> Vec<Element<'a>> {
+    children(data, parent.offset, parent.offset + parent.data.len())
+}
+
+fn uint(data: &[u8]) -> u64 {
+    data.iter().take(8).fold(0, |v, &b| v << 8 | b as u64)
+}
+
+fn float(data: &[u8]) -> f64 {
+    match data.len() {
+        4 => f32::from_be_bytes([data[0], data[1], data[2], data[3]]) as f64,
+        8 => f64::from_be_bytes(data.try_into().unwrap_or_default()),
+        _ => 0.0,
+    }
+}
+
+fn string(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+fn parse_track(data: &[u8], entry: &Element) -> MkvTrack {
+    let mut track = MkvTrack {
+        number: 0,
+        kind: TrackKind::Other,
+        codec: String::new(),
+        codec_private: Vec::new(),
+        name: String::new(),
+        language: "eng".to_string(),
+        frame_duration: 0.0,
+        width: 0,
+        height: 0,
+        sample_rate: 0,
+        channels: 0,
+        frame_count: 0,
+        compression: Compression::None,
+        encrypted: false,
+    };
+    let mut bcp47 = None;
+    for e in elements_of(data, entry) {
+        match e.id {
+            TRACK_NUMBER => track.number = uint(e.data) as u32,
+            TRACK_TYPE => {
+                track.kind = match uint(e.data) {
+                    1 => TrackKind::Video,
+                    2 => TrackKind::Audio,
+                    17 => TrackKind::Text,
+                    _ => TrackKind::Other,
+                }
+            }
+            CODEC_ID => track.codec = string(e.data),
+            CODEC_PRIVATE => track.codec_private = e.data.to_vec(),
+            NAME => track.name = string(e.data),
+            LANGUAGE => track.language = string(e.data),
+            LANGUAGE_BCP47 => bcp47 = Some(string(e.data)),
+            DEFAULT_DURATION => track.frame_duration = uint(e.data) as f64 / 1e9,
+            VIDEO => {
+                for v in elements_of(data, &e) {
+                    match v.id {
+                        PIXEL_WIDTH => track.width = uint(v.data) as u32,
+                        PIXEL_HEIGHT => track.height = uint(v.data) as u32,
+                        _ => {}
+                    }
+                }
+            }
+            AUDIO => {
+                track.sample_rate = 8000;
+                track.channels = 1;
+                for a in elements_of(data, &e) {
+                    match a.id {
+                        SAMPLING_FREQUENCY => track.sample_rate = float(a.data).round() as u32,
+                        CHANNELS => track.channels = uint(a.data) as u16,
+                        _ => {}
+                    }
+                }
+            }
+            CONTENT_ENCODINGS => {
+                for encoding in elements_of(data, &e).iter().filter(|c| c.id == CONTENT_ENCODING) {
+                    for c in elements_of(data, encoding) {
+                        match c.id {
+                            CONTENT_COMPRESSION => {
+                                let settings = elements_of(data, &c);
+                                let field = |id| settings.iter().find(|s| s.id == id).map(|s| s.data);
+                                track.compression = match field(CONTENT_COMP_ALGO).map(uint).unwrap_or(0) {
+                                    0 => Compression::Zlib,
+                                    3 => Compression::HeaderStripping(field(CONTENT_COMP_SETTINGS).unwrap_or_default().to_vec()),
+                                    _ => {
+                                        track.encrypted = true;
+                                        Compression::None
+                                    }
+                                };
+                            }
+                            CONTENT_ENCRYPTION => track.encrypted = true,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(language) = bcp47 {
+        track.language = language;
+    }
+    track
+}
+
+/// Sizes of the frames laced into a block payload that starts at `pos`,
+/// and where the first frame begins
+fn lace_sizes(block: &[u8], mut pos: usize, lacing: u8) -> Result<(Vec<usize>, usize), String> {
+    const TRUNCATED: &str = "Matroska block is truncated";
+    if lacing == 0 {
+        return Ok((vec![block.len().saturating_sub(pos)], pos));
+    }
+    let count = *block.get(pos).ok_or(TRUNCATED)? as usize + 1;
+    pos += 1;
+    let mut sizes = Vec::with_capacity(count);
+    match lacing {
+        // Xiph: each size is a run of 255s plus a final byte
+        1 => {
+            for _ in 1..count {
+                let mut size = 0;
+                loop {
+                    let b = *block.get(pos).ok_or(TRUNCATED)?;
+                    pos += 1;
+                    size += b as usize;
+                    if b != 255 {
+                        break;
+                    }
+                }
+                sizes.push(size);
+            }
+        }
+        // EBML: the first size, then signed differences from the previous
+        3 => {
+            let (first, len) = vint(block, pos).ok_or(TRUNCATED)?;
+            pos += len;
+            sizes.push(first as usize);
+            for _ in 2..count {
+                let (raw, len) = vint(block, pos).ok_or(TRUNCATED)?;
+                pos += len;
+                let diff = raw as i64 - ((1 << (7 * len - 1)) - 1);
+                let size = usize::try_from(*sizes.last().unwrap_or(&0) as i64 + diff).map_err(|_| "Matroska lace size is negative")?;
+                sizes.push(size);
+            }
+        }
+        // Fixed: equal shares of the payload
+        _ => {
+            let remaining = block.len().saturating_sub(pos);
+            if !remaining.is_multiple_of(count) {
+                return Err("Matroska fixed-size lace does not divide evenly".to_string());
+            }
+            sizes.resize(count - 1, remaining / count);
+        }
+    }
+    let used: usize = sizes.iter().sum();
+    let last = block.len().checked_sub(pos + used).ok_or("Matroska lace sizes exceed the block")?;
+    sizes.push(last);
+    Ok((sizes, pos))
+}
+
+/// Timing of the block being indexed, in timestamp ticks
+struct BlockTiming {
+    cluster: i64,
+    /// Nanoseconds per tick
+    scale: f64,
+    /// From the enclosing block group
+    duration: Option<u64>,
+}
+
+fn index_block(block: &Element, timing: &BlockTiming, keyframe: Option<bool>, tracks: &[MkvTrack], frames: &mut [Vec<MkvFrame>]) -> Result<(), String> {
+    let data = block.data;
+    let (number, len) = vint(data, 0).ok_or("Matroska block has no track number")?;
+    let Some(index) = tracks.iter().position(|t| t.number as u64 == number) else { return Ok(()) };
+    let header = data.get(len..len + 3).ok_or("Matroska block is truncated")?;
+    let relative = i16::from_be_bytes([header[0], header[1]]) as i64;
+    let flags = header[2];
+    let (sizes, mut pos) = lace_sizes(data, len + 3, (flags >> 1) & 3)?;
+
+    let track = &tracks[index];
+    let time = (timing.cluster + relative) as f64 * timing.scale / 1e9;
+    let duration = match timing.duration {
+        Some(ticks) => ticks as f64 * timing.scale / 1e9 / sizes.len() as f64,
+        None => track.frame_duration,
+    };
+    let keyframe = keyframe.unwrap_or(flags & 0x80 != 0);
+    for (i, &size) in sizes.iter().enumerate() {
+        frames[index].push(MkvFrame {
+            offset: (block.offset + pos) as u64,
+            size: size as u32,
+            time: time + i as f64 * duration,
+            duration,
+            // Only the first frame of a laced key block is a sure entry point
+            keyframe: keyframe && i == 0,
+        });
+        pos += size;
+    }
+    Ok(())
+}
+
+/// Parse the tracks, frame index and attachments of a Matroska or WebM file
+pub fn read_matroska(data: &[u8]) -> Result<Matroska, String> {
+    let top = children(data, 0, data.len());
+    let ebml = top.first().filter(|e| e.id == EBML).ok_or("Not a Matroska file")?;
+    let doc_type = elements_of(data, ebml).iter().find(|e| e.id == DOC_TYPE).map(|e| string(e.data)).unwrap_or_else(|| "matroska".to_string());
+    let segment = top.iter().find(|e| e.id == SEGMENT).ok_or("Matroska file has no segment")?;
+
+    let mut scale = 1_000_000.0;
+    let mut duration = 0.0;
+    let mut title = String::new();
+    let mut tracks = Vec::new();
+    let mut clusters = Vec::new();
+    let mut attachments = Vec::new();
+    for e in elements_of(data, segment) {
+        match e.id {
+            INFO => {
+                for i in elements_of(data, &e) {
+                    match i.id {
+                        TIMESTAMP_SCALE => scale = uint(i.data) as f64,
+                        DURATION => duration = float(i.data),
+                        TITLE => title = string(i.data),
+                        _ => {}
+                    }
+                }
+            }
+            TRACKS => tracks.extend(elements_of(data, &e).iter().filter(|t| t.id == TRACK_ENTRY).map(|t| parse_track(data, t))),
+            CLUSTER => clusters.push(e),
+            ATTACHMENTS => {
+                for file in elements_of(data, &e).iter().filter(|f| f.id == ATTACHED_FILE) {
+                    let mut attachment = MkvAttachment { name: String::new(), mime: String::new(), description: String::new(), data: Vec::new() };
+                    for f in elements_of(data, file) {
+                        match f.id {
+                            FILE_NAME => attachment.name = string(f.data),
+                            FILE_MIME_TYPE => attachment.mime = string(f.data),
+                            FILE_DESCRIPTION => attachment.description = string(f.data),
+                            FILE_DATA => attachment.data = f.data.to_vec(),
+                            _ => {}
+                        }
+                    }
+                    attachments.push(attachment);
+                }
+            }
+            _ => {}
+        }
+    }
+    if tracks.is_empty() && attachments.is_empty() {
+        return Err("Matroska file has no tracks".to_string());
+    }
+    // The segment duration is in ticks
+    duration *= scale / 1e9;
+
+    let mut frames = vec![Vec::new(); tracks.len()];
+    for cluster in &clusters {
+        let elements = elements_of(data, cluster);
+        let time = elements.iter().find(|e| e.id == CLUSTER_TIMESTAMP).map_or(0, |e| uint(e.data) as i64);
+        for e in &elements {
+            match e.id {
+                SIMPLE_BLOCK => index_block(e, &BlockTiming { cluster: time, scale, duration: None }, None, &tracks, &mut frames)?,
+                BLOCK_GROUP => {
+                    let group = elements_of(data, e);
+                    let Some(block) = group.iter().find(|g| g.id == BLOCK) else { continue };
+                    let block_duration = group.iter().find(|g| g.id == BLOCK_DURATION).map(|g| uint(g.data));
+                    let keyframe = !group.iter().any(|g| g.id == REFERENCE_BLOCK);
+                    index_block(block, &BlockTiming { cluster: time, scale, duration: block_duration }, Some(keyframe), &tracks, &mut frames)?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (track, track_frames) in tracks.iter_mut().zip(&frames) {
+        track.frame_count = track_frames.len() as u32;
+    }
+    if duration == 0.0 {
+        duration = frames.iter().flatten().map(|f| f.time + f.duration).fold(0.0, f64::max);
+    }
+    Ok(Matroska { doc_type, title, duration, tracks, frames, attachments })
+}
+
+impl Matroska {
+    /// Bytes of frame `index` of track `track`, decompressed if the track
+    /// uses content compression
+    pub fn frame_data<'a>(&self, file: &'a [u8], track: usize, index: usize) -> Result<Cow<'a, [u8]>, String> {
+        let frame = self.frames.get(track).and_then(|f| f.get(index)).ok_or_else(|| format!("No Matroska frame {} in track {}", index, track))?;
+        let info = &self.tracks[track];
+        if info.encrypted {
+            return Err("Matroska track is encrypted".to_string());
+        }
+        let start = usize::try_from(frame.offset).map_err(|_| "Matroska frame is out of range")?;
+        let stored = file.get(start..start + frame.size as usize).ok_or("Matroska frame lies outside the file")?;
+        Ok(match &info.compression {
+            Compression::None => Cow::Borrowed(stored),
+            Compression::Zlib => Cow::Owned(zlib_decompress(stored)?),
+            Compression::HeaderStripping(prefix) => Cow::Owned([prefix, stored].concat()),
+        })
+    }
+
+    /// The cover image among the attachments: one named `cover.*`, else the
+    /// first image
+    pub fn cover_art(&self) -> Option<Picture> {
+        let images: Vec<_> = self.attachments.iter().filter(|a| a.mime.starts_with("image/")).collect();
+        let cover = images.iter().find(|a| a.name.to_ascii_lowercase().starts_with("cover."));
+        let (attachment, picture_type) = match cover {
+            Some(a) => (*a, PICTURE_FRONT_COVER),
+            None => (*images.first()?, 0),
+        };
+        Some(Picture {
+            mime: attachment.mime.clone(),
+            picture_type,
+            description: attachment.description.clone(),
+            data: attachment.data.clone(),
+        })
+    }
+}
+
+/// A Matroska or WebM file whose tracks and frames can be read individually
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct MkvDemuxer {
+    data: Vec<u8>,
+    matroska: Matroska,
+}
+
+impl MkvDemuxer {
+    pub fn parse(data: Vec<u8>) -> Result<MkvDemuxer, String> {
+        let matroska = read_matroska(&data)?;
+        Ok(MkvDemuxer { data, matroska })
+    }
+
+    pub fn matroska(&self) -> &Matroska {
+        &self.matroska
+    }
+
+    /// Bytes of frame `index` of track `track`
+    pub fn read(&self, track: usize, index: usize) -> Result<Cow<'_, [u8]>, String> {
+        self.matroska.frame_data(&self.data, track, index)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MkvDemuxer {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<MkvDemuxer, JsError> {
+        MkvDemuxer::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// `matroska` or `webm`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = docType))]
+    pub fn doc_type(&self) -> String {
+        self.matroska.doc_type.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn title(&self) -> String {
+        self.matroska.title.clone()
+    }
+
+    /// Duration in seconds
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn duration(&self) -> f64 {
+        self.matroska.duration
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = trackCount))]
+    pub fn track_count(&self) -> usize {
+        self.matroska.tracks.len()
+    }
+
+    pub fn track(&self, index: usize) -> Option<MkvTrack> {
+        self.matroska.tracks.get(index).cloned()
+    }
+
+    /// Index of the first track of this kind
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = findTrack))]
+    pub fn find_track(&self, kind: TrackKind) -> Option<usize> {
+        self.matroska.tracks.iter().position(|t| t.kind == kind)
+    }
+
+    /// Location and timing of frame `index` of track `track`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = frameInfo))]
+    pub fn frame_info(&self, track: usize, index: usize) -> Option<MkvFrame> {
+        self.matroska.frames.get(track)?.get(index).copied()
+    }
+
+    /// Bytes of frame `index` of track `track`
+    #[cfg(feature = "wasm")]
+    pub fn frame(&self, track: usize, index: usize) -> Result<Vec<u8>, JsError> {
+        self.read(track, index).map(Cow::into_owned).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = attachmentCount))]
+    pub fn attachment_count(&self) -> usize {
+        self.matroska.attachments.len()
+    }
+
+    pub fn attachment(&self, index: usize) -> Option<MkvAttachment> {
+        self.matroska.attachments.get(index).cloned()
+    }
+
+    /// The attached cover image, if any
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = coverArt))]
+    pub fn cover_art(&self) -> Option<Picture> {
+        self.matroska.cover_art()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress::zlib_compress;
+
+    fn el(id: u32, parts: &[&[u8]]) -> Vec<u8> {
+        let payload = parts.concat();
+        let mut out: Vec<u8> = id.to_be_bytes().into_iter().skip_while(|&b| b == 0).collect();
+        out.extend_from_slice(&(0x1000_0000 | payload.len() as u32).to_be_bytes());
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// An element of unknown size
+    fn open(id: u32, parts: &[&[u8]]) -> Vec<u8> {
+        let mut out: Vec<u8> = id.to_be_bytes().into_iter().skip_while(|&b| b == 0).collect();
+        out.push(0xFF);
+        out.extend_from_slice(&parts.concat());
+        out
+    }
+
+    fn block(track: u8, time: i16, flags: u8, lacing: &[u8], frames: &[&[u8]]) -> Vec<u8> {
+        [&[0x80 | track][..], &time.to_be_bytes(), &[flags], lacing, &frames.concat()].concat()
+    }
+
+    fn file(tracks: &[&[u8]], body: &[&[u8]]) -> Vec<u8> {
+        let header = el(EBML, &[&el(DOC_TYPE, &[b"webm"])]);
+        let info = el(INFO, &[&el(TIMESTAMP_SCALE, &[&[0x0F, 0x42, 0x40]]), &el(TITLE, &[b"Clip"])]);
+        let segment = open(SEGMENT, &[&[&info[..], &el(TRACKS, tracks)][..], body].concat());
+        [header, segment].concat()
+    }
+
+    #[test]
+    fn test_tracks_and_frames() {
+        let video = el(TRACK_ENTRY, &[&el(TRACK_NUMBER, &[&[1]]), &el(TRACK_TYPE, &[&[1]]), &el(CODEC_ID, &[b"V_VP9"]), &el(VIDEO, &[&el(PIXEL_WIDTH, &[&[1, 64]]), &el(PIXEL_HEIGHT, &[&[240]])])]);
+        let audio = el(TRACK_ENTRY, &[
+            &el(TRACK_NUMBER, &[&[2]]),
+            &el(TRACK_TYPE, &[&[2]]),
+            &el(CODEC_ID, &[b"A_OPUS"]),
+            &el(CODEC_PRIVATE, &[b"OpusHead"]),
+            &el(LANGUAGE, &[b"fre"]),
+            &el(DEFAULT_DURATION, &[&20_000_000u32.to_be_bytes()]),
+            &el(AUDIO, &[&el(SAMPLING_FREQUENCY, &[&48000f64.to_be_bytes()]), &el(CHANNELS, &[&[2]])]),
+        ]);
+        // A live-style cluster of unknown size, then one of known size
+        let first = open(CLUSTER, &[
+            &el(CLUSTER_TIMESTAMP, &[&[0x03, 0xE8]]),
+            &el(SIMPLE_BLOCK, &[&block(1, 0, 0x80, &[], &[b"key"])]),
+            &el(SIMPLE_BLOCK, &[&block(2, 5, 0x82, &[2, 3, 1], &[b"aaa", b"b", b"cc"])]),
+        ]);
+        let group = el(BLOCK_GROUP, &[&el(BLOCK, &[&block(1, 40, 0, &[], &[b"delta"])]), &el(BLOCK_DURATION, &[&[40]]), &el(REFERENCE_BLOCK, &[&[0x80]])]);
+        // EBML lacing: sizes 4, then 4 - 2 = 2
+        let ebml_laced = el(SIMPLE_BLOCK, &[&block(2, -10, 0x86, &[2, 0x84, 0xBD], &[b"dddd", b"ee", b"f"])]);
+        let fixed = el(SIMPLE_BLOCK, &[&block(2, 0, 0x84, &[1], &[b"gg", b"hh"])]);
+        let second = el(CLUSTER, &[&el(CLUSTER_TIMESTAMP, &[&[0x07, 0xD0]]), &group, &ebml_laced, &fixed]);
+        let data = file(&[&video, &audio], &[&first, &second]);
+
+        let mkv = read_matroska(&data).unwrap();
+        assert_eq!((mkv.doc_type.as_str(), mkv.title.as_str()), ("webm", "Clip"));
+        let (v, a) = (&mkv.tracks[0], &mkv.tracks[1]);
+        assert_eq!((v.number, v.kind, v.codec.as_str(), v.width, v.height, v.frame_count), (1, TrackKind::Video, "V_VP9", 320, 240, 2));
+        assert_eq!((a.kind, a.sample_rate, a.channels, a.language.as_str(), a.frame_duration), (TrackKind::Audio, 48000, 2, "fre", 0.02));
+        assert_eq!(a.codec_private, b"OpusHead");
+
+        let video: Vec<_> = (0..2).map(|i| (mkv.frame_data(&data, 0, i).unwrap().into_owned(), mkv.frames[0][i].time, mkv.frames[0][i].keyframe)).collect();
+        assert_eq!(video, [(b"key".to_vec(), 1.0, true), (b"delta".to_vec(), 2.04, false)]);
+        assert_eq!(mkv.frames[0][1].duration, 0.04);
+        let audio: Vec<_> = (0..8).map(|i| mkv.frame_data(&data, 1, i).unwrap().into_owned()).collect();
+        assert_eq!(audio.concat(), b"aaabccddddeefgghh");
+        assert_eq!(audio[5], b"f");
+        let times: Vec<_> = mkv.frames[1][..3].iter().map(|f| (f.time * 1000.0).round()).collect();
+        assert_eq!(times, [1005.0, 1025.0, 1045.0]);
+        assert_eq!(mkv.frames[1][3].time, 1.99);
+        assert!((mkv.duration - 2.08).abs() < 1e-9);
+        assert!(mkv.frame_data(&data, 1, 8).is_err());
+        assert!(read_matroska(b"\x1A\x45\xDF\xA3\x80").is_err());
+        assert!(read_matroska(b"RIFF").is_err());
+    }
+
+    #[test]
+    fn test_compression_and_cover_art() {
+        let compressed = el(CONTENT_COMPRESSION, &[&el(CONTENT_COMP_ALGO, &[&[3]]), &el(CONTENT_COMP_SETTINGS, &[&[0x0B, 0x77]])]);
+        let stripped = el(TRACK_ENTRY, &[&el(TRACK_NUMBER, &[&[1]]), &el(TRACK_TYPE, &[&[2]]), &el(CODEC_ID, &[b"A_AC3"]), &el(CONTENT_ENCODINGS, &[&el(CONTENT_ENCODING, &[&compressed])])]);
+        let zlib = el(CONTENT_COMPRESSION, &[&el(CONTENT_COMP_ALGO, &[&[0]])]);
+        let deflated = el(TRACK_ENTRY, &[&el(TRACK_NUMBER, &[&[2]]), &el(TRACK_TYPE, &[&[17]]), &el(CODEC_ID, &[b"S_TEXT/UTF8"]), &el(CONTENT_ENCODINGS, &[&el(CONTENT_ENCODING, &[&zlib])])]);
+        let cluster = el(CLUSTER, &[
+            &el(CLUSTER_TIMESTAMP, &[&[0]]),
+            &el(SIMPLE_BLOCK, &[&block(1, 0, 0x80, &[], &[b"body"])]),
+            &el(SIMPLE_BLOCK, &[&block(2, 0, 0x80, &[], &[&zlib_compress(b"Hello", 6)])]),
+        ]);
+        let attached = |name: &[u8], mime: &[u8], data: &[u8]| el(ATTACHED_FILE, &[&el(FILE_NAME, &[name]), &el(FILE_MIME_TYPE, &[mime]), &el(FILE_DATA, &[data])]);
+        let attachments = el(ATTACHMENTS, &[&attached(b"font.ttf", b"font/ttf", b"glyphs"), &attached(b"small_cover.png", b"image/png", b"small"), &attached(b"Cover.jpg", b"image/jpeg", b"\xFF\xD8big")]);
+        let data = file(&[&stripped, &deflated], &[&cluster, &attachments]);
+
+        let demuxer = MkvDemuxer::parse(data.clone()).unwrap();
+        assert_eq!(demuxer.read(0, 0).unwrap().as_ref(), b"\x0B\x77body");
+        assert_eq!(demuxer.read(1, 0).unwrap().as_ref(), b"Hello");
+        assert_eq!(demuxer.find_track(TrackKind::Text), Some(1));
+        assert_eq!(demuxer.attachment_count(), 3);
+        let cover = demuxer.cover_art().unwrap();
+        assert_eq!((cover.mime.as_str(), cover.picture_type, cover.data.as_slice()), ("image/jpeg", PICTURE_FRONT_COVER, &b"\xFF\xD8big"[..]));
+        assert_eq!(crate::metadata::audio::cover_art(&data), Some(cover));
+    }
+}
@@ -0,0 +1,11 @@
+//! Video containers and raw video formats
+
+pub mod avi;
+pub mod matroska;
+pub mod mp4;
+pub mod y4m;
+
+pub use avi::{read_avi, Avi, AviChunk, AviDemuxer, AviStream};
+pub use matroska::{read_matroska, Matroska, MkvAttachment, MkvDemuxer, MkvFrame, MkvTrack};
+pub use mp4::{read_mp4, Mp4Demuxer, Mp4Movie, Mp4Sample, Mp4Track, TrackKind};
+pub use y4m::{rgba_to_yuv, yuv_to_rgba, Y4mChroma, Y4mHeader, Y4mReader, Y4mWriter};
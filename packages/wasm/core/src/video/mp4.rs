@@ -0,0 +1,783 @@
+//! MP4/ISOBMFF demuxing - tracks, codec configuration and samples
+//!
+//! Reads progressive files through their `moov` sample tables and
+//! fragmented files through `moof` track runs. Edit lists are not applied,
+//! so sample times are on the raw media timeline.
+
+use crate::metadata::container::iso_boxes;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// What a track carries, from its handler type
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackKind {
+    Video = 0,
+    Audio = 1,
+    /// Subtitles and timed text
+    Text = 2,
+    Other = 3,
+}
+
+/// Facts about one track, read without touching sample data
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mp4Track {
+    pub id: u32,
+    pub kind: TrackKind,
+    /// Sample entry type, e.g. `avc1`, `hvc1`, `av01`, `mp4a`, `Opus`
+    pub codec: String,
+    /// RFC 6381 codecs parameter (`avc1.64001F`, `mp4a.40.2`) where it can
+    /// be derived, otherwise the sample entry type
+    pub codec_string: String,
+    /// Decoder configuration: the AudioSpecificConfig for MPEG-4 audio,
+    /// otherwise the payload of the `avcC`, `hvcC`, `av1C`, `vpcC`, `dOps` or
+    /// `dfLa` box; empty when there is none
+    pub codec_config: Vec<u8>,
+    /// Ticks per second of sample times
+    pub timescale: u32,
+    /// Duration in seconds
+    pub duration: f64,
+    /// Coded frame size of video tracks
+    pub width: u32,
+    pub height: u32,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// ISO 639-2 language code, `und` when unspecified
+    pub language: String,
+    pub sample_count: u32,
+}
+
+/// Location and timing of one sample (an access unit)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mp4Sample {
+    /// Byte offset in the file
+    pub offset: u64,
+    pub size: u32,
+    /// Decode time in track timescale ticks
+    pub decode_time: u64,
+    /// Presentation time minus decode time, in ticks
+    pub composition_offset: i32,
+    pub duration: u32,
+    /// A random access point, e.g. a key frame
+    pub sync: bool,
+}
+
+impl Mp4Sample {
+    /// This sample's bytes within the file
+    pub fn data<'a>(&self, file: &'a [u8]) -> Result<&'a [u8], String> {
+        let start = usize::try_from(self.offset).map_err(|_| "MP4 sample is out of range")?;
+        file.get(start..start + self.size as usize).ok_or_else(|| "MP4 sample lies outside the file".to_string())
+    }
+}
+
+/// A parsed movie: brands, tracks and each track's sample index
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mp4Movie {
+    pub major_brand: String,
+    pub compatible_brands: Vec<String>,
+    /// Duration in seconds of the longest track
+    pub duration: f64,
+    pub tracks: Vec<Mp4Track>,
+    /// Samples of each track, in decode order
+    pub samples: Vec<Vec<Mp4Sample>>,
+}
+
+/// Bounds-checked big-endian reads within a box
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8]) -> Cursor<'a> {
+        Cursor { data, pos: 0 }
+    }
+
+    /// Start of a full box: (version, flags)
+    fn full(data: &'a [u8]) -> Result<(Cursor<'a>, u8, u32), String> {
+        let mut c = Cursor::new(data);
+        let word = c.u32()?;
+        Ok((c, (word >> 24) as u8, word & 0xFF_FFFF))
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let bytes = self.data.get(self.pos..self.pos.saturating_add(n)).ok_or("MP4 box is truncated")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, n: usize) -> Result<(), String> {
+        self.bytes(n).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok((self.u32()? as u64) << 32 | self.u32()? as u64)
+    }
+
+    /// A 32-bit field in version 0 boxes, 64-bit in version 1
+    fn versioned(&mut self, version: u8) -> Result<u64, String> {
+        if version == 1 {
+            self.u64()
+        } else {
+            self.u32().map(u64::from)
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    /// Reject an entry count whose entries cannot fit in the box
+    fn count(&mut self, entry_size: usize) -> Result<usize, String> {
+        let count = self.u32()? as usize;
+        if count.saturating_mul(entry_size) > self.remaining() {
+            return Err("MP4 table is truncated".to_string());
+        }
+        Ok(count)
+    }
+}
+
+/// Payload of the first child box of this type
+fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    iso_boxes(data).into_iter().find(|b| &b.kind == kind).map(|b| b.data)
+}
+
+/// Payload of the box at the end of a path of nested boxes
+fn find<'a>(data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    path.iter().try_fold(data, |data, kind| child(data, kind))
+}
+
+fn fourcc(kind: &[u8]) -> String {
+    kind.iter().map(|&b| b as char).collect()
+}
+
+/// Duration in seconds; all-ones durations mean unknown
+fn seconds(duration: u64, timescale: u32) -> f64 {
+    if timescale == 0 || duration == u64::MAX || duration == u32::MAX as u64 {
+        return 0.0;
+    }
+    duration as f64 / timescale as f64
+}
+
+/// Object type indication and DecoderSpecificInfo from an `esds` payload
+fn parse_esds(data: &[u8]) -> Result<(u8, Vec<u8>), String> {
+    let (mut c, _, _) = Cursor::full(data)?;
+    let mut object_type = 0;
+    // ES and decoder config descriptors nest the ones that follow
+    while c.remaining() > 0 {
+        let tag = c.u8()?;
+        let mut len = 0;
+        for _ in 0..4 {
+            let b = c.u8()?;
+            len = len << 7 | (b & 0x7F) as usize;
+            if b & 0x80 == 0 {
+                break;
+            }
+        }
+        match tag {
+            3 => {
+                c.skip(2)?;
+                let flags = c.u8()?;
+                if flags & 0x80 != 0 {
+                    c.skip(2)?;
+                }
+                if flags & 0x40 != 0 {
+                    let url_len = c.u8()? as usize;
+                    c.skip(url_len)?;
+                }
+                if flags & 0x20 != 0 {
+                    c.skip(2)?;
+                }
+            }
+            4 => {
+                object_type = c.u8()?;
+                c.skip(12)?;
+            }
+            5 => return Ok((object_type, c.bytes(len)?.to_vec())),
+            _ => c.skip(len)?,
+        }
+    }
+    Ok((object_type, Vec::new()))
+}
+
+/// RFC 6381 codecs parameter for the codecs where it is well defined
+fn codec_string(codec: &str, config: &[u8], object_type: u8) -> String {
+    match codec {
+        "avc1" | "avc3" if config.len() >= 4 => format!("{}.{:02X}{:02X}{:02X}", codec, config[1], config[2], config[3]),
+        "mp4a" if object_type == 0x40 && !config.is_empty() => {
+            let audio_object_type = match config[0] >> 3 {
+                31 if config.len() >= 2 => 32 + ((config[0] & 7) << 3 | config[1] >> 5),
+                aot => aot,
+            };
+            format!("mp4a.40.{}", audio_object_type)
+        }
+        "mp4a" if object_type != 0 => format!("mp4a.{:02x}", object_type),
+        "av01" if config.len() >= 3 => {
+            let depth = match (config[2] >> 6 & 1, config[2] >> 5 & 1) {
+                (1, 1) => 12,
+                (1, 0) => 10,
+                _ => 8,
+            };
+            let tier = if config[2] >> 7 != 0 { 'H' } else { 'M' };
+            format!("av01.{}.{:02}{}.{:02}", config[1] >> 5, config[1] & 0x1F, tier, depth)
+        }
+        // vpcC is a full box: profile, level and depth follow the version
+        "vp09" if config.len() >= 7 => format!("vp09.{:02}.{:02}.{:02}", config[4], config[5], config[6] >> 4),
+        _ => codec.to_string(),
+    }
+}
+
+/// Fill in codec fields from the first sample description
+fn parse_sample_entry(stsd: &[u8], track: &mut Mp4Track) -> Result<(), String> {
+    let (mut c, _, _) = Cursor::full(stsd)?;
+    c.skip(4)?;
+    let Some(entry) = iso_boxes(&stsd[c.pos..]).into_iter().next() else {
+        return Ok(());
+    };
+    track.codec = fourcc(&entry.kind);
+    track.codec_string = track.codec.clone();
+    let mut e = Cursor::new(entry.data);
+    // Reserved bytes and data reference index
+    e.skip(8)?;
+    let children = match track.kind {
+        TrackKind::Video => {
+            e.skip(16)?;
+            track.width = e.u16()? as u32;
+            track.height = e.u16()? as u32;
+            e.skip(50)?;
+            &entry.data[e.pos..]
+        }
+        TrackKind::Audio => {
+            // QuickTime sound descriptions extend the entry by version
+            let version = e.u16()?;
+            e.skip(6)?;
+            track.channels = e.u16()?;
+            e.skip(6)?;
+            track.sample_rate = e.u32()? >> 16;
+            match version {
+                1 => e.skip(16)?,
+                2 => {
+                    e.skip(4)?;
+                    track.sample_rate = f64::from_bits(e.u64()?) as u32;
+                    track.channels = e.u32()? as u16;
+                    e.skip(20)?;
+                }
+                _ => {}
+            }
+            &entry.data[e.pos..]
+        }
+        _ => return Ok(()),
+    };
+    // QuickTime nests the esds of AAC inside a `wave` box
+    let children = child(children, b"wave").unwrap_or(children);
+    let mut object_type = 0;
+    for config in iso_boxes(children) {
+        match &config.kind {
+            b"esds" => {
+                (object_type, track.codec_config) = parse_esds(config.data)?;
+                break;
+            }
+            b"avcC" | b"hvcC" | b"av1C" | b"vpcC" | b"dOps" | b"dfLa" => {
+                track.codec_config = config.data.to_vec();
+                break;
+            }
+            _ => {}
+        }
+    }
+    track.codec_string = codec_string(&track.codec, &track.codec_config, object_type);
+    Ok(())
+}
+
+/// Expand a progressive sample table into per-sample entries
+fn parse_sample_table(stbl: &[u8], file_len: usize) -> Result<Vec<Mp4Sample>, String> {
+    let mut sizes = Vec::new();
+    if let Some(stsz) = child(stbl, b"stsz") {
+        let (mut c, _, _) = Cursor::full(stsz)?;
+        let size = c.u32()?;
+        if size == 0 {
+            let count = c.count(4)?;
+            for _ in 0..count {
+                sizes.push(c.u32()?);
+            }
+        } else {
+            let count = c.u32()? as usize;
+            if count.saturating_mul(size as usize) > file_len {
+                return Err("MP4 sample sizes exceed the file".to_string());
+            }
+            sizes = vec![size; count];
+        }
+    } else if let Some(stz2) = child(stbl, b"stz2") {
+        let (mut c, _, _) = Cursor::full(stz2)?;
+        c.skip(3)?;
+        let field_size = c.u8()? as usize;
+        if ![4, 8, 16].contains(&field_size) {
+            return Err(format!("Invalid MP4 sample size field of {} bits", field_size));
+        }
+        let count = c.u32()? as usize;
+        let table = c.bytes(count.saturating_mul(field_size).div_ceil(8))?;
+        sizes = (0..count)
+            .map(|i| match field_size {
+                4 => (table[i / 2] >> (4 - 4 * (i % 2)) & 15) as u32,
+                8 => table[i] as u32,
+                _ => u16::from_be_bytes([table[2 * i], table[2 * i + 1]]) as u32,
+            })
+            .collect();
+    }
+
+    let mut chunks = Vec::new();
+    if let Some(stco) = child(stbl, b"stco") {
+        let (mut c, _, _) = Cursor::full(stco)?;
+        for _ in 0..c.count(4)? {
+            chunks.push(c.u32()? as u64);
+        }
+    } else if let Some(co64) = child(stbl, b"co64") {
+        let (mut c, _, _) = Cursor::full(co64)?;
+        for _ in 0..c.count(8)? {
+            chunks.push(c.u64()?);
+        }
+    }
+
+    let mut samples: Vec<Mp4Sample> = Vec::with_capacity(sizes.len());
+    if let Some(stsc) = child(stbl, b"stsc") {
+        let (mut c, _, _) = Cursor::full(stsc)?;
+        let count = c.count(12)?;
+        let mut runs = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (first_chunk, per_chunk) = (c.u32()? as usize, c.u32()? as usize);
+            c.skip(4)?;
+            runs.push((first_chunk.max(1), per_chunk));
+        }
+        // Each run covers the chunks up to the next run's first chunk
+        'runs: for (i, &(first_chunk, per_chunk)) in runs.iter().enumerate() {
+            let end = runs.get(i + 1).map_or(chunks.len() + 1, |r| r.0).min(chunks.len() + 1);
+            for &chunk_offset in chunks.get(first_chunk - 1..end - 1).unwrap_or(&[]) {
+                let mut offset = chunk_offset;
+                for _ in 0..per_chunk {
+                    let Some(&size) = sizes.get(samples.len()) else { break 'runs };
+                    samples.push(Mp4Sample { offset, size, decode_time: 0, composition_offset: 0, duration: 0, sync: true });
+                    offset += size as u64;
+                }
+            }
+        }
+    }
+
+    if let Some(stts) = child(stbl, b"stts") {
+        let (mut c, _, _) = Cursor::full(stts)?;
+        let mut iter = samples.iter_mut();
+        let mut time = 0u64;
+        for _ in 0..c.count(8)? {
+            let (count, delta) = (c.u32()?, c.u32()?);
+            for sample in iter.by_ref().take(count as usize) {
+                sample.decode_time = time;
+                sample.duration = delta;
+                time += delta as u64;
+            }
+        }
+    }
+    if let Some(ctts) = child(stbl, b"ctts") {
+        let (mut c, _, _) = Cursor::full(ctts)?;
+        let mut iter = samples.iter_mut();
+        for _ in 0..c.count(8)? {
+            let (count, offset) = (c.u32()?, c.u32()? as i32);
+            for sample in iter.by_ref().take(count as usize) {
+                sample.composition_offset = offset;
+            }
+        }
+    }
+    if let Some(stss) = child(stbl, b"stss") {
+        let (mut c, _, _) = Cursor::full(stss)?;
+        samples.iter_mut().for_each(|s| s.sync = false);
+        for _ in 0..c.count(4)? {
+            if let Some(sample) = (c.u32()? as usize).checked_sub(1).and_then(|i| samples.get_mut(i)) {
+                sample.sync = true;
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Per-track sample defaults from `trex` or `tfhd`
+#[derive(Clone, Copy, Default)]
+struct FragmentDefaults {
+    duration: u32,
+    size: u32,
+    flags: u32,
+}
+
+/// Sample flags bit marking a sample that is not a random access point
+const NON_SYNC: u32 = 0x1_0000;
+
+/// Append the samples of the track runs in one `moof` box
+fn parse_fragment(moof: &[u8], moof_offset: u64, tracks: &[Mp4Track], trex: &[FragmentDefaults], samples: &mut [Vec<Mp4Sample>], file_len: usize) -> Result<(), String> {
+    let mut data_end = moof_offset;
+    for traf in iso_boxes(moof).into_iter().filter(|b| &b.kind == b"traf") {
+        let (mut c, _, flags) = Cursor::full(child(traf.data, b"tfhd").ok_or("MP4 fragment lacks a tfhd box")?)?;
+        let id = c.u32()?;
+        let Some(index) = tracks.iter().position(|t| t.id == id) else { continue };
+        let mut defaults = trex[index];
+        // The first fragment's data follows the moof unless told otherwise
+        let mut base = if flags & 0x2_0000 != 0 { moof_offset } else { data_end };
+        if flags & 0x1 != 0 {
+            base = c.u64()?;
+        }
+        if flags & 0x2 != 0 {
+            c.skip(4)?;
+        }
+        if flags & 0x8 != 0 {
+            defaults.duration = c.u32()?;
+        }
+        if flags & 0x10 != 0 {
+            defaults.size = c.u32()?;
+        }
+        if flags & 0x20 != 0 {
+            defaults.flags = c.u32()?;
+        }
+
+        let track_samples = &mut samples[index];
+        let mut time = match child(traf.data, b"tfdt") {
+            Some(tfdt) => {
+                let (mut c, version, _) = Cursor::full(tfdt)?;
+                c.versioned(version)?
+            }
+            None => track_samples.last().map_or(0, |s| s.decode_time + s.duration as u64),
+        };
+        let mut offset = base;
+        for trun in iso_boxes(traf.data).into_iter().filter(|b| &b.kind == b"trun") {
+            let (mut c, _, flags) = Cursor::full(trun.data)?;
+            let count = c.u32()? as usize;
+            if count > file_len {
+                return Err("MP4 track run is too long".to_string());
+            }
+            if flags & 0x1 != 0 {
+                offset = base.wrapping_add(c.u32()? as i32 as i64 as u64);
+            }
+            let first_flags = if flags & 0x4 != 0 { Some(c.u32()?) } else { None };
+            for i in 0..count {
+                let duration = if flags & 0x100 != 0 { c.u32()? } else { defaults.duration };
+                let size = if flags & 0x200 != 0 { c.u32()? } else { defaults.size };
+                let sample_flags = if flags & 0x400 != 0 { c.u32()? } else { first_flags.filter(|_| i == 0).unwrap_or(defaults.flags) };
+                let composition_offset = if flags & 0x800 != 0 { c.u32()? as i32 } else { 0 };
+                track_samples.push(Mp4Sample { offset, size, decode_time: time, composition_offset, duration, sync: sample_flags & NON_SYNC == 0 });
+                offset += size as u64;
+                time += duration as u64;
+            }
+        }
+        data_end = offset;
+    }
+    Ok(())
+}
+
+fn parse_track(trak: &[u8], file_len: usize) -> Result<(Mp4Track, Vec<Mp4Sample>), String> {
+    let (mut c, version, _) = Cursor::full(child(trak, b"tkhd").ok_or("MP4 track lacks a tkhd box")?)?;
+    c.versioned(version)?;
+    c.versioned(version)?;
+    let id = c.u32()?;
+
+    let mdia = child(trak, b"mdia").ok_or("MP4 track lacks an mdia box")?;
+    let (mut c, version, _) = Cursor::full(child(mdia, b"mdhd").ok_or("MP4 track lacks an mdhd box")?)?;
+    c.versioned(version)?;
+    c.versioned(version)?;
+    let timescale = c.u32()?;
+    let duration = c.versioned(version)?;
+    let packed = c.u16()?;
+    let language: String = [10, 5, 0].iter().map(|shift| ((packed >> shift & 31) as u8 + 0x60) as char).collect();
+
+    let handler = find(mdia, &[b"hdlr"]).and_then(|hdlr| hdlr.get(8..12)).unwrap_or(b"");
+    let kind = match handler {
+        b"vide" => TrackKind::Video,
+        b"soun" => TrackKind::Audio,
+        b"text" | b"sbtl" | b"subt" | b"clcp" => TrackKind::Text,
+        _ => TrackKind::Other,
+    };
+
+    let mut track = Mp4Track {
+        id,
+        kind,
+        codec: String::new(),
+        codec_string: String::new(),
+        codec_config: Vec::new(),
+        timescale,
+        duration: seconds(duration, timescale),
+        width: 0,
+        height: 0,
+        sample_rate: 0,
+        channels: 0,
+        language: if packed == 0 { "und".to_string() } else { language },
+        sample_count: 0,
+    };
+    let stbl = find(mdia, &[b"minf", b"stbl"]).ok_or("MP4 track lacks a sample table")?;
+    if let Some(stsd) = child(stbl, b"stsd") {
+        parse_sample_entry(stsd, &mut track)?;
+    }
+    Ok((track, parse_sample_table(stbl, file_len)?))
+}
+
+/// Parse the movie structure of an MP4, MOV, M4A or other ISOBMFF file
+pub fn read_mp4(data: &[u8]) -> Result<Mp4Movie, String> {
+    let boxes = iso_boxes(data);
+    let moov = boxes.iter().find(|b| &b.kind == b"moov").ok_or("No moov box; not an MP4 file")?;
+    let (major_brand, compatible_brands) = match boxes.iter().find(|b| &b.kind == b"ftyp") {
+        Some(ftyp) if ftyp.data.len() >= 8 => (fourcc(&ftyp.data[..4]), ftyp.data[8..].chunks_exact(4).map(fourcc).collect()),
+        _ => (String::new(), Vec::new()),
+    };
+    let movie_duration = match child(moov.data, b"mvhd") {
+        Some(mvhd) => {
+            let (mut c, version, _) = Cursor::full(mvhd)?;
+            c.versioned(version)?;
+            c.versioned(version)?;
+            let timescale = c.u32()?;
+            seconds(c.versioned(version)?, timescale)
+        }
+        None => 0.0,
+    };
+
+    let mut tracks = Vec::new();
+    let mut samples = Vec::new();
+    for trak in iso_boxes(moov.data).into_iter().filter(|b| &b.kind == b"trak") {
+        let (track, track_samples) = parse_track(trak.data, data.len())?;
+        tracks.push(track);
+        samples.push(track_samples);
+    }
+
+    if let Some(mvex) = child(moov.data, b"mvex") {
+        let mut trex = vec![FragmentDefaults::default(); tracks.len()];
+        for b in iso_boxes(mvex).into_iter().filter(|b| &b.kind == b"trex") {
+            let (mut c, _, _) = Cursor::full(b.data)?;
+            let id = c.u32()?;
+            c.skip(4)?;
+            let defaults = FragmentDefaults { duration: c.u32()?, size: c.u32()?, flags: c.u32()? };
+            if let Some(index) = tracks.iter().position(|t| t.id == id) {
+                trex[index] = defaults;
+            }
+        }
+        for moof in boxes.iter().filter(|b| &b.kind == b"moof") {
+            parse_fragment(moof.data, moof.offset as u64, &tracks, &trex, &mut samples, data.len())?;
+        }
+    }
+
+    for (track, track_samples) in tracks.iter_mut().zip(&samples) {
+        track.sample_count = track_samples.len() as u32;
+        if track.duration == 0.0 {
+            let end = track_samples.iter().map(|s| s.decode_time + s.duration as u64).max().unwrap_or(0);
+            track.duration = seconds(end, track.timescale);
+        }
+    }
+    let duration = tracks.iter().map(|t| t.duration).fold(movie_duration, f64::max);
+    Ok(Mp4Movie { major_brand, compatible_brands, duration, tracks, samples })
+}
+
+/// An MP4 file whose tracks and samples can be read individually
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Mp4Demuxer {
+    data: Vec<u8>,
+    movie: Mp4Movie,
+}
+
+impl Mp4Demuxer {
+    pub fn parse(data: Vec<u8>) -> Result<Mp4Demuxer, String> {
+        let movie = read_mp4(&data)?;
+        Ok(Mp4Demuxer { data, movie })
+    }
+
+    pub fn movie(&self) -> &Mp4Movie {
+        &self.movie
+    }
+
+    /// Bytes of sample `index` of track `track`
+    pub fn read(&self, track: usize, index: usize) -> Result<&[u8], String> {
+        let sample = self.movie.samples.get(track).and_then(|s| s.get(index)).ok_or_else(|| format!("No MP4 sample {} in track {}", index, track))?;
+        sample.data(&self.data)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Mp4Demuxer {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<Mp4Demuxer, JsError> {
+        Mp4Demuxer::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = majorBrand))]
+    pub fn major_brand(&self) -> String {
+        self.movie.major_brand.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = compatibleBrands))]
+    pub fn compatible_brands(&self) -> Vec<String> {
+        self.movie.compatible_brands.clone()
+    }
+
+    /// Duration in seconds
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn duration(&self) -> f64 {
+        self.movie.duration
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = trackCount))]
+    pub fn track_count(&self) -> usize {
+        self.movie.tracks.len()
+    }
+
+    pub fn track(&self, index: usize) -> Option<Mp4Track> {
+        self.movie.tracks.get(index).cloned()
+    }
+
+    /// Index of the first track of this kind
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = findTrack))]
+    pub fn find_track(&self, kind: TrackKind) -> Option<usize> {
+        self.movie.tracks.iter().position(|t| t.kind == kind)
+    }
+
+    /// Location and timing of sample `index` of track `track`
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = sampleInfo))]
+    pub fn sample_info(&self, track: usize, index: usize) -> Option<Mp4Sample> {
+        self.movie.samples.get(track)?.get(index).copied()
+    }
+
+    /// Bytes of sample `index` of track `track`
+    #[cfg(feature = "wasm")]
+    pub fn sample(&self, track: usize, index: usize) -> Result<Vec<u8>, JsError> {
+        self.read(track, index).map(|d| d.to_vec()).map_err(|e| JsError::new(&e))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn mp4_box(kind: &[u8; 4], parts: &[&[u8]]) -> Vec<u8> {
+        let payload = parts.concat();
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    fn full_box(kind: &[u8; 4], version: u8, flags: u32, parts: &[&[u8]]) -> Vec<u8> {
+        let header = (version as u32) << 24 | flags;
+        mp4_box(kind, &[&header.to_be_bytes(), &parts.concat()])
+    }
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    /// A track box; the sample table boxes follow the sample description
+    fn trak(id: u32, handler: &[u8; 4], timescale: u32, duration: u32, entry: &[u8], tables: &[&[u8]]) -> Vec<u8> {
+        let tkhd = full_box(b"tkhd", 0, 3, &[&words(&[0, 0, id, 0, duration]), &[0; 60]]);
+        // Language "eng"
+        let mdhd = full_box(b"mdhd", 0, 0, &[&words(&[0, 0, timescale, duration]), &[0x15, 0xC7, 0, 0]]);
+        let hdlr = full_box(b"hdlr", 0, 0, &[&words(&[0]), handler, &[0; 13]]);
+        let stsd = full_box(b"stsd", 0, 0, &[&words(&[1]), entry]);
+        let stbl = mp4_box(b"stbl", &[&[&stsd[..]], tables].concat());
+        let minf = mp4_box(b"minf", &[&stbl]);
+        mp4_box(b"trak", &[&tkhd, &mp4_box(b"mdia", &[&mdhd, &hdlr, &minf])])
+    }
+
+    fn mp4a_entry(asc: &[u8]) -> Vec<u8> {
+        let mut dsi = vec![0x05, asc.len() as u8];
+        dsi.extend_from_slice(asc);
+        let mut config = vec![0x04, (13 + dsi.len()) as u8, 0x40, 0x15];
+        config.extend_from_slice(&[0; 11]);
+        config.extend_from_slice(&dsi);
+        let mut es = vec![0x03, (3 + config.len()) as u8, 0, 1, 0];
+        es.extend_from_slice(&config);
+        let esds = full_box(b"esds", 0, 0, &[&es]);
+        mp4_box(b"mp4a", &[&[0, 0, 0, 0, 0, 0, 0, 1], &[0; 8], &[0, 2, 0, 16, 0, 0, 0, 0], &words(&[44100 << 16]), &esds])
+    }
+
+    /// An M4A file holding `frames` as one chunk of AAC samples
+    pub(crate) fn audio_mp4(asc: &[u8], frames: &[&[u8]]) -> Vec<u8> {
+        let ftyp = mp4_box(b"ftyp", &[b"M4A ", &[0; 4], b"M4A isom"]);
+        let sizes: Vec<u32> = frames.iter().map(|f| f.len() as u32).collect();
+        let n = frames.len() as u32;
+        let build = |mdat_offset: u32| {
+            let stts = full_box(b"stts", 0, 0, &[&words(&[1, n, 1024])]);
+            let stsc = full_box(b"stsc", 0, 0, &[&words(&[1, 1, n, 1])]);
+            let stsz = full_box(b"stsz", 0, 0, &[&words(&[0, n]), &words(&sizes)]);
+            let stco = full_box(b"stco", 0, 0, &[&words(&[1, mdat_offset])]);
+            let trak = trak(1, b"soun", 44100, n * 1024, &mp4a_entry(asc), &[&stts, &stsc, &stsz, &stco]);
+            let mvhd = full_box(b"mvhd", 0, 0, &[&words(&[0, 0, 1000, n * 1024 * 1000 / 44100]), &[0; 80]]);
+            mp4_box(b"moov", &[&mvhd, &trak])
+        };
+        let moov_len = build(0).len();
+        let moov = build((ftyp.len() + moov_len + 8) as u32);
+        [ftyp, moov, mp4_box(b"mdat", &[&frames.concat()])].concat()
+    }
+
+    #[test]
+    fn test_progressive_audio_and_video() {
+        let file = audio_mp4(&[0x12, 0x10], &[b"abc", b"de", b"f"]);
+        let movie = read_mp4(&file).unwrap();
+        assert_eq!((movie.major_brand.as_str(), movie.compatible_brands.len()), ("M4A ", 2));
+        let track = &movie.tracks[0];
+        assert_eq!((track.kind, track.codec.as_str(), track.codec_string.as_str()), (TrackKind::Audio, "mp4a", "mp4a.40.2"));
+        assert_eq!((track.sample_rate, track.channels, track.language.as_str()), (44100, 2, "eng"));
+        assert_eq!(track.codec_config, [0x12, 0x10]);
+        let data: Vec<_> = movie.samples[0].iter().map(|s| s.data(&file).unwrap()).collect();
+        assert_eq!(data, [&b"abc"[..], b"de", b"f"]);
+        assert_eq!(movie.samples[0][2].decode_time, 2048);
+
+        // Two chunks of H.264 with B-frame reordering and one key frame
+        let avcc = mp4_box(b"avcC", &[&[1, 0x64, 0x00, 0x1F, 0xFF]]);
+        let avc1 = mp4_box(b"avc1", &[&[0; 24], &[0x02, 0x80, 0x01, 0x68], &[0; 50], &avcc]);
+        let stts = full_box(b"stts", 0, 0, &[&words(&[1, 3, 512])]);
+        let ctts = full_box(b"ctts", 0, 0, &[&words(&[1, 3, 1024])]);
+        let stsc = full_box(b"stsc", 0, 0, &[&words(&[2, 1, 2, 1, 2, 1, 1])]);
+        let stsz = full_box(b"stsz", 0, 0, &[&words(&[4, 3])]);
+        let stco = full_box(b"co64", 0, 0, &[&words(&[2, 0, 100, 0, 200])]);
+        let stss = full_box(b"stss", 0, 0, &[&words(&[1, 1])]);
+        let video = trak(7, b"vide", 12800, 1536, &avc1, &[&stts, &ctts, &stsc, &stsz, &stco, &stss]);
+        let file = [mp4_box(b"moov", &[&video]), vec![0; 300]].concat();
+        let movie = read_mp4(&file).unwrap();
+        let track = &movie.tracks[0];
+        assert_eq!((track.id, track.kind, track.width, track.height), (7, TrackKind::Video, 640, 360));
+        assert_eq!((track.codec_string.as_str(), track.duration, track.sample_count), ("avc1.64001F", 0.12, 3));
+        let offsets: Vec<_> = movie.samples[0].iter().map(|s| (s.offset, s.sync, s.composition_offset)).collect();
+        assert_eq!(offsets, [(100, true, 1024), (104, false, 1024), (200, false, 1024)]);
+        assert!(read_mp4(b"\0\0\0\x08free").is_err());
+    }
+
+    #[test]
+    fn test_fragmented() {
+        let entry = mp4_box(b"Opus", &[&[0; 8], &[0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 16, 0, 0, 0, 0], &words(&[48000 << 16])]);
+        let trak = trak(1, b"soun", 48000, 0, &entry, &[]);
+        let trex = full_box(b"trex", 0, 0, &[&words(&[1, 1, 960, 3, 0])]);
+        let moov = mp4_box(b"moov", &[&trak, &mp4_box(b"mvex", &[&trex])]);
+        let fragment = |time: u32, sizes: &[u32]| {
+            let tfhd = full_box(b"tfhd", 0, 0x2_0000, &[&words(&[1])]);
+            let tfdt = full_box(b"tfdt", 0, 0, &[&words(&[time])]);
+            let build = |data_offset: u32| {
+                let trun = full_box(b"trun", 0, 0x201, &[&words(&[sizes.len() as u32, data_offset]), &words(sizes)]);
+                mp4_box(b"moof", &[&mp4_box(b"traf", &[&tfhd, &tfdt, &trun])])
+            };
+            let moof_len = build(0).len() as u32;
+            let data: Vec<u8> = sizes.iter().flat_map(|&s| vec![s as u8; s as usize]).collect();
+            [build(moof_len + 8), mp4_box(b"mdat", &[&data])].concat()
+        };
+        let file = [moov, fragment(0, &[1, 2]), fragment(1920, &[3])].concat();
+        let movie = read_mp4(&file).unwrap();
+        let track = &movie.tracks[0];
+        assert_eq!((track.codec.as_str(), track.sample_rate, track.sample_count, track.duration), ("Opus", 48000, 3, 0.06));
+        let samples: Vec<_> = movie.samples[0].iter().map(|s| (s.decode_time, s.data(&file).unwrap().to_vec())).collect();
+        assert_eq!(samples, [(0, vec![1]), (960, vec![2, 2]), (1920, vec![3, 3, 3])]);
+        assert_eq!(movie.duration, 0.06);
+    }
+}
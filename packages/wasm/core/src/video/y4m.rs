@@ -0,0 +1,413 @@
+//! YUV4MPEG2 (`.y4m`) raw video
+//!
+//! A text header followed by frames of 8-bit planar YCbCr, Y then Cb then
+//! Cr. Samples are limited range (16-235) unless the header carries the
+//! `XCOLORRANGE=FULL` extension. Chroma siting is ignored: subsampled
+//! chroma is averaged on encode and replicated on decode.
+
+use crate::color::space::{rgb_to_ycbcr, ycbcr_to_rgb, YCbCrMatrix};
+use crate::limits::Budget;
+use crate::utils::check_rgba;
+use std::ops::Range;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const MAGIC: &[u8] = b"YUV4MPEG2";
+
+/// Chroma subsampling of the Cb and Cr planes
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Y4mChroma {
+    /// Half width, half height
+    C420 = 0,
+    /// Half width, full height
+    C422 = 1,
+    /// No subsampling
+    C444 = 2,
+    /// Luma only
+    Mono = 3,
+}
+
+impl Y4mChroma {
+    /// Size of each chroma plane, (0, 0) for mono
+    fn plane_size(self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Y4mChroma::C420 => (width.div_ceil(2), height.div_ceil(2)),
+            Y4mChroma::C422 => (width.div_ceil(2), height),
+            Y4mChroma::C444 => (width, height),
+            Y4mChroma::Mono => (0, 0),
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            Y4mChroma::C420 => "420jpeg",
+            Y4mChroma::C422 => "422",
+            Y4mChroma::C444 => "444",
+            Y4mChroma::Mono => "mono",
+        }
+    }
+}
+
+/// Stream parameters from the Y4M header
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Y4mHeader {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate_num: u32,
+    pub frame_rate_den: u32,
+    /// Pixel aspect ratio; 0:0 when unknown
+    pub aspect_num: u32,
+    pub aspect_den: u32,
+    /// `p` progressive, `t` top field first, `b` bottom field first, `m` mixed
+    pub interlace: char,
+    pub chroma: Y4mChroma,
+    /// Samples use the full 0-255 range rather than 16-235
+    pub full_range: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Y4mHeader {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32, frame_rate_num: u32, frame_rate_den: u32, chroma: Y4mChroma) -> Y4mHeader {
+        Y4mHeader {
+            width,
+            height,
+            frame_rate_num,
+            frame_rate_den,
+            aspect_num: 1,
+            aspect_den: 1,
+            interlace: 'p',
+            chroma,
+            full_range: false,
+        }
+    }
+
+    /// Bytes in one frame's planes
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = frameSize))]
+    pub fn frame_size(&self) -> usize {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let (cw, ch) = self.chroma.plane_size(w, h);
+        w * h + 2 * cw * ch
+    }
+}
+
+fn parse_ratio(value: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("Invalid Y4M ratio '{}'", value);
+    let (num, den) = value.split_once(':').ok_or_else(invalid)?;
+    Ok((num.parse().map_err(|_| invalid())?, den.parse().map_err(|_| invalid())?))
+}
+
+/// Parse the stream header; returns it with the offset of the first frame
+fn parse_header(data: &[u8]) -> Result<(Y4mHeader, usize), String> {
+    if !data.starts_with(MAGIC) {
+        return Err("Not a Y4M file".to_string());
+    }
+    let end = data.iter().position(|&b| b == b'\n').ok_or("Y4M header is truncated")?;
+    let line = std::str::from_utf8(&data[MAGIC.len()..end]).map_err(|_| "Y4M header is not ASCII")?;
+    let mut header = Y4mHeader::new(0, 0, 0, 0, Y4mChroma::C420);
+    header.aspect_num = 0;
+    header.aspect_den = 0;
+    for token in line.split(' ').filter(|t| !t.is_empty()) {
+        let (key, value) = token.split_at(1);
+        match key {
+            "W" => header.width = value.parse().map_err(|_| "Invalid Y4M width")?,
+            "H" => header.height = value.parse().map_err(|_| "Invalid Y4M height")?,
+            "F" => (header.frame_rate_num, header.frame_rate_den) = parse_ratio(value)?,
+            "A" => (header.aspect_num, header.aspect_den) = parse_ratio(value)?,
+            "I" => header.interlace = value.chars().next().unwrap_or('?'),
+            "C" => {
+                header.chroma = match value {
+                    "420" | "420jpeg" | "420paldv" | "420mpeg2" => Y4mChroma::C420,
+                    "422" => Y4mChroma::C422,
+                    "444" => Y4mChroma::C444,
+                    "mono" => Y4mChroma::Mono,
+                    _ => return Err(format!("Unsupported Y4M colorspace {}", value)),
+                }
+            }
+            "X" => {
+                if let Some(range) = value.strip_prefix("COLORRANGE=") {
+                    header.full_range = range == "FULL";
+                }
+            }
+            _ => {}
+        }
+    }
+    if header.width == 0 || header.height == 0 {
+        return Err("Y4M header lacks frame dimensions".to_string());
+    }
+    Ok((header, end + 1))
+}
+
+fn header_line(header: &Y4mHeader) -> String {
+    let mut line = format!(
+        "YUV4MPEG2 W{} H{} F{}:{} I{} A{}:{} C{}",
+        header.width,
+        header.height,
+        header.frame_rate_num,
+        header.frame_rate_den,
+        header.interlace,
+        header.aspect_num,
+        header.aspect_den,
+        header.chroma.tag()
+    );
+    if header.full_range {
+        line.push_str(" XCOLORRANGE=FULL");
+    }
+    line.push('\n');
+    line
+}
+
+/// Convert one planar frame to RGBA
+pub fn yuv_to_rgba(frame: &[u8], header: &Y4mHeader, matrix: YCbCrMatrix) -> Result<Vec<u8>, String> {
+    if frame.len() != header.frame_size() {
+        return Err(format!("Y4M frame must be {} bytes, got {}", header.frame_size(), frame.len()));
+    }
+    let (w, h) = (header.width as usize, header.height as usize);
+    let (cw, ch) = header.chroma.plane_size(w, h);
+    let (luma, chroma) = frame.split_at(w * h);
+    let (cb, cr) = chroma.split_at(cw * ch);
+    // Chroma plane position of each luma column and row
+    let (sx, sy) = (if cw < w { 1 } else { 0 }, if ch < h { 1 } else { 0 });
+    let expand = |v: u8, luma: bool| -> f32 {
+        match (header.full_range, luma) {
+            (true, _) => v as f32,
+            (false, true) => (v as f32 - 16.0) * 255.0 / 219.0,
+            (false, false) => (v as f32 - 128.0) * 255.0 / 224.0 + 128.0,
+        }
+    };
+    let mut out = vec![255u8; w * h * 4];
+    for (i, pixel) in out.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (i % w, i / w);
+        let c = (y >> sy) * cw + (x >> sx);
+        let (u, v) = if cw == 0 { (128.0, 128.0) } else { (expand(cb[c], false), expand(cr[c], false)) };
+        let rgb = ycbcr_to_rgb([expand(luma[i], true), u, v], matrix);
+        for (p, v) in pixel.iter_mut().zip(rgb) {
+            *p = v.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    Ok(out)
+}
+
+/// Convert RGBA to one planar frame, ignoring alpha
+pub fn rgba_to_yuv(data: &[u8], header: &Y4mHeader, matrix: YCbCrMatrix) -> Result<Vec<u8>, String> {
+    check_rgba(data, header.width, header.height)?;
+    let (w, h) = (header.width as usize, header.height as usize);
+    let (cw, ch) = header.chroma.plane_size(w, h);
+    let compress = |v: f32, luma: bool| -> u8 {
+        let v = match (header.full_range, luma) {
+            (true, _) => v,
+            (false, true) => 16.0 + v * 219.0 / 255.0,
+            (false, false) => 128.0 + (v - 128.0) * 224.0 / 255.0,
+        };
+        v.round().clamp(0.0, 255.0) as u8
+    };
+    let ycc: Vec<[f32; 3]> = data.chunks_exact(4).map(|p| rgb_to_ycbcr([p[0] as f32, p[1] as f32, p[2] as f32], matrix)).collect();
+    let mut out: Vec<u8> = ycc.iter().map(|p| compress(p[0], true)).collect();
+    if cw > 0 {
+        let (bw, bh) = (w.div_ceil(cw), h.div_ceil(ch));
+        // Average the chroma of each block of pixels sharing a sample
+        let mut sums = vec![[0.0f32; 3]; cw * ch];
+        for (i, p) in ycc.iter().enumerate() {
+            let sum = &mut sums[(i / w / bh) * cw + (i % w) / bw];
+            sum[0] += p[1];
+            sum[1] += p[2];
+            sum[2] += 1.0;
+        }
+        out.extend(sums.iter().map(|s| compress(s[0] / s[2], false)));
+        out.extend(sums.iter().map(|s| compress(s[1] / s[2], false)));
+    }
+    Ok(out)
+}
+
+/// A Y4M stream whose frames can be read individually
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Y4mReader {
+    data: Vec<u8>,
+    header: Y4mHeader,
+    frames: Vec<Range<usize>>,
+}
+
+impl Y4mReader {
+    pub fn parse(data: Vec<u8>) -> Result<Y4mReader, String> {
+        let (header, mut pos) = parse_header(&data)?;
+        let size = header.frame_size();
+        let mut frames = Vec::new();
+        while pos < data.len() {
+            if !data[pos..].starts_with(b"FRAME") {
+                return Err(format!("Expected a Y4M frame header at offset {}", pos));
+            }
+            let Some(end) = data[pos..].iter().position(|&b| b == b'\n') else { break };
+            let start = pos + end + 1;
+            if start + size > data.len() {
+                // A truncated final frame ends the stream
+                break;
+            }
+            frames.push(start..start + size);
+            pos = start + size;
+        }
+        Ok(Y4mReader { data, header, frames })
+    }
+
+    pub fn header(&self) -> &Y4mHeader {
+        &self.header
+    }
+
+    /// Planes of the frame at `index`
+    pub fn frame(&self, index: usize) -> Result<&[u8], String> {
+        let range = self.frames.get(index).ok_or_else(|| format!("No Y4M frame {}", index))?;
+        Ok(&self.data[range.clone()])
+    }
+
+    /// The frame at `index` converted to RGBA
+    pub fn frame_rgba(&self, index: usize, matrix: YCbCrMatrix) -> Result<Vec<u8>, String> {
+        let mut budget = Budget::new();
+        budget.check_size(self.header.width as usize, self.header.height as usize)?;
+        budget.rows(self.header.height as usize)?;
+        yuv_to_rgba(self.frame(index)?, &self.header, matrix)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Y4mReader {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<Y4mReader, JsError> {
+        Y4mReader::parse(data).map_err(|e| JsError::new(&e))
+    }
+
+    /// Stream parameters
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter, js_name = header)]
+    pub fn header_js(&self) -> Y4mHeader {
+        self.header
+    }
+
+    /// Number of complete frames
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn length(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Planar Y, Cb, Cr bytes of the frame at `index`
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = frame)]
+    pub fn frame_js(&self, index: usize) -> Result<Vec<u8>, JsError> {
+        self.frame(index).map(|f| f.to_vec()).map_err(|e| JsError::new(&e))
+    }
+
+    /// The frame at `index` converted to RGBA
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = frameRgba)]
+    pub fn frame_rgba_js(&self, index: usize, matrix: YCbCrMatrix) -> Result<Vec<u8>, JsError> {
+        self.frame_rgba(index, matrix).map_err(|e| JsError::new(&e))
+    }
+}
+
+/// Builds a Y4M stream one frame at a time
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Y4mWriter {
+    header: Y4mHeader,
+    out: Vec<u8>,
+}
+
+impl Y4mWriter {
+    pub fn new(header: &Y4mHeader) -> Result<Y4mWriter, String> {
+        if header.width == 0 || header.height == 0 {
+            return Err("Y4M frames must have nonzero dimensions".to_string());
+        }
+        Ok(Y4mWriter { header: *header, out: header_line(header).into_bytes() })
+    }
+
+    pub fn add_frame(&mut self, frame: &[u8]) -> Result<(), String> {
+        if frame.len() != self.header.frame_size() {
+            return Err(format!("Y4M frame must be {} bytes, got {}", self.header.frame_size(), frame.len()));
+        }
+        self.out.extend_from_slice(b"FRAME\n");
+        self.out.extend_from_slice(frame);
+        Ok(())
+    }
+
+    pub fn add_rgba_frame(&mut self, data: &[u8], matrix: YCbCrMatrix) -> Result<(), String> {
+        let frame = rgba_to_yuv(data, &self.header, matrix)?;
+        self.add_frame(&frame)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Y4mWriter {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new_js(header: &Y4mHeader) -> Result<Y4mWriter, JsError> {
+        Y4mWriter::new(header).map_err(|e| JsError::new(&e))
+    }
+
+    /// Append planar Y, Cb, Cr bytes
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = addFrame)]
+    pub fn add_frame_js(&mut self, frame: &[u8]) -> Result<(), JsError> {
+        self.add_frame(frame).map_err(|e| JsError::new(&e))
+    }
+
+    /// Append an RGBA image, converted with the given matrix
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = addRgbaFrame)]
+    pub fn add_rgba_frame_js(&mut self, data: &[u8], matrix: YCbCrMatrix) -> Result<(), JsError> {
+        self.add_rgba_frame(data, matrix).map_err(|e| JsError::new(&e))
+    }
+
+    /// Drain the bytes written so far
+    pub fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.out)
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let data = b"YUV4MPEG2 W3 H2 F30000:1001 It A1:1 C422 XYSCSS=422 XCOLORRANGE=FULL\nFRAME\n\x01\x02\x03\x04\x05\x06abcdefgh";
+        let reader = Y4mReader::parse(data.to_vec()).unwrap();
+        let header = reader.header();
+        assert_eq!((header.width, header.height, header.frame_rate_num, header.frame_rate_den), (3, 2, 30000, 1001));
+        assert_eq!((header.interlace, header.chroma, header.full_range), ('t', Y4mChroma::C422, true));
+        assert_eq!(reader.frames.len(), 1);
+        assert_eq!(reader.frame(0).unwrap(), b"\x01\x02\x03\x04\x05\x06abcdefgh");
+        assert!(Y4mReader::parse(b"YUV4MPEG2 W2 H2 C420p10\n".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_rgba_roundtrip() {
+        // 4x2 pixels in flat 2x2 color blocks survive 4:2:0 subsampling
+        let colors = [[200u8, 30, 40], [20, 180, 90]];
+        let rgba: Vec<u8> = (0..8)
+            .flat_map(|i| {
+                let c = colors[i % 4 / 2];
+                [c[0], c[1], c[2], 255]
+            })
+            .collect();
+        let header = Y4mHeader::new(4, 2, 25, 1, Y4mChroma::C420);
+        let mut writer = Y4mWriter::new(&header).unwrap();
+        writer.add_rgba_frame(&rgba, YCbCrMatrix::Bt709).unwrap();
+        writer.add_rgba_frame(&rgba, YCbCrMatrix::Bt709).unwrap();
+        let data = writer.finish();
+        assert!(data.starts_with(b"YUV4MPEG2 W4 H2 F25:1 Ip A1:1 C420jpeg\nFRAME\n"));
+
+        let reader = Y4mReader::parse(data).unwrap();
+        assert_eq!(reader.frames.len(), 2);
+        let decoded = yuv_to_rgba(reader.frame(1).unwrap(), reader.header(), YCbCrMatrix::Bt709).unwrap();
+        for (a, b) in decoded.iter().zip(&rgba) {
+            assert!((*a as i32 - *b as i32).abs() <= 2, "{} vs {}", a, b);
+        }
+        // White maps to the top of the limited range
+        let gray = rgba_to_yuv(&[255; 4], &Y4mHeader::new(1, 1, 1, 1, Y4mChroma::C444), YCbCrMatrix::Bt601).unwrap();
+        assert_eq!(gray, [235, 128, 128]);
+    }
+}
@@ -0,0 +1,134 @@
+//! WebP decoder: simple and extended (VP8X) files, lossy and lossless,
+//! with alpha and animation
+
+use super::vp8::decode_vp8;
+use super::vp8l::{decode_vp8l, decode_vp8l_stream};
+use crate::animation::{Canvas, FrameSequence, Rect};
+use crate::limits::Budget;
+use crate::metadata::container::{riff_chunks, riff_sub_chunks, RiffChunk};
+use crate::pool;
+
+/// VP8X flag for an animated file
+const ANIMATION_FLAG: u8 = 0x02;
+
+fn read_u24(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], 0])
+}
+
+fn find<'a>(chunks: &[RiffChunk<'a>], fourcc: &[u8; 4]) -> Option<&'a [u8]> {
+    chunks.iter().find(|c| &c.fourcc == fourcc).map(|c| c.data)
+}
+
+pub(super) fn argb_to_rgba(argb: &[u32]) -> Vec<u8> {
+    argb.iter().flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8, (p >> 24) as u8]).collect()
+}
+
+/// Decode the image in `chunks` (`VP8L`, or `VP8` with an optional `ALPH`) to RGBA
+fn decode_image(chunks: &[RiffChunk], budget: &mut Budget) -> Result<(u32, u32, Vec<u8>), String> {
+    if let Some(data) = find(chunks, b"VP8L") {
+        let (width, height, argb) = decode_vp8l(data, budget)?;
+        return Ok((width, height, argb_to_rgba(&argb)));
+    }
+    let data = find(chunks, b"VP8 ").ok_or("WebP has no image data")?;
+    let (width, height, mut rgba) = decode_vp8(data, budget)?;
+    if let Some(alpha) = find(chunks, b"ALPH") {
+        budget.rows(height as usize)?;
+        apply_alpha(&mut rgba, width as usize, height as usize, alpha)?;
+    }
+    Ok((width, height, rgba))
+}
+
+/// Decode an `ALPH` chunk into the alpha channel of `rgba`
+fn apply_alpha(rgba: &mut [u8], width: usize, height: usize, chunk: &[u8]) -> Result<(), String> {
+    let (&header, payload) = chunk.split_first().ok_or("Empty WebP alpha chunk")?;
+    let mut alpha = pool::take_empty(width * height);
+    match header & 3 {
+        0 => alpha.extend_from_slice(payload.get(..width * height).ok_or("Truncated WebP alpha data")?),
+        // Alpha is carried in the green channel of a headerless VP8L stream
+        1 => alpha.extend(decode_vp8l_stream(payload, width as u32, height as u32)?.iter().map(|&p| (p >> 8) as u8)),
+        c => return Err(format!("Unknown WebP alpha compression {}", c)),
+    }
+
+    let filter = (header >> 2) & 3;
+    if filter != 0 {
+        for i in 1..alpha.len() {
+            let (x, y) = (i % width, i / width);
+            let left = if x > 0 { alpha[i - 1] } else { alpha[i - width] };
+            let above = if y > 0 { alpha[i - width] } else { left };
+            let predicted = match filter {
+                1 => left,
+                2 => above,
+                _ if x > 0 && y > 0 => (left as i16 + above as i16 - alpha[i - width - 1] as i16).clamp(0, 255) as u8,
+                _ => left,
+            };
+            alpha[i] = alpha[i].wrapping_add(predicted);
+        }
+    }
+    for (pixel, &a) in rgba.chunks_exact_mut(4).zip(alpha.iter()) {
+        pixel[3] = a;
+    }
+    Ok(())
+}
+
+/// Decode a WebP into composited frames; a still image becomes one frame
+///
+/// Frames start on a transparent canvas; the `ANIM` background color is a
+/// hint that browsers ignore too.
+pub fn decode_webp_animation(data: &[u8]) -> Result<FrameSequence, String> {
+    let chunks = riff_chunks(data, b"WEBP");
+    if chunks.is_empty() {
+        return Err("Invalid WebP file".to_string());
+    }
+    let animated = find(&chunks, b"VP8X").is_some_and(|x| x.len() >= 10 && x[0] & ANIMATION_FLAG != 0);
+    let mut budget = Budget::new();
+    if !animated {
+        let (width, height, rgba) = decode_image(&chunks, &mut budget)?;
+        let mut sequence = FrameSequence::new(width, height);
+        budget.frame(rgba.len())?;
+        sequence.push(rgba, 0)?;
+        return Ok(sequence);
+    }
+
+    let vp8x = find(&chunks, b"VP8X").unwrap_or_default();
+    let (width, height) = (read_u24(vp8x, 4) + 1, read_u24(vp8x, 7) + 1);
+    budget.check_size(width as usize, height as usize)?;
+    let mut sequence = FrameSequence::new(width, height);
+    sequence.loop_count = find(&chunks, b"ANIM").filter(|a| a.len() >= 6).map_or(0, |a| u16::from_le_bytes([a[4], a[5]]) as u32);
+    let mut canvas = Canvas::new(width, height);
+
+    for frame in chunks.iter().filter(|c| &c.fourcc == b"ANMF") {
+        let header = frame.data.get(..16).ok_or("Truncated WebP animation frame")?;
+        let rect = Rect {
+            x: read_u24(header, 0) * 2,
+            y: read_u24(header, 3) * 2,
+            width: read_u24(header, 6) + 1,
+            height: read_u24(header, 9) + 1,
+        };
+        let flags = header[15];
+        budget.frame(canvas.data.len())?;
+        let (w, h, pixels) = decode_image(&riff_sub_chunks(&frame.data[16..]), &mut budget)?;
+        if (w, h) != (rect.width, rect.height) {
+            return Err(format!("WebP frame is {}x{}, expected {}x{}", w, h, rect.width, rect.height));
+        }
+        canvas.draw(&pixels, rect, flags & 2 == 0);
+        sequence.push(canvas.data.clone(), read_u24(header, 12))?;
+        if flags & 1 != 0 {
+            canvas.clear(rect);
+        }
+    }
+    if sequence.frames().is_empty() {
+        return Err("Animated WebP has no frames".to_string());
+    }
+    Ok(sequence)
+}
+
+/// Decode WebP to RGBA; animations yield their first frame
+pub fn decode_webp(data: &[u8]) -> Result<Vec<u8>, String> {
+    let sequence = decode_webp_animation(data)?;
+    let pixels = &sequence.frames()[0].data;
+    let mut output = Vec::with_capacity(8 + pixels.len());
+    output.extend_from_slice(&sequence.width().to_le_bytes());
+    output.extend_from_slice(&sequence.height().to_le_bytes());
+    output.extend_from_slice(pixels);
+    Ok(output)
+}
@@ -0,0 +1,99 @@
+//! Lossless WebP encoder: simple VP8L files and animated VP8X files
+
+use super::vp8l_encoder::encode_vp8l;
+use crate::animation::{changed_rect, FrameSequence, Rect};
+use crate::transform::{crop, CropRegion};
+use crate::utils::check_rgba;
+
+/// Largest VP8L side (14-bit size fields)
+const MAX_SIZE: u32 = 1 << 14;
+const MAX_DURATION: u32 = (1 << 24) - 1;
+
+fn rgba_to_argb(rgba: &[u8]) -> Vec<u32> {
+    rgba.chunks_exact(4).map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]])).collect()
+}
+
+fn write_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+fn write_u24(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes()[..3]);
+}
+
+fn riff(body: Vec<u8>) -> Vec<u8> {
+    let mut out = b"RIFF".to_vec();
+    out.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    out.extend_from_slice(b"WEBP");
+    out.extend_from_slice(&body);
+    out
+}
+
+fn check_size(width: u32, height: u32) -> Result<(), String> {
+    if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+        return Err(format!("WebP dimensions must be 1-{}, got {}x{}", MAX_SIZE, width, height));
+    }
+    Ok(())
+}
+
+/// Encode RGBA as a lossless WebP
+pub fn encode_webp(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+    check_rgba(data, width, height)?;
+    check_size(width, height)?;
+    let mut body = Vec::new();
+    write_chunk(&mut body, b"VP8L", &encode_vp8l(width, height, &rgba_to_argb(data)));
+    Ok(riff(body))
+}
+
+/// Encode a frame sequence as a lossless animated WebP
+///
+/// Each frame after the first stores only the rectangle that changed (its
+/// origin moved to even coordinates, as the format requires) and replaces
+/// the canvas there without blending.
+pub fn encode_webp_animation(sequence: &FrameSequence) -> Result<Vec<u8>, String> {
+    let (width, height) = (sequence.width(), sequence.height());
+    let frames = sequence.frames();
+    if frames.is_empty() {
+        return Err("Animation has no frames".to_string());
+    }
+    check_size(width, height)?;
+
+    let mut body = Vec::new();
+    let alpha = frames.iter().any(|f| f.data.chunks_exact(4).any(|p| p[3] != 255));
+    let mut vp8x = vec![0x02 | if alpha { 0x10 } else { 0 }, 0, 0, 0];
+    write_u24(&mut vp8x, width - 1);
+    write_u24(&mut vp8x, height - 1);
+    write_chunk(&mut body, b"VP8X", &vp8x);
+    let mut anim = vec![0; 4];
+    anim.extend_from_slice(&(sequence.loop_count.min(u16::MAX as u32) as u16).to_le_bytes());
+    write_chunk(&mut body, b"ANIM", &anim);
+
+    let full = Rect { x: 0, y: 0, width, height };
+    for (i, frame) in frames.iter().enumerate() {
+        let rect = match i {
+            0 => full,
+            _ => {
+                let Rect { x, y, width: w, height: h } = changed_rect(&frames[i - 1].data, &frame.data, width).unwrap_or(Rect { width: 1, height: 1, ..full });
+                Rect { x: x & !1, y: y & !1, width: w + (x & 1), height: h + (y & 1) }
+            }
+        };
+        let pixels = crop(&frame.data, width, height, CropRegion { x: rect.x, y: rect.y, width: rect.width, height: rect.height })?;
+
+        let mut anmf = Vec::new();
+        write_u24(&mut anmf, rect.x / 2);
+        write_u24(&mut anmf, rect.y / 2);
+        write_u24(&mut anmf, rect.width - 1);
+        write_u24(&mut anmf, rect.height - 1);
+        write_u24(&mut anmf, frame.duration.min(MAX_DURATION));
+        // Do not blend, do not dispose
+        anmf.push(0x02);
+        write_chunk(&mut anmf, b"VP8L", &encode_vp8l(rect.width, rect.height, &rgba_to_argb(&pixels)));
+        write_chunk(&mut body, b"ANMF", &anmf);
+    }
+    Ok(riff(body))
+}
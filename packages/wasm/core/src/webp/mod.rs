@@ -0,0 +1,96 @@
+//! WebP codec implementation in pure Rust
+//!
+//! Decodes lossy (VP8) and lossless (VP8L) images, alpha and animation;
+//! encodes lossless only.
+
+mod decoder;
+mod encoder;
+mod vp8;
+mod vp8l;
+mod vp8l_encoder;
+
+pub use decoder::{decode_webp, decode_webp_animation};
+pub use encoder::{encode_webp, encode_webp_animation};
+
+#[cfg(feature = "wasm")]
+use crate::animation::FrameSequence;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Decode WebP to RGBA
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeWebp)]
+pub fn decode_webp_js(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    decode_webp(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode RGBA to lossless WebP
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeWebp)]
+pub fn encode_webp_js(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, JsError> {
+    encode_webp(width, height, data).map_err(|e| JsError::new(&e))
+}
+
+/// Decode an animated (or still) WebP to composited frames
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = decodeWebpAnimation)]
+pub fn decode_webp_animation_js(data: &[u8]) -> Result<FrameSequence, JsError> {
+    decode_webp_animation(data).map_err(|e| JsError::new(&e))
+}
+
+/// Encode frames as lossless animated WebP
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = encodeWebpAnimation)]
+pub fn encode_webp_animation_js(sequence: &FrameSequence) -> Result<Vec<u8>, JsError> {
+    encode_webp_animation(sequence).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::FrameSequence;
+    use crate::convert::unpack;
+
+    fn gradient(width: u32, height: u32) -> Vec<u8> {
+        (0..width * height).flat_map(|i| [(i * 7) as u8, (i * 13 / width) as u8, (i % width * 31) as u8, if i % 5 == 0 { 128 } else { 255 }]).collect()
+    }
+
+    #[test]
+    fn test_lossless_roundtrip() {
+        // Many colors take the predictor path, few the palette path
+        for (width, height, data) in [(37, 11, gradient(37, 11)), (5, 3, [9, 8, 7, 255, 0, 0, 0, 0, 255, 255, 255, 255].repeat(5))] {
+            let webp = encode_webp(width, height, &data).unwrap();
+            let image = unpack(decode_webp(&webp).unwrap());
+            assert_eq!((image.width, image.height), (width, height));
+            assert_eq!(image.data, data);
+        }
+        assert!(encode_webp(0, 1, &[]).is_err());
+        assert!(decode_webp(b"RIFF\x04\0\0\0WEBP").is_err());
+    }
+
+    #[test]
+    fn test_animation_roundtrip() {
+        let mut sequence = FrameSequence::new(9, 7);
+        let first = gradient(9, 7);
+        let mut second = first.clone();
+        second[(3 * 9 + 5) * 4..(3 * 9 + 5) * 4 + 4].copy_from_slice(&[1, 2, 3, 4]);
+        sequence.push(first, 50).unwrap();
+        sequence.push(second.clone(), 20).unwrap();
+        sequence.push(second, 30).unwrap();
+        sequence.loop_count = 2;
+        let webp = encode_webp_animation(&sequence).unwrap();
+        assert_eq!(decode_webp_animation(&webp).unwrap(), sequence);
+    }
+
+    #[test]
+    fn test_decode_lossy() {
+        // 4x4 stripes (two columns red, two blue) encoded by libwebp at quality 90
+        let webp = b"RIFF\x5c\0\0\0WEBPVP8 \x50\0\0\0\x70\x03\0\x9d\x01\x2a\x04\0\x04\0\0\xc0\x12\x25\xa8\x02\x74\xba\x01\x40\x03\xf0\x02\xb4\x03\xf8\x06\x50\x07\xe8\0\x2d\xda\xcb\xa8\x80\0\xfe\xf9\x60\x21\xf9\x77\xff\xbf\x8e\x0d\x5a\x96\xff\xff\x61\x9f\xff\x7d\x52\xae\x5a\xc1\xbf\xfb\x0c\xff\xee\xb4\xd6\xf5\xe5\xff\xda\x7a\xb9\xa5\x0e\x52\x87\xfd\xa2\0\0";
+        let image = unpack(decode_webp(webp).unwrap());
+        assert_eq!((image.width, image.height), (4, 4));
+        // Chroma is upsampled, so the middle columns blend (as libwebp decodes them)
+        let row = [199, 62, 38, 255, 158, 71, 87, 255, 69, 81, 175, 255, 28, 91, 224, 255];
+        assert!(image.data.chunks_exact(16).all(|r| r == row));
+        assert!(decode_webp(&webp[..40]).is_err());
+    }
+}
@@ -0,0 +1,1010 @@
+//! VP8 (lossy WebP) key frame decoder
+//!
+//! Follows RFC 6386 for the bitstream, with libwebp's reconstruction, loop
+//! filter and fancy chroma upsampling so output matches common decoders.
+
+use crate::limits::Budget;
+use crate::pool::{self, Scratch};
+use crate::utils::read_u16_le;
+
+const DC_PRED: u8 = 0;
+const V_PRED: u8 = 1;
+const H_PRED: u8 = 2;
+const B_PRED: u8 = 4;
+
+const B_DC_PRED: u8 = 0;
+const B_TM_PRED: u8 = 1;
+const B_VE_PRED: u8 = 2;
+const B_HE_PRED: u8 = 3;
+const B_LD_PRED: u8 = 4;
+const B_RD_PRED: u8 = 5;
+const B_VR_PRED: u8 = 6;
+const B_VL_PRED: u8 = 7;
+const B_HD_PRED: u8 = 8;
+
+// Trees as in the RFC: positive entries index the next node pair, others
+// are negated leaf values (the mode constants above)
+const SEGMENT_TREE: [i8; 6] = [2, 4, -0, -1, -2, -3];
+const YMODE_TREE: [i8; 8] = [-4, 2, 4, 6, -0, -1, -2, -3];
+const YMODE_PROBS: [u8; 4] = [145, 156, 163, 128];
+const UV_MODE_TREE: [i8; 6] = [-0, 2, -1, 4, -2, -3];
+const UV_MODE_PROBS: [u8; 3] = [142, 114, 183];
+const BMODE_TREE: [i8; 18] = [-0, 2, -1, 4, -2, 6, 8, 12, -3, 10, -5, -6, -4, 14, -7, 16, -8, -9];
+
+const COEFF_BANDS: [usize; 16] = [0, 1, 2, 3, 6, 4, 5, 6, 6, 6, 6, 6, 6, 6, 6, 7];
+const ZIGZAG: [usize; 16] = [0, 1, 4, 8, 5, 2, 3, 6, 9, 12, 13, 10, 7, 11, 14, 15];
+/// Extra bit probabilities of token categories 3 to 6
+const CATEGORY_PROBS: [&[u8]; 4] = [&[173, 148, 140], &[176, 155, 140, 135], &[180, 157, 141, 134, 130], &[254, 254, 243, 230, 196, 177, 153, 140, 133, 130, 129]];
+
+
+/// Boolean entropy decoder (RFC 6386 section 7); reads past the end as zeros
+struct BoolDecoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    value: u32,
+    range: u32,
+    bit_count: u32,
+}
+
+impl<'a> BoolDecoder<'a> {
+    fn new(data: &'a [u8]) -> BoolDecoder<'a> {
+        let mut decoder = BoolDecoder { data, pos: 0, value: 0, range: 255, bit_count: 0 };
+        decoder.value = decoder.next_byte() << 8 | decoder.next_byte();
+        decoder
+    }
+
+    fn next_byte(&mut self) -> u32 {
+        let byte = self.data.get(self.pos).copied().unwrap_or(0);
+        self.pos += 1;
+        byte as u32
+    }
+
+    fn read(&mut self, prob: u8) -> bool {
+        let split = 1 + (((self.range - 1) * prob as u32) >> 8);
+        let big_split = split << 8;
+        let bit = self.value >= big_split;
+        if bit {
+            self.range -= split;
+            self.value -= big_split;
+        } else {
+            self.range = split;
+        }
+        while self.range < 128 {
+            self.value <<= 1;
+            self.range <<= 1;
+            self.bit_count += 1;
+            if self.bit_count == 8 {
+                self.bit_count = 0;
+                self.value |= self.next_byte();
+            }
+        }
+        bit
+    }
+
+    fn flag(&mut self) -> bool {
+        self.read(128)
+    }
+
+    fn literal(&mut self, bits: u32) -> u32 {
+        (0..bits).fold(0, |v, _| v << 1 | self.flag() as u32)
+    }
+
+    /// A magnitude followed by a sign bit, if present
+    fn optional_signed(&mut self, bits: u32) -> i32 {
+        if !self.flag() {
+            return 0;
+        }
+        let value = self.literal(bits) as i32;
+        if self.flag() {
+            -value
+        } else {
+            value
+        }
+    }
+
+    fn tree(&mut self, tree: &[i8], probs: &[u8]) -> u8 {
+        let mut i = 0;
+        loop {
+            let next = tree[i + self.read(probs[i >> 1]) as usize];
+            if next <= 0 {
+                return -next as u8;
+            }
+            i = next as usize;
+        }
+    }
+}
+
+/// Dequantization factors for DC and AC coefficients
+#[derive(Clone, Copy, Default)]
+struct Quant {
+    y: [i32; 2],
+    y2: [i32; 2],
+    uv: [i32; 2],
+}
+
+/// Loop filter thresholds for one macroblock
+#[derive(Clone, Copy)]
+struct FilterLimits {
+    /// Edge limit for inner edges; macroblock edges use 4 more
+    limit: i32,
+    interior: i32,
+    hev: i32,
+}
+
+/// Everything the frame header says about decoding macroblocks
+struct Header {
+    segment_map: bool,
+    segment_probs: [u8; 3],
+    quant: [Quant; 4],
+    /// Per segment, for other modes and for B_PRED; `None` when unfiltered
+    filters: [[Option<FilterLimits>; 2]; 4],
+    simple_filter: bool,
+    coeff_probs: [[[[u8; 11]; 3]; 8]; 4],
+    skip_prob: Option<u8>,
+}
+
+impl Header {
+    fn read(d: &mut BoolDecoder) -> Result<(Header, u32), String> {
+        // Color space and clamping type
+        d.literal(2);
+
+        let (mut segment_map, mut absolute) = (false, false);
+        let (mut quantizers, mut levels) = ([0i32; 4], [0i32; 4]);
+        let mut segment_probs = [255u8; 3];
+        let segmentation = d.flag();
+        if segmentation {
+            segment_map = d.flag();
+            if d.flag() {
+                absolute = d.flag();
+                quantizers = [0; 4].map(|_| d.optional_signed(7));
+                levels = [0; 4].map(|_| d.optional_signed(6));
+            }
+            if segment_map {
+                segment_probs = [0; 3].map(|_| if d.flag() { d.literal(8) as u8 } else { 255 });
+            }
+        }
+
+        let simple_filter = d.flag();
+        let frame_level = d.literal(6) as i32;
+        let sharpness = d.literal(3) as i32;
+        // Deltas for the intra reference frame and for B_PRED
+        let (mut ref_delta, mut mode_delta) = (0, 0);
+        let deltas = d.flag();
+        if deltas && d.flag() {
+            let refs = [0; 4].map(|_| d.optional_signed(6));
+            let modes = [0; 4].map(|_| d.optional_signed(6));
+            (ref_delta, mode_delta) = (refs[0], modes[0]);
+        }
+
+        let partitions = 1 << d.literal(2);
+
+        let base = d.literal(7) as i32;
+        let [y_dc, y2_dc, y2_ac, uv_dc, uv_ac] = [0; 5].map(|_| d.optional_signed(4));
+        let quant = std::array::from_fn(|s| {
+            let q = match (segmentation, absolute) {
+                (true, true) => quantizers[s],
+                (true, false) => base + quantizers[s],
+                _ => base,
+            };
+            let table = |table: &[i32; 128], delta: i32, max: i32| table[(q + delta).clamp(0, max) as usize];
+            Quant {
+                y: [table(&DC_QUANT, y_dc, 127), table(&AC_QUANT, 0, 127)],
+                y2: [table(&DC_QUANT, y2_dc, 127) * 2, ((table(&AC_QUANT, y2_ac, 127) * 101581) >> 16).max(8)],
+                uv: [table(&DC_QUANT, uv_dc, 117), table(&AC_QUANT, uv_ac, 127)],
+            }
+        });
+
+        let filters = std::array::from_fn(|s| {
+            let base = match (segmentation, absolute) {
+                (true, true) => levels[s],
+                (true, false) => frame_level + levels[s],
+                _ => frame_level,
+            };
+            std::array::from_fn(|b_pred| {
+                let mut level = base;
+                if deltas {
+                    level += ref_delta + if b_pred == 1 { mode_delta } else { 0 };
+                }
+                let level = level.clamp(0, 63);
+                // A zero frame level turns the filter off whatever the segments say
+                if level == 0 || frame_level == 0 {
+                    return None;
+                }
+                let mut interior = level;
+                if sharpness > 0 {
+                    interior >>= if sharpness > 4 { 2 } else { 1 };
+                    interior = interior.min(9 - sharpness);
+                }
+                let interior = interior.max(1);
+                let hev = match level {
+                    40.. => 2,
+                    15.. => 1,
+                    _ => 0,
+                };
+                Some(FilterLimits { limit: 2 * level + interior, interior, hev })
+            })
+        });
+
+        // Refresh entropy probabilities: irrelevant for a single key frame
+        d.flag();
+        let mut coeff_probs = DEFAULT_COEFF_PROBS;
+        for (t, types) in coeff_probs.iter_mut().enumerate() {
+            for (b, bands) in types.iter_mut().enumerate() {
+                for (c, contexts) in bands.iter_mut().enumerate() {
+                    for (p, prob) in contexts.iter_mut().enumerate() {
+                        if d.read(COEFF_UPDATE_PROBS[t][b][c][p]) {
+                            *prob = d.literal(8) as u8;
+                        }
+                    }
+                }
+            }
+        }
+        let skip_prob = d.flag().then(|| d.literal(8) as u8);
+
+        let header = Header { segment_map, segment_probs, quant, filters, simple_filter, coeff_probs, skip_prob };
+        Ok((header, partitions))
+    }
+}
+
+/// Prediction modes and residuals of one macroblock
+struct Macroblock {
+    segment: usize,
+    luma: u8,
+    /// Subblock modes, also implied for whole-block luma modes as context
+    sub: [u8; 16],
+    chroma: u8,
+    /// Dequantized coefficients: 16 luma blocks, then 4 U and 4 V
+    coeffs: [i32; 24 * 16],
+    /// Whether any coefficient is non-zero, which enables inner edge filtering
+    residual: bool,
+}
+
+/// Per-column (above) or per-row (left) prediction context
+#[derive(Clone, Copy, Default)]
+struct Context {
+    sub: [u8; 4],
+    /// Non-zero flags: 4 luma, 2 U, 2 V, then Y2
+    nz: [bool; 9],
+}
+
+fn read_modes(d: &mut BoolDecoder, header: &Header, above: &mut Context, left: &mut Context) -> (Macroblock, bool) {
+    let segment = if header.segment_map { d.tree(&SEGMENT_TREE, &header.segment_probs) as usize } else { 0 };
+    let skip = header.skip_prob.is_some_and(|p| d.read(p));
+    let luma = d.tree(&YMODE_TREE, &YMODE_PROBS);
+    let sub = if luma == B_PRED {
+        let mut sub = [0; 16];
+        for i in 0..16 {
+            let a = if i < 4 { above.sub[i] } else { sub[i - 4] };
+            let l = if i % 4 == 0 { left.sub[i / 4] } else { sub[i - 1] };
+            sub[i] = d.tree(&BMODE_TREE, &BMODE_PROBS[a as usize][l as usize]);
+        }
+        sub
+    } else {
+        [[B_DC_PRED, B_VE_PRED, B_HE_PRED, B_TM_PRED][luma as usize]; 16]
+    };
+    above.sub.copy_from_slice(&sub[12..]);
+    left.sub = [sub[3], sub[7], sub[11], sub[15]];
+    let chroma = d.tree(&UV_MODE_TREE, &UV_MODE_PROBS);
+    (Macroblock { segment, luma, sub, chroma, coeffs: [0; 24 * 16], residual: false }, skip)
+}
+
+fn large_value(d: &mut BoolDecoder, p: &[u8; 11]) -> i32 {
+    if !d.read(p[3]) {
+        if !d.read(p[4]) {
+            2
+        } else {
+            3 + d.read(p[5]) as i32
+        }
+    } else if !d.read(p[6]) {
+        if !d.read(p[7]) {
+            5 + d.read(159) as i32
+        } else {
+            let high = d.read(165) as i32;
+            7 + 2 * high + d.read(145) as i32
+        }
+    } else {
+        let high = d.read(p[8]) as usize;
+        let category = 2 * high + d.read(p[9 + high]) as usize;
+        let extra = CATEGORY_PROBS[category].iter().fold(0, |v, &prob| 2 * v + d.read(prob) as i32);
+        3 + (8 << category) + extra
+    }
+}
+
+/// Read one block's tokens from position `first`, returning where they ended
+fn read_block(d: &mut BoolDecoder, probs: &[[[u8; 11]; 3]; 8], context: usize, first: usize, quant: [i32; 2], out: &mut [i32]) -> usize {
+    let mut i = first;
+    let mut p = &probs[COEFF_BANDS[i]][context];
+    while i < 16 {
+        if !d.read(p[0]) {
+            return i;
+        }
+        // A zero is never followed by the end of block
+        while !d.read(p[1]) {
+            i += 1;
+            if i == 16 {
+                return 16;
+            }
+            p = &probs[COEFF_BANDS[i]][0];
+        }
+        let (value, next) = if d.read(p[2]) { (large_value(d, p), 2) } else { (1, 1) };
+        let value = if d.flag() { -value } else { value };
+        out[ZIGZAG[i]] = value * quant[(i > 0) as usize];
+        i += 1;
+        if i < 16 {
+            p = &probs[COEFF_BANDS[i]][next];
+        }
+    }
+    16
+}
+
+/// Inverse Walsh-Hadamard transform of the Y2 block into the luma DC terms
+fn inverse_wht(input: &[i32; 16], coeffs: &mut [i32]) {
+    let mut tmp = [0; 16];
+    for i in 0..4 {
+        let (a0, a1) = (input[i] + input[12 + i], input[4 + i] + input[8 + i]);
+        let (a2, a3) = (input[4 + i] - input[8 + i], input[i] - input[12 + i]);
+        (tmp[i], tmp[4 + i], tmp[8 + i], tmp[12 + i]) = (a0 + a1, a3 + a2, a0 - a1, a3 - a2);
+    }
+    for (i, row) in tmp.chunks_exact(4).enumerate() {
+        let dc = row[0] + 3;
+        let (a0, a1, a2, a3) = (dc + row[3], row[1] + row[2], row[1] - row[2], dc - row[3]);
+        for (j, v) in [a0 + a1, a3 + a2, a0 - a1, a3 - a2].into_iter().enumerate() {
+            coeffs[(i * 4 + j) * 16] = v >> 3;
+        }
+    }
+}
+
+fn read_residuals(d: &mut BoolDecoder, header: &Header, mb: &mut Macroblock, above: &mut Context, left: &mut Context) {
+    let probs = &header.coeff_probs;
+    let quant = header.quant[mb.segment];
+    let (first, luma_type) = if mb.luma == B_PRED {
+        (0, 3)
+    } else {
+        let mut y2 = [0; 16];
+        let end = read_block(d, &probs[1], above.nz[8] as usize + left.nz[8] as usize, 0, quant.y2, &mut y2);
+        (above.nz[8], left.nz[8]) = (end > 0, end > 0);
+        inverse_wht(&y2, &mut mb.coeffs);
+        (1, 0)
+    };
+
+    for (i, block) in mb.coeffs.chunks_exact_mut(16).enumerate() {
+        let (probs, quant, first, a, l) = match i {
+            0..16 => (&probs[luma_type], quant.y, first, i % 4, i / 4),
+            // U then V, each 2x2 blocks with their own context slots
+            _ => (&probs[2], quant.uv, 0, 4 + (i - 16) / 4 * 2 + i % 2, 4 + (i - 16) / 4 * 2 + (i - 16) % 4 / 2),
+        };
+        let end = read_block(d, probs, above.nz[a] as usize + left.nz[l] as usize, first, quant, block);
+        (above.nz[a], left.nz[l]) = (end > first, end > first);
+    }
+    mb.residual = mb.coeffs.iter().any(|&c| c != 0);
+}
+
+/// Inverse DCT of one block, added onto the prediction at `at`
+fn idct_add(coeffs: &[i32], out: &mut [u8], at: usize, stride: usize) {
+    let mul = |a: i32, c: i64| ((a as i64 * c) >> 16) as i32;
+    let (c1, c2) = (20091 + (1 << 16), 35468);
+    let mut tmp = [0; 16];
+    for i in 0..4 {
+        let (a, b) = (coeffs[i] + coeffs[8 + i], coeffs[i] - coeffs[8 + i]);
+        let c = mul(coeffs[4 + i], c2) - mul(coeffs[12 + i], c1);
+        let d = mul(coeffs[4 + i], c1) + mul(coeffs[12 + i], c2);
+        tmp[i * 4..i * 4 + 4].copy_from_slice(&[a + d, b + c, b - c, a - d]);
+    }
+    for i in 0..4 {
+        let dc = tmp[i] + 4;
+        let (a, b) = (dc + tmp[8 + i], dc - tmp[8 + i]);
+        let c = mul(tmp[4 + i], c2) - mul(tmp[12 + i], c1);
+        let d = mul(tmp[4 + i], c1) + mul(tmp[12 + i], c2);
+        let row = &mut out[at + i * stride..at + i * stride + 4];
+        for (pixel, v) in row.iter_mut().zip([a + d, b + c, b - c, a - d]) {
+            *pixel = (*pixel as i32 + (v >> 3)).clamp(0, 255) as u8;
+        }
+    }
+}
+
+fn avg2(a: u8, b: u8) -> u8 {
+    ((a as u16 + b as u16 + 1) >> 1) as u8
+}
+
+fn avg3(a: u8, b: u8, c: u8) -> u8 {
+    ((a as u16 + 2 * b as u16 + c as u16 + 2) >> 2) as u8
+}
+
+/// Whole-block prediction of the `size` square at (1, 1) of a bordered workspace
+fn predict_block(ws: &mut [u8], stride: usize, size: usize, mode: u8, has_above: bool, has_left: bool) {
+    let above: Vec<u8> = ws[1..1 + size].to_vec();
+    let left: Vec<u8> = (1..=size).map(|y| ws[y * stride]).collect();
+    let corner = ws[0] as i32;
+    let shift = size.trailing_zeros();
+    let sum = |v: &[u8]| v.iter().map(|&p| p as u32).sum::<u32>();
+    let dc = match (has_above, has_left) {
+        (true, true) => (sum(&above) + sum(&left) + size as u32) >> (shift + 1),
+        (true, false) => (sum(&above) + size as u32 / 2) >> shift,
+        (false, true) => (sum(&left) + size as u32 / 2) >> shift,
+        (false, false) => 128,
+    } as u8;
+    for y in 0..size {
+        let row = &mut ws[(y + 1) * stride + 1..(y + 1) * stride + 1 + size];
+        match mode {
+            DC_PRED => row.fill(dc),
+            V_PRED => row.copy_from_slice(&above),
+            H_PRED => row.fill(left[y]),
+            // TM_PRED
+            _ => {
+                for (pixel, &a) in row.iter_mut().zip(&above) {
+                    *pixel = (left[y] as i32 + a as i32 - corner).clamp(0, 255) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// 4x4 subblock prediction at `at`, from the row above (with four more to the
+/// right), the column to the left and the corner
+fn predict_subblock(ws: &mut [u8], at: usize, stride: usize, mode: u8) {
+    let x = ws[at - stride - 1];
+    let t: [u8; 8] = std::array::from_fn(|i| ws[at - stride + i]);
+    let l: [u8; 4] = std::array::from_fn(|i| ws[at + i * stride - 1]);
+    let [a, b, c, d, e, f, g, h] = t;
+    let [i, j, k, m] = l;
+    let block: [u8; 16] = match mode {
+        B_DC_PRED => [((t[..4].iter().chain(&l).map(|&p| p as u32).sum::<u32>() + 4) >> 3) as u8; 16],
+        B_TM_PRED => std::array::from_fn(|n| (l[n / 4] as i32 + t[n % 4] as i32 - x as i32).clamp(0, 255) as u8),
+        B_VE_PRED => {
+            let row = [avg3(x, a, b), avg3(a, b, c), avg3(b, c, d), avg3(c, d, e)];
+            std::array::from_fn(|n| row[n % 4])
+        }
+        B_HE_PRED => {
+            let column = [avg3(x, i, j), avg3(i, j, k), avg3(j, k, m), avg3(k, m, m)];
+            std::array::from_fn(|n| column[n / 4])
+        }
+        B_LD_PRED => std::array::from_fn(|n| {
+            let s = n % 4 + n / 4;
+            if s < 6 {
+                avg3(t[s], t[s + 1], t[s + 2])
+            } else {
+                avg3(g, h, h)
+            }
+        }),
+        B_RD_PRED => {
+            let edge = [m, k, j, i, x, a, b, c, d];
+            std::array::from_fn(|n| {
+                let s = 3 + n % 4 - n / 4;
+                avg3(edge[s], edge[s + 1], edge[s + 2])
+            })
+        }
+        B_VR_PRED => [
+            avg2(x, a), avg2(a, b), avg2(b, c), avg2(c, d),
+            avg3(i, x, a), avg3(x, a, b), avg3(a, b, c), avg3(b, c, d),
+            avg3(j, i, x), avg2(x, a), avg2(a, b), avg2(b, c),
+            avg3(k, j, i), avg3(i, x, a), avg3(x, a, b), avg3(a, b, c),
+        ],
+        B_VL_PRED => [
+            avg2(a, b), avg2(b, c), avg2(c, d), avg2(d, e),
+            avg3(a, b, c), avg3(b, c, d), avg3(c, d, e), avg3(d, e, f),
+            avg2(b, c), avg2(c, d), avg2(d, e), avg3(e, f, g),
+            avg3(b, c, d), avg3(c, d, e), avg3(d, e, f), avg3(f, g, h),
+        ],
+        B_HD_PRED => [
+            avg2(i, x), avg3(i, x, a), avg3(x, a, b), avg3(a, b, c),
+            avg2(j, i), avg3(j, i, x), avg2(i, x), avg3(i, x, a),
+            avg2(k, j), avg3(k, j, i), avg2(j, i), avg3(j, i, x),
+            avg2(m, k), avg3(m, k, j), avg2(k, j), avg3(k, j, i),
+        ],
+        _ => [
+            avg2(i, j), avg3(i, j, k), avg2(j, k), avg3(j, k, m),
+            avg2(j, k), avg3(j, k, m), avg2(k, m), avg3(k, m, m),
+            avg2(k, m), avg3(k, m, m), m, m,
+            m, m, m, m,
+        ],
+    };
+    for (y, row) in block.chunks_exact(4).enumerate() {
+        ws[at + y * stride..at + y * stride + 4].copy_from_slice(row);
+    }
+}
+
+/// Decoded planes padded to whole macroblocks
+struct Planes {
+    mb_width: usize,
+    y: Scratch<u8>,
+    u: Scratch<u8>,
+    v: Scratch<u8>,
+}
+
+impl Planes {
+    /// Copy the `size` square at (x, y) of `plane` into a workspace with a one-pixel
+    /// border above and left (plus `extra` pixels above-right), using libwebp's
+    /// edge values: 127 above the frame, 129 left of it
+    fn workspace(plane: &[u8], stride: usize, x: usize, y: usize, size: usize, extra: usize) -> Scratch<u8> {
+        let ws_stride = 1 + size + extra;
+        let mut ws = pool::take(ws_stride * (size + 1), 0u8);
+        if y == 0 {
+            ws[..ws_stride].fill(127);
+        } else {
+            let above = (y - 1) * stride + x;
+            ws[0] = if x == 0 { 129 } else { plane[above - 1] };
+            ws[1..1 + size].copy_from_slice(&plane[above..above + size]);
+            for i in 0..extra {
+                // Past the right edge the last pixel above repeats
+                ws[1 + size + i] = if x + size < stride { plane[above + size + i] } else { plane[above + size - 1] };
+            }
+        }
+        for row in 0..size {
+            ws[(row + 1) * ws_stride] = if x == 0 { 129 } else { plane[(y + row) * stride + x - 1] };
+        }
+        ws
+    }
+
+    fn store(plane: &mut [u8], stride: usize, x: usize, y: usize, size: usize, ws: &[u8]) {
+        let ws_stride = ws.len() / (size + 1);
+        for row in 0..size {
+            let at = (y + row) * stride + x;
+            plane[at..at + size].copy_from_slice(&ws[(row + 1) * ws_stride + 1..(row + 1) * ws_stride + 1 + size]);
+        }
+    }
+
+    fn reconstruct(&mut self, mbx: usize, mby: usize, mb: &Macroblock) {
+        const S: usize = 21;
+        let stride = self.mb_width * 16;
+        let (x, y) = (mbx * 16, mby * 16);
+        let mut ws = Planes::workspace(&self.y, stride, x, y, 16, 4);
+        // Subblocks on the right use the macroblock's above-right pixels on every row
+        for row in [4, 8, 12] {
+            ws.copy_within(17..21, row * S + 17);
+        }
+        if mb.luma != B_PRED {
+            predict_block(&mut ws, S, 16, mb.luma, y > 0, x > 0);
+        }
+        for (i, block) in mb.coeffs[..256].chunks_exact(16).enumerate() {
+            let at = (1 + i / 4 * 4) * S + 1 + i % 4 * 4;
+            if mb.luma == B_PRED {
+                predict_subblock(&mut ws, at, S, mb.sub[i]);
+            }
+            if block.iter().any(|&c| c != 0) {
+                idct_add(block, &mut ws, at, S);
+            }
+        }
+        Planes::store(&mut self.y, stride, x, y, 16, &ws);
+
+        let (x, y) = (mbx * 8, mby * 8);
+        for (plane, coeffs) in [&mut self.u, &mut self.v].into_iter().zip(mb.coeffs[256..].chunks_exact(64)) {
+            let mut ws = Planes::workspace(plane, stride / 2, x, y, 8, 0);
+            predict_block(&mut ws, 9, 8, mb.chroma, y > 0, x > 0);
+            for (i, block) in coeffs.chunks_exact(16).enumerate() {
+                if block.iter().any(|&c| c != 0) {
+                    idct_add(block, &mut ws, (1 + i / 2 * 4) * 9 + 1 + i % 2 * 4, 9);
+                }
+            }
+            Planes::store(plane, stride / 2, x, y, 8, &ws);
+        }
+    }
+}
+
+/// Which filter runs across an edge
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EdgeKind {
+    Simple,
+    Macroblock,
+    Inner,
+}
+
+fn filter_common(b: &mut [u8], p: usize, step: usize, use_outer_taps: bool) {
+    let (p1, p0, q0, q1) = (b[p - 2 * step] as i32, b[p - step] as i32, b[p] as i32, b[p + step] as i32);
+    let outer = if use_outer_taps { (p1 - q1).clamp(-128, 127) } else { 0 };
+    let a = 3 * (q0 - p0) + outer;
+    let a1 = ((a + 4) >> 3).clamp(-16, 15);
+    let a2 = ((a + 3) >> 3).clamp(-16, 15);
+    b[p - step] = (p0 + a2).clamp(0, 255) as u8;
+    b[p] = (q0 - a1).clamp(0, 255) as u8;
+    if !use_outer_taps {
+        let a3 = (a1 + 1) >> 1;
+        b[p - 2 * step] = (p1 + a3).clamp(0, 255) as u8;
+        b[p + step] = (q1 - a3).clamp(0, 255) as u8;
+    }
+}
+
+fn filter_macroblock(b: &mut [u8], p: usize, step: usize) {
+    let [p2, p1, p0, q0, q1, q2] = [3, 2, 1, 0, -1, -2].map(|k: isize| b[(p as isize - k * step as isize) as usize] as i32);
+    let a = (3 * (q0 - p0) + (p1 - q1).clamp(-128, 127)).clamp(-128, 127);
+    let (a1, a2, a3) = ((27 * a + 63) >> 7, (18 * a + 63) >> 7, (9 * a + 63) >> 7);
+    for (k, v) in [(3, p2 + a3), (2, p1 + a2), (1, p0 + a1)] {
+        b[p - k * step] = v.clamp(0, 255) as u8;
+    }
+    for (k, v) in [(0, q0 - a1), (1, q1 - a2), (2, q2 - a3)] {
+        b[p + k * step] = v.clamp(0, 255) as u8;
+    }
+}
+
+/// Filter `count` pixels along an edge at `start`, `step` crossing the edge
+/// and `along` moving down it
+fn filter_edge(b: &mut [u8], start: usize, step: usize, along: usize, count: usize, limits: FilterLimits, kind: EdgeKind) {
+    let limit = if kind == EdgeKind::Inner { limits.limit } else { limits.limit + 4 };
+    let threshold = 2 * limit + 1;
+    for n in 0..count {
+        let p = start + n * along;
+        let px = |k: isize| b[(p as isize + k * step as isize) as usize] as i32;
+        if 4 * (px(-1) - px(0)).abs() + (px(-2) - px(1)).abs() > threshold {
+            continue;
+        }
+        if kind == EdgeKind::Simple {
+            filter_common(b, p, step, true);
+            continue;
+        }
+        let interior = [(-4, -3), (-3, -2), (-2, -1), (3, 2), (2, 1), (1, 0)].iter().all(|&(i, j)| (px(i) - px(j)).abs() <= limits.interior);
+        if !interior {
+            continue;
+        }
+        let hev = (px(-2) - px(-1)).abs() > limits.hev || (px(1) - px(0)).abs() > limits.hev;
+        match (hev, kind) {
+            (true, _) => filter_common(b, p, step, true),
+            (false, EdgeKind::Macroblock) => filter_macroblock(b, p, step),
+            (false, _) => filter_common(b, p, step, false),
+        }
+    }
+}
+
+/// Loop-filter one macroblock's left, inner vertical, top and inner horizontal edges
+fn filter_macroblock_edges(plane: &mut [u8], stride: usize, x: usize, y: usize, size: usize, (limits, inner): (FilterLimits, bool), simple: bool) {
+    let origin = y * stride + x;
+    let (edge, inside) = if simple { (EdgeKind::Simple, EdgeKind::Simple) } else { (EdgeKind::Macroblock, EdgeKind::Inner) };
+    if x > 0 {
+        filter_edge(plane, origin, 1, stride, size, limits, edge);
+    }
+    if inner {
+        for dx in (4..size).step_by(4) {
+            filter_edge(plane, origin + dx, 1, stride, size, limits, inside);
+        }
+    }
+    if y > 0 {
+        filter_edge(plane, origin, stride, 1, size, limits, edge);
+    }
+    if inner {
+        for dy in (4..size).step_by(4) {
+            filter_edge(plane, origin + dy * stride, stride, 1, size, limits, inside);
+        }
+    }
+}
+
+/// BT.601 limited-range YUV to RGB with libwebp's fixed-point constants
+fn yuv_to_rgb(y: i32, u: i32, v: i32) -> [u8; 3] {
+    let mult = |value: i32, coeff: i32| (value * coeff) >> 8;
+    let clip = |value: i32| if value & !16383 == 0 { (value >> 6) as u8 } else if value < 0 { 0 } else { 255 };
+    let luma = mult(y, 19077);
+    [
+        clip(luma + mult(v, 26149) - 14234),
+        clip(luma - mult(u, 6419) - mult(v, 13320) + 8708),
+        clip(luma + mult(u, 33050) - 17685),
+    ]
+}
+
+/// Decode a VP8 key frame to RGBA (fully opaque)
+pub(super) fn decode_vp8(data: &[u8], budget: &mut Budget) -> Result<(u32, u32, Vec<u8>), String> {
+    if data.len() < 10 {
+        return Err("Truncated VP8 header".to_string());
+    }
+    let tag = u32::from_le_bytes([data[0], data[1], data[2], 0]);
+    if tag & 1 != 0 {
+        return Err("VP8 inter frames are not supported".to_string());
+    }
+    if data[3..6] != [0x9D, 0x01, 0x2A] {
+        return Err("Invalid VP8 start code".to_string());
+    }
+    // The top two bits of each size are an upscaling hint
+    let (width, height) = ((read_u16_le(data, 6) & 0x3FFF) as usize, (read_u16_le(data, 8) & 0x3FFF) as usize);
+    if width == 0 || height == 0 {
+        return Err("VP8 frame has zero width or height".to_string());
+    }
+    budget.check_size(width, height)?;
+    budget.rows(height)?;
+    let first_size = (tag >> 5) as usize;
+    let first = data.get(10..10 + first_size).ok_or("Truncated VP8 first partition")?;
+    let mut d = BoolDecoder::new(first);
+    let (header, count) = Header::read(&mut d)?;
+
+    let count = count as usize;
+    let mut pos = 10 + first_size;
+    let sizes = data.get(pos..pos + 3 * (count - 1)).ok_or("Truncated VP8 partition sizes")?;
+    pos += sizes.len();
+    let mut partitions = Vec::with_capacity(count);
+    for i in 0..count {
+        let size = if i + 1 < count { u32::from_le_bytes([sizes[i * 3], sizes[i * 3 + 1], sizes[i * 3 + 2], 0]) as usize } else { data.len().saturating_sub(pos) };
+        let partition = data.get(pos..pos + size).ok_or("Truncated VP8 partition")?;
+        partitions.push(BoolDecoder::new(partition));
+        pos += size;
+    }
+
+    let (mb_width, mb_height) = (width.div_ceil(16), height.div_ceil(16));
+    let mut planes = Planes {
+        mb_width,
+        y: pool::take(mb_width * mb_height * 256, 0),
+        u: pool::take(mb_width * mb_height * 64, 0),
+        v: pool::take(mb_width * mb_height * 64, 0),
+    };
+    let mut above = vec![Context::default(); mb_width];
+    let mut filters = Vec::with_capacity(mb_width * mb_height);
+    for mby in 0..mb_height {
+        let mut left = Context::default();
+        let partition = &mut partitions[mby % count];
+        for (mbx, above) in above.iter_mut().enumerate() {
+            let (mut mb, skip) = read_modes(&mut d, &header, above, &mut left);
+            if skip {
+                // Skipped macroblocks without Y2 leave its context alone
+                let y2 = (above.nz[8], left.nz[8]);
+                (above.nz, left.nz) = ([false; 9], [false; 9]);
+                if mb.luma == B_PRED {
+                    (above.nz[8], left.nz[8]) = y2;
+                }
+            } else {
+                read_residuals(partition, &header, &mut mb, above, &mut left);
+            }
+            planes.reconstruct(mbx, mby, &mb);
+            let b_pred = mb.luma == B_PRED;
+            filters.push(header.filters[mb.segment][b_pred as usize].map(|limits| (limits, b_pred || mb.residual)));
+        }
+    }
+
+    // Filtering follows reconstruction since prediction uses unfiltered pixels
+    let stride = mb_width * 16;
+    for (i, filter) in filters.into_iter().enumerate() {
+        let Some(filter) = filter else { continue };
+        let (mbx, mby) = (i % mb_width, i / mb_width);
+        filter_macroblock_edges(&mut planes.y, stride, mbx * 16, mby * 16, 16, filter, header.simple_filter);
+        if !header.simple_filter {
+            for plane in [&mut planes.u, &mut planes.v] {
+                filter_macroblock_edges(plane, stride / 2, mbx * 8, mby * 8, 8, filter, false);
+            }
+        }
+    }
+
+    // Fancy upsampling: each chroma sample weighs 9:3:3:1 with its neighbours
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    let neighbour = |n: usize, size: usize| if n % 2 == 1 { (n / 2 + 1).min(size - 1) } else { (n / 2).saturating_sub(1) };
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        let (near_y, far_y) = (y / 2, neighbour(y, chroma_height));
+        for x in 0..width {
+            let (near_x, far_x) = (x / 2, neighbour(x, chroma_width));
+            let sample = |plane: &[u8]| {
+                let at = |cx: usize, cy: usize| plane[cy * stride / 2 + cx] as i32;
+                (9 * at(near_x, near_y) + 3 * at(far_x, near_y) + 3 * at(near_x, far_y) + at(far_x, far_y) + 8) >> 4
+            };
+            let [r, g, b] = yuv_to_rgb(planes.y[y * stride + x] as i32, sample(&planes.u), sample(&planes.v));
+            rgba.extend_from_slice(&[r, g, b, 255]);
+        }
+    }
+    Ok((width as u32, height as u32, rgba))
+}
+
+/// Subblock mode probabilities indexed by the modes above and to the left
+const BMODE_PROBS: [[[u8; 9]; 10]; 10] = [
+    [
+        [231, 120, 48, 89, 115, 113, 120, 152, 112],
+        [152, 179, 64, 126, 170, 118, 46, 70, 95],
+        [175, 69, 143, 80, 85, 82, 72, 155, 103],
+        [56, 58, 10, 171, 218, 189, 17, 13, 152],
+        [144, 71, 10, 38, 171, 213, 144, 34, 26],
+        [114, 26, 17, 163, 44, 195, 21, 10, 173],
+        [121, 24, 80, 195, 26, 62, 44, 64, 85],
+        [170, 46, 55, 19, 136, 160, 33, 206, 71],
+        [63, 20, 8, 114, 114, 208, 12, 9, 226],
+        [81, 40, 11, 96, 182, 84, 29, 16, 36],
+    ],
+    [
+        [134, 183, 89, 137, 98, 101, 106, 165, 148],
+        [72, 187, 100, 130, 157, 111, 32, 75, 80],
+        [66, 102, 167, 99, 74, 62, 40, 234, 128],
+        [41, 53, 9, 178, 241, 141, 26, 8, 107],
+        [104, 79, 12, 27, 217, 255, 87, 17, 7],
+        [74, 43, 26, 146, 73, 166, 49, 23, 157],
+        [65, 38, 105, 160, 51, 52, 31, 115, 128],
+        [87, 68, 71, 44, 114, 51, 15, 186, 23],
+        [47, 41, 14, 110, 182, 183, 21, 17, 194],
+        [66, 45, 25, 102, 197, 189, 23, 18, 22],
+    ],
+    [
+        [88, 88, 147, 150, 42, 46, 45, 196, 205],
+        [43, 97, 183, 117, 85, 38, 35, 179, 61],
+        [39, 53, 200, 87, 26, 21, 43, 232, 171],
+        [56, 34, 51, 104, 114, 102, 29, 93, 77],
+        [107, 54, 32, 26, 51, 1, 81, 43, 31],
+        [39, 28, 85, 171, 58, 165, 90, 98, 64],
+        [34, 22, 116, 206, 23, 34, 43, 166, 73],
+        [68, 25, 106, 22, 64, 171, 36, 225, 114],
+        [34, 19, 21, 102, 132, 188, 16, 76, 124],
+        [62, 18, 78, 95, 85, 57, 50, 48, 51],
+    ],
+    [
+        [193, 101, 35, 159, 215, 111, 89, 46, 111],
+        [60, 148, 31, 172, 219, 228, 21, 18, 111],
+        [112, 113, 77, 85, 179, 255, 38, 120, 114],
+        [40, 42, 1, 196, 245, 209, 10, 25, 109],
+        [100, 80, 8, 43, 154, 1, 51, 26, 71],
+        [88, 43, 29, 140, 166, 213, 37, 43, 154],
+        [61, 63, 30, 155, 67, 45, 68, 1, 209],
+        [142, 78, 78, 16, 255, 128, 34, 197, 171],
+        [41, 40, 5, 102, 211, 183, 4, 1, 221],
+        [51, 50, 17, 168, 209, 192, 23, 25, 82],
+    ],
+    [
+        [125, 98, 42, 88, 104, 85, 117, 175, 82],
+        [95, 84, 53, 89, 128, 100, 113, 101, 45],
+        [75, 79, 123, 47, 51, 128, 81, 171, 1],
+        [57, 17, 5, 71, 102, 57, 53, 41, 49],
+        [115, 21, 2, 10, 102, 255, 166, 23, 6],
+        [38, 33, 13, 121, 57, 73, 26, 1, 85],
+        [41, 10, 67, 138, 77, 110, 90, 47, 114],
+        [101, 29, 16, 10, 85, 128, 101, 196, 26],
+        [57, 18, 10, 102, 102, 213, 34, 20, 43],
+        [117, 20, 15, 36, 163, 128, 68, 1, 26],
+    ],
+    [
+        [138, 31, 36, 171, 27, 166, 38, 44, 229],
+        [67, 87, 58, 169, 82, 115, 26, 59, 179],
+        [63, 59, 90, 180, 59, 166, 93, 73, 154],
+        [40, 40, 21, 116, 143, 209, 34, 39, 175],
+        [57, 46, 22, 24, 128, 1, 54, 17, 37],
+        [47, 15, 16, 183, 34, 223, 49, 45, 183],
+        [46, 17, 33, 183, 6, 98, 15, 32, 183],
+        [65, 32, 73, 115, 28, 128, 23, 128, 205],
+        [40, 3, 9, 115, 51, 192, 18, 6, 223],
+        [87, 37, 9, 115, 59, 77, 64, 21, 47],
+    ],
+    [
+        [104, 55, 44, 218, 9, 54, 53, 130, 226],
+        [64, 90, 70, 205, 40, 41, 23, 26, 57],
+        [54, 57, 112, 184, 5, 41, 38, 166, 213],
+        [30, 34, 26, 133, 152, 116, 10, 32, 134],
+        [75, 32, 12, 51, 192, 255, 160, 43, 51],
+        [39, 19, 53, 221, 26, 114, 32, 73, 255],
+        [31, 9, 65, 234, 2, 15, 1, 118, 73],
+        [88, 31, 35, 67, 102, 85, 55, 186, 85],
+        [56, 21, 23, 111, 59, 205, 45, 37, 192],
+        [55, 38, 70, 124, 73, 102, 1, 34, 98],
+    ],
+    [
+        [102, 61, 71, 37, 34, 53, 31, 243, 192],
+        [69, 60, 71, 38, 73, 119, 28, 222, 37],
+        [68, 45, 128, 34, 1, 47, 11, 245, 171],
+        [62, 17, 19, 70, 146, 85, 55, 62, 70],
+        [75, 15, 9, 9, 64, 255, 184, 119, 16],
+        [37, 43, 37, 154, 100, 163, 85, 160, 1],
+        [63, 9, 92, 136, 28, 64, 32, 201, 85],
+        [86, 6, 28, 5, 64, 255, 25, 248, 1],
+        [56, 8, 17, 132, 137, 255, 55, 116, 128],
+        [58, 15, 20, 82, 135, 57, 26, 121, 40],
+    ],
+    [
+        [164, 50, 31, 137, 154, 133, 25, 35, 218],
+        [51, 103, 44, 131, 131, 123, 31, 6, 158],
+        [86, 40, 64, 135, 148, 224, 45, 183, 128],
+        [22, 26, 17, 131, 240, 154, 14, 1, 209],
+        [83, 12, 13, 54, 192, 255, 68, 47, 28],
+        [45, 16, 21, 91, 64, 222, 7, 1, 197],
+        [56, 21, 39, 155, 60, 138, 23, 102, 213],
+        [85, 26, 85, 85, 128, 128, 32, 146, 171],
+        [18, 11, 7, 63, 144, 171, 4, 4, 246],
+        [35, 27, 10, 146, 174, 171, 12, 26, 128],
+    ],
+    [
+        [190, 80, 35, 99, 180, 80, 126, 54, 45],
+        [85, 126, 47, 87, 176, 51, 41, 20, 32],
+        [101, 75, 128, 139, 118, 146, 116, 128, 85],
+        [56, 41, 15, 176, 236, 85, 37, 9, 62],
+        [146, 36, 19, 30, 171, 255, 97, 27, 20],
+        [71, 30, 17, 119, 118, 255, 17, 18, 138],
+        [101, 38, 60, 138, 55, 70, 43, 26, 142],
+        [138, 45, 61, 62, 219, 1, 81, 188, 64],
+        [32, 41, 20, 117, 151, 142, 20, 21, 163],
+        [112, 19, 12, 61, 195, 128, 48, 4, 24],
+    ],
+];
+
+/// Probabilities that the frame header updates each token probability
+const COEFF_UPDATE_PROBS: [[[[u8; 11]; 3]; 8]; 4] = [
+    [
+        [[255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[176, 246, 255, 255, 255, 255, 255, 255, 255, 255, 255], [223, 241, 252, 255, 255, 255, 255, 255, 255, 255, 255], [249, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 244, 252, 255, 255, 255, 255, 255, 255, 255, 255], [234, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255], [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 246, 254, 255, 255, 255, 255, 255, 255, 255, 255], [239, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255], [254, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 248, 254, 255, 255, 255, 255, 255, 255, 255, 255], [251, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255], [251, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255], [254, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 254, 253, 255, 254, 255, 255, 255, 255, 255, 255], [250, 255, 254, 255, 254, 255, 255, 255, 255, 255, 255], [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+    ],
+    [
+        [[217, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [225, 252, 241, 253, 255, 255, 254, 255, 255, 255, 255], [234, 250, 241, 250, 253, 255, 253, 254, 255, 255, 255]],
+        [[255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255], [223, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255], [238, 253, 254, 254, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 248, 254, 255, 255, 255, 255, 255, 255, 255, 255], [249, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 253, 255, 255, 255, 255, 255, 255, 255, 255, 255], [247, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255], [252, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255], [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 254, 253, 255, 255, 255, 255, 255, 255, 255, 255], [250, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+    ],
+    [
+        [[186, 251, 250, 255, 255, 255, 255, 255, 255, 255, 255], [234, 251, 244, 254, 255, 255, 255, 255, 255, 255, 255], [251, 251, 243, 253, 254, 255, 254, 255, 255, 255, 255]],
+        [[255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255], [236, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255], [251, 253, 253, 254, 254, 255, 255, 255, 255, 255, 255]],
+        [[255, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255], [254, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255], [254, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255], [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+    ],
+    [
+        [[248, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [250, 254, 252, 254, 255, 255, 255, 255, 255, 255, 255], [248, 254, 249, 253, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255], [246, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255], [252, 254, 251, 254, 254, 255, 255, 255, 255, 255, 255]],
+        [[255, 254, 252, 255, 255, 255, 255, 255, 255, 255, 255], [248, 254, 253, 255, 255, 255, 255, 255, 255, 255, 255], [253, 255, 254, 254, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 251, 254, 255, 255, 255, 255, 255, 255, 255, 255], [245, 251, 254, 255, 255, 255, 255, 255, 255, 255, 255], [253, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 251, 253, 255, 255, 255, 255, 255, 255, 255, 255], [252, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255], [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 252, 255, 255, 255, 255, 255, 255, 255, 255, 255], [249, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 255, 253, 255, 255, 255, 255, 255, 255, 255, 255], [250, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+        [[255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255], [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]],
+    ],
+];
+
+/// Token probabilities by block type, band, context and tree node
+const DEFAULT_COEFF_PROBS: [[[[u8; 11]; 3]; 8]; 4] = [
+    [
+        [[128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128], [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128], [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128]],
+        [[253, 136, 254, 255, 228, 219, 128, 128, 128, 128, 128], [189, 129, 242, 255, 227, 213, 255, 219, 128, 128, 128], [106, 126, 227, 252, 214, 209, 255, 255, 128, 128, 128]],
+        [[1, 98, 248, 255, 236, 226, 255, 255, 128, 128, 128], [181, 133, 238, 254, 221, 234, 255, 154, 128, 128, 128], [78, 134, 202, 247, 198, 180, 255, 219, 128, 128, 128]],
+        [[1, 185, 249, 255, 243, 255, 128, 128, 128, 128, 128], [184, 150, 247, 255, 236, 224, 128, 128, 128, 128, 128], [77, 110, 216, 255, 236, 230, 128, 128, 128, 128, 128]],
+        [[1, 101, 251, 255, 241, 255, 128, 128, 128, 128, 128], [170, 139, 241, 252, 236, 209, 255, 255, 128, 128, 128], [37, 116, 196, 243, 228, 255, 255, 255, 128, 128, 128]],
+        [[1, 204, 254, 255, 245, 255, 128, 128, 128, 128, 128], [207, 160, 250, 255, 238, 128, 128, 128, 128, 128, 128], [102, 103, 231, 255, 211, 171, 128, 128, 128, 128, 128]],
+        [[1, 152, 252, 255, 240, 255, 128, 128, 128, 128, 128], [177, 135, 243, 255, 234, 225, 128, 128, 128, 128, 128], [80, 129, 211, 255, 194, 224, 128, 128, 128, 128, 128]],
+        [[1, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128], [246, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128], [255, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128]],
+    ],
+    [
+        [[198, 35, 237, 223, 193, 187, 162, 160, 145, 155, 62], [131, 45, 198, 221, 172, 176, 220, 157, 252, 221, 1], [68, 47, 146, 208, 149, 167, 221, 162, 255, 223, 128]],
+        [[1, 149, 241, 255, 221, 224, 255, 255, 128, 128, 128], [184, 141, 234, 253, 222, 220, 255, 199, 128, 128, 128], [81, 99, 181, 242, 176, 190, 249, 202, 255, 255, 128]],
+        [[1, 129, 232, 253, 214, 197, 242, 196, 255, 255, 128], [99, 121, 210, 250, 201, 198, 255, 202, 128, 128, 128], [23, 91, 163, 242, 170, 187, 247, 210, 255, 255, 128]],
+        [[1, 200, 246, 255, 234, 255, 128, 128, 128, 128, 128], [109, 178, 241, 255, 231, 245, 255, 255, 128, 128, 128], [44, 130, 201, 253, 205, 192, 255, 255, 128, 128, 128]],
+        [[1, 132, 239, 251, 219, 209, 255, 165, 128, 128, 128], [94, 136, 225, 251, 218, 190, 255, 255, 128, 128, 128], [22, 100, 174, 245, 186, 161, 255, 199, 128, 128, 128]],
+        [[1, 182, 249, 255, 232, 235, 128, 128, 128, 128, 128], [124, 143, 241, 255, 227, 234, 128, 128, 128, 128, 128], [35, 77, 181, 251, 193, 211, 255, 205, 128, 128, 128]],
+        [[1, 157, 247, 255, 236, 231, 255, 255, 128, 128, 128], [121, 141, 235, 255, 225, 227, 255, 255, 128, 128, 128], [45, 99, 188, 251, 195, 217, 255, 224, 128, 128, 128]],
+        [[1, 1, 251, 255, 213, 255, 128, 128, 128, 128, 128], [203, 1, 248, 255, 255, 128, 128, 128, 128, 128, 128], [137, 1, 177, 255, 224, 255, 128, 128, 128, 128, 128]],
+    ],
+    [
+        [[253, 9, 248, 251, 207, 208, 255, 192, 128, 128, 128], [175, 13, 224, 243, 193, 185, 249, 198, 255, 255, 128], [73, 17, 171, 221, 161, 179, 236, 167, 255, 234, 128]],
+        [[1, 95, 247, 253, 212, 183, 255, 255, 128, 128, 128], [239, 90, 244, 250, 211, 209, 255, 255, 128, 128, 128], [155, 77, 195, 248, 188, 195, 255, 255, 128, 128, 128]],
+        [[1, 24, 239, 251, 218, 219, 255, 205, 128, 128, 128], [201, 51, 219, 255, 196, 186, 128, 128, 128, 128, 128], [69, 46, 190, 239, 201, 218, 255, 228, 128, 128, 128]],
+        [[1, 191, 251, 255, 255, 128, 128, 128, 128, 128, 128], [223, 165, 249, 255, 213, 255, 128, 128, 128, 128, 128], [141, 124, 248, 255, 255, 128, 128, 128, 128, 128, 128]],
+        [[1, 16, 248, 255, 255, 128, 128, 128, 128, 128, 128], [190, 36, 230, 255, 236, 255, 128, 128, 128, 128, 128], [149, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128]],
+        [[1, 226, 255, 128, 128, 128, 128, 128, 128, 128, 128], [247, 192, 255, 128, 128, 128, 128, 128, 128, 128, 128], [240, 128, 255, 128, 128, 128, 128, 128, 128, 128, 128]],
+        [[1, 134, 252, 255, 255, 128, 128, 128, 128, 128, 128], [213, 62, 250, 255, 255, 128, 128, 128, 128, 128, 128], [55, 93, 255, 128, 128, 128, 128, 128, 128, 128, 128]],
+        [[128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128], [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128], [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128]],
+    ],
+    [
+        [[202, 24, 213, 235, 186, 191, 220, 160, 240, 175, 255], [126, 38, 182, 232, 169, 184, 228, 174, 255, 187, 128], [61, 46, 138, 219, 151, 178, 240, 170, 255, 216, 128]],
+        [[1, 112, 230, 250, 199, 191, 247, 159, 255, 255, 128], [166, 109, 228, 252, 211, 215, 255, 174, 128, 128, 128], [39, 77, 162, 232, 172, 180, 245, 178, 255, 255, 128]],
+        [[1, 52, 220, 246, 198, 199, 249, 220, 255, 255, 128], [124, 74, 191, 243, 183, 193, 250, 221, 255, 255, 128], [24, 71, 130, 219, 154, 170, 243, 182, 255, 255, 128]],
+        [[1, 182, 225, 249, 219, 240, 255, 224, 128, 128, 128], [149, 150, 226, 252, 216, 205, 255, 171, 128, 128, 128], [28, 108, 170, 242, 183, 194, 254, 223, 255, 255, 128]],
+        [[1, 81, 230, 252, 204, 203, 255, 192, 128, 128, 128], [123, 102, 209, 247, 188, 196, 255, 233, 128, 128, 128], [20, 95, 153, 243, 164, 173, 255, 203, 128, 128, 128]],
+        [[1, 222, 248, 255, 216, 213, 128, 128, 128, 128, 128], [168, 175, 246, 252, 235, 205, 255, 255, 128, 128, 128], [47, 116, 215, 255, 211, 212, 255, 255, 128, 128, 128]],
+        [[1, 121, 236, 253, 212, 214, 255, 255, 128, 128, 128], [141, 84, 213, 252, 201, 202, 255, 219, 128, 128, 128], [42, 80, 160, 240, 162, 185, 255, 205, 128, 128, 128]],
+        [[1, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128], [244, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128], [238, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128]],
+    ],
+];
+
+const DC_QUANT: [i32; 128] = [
+    4, 5, 6, 7, 8, 9, 10, 10, 11, 12, 13, 14, 15, 16, 17, 17,
+    18, 19, 20, 20, 21, 21, 22, 22, 23, 23, 24, 25, 25, 26, 27, 28,
+    29, 30, 31, 32, 33, 34, 35, 36, 37, 37, 38, 39, 40, 41, 42, 43,
+    44, 45, 46, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58,
+    59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74,
+    75, 76, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89,
+    91, 93, 95, 96, 98, 100, 101, 102, 104, 106, 108, 110, 112, 114, 116, 118,
+    122, 124, 126, 128, 130, 132, 134, 136, 138, 140, 143, 145, 148, 151, 154, 157,
+];
+
+const AC_QUANT: [i32; 128] = [
+    4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19,
+    20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35,
+    36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51,
+    52, 53, 54, 55, 56, 57, 58, 60, 62, 64, 66, 68, 70, 72, 74, 76,
+    78, 80, 82, 84, 86, 88, 90, 92, 94, 96, 98, 100, 102, 104, 106, 108,
+    110, 112, 114, 116, 119, 122, 125, 128, 131, 134, 137, 140, 143, 146, 149, 152,
+    155, 158, 161, 164, 167, 170, 173, 177, 181, 185, 189, 193, 197, 201, 205, 209,
+    213, 217, 221, 225, 229, 234, 239, 245, 249, 254, 259, 264, 269, 274, 279, 284,
+];
@@ -0,0 +1,502 @@
+//! VP8L (lossless WebP) bitstream decoder
+
+use crate::compress::bits::BitReader;
+use crate::compress::primitives::{canonical_codes, reverse_bits};
+use crate::limits::Budget;
+use crate::pool::{self, Scratch};
+
+pub(super) const SIGNATURE: u8 = 0x2F;
+
+/// Transmission order of code length code lengths
+pub(super) const CODE_LENGTH_ORDER: [usize; 19] = [17, 18, 0, 1, 2, 3, 4, 5, 16, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+/// (dx, dy) neighbourhood offsets for distance codes 1-120
+#[rustfmt::skip]
+pub(super) const DISTANCE_MAP: [(i8, i8); 120] = [
+    (0, 1),  (1, 0),  (1, 1),  (-1, 1), (0, 2),  (2, 0),  (1, 2),  (-1, 2),
+    (2, 1),  (-2, 1), (2, 2),  (-2, 2), (0, 3),  (3, 0),  (1, 3),  (-1, 3),
+    (3, 1),  (-3, 1), (2, 3),  (-2, 3), (3, 2),  (-3, 2), (0, 4),  (4, 0),
+    (1, 4),  (-1, 4), (4, 1),  (-4, 1), (3, 3),  (-3, 3), (2, 4),  (-2, 4),
+    (4, 2),  (-4, 2), (0, 5),  (3, 4),  (-3, 4), (4, 3),  (-4, 3), (5, 0),
+    (1, 5),  (-1, 5), (5, 1),  (-5, 1), (2, 5),  (-2, 5), (5, 2),  (-5, 2),
+    (4, 4),  (-4, 4), (3, 5),  (-3, 5), (5, 3),  (-5, 3), (0, 6),  (6, 0),
+    (1, 6),  (-1, 6), (6, 1),  (-6, 1), (2, 6),  (-2, 6), (6, 2),  (-6, 2),
+    (4, 5),  (-4, 5), (5, 4),  (-5, 4), (3, 6),  (-3, 6), (6, 3),  (-6, 3),
+    (0, 7),  (7, 0),  (1, 7),  (-1, 7), (5, 5),  (-5, 5), (7, 1),  (-7, 1),
+    (4, 6),  (-4, 6), (6, 4),  (-6, 4), (2, 7),  (-2, 7), (7, 2),  (-7, 2),
+    (3, 7),  (-3, 7), (7, 3),  (-7, 3), (5, 6),  (-5, 6), (6, 5),  (-6, 5),
+    (8, 0),  (4, 7),  (-4, 7), (7, 4),  (-7, 4), (8, 1),  (8, 2),  (6, 6),
+    (-6, 6), (8, 3),  (5, 7),  (-5, 7), (7, 5),  (-7, 5), (8, 4),  (6, 7),
+    (-6, 7), (7, 6),  (-7, 6), (8, 5),  (7, 7),  (-7, 7), (8, 6),  (8, 7),
+];
+
+/// Number of length/distance prefix symbols in the green alphabet
+pub(super) const LENGTH_CODES: usize = 24;
+
+/// Multiplier of the color cache hash
+pub(super) const CACHE_HASH: u32 = 0x1E35_A7BD;
+
+#[inline]
+pub(super) fn subsample(size: u32, bits: u32) -> u32 {
+    (size + (1 << bits) - 1) >> bits
+}
+
+/// Canonical Huffman decoder with an 8-bit lookup table and a slow path for
+/// longer codes, small enough to keep one per code group
+struct Huffman {
+    /// Codes with a single symbol take no bits
+    single: Option<u16>,
+    /// Indexed by the next 8 stream bits: (symbol, length), length 0 for longer codes
+    fast: Vec<(u16, u8)>,
+    /// Codes per length, then symbols in canonical order
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+const FAST_BITS: u32 = 8;
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, String> {
+        let mut counts = [0u16; 16];
+        for &l in lengths {
+            counts[l as usize] += 1;
+        }
+        counts[0] = 0;
+        let used: u16 = counts.iter().sum();
+        if used == 0 {
+            return Err("Empty Huffman code".to_string());
+        }
+        if used == 1 {
+            let symbol = lengths.iter().position(|&l| l > 0).unwrap() as u16;
+            return Ok(Huffman { single: Some(symbol), fast: Vec::new(), counts, symbols: Vec::new() });
+        }
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err("Invalid Huffman code lengths".to_string());
+            }
+        }
+
+        let codes = canonical_codes(lengths);
+        let mut fast = vec![(0u16, 0u8); 1 << FAST_BITS];
+        for (symbol, (&len, &code)) in lengths.iter().zip(&codes).enumerate() {
+            if len == 0 || len as u32 > FAST_BITS {
+                continue;
+            }
+            let rev = reverse_bits(code, len) as usize;
+            for entry in fast.iter_mut().skip(rev).step_by(1 << len) {
+                *entry = (symbol as u16, len);
+            }
+        }
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16).filter(|&s| lengths[s as usize] > 0).collect();
+        symbols.sort_by_key(|&s| lengths[s as usize]);
+        Ok(Huffman { single: None, fast, counts, symbols })
+    }
+
+    #[inline]
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        if let Some(symbol) = self.single {
+            return Ok(symbol);
+        }
+        let bits = reader.peek(15);
+        let (symbol, len) = self.fast[(bits & ((1 << FAST_BITS) - 1)) as usize];
+        if len > 0 {
+            reader.consume(len as u32)?;
+            return Ok(symbol);
+        }
+        // Walk the canonical code one bit at a time, most significant first
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= ((bits >> (len - 1)) & 1) as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                reader.consume(len as u32)?;
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("Invalid Huffman code".to_string())
+    }
+}
+
+/// The five codes of one prefix code group: green+length+cache, red, blue, alpha, distance
+struct Group([Huffman; 5]);
+
+enum Transform {
+    Predictor { bits: u32, modes: Scratch<u32> },
+    Color { bits: u32, elements: Scratch<u32> },
+    SubtractGreen,
+    /// Palette, and how many pixels share each packed pixel (as a shift)
+    ColorIndexing { bits: u32, palette: Scratch<u32> },
+}
+
+struct Decoder<'a> {
+    reader: BitReader<'a>,
+}
+
+/// Value of a length or distance prefix symbol plus its extra bits
+fn prefix_value(symbol: u16, reader: &mut BitReader) -> Result<usize, String> {
+    let symbol = symbol as usize;
+    if symbol < 4 {
+        return Ok(symbol + 1);
+    }
+    let extra = (symbol - 2) >> 1;
+    let offset = (2 + (symbol & 1)) << extra;
+    Ok(offset + reader.bits(extra as u32)? as usize + 1)
+}
+
+/// Per-channel sum of two ARGB pixels
+#[inline]
+pub(super) fn add_pixels(a: u32, b: u32) -> u32 {
+    let alpha_green = (a & 0xFF00_FF00).wrapping_add(b & 0xFF00_FF00);
+    let red_blue = (a & 0x00FF_00FF).wrapping_add(b & 0x00FF_00FF);
+    (alpha_green & 0xFF00_FF00) | (red_blue & 0x00FF_00FF)
+}
+
+#[inline]
+fn average2(a: u32, b: u32) -> u32 {
+    (((a ^ b) & 0xFEFE_FEFE) >> 1) + (a & b)
+}
+
+#[inline]
+fn channel(p: u32, shift: u32) -> i32 {
+    ((p >> shift) & 0xFF) as i32
+}
+
+fn select(top: u32, left: u32, top_left: u32) -> u32 {
+    let distance: i32 = [24, 16, 8, 0]
+        .iter()
+        .map(|&s| (channel(left, s) - channel(top_left, s)).abs() - (channel(top, s) - channel(top_left, s)).abs())
+        .sum();
+    if distance <= 0 {
+        top
+    } else {
+        left
+    }
+}
+
+fn clamp_add_subtract_full(a: u32, b: u32, c: u32) -> u32 {
+    [24, 16, 8, 0].iter().fold(0, |acc, &s| acc | ((channel(a, s) + channel(b, s) - channel(c, s)).clamp(0, 255) as u32) << s)
+}
+
+fn clamp_add_subtract_half(a: u32, b: u32) -> u32 {
+    [24, 16, 8, 0].iter().fold(0, |acc, &s| {
+        let (a, b) = (channel(a, s), channel(b, s));
+        acc | ((a + (a - b) / 2).clamp(0, 255) as u32) << s
+    })
+}
+
+/// Predicted pixel for predictor `mode` from the left, top, top-left and top-right neighbours
+#[inline]
+pub(super) fn predict(mode: u32, left: u32, top: u32, top_left: u32, top_right: u32) -> u32 {
+    match mode {
+        1 => left,
+        2 => top,
+        3 => top_right,
+        4 => top_left,
+        5 => average2(average2(left, top_right), top),
+        6 => average2(left, top_left),
+        7 => average2(left, top),
+        8 => average2(top_left, top),
+        9 => average2(top, top_right),
+        10 => average2(average2(left, top_left), average2(top, top_right)),
+        11 => select(top, left, top_left),
+        12 => clamp_add_subtract_full(left, top, top_left),
+        13 => clamp_add_subtract_half(average2(left, top), top_left),
+        _ => 0xFF00_0000,
+    }
+}
+
+/// Prediction for pixel `i` of a `width`-wide image from already reconstructed pixels
+#[inline]
+pub(super) fn predict_at(pixels: &[u32], i: usize, width: usize, mode: impl FnOnce() -> u32) -> u32 {
+    match (i % width, i / width) {
+        (0, 0) => 0xFF00_0000,
+        (_, 0) => pixels[i - 1],
+        (0, _) => pixels[i - width],
+        // The top-right of the last column is the first pixel of the current row
+        _ => predict(mode(), pixels[i - 1], pixels[i - width], pixels[i - width - 1], pixels[i - width + 1]),
+    }
+}
+
+/// Signed 3.5 fixed-point product used by the color transform
+#[inline]
+pub(super) fn color_delta(t: u8, c: u8) -> i32 {
+    (t as i8 as i32 * c as i8 as i32) >> 5
+}
+
+impl Decoder<'_> {
+    fn bit(&mut self) -> Result<bool, String> {
+        Ok(self.reader.bits(1)? == 1)
+    }
+
+    fn read_code_lengths(&mut self, alphabet: usize) -> Result<Vec<u8>, String> {
+        let mut lengths = vec![0u8; alphabet];
+        if self.bit()? {
+            // Simple code: one or two symbols
+            let count = self.reader.bits(1)? as usize + 1;
+            let first_bits = if self.bit()? { 8 } else { 1 };
+            let first = self.reader.bits(first_bits)? as usize;
+            *lengths.get_mut(first).ok_or("Huffman symbol out of range")? = 1;
+            if count == 2 {
+                let second = self.reader.bits(8)? as usize;
+                *lengths.get_mut(second).ok_or("Huffman symbol out of range")? = 1;
+            }
+            return Ok(lengths);
+        }
+
+        let mut cl_lengths = [0u8; 19];
+        let count = self.reader.bits(4)? as usize + 4;
+        for &i in &CODE_LENGTH_ORDER[..count] {
+            cl_lengths[i] = self.reader.bits(3)? as u8;
+        }
+        let cl = Huffman::new(&cl_lengths)?;
+        let mut max_symbol = if self.bit()? {
+            let bits = 2 + 2 * self.reader.bits(3)?;
+            let max = 2 + self.reader.bits(bits)? as usize;
+            if max > alphabet {
+                return Err("Invalid Huffman max symbol".to_string());
+            }
+            max
+        } else {
+            alphabet
+        };
+
+        let mut previous = 8u8;
+        let mut i = 0;
+        while i < alphabet && max_symbol > 0 {
+            max_symbol -= 1;
+            let (value, repeat) = match cl.decode(&mut self.reader)? {
+                len @ 0..=15 => {
+                    if len != 0 {
+                        previous = len as u8;
+                    }
+                    (len as u8, 1)
+                }
+                16 => (previous, 3 + self.reader.bits(2)? as usize),
+                17 => (0, 3 + self.reader.bits(3)? as usize),
+                _ => (0, 11 + self.reader.bits(7)? as usize),
+            };
+            if i + repeat > alphabet {
+                return Err("Huffman code lengths overflow".to_string());
+            }
+            lengths[i..i + repeat].fill(value);
+            i += repeat;
+        }
+        Ok(lengths)
+    }
+
+    fn read_group(&mut self, cache_size: usize) -> Result<Group, String> {
+        let alphabets = [256 + LENGTH_CODES + cache_size, 256, 256, 256, 40];
+        let mut codes = Vec::with_capacity(5);
+        for alphabet in alphabets {
+            codes.push(Huffman::new(&self.read_code_lengths(alphabet)?)?);
+        }
+        let codes: [Huffman; 5] = codes.try_into().map_err(|_| "Invalid code group")?;
+        Ok(Group(codes))
+    }
+
+    /// Entropy-coded pixels of a `width` x `height` image; the main image may use meta prefix codes
+    fn image_data(&mut self, width: u32, height: u32, main: bool) -> Result<Scratch<u32>, String> {
+        let cache_bits = if self.bit()? {
+            let bits = self.reader.bits(4)?;
+            if !(1..=11).contains(&bits) {
+                return Err(format!("Invalid color cache size {}", bits));
+            }
+            bits
+        } else {
+            0
+        };
+        let cache_size = if cache_bits > 0 { 1usize << cache_bits } else { 0 };
+
+        let meta = if main && self.bit()? {
+            let bits = self.reader.bits(3)? + 2;
+            let mut image = self.image_data(subsample(width, bits), subsample(height, bits), false)?;
+            image.iter_mut().for_each(|p| *p = (*p >> 8) & 0xFFFF);
+            Some((bits, image))
+        } else {
+            None
+        };
+        let group_count = meta.as_ref().map_or(1, |(_, m)| m.iter().copied().max().unwrap_or(0) as usize + 1);
+        let mut groups = Vec::with_capacity(group_count);
+        for _ in 0..group_count {
+            groups.push(self.read_group(cache_size)?);
+        }
+
+        let (width, total) = (width as usize, width as usize * height as usize);
+        let mut pixels = pool::take(total, 0u32);
+        let mut cache = pool::take(cache_size, 0u32);
+        let mut cached = 0;
+        let mut i = 0;
+        while i < total {
+            let group = match &meta {
+                Some((bits, image)) => {
+                    let (x, y) = (i % width, i / width);
+                    &groups[image[(y >> bits) * subsample(width as u32, *bits) as usize + (x >> bits)] as usize]
+                }
+                None => &groups[0],
+            };
+            let [green, red, blue, alpha, distance] = &group.0;
+            let g = green.decode(&mut self.reader)? as usize;
+            if g < 256 {
+                let r = red.decode(&mut self.reader)? as u32;
+                let b = blue.decode(&mut self.reader)? as u32;
+                let a = alpha.decode(&mut self.reader)? as u32;
+                pixels[i] = a << 24 | r << 16 | (g as u32) << 8 | b;
+                i += 1;
+            } else if g < 256 + LENGTH_CODES {
+                let length = prefix_value((g - 256) as u16, &mut self.reader)?;
+                let code = prefix_value(distance.decode(&mut self.reader)?, &mut self.reader)?;
+                let dist = if code > 120 {
+                    code - 120
+                } else {
+                    let (dx, dy) = DISTANCE_MAP[code - 1];
+                    (dx as isize + dy as isize * width as isize).max(1) as usize
+                };
+                if dist > i || i + length > total {
+                    return Err("Invalid backward reference".to_string());
+                }
+                for j in i..i + length {
+                    pixels[j] = pixels[j - dist];
+                }
+                i += length;
+            } else {
+                let index = g - 256 - LENGTH_CODES;
+                pixels[i] = *cache.get(index).ok_or("Invalid color cache index")?;
+                i += 1;
+            }
+            if cache_size > 0 {
+                for &p in &pixels[cached..i] {
+                    cache[(CACHE_HASH.wrapping_mul(p) >> (32 - cache_bits)) as usize] = p;
+                }
+                cached = i;
+            }
+        }
+        Ok(pixels)
+    }
+
+    /// Transforms, entropy-coded pixels and inverse transforms of a full image
+    fn image(&mut self, width: u32, height: u32) -> Result<Scratch<u32>, String> {
+        let mut transforms: Vec<(Transform, u32)> = Vec::new();
+        let mut seen = [false; 4];
+        let mut coded_width = width;
+        while self.bit()? {
+            let kind = self.reader.bits(2)? as usize;
+            if std::mem::replace(&mut seen[kind], true) {
+                return Err("Repeated VP8L transform".to_string());
+            }
+            let transform = match kind {
+                0 | 1 => {
+                    let bits = self.reader.bits(3)? + 2;
+                    let mut data = self.image_data(subsample(coded_width, bits), subsample(height, bits), false)?;
+                    if kind == 0 {
+                        data.iter_mut().for_each(|p| *p = (*p >> 8) & 0xF);
+                        Transform::Predictor { bits, modes: data }
+                    } else {
+                        Transform::Color { bits, elements: data }
+                    }
+                }
+                2 => Transform::SubtractGreen,
+                _ => {
+                    let size = self.reader.bits(8)? + 1;
+                    let mut palette = self.image_data(size, 1, false)?;
+                    for i in 1..palette.len() {
+                        palette[i] = add_pixels(palette[i], palette[i - 1]);
+                    }
+                    let bits = match size {
+                        0..=2 => 3,
+                        3..=4 => 2,
+                        5..=16 => 1,
+                        _ => 0,
+                    };
+                    Transform::ColorIndexing { bits, palette }
+                }
+            };
+            // Each transform records the width of the image it reconstructs
+            let before = coded_width;
+            if let Transform::ColorIndexing { bits, .. } = transform {
+                coded_width = subsample(coded_width, bits);
+            }
+            transforms.push((transform, before));
+        }
+
+        let mut pixels = self.image_data(coded_width, height, true)?;
+        for (transform, width) in transforms.iter().rev() {
+            pixels = inverse_transform(transform, pixels, *width as usize, height as usize);
+        }
+        Ok(pixels)
+    }
+}
+
+fn inverse_transform(transform: &Transform, mut pixels: Scratch<u32>, width: usize, height: usize) -> Scratch<u32> {
+    match transform {
+        Transform::Predictor { bits, modes } => {
+            let blocks = subsample(width as u32, *bits) as usize;
+            for i in 0..width * height {
+                let (x, y) = (i % width, i / width);
+                let prediction = predict_at(&pixels, i, width, || modes[(y >> bits) * blocks + (x >> bits)]);
+                pixels[i] = add_pixels(pixels[i], prediction);
+            }
+            pixels
+        }
+        Transform::Color { bits, elements } => {
+            let blocks = subsample(width as u32, *bits) as usize;
+            for (i, p) in pixels.iter_mut().enumerate() {
+                let element = elements[((i / width) >> bits) * blocks + ((i % width) >> bits)];
+                let [green_to_red, green_to_blue, red_to_blue, _] = element.to_le_bytes();
+                let green = (*p >> 8) as u8;
+                let red = ((*p >> 16) as i32 + color_delta(green_to_red, green)) as u8;
+                let blue = (*p as i32 + color_delta(green_to_blue, green) + color_delta(red_to_blue, red)) as u8;
+                *p = (*p & 0xFF00_FF00) | (red as u32) << 16 | blue as u32;
+            }
+            pixels
+        }
+        Transform::SubtractGreen => {
+            for p in pixels.iter_mut() {
+                let green = (*p >> 8) & 0xFF;
+                *p = add_pixels(*p, green << 16 | green);
+            }
+            pixels
+        }
+        Transform::ColorIndexing { bits, palette } => {
+            let packed_width = subsample(width as u32, *bits) as usize;
+            let per_pixel = 8 >> bits;
+            let mask = (1u32 << per_pixel) - 1;
+            let mut out = pool::take_empty(width * height);
+            for y in 0..height {
+                for x in 0..width {
+                    let packed = pixels[y * packed_width + (x >> bits)] >> 8;
+                    let index = (packed >> ((x & ((1 << bits) - 1)) * per_pixel)) & mask;
+                    // Indices past the palette decode as transparent black
+                    out.push(palette.get(index as usize).copied().unwrap_or(0));
+                }
+            }
+            out
+        }
+    }
+}
+
+/// Decode a VP8L chunk to ARGB pixels, returning (width, height, pixels)
+pub(super) fn decode_vp8l(data: &[u8], budget: &mut Budget) -> Result<(u32, u32, Scratch<u32>), String> {
+    if data.len() < 5 || data[0] != SIGNATURE {
+        return Err("Invalid VP8L signature".to_string());
+    }
+    let mut decoder = Decoder { reader: BitReader::new(&data[1..]) };
+    let width = decoder.reader.bits(14)? + 1;
+    let height = decoder.reader.bits(14)? + 1;
+    let _alpha_hint = decoder.reader.bits(1)?;
+    if decoder.reader.bits(3)? != 0 {
+        return Err("Unsupported VP8L version".to_string());
+    }
+    budget.check_size(width as usize, height as usize)?;
+    budget.rows(height as usize)?;
+    Ok((width, height, decoder.image(width, height)?))
+}
+
+/// Decode a headerless VP8L stream of known size, as used by ALPH chunks
+pub(super) fn decode_vp8l_stream(data: &[u8], width: u32, height: u32) -> Result<Scratch<u32>, String> {
+    Decoder { reader: BitReader::new(data) }.image(width, height)
+}
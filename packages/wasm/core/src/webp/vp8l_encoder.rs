@@ -0,0 +1,311 @@
+//! VP8L (lossless WebP) bitstream encoder
+//!
+//! Images with at most 256 colors use the color indexing transform with
+//! pixel packing; others use subtract-green plus a per-tile predictor. Pixels
+//! are then LZ77-coded with a single prefix code group.
+
+use super::vp8l::{predict_at, subsample, CODE_LENGTH_ORDER, DISTANCE_MAP, LENGTH_CODES, SIGNATURE};
+use crate::compress::bits::BitWriter;
+use crate::compress::deflate::rle_code_lengths;
+use crate::compress::primitives::{canonical_codes, code_lengths, reverse_bits};
+use std::collections::{HashMap, HashSet};
+
+/// log2 of the predictor tile size
+const PREDICTOR_BITS: u32 = 4;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 4096;
+/// Largest distance the 40 distance prefix symbols can express, less the 120 plane codes
+const MAX_DISTANCE: usize = (1 << 20) - 120;
+const HASH_BITS: u32 = 16;
+const MAX_CHAIN: usize = 32;
+
+enum Token {
+    Literal(u32),
+    Copy { length: usize, distance_code: usize },
+}
+
+/// Per-channel difference of two ARGB pixels
+#[inline]
+fn sub_pixels(a: u32, b: u32) -> u32 {
+    let alpha_green = (a | 0x00FF_00FF).wrapping_sub(b & 0xFF00_FF00);
+    let red_blue = (a | 0xFF00_FF00).wrapping_sub(b & 0x00FF_00FF);
+    (alpha_green & 0xFF00_FF00) | (red_blue & 0x00FF_00FF)
+}
+
+/// Symbol, extra bit count and extra bits for a length or distance value
+fn prefix(value: usize) -> (usize, u32, u32) {
+    let n = value - 1;
+    if n < 4 {
+        return (n, 0, 0);
+    }
+    let high = usize::BITS - 1 - n.leading_zeros();
+    let second = (n >> (high - 1)) & 1;
+    (2 * high as usize + second, high - 1, (n & ((1 << (high - 1)) - 1)) as u32)
+}
+
+/// Distance code for a backward distance, preferring the short 2D neighbourhood codes
+fn distance_code(distance: usize, width: usize) -> usize {
+    let (dy, dx) = ((distance / width) as isize, (distance % width) as isize);
+    let find = |dx: isize, dy: isize| DISTANCE_MAP.iter().position(|&(x, y)| x as isize == dx && y as isize == dy);
+    find(dx, dy).or_else(|| find(dx - width as isize, dy + 1)).map_or(distance + 120, |i| i + 1)
+}
+
+/// Greedy LZ77 over pixels with hash chains, also trying the left and upper neighbours
+fn backward_references(pixels: &[u32], width: usize) -> Vec<Token> {
+    let n = pixels.len();
+    let hash = |i: usize| (pixels[i].wrapping_mul(0x1E35_A7BD) ^ pixels[i + 1].wrapping_mul(0x9E37_79B1)) >> (32 - HASH_BITS);
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut chain = vec![usize::MAX; n];
+    let insert = |i: usize, head: &mut [usize], chain: &mut [usize]| {
+        if i + 1 < n {
+            let h = hash(i) as usize;
+            chain[i] = head[h];
+            head[h] = i;
+        }
+    };
+    let match_length = |a: usize, b: usize| pixels[a..(a + MAX_MATCH).min(n)].iter().zip(&pixels[b..]).take_while(|(x, y)| x == y).count();
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < n {
+        let (mut best_length, mut best_distance) = (0, 0);
+        for distance in [1, width] {
+            if distance <= i {
+                let length = match_length(i, i - distance);
+                if length > best_length {
+                    (best_length, best_distance) = (length, distance);
+                }
+            }
+        }
+        if i + 1 < n {
+            let mut candidate = head[hash(i) as usize];
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || i - candidate > MAX_DISTANCE {
+                    break;
+                }
+                let length = match_length(i, candidate);
+                if length > best_length {
+                    (best_length, best_distance) = (length, i - candidate);
+                }
+                candidate = chain[candidate];
+            }
+        }
+
+        if best_length >= MIN_MATCH {
+            tokens.push(Token::Copy { length: best_length, distance_code: distance_code(best_distance, width) });
+            for j in i..i + best_length {
+                insert(j, &mut head, &mut chain);
+            }
+            i += best_length;
+        } else {
+            tokens.push(Token::Literal(pixels[i]));
+            insert(i, &mut head, &mut chain);
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// A prefix code as written: lengths and canonical codes per symbol
+struct Code {
+    lengths: Vec<u8>,
+    codes: Vec<u16>,
+}
+
+impl Code {
+    fn put(&self, w: &mut BitWriter, symbol: usize) {
+        let len = self.lengths[symbol];
+        if len > 0 {
+            w.write(reverse_bits(self.codes[symbol], len) as u32, len as u32);
+        }
+    }
+}
+
+/// Write the prefix code for `freq`, as a simple code when at most two small symbols occur
+fn write_code(w: &mut BitWriter, freq: &[u32]) -> Code {
+    let used: Vec<usize> = (0..freq.len()).filter(|&s| freq[s] > 0).collect();
+    if used.len() <= 2 && used.iter().all(|&s| s < 256) {
+        w.write(1, 1);
+        w.write(used.len().max(1) as u32 - 1, 1);
+        let first = used.first().copied().unwrap_or(0);
+        if first < 2 {
+            w.write(0, 1);
+            w.write(first as u32, 1);
+        } else {
+            w.write(1, 1);
+            w.write(first as u32, 8);
+        }
+        let mut lengths = vec![0u8; freq.len()];
+        if let [a, b] = used[..] {
+            w.write(b as u32, 8);
+            (lengths[a], lengths[b]) = (1, 1);
+        }
+        let codes = canonical_codes(&lengths);
+        return Code { lengths, codes };
+    }
+
+    let lengths = code_lengths(freq, 15);
+    let tokens = rle_code_lengths(&lengths);
+    let mut cl_freq = [0u32; 19];
+    for &(symbol, _) in &tokens {
+        cl_freq[symbol as usize] += 1;
+    }
+    let cl = Code { lengths: code_lengths(&cl_freq, 7), codes: Vec::new() };
+    let cl = Code { codes: canonical_codes(&cl.lengths), ..cl };
+    let count = CODE_LENGTH_ORDER.iter().rposition(|&i| cl.lengths[i] > 0).map_or(0, |p| p + 1).max(4);
+
+    w.write(0, 1);
+    w.write(count as u32 - 4, 4);
+    for &i in &CODE_LENGTH_ORDER[..count] {
+        w.write(cl.lengths[i] as u32, 3);
+    }
+    // Code lengths run to the end of the alphabet
+    w.write(0, 1);
+    for (symbol, extra) in tokens {
+        cl.put(w, symbol as usize);
+        match symbol {
+            16 => w.write(extra as u32, 2),
+            17 => w.write(extra as u32, 3),
+            18 => w.write(extra as u32, 7),
+            _ => {}
+        }
+    }
+    let codes = canonical_codes(&lengths);
+    Code { lengths, codes }
+}
+
+/// Entropy-code an image with one prefix code group and no color cache
+fn write_image_data(w: &mut BitWriter, pixels: &[u32], width: usize, main: bool) {
+    // No color cache, and for the main image no meta prefix codes
+    w.write(0, 1);
+    if main {
+        w.write(0, 1);
+    }
+
+    let tokens = backward_references(pixels, width);
+    let mut freq = [vec![0u32; 256 + LENGTH_CODES], vec![0; 256], vec![0; 256], vec![0; 256], vec![0; 40]];
+    for token in &tokens {
+        match *token {
+            Token::Literal(p) => {
+                freq[0][((p >> 8) & 0xFF) as usize] += 1;
+                freq[1][((p >> 16) & 0xFF) as usize] += 1;
+                freq[2][(p & 0xFF) as usize] += 1;
+                freq[3][(p >> 24) as usize] += 1;
+            }
+            Token::Copy { length, distance_code } => {
+                freq[0][256 + prefix(length).0] += 1;
+                freq[4][prefix(distance_code).0] += 1;
+            }
+        }
+    }
+    let [green, red, blue, alpha, distance] = freq.map(|f| write_code(w, &f));
+
+    for token in tokens {
+        match token {
+            Token::Literal(p) => {
+                green.put(w, ((p >> 8) & 0xFF) as usize);
+                red.put(w, ((p >> 16) & 0xFF) as usize);
+                blue.put(w, (p & 0xFF) as usize);
+                alpha.put(w, (p >> 24) as usize);
+            }
+            Token::Copy { length, distance_code } => {
+                let (symbol, bits, extra) = prefix(length);
+                green.put(w, 256 + symbol);
+                w.write(extra, bits);
+                let (symbol, bits, extra) = prefix(distance_code);
+                distance.put(w, symbol);
+                w.write(extra, bits);
+            }
+        }
+    }
+}
+
+/// Predictor mode per tile with the smallest absolute residuals, and the residual image
+fn predict(pixels: &[u32], width: usize, height: usize) -> (Vec<u32>, Vec<u32>) {
+    let tiles_x = subsample(width as u32, PREDICTOR_BITS) as usize;
+    let tiles_y = subsample(height as u32, PREDICTOR_BITS) as usize;
+    let tile = 1usize << PREDICTOR_BITS;
+    let cost = |p: u32| p.to_le_bytes().iter().map(|&b| (b as i8).unsigned_abs() as u32).sum::<u32>();
+
+    let mut modes = vec![0u32; tiles_x * tiles_y];
+    for (t, mode) in modes.iter_mut().enumerate() {
+        let (x0, y0) = (t % tiles_x * tile, t / tiles_x * tile);
+        let positions: Vec<usize> = (y0..(y0 + tile).min(height)).flat_map(|y| (x0..(x0 + tile).min(width)).map(move |x| y * width + x)).collect();
+        *mode = (0..14u32)
+            .min_by_key(|&m| positions.iter().map(|&i| cost(sub_pixels(pixels[i], predict_at(pixels, i, width, || m)))).sum::<u32>())
+            .unwrap_or(0);
+    }
+
+    let residuals = (0..pixels.len())
+        .map(|i| {
+            let tile_index = ((i / width) >> PREDICTOR_BITS) * tiles_x + ((i % width) >> PREDICTOR_BITS);
+            sub_pixels(pixels[i], predict_at(pixels, i, width, || modes[tile_index]))
+        })
+        .collect();
+    (modes.into_iter().map(|m| 0xFF00_0000 | m << 8).collect(), residuals)
+}
+
+/// Encode ARGB pixels as a VP8L chunk payload
+pub(super) fn encode_vp8l(width: u32, height: u32, argb: &[u32]) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    w.write(SIGNATURE as u32, 8);
+    w.write(width - 1, 14);
+    w.write(height - 1, 14);
+    w.write(argb.iter().any(|&p| p >> 24 != 0xFF) as u32, 1);
+    w.write(0, 3);
+
+    let (width, height) = (width as usize, height as usize);
+    let mut colors = HashSet::new();
+    for &p in argb {
+        if colors.insert(p) && colors.len() > 256 {
+            break;
+        }
+    }
+
+    let (pixels, coded_width) = if colors.len() <= 256 {
+        // Sorted palettes delta-code compactly
+        let mut palette: Vec<u32> = colors.into_iter().collect();
+        palette.sort_unstable();
+        let index: HashMap<u32, u32> = palette.iter().enumerate().map(|(i, &c)| (c, i as u32)).collect();
+        let bits = match palette.len() {
+            0..=2 => 3,
+            3..=4 => 2,
+            5..=16 => 1,
+            _ => 0,
+        };
+        w.write(1, 1);
+        w.write(3, 2);
+        w.write(palette.len() as u32 - 1, 8);
+        let deltas: Vec<u32> = palette.iter().enumerate().map(|(i, &c)| if i == 0 { c } else { sub_pixels(c, palette[i - 1]) }).collect();
+        write_image_data(&mut w, &deltas, palette.len(), false);
+
+        let coded_width = subsample(width as u32, bits) as usize;
+        let per_pixel = 8 >> bits;
+        let mut packed = vec![0xFF00_0000u32; coded_width * height];
+        for (i, &p) in argb.iter().enumerate() {
+            let (x, y) = (i % width, i / width);
+            packed[y * coded_width + (x >> bits)] |= index[&p] << (8 + (x & ((1 << bits) - 1)) * per_pixel);
+        }
+        (packed, coded_width)
+    } else {
+        w.write(1, 1);
+        w.write(2, 2);
+        let green_subtracted: Vec<u32> = argb
+            .iter()
+            .map(|&p| {
+                let green = (p >> 8) & 0xFF;
+                sub_pixels(p, green << 16 | green)
+            })
+            .collect();
+
+        w.write(1, 1);
+        w.write(0, 2);
+        w.write(PREDICTOR_BITS - 2, 3);
+        let (modes, residuals) = predict(&green_subtracted, width, height);
+        write_image_data(&mut w, &modes, subsample(width as u32, PREDICTOR_BITS) as usize, false);
+        (residuals, width)
+    };
+    w.write(0, 1);
+    write_image_data(&mut w, &pixels, coded_width, true);
+    w.finish()
+}
@@ -0,0 +1,81 @@
+/*
+ * mconv: pure Rust image codecs, C interface
+ *
+ * Link against libmconv (built from packages/wasm/ffi). Every function
+ * returns an MCONV_* status code and writes into buffers the caller owns.
+ * When a buffer is too small the call returns MCONV_ERR_BUFFER_TOO_SMALL
+ * and still reports the size it needs, so pass NULL and 0 first to size
+ * the buffer, then call again. mconv_last_error describes the last failure
+ * on the calling thread.
+ */
+
+#ifndef MCONV_H
+#define MCONV_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes */
+#define MCONV_OK 0
+#define MCONV_ERR_NULL_POINTER 1
+#define MCONV_ERR_INVALID_ARGUMENT 2
+#define MCONV_ERR_BUFFER_TOO_SMALL 3
+#define MCONV_ERR_DECODE 4
+#define MCONV_ERR_ENCODE 5
+#define MCONV_ERR_INTERNAL 6
+
+/* Image formats */
+#define MCONV_FORMAT_BMP 0
+#define MCONV_FORMAT_JPEG 1 /* decode only */
+#define MCONV_FORMAT_PNG 2
+#define MCONV_FORMAT_GIF 3
+#define MCONV_FORMAT_WEBP 4 /* lossless on encode */
+
+/* Resize algorithms */
+#define MCONV_RESIZE_NEAREST 0
+#define MCONV_RESIZE_BILINEAR 1
+#define MCONV_RESIZE_BICUBIC 2
+#define MCONV_RESIZE_LANCZOS 3
+
+/* MCONV_FORMAT_* of an encoded image, or -1 if unrecognised */
+int32_t mconv_detect_format(const uint8_t *data, size_t len);
+
+/*
+ * Decode any supported image to RGBA. width and height are set whenever
+ * decoding succeeds; out needs width * height * 4 bytes. auto_orient
+ * applies the EXIF orientation tag.
+ */
+int32_t mconv_decode(const uint8_t *data, size_t len, bool auto_orient,
+                     uint8_t *out, size_t out_capacity,
+                     uint32_t *width, uint32_t *height);
+
+/* Encode width * height * 4 bytes of RGBA as an MCONV_FORMAT_* */
+int32_t mconv_encode(const uint8_t *rgba, uint32_t width, uint32_t height,
+                     uint32_t format, uint8_t *out, size_t out_capacity,
+                     size_t *out_len);
+
+/* Decode any supported image and re-encode it as an MCONV_FORMAT_* */
+int32_t mconv_convert(const uint8_t *data, size_t len, uint32_t format,
+                      uint8_t *out, size_t out_capacity, size_t *out_len);
+
+/* Resize RGBA; out needs dst_width * dst_height * 4 bytes */
+int32_t mconv_resize(const uint8_t *rgba, uint32_t width, uint32_t height,
+                     uint32_t dst_width, uint32_t dst_height,
+                     uint32_t algorithm, uint8_t *out, size_t out_capacity);
+
+/*
+ * Copy the last error message on this thread into buf, truncated and
+ * NUL-terminated. Returns the full length without the NUL.
+ */
+size_t mconv_last_error(char *buf, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MCONV_H */
@@ -0,0 +1,319 @@
+//! mconv-ffi: C ABI for the mconv-core codecs
+//!
+//! Lets C, C++ and Python (through ctypes or cffi) use the same codecs as
+//! the WASM build. Every function returns one of the `MCONV_*` status codes
+//! and writes its output into a buffer the caller owns. When that buffer is
+//! too small the call fails with `MCONV_ERR_BUFFER_TOO_SMALL` and still
+//! reports the size it needs, so a first call with a null buffer sizes the
+//! second. `mconv_last_error` describes the last failure on the calling
+//! thread. The C declarations live in `include/mconv.h`.
+
+use mconv_core::convert::{decode_auto, detect_format, encode, ConvertOptions, ImageFormat};
+use mconv_core::resize::{resize, ResizeAlgorithm};
+use mconv_core::transform::RgbaImage;
+use std::cell::RefCell;
+use std::ffi::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::{ptr, slice};
+
+/// The call succeeded
+pub const MCONV_OK: i32 = 0;
+/// A pointer that must be set was null
+pub const MCONV_ERR_NULL_POINTER: i32 = 1;
+/// A format, algorithm or size argument was out of range
+pub const MCONV_ERR_INVALID_ARGUMENT: i32 = 2;
+/// The output buffer is smaller than the size reported back
+pub const MCONV_ERR_BUFFER_TOO_SMALL: i32 = 3;
+/// The input is not an image this build can decode
+pub const MCONV_ERR_DECODE: i32 = 4;
+/// The image cannot be encoded in the requested format
+pub const MCONV_ERR_ENCODE: i32 = 5;
+/// The library hit an internal error; the message has details
+pub const MCONV_ERR_INTERNAL: i32 = 6;
+
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// A failed call: its status code and a message for `mconv_last_error`
+struct Error {
+    code: i32,
+    message: String,
+}
+
+impl Error {
+    fn new(code: i32, message: impl Into<String>) -> Error {
+        Error { code, message: message.into() }
+    }
+}
+
+/// Run a call body, turning errors and panics into status codes
+fn run(body: impl FnOnce() -> Result<(), Error>) -> i32 {
+    let result = catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Unknown panic".to_string());
+        Err(Error::new(MCONV_ERR_INTERNAL, message))
+    });
+    match result {
+        Ok(()) => MCONV_OK,
+        Err(error) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = error.message);
+            error.code
+        }
+    }
+}
+
+/// Borrow `len` bytes at `data`; null is only allowed for an empty input
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], Error> {
+    match (data.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(Error::new(MCONV_ERR_NULL_POINTER, "Input pointer is null")),
+        (false, _) => Ok(slice::from_raw_parts(data, len)),
+    }
+}
+
+/// Borrow a `width` x `height` RGBA image at `data`
+unsafe fn input_rgba<'a>(data: *const u8, width: u32, height: u32) -> Result<&'a [u8], Error> {
+    let len = rgba_len(width, height)?;
+    input(data, len)
+}
+
+/// Byte size of a `width` x `height` RGBA image
+fn rgba_len(width: u32, height: u32) -> Result<usize, Error> {
+    if width == 0 || height == 0 {
+        return Err(Error::new(MCONV_ERR_INVALID_ARGUMENT, format!("Invalid size {}x{}", width, height)));
+    }
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(4))
+        .ok_or_else(|| Error::new(MCONV_ERR_INVALID_ARGUMENT, format!("Image {}x{} is too large", width, height)))
+}
+
+/// Store `value` through `target` if the caller asked for it
+unsafe fn report<T>(target: *mut T, value: T) {
+    if !target.is_null() {
+        *target = value;
+    }
+}
+
+/// Copy `data` into the caller's `out` buffer of `capacity` bytes
+unsafe fn output(data: &[u8], out: *mut u8, capacity: usize) -> Result<(), Error> {
+    if data.len() > capacity {
+        return Err(Error::new(
+            MCONV_ERR_BUFFER_TOO_SMALL,
+            format!("Output needs {} bytes, buffer holds {}", data.len(), capacity),
+        ));
+    }
+    if out.is_null() {
+        return Err(Error::new(MCONV_ERR_NULL_POINTER, "Output pointer is null"));
+    }
+    ptr::copy_nonoverlapping(data.as_ptr(), out, data.len());
+    Ok(())
+}
+
+fn image_format(format: u32) -> Result<ImageFormat, Error> {
+    match format {
+        0 => Ok(ImageFormat::Bmp),
+        1 => Ok(ImageFormat::Jpeg),
+        2 => Ok(ImageFormat::Png),
+        3 => Ok(ImageFormat::Gif),
+        4 => Ok(ImageFormat::WebP),
+        _ => Err(Error::new(MCONV_ERR_INVALID_ARGUMENT, format!("Unknown image format {}", format))),
+    }
+}
+
+fn resize_algorithm(algorithm: u32) -> Result<ResizeAlgorithm, Error> {
+    match algorithm {
+        0 => Ok(ResizeAlgorithm::Nearest),
+        1 => Ok(ResizeAlgorithm::Bilinear),
+        2 => Ok(ResizeAlgorithm::Bicubic),
+        3 => Ok(ResizeAlgorithm::Lanczos),
+        _ => Err(Error::new(MCONV_ERR_INVALID_ARGUMENT, format!("Unknown resize algorithm {}", algorithm))),
+    }
+}
+
+/// Identify the format of an encoded image
+///
+/// Returns an `MCONV_FORMAT_*` value, or -1 when the signature is not recognised.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes (or be null with `len` 0).
+#[no_mangle]
+pub unsafe extern "C" fn mconv_detect_format(data: *const u8, len: usize) -> i32 {
+    match input(data, len) {
+        Ok(data) => detect_format(data).map_or(-1, |format| format as i32),
+        Err(_) => -1,
+    }
+}
+
+/// Decode any supported image to RGBA
+///
+/// The decoded size is stored in `width` and `height` whenever decoding
+/// succeeds, including when `out` is too small; it needs `width * height * 4`
+/// bytes. `auto_orient` applies the EXIF orientation tag.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, `out` to `out_capacity`
+/// writable bytes, and `width` and `height` must each be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn mconv_decode(data: *const u8, len: usize, auto_orient: bool, out: *mut u8, out_capacity: usize, width: *mut u32, height: *mut u32) -> i32 {
+    run(|| {
+        let data = input(data, len)?;
+        let image = decode_auto(data, &ConvertOptions { auto_orient }).map_err(|e| Error::new(MCONV_ERR_DECODE, e))?;
+        report(width, image.width);
+        report(height, image.height);
+        output(&image.data, out, out_capacity)
+    })
+}
+
+/// Encode a `width` x `height` RGBA image as `format`
+///
+/// The encoded size is stored in `out_len` whenever encoding succeeds,
+/// including when `out` is too small.
+///
+/// # Safety
+///
+/// `rgba` must point to `width * height * 4` readable bytes, `out` to
+/// `out_capacity` writable bytes, and `out_len` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn mconv_encode(rgba: *const u8, width: u32, height: u32, format: u32, out: *mut u8, out_capacity: usize, out_len: *mut usize) -> i32 {
+    run(|| {
+        let format = image_format(format)?;
+        let data = input_rgba(rgba, width, height)?.to_vec();
+        let encoded = encode(&RgbaImage { width, height, data }, format).map_err(|e| Error::new(MCONV_ERR_ENCODE, e))?;
+        report(out_len, encoded.len());
+        output(&encoded, out, out_capacity)
+    })
+}
+
+/// Decode any supported image and re-encode it as `format`
+///
+/// The converted size is stored in `out_len` whenever conversion succeeds,
+/// including when `out` is too small.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, `out` to `out_capacity`
+/// writable bytes, and `out_len` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn mconv_convert(data: *const u8, len: usize, format: u32, out: *mut u8, out_capacity: usize, out_len: *mut usize) -> i32 {
+    run(|| {
+        let format = image_format(format)?;
+        let image = decode_auto(input(data, len)?, &ConvertOptions::new()).map_err(|e| Error::new(MCONV_ERR_DECODE, e))?;
+        let encoded = encode(&image, format).map_err(|e| Error::new(MCONV_ERR_ENCODE, e))?;
+        report(out_len, encoded.len());
+        output(&encoded, out, out_capacity)
+    })
+}
+
+/// Resize a `width` x `height` RGBA image to `dst_width` x `dst_height`
+///
+/// `out` needs `dst_width * dst_height * 4` bytes.
+///
+/// # Safety
+///
+/// `rgba` must point to `width * height * 4` readable bytes and `out` to
+/// `out_capacity` writable bytes.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn mconv_resize(rgba: *const u8, width: u32, height: u32, dst_width: u32, dst_height: u32, algorithm: u32, out: *mut u8, out_capacity: usize) -> i32 {
+    run(|| {
+        let algorithm = resize_algorithm(algorithm)?;
+        let data = input_rgba(rgba, width, height)?;
+        let needed = rgba_len(dst_width, dst_height)?;
+        if needed > out_capacity {
+            return Err(Error::new(MCONV_ERR_BUFFER_TOO_SMALL, format!("Output needs {} bytes, buffer holds {}", needed, out_capacity)));
+        }
+        output(&resize(data, width, height, dst_width, dst_height, algorithm), out, out_capacity)
+    })
+}
+
+/// Copy the message of the last failed call on this thread into `buf`
+///
+/// The message is truncated to fit and always NUL-terminated when
+/// `capacity` is non-zero. Returns the full message length without the NUL,
+/// so a return value >= `capacity` means it was cut short.
+///
+/// # Safety
+///
+/// `buf` must point to `capacity` writable bytes (or be null with `capacity` 0).
+#[no_mangle]
+pub unsafe extern "C" fn mconv_last_error(buf: *mut c_char, capacity: usize) -> usize {
+    LAST_ERROR.with(|last| {
+        let message = last.borrow();
+        if !buf.is_null() && capacity > 0 {
+            let n = message.len().min(capacity - 1);
+            ptr::copy_nonoverlapping(message.as_ptr().cast::<c_char>(), buf, n);
+            *buf.add(n) = 0;
+        }
+        message.len()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let mut buf = [0 as c_char; 128];
+        let len = unsafe { mconv_last_error(buf.as_mut_ptr(), buf.len()) };
+        let bytes: Vec<u8> = buf[..len.min(127)].iter().map(|&c| c as u8).collect();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let rgba = [255, 0, 0, 255, 0, 255, 0, 128];
+        let mut len = 0;
+        unsafe {
+            assert_eq!(mconv_encode(rgba.as_ptr(), 2, 1, 2, ptr::null_mut(), 0, &mut len), MCONV_ERR_BUFFER_TOO_SMALL);
+            let mut png = vec![0; len];
+            assert_eq!(mconv_encode(rgba.as_ptr(), 2, 1, 2, png.as_mut_ptr(), png.len(), &mut len), MCONV_OK);
+            assert_eq!(mconv_detect_format(png.as_ptr(), png.len()), ImageFormat::Png as i32);
+
+            let (mut width, mut height) = (0, 0);
+            assert_eq!(mconv_decode(png.as_ptr(), png.len(), true, ptr::null_mut(), 0, &mut width, &mut height), MCONV_ERR_BUFFER_TOO_SMALL);
+            assert_eq!((width, height), (2, 1));
+            let mut decoded = [0; 8];
+            assert_eq!(mconv_decode(png.as_ptr(), png.len(), true, decoded.as_mut_ptr(), 8, &mut width, &mut height), MCONV_OK);
+            assert_eq!(decoded, rgba);
+        }
+    }
+
+    #[test]
+    fn test_convert_and_resize() {
+        let bmp = mconv_core::bmp::encode_bmp(2, 2, &[9; 16]).unwrap();
+        let mut out = vec![0; 1024];
+        let mut len = 0;
+        let mut resized = [0; 4];
+        unsafe {
+            assert_eq!(mconv_convert(bmp.as_ptr(), bmp.len(), 2, out.as_mut_ptr(), out.len(), &mut len), MCONV_OK);
+            assert_eq!(mconv_detect_format(out.as_ptr(), len), ImageFormat::Png as i32);
+            assert_eq!(mconv_resize([9; 16].as_ptr(), 2, 2, 1, 1, 0, resized.as_mut_ptr(), 4), MCONV_OK);
+        }
+        assert_eq!(resized, [9; 4]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut out = [0; 4];
+        unsafe {
+            assert_eq!(mconv_decode(ptr::null(), 4, true, out.as_mut_ptr(), 4, ptr::null_mut(), ptr::null_mut()), MCONV_ERR_NULL_POINTER);
+            assert_eq!(mconv_decode(b"junk".as_ptr(), 4, true, out.as_mut_ptr(), 4, ptr::null_mut(), ptr::null_mut()), MCONV_ERR_DECODE);
+            assert_eq!(last_error(), "Unknown or unsupported image format");
+            assert_eq!(mconv_encode(out.as_ptr(), 1, 1, 9, out.as_mut_ptr(), 4, ptr::null_mut()), MCONV_ERR_INVALID_ARGUMENT);
+            assert_eq!(mconv_encode(out.as_ptr(), 1, 1, 1, out.as_mut_ptr(), 4, ptr::null_mut()), MCONV_ERR_ENCODE);
+            assert_eq!(mconv_resize(out.as_ptr(), 1, 1, 0, 1, 0, out.as_mut_ptr(), 4), MCONV_ERR_INVALID_ARGUMENT);
+            assert_eq!(mconv_detect_format(ptr::null(), 0), -1);
+
+            let mut short = [0 as c_char; 4];
+            assert_eq!(mconv_last_error(short.as_mut_ptr(), 4), "Invalid size 0x1".len());
+            assert_eq!(short.map(|c| c as u8), *b"Inv\0");
+        }
+    }
+}
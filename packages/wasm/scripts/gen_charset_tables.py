@@ -0,0 +1,107 @@
+#!/usr/bin/env python3
+"""Generate the legacy charset tables embedded by core/src/text/charset.
+
+Tables are little-endian u16 code points indexed by WHATWG pointers, with 0
+marking unmapped pointers. CJK tables come from the WHATWG Encoding Standard
+indexes, downloaded unless a directory holding the index-*.txt files is
+given; single-byte tables come from Python's codecs. Run from packages/wasm:
+
+    python3 scripts/gen_charset_tables.py [index-dir]
+"""
+
+import os
+import struct
+import sys
+import urllib.request
+
+OUT = os.path.join(os.path.dirname(__file__), '..', 'core', 'src', 'text', 'charset', 'tables')
+INDEX_URL = 'https://encoding.spec.whatwg.org/index-{}.txt'
+
+SINGLE_BYTE = ['cp1252'] + [f'iso8859_{n}' for n in (2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 14, 15, 16)] + ['cp874', 'cp1254']
+
+
+def decode(data, codec):
+    try:
+        text = data.decode(codec)
+    except UnicodeDecodeError:
+        return 0
+    return ord(text) if len(text) == 1 and ord(text) <= 0xFFFF else 0
+
+
+def read_index(name):
+    """(pointer, code point) pairs of a WHATWG index"""
+    if len(sys.argv) > 1:
+        with open(os.path.join(sys.argv[1], f'index-{name}.txt'), encoding='utf-8') as f:
+            text = f.read()
+    else:
+        with urllib.request.urlopen(INDEX_URL.format(name)) as response:
+            text = response.read().decode('utf-8')
+    for line in text.splitlines():
+        fields = line.split()
+        if fields and not fields[0].startswith('#'):
+            yield int(fields[0]), int(fields[1], 16)
+
+
+def index_table(name, size):
+    values = [0] * size
+    for pointer, cp in read_index(name):
+        values[pointer] = cp
+    return values
+
+
+def write_u16(name, values):
+    with open(os.path.join(OUT, name), 'wb') as f:
+        f.write(struct.pack(f'<{len(values)}H', *values))
+
+
+def single_byte():
+    # Upper halves (0x80-0xFF) of each code page, concatenated
+    values = []
+    for codec in SINGLE_BYTE:
+        for b in range(0x80, 0x100):
+            cp = decode(bytes([b]), codec)
+            # WHATWG maps the undefined C1 bytes of Windows code pages to C1 controls
+            values.append(cp or (b if codec.startswith('cp') and b < 0xA0 else 0))
+    write_u16('single_byte.bin', values)
+
+
+def jis0208():
+    # Shared by Shift_JIS (lead * 188 + trail) and EUC-JP (row * 94 + cell);
+    # the Shift_JIS decoder maps the user-defined pointers 8836-10715 itself
+    write_u16('jis0208.bin', index_table('jis0208', 11280))
+
+
+def jis0212():
+    write_u16('jis0212.bin', index_table('jis0212', 94 * 94))
+
+
+def gb18030():
+    write_u16('gb18030.bin', index_table('gb18030', 126 * 190))
+
+    # Four-byte BMP sequences as (pointer, code point) runs
+    with open(os.path.join(OUT, 'gb18030_ranges.bin'), 'wb') as f:
+        for pointer, cp in read_index('gb18030-ranges'):
+            f.write(struct.pack('<II', pointer, cp))
+
+
+def big5():
+    # HKSCS code points past the BMP are all in plane 2: keep the low 16 bits
+    # and set the pointer's bit in big5_astral.bin
+    values = index_table('big5', 126 * 157)
+    astral = bytearray((len(values) + 7) // 8)
+    for pointer, cp in enumerate(values):
+        if cp > 0xFFFF:
+            assert cp >> 16 == 2, hex(cp)
+            astral[pointer // 8] |= 1 << pointer % 8
+    write_u16('big5.bin', [cp & 0xFFFF for cp in values])
+    with open(os.path.join(OUT, 'big5_astral.bin'), 'wb') as f:
+        f.write(astral)
+
+
+if __name__ == '__main__':
+    os.makedirs(OUT, exist_ok=True)
+    single_byte()
+    jis0208()
+    jis0212()
+    gb18030()
+    big5()
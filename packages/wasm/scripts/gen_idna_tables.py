@@ -0,0 +1,123 @@
+#!/usr/bin/env python3
+"""Generate the IDNA mapping tables embedded by core/src/text/punycode.rs.
+
+Both tables are sorted runs of little-endian u32 (first, last, value):
+
+- idna_map.bin holds the UTS #46 mapping of every code point whose
+  NFKC_Casefold differs from its lowercase. A value below 0x80000000 maps
+  the run onto consecutive code points starting there; otherwise its low
+  bits are (offset << 8 | length) of a UTF-8 string in idna_strings.bin that
+  every code point of the run maps to.
+- idna_props.bin holds, per run, the canonical combining class in the low
+  byte, 0x100 for code points that may compose with what precedes them
+  (NFC_Quick_Check = Maybe) and 0x200 for ones UTS #46 disallows: controls,
+  private use and code points unassigned in this Unicode version.
+
+Data comes from Python's unicodedata, so it follows that Unicode version.
+Run from packages/wasm:
+
+    python3 scripts/gen_idna_tables.py
+"""
+
+import os
+import struct
+import unicodedata
+
+OUT = os.path.join(os.path.dirname(__file__), '..', 'core', 'src', 'text', 'tables')
+
+# Default_Ignorable_Code_Point, which NFKC_Casefold removes
+IGNORABLE = [
+    (0x00AD, 0x00AD), (0x034F, 0x034F), (0x061C, 0x061C), (0x115F, 0x1160), (0x17B4, 0x17B5), (0x180B, 0x180F),
+    (0x200B, 0x200F), (0x202A, 0x202E), (0x2060, 0x206F), (0x3164, 0x3164), (0xFE00, 0xFE0F), (0xFEFF, 0xFEFF),
+    (0xFFA0, 0xFFA0), (0xFFF0, 0xFFF8), (0x1BCA0, 0x1BCA3), (0x1D173, 0x1D17A), (0xE0000, 0xE0FFF),
+]
+# Deviations that nontransitional processing, as in browsers, keeps as is
+DEVIATIONS = {0x00DF, 0x03C2, 0x200C, 0x200D}
+
+
+def ignorable(cp):
+    return any(first <= cp <= last for first, last in IGNORABLE)
+
+
+def nfkc_casefold(c):
+    s = unicodedata.normalize('NFKC', unicodedata.normalize('NFKC', c).casefold())
+    return ''.join(ch for ch in s if not ignorable(ord(ch)))
+
+
+def code_points():
+    for cp in range(0x80, 0x110000):
+        if not 0xD800 <= cp < 0xE000 and unicodedata.category(chr(cp)) != 'Cn':
+            yield cp
+
+
+def disallowed(cp):
+    return 0xD800 <= cp < 0xE000 or unicodedata.category(chr(cp)) in ('Cn', 'Co', 'Cc')
+
+
+def runs(values):
+    """Merge (code point, value) pairs into (first, last, value) runs;
+    `step` values advance by one per code point"""
+    out = []
+    for cp, value, step in values:
+        if out and out[-1][1] + 1 == cp and out[-1][3] == step and out[-1][2] + step * (cp - out[-1][0]) == value:
+            out[-1][1] = cp
+        else:
+            out.append([cp, cp, value, step])
+    return [(first, last, value) for first, last, value, _ in out]
+
+
+def write_runs(name, values):
+    with open(os.path.join(OUT, name), 'wb') as f:
+        for run in runs(values):
+            f.write(struct.pack('<III', *run))
+
+
+def mapping():
+    strings = bytearray()
+    offsets = {}
+    values = []
+    for cp in code_points():
+        if cp in DEVIATIONS:
+            continue
+        c = chr(cp)
+        # UTS #46 maps capital sharp s to the deviation ß rather than ss
+        mapped = '\u00df' if cp == 0x1E9E else nfkc_casefold(c)
+        if mapped == c.lower():
+            continue
+        if len(mapped) == 1:
+            values.append((cp, ord(mapped), 1))
+            continue
+        encoded = mapped.encode('utf-8')
+        if encoded not in offsets:
+            offsets[encoded] = len(strings)
+            strings += encoded
+        values.append((cp, 0x80000000 | offsets[encoded] << 8 | len(encoded), 0))
+    write_runs('idna_map.bin', values)
+    with open(os.path.join(OUT, 'idna_strings.bin'), 'wb') as f:
+        f.write(strings)
+
+
+def props():
+    # Second halves of the pairs that canonical composition recombines
+    composes = set()
+    for cp in code_points():
+        parts = unicodedata.decomposition(chr(cp)).split()
+        if len(parts) == 2 and not parts[0].startswith('<') and unicodedata.normalize('NFC', chr(cp)) == chr(cp):
+            composes.add(int(parts[1], 16))
+    # Hangul vowels and trailing consonants compose algorithmically
+    composes.update(range(0x1161, 0x1176), range(0x11A8, 0x11C3))
+    values = []
+    for cp in range(0x80, 0x110000):
+        if disallowed(cp):
+            values.append((cp, 0x200, 0))
+            continue
+        info = unicodedata.combining(chr(cp)) | (0x100 if cp in composes else 0)
+        if info:
+            values.append((cp, info, 0))
+    write_runs('idna_props.bin', values)
+
+
+if __name__ == '__main__':
+    os.makedirs(OUT, exist_ok=True)
+    mapping()
+    props()
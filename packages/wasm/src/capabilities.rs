@@ -0,0 +1,104 @@
+//! What the module was built with and what the engine can run
+//!
+//! A WebAssembly module is validated as a whole, so SIMD code cannot sit
+//! behind a runtime check: the loader in ts/loader.ts validates the same
+//! probe modules before loading and picks the matching build (`build`,
+//! `build:simd`, `build:threads`), and `capabilities()` reports what the
+//! loaded build and engine support. Within a build, parallel paths check
+//! `parallel()` on every call and fall back to running on the calling thread
+//! when no pool can be used.
+
+use wasm_bindgen::prelude::*;
+
+/// WebAssembly proposals that matter for speed
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeatureSet {
+    /// 128-bit SIMD
+    pub simd: bool,
+    /// Shared memory and atomics (and, at runtime, `SharedArrayBuffer`)
+    pub threads: bool,
+    #[wasm_bindgen(js_name = bulkMemory)]
+    pub bulk_memory: bool,
+}
+
+/// Result of `capabilities()`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Features this module was compiled with
+    pub built: FeatureSet,
+    /// Features the engine supports
+    pub runtime: FeatureSet,
+    /// Whether parallel code paths currently run on a thread pool
+    pub parallel: bool,
+}
+
+/// `(module (func (result v128) i32.const 0 i8x16.splat i8x16.popcnt))`
+#[cfg(target_arch = "wasm32")]
+const SIMD_PROBE: &[u8] = &[0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 123, 3, 2, 1, 0, 10, 10, 1, 8, 0, 65, 0, 253, 15, 253, 98, 11];
+/// `(module (memory 1 1 shared) (func i32.const 0 i32.atomic.load drop))`
+#[cfg(target_arch = "wasm32")]
+const THREADS_PROBE: &[u8] = &[0, 97, 115, 109, 1, 0, 0, 0, 1, 4, 1, 96, 0, 0, 3, 2, 1, 0, 5, 4, 1, 3, 1, 1, 10, 11, 1, 9, 0, 65, 0, 254, 16, 2, 0, 26, 11];
+/// `(module (memory 1) (func i32.const 0 i32.const 0 i32.const 0 memory.copy))`
+#[cfg(target_arch = "wasm32")]
+const BULK_MEMORY_PROBE: &[u8] = &[0, 97, 115, 109, 1, 0, 0, 0, 1, 4, 1, 96, 0, 0, 3, 2, 1, 0, 5, 3, 1, 0, 1, 10, 14, 1, 12, 0, 65, 0, 65, 0, 65, 0, 252, 10, 0, 0, 11];
+
+/// Features enabled when this module was compiled
+pub fn built() -> FeatureSet {
+    FeatureSet { simd: cfg!(target_feature = "simd128"), threads: cfg!(feature = "threads"), bulk_memory: cfg!(target_feature = "bulk-memory") }
+}
+
+/// Features the engine supports, found by validating tiny probe modules;
+/// threads also need `SharedArrayBuffer` and, on the web, a cross-origin
+/// isolated page
+#[cfg(target_arch = "wasm32")]
+pub fn runtime() -> FeatureSet {
+    let validates = |probe: &[u8]| js_sys::WebAssembly::validate(&js_sys::Uint8Array::from(probe).into()).unwrap_or(false);
+    let global = js_sys::global();
+    let property = |name: &str| js_sys::Reflect::get(&global, &name.into()).unwrap_or(JsValue::UNDEFINED);
+    // Node has no `crossOriginIsolated` and never needs it
+    let isolated = property("crossOriginIsolated").as_bool().unwrap_or(true);
+    let shared = !property("SharedArrayBuffer").is_undefined() && isolated;
+    FeatureSet { simd: validates(SIMD_PROBE), threads: shared && validates(THREADS_PROBE), bulk_memory: validates(BULK_MEMORY_PROBE) }
+}
+
+/// Native code has none of these limits
+#[cfg(not(target_arch = "wasm32"))]
+pub fn runtime() -> FeatureSet {
+    FeatureSet { simd: true, threads: true, bulk_memory: true }
+}
+
+/// Whether parallel code paths can use a thread pool right now: built with
+/// the `threads` feature and either on a pool thread or with the global pool
+/// started (natively it starts on demand), but never on the browser main
+/// thread, which cannot block
+pub fn parallel() -> bool {
+    #[cfg(feature = "threads")]
+    {
+        mconv_core::parallel::available()
+    }
+    #[cfg(not(feature = "threads"))]
+    {
+        false
+    }
+}
+
+/// Report build-time and runtime features
+#[wasm_bindgen]
+pub fn capabilities() -> Capabilities {
+    Capabilities { built: built(), runtime: runtime(), parallel: parallel() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let caps = capabilities();
+        assert_eq!(caps.built.threads, cfg!(feature = "threads"));
+        assert_eq!(caps.parallel, cfg!(feature = "threads"));
+        assert!(caps.runtime.simd && caps.runtime.threads);
+    }
+}
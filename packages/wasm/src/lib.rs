@@ -1,13 +1,45 @@
 //! mconv-wasm: Pure Rust codec implementations compiled to WebAssembly
 //!
-//! Zero external dependencies for codec implementations.
-//! All codecs written from scratch.
+//! The codecs live in mconv-core, whose `wasm` feature carries their
+//! wasm-bindgen exports; this crate re-exports them and adds what only makes
+//! sense in a JS runtime: WebCodecs interop, worker threads and the
+//! background scheduler. Codec features pass through to mconv-core.
 
 use wasm_bindgen::prelude::*;
 
-pub mod bmp;
-pub mod resize;
+#[cfg(feature = "archive")]
+pub use mconv_core::archive;
+#[cfg(feature = "audio")]
+pub use mconv_core::audio;
+#[cfg(feature = "barcode")]
+pub use mconv_core::barcode;
+#[cfg(feature = "bmp")]
+pub use mconv_core::bmp;
+#[cfg(feature = "gif")]
+pub use mconv_core::gif;
+#[cfg(feature = "jpeg")]
+pub use mconv_core::jpeg;
+#[cfg(feature = "png")]
+pub use mconv_core::png;
+#[cfg(feature = "video")]
+pub use mconv_core::video;
+#[cfg(feature = "webp")]
+pub use mconv_core::webp;
+pub use mconv_core::{
+    animation, binary, checksum, color, compare, compose, compress, convert, draw, ecc, filters, font, hash, histogram, icc, limits, lut, memory, metadata, placeholder, pool, probe, quantize, raw, resize, text,
+    transform,
+};
+
+pub mod capabilities;
+pub mod scheduler;
+#[cfg(feature = "threads")]
+pub mod threads;
 pub mod utils;
+pub mod webcodecs;
+
+/// Count every allocation so `memoryStats` can report heap use
+#[global_allocator]
+static ALLOCATOR: memory::TrackingAllocator = memory::TrackingAllocator;
 
 /// Initialize the WASM module
 #[wasm_bindgen(start)]
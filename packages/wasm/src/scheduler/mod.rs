@@ -0,0 +1,265 @@
+//! Conversion queue with priorities, cancellation and progress events
+//!
+//! Jobs run highest priority first, at most `concurrency` at a time, on the
+//! global pool (see `initThreadPool`). Without a pool they run one at a time
+//! on the calling thread, yielding to the event loop between jobs so the
+//! page stays responsive.
+
+mod queue;
+
+pub use queue::{Job, JobQueue};
+
+use crate::capabilities::parallel;
+use crate::convert::{convert, ConvertOptions, ImageFormat};
+use crate::utils::sleep;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::prelude::*;
+
+/// What happened to a job
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Started,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A progress event, as passed to the `onProgress` listener
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobEvent {
+    pub id: u32,
+    pub status: JobStatus,
+    /// The converted file, for `Completed`
+    pub data: Option<Vec<u8>>,
+    /// Why the job failed, for `Failed`
+    pub error: Option<String>,
+    /// Jobs still queued
+    pub pending: u32,
+    /// Jobs running
+    pub running: u32,
+}
+
+type Slot = Arc<Mutex<Option<Result<Vec<u8>, String>>>>;
+
+/// Start converting a job, on the pool when there is one
+fn start(job: Job) -> Slot {
+    let slot = Slot::default();
+    let filled = slot.clone();
+    let run = move || *filled.lock().unwrap() = Some(convert(&job.data, job.format, &job.options));
+    #[cfg(feature = "threads")]
+    if parallel() {
+        rayon::spawn(run);
+        return slot;
+    }
+    run();
+    slot
+}
+
+/// Jobs and listener, shared with the task that drives them
+struct State {
+    queue: JobQueue,
+    running: Vec<(u32, Slot)>,
+    concurrency: usize,
+    listener: Option<js_sys::Function>,
+    driving: bool,
+}
+
+impl State {
+    fn event(&self, id: u32, status: JobStatus) -> JobEvent {
+        JobEvent { id, status, data: None, error: None, pending: self.queue.len() as u32, running: self.running.len() as u32 }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.queue.is_empty() && self.running.is_empty()
+    }
+}
+
+/// Runs conversions in the background in priority order
+#[wasm_bindgen]
+pub struct Scheduler {
+    state: Rc<RefCell<State>>,
+}
+
+impl Scheduler {
+    /// A scheduler running up to `concurrency` jobs at once (one while no
+    /// pool is available)
+    pub fn new(concurrency: usize) -> Result<Scheduler, String> {
+        if concurrency == 0 {
+            return Err("Scheduler needs a concurrency of at least 1".to_string());
+        }
+        let state = State { queue: JobQueue::new(), running: Vec::new(), concurrency, listener: None, driving: false };
+        Ok(Scheduler { state: Rc::new(RefCell::new(state)) })
+    }
+
+    /// Queue a conversion, returning its job id; it runs on the next `step`
+    pub fn enqueue(&self, data: Vec<u8>, format: ImageFormat, options: ConvertOptions, priority: i32) -> u32 {
+        self.state.borrow_mut().queue.push(data, format, options, priority)
+    }
+
+    /// Drop a queued or running job; a running conversion finishes but its
+    /// result is discarded. `None` if the job is unknown or already done.
+    pub fn cancel(&self, id: u32) -> Option<JobEvent> {
+        let mut state = self.state.borrow_mut();
+        let before = state.running.len();
+        state.running.retain(|(running, _)| *running != id);
+        (state.queue.cancel(id) || state.running.len() != before).then(|| state.event(id, JobStatus::Cancelled))
+    }
+
+    /// Collect finished jobs, then start queued ones up to the concurrency
+    /// limit, returning what happened
+    pub fn step(&self) -> Vec<JobEvent> {
+        let mut state = self.state.borrow_mut();
+        let mut events = Vec::new();
+        let mut i = 0;
+        while i < state.running.len() {
+            let Some(result) = state.running[i].1.lock().unwrap().take() else {
+                i += 1;
+                continue;
+            };
+            let (id, _) = state.running.remove(i);
+            events.push(match result {
+                Ok(data) => JobEvent { data: Some(data), ..state.event(id, JobStatus::Completed) },
+                Err(error) => JobEvent { error: Some(error), ..state.event(id, JobStatus::Failed) },
+            });
+        }
+        let limit = if parallel() { state.concurrency } else { 1 };
+        while state.running.len() < limit {
+            let Some(job) = state.queue.pop() else { break };
+            let id = job.id;
+            state.running.push((id, start(job)));
+            events.push(state.event(id, JobStatus::Started));
+        }
+        events
+    }
+
+    pub fn pending(&self) -> usize {
+        self.state.borrow().queue.len()
+    }
+
+    pub fn running(&self) -> usize {
+        self.state.borrow().running.len()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.state.borrow().is_idle()
+    }
+
+    /// Pass events to the listener; the state is not borrowed meanwhile, so
+    /// the listener may enqueue or cancel. Listener errors are ignored.
+    fn emit(&self, events: Vec<JobEvent>) {
+        let listener = self.state.borrow().listener.clone();
+        if let Some(listener) = listener {
+            for event in events {
+                let _ = listener.call1(&JsValue::NULL, &event.into());
+            }
+        }
+    }
+
+    /// Step until idle on the event loop, unless that is already happening
+    fn drive(&self) {
+        if std::mem::replace(&mut self.state.borrow_mut().driving, true) {
+            return;
+        }
+        let scheduler = Scheduler { state: self.state.clone() };
+        wasm_bindgen_futures::spawn_local(async move {
+            loop {
+                let events = scheduler.step();
+                scheduler.emit(events);
+                let wait = {
+                    let mut state = scheduler.state.borrow_mut();
+                    if state.is_idle() {
+                        state.driving = false;
+                        return;
+                    }
+                    // Poll pool jobs gently; between inline jobs just yield
+                    if state.running.is_empty() || !parallel() { 0 } else { 4 }
+                };
+                sleep(wait).await;
+            }
+        });
+    }
+}
+
+#[wasm_bindgen]
+impl Scheduler {
+    /// A scheduler running up to `concurrency` jobs at once
+    #[wasm_bindgen(constructor)]
+    pub fn new_js(concurrency: usize) -> Result<Scheduler, JsError> {
+        Scheduler::new(concurrency).map_err(|e| JsError::new(&e))
+    }
+
+    /// Queue a conversion (higher `priority` runs first), returning its id
+    #[wasm_bindgen(js_name = enqueue)]
+    pub fn enqueue_js(&self, data: Vec<u8>, format: ImageFormat, options: &ConvertOptions, priority: i32) -> u32 {
+        let id = self.enqueue(data, format, *options, priority);
+        self.drive();
+        id
+    }
+
+    /// Cancel a queued or running job; false if it already finished
+    #[wasm_bindgen(js_name = cancel)]
+    pub fn cancel_js(&self, id: u32) -> bool {
+        let event = self.cancel(id);
+        let cancelled = event.is_some();
+        self.emit(event.into_iter().collect());
+        cancelled
+    }
+
+    /// Function called with a `JobEvent` as jobs start, finish, fail or are
+    /// cancelled
+    #[wasm_bindgen(setter, js_name = onProgress)]
+    pub fn set_on_progress(&self, listener: Option<js_sys::Function>) {
+        self.state.borrow_mut().listener = listener;
+    }
+
+    #[wasm_bindgen(getter, js_name = pending)]
+    pub fn pending_js(&self) -> usize {
+        self.pending()
+    }
+
+    #[wasm_bindgen(getter, js_name = running)]
+    pub fn running_js(&self) -> usize {
+        self.running()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheduler() {
+        let scheduler = Scheduler::new(1).unwrap();
+        let bmp = crate::bmp::encode_bmp(1, 1, &[1, 2, 3, 255]).unwrap();
+        let options = ConvertOptions::new();
+        let low = scheduler.enqueue(bmp.clone(), ImageFormat::Png, options, 0);
+        let junk = scheduler.enqueue(b"junk".to_vec(), ImageFormat::Png, options, 1);
+        let dropped = scheduler.enqueue(bmp.clone(), ImageFormat::Png, options, 2);
+        let high = scheduler.enqueue(bmp, ImageFormat::Png, options, 2);
+        assert_eq!(scheduler.cancel(dropped).map(|e| (e.status, e.pending)), Some((JobStatus::Cancelled, 3)));
+        assert_eq!(scheduler.cancel(dropped), None);
+
+        let mut events = Vec::new();
+        while !scheduler.is_idle() {
+            events.extend(scheduler.step());
+        }
+        let summary: Vec<_> = events.iter().map(|e| (e.id, e.status, e.pending, e.running)).collect();
+        assert_eq!(
+            summary,
+            [
+                (high, JobStatus::Started, 2, 1),
+                (high, JobStatus::Completed, 2, 0),
+                (junk, JobStatus::Started, 1, 1),
+                (junk, JobStatus::Failed, 1, 0),
+                (low, JobStatus::Started, 0, 1),
+                (low, JobStatus::Completed, 0, 0),
+            ]
+        );
+        assert!(events[1].data.is_some() && events[3].error.is_some());
+        assert!(Scheduler::new(0).is_err());
+    }
+}
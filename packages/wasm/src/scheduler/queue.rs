@@ -0,0 +1,77 @@
+//! Priority queue of pending conversions
+
+use crate::convert::{ConvertOptions, ImageFormat};
+
+/// A conversion waiting to run
+#[derive(Clone, Debug, PartialEq)]
+pub struct Job {
+    pub id: u32,
+    pub priority: i32,
+    pub data: Vec<u8>,
+    pub format: ImageFormat,
+    pub options: ConvertOptions,
+}
+
+/// Pending jobs, taken highest priority first and in submission order among
+/// equal priorities
+///
+/// A plain list rather than a heap: queues are short and jobs can be
+/// cancelled from anywhere in them.
+#[derive(Debug, Default)]
+pub struct JobQueue {
+    jobs: Vec<Job>,
+    next_id: u32,
+}
+
+impl JobQueue {
+    pub fn new() -> JobQueue {
+        JobQueue::default()
+    }
+
+    /// Add a job, returning its id (ids start at 1 and are never reused)
+    pub fn push(&mut self, data: Vec<u8>, format: ImageFormat, options: ConvertOptions, priority: i32) -> u32 {
+        self.next_id += 1;
+        self.jobs.push(Job { id: self.next_id, priority, data, format, options });
+        self.next_id
+    }
+
+    /// Take the next job to run
+    pub fn pop(&mut self) -> Option<Job> {
+        let (index, _) = self.jobs.iter().enumerate().max_by_key(|(_, job)| (job.priority, std::cmp::Reverse(job.id)))?;
+        Some(self.jobs.remove(index))
+    }
+
+    /// Remove a pending job; false if it is not queued
+    pub fn cancel(&mut self, id: u32) -> bool {
+        let before = self.jobs.len();
+        self.jobs.retain(|job| job.id != id);
+        self.jobs.len() != before
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_order() {
+        let mut queue = JobQueue::new();
+        let options = ConvertOptions::new();
+        let ids: Vec<u32> = [0, 5, 0, 5, -1].into_iter().map(|priority| queue.push(vec![], ImageFormat::Png, options, priority)).collect();
+        assert_eq!(ids, [1, 2, 3, 4, 5]);
+        assert!(queue.cancel(3));
+        assert!(!queue.cancel(3));
+        assert_eq!(queue.len(), 4);
+        let order: Vec<u32> = std::iter::from_fn(|| queue.pop()).map(|job| job.id).collect();
+        assert_eq!(order, [2, 4, 1, 5]);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! Worker pool for parallel conversion (the `threads` feature)
+//!
+//! Pool threads are rayon threads, each running in a Web Worker that
+//! instantiates this module on the same shared memory. This needs the
+//! `build:threads` build (atomics and bulk memory) and a cross-origin
+//! isolated page, so that `SharedArrayBuffer` is available.
+
+use crate::convert::{convert_batch, BatchResult, ConvertOptions, ImageFormat};
+use crate::utils::sleep;
+use js_sys::{Array, Promise, Uint8Array};
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(module = "/src/threads/worker.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = spawnWorker, catch)]
+    fn spawn_worker(module: &JsValue, memory: &JsValue, builder: u32) -> Result<(), JsValue>;
+}
+
+/// Entry point for a pool worker: run the rayon thread handed over by
+/// `spawnWorker` until the pool shuts down
+#[wasm_bindgen(js_name = startWorker)]
+pub fn start_worker(builder: u32) {
+    // SAFETY: `builder` came from `Box::into_raw` in the spawn handler and is
+    // handed to exactly one worker
+    let thread = unsafe { Box::from_raw(builder as *mut rayon::ThreadBuilder) };
+    thread.run();
+}
+
+/// Start one pool thread in a new Web Worker
+#[cfg(target_arch = "wasm32")]
+fn spawn(thread: rayon::ThreadBuilder) -> std::io::Result<()> {
+    let pointer = Box::into_raw(Box::new(thread));
+    spawn_worker(&wasm_bindgen::module(), &wasm_bindgen::memory(), pointer as u32).map_err(|_| {
+        // SAFETY: the worker was not created, so the pointer is still ours
+        drop(unsafe { Box::from_raw(pointer) });
+        std::io::Error::other("Cannot start a pool worker")
+    })
+}
+
+/// Start the global pool of `threads` workers, used by `convertBatch` and
+/// other parallel functions called outside a `ThreadPool`; call it once
+#[wasm_bindgen(js_name = initThreadPool)]
+pub fn init_thread_pool(threads: usize) -> Result<(), JsError> {
+    let builder = rayon::ThreadPoolBuilder::new().num_threads(threads);
+    #[cfg(target_arch = "wasm32")]
+    let builder = builder.spawn_handler(spawn);
+    builder.build_global().map_err(|e| JsError::new(&e.to_string()))?;
+    mconv_core::parallel::mark_pool_started();
+    Ok(())
+}
+
+/// A pool of worker threads sharing this module's memory
+#[wasm_bindgen]
+pub struct ThreadPool {
+    pool: Arc<rayon::ThreadPool>,
+}
+
+impl ThreadPool {
+    /// Start a pool of `threads` workers (natively, of OS threads)
+    pub fn new(threads: usize) -> Result<ThreadPool, String> {
+        if threads == 0 {
+            return Err("Thread pool needs at least one thread".to_string());
+        }
+        let builder = rayon::ThreadPoolBuilder::new().num_threads(threads);
+        #[cfg(target_arch = "wasm32")]
+        let builder = builder.spawn_handler(spawn);
+        let pool = builder.build().map_err(|e| e.to_string())?;
+        Ok(ThreadPool { pool: Arc::new(pool) })
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Convert every input on the pool, blocking until all are done
+    pub fn convert_all(&self, inputs: &[Vec<u8>], format: ImageFormat, options: &ConvertOptions) -> Vec<BatchResult> {
+        self.pool.install(|| convert_batch(inputs, format, options))
+    }
+}
+
+#[wasm_bindgen]
+impl ThreadPool {
+    /// Start a pool of `threads` workers
+    #[wasm_bindgen(constructor)]
+    pub fn new_js(threads: usize) -> Result<ThreadPool, JsError> {
+        ThreadPool::new(threads).map_err(|e| JsError::new(&e))
+    }
+
+    #[wasm_bindgen(getter, js_name = threads)]
+    pub fn threads_js(&self) -> usize {
+        self.threads()
+    }
+
+    /// Convert an array of encoded images on the pool without blocking the
+    /// calling thread
+    ///
+    /// Resolves to one `BatchResult` per input, in order. Safe to call from
+    /// the main thread, which polls for completion instead of waiting on a
+    /// lock.
+    #[wasm_bindgen(js_name = submitConvert)]
+    pub fn submit_convert(&self, inputs: Array, format: ImageFormat, options: &ConvertOptions) -> Promise {
+        let pool = self.pool.clone();
+        let options = *options;
+        // JS values stay on this thread; workers get plain bytes
+        let inputs: Vec<Vec<u8>> = inputs.iter().map(|input| Uint8Array::new(&input).to_vec()).collect();
+        let slot = Arc::new(Mutex::new(None));
+        let filled = slot.clone();
+        pool.spawn(move || *filled.lock().unwrap() = Some(convert_batch(&inputs, format, &options)));
+
+        future_to_promise(async move {
+            let results = loop {
+                if let Some(results) = slot.lock().unwrap().take() {
+                    break results;
+                }
+                sleep(4).await;
+            };
+            Ok(results.into_iter().map(JsValue::from).collect::<Array>().into())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::decode_auto;
+
+    #[test]
+    fn test_convert_all() {
+        let pool = ThreadPool::new(3).unwrap();
+        assert_eq!(pool.threads(), 3);
+        let inputs: Vec<Vec<u8>> = (1..=5u8).map(|i| crate::bmp::encode_bmp(i as u32, 2, &[i; 4].repeat(i as usize * 2)).unwrap()).chain([b"junk".to_vec()]).collect();
+        let results = pool.convert_all(&inputs, ImageFormat::Png, &ConvertOptions::new());
+        for (i, result) in results[..5].iter().enumerate() {
+            let image = decode_auto(result.data.as_ref().unwrap(), &ConvertOptions::new()).unwrap();
+            assert_eq!((image.width, image.height), (i as u32 + 1, 2));
+        }
+        assert!(!results[5].ok());
+        assert!(ThreadPool::new(0).is_err());
+    }
+}
@@ -0,0 +1,22 @@
+// Web Worker bootstrap for the `threads` feature's pool
+//
+// The main module calls `spawnWorker` once per pool thread. Each worker loads
+// this same file, instantiates the module on the shared memory and runs its
+// rayon thread until the pool shuts down.
+
+const INIT = 'mconv-pool-worker-init'
+
+export function spawnWorker(module, memory, builder) {
+	const worker = new Worker(new URL('./worker.js', import.meta.url), { type: 'module' })
+	worker.postMessage({ type: INIT, module, memory, builder })
+}
+
+if (typeof WorkerGlobalScope !== 'undefined' && self instanceof WorkerGlobalScope) {
+	self.addEventListener('message', async ({ data }) => {
+		if (data?.type !== INIT) return
+		// From pkg/snippets/<crate>/src/threads/ back to the generated glue
+		const pkg = await import('../../../../mconv_wasm.js')
+		await pkg.default({ module_or_path: data.module, memory: data.memory })
+		pkg.startWorker(data.builder)
+	})
+}
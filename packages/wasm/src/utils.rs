@@ -1,53 +1,23 @@
 //! Utility functions for WASM
 
-/// Set panic hook for better error messages in console
-pub fn set_panic_hook() {
-    // Panic hook can be enabled by adding console_error_panic_hook crate
-    // For now, this is a no-op
-}
-
-/// Read u16 little-endian from slice
-#[inline]
-pub fn read_u16_le(data: &[u8], offset: usize) -> u16 {
-    u16::from_le_bytes([data[offset], data[offset + 1]])
-}
+pub use mconv_core::utils::*;
 
-/// Read u32 little-endian from slice
-#[inline]
-pub fn read_u32_le(data: &[u8], offset: usize) -> u32 {
-    u32::from_le_bytes([
-        data[offset],
-        data[offset + 1],
-        data[offset + 2],
-        data[offset + 3],
-    ])
-}
+use wasm_bindgen::prelude::*;
 
-/// Read i32 little-endian from slice
-#[inline]
-pub fn read_i32_le(data: &[u8], offset: usize) -> i32 {
-    i32::from_le_bytes([
-        data[offset],
-        data[offset + 1],
-        data[offset + 2],
-        data[offset + 3],
-    ])
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(callback: &js_sys::Function, ms: i32);
 }
 
-/// Write u16 little-endian to slice
-#[inline]
-pub fn write_u16_le(data: &mut [u8], offset: usize, value: u16) {
-    let bytes = value.to_le_bytes();
-    data[offset] = bytes[0];
-    data[offset + 1] = bytes[1];
+/// Set panic hook for better error messages in console
+pub fn set_panic_hook() {
+    // Panic hook can be enabled by adding console_error_panic_hook crate
+    // For now, this is a no-op
 }
 
-/// Write u32 little-endian to slice
-#[inline]
-pub fn write_u32_le(data: &mut [u8], offset: usize, value: u32) {
-    let bytes = value.to_le_bytes();
-    data[offset] = bytes[0];
-    data[offset + 1] = bytes[1];
-    data[offset + 2] = bytes[2];
-    data[offset + 3] = bytes[3];
+/// Resolve after `ms` milliseconds, yielding to the event loop
+pub async fn sleep(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| set_timeout(&resolve, ms));
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
 }
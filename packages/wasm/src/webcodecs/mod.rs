@@ -0,0 +1,159 @@
+//! WebCodecs integration: `VideoFrame` and `ImageBitmap` input
+//!
+//! Frames are copied with `VideoFrame.copyTo` straight into a buffer in WASM
+//! memory, in their native pixel format, then normalized to RGBA here. An
+//! `ImageBitmap` is wrapped in a temporary `VideoFrame` to take the same path.
+
+use crate::color::space::YCbCrMatrix;
+use crate::convert::{encode, ImageFormat};
+use crate::raw::{import_raw, RawFormat};
+use crate::transform::RgbaImage;
+use js_sys::{Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+#[wasm_bindgen]
+extern "C" {
+    /// A WebCodecs video frame, e.g. from a `VideoDecoder` or a camera's
+    /// `MediaStreamTrackProcessor`
+    pub type VideoFrame;
+
+    #[wasm_bindgen(constructor, catch)]
+    fn from_bitmap(image: &ImageBitmap, init: &Object) -> Result<VideoFrame, JsValue>;
+    #[wasm_bindgen(method, getter)]
+    fn format(this: &VideoFrame) -> Option<String>;
+    #[wasm_bindgen(method, getter, js_name = visibleRect)]
+    fn visible_rect(this: &VideoFrame) -> Option<DomRectReadOnly>;
+    #[wasm_bindgen(method, getter, js_name = colorSpace)]
+    fn color_space(this: &VideoFrame) -> VideoColorSpace;
+    #[wasm_bindgen(method, catch, js_name = allocationSize)]
+    fn allocation_size(this: &VideoFrame) -> Result<u32, JsValue>;
+    #[wasm_bindgen(method, catch, js_name = copyTo)]
+    fn copy_to(this: &VideoFrame, destination: &Uint8Array) -> Result<Promise, JsValue>;
+    #[wasm_bindgen(method)]
+    fn close(this: &VideoFrame);
+
+    #[wasm_bindgen(js_name = DOMRectReadOnly)]
+    type DomRectReadOnly;
+    #[wasm_bindgen(method, getter)]
+    fn width(this: &DomRectReadOnly) -> f64;
+    #[wasm_bindgen(method, getter)]
+    fn height(this: &DomRectReadOnly) -> f64;
+
+    type VideoColorSpace;
+    #[wasm_bindgen(method, getter)]
+    fn matrix(this: &VideoColorSpace) -> Option<String>;
+    #[wasm_bindgen(method, getter, js_name = fullRange)]
+    fn full_range(this: &VideoColorSpace) -> Option<bool>;
+
+    /// A decoded image ready for drawing, e.g. from `createImageBitmap`
+    pub type ImageBitmap;
+}
+
+fn js_error(error: JsValue, context: &str) -> String {
+    error.dyn_into::<js_sys::Error>().map_or_else(|_| context.to_string(), |e| e.message().into())
+}
+
+/// Convert a frame buffer laid out as `copyTo` writes it by default (planes
+/// tightly packed, one after another) to RGBA
+///
+/// `format` is a WebCodecs `VideoPixelFormat`; 8-bit I420, I420A, NV12,
+/// RGBA, RGBX, BGRA and BGRX are supported.
+pub fn frame_to_rgba(data: &[u8], width: u32, height: u32, format: &str, matrix: YCbCrMatrix, full_range: bool) -> Result<Vec<u8>, String> {
+    let raw_format = match format {
+        "I420" | "I420A" => RawFormat::Yuv420,
+        "NV12" => RawFormat::Nv12,
+        "RGBA" | "RGBX" => RawFormat::Rgba,
+        "BGRA" | "BGRX" => RawFormat::Bgra,
+        _ => return Err(format!("Unsupported VideoFrame format {}", format)),
+    };
+    let mut rgba = import_raw(data, width, height, 0, raw_format, matrix, full_range)?;
+    match format {
+        "RGBX" | "BGRX" => rgba.chunks_exact_mut(4).for_each(|p| p[3] = 255),
+        "I420A" => {
+            // The alpha plane follows the three YUV planes at full size
+            let (w, h) = (width as usize, height as usize);
+            let start = w * h + 2 * w.div_ceil(2) * h.div_ceil(2);
+            let alpha = data.get(start..start + w * h).ok_or("VideoFrame alpha plane is truncated")?;
+            rgba.chunks_exact_mut(4).zip(alpha).for_each(|(p, &a)| p[3] = a);
+        }
+        _ => {}
+    }
+    Ok(rgba)
+}
+
+/// Copy a frame's visible pixels into WASM memory and convert them to RGBA
+pub async fn video_frame_to_rgba(frame: &VideoFrame) -> Result<RgbaImage, String> {
+    let format = frame.format().ok_or("VideoFrame is closed or its format is not readable")?;
+    let rect = frame.visible_rect().ok_or("VideoFrame is closed")?;
+    let (width, height) = (rect.width() as u32, rect.height() as u32);
+    let size = frame.allocation_size().map_err(|e| js_error(e, "VideoFrame size is unavailable"))?;
+
+    let mut buffer = vec![0u8; size as usize];
+    // SAFETY: the view covers `buffer`, which outlives the copy and is not
+    // touched until the promise settles; nothing allocates in between, so
+    // WASM memory cannot grow and detach the view
+    let view = unsafe { Uint8Array::view_mut_raw(buffer.as_mut_ptr(), buffer.len()) };
+    let copied = frame.copy_to(&view).map_err(|e| js_error(e, "VideoFrame copy failed"))?;
+    JsFuture::from(copied).await.map_err(|e| js_error(e, "VideoFrame copy failed"))?;
+
+    let space = frame.color_space();
+    let matrix = if space.matrix().as_deref() == Some("bt709") { YCbCrMatrix::Bt709 } else { YCbCrMatrix::Bt601 };
+    let data = frame_to_rgba(&buffer, width, height, &format, matrix, space.full_range().unwrap_or(false))?;
+    Ok(RgbaImage { width, height, data })
+}
+
+/// Read an `ImageBitmap` through a temporary `VideoFrame`
+pub async fn image_bitmap_to_rgba(bitmap: &ImageBitmap) -> Result<RgbaImage, String> {
+    let init = Object::new();
+    let _ = Reflect::set(&init, &JsValue::from_str("timestamp"), &JsValue::from_f64(0.0));
+    let frame = VideoFrame::from_bitmap(bitmap, &init).map_err(|e| js_error(e, "Cannot read ImageBitmap"))?;
+    let image = video_frame_to_rgba(&frame).await;
+    frame.close();
+    image
+}
+
+/// Read a `VideoFrame` into RGBA; the frame stays open
+#[wasm_bindgen(js_name = videoFrameToRgba)]
+pub async fn video_frame_to_rgba_js(frame: VideoFrame) -> Result<RgbaImage, JsError> {
+    video_frame_to_rgba(&frame).await.map_err(|e| JsError::new(&e))
+}
+
+/// Read an `ImageBitmap` into RGBA
+#[wasm_bindgen(js_name = imageBitmapToRgba)]
+pub async fn image_bitmap_to_rgba_js(bitmap: ImageBitmap) -> Result<RgbaImage, JsError> {
+    image_bitmap_to_rgba(&bitmap).await.map_err(|e| JsError::new(&e))
+}
+
+/// Encode a `VideoFrame` as an image file
+#[wasm_bindgen(js_name = convertVideoFrame)]
+pub async fn convert_video_frame_js(frame: VideoFrame, format: ImageFormat) -> Result<Vec<u8>, JsError> {
+    let image = video_frame_to_rgba(&frame).await.map_err(|e| JsError::new(&e))?;
+    encode(&image, format).map_err(|e| JsError::new(&e))
+}
+
+/// Encode an `ImageBitmap` as an image file
+#[wasm_bindgen(js_name = convertImageBitmap)]
+pub async fn convert_image_bitmap_js(bitmap: ImageBitmap, format: ImageFormat) -> Result<Vec<u8>, JsError> {
+    let image = image_bitmap_to_rgba(&bitmap).await.map_err(|e| JsError::new(&e))?;
+    encode(&image, format).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_to_rgba() {
+        let bgrx = [1, 2, 3, 0, 4, 5, 6, 9];
+        assert_eq!(frame_to_rgba(&bgrx, 2, 1, "BGRX", YCbCrMatrix::Bt709, false).unwrap(), [3, 2, 1, 255, 6, 5, 4, 255]);
+        assert_eq!(frame_to_rgba(&bgrx, 2, 1, "RGBA", YCbCrMatrix::Bt709, false).unwrap(), bgrx);
+
+        // 2x2 I420A: white luma, neutral chroma, then an alpha ramp
+        let i420a = [235, 235, 235, 235, 128, 128, 0, 85, 170, 255];
+        let rgba = frame_to_rgba(&i420a, 2, 2, "I420A", YCbCrMatrix::Bt709, false).unwrap();
+        assert_eq!(rgba, [255, 255, 255, 0, 255, 255, 255, 85, 255, 255, 255, 170, 255, 255, 255, 255]);
+        assert!(frame_to_rgba(&i420a[..8], 2, 2, "I420A", YCbCrMatrix::Bt709, false).is_err());
+        assert!(frame_to_rgba(&i420a, 2, 2, "I444", YCbCrMatrix::Bt709, false).is_err());
+    }
+}
@@ -22,10 +22,68 @@ export interface WasmModule {
 /** WASM loading state */
 type LoadState = 'unloaded' | 'loading' | 'loaded' | 'failed'
 
+/**
+ * WASM builds, from `build:threads`, `build:simd` and `build`
+ *
+ * A module is validated as a whole, so SIMD or atomics code cannot sit
+ * behind a runtime check inside one build: the loader picks the build.
+ */
+export type WasmBuild = 'threads' | 'simd' | 'baseline'
+
+/** Runtime support for the WebAssembly features the builds need */
+export interface WasmFeatures {
+	simd: boolean
+	threads: boolean
+}
+
+/** `(module (func (result v128) i32.const 0 i8x16.splat i8x16.popcnt))` */
+const SIMD_PROBE = new Uint8Array([
+	0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 123, 3, 2, 1, 0, 10, 10, 1, 8, 0, 65, 0, 253, 15, 253, 98, 11,
+])
+/** `(module (memory 1 1 shared) (func i32.const 0 i32.atomic.load drop))` */
+const THREADS_PROBE = new Uint8Array([
+	0, 97, 115, 109, 1, 0, 0, 0, 1, 4, 1, 96, 0, 0, 3, 2, 1, 0, 5, 4, 1, 3, 1, 1, 10, 11, 1, 9, 0, 65, 0, 254, 16, 2, 0,
+	26, 11,
+])
+
+const BUILDS: Record<WasmBuild, () => Promise<unknown>> = {
+	threads: () => import('../pkg-mt/mconv_wasm'),
+	simd: () => import('../pkg-simd/mconv_wasm'),
+	baseline: () => import('../pkg/mconv_wasm'),
+}
+
 let wasmModule: WasmModule | null = null
+let wasmBuild: WasmBuild | null = null
 let loadState: LoadState = 'unloaded'
 let loadPromise: Promise<WasmModule | null> | null = null
 
+/**
+ * Probe the engine by validating tiny modules; threads also need
+ * `SharedArrayBuffer` and, on the web, a cross-origin isolated page
+ */
+export function detectFeatures(): WasmFeatures {
+	const validates = (probe: Uint8Array): boolean => {
+		try {
+			return typeof WebAssembly === 'object' && WebAssembly.validate(probe)
+		} catch {
+			return false
+		}
+	}
+	// Node has no `crossOriginIsolated` and never needs it
+	const isolated = (globalThis as { crossOriginIsolated?: boolean }).crossOriginIsolated ?? true
+	const shared = typeof SharedArrayBuffer !== 'undefined' && isolated
+	return { simd: validates(SIMD_PROBE), threads: shared && validates(THREADS_PROBE) }
+}
+
+/** Builds the engine can run, fastest first */
+export function selectBuilds(features: WasmFeatures = detectFeatures()): WasmBuild[] {
+	const builds: WasmBuild[] = []
+	if (features.threads) builds.push('threads')
+	if (features.simd) builds.push('simd')
+	builds.push('baseline')
+	return builds
+}
+
 /**
  * Load WASM module
  * Returns null if loading fails (will use TS fallback)
@@ -38,22 +96,33 @@ export async function loadWasm(): Promise<WasmModule | null> {
 	loadState = 'loading'
 
 	loadPromise = (async () => {
-		try {
-			// Try to import the WASM module
-			// In production, this would be the compiled .wasm file
-			const wasm = await import('../pkg/mconv_wasm')
-			await wasm.default()
-
-			wasmModule = wasm as unknown as WasmModule
-			loadState = 'loaded'
-
-			console.log(`[mconv] WASM loaded: v${wasmModule.version()}, threads: ${wasmModule.has_threads()}`)
-			return wasmModule
-		} catch (e) {
-			loadState = 'failed'
-			console.warn('[mconv] WASM unavailable, using pure TypeScript:', e)
-			return null
+		// Fall back to the next build when one is missing or fails to start
+		let error: unknown = null
+		for (const build of selectBuilds()) {
+			try {
+				const wasm = (await BUILDS[build]()) as {
+					default: () => Promise<unknown>
+					initThreadPool?: (threads: number) => unknown
+				}
+				await wasm.default()
+				if (build === 'threads' && wasm.initThreadPool) {
+					const nav = (globalThis as { navigator?: { hardwareConcurrency?: number } }).navigator
+					await wasm.initThreadPool(nav?.hardwareConcurrency ?? 4)
+				}
+
+				wasmModule = wasm as unknown as WasmModule
+				wasmBuild = build
+				loadState = 'loaded'
+
+				console.log(`[mconv] WASM loaded: v${wasmModule.version()}, build: ${build}`)
+				return wasmModule
+			} catch (e) {
+				error = e
+			}
 		}
+		loadState = 'failed'
+		console.warn('[mconv] WASM unavailable, using pure TypeScript:', error)
+		return null
 	})()
 
 	return loadPromise
@@ -66,6 +135,13 @@ export function isWasmAvailable(): boolean {
 	return loadState === 'loaded' && wasmModule !== null
 }
 
+/**
+ * Build that was loaded, or null before loading or when WASM is unavailable
+ */
+export function getWasmBuild(): WasmBuild | null {
+	return wasmBuild
+}
+
 /**
  * Get WASM module (sync)
  * Returns null if not loaded